@@ -3,6 +3,9 @@ use color_eyre::Result;
 use mockall::automock;
 use mockall::predicate::*;
 
+pub mod metrics;
+pub mod retry;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DaVerificationStatus {
     #[allow(dead_code)]
@@ -22,6 +25,19 @@ pub trait DaClient: Send + Sync {
     async fn publish_state_diff(&self, state_diff: Vec<Vec<u8>>, to: &[u8; 32]) -> Result<String>;
     /// Should verify the inclusion of the state diff in the DA layer and return the status
     async fn verify_inclusion(&self, external_id: &str) -> Result<DaVerificationStatus>;
+    /// Should return the serialized proof of inclusion of the state diff in the DA layer.
+    /// Returns `None` if the DA layer doesn't provide any proof for the given external id.
+    async fn get_inclusion_proof(&self, external_id: &str) -> Result<Option<Vec<u8>>>;
+    /// Should return the fee paid for the submission, in the smallest unit of the DA layer token.
+    /// Returns `None` if nothing was paid to the DA layer for the given external id.
+    async fn get_submission_fee(&self, external_id: &str) -> Result<Option<u128>>;
+    /// Should return the balance of the account paying for the submissions, in the smallest unit
+    /// of the DA layer token. Returns `None` if the submissions aren't paid by the DA client.
+    async fn get_operator_balance(&self) -> Result<Option<u128>>;
+    /// Should download the blobs carried by the given transaction back from the DA layer, in the
+    /// order they were published. Returns `None` if the transaction carries no blob or the DA
+    /// layer doesn't serve them.
+    async fn get_published_blobs(&self, tx_hash: &str) -> Result<Option<Vec<Vec<u8>>>>;
     /// Should return the max blobs per txn
     async fn max_blob_per_txn(&self) -> u64;
     /// Should return the max bytes per blob
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use prometheus::{Histogram, IntCounter, register_histogram, register_int_counter};
+
+use crate::{DaClient, DaVerificationStatus};
+
+lazy_static! {
+    /// Size in bytes of the state diffs submitted to the DA layer
+    pub static ref DA_SUBMISSION_SIZE_BYTES: Histogram = register_histogram!(
+        "da_submission_size_bytes",
+        "Size in bytes of each submission to the DA layer",
+        prometheus::exponential_buckets(4096.0, 2.0, 12).unwrap()
+    )
+    .unwrap();
+    /// Total number of bytes submitted to the DA layer
+    pub static ref DA_SUBMITTED_BYTES_TOTAL: IntCounter =
+        register_int_counter!("da_submitted_bytes_total", "Total number of bytes submitted to the DA layer").unwrap();
+    /// Number of blobs of the submissions to the DA layer
+    pub static ref DA_SUBMISSION_BLOBS: Histogram = register_histogram!(
+        "da_submission_blobs",
+        "Number of blobs of each submission to the DA layer",
+        prometheus::linear_buckets(1.0, 1.0, 16).unwrap()
+    )
+    .unwrap();
+    /// Fee paid for the submissions, in the smallest unit of the DA layer token
+    pub static ref DA_SUBMISSION_FEE: Histogram = register_histogram!(
+        "da_submission_fee",
+        "Fee paid for each submission to the DA layer, in the smallest unit of the DA layer token",
+        prometheus::exponential_buckets(1e9, 10.0, 12).unwrap()
+    )
+    .unwrap();
+    /// Latency of the `publish_state_diff` calls
+    pub static ref DA_PUBLISH_LATENCY_SECONDS: Histogram = register_histogram!(
+        "da_publish_latency_seconds",
+        "Latency of the calls publishing state diffs to the DA layer"
+    )
+    .unwrap();
+    /// Time between the publication of a state diff and the verification of its inclusion
+    pub static ref DA_END_TO_END_LATENCY_SECONDS: Histogram = register_histogram!(
+        "da_end_to_end_latency_seconds",
+        "Time between the publication of a state diff and the verification of its inclusion",
+        prometheus::exponential_buckets(1.0, 2.0, 14).unwrap()
+    )
+    .unwrap();
+}
+
+/// Wraps a DA client to record the size, fee and latency of every submission
+pub struct MeteredDaClient {
+    inner: Box<dyn DaClient>,
+    /// Publication time of the submissions which aren't verified yet, by external id
+    pending_submissions: Mutex<HashMap<String, Instant>>,
+}
+
+impl MeteredDaClient {
+    pub fn new(inner: Box<dyn DaClient>) -> Self {
+        Self { inner, pending_submissions: Mutex::new(HashMap::new()) }
+    }
+}
+
+#[async_trait]
+impl DaClient for MeteredDaClient {
+    async fn publish_state_diff(&self, state_diff: Vec<Vec<u8>>, to: &[u8; 32]) -> Result<String> {
+        let size_bytes: usize = state_diff.iter().map(|blob| blob.len()).sum();
+        let blobs = state_diff.len();
+
+        let started_at = Instant::now();
+        let external_id = self.inner.publish_state_diff(state_diff, to).await?;
+        DA_PUBLISH_LATENCY_SECONDS.observe(started_at.elapsed().as_secs_f64());
+
+        DA_SUBMISSION_SIZE_BYTES.observe(size_bytes as f64);
+        DA_SUBMITTED_BYTES_TOTAL.inc_by(size_bytes as u64);
+        DA_SUBMISSION_BLOBS.observe(blobs as f64);
+        self.pending_submissions
+            .lock()
+            .expect("Failed to lock pending DA submissions")
+            .insert(external_id.clone(), started_at);
+
+        Ok(external_id)
+    }
+
+    async fn verify_inclusion(&self, external_id: &str) -> Result<DaVerificationStatus> {
+        let status = self.inner.verify_inclusion(external_id).await?;
+        if status == DaVerificationStatus::Verified {
+            let published_at =
+                self.pending_submissions.lock().expect("Failed to lock pending DA submissions").remove(external_id);
+            // submissions published before a restart aren't tracked
+            if let Some(published_at) = published_at {
+                DA_END_TO_END_LATENCY_SECONDS.observe(published_at.elapsed().as_secs_f64());
+            }
+            if let Some(fee) = self.inner.get_submission_fee(external_id).await? {
+                DA_SUBMISSION_FEE.observe(fee as f64);
+            }
+        }
+        Ok(status)
+    }
+
+    async fn get_inclusion_proof(&self, external_id: &str) -> Result<Option<Vec<u8>>> {
+        self.inner.get_inclusion_proof(external_id).await
+    }
+
+    async fn get_submission_fee(&self, external_id: &str) -> Result<Option<u128>> {
+        self.inner.get_submission_fee(external_id).await
+    }
+
+    async fn get_operator_balance(&self) -> Result<Option<u128>> {
+        self.inner.get_operator_balance().await
+    }
+
+    async fn get_published_blobs(&self, tx_hash: &str) -> Result<Option<Vec<Vec<u8>>>> {
+        self.inner.get_published_blobs(tx_hash).await
+    }
+
+    async fn max_blob_per_txn(&self) -> u64 {
+        self.inner.max_blob_per_txn().await
+    }
+
+    async fn max_bytes_per_blob(&self) -> u64 {
+        self.inner.max_bytes_per_blob().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::MockDaClient;
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_metered_da_client_records_submission() {
+        let mut inner = MockDaClient::new();
+        inner.expect_publish_state_diff().times(1).returning(|_, _| Ok("0x1".to_string()));
+        inner.expect_verify_inclusion().times(1).returning(|_| Ok(DaVerificationStatus::Verified));
+        inner.expect_get_submission_fee().times(1).returning(|_| Ok(Some(1_000_000_000)));
+
+        let submitted_bytes = DA_SUBMITTED_BYTES_TOTAL.get();
+        let end_to_end_samples = DA_END_TO_END_LATENCY_SECONDS.get_sample_count();
+        let fee_samples = DA_SUBMISSION_FEE.get_sample_count();
+
+        let client = MeteredDaClient::new(Box::new(inner));
+        let external_id = client.publish_state_diff(vec![vec![0; 32], vec![0; 64]], &[0; 32]).await.unwrap();
+        assert_eq!(client.verify_inclusion(&external_id).await.unwrap(), DaVerificationStatus::Verified);
+
+        assert_eq!(DA_SUBMITTED_BYTES_TOTAL.get() - submitted_bytes, 96);
+        assert_eq!(DA_END_TO_END_LATENCY_SECONDS.get_sample_count() - end_to_end_samples, 1);
+        assert_eq!(DA_SUBMISSION_FEE.get_sample_count() - fee_samples, 1);
+    }
+}
@@ -0,0 +1,190 @@
+use std::future::Future;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use color_eyre::{Report, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use utils::settings::SettingsProvider;
+
+use crate::{DaClient, DaVerificationStatus};
+
+pub const DA_RETRY_SETTINGS_NAME: &str = "da_retry_settings";
+
+/// Retry settings shared by all the DA clients
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Number of retries after the first attempt
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled on every retry
+    pub initial_backoff_ms: u64,
+    /// Upper bound of the backoff
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self { max_retries: 3, initial_backoff_ms: 500, max_backoff_ms: 10_000 }
+    }
+}
+
+impl RetryConfig {
+    /// Returns the jittered backoff to wait before the retry following `attempt`.
+    /// The delay is drawn uniformly between half and the whole of the exponential backoff.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff_ms = self.initial_backoff_ms.saturating_mul(2u64.saturating_pow(attempt)).min(self.max_backoff_ms);
+        let jittered_ms = rand::thread_rng().gen_range(backoff_ms / 2..=backoff_ms);
+        Duration::from_millis(jittered_ms)
+    }
+}
+
+/// Errors which are not worth retrying (invalid input, rejected transaction...).
+/// DA clients should wrap such errors in `PermanentError` so that they are returned right away.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct PermanentError(pub String);
+
+/// Retry-on classification, everything is assumed to be transient unless it's a `PermanentError`
+pub fn is_retryable(error: &Report) -> bool {
+    error.downcast_ref::<PermanentError>().is_none()
+}
+
+/// Runs `operation` until it succeeds, fails with a non retryable error or runs out of retries
+pub async fn retry<T, F, Fut>(config: &RetryConfig, mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < config.max_retries && is_retryable(&e) => {
+                let backoff = config.backoff(attempt);
+                tracing::warn!("DA client call failed (attempt {}), retrying in {:?}: {:?}", attempt + 1, backoff, e);
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Wraps a DA client so that every RPC call is retried with exponential backoff
+pub struct RetryingDaClient {
+    inner: Box<dyn DaClient>,
+    config: RetryConfig,
+}
+
+impl RetryingDaClient {
+    pub fn new(inner: Box<dyn DaClient>, config: RetryConfig) -> Self {
+        Self { inner, config }
+    }
+
+    pub fn with_settings(inner: Box<dyn DaClient>, settings: &impl SettingsProvider) -> Self {
+        let config: RetryConfig = settings.get_settings(DA_RETRY_SETTINGS_NAME).unwrap();
+        Self::new(inner, config)
+    }
+}
+
+#[async_trait]
+impl DaClient for RetryingDaClient {
+    async fn publish_state_diff(&self, state_diff: Vec<Vec<u8>>, to: &[u8; 32]) -> Result<String> {
+        retry(&self.config, || self.inner.publish_state_diff(state_diff.clone(), to)).await
+    }
+
+    async fn verify_inclusion(&self, external_id: &str) -> Result<DaVerificationStatus> {
+        retry(&self.config, || self.inner.verify_inclusion(external_id)).await
+    }
+
+    async fn get_inclusion_proof(&self, external_id: &str) -> Result<Option<Vec<u8>>> {
+        retry(&self.config, || self.inner.get_inclusion_proof(external_id)).await
+    }
+
+    async fn get_submission_fee(&self, external_id: &str) -> Result<Option<u128>> {
+        retry(&self.config, || self.inner.get_submission_fee(external_id)).await
+    }
+
+    async fn get_operator_balance(&self) -> Result<Option<u128>> {
+        retry(&self.config, || self.inner.get_operator_balance()).await
+    }
+
+    async fn get_published_blobs(&self, tx_hash: &str) -> Result<Option<Vec<Vec<u8>>>> {
+        retry(&self.config, || self.inner.get_published_blobs(tx_hash)).await
+    }
+
+    async fn max_blob_per_txn(&self) -> u64 {
+        self.inner.max_blob_per_txn().await
+    }
+
+    async fn max_bytes_per_blob(&self) -> u64 {
+        self.inner.max_bytes_per_blob().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use color_eyre::eyre::eyre;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn no_backoff(max_retries: u32) -> RetryConfig {
+        RetryConfig { max_retries, initial_backoff_ms: 0, max_backoff_ms: 0 }
+    }
+
+    #[rstest]
+    #[case(0, 1)]
+    #[case(1, 2)]
+    #[case(5, 4)]
+    fn test_backoff_is_capped(#[case] attempt: u32, #[case] expected_max_ms: u64) {
+        let config = RetryConfig { max_retries: 10, initial_backoff_ms: 1, max_backoff_ms: 4 };
+        let backoff = config.backoff(attempt);
+        assert!(backoff <= Duration::from_millis(expected_max_ms));
+        assert!(backoff >= Duration::from_millis(expected_max_ms / 2));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let calls = AtomicU32::new(0);
+        let result = retry(&no_backoff(3), || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(eyre!("transient"))
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_retry_gives_up() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = retry(&no_backoff(2), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(eyre!("transient"))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_permanent_error_is_not_retried() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = retry(&no_backoff(3), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(PermanentError("rejected".to_string()).into())
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
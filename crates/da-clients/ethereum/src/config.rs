@@ -1,10 +1,11 @@
 use std::str::FromStr;
 
-use alloy::{network::Ethereum, providers::ProviderBuilder, rpc::client::RpcClient};
+use alloy::{network::Ethereum, providers::ProviderBuilder, rpc::client::RpcClient, transports::http::Http};
 use async_trait::async_trait;
 use da_client_interface::DaConfig;
 use url::Url;
-use utils::env_utils::get_env_var_or_panic;
+use utils::build_http_client;
+use utils::env_utils::{get_env_car_optional_or_panic, get_env_var_or_panic};
 
 use crate::EthereumDaClient;
 
@@ -13,6 +14,9 @@ pub struct EthereumDaConfig {
     pub rpc_url: String,
     pub memory_pages_contract: String,
     pub private_key: String,
+    pub beacon_genesis_timestamp: Option<u64>,
+    /// Beacon node serving the blob sidecars, needed to download the published blobs back
+    pub beacon_rpc_url: Option<String>,
 }
 
 #[async_trait]
@@ -22,13 +26,26 @@ impl DaConfig<EthereumDaClient> for EthereumDaConfig {
             rpc_url: get_env_var_or_panic("ETHEREUM_RPC_URL"),
             memory_pages_contract: get_env_var_or_panic("MEMORY_PAGES_CONTRACT_ADDRESS"),
             private_key: get_env_var_or_panic("PRIVATE_KEY"),
+            beacon_genesis_timestamp: get_env_car_optional_or_panic("ETHEREUM_BEACON_GENESIS_TIMESTAMP")
+                .map(|timestamp| timestamp.parse().expect("Failed to parse ETHEREUM_BEACON_GENESIS_TIMESTAMP")),
+            beacon_rpc_url: get_env_car_optional_or_panic("ETHEREUM_BEACON_RPC_URL"),
         }
     }
     async fn build_client(&self) -> EthereumDaClient {
-        let client =
-            RpcClient::new_http(Url::from_str(self.rpc_url.as_str()).expect("Failed to parse ETHEREUM_RPC_URL"));
+        let http_client = build_http_client!(reqwest).expect("Failed to build the Ethereum HTTP client");
+        let url = Url::from_str(self.rpc_url.as_str()).expect("Failed to parse ETHEREUM_RPC_URL");
+        let client = RpcClient::new(Http::with_client(http_client, url), false);
         let provider = ProviderBuilder::<_, Ethereum>::new().on_client(client);
+        let beacon_rpc_url = self
+            .beacon_rpc_url
+            .as_ref()
+            .map(|url| Url::from_str(url).expect("Failed to parse ETHEREUM_BEACON_RPC_URL"));
 
-        EthereumDaClient { provider }
+        EthereumDaClient {
+            provider,
+            http_client: build_http_client!(reqwest).expect("Failed to build the beacon HTTP client"),
+            beacon_genesis_timestamp: self.beacon_genesis_timestamp,
+            beacon_rpc_url,
+        }
     }
 }
@@ -1,19 +1,55 @@
 #![allow(missing_docs)]
 #![allow(clippy::missing_docs_in_private_items)]
 
+use std::str::FromStr;
+
+use alloy::eips::BlockNumberOrTag;
 use alloy::network::Ethereum;
-use alloy::providers::RootProvider;
+use alloy::primitives::{Bytes, B256};
+use alloy::providers::{Provider, RootProvider};
 use alloy::transports::http::Http;
 use async_trait::async_trait;
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
 use da_client_interface::{DaClient, DaVerificationStatus};
 use mockall::automock;
 use mockall::predicate::*;
 use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use url::Url;
+use utils::trace_id_header;
 pub mod config;
+
+/// External id returned when nothing is sent to the DA layer, the blobs are published
+/// along with the state update instead.
+pub const NO_DA_TRANSACTION: &str = "NA";
+
+/// Duration of a beacon chain slot in seconds.
+const SECONDS_PER_SLOT: u64 = 12;
+
+/// Version byte of the hashes committing to the blobs of a transaction, see EIP-4844.
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
 pub struct EthereumDaClient {
-    #[allow(dead_code)]
     provider: RootProvider<Ethereum, Http<Client>>,
+    http_client: Client,
+    /// Genesis time of the beacon chain, needed to derive the slot of a blob transaction
+    beacon_genesis_timestamp: Option<u64>,
+    /// Beacon node serving the blob sidecars
+    beacon_rpc_url: Option<Url>,
+}
+
+/// Response of the `blob_sidecars` endpoint of the beacon API
+#[derive(Debug, Deserialize)]
+struct BlobSidecars {
+    data: Vec<BlobSidecar>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlobSidecar {
+    blob: Bytes,
+    kzg_commitment: Bytes,
 }
 
 #[automock]
@@ -22,13 +58,82 @@ impl DaClient for EthereumDaClient {
     async fn publish_state_diff(&self, _state_diff: Vec<Vec<u8>>, _to: &[u8; 32]) -> Result<String> {
         // Here in case of ethereum we are not publishing the state diff because we are doing it all together in update_state job.
         // So we don't need to send the blob here.
-        Ok("NA".to_string())
+        Ok(NO_DA_TRANSACTION.to_string())
     }
 
     async fn verify_inclusion(&self, _external_id: &str) -> Result<DaVerificationStatus> {
         Ok(DaVerificationStatus::Verified)
     }
 
+    async fn get_inclusion_proof(&self, _external_id: &str) -> Result<Option<Vec<u8>>> {
+        // nothing is sent to the DA layer, the blobs are included with the state update transaction
+        Ok(None)
+    }
+
+    async fn get_submission_fee(&self, external_id: &str) -> Result<Option<u128>> {
+        if external_id == NO_DA_TRANSACTION {
+            return Ok(None);
+        }
+
+        let receipt = self
+            .provider
+            .get_transaction_receipt(B256::from_str(external_id)?)
+            .await?
+            .ok_or_else(|| eyre!("No receipt found for DA transaction {}", external_id))?;
+        let execution_fee = receipt.gas_used * receipt.effective_gas_price;
+        let blob_fee = receipt.blob_gas_used.unwrap_or_default() * receipt.blob_gas_price.unwrap_or_default();
+
+        Ok(Some(execution_fee + blob_fee))
+    }
+
+    async fn get_operator_balance(&self) -> Result<Option<u128>> {
+        // the blobs are sent and paid for by the settlement client
+        Ok(None)
+    }
+
+    async fn get_published_blobs(&self, tx_hash: &str) -> Result<Option<Vec<Vec<u8>>>> {
+        if tx_hash == NO_DA_TRANSACTION {
+            return Ok(None);
+        }
+        // the blobs are only served by the beacon chain, and pruned after some time
+        let (Some(beacon_rpc_url), Some(genesis)) = (&self.beacon_rpc_url, self.beacon_genesis_timestamp) else {
+            return Ok(None);
+        };
+
+        let tx = self.provider.get_transaction_by_hash(B256::from_str(tx_hash)?).await?;
+        let versioned_hashes = tx.blob_versioned_hashes.unwrap_or_default();
+        if versioned_hashes.is_empty() {
+            return Ok(None);
+        }
+        let block_number = tx.block_number.ok_or_else(|| eyre!("Transaction {} is not included yet", tx_hash))?;
+        let block = self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Number(block_number), false)
+            .await?
+            .ok_or_else(|| eyre!("Block {} not found", block_number))?;
+        let slot = block.header.timestamp.saturating_sub(genesis) / SECONDS_PER_SLOT;
+
+        let url = beacon_rpc_url.join(&format!("eth/v1/beacon/blob_sidecars/{}", slot))?;
+        let response = trace_id_header!(self.http_client.get(url)).send().await?.error_for_status()?.bytes().await?;
+        let sidecars: BlobSidecars = serde_json::from_slice(&response)?;
+
+        // the sidecars hold the blobs of every transaction of the block
+        let blobs = versioned_hashes
+            .iter()
+            .map(|versioned_hash| {
+                sidecars
+                    .data
+                    .iter()
+                    .find(|sidecar| kzg_to_versioned_hash(&sidecar.kzg_commitment) == *versioned_hash)
+                    .map(|sidecar| sidecar.blob.to_vec())
+                    .ok_or_else(|| {
+                        eyre!("Blob 0x{:x} of transaction {} not found in slot {}", versioned_hash, tx_hash, slot)
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(blobs))
+    }
+
     async fn max_blob_per_txn(&self) -> u64 {
         6
     }
@@ -37,3 +142,10 @@ impl DaClient for EthereumDaClient {
         131072
     }
 }
+
+/// Versioned hash of a blob, as committed to by the blob transaction.
+fn kzg_to_versioned_hash(kzg_commitment: &[u8]) -> B256 {
+    let mut hash: [u8; 32] = Sha256::digest(kzg_commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    B256::from(hash)
+}
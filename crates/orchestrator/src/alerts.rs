@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::json;
+use utils::build_http_client;
+use utils::env_utils::{get_env_car_optional_or_panic, get_env_var_or_default};
+
+use crate::deployment::{DeploymentDescriptor, DEPLOYMENT};
+
+/// Slack incoming webhook the alerts are posted to
+pub const ENV_ALERT_SLACK_WEBHOOK_URL: &str = "ALERT_SLACK_WEBHOOK_URL";
+/// Routing key of the PagerDuty service the alerts trigger incidents on
+pub const ENV_ALERT_PAGERDUTY_ROUTING_KEY: &str = "ALERT_PAGERDUTY_ROUTING_KEY";
+/// PagerDuty events API, the public one by default
+pub const ENV_ALERT_PAGERDUTY_EVENTS_URL: &str = "ALERT_PAGERDUTY_EVENTS_URL";
+/// Endpoint the alerts are posted to as JSON, for any other alerting system
+pub const ENV_ALERT_WEBHOOK_URL: &str = "ALERT_WEBHOOK_URL";
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+/// Time a sink has to take an alert, a sink down doesn't hold the jobs back
+const ALERT_SINK_TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    /// Sinks the alerts are sent to on top of the logs, configured through the environment
+    pub static ref ALERT_SINKS: Vec<Box<dyn AlertSink>> = alert_sinks_from_env();
+}
+
+/// What an alert is about, the sinks route the alerts on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// A job failed for good: it ran out of attempts, it's blocked or its message was dead-lettered
+    JobFailed,
+    /// A worker stopped creating jobs, halted by failed jobs
+    WorkerHalted,
+    /// The settled blocks lag the chain head by more than the threshold
+    BlockLag,
+    /// The audit of the settled blocks found a divergence with the L2
+    AuditMismatch,
+    /// The settlement layer doesn't match the state update jobs
+    SettlementMismatch,
+    /// A settlement transaction of a completed job was reorged out
+    SettlementReorg,
+    /// A transaction was sent by the operator account without any job referencing it
+    OrphanTransaction,
+    /// An operator account is running out of funds
+    LowBalance,
+    /// A block spent more than its cost cap, its DA and settlement submissions are held back
+    BlockCostCap,
+}
+
+/// How urgent an alert is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    /// The chain stopped settling, or settled something wrong, until an operator steps in
+    Critical,
+    /// Needs attention before it becomes critical
+    Warning,
+}
+
+impl AlertKind {
+    pub fn severity(&self) -> AlertSeverity {
+        match self {
+            AlertKind::LowBalance | AlertKind::OrphanTransaction | AlertKind::BlockCostCap => AlertSeverity::Warning,
+            _ => AlertSeverity::Critical,
+        }
+    }
+}
+
+/// Payload of an alert
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert<'a> {
+    pub kind: AlertKind,
+    pub severity: AlertSeverity,
+    pub message: &'a str,
+    pub deployment: &'a DeploymentDescriptor,
+}
+
+impl<'a> Alert<'a> {
+    /// Alert of the deployment, with the severity of its kind
+    pub fn new(kind: AlertKind, message: &'a str) -> Self {
+        Self { kind, severity: kind.severity(), message, deployment: &DEPLOYMENT }
+    }
+}
+
+/// Destination of the alerts, e.g. a chat channel or a paging service
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    async fn send(&self, alert: &Alert<'_>) -> Result<()>;
+}
+
+/// Posts the alerts to a Slack channel through an incoming webhook
+pub struct SlackAlertSink {
+    webhook_url: String,
+    client: reqwest::Client,
+}
+
+impl SlackAlertSink {
+    pub fn new(webhook_url: String) -> Self {
+        Self { webhook_url, client: build_http_client!(reqwest).expect("Failed to build the Slack HTTP client") }
+    }
+}
+
+#[async_trait]
+impl AlertSink for SlackAlertSink {
+    async fn send(&self, alert: &Alert<'_>) -> Result<()> {
+        let text = format!("*[{:?}] {:?}* ({})\n{}", alert.severity, alert.kind, alert.deployment, alert.message);
+        self.client
+            .post(&self.webhook_url)
+            .timeout(ALERT_SINK_TIMEOUT)
+            .json(&json!({ "text": text }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Triggers a PagerDuty incident per alert through the events API
+pub struct PagerDutyAlertSink {
+    routing_key: String,
+    events_url: String,
+    client: reqwest::Client,
+}
+
+impl PagerDutyAlertSink {
+    pub fn new(routing_key: String, events_url: String) -> Self {
+        Self {
+            routing_key,
+            events_url,
+            client: build_http_client!(reqwest).expect("Failed to build the PagerDuty HTTP client"),
+        }
+    }
+}
+
+#[async_trait]
+impl AlertSink for PagerDutyAlertSink {
+    async fn send(&self, alert: &Alert<'_>) -> Result<()> {
+        let event = json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            "payload": {
+                "summary": alert.message,
+                "source": format!("orchestrator {}", alert.deployment.chain_id),
+                "severity": match alert.severity {
+                    AlertSeverity::Critical => "critical",
+                    AlertSeverity::Warning => "warning",
+                },
+                "component": alert.kind,
+                "custom_details": alert.deployment,
+            },
+        });
+        self.client.post(&self.events_url).timeout(ALERT_SINK_TIMEOUT).json(&event).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Posts the [`Alert`] as JSON to an endpoint
+pub struct WebhookAlertSink {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookAlertSink {
+    pub fn new(url: String) -> Self {
+        Self { url, client: build_http_client!(reqwest).expect("Failed to build the alert webhook HTTP client") }
+    }
+}
+
+#[async_trait]
+impl AlertSink for WebhookAlertSink {
+    async fn send(&self, alert: &Alert<'_>) -> Result<()> {
+        self.client.post(&self.url).timeout(ALERT_SINK_TIMEOUT).json(alert).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// The sinks whose settings are set in the environment
+pub fn alert_sinks_from_env() -> Vec<Box<dyn AlertSink>> {
+    let mut sinks: Vec<Box<dyn AlertSink>> = Vec::new();
+    if let Some(webhook_url) = get_env_car_optional_or_panic(ENV_ALERT_SLACK_WEBHOOK_URL) {
+        sinks.push(Box::new(SlackAlertSink::new(webhook_url)));
+    }
+    if let Some(routing_key) = get_env_car_optional_or_panic(ENV_ALERT_PAGERDUTY_ROUTING_KEY) {
+        let events_url = get_env_var_or_default(ENV_ALERT_PAGERDUTY_EVENTS_URL, PAGERDUTY_EVENTS_URL);
+        sinks.push(Box::new(PagerDutyAlertSink::new(routing_key, events_url)));
+    }
+    if let Some(url) = get_env_car_optional_or_panic(ENV_ALERT_WEBHOOK_URL) {
+        sinks.push(Box::new(WebhookAlertSink::new(url)));
+    }
+    sinks
+}
+
+/// Raises an alert for the operators. The alert is logged, for the log based alerting of the
+/// deployment, and sent to each of the [`ALERT_SINKS`]. A sink failing to take it is logged, the
+/// caller isn't held back.
+pub async fn send_alert(kind: AlertKind, message: &str) {
+    dispatch_alert(&Alert::new(kind, message), &ALERT_SINKS).await;
+}
+
+/// Logs the alert and sends it to the sinks
+pub async fn dispatch_alert(alert: &Alert<'_>, sinks: &[Box<dyn AlertSink>]) {
+    match serde_json::to_string(alert) {
+        Ok(payload) => tracing::error!("[ALERT] {}", payload),
+        Err(_) => tracing::error!("[ALERT] {}", alert.message),
+    }
+    for sink in sinks {
+        if let Err(e) = sink.send(alert).await {
+            tracing::error!("Failed to send the alert to a sink: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use aws_sdk_s3::config::SharedHttpClient;
+use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
+use hyper::service::Service;
+use hyper::Uri;
+use hyper_rustls::HttpsConnectorBuilder;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use utils::http_client::HttpClientConfig;
+
+/// Longest response of the proxy to a `CONNECT` accepted, the tunnel starts right after it
+const MAX_CONNECT_RESPONSE_BYTES: usize = 8 * 1024;
+
+/// HTTP client of the AWS clients (S3, SQS) going through `OUTBOUND_PROXY_URL` and trusting the
+/// root CAs of `OUTBOUND_ROOT_CA_PATH` on top of the native ones. `None` when neither is set and
+/// the default client of the SDK does the job.
+pub fn aws_http_client() -> Option<SharedHttpClient> {
+    let config = HttpClientConfig::from_env().expect("Failed to read the outbound HTTP client config");
+    build_aws_http_client(&config)
+}
+
+fn build_aws_http_client(config: &HttpClientConfig) -> Option<SharedHttpClient> {
+    if config.proxy_url.is_none() && config.root_certificates_pem.is_empty() {
+        return None;
+    }
+    let mut roots = rustls::RootCertStore::empty();
+    for certificate in rustls_native_certs::load_native_certs().expect("Failed to load the native root CAs") {
+        // invalid native certificates are skipped like the default client does
+        let _ = roots.add(&rustls::Certificate(certificate.0));
+    }
+    for pem in &config.root_certificates_pem {
+        for der in rustls_pemfile::certs(&mut pem.as_slice()).expect("Failed to parse the custom root CA") {
+            roots.add(&rustls::Certificate(der)).expect("Invalid custom root CA");
+        }
+    }
+    let tls_config =
+        rustls::ClientConfig::builder().with_safe_defaults().with_root_certificates(roots).with_no_client_auth();
+    let connector =
+        HttpsConnectorBuilder::new().with_tls_config(tls_config).https_or_http().enable_http1().enable_http2();
+    match &config.proxy_url {
+        Some(proxy_url) => {
+            let proxy = proxy_url.parse().unwrap_or_else(|_| panic!("Invalid outbound proxy url {}", proxy_url));
+            Some(HyperClientBuilder::new().build(connector.wrap_connector(ProxyTunnelConnector::new(proxy))))
+        }
+        None => Some(HyperClientBuilder::new().build(connector.build())),
+    }
+}
+
+/// Connector opening the connections through an HTTP proxy with `CONNECT`, the TLS session with
+/// the destination is then established through the tunnel by the HTTPS connector wrapping it
+#[derive(Debug, Clone)]
+pub struct ProxyTunnelConnector {
+    proxy: Uri,
+}
+
+impl ProxyTunnelConnector {
+    pub fn new(proxy: Uri) -> Self {
+        Self { proxy }
+    }
+}
+
+impl Service<Uri> for ProxyTunnelConnector {
+    type Response = TcpStream;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, destination: Uri) -> Self::Future {
+        let proxy = self.proxy.clone();
+        Box::pin(async move { connect_through_proxy(&proxy, &destination).await })
+    }
+}
+
+async fn connect_through_proxy(proxy: &Uri, destination: &Uri) -> io::Result<TcpStream> {
+    let invalid_input = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let proxy_host = proxy.host().ok_or_else(|| invalid_input(format!("Proxy url {} has no host", proxy)))?;
+    let host = destination.host().ok_or_else(|| invalid_input(format!("Url {} has no host", destination)))?;
+    let default_port = if destination.scheme_str() == Some("https") { 443 } else { 80 };
+    let target = format!("{}:{}", host, destination.port_u16().unwrap_or(default_port));
+
+    let mut stream = TcpStream::connect((proxy_host, proxy.port_u16().unwrap_or(80))).await?;
+    stream.write_all(format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", target).as_bytes()).await?;
+
+    // read byte by byte so that nothing past the response, i.e. from the tunnel, is consumed
+    let mut response = Vec::new();
+    let mut byte = [0u8; 1];
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_CONNECT_RESPONSE_BYTES {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Proxy response to CONNECT is too long"));
+        }
+        if stream.read(&mut byte).await? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Proxy closed the connection during CONNECT"));
+        }
+        response.push(byte[0]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("Proxy refused to CONNECT to {}: {}", target, status_line),
+        ));
+    }
+    Ok(stream)
+}
@@ -0,0 +1,322 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+use std::time::Instant;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use prometheus::{HistogramVec, IntCounterVec, register_histogram_vec, register_int_counter_vec};
+use utils::env_utils::get_env_var_or_default;
+
+/// Environment variable selecting the codec of the payloads published to the DA layer
+pub const ENV_DA_PAYLOAD_CODEC: &str = "DA_PAYLOAD_CODEC";
+/// Environment variable selecting the codec of the artifacts written to the storage
+pub const ENV_STORAGE_ARTIFACT_CODEC: &str = "STORAGE_ARTIFACT_CODEC";
+/// Environment variable selecting the codec of the Cairo PIEs written to the storage
+pub const ENV_CAIRO_PIE_CODEC: &str = "CAIRO_PIE_CODEC";
+/// Environment variable selecting the codec every object written to the storage is compressed with
+pub const ENV_STORAGE_COMPRESSION_CODEC: &str = "STORAGE_COMPRESSION_CODEC";
+
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+pub const DEFAULT_BROTLI_QUALITY: u32 = 6;
+const BROTLI_LG_WINDOW_SIZE: u32 = 22;
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+lazy_static! {
+    /// Bytes going in and out of the codecs
+    pub static ref CODEC_BYTES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "codec_bytes_total",
+        "Number of bytes going in and out of the codecs",
+        &["codec", "usage", "operation", "direction"]
+    )
+    .unwrap();
+    /// Latency of the codec operations
+    pub static ref CODEC_LATENCY_SECONDS: HistogramVec = register_histogram_vec!(
+        "codec_latency_seconds",
+        "Latency of the codec operations",
+        &["codec", "usage", "operation"],
+        prometheus::exponential_buckets(0.0001, 4.0, 10).unwrap()
+    )
+    .unwrap();
+}
+
+/// A compression codec applied to the blobs and artifacts handled by the orchestrator.
+pub trait Codec: Send + Sync {
+    /// Name of the codec, used in the metric labels
+    fn name(&self) -> &'static str;
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>>;
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Leaves the data as is
+pub struct IdentityCodec;
+
+impl Codec for IdentityCodec {
+    fn name(&self) -> &'static str {
+        "identity"
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+}
+
+pub struct ZstdCodec {
+    level: i32,
+}
+
+impl ZstdCodec {
+    pub fn new(level: i32) -> Self {
+        Self { level }
+    }
+}
+
+impl Codec for ZstdCodec {
+    fn name(&self) -> &'static str {
+        "zstd"
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(zstd::encode_all(data, self.level)?)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(zstd::decode_all(data)?)
+    }
+}
+
+pub struct BrotliCodec {
+    quality: u32,
+}
+
+impl BrotliCodec {
+    pub fn new(quality: u32) -> Self {
+        Self { quality }
+    }
+}
+
+impl Codec for BrotliCodec {
+    fn name(&self) -> &'static str {
+        "brotli"
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoded = Vec::new();
+        {
+            let mut writer =
+                brotli::CompressorWriter::new(&mut encoded, BROTLI_BUFFER_SIZE, self.quality, BROTLI_LG_WINDOW_SIZE);
+            writer.write_all(data)?;
+            writer.flush()?;
+        }
+        Ok(encoded)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        brotli::Decompressor::new(data, BROTLI_BUFFER_SIZE).read_to_end(&mut decoded)?;
+        Ok(decoded)
+    }
+}
+
+/// Codec as configured through the environment: `identity`, `zstd` or `brotli`, optionally
+/// followed by the compression level, e.g. `zstd:19` or `brotli:11`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecKind {
+    Identity,
+    Zstd(i32),
+    Brotli(u32),
+}
+
+impl CodecKind {
+    pub fn build(self) -> Box<dyn Codec> {
+        match self {
+            CodecKind::Identity => Box::new(IdentityCodec),
+            CodecKind::Zstd(level) => Box::new(ZstdCodec::new(level)),
+            CodecKind::Brotli(quality) => Box::new(BrotliCodec::new(quality)),
+        }
+    }
+}
+
+impl FromStr for CodecKind {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, level) = match s.split_once(':') {
+            Some((name, level)) => (name, Some(level)),
+            None => (s, None),
+        };
+        match (name.to_lowercase().as_str(), level) {
+            ("identity", None) => Ok(CodecKind::Identity),
+            ("zstd", level) => {
+                let level = level.map(str::parse).transpose()?.unwrap_or(DEFAULT_ZSTD_LEVEL);
+                if !zstd::compression_level_range().contains(&level) {
+                    return Err(eyre!("Invalid zstd compression level {}", level));
+                }
+                Ok(CodecKind::Zstd(level))
+            }
+            ("brotli", level) => {
+                let quality = level.map(str::parse).transpose()?.unwrap_or(DEFAULT_BROTLI_QUALITY);
+                if quality > 11 {
+                    return Err(eyre!("Invalid brotli quality {}", quality));
+                }
+                Ok(CodecKind::Brotli(quality))
+            }
+            _ => Err(eyre!("Unknown codec {}", s)),
+        }
+    }
+}
+
+/// What the codec is applied to, each usage is configured separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecUsage {
+    /// Payloads published to the DA layer. Only DA layers taking opaque payloads can use a codec
+    /// other than identity, the Ethereum blobs are committed to by the OS and must be posted as is.
+    DaPayload,
+    /// Artifacts written to the storage, e.g. the blob data of the blocks
+    StorageArtifact,
+    /// Cairo PIEs written to the storage, hundreds of MB for big blocks
+    CairoPie,
+    /// Every object written to the storage, by the storage itself, on top of the codecs above
+    StorageCompression,
+}
+
+impl CodecUsage {
+    fn env_var(&self) -> &'static str {
+        match self {
+            CodecUsage::DaPayload => ENV_DA_PAYLOAD_CODEC,
+            CodecUsage::StorageArtifact => ENV_STORAGE_ARTIFACT_CODEC,
+            CodecUsage::CairoPie => ENV_CAIRO_PIE_CODEC,
+            CodecUsage::StorageCompression => ENV_STORAGE_COMPRESSION_CODEC,
+        }
+    }
+
+    fn default_codec(&self) -> &'static str {
+        match self {
+            CodecUsage::DaPayload | CodecUsage::StorageArtifact | CodecUsage::StorageCompression => "identity",
+            // the PIEs are big enough for the compression to pay off on every upload and download
+            CodecUsage::CairoPie => "zstd",
+        }
+    }
+}
+
+impl fmt::Display for CodecUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodecUsage::DaPayload => write!(f, "da_payload"),
+            CodecUsage::StorageArtifact => write!(f, "storage_artifact"),
+            CodecUsage::CairoPie => write!(f, "cairo_pie"),
+            CodecUsage::StorageCompression => write!(f, "storage_compression"),
+        }
+    }
+}
+
+/// Wraps a codec to record the bytes it handles and its latency
+pub struct MeteredCodec {
+    inner: Box<dyn Codec>,
+    usage: CodecUsage,
+}
+
+impl MeteredCodec {
+    pub fn new(inner: Box<dyn Codec>, usage: CodecUsage) -> Self {
+        Self { inner, usage }
+    }
+
+    fn record<F>(&self, operation: &str, data: &[u8], f: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce(&[u8]) -> Result<Vec<u8>>,
+    {
+        let usage = self.usage.to_string();
+        let labels = [self.inner.name(), usage.as_str(), operation];
+        let start = Instant::now();
+        let output = f(data)?;
+        CODEC_LATENCY_SECONDS.with_label_values(&labels).observe(start.elapsed().as_secs_f64());
+        CODEC_BYTES_TOTAL.with_label_values(&[labels[0], labels[1], labels[2], "in"]).inc_by(data.len() as u64);
+        CODEC_BYTES_TOTAL.with_label_values(&[labels[0], labels[1], labels[2], "out"]).inc_by(output.len() as u64);
+        Ok(output)
+    }
+}
+
+impl Codec for MeteredCodec {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.record("encode", data, |data| self.inner.encode(data))
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.record("decode", data, |data| self.inner.decode(data))
+    }
+}
+
+/// Returns the metered codec configured for `usage`, identity by default except for the Cairo
+/// PIEs which are compressed with zstd.
+///
+/// The codec is read from the environment on every call so it has to stay the same for the
+/// lifetime of the artifacts it encodes: switching it makes the artifacts already written
+/// unreadable.
+pub fn codec_for(usage: CodecUsage) -> Result<Box<dyn Codec>> {
+    let kind: CodecKind = get_env_var_or_default(usage.env_var(), usage.default_codec()).parse()?;
+    Ok(Box::new(MeteredCodec::new(kind.build(), usage)))
+}
+
+/// Fails unless identity is the codec configured for `usage`, for the data which must be written
+/// as is, e.g. the payloads of the DA layers committing to them.
+pub fn ensure_identity_codec(usage: CodecUsage) -> Result<()> {
+    let kind: CodecKind = get_env_var_or_default(usage.env_var(), usage.default_codec()).parse()?;
+    if kind != CodecKind::Identity {
+        return Err(eyre!("The {} codec must be identity, found {:?} ({})", usage, kind, usage.env_var()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::tests::common::EnvVarGuard;
+
+    #[rstest]
+    #[case::identity("identity")]
+    #[case::zstd("zstd")]
+    #[case::zstd_level("zstd:19")]
+    #[case::brotli("brotli")]
+    #[case::brotli_quality("brotli:11")]
+    fn test_codec_round_trip(#[case] kind: &str) {
+        let codec = MeteredCodec::new(kind.parse::<CodecKind>().unwrap().build(), CodecUsage::StorageArtifact);
+        let data: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+
+        let encoded = codec.encode(&data).unwrap();
+        if kind != "identity" {
+            assert!(encoded.len() < data.len());
+        }
+        assert_eq!(codec.decode(&encoded).unwrap(), data);
+    }
+
+    #[rstest]
+    #[case("gzip")]
+    #[case("identity:1")]
+    #[case("zstd:100")]
+    #[case("brotli:12")]
+    fn test_invalid_codec_kind(#[case] kind: &str) {
+        assert!(kind.parse::<CodecKind>().is_err());
+    }
+
+    #[rstest]
+    #[case::identity("identity", true)]
+    #[case::zstd("zstd", false)]
+    #[case::brotli("brotli:11", false)]
+    fn test_ensure_identity_codec(#[case] kind: &str, #[case] accepted: bool) {
+        let _codec = EnvVarGuard::set(ENV_DA_PAYLOAD_CODEC, kind);
+        assert_eq!(ensure_identity_codec(CodecUsage::DaPayload).is_ok(), accepted);
+    }
+}
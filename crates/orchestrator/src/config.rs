@@ -2,26 +2,39 @@ use std::sync::Arc;
 
 use crate::data_storage::aws_s3::config::{AWSS3Config, AWSS3ConfigType};
 use crate::data_storage::aws_s3::AWSS3;
+use crate::data_storage::compressed::CompressedStorage;
+use crate::data_storage::encrypted::EncryptedStorage;
 use crate::data_storage::{DataStorage, DataStorageConfig};
 use arc_swap::{ArcSwap, Guard};
+use atlantic_service::AtlanticProverService;
+use da_client_interface::metrics::MeteredDaClient;
+use da_client_interface::retry::RetryingDaClient;
 use da_client_interface::{DaClient, DaConfig};
 use dotenvy::dotenv;
 use ethereum_da_client::config::EthereumDaConfig;
 use ethereum_settlement_client::EthereumSettlementClient;
+use mock_prover_service::MockProverService;
 use prover_client_interface::ProverClient;
 use settlement_client_interface::SettlementClient;
 use sharp_service::SharpProverService;
 use starknet::providers::jsonrpc::HttpTransport;
 use starknet::providers::{JsonRpcClient, Url};
 use starknet_settlement_client::StarknetSettlementClient;
+use stone_prover_service::StoneProverService;
 use tokio::sync::OnceCell;
-use utils::env_utils::get_env_var_or_panic;
+use utils::build_http_client;
+use utils::env_utils::{get_env_var_or_default, get_env_var_or_panic};
 use utils::settings::default::DefaultSettingsProvider;
 use utils::settings::SettingsProvider;
 
+use crate::codec::{ensure_identity_codec, CodecUsage};
 use crate::database::mongodb::config::MongoDbConfig;
 use crate::database::mongodb::MongoDb;
+use crate::database::sqlite::config::SqliteDbConfig;
+use crate::database::sqlite::SqliteDb;
 use crate::database::{Database, DatabaseConfig};
+use crate::queue::inprocess::InProcessQueue;
+use crate::queue::memory::InMemoryQueue;
 use crate::queue::sqs::SqsQueue;
 use crate::queue::QueueProvider;
 
@@ -49,23 +62,24 @@ pub async fn init_config() -> Config {
     dotenv().ok();
 
     // init starknet client
-    let provider = JsonRpcClient::new(HttpTransport::new(
+    let provider = JsonRpcClient::new(HttpTransport::new_with_client(
         Url::parse(get_env_var_or_panic("MADARA_RPC_URL").as_str()).expect("Failed to parse URL"),
+        build_http_client!(reqwest).expect("Failed to build the Starknet HTTP client"),
     ));
 
     // init database
-    let database = Box::new(MongoDb::new(MongoDbConfig::new_from_env()).await);
+    let database = build_database_client().await;
 
     // init the queue
-    let queue = Box::new(SqsQueue {});
-
-    let da_client = build_da_client().await;
+    let queue = build_queue_client().await;
 
     let settings_provider = DefaultSettingsProvider {};
+    let da_client = build_da_client(&settings_provider).await;
+
     let settlement_client = build_settlement_client(&settings_provider).await;
     let prover_client = build_prover_service(&settings_provider);
 
-    let storage_client = build_storage_client().await;
+    let storage_client = build_storage_client(&settings_provider).await;
 
     Config::new(Arc::new(provider), da_client, prover_client, settlement_client, database, queue, storage_client)
 }
@@ -147,21 +161,29 @@ pub async fn config_force_init(config: Config) {
     }
 }
 
-/// Builds the DA client based on the environment variable DA_LAYER
-pub async fn build_da_client() -> Box<dyn DaClient + Send + Sync> {
-    match get_env_var_or_panic("DA_LAYER").as_str() {
+/// Builds the DA client based on the environment variable DA_LAYER.
+/// The client is wrapped so that its RPC calls are retried with backoff and metered.
+pub async fn build_da_client(settings_provider: &impl SettingsProvider) -> Box<dyn DaClient + Send + Sync> {
+    let da_client: Box<dyn DaClient> = match get_env_var_or_panic("DA_LAYER").as_str() {
         "ethereum" => {
+            // the blobs are committed to by the OS, they can't be encoded
+            ensure_identity_codec(CodecUsage::DaPayload).expect("Invalid DA payload codec for the Ethereum blobs");
             let config = EthereumDaConfig::new_from_env();
             Box::new(config.build_client().await)
         }
         _ => panic!("Unsupported DA layer"),
-    }
+    };
+    let da_client = Box::new(RetryingDaClient::with_settings(da_client, settings_provider));
+    Box::new(MeteredDaClient::new(da_client))
 }
 
 /// Builds the prover service based on the environment variable PROVER_SERVICE
 pub fn build_prover_service(settings_provider: &impl SettingsProvider) -> Box<dyn ProverClient> {
     match get_env_var_or_panic("PROVER_SERVICE").as_str() {
         "sharp" => Box::new(SharpProverService::with_settings(settings_provider)),
+        "stone" => Box::new(StoneProverService::with_settings(settings_provider)),
+        "atlantic" => Box::new(AtlanticProverService::with_settings(settings_provider)),
+        "mock" => Box::new(MockProverService::with_settings(settings_provider)),
         _ => panic!("Unsupported prover service"),
     }
 }
@@ -171,15 +193,39 @@ pub async fn build_settlement_client(
     settings_provider: &impl SettingsProvider,
 ) -> Box<dyn SettlementClient + Send + Sync> {
     match get_env_var_or_panic("SETTLEMENT_LAYER").as_str() {
-        "ethereum" => Box::new(EthereumSettlementClient::with_settings(settings_provider)),
+        "ethereum" => Box::new(EthereumSettlementClient::with_settings(settings_provider).await),
         "starknet" => Box::new(StarknetSettlementClient::with_settings(settings_provider).await),
         _ => panic!("Unsupported Settlement layer"),
     }
 }
 
-pub async fn build_storage_client() -> Box<dyn DataStorage + Send + Sync> {
-    match get_env_var_or_panic("DATA_STORAGE").as_str() {
+/// Builds the database client based on the environment variable DATABASE
+pub async fn build_database_client() -> Box<dyn Database> {
+    match get_env_var_or_default("DATABASE", "mongodb").as_str() {
+        "mongodb" => Box::new(MongoDb::new(MongoDbConfig::new_from_env()).await),
+        "sqlite" => Box::new(SqliteDb::new(SqliteDbConfig::new_from_env()).await),
+        _ => panic!("Unsupported Database"),
+    }
+}
+
+/// Builds the queue client based on the environment variable QUEUE
+pub async fn build_queue_client() -> Box<dyn QueueProvider> {
+    match get_env_var_or_default("QUEUE", "sqs").as_str() {
+        "sqs" => Box::new(SqsQueue {}),
+        "inprocess" => Box::new(InProcessQueue::new()),
+        "memory" => Box::new(InMemoryQueue::new().await.expect("Failed to create the in-memory queue")),
+        _ => panic!("Unsupported Queue"),
+    }
+}
+
+/// Builds the storage client based on the environment variable DATA_STORAGE. The data is
+/// compressed with `STORAGE_COMPRESSION_CODEC` then encrypted with the key of the settings, if any.
+pub async fn build_storage_client(settings_provider: &impl SettingsProvider) -> Box<dyn DataStorage + Send + Sync> {
+    let storage: Box<dyn DataStorage> = match get_env_var_or_panic("DATA_STORAGE").as_str() {
         "s3" => Box::new(AWSS3::new(AWSS3ConfigType::WithoutEndpoint(AWSS3Config::new_from_env())).await),
         _ => panic!("Unsupported Storage Client"),
-    }
+    };
+    // the ciphertext doesn't compress, the data is compressed before being encrypted
+    let storage = EncryptedStorage::with_settings(storage, settings_provider).expect("Invalid storage encryption key");
+    CompressedStorage::from_env(storage).expect("Invalid STORAGE_COMPRESSION_CODEC")
 }
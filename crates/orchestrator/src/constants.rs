@@ -1,2 +1,9 @@
 pub const BLOB_DATA_FILE_NAME: &str = "blob_data.txt";
+pub const CAIRO_PIE_FILE_NAME: &str = "cairo_pie.zip";
+pub const SNOS_INPUT_FILE_NAME: &str = "snos_input.json";
 pub const SNOS_OUTPUT_FILE_NAME: &str = "snos_output.json";
+pub const PROGRAM_OUTPUT_FILE_NAME: &str = "program_output.json";
+pub const SNOS_STDOUT_FILE_NAME: &str = "snos_stdout.log";
+pub const SNOS_STDERR_FILE_NAME: &str = "snos_stderr.log";
+pub const DA_INCLUSION_PROOF_FILE_NAME: &str = "da_inclusion_proof.json";
+pub const PROOF_FILE_NAME: &str = "proof.json";
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_default;
+use utils::settings::default::DefaultSettingsProvider;
+use utils::settings::SettingsProvider;
+use uuid::Uuid;
+
+use crate::config::config;
+use crate::controllers::errors::AppError;
+use crate::database::types::JobFilter;
+use crate::jobs::types::{ExternalId, JobCounters, JobItem, JobPriority, JobStatus, JobType};
+use crate::jobs::{cancel_job, retry_job};
+use crate::workers::scheduler::trigger_worker_now;
+
+pub const ADMIN_API_SETTINGS_NAME: &str = "admin_api_settings";
+/// Token the requests to the admin API must carry as `Authorization: Bearer <token>`, the admin
+/// API is disabled without it
+pub const ENV_ADMIN_API_TOKEN: &str = "ADMIN_API_TOKEN";
+
+/// Jobs listed per page by default, and at most
+const DEFAULT_JOBS_PAGE_SIZE: i64 = 50;
+const MAX_JOBS_PAGE_SIZE: i64 = 500;
+
+/// Settings of the admin API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminApiSettings {
+    pub token: Option<String>,
+}
+
+impl Default for AdminApiSettings {
+    /// The token is read from `ADMIN_API_TOKEN` unless the settings provider has it
+    fn default() -> Self {
+        Self { token: Some(get_env_var_or_default(ENV_ADMIN_API_TOKEN, "")).filter(|token| !token.is_empty()) }
+    }
+}
+
+/// Routes of the admin API, managing the jobs and the workers without access to the database. The
+/// routes aren't served when no API token is set.
+pub fn admin_router() -> Router {
+    let settings: AdminApiSettings = DefaultSettingsProvider {}
+        .get_settings(ADMIN_API_SETTINGS_NAME)
+        .expect("Failed to read the admin API settings");
+    let Some(token) = settings.token else {
+        tracing::warn!("No admin API token is set, the admin API is disabled");
+        return Router::new();
+    };
+    admin_routes().route_layer(middleware::from_fn_with_state(Arc::new(token), require_api_token))
+}
+
+fn admin_routes() -> Router {
+    Router::new()
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/:id", get(get_job))
+        .route("/jobs/:id/retry", post(retry))
+        .route("/jobs/:id/cancel", post(cancel))
+        .route("/blocks/:block_number/jobs", get(get_jobs_by_block))
+        .route("/workers/:name/trigger", post(trigger_worker))
+}
+
+/// Rejects the requests without the API token
+async fn require_api_token(
+    State(token): State<Arc<String>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let provided = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match provided {
+        Some(provided) if tokens_match(provided, &token) => Ok(next.run(request).await),
+        _ => Err(AppError::Unauthorized),
+    }
+}
+
+/// Compares the tokens in a time independent of where they differ
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Job as returned by the admin API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobResponse {
+    pub id: Uuid,
+    pub internal_id: String,
+    pub job_type: JobType,
+    pub status: JobStatus,
+    pub external_id: ExternalId,
+    pub metadata: HashMap<String, String>,
+    pub version: i32,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub parent_ids: Vec<Uuid>,
+    pub priority: JobPriority,
+    pub counters: JobCounters,
+}
+
+impl From<JobItem> for JobResponse {
+    fn from(job: JobItem) -> Self {
+        Self {
+            id: job.id,
+            internal_id: job.internal_id,
+            job_type: job.job_type,
+            status: job.status,
+            external_id: job.external_id,
+            metadata: job.metadata,
+            version: job.version,
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+            parent_ids: job.parent_ids,
+            priority: job.priority,
+            counters: job.counters,
+        }
+    }
+}
+
+/// Page of the jobs listed, `next_cursor` is passed as `cursor` to get the next page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobsPageResponse {
+    pub jobs: Vec<JobResponse>,
+    pub next_cursor: Option<String>,
+}
+
+/// Criteria of the listing of the jobs, all optional
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListJobsQuery {
+    pub job_type: Option<JobType>,
+    pub status: Option<JobStatus>,
+    /// First block of the numeric internal ids, included
+    pub from_block: Option<u64>,
+    /// Last block of the numeric internal ids, included
+    pub to_block: Option<u64>,
+    /// Unix timestamp in seconds, included
+    pub created_after: Option<u64>,
+    /// Unix timestamp in seconds, excluded
+    pub created_before: Option<u64>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Lists the jobs by creation order, a page at a time
+async fn list_jobs(Query(query): Query<ListJobsQuery>) -> Result<Json<JobsPageResponse>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_JOBS_PAGE_SIZE);
+    if !(1..=MAX_JOBS_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::BadRequest(format!("The limit must be between 1 and {}", MAX_JOBS_PAGE_SIZE)));
+    }
+    let internal_id_range = match (query.from_block, query.to_block) {
+        (None, None) => None,
+        // the bounds of the range are stored as signed integers
+        (from_block, to_block) => Some(from_block.unwrap_or(0)..=to_block.unwrap_or(i64::MAX as u64)),
+    };
+    let filter = JobFilter {
+        job_type: query.job_type,
+        status: query.status,
+        internal_id_range,
+        created_after: query.created_after,
+        created_before: query.created_before,
+    };
+    let config = config().await;
+    let page = config.database().get_jobs_paginated(filter, query.cursor, limit).await?;
+    Ok(Json(JobsPageResponse {
+        jobs: page.jobs.into_iter().map(JobResponse::from).collect(),
+        next_cursor: page.next_cursor,
+    }))
+}
+
+async fn get_job(Path(id): Path<Uuid>) -> Result<Json<JobResponse>, AppError> {
+    Ok(Json(find_job(id).await?.into()))
+}
+
+/// Jobs of any type covering the block, by creation order
+async fn get_jobs_by_block(Path(block_number): Path<u64>) -> Result<Json<Vec<JobResponse>>, AppError> {
+    let config = config().await;
+    let jobs = config.database().get_jobs_by_block(block_number).await?;
+    Ok(Json(jobs.into_iter().map(JobResponse::from).collect()))
+}
+
+/// Retries a failed job, see [`retry_job`]
+async fn retry(Path(id): Path<Uuid>) -> Result<Json<JobResponse>, AppError> {
+    let job = find_job(id).await?;
+    if !job.status.is_retryable() {
+        return Err(AppError::BadRequest(format!("Job {} is {:?}, it can't be retried", id, job.status)));
+    }
+    retry_job(id).await?;
+    Ok(Json(find_job(id).await?.into()))
+}
+
+/// Cancels a job, see [`cancel_job`]
+async fn cancel(Path(id): Path<Uuid>) -> Result<Json<JobResponse>, AppError> {
+    let job = find_job(id).await?;
+    if !job.status.is_cancellable() {
+        return Err(AppError::BadRequest(format!("Job {} is {:?}, it can't be cancelled", id, job.status)));
+    }
+    cancel_job(id).await?;
+    Ok(Json(find_job(id).await?.into()))
+}
+
+/// Runs a worker of this instance now, see [`trigger_worker_now`]
+async fn trigger_worker(Path(name): Path<String>) -> Result<impl IntoResponse, AppError> {
+    trigger_worker_now(&name).map_err(|e| AppError::NotFound(e.to_string()))?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn find_job(id: Uuid) -> Result<JobItem, AppError> {
+    let config = config().await;
+    config.database().get_job_by_id(id).await?.ok_or_else(|| AppError::NotFound(format!("No job with id {}", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secreT", "secret"));
+        assert!(!tokens_match("secret-longer", "secret"));
+        assert!(!tokens_match("", "secret"));
+    }
+}
@@ -0,0 +1,96 @@
+use axum::extract::Path;
+use axum::routing::get;
+use axum::{Json, Router};
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::config;
+use crate::controllers::errors::AppError;
+use crate::jobs::types::{ExternalId, JobItem, JobStatus, JobType};
+
+/// Stages every block goes through, listed in the status of a block even before their job is
+/// created. The jobs of the other types are listed when there's one for the block.
+pub const BLOCK_PIPELINE_STAGES: [JobType; 4] =
+    [JobType::SnosRun, JobType::ProofCreation, JobType::DataSubmission, JobType::StateTransition];
+
+/// Routes of the status of the blocks
+pub fn blocks_router() -> Router {
+    Router::new().route("/:block_number/status", get(block_status_handler))
+}
+
+/// Status of a block through the pipeline, for the support teams
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockStatus {
+    pub block_number: u64,
+    /// Whether the state update job of the block is completed
+    pub settled: bool,
+    /// Stages of the block, by job type
+    pub stages: Vec<BlockStageStatus>,
+}
+
+/// Status of a stage of a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockStageStatus {
+    pub job_type: JobType,
+    /// Latest job of the type covering the block, `None` until it's created
+    pub job: Option<BlockJobStatus>,
+}
+
+/// Job of a stage of a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockJobStatus {
+    pub id: Uuid,
+    /// The block, or the range of blocks the job covers
+    pub internal_id: String,
+    pub status: JobStatus,
+    /// Id of the job on the external service: the transaction hash of the settlement or the DA
+    /// submission, the id of the proving task
+    pub external_id: ExternalId,
+    /// Error of the last failed attempt, if any
+    pub error: Option<String>,
+    /// Unix timestamp in seconds
+    pub updated_at: u64,
+}
+
+impl From<JobItem> for BlockJobStatus {
+    fn from(job: JobItem) -> Self {
+        Self {
+            id: job.id,
+            error: job.metadata.get("error").cloned(),
+            internal_id: job.internal_id,
+            status: job.status,
+            external_id: job.external_id,
+            updated_at: job.updated_at,
+        }
+    }
+}
+
+/// Aggregates the jobs covering the block into its status. A stage retried through a new job, e.g.
+/// after the cancellation of the first one, reports the latest job.
+pub async fn block_status(block_number: u64) -> Result<BlockStatus> {
+    let config = config().await;
+    let jobs = config.database().get_jobs_by_block(block_number).await?;
+
+    let stages: Vec<BlockStageStatus> = JobType::ALL
+        .into_iter()
+        .filter_map(|job_type| {
+            // the jobs are listed by creation order
+            let latest_job = jobs.iter().rev().find(|job| job.job_type == job_type).cloned();
+            if latest_job.is_none() && !BLOCK_PIPELINE_STAGES.contains(&job_type) {
+                return None;
+            }
+            Some(BlockStageStatus { job_type, job: latest_job.map(BlockJobStatus::from) })
+        })
+        .collect();
+    let settled = stages.iter().any(|stage| {
+        stage.job_type == JobType::StateTransition
+            && stage.job.as_ref().is_some_and(|job| job.status == JobStatus::Completed)
+    });
+
+    Ok(BlockStatus { block_number, settled, stages })
+}
+
+async fn block_status_handler(Path(block_number): Path<u64>) -> Result<Json<BlockStatus>, AppError> {
+    Ok(Json(block_status(block_number).await?))
+}
@@ -1,8 +1,8 @@
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Json;
 use color_eyre::eyre::ErrReport;
 use serde_json::json;
-use tracing::log;
 
 /// Root level error which is sent back to the client
 #[derive(thiserror::Error, Debug)]
@@ -10,14 +10,28 @@ pub enum AppError {
     /// Internal server error
     #[error("Internal Server Error {0}")]
     InternalServerError(#[from] ErrReport),
+    /// The request is invalid, e.g. an action on a job in a status which doesn't allow it
+    #[error("Bad Request: {0}")]
+    BadRequest(String),
+    /// The request doesn't carry a valid API token
+    #[error("Unauthorized")]
+    Unauthorized,
+    /// The resource requested doesn't exist
+    #[error("Not Found: {0}")]
+    NotFound(String),
 }
 
 /// Convert the error into a response so that it can be sent back to the client
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::http::Response<axum::body::Body> {
-        log::error!("Error: {:?}", self);
         let (status, err_msg) = match self {
-            Self::InternalServerError(msg) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, msg.to_string()),
+            Self::InternalServerError(msg) => {
+                tracing::error!("Error: {:?}", msg);
+                (StatusCode::INTERNAL_SERVER_ERROR, msg.to_string())
+            }
+            Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            Self::Unauthorized => (StatusCode::UNAUTHORIZED, "Missing or invalid API token".to_string()),
+            Self::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
         };
         (status, Json(json!({"message": err_msg }))).into_response()
     }
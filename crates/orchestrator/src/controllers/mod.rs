@@ -1,2 +1,6 @@
+/// Admin API managing the jobs and the workers
+pub mod admin;
+/// Status of the blocks through the pipeline
+pub mod blocks;
 /// Errors
-mod errors;
+pub mod errors;
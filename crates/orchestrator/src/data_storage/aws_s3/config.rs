@@ -1,7 +1,39 @@
-use utils::env_utils::get_env_var_or_panic;
+use utils::env_utils::{get_env_var_or_default, get_env_var_or_panic};
 
 use crate::data_storage::DataStorageConfig;
 
+/// Size of the parts of the multipart uploads and of the ranges of the chunked downloads, the
+/// objects bigger than one part are transferred in parts
+pub const ENV_S3_MULTIPART_CHUNK_SIZE_BYTES: &str = "AWS_S3_MULTIPART_CHUNK_SIZE_BYTES";
+/// 16 MiB by default, S3 rejects the parts smaller than 5 MiB other than the last one
+pub const DEFAULT_MULTIPART_CHUNK_SIZE_BYTES: usize = 16 * 1024 * 1024;
+pub const MIN_MULTIPART_CHUNK_SIZE_BYTES: usize = 5 * 1024 * 1024;
+/// KMS key the objects are encrypted with by S3 (SSE-KMS), the bucket's default encryption
+/// applies when unset
+pub const ENV_S3_SSE_KMS_KEY_ID: &str = "AWS_S3_SSE_KMS_KEY_ID";
+
+/// KMS key of the server-side encryption, as configured through the environment
+pub fn sse_kms_key_id_from_env() -> Option<String> {
+    Some(get_env_var_or_default(ENV_S3_SSE_KMS_KEY_ID, "")).filter(|key_id| !key_id.is_empty())
+}
+
+/// Size of the parts of the multipart uploads and chunked downloads, as configured through the
+/// environment
+pub fn multipart_chunk_size_from_env() -> usize {
+    let chunk_size: usize = get_env_var_or_default(
+        ENV_S3_MULTIPART_CHUNK_SIZE_BYTES,
+        &DEFAULT_MULTIPART_CHUNK_SIZE_BYTES.to_string(),
+    )
+    .parse()
+    .expect("Failed to parse AWS_S3_MULTIPART_CHUNK_SIZE_BYTES");
+    assert!(
+        chunk_size >= MIN_MULTIPART_CHUNK_SIZE_BYTES,
+        "AWS_S3_MULTIPART_CHUNK_SIZE_BYTES must be at least {} bytes",
+        MIN_MULTIPART_CHUNK_SIZE_BYTES
+    );
+    chunk_size
+}
+
 /// Represents the type of the config which one wants to pass to create the client
 #[derive(Clone)]
 pub enum AWSS3ConfigType {
@@ -10,12 +42,16 @@ pub enum AWSS3ConfigType {
 }
 
 /// Represents AWS S3 config struct with all the necessary variables.
+///
+/// The static key is optional: without it, the credentials are resolved with the default AWS
+/// credential chain (profile and SSO, web identity as with IRSA, ECS task role, instance profile)
+/// so that the deployments don't need long-lived keys.
 #[derive(Clone)]
 pub struct AWSS3Config {
     /// AWS ACCESS KEY ID
-    pub s3_key_id: String,
+    pub s3_key_id: Option<String>,
     /// AWS ACCESS KEY SECRET
-    pub s3_key_secret: String,
+    pub s3_key_secret: Option<String>,
     /// S3 Bucket Name
     pub s3_bucket_name: String,
     /// S3 Bucket region
@@ -41,9 +77,17 @@ pub struct S3LocalStackConfig {
 impl DataStorageConfig for AWSS3Config {
     /// To return the config struct by creating it from the environment variables.
     fn new_from_env() -> Self {
+        let s3_key_id = Some(get_env_var_or_default("AWS_ACCESS_KEY_ID", "")).filter(|key_id| !key_id.is_empty());
+        let s3_key_secret =
+            Some(get_env_var_or_default("AWS_SECRET_ACCESS_KEY", "")).filter(|key_secret| !key_secret.is_empty());
+        assert_eq!(
+            s3_key_id.is_some(),
+            s3_key_secret.is_some(),
+            "AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY must be set together"
+        );
         Self {
-            s3_key_id: get_env_var_or_panic("AWS_ACCESS_KEY_ID"),
-            s3_key_secret: get_env_var_or_panic("AWS_SECRET_ACCESS_KEY"),
+            s3_key_id,
+            s3_key_secret,
             s3_bucket_name: get_env_var_or_panic("AWS_S3_BUCKET_NAME"),
             s3_bucket_region: get_env_var_or_panic("AWS_S3_BUCKET_REGION"),
         }
@@ -1,11 +1,21 @@
-use crate::data_storage::aws_s3::config::AWSS3ConfigType;
-use crate::data_storage::DataStorage;
+use crate::aws_http_client::aws_http_client;
+use crate::data_storage::aws_s3::config::{multipart_chunk_size_from_env, sse_kms_key_id_from_env, AWSS3ConfigType};
+use crate::data_storage::{DataStorage, DataStream};
 use async_trait::async_trait;
+use aws_config::default_provider::credentials::DefaultCredentialsChain;
 use aws_sdk_s3::config::{Builder, Credentials, Region};
+use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, ServerSideEncryption};
 use aws_sdk_s3::Client;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
+use futures::{StreamExt, TryStreamExt};
+use std::time::Duration;
+
+/// Number of parts transferred at the same time by the multipart uploads and chunked downloads
+const MULTIPART_CONCURRENCY: usize = 4;
 
 /// Module for AWS S3 config structs and implementations
 pub mod config;
@@ -14,6 +24,9 @@ pub mod config;
 pub struct AWSS3 {
     client: Client,
     config: AWSS3ConfigType,
+    multipart_chunk_size: usize,
+    /// KMS key the objects are encrypted with by S3, if any
+    sse_kms_key_id: Option<String>,
 }
 
 /// Implementation for AWS S3 client. Contains the function for :
@@ -25,15 +38,21 @@ impl AWSS3 {
     pub async fn new(config: AWSS3ConfigType) -> Self {
         let (config_builder, config) = match config {
             AWSS3ConfigType::WithoutEndpoint(config) => {
-                let (credentials, region) = get_credentials_and_region_from_config(
-                    config.s3_key_id.clone(),
-                    config.s3_key_secret.clone(),
-                    config.s3_bucket_region.clone(),
-                );
-                (
-                    Builder::new().region(region).credentials_provider(credentials).force_path_style(true),
-                    AWSS3ConfigType::WithoutEndpoint(config),
-                )
+                let region = Region::new(config.s3_bucket_region.clone());
+                let config_builder = Builder::new().region(region.clone()).force_path_style(true);
+                let config_builder = match (&config.s3_key_id, &config.s3_key_secret) {
+                    (Some(s3_key_id), Some(s3_key_secret)) => config_builder.credentials_provider(Credentials::new(
+                        s3_key_id.clone(),
+                        s3_key_secret.clone(),
+                        None,
+                        None,
+                        "loaded_from_custom_env",
+                    )),
+                    // instance profile, IRSA, SSO... resolved and refreshed by the SDK
+                    _ => config_builder
+                        .credentials_provider(DefaultCredentialsChain::builder().region(region).build().await),
+                };
+                (config_builder, AWSS3ConfigType::WithoutEndpoint(config))
             }
             AWSS3ConfigType::WithEndpoint(config) => {
                 let (credentials, region) = get_credentials_and_region_from_config(
@@ -52,12 +71,21 @@ impl AWSS3 {
             }
         };
 
+        let config_builder = match aws_http_client() {
+            Some(http_client) => config_builder.http_client(http_client),
+            None => config_builder,
+        };
         let conf = config_builder.build();
 
         // Building AWS S3 config
         let client = Client::from_conf(conf);
 
-        Self { client, config }
+        Self {
+            client,
+            config,
+            multipart_chunk_size: multipart_chunk_size_from_env(),
+            sse_kms_key_id: sse_kms_key_id_from_env(),
+        }
     }
 
     pub fn get_bucket_name(&self) -> String {
@@ -66,6 +94,156 @@ impl AWSS3 {
             AWSS3ConfigType::WithoutEndpoint(config) => config.s3_bucket_name,
         }
     }
+
+    /// SSE-KMS when a KMS key is configured, the bucket's default encryption otherwise
+    fn server_side_encryption(&self) -> Option<ServerSideEncryption> {
+        self.sse_kms_key_id.as_ref().map(|_| ServerSideEncryption::AwsKms)
+    }
+
+    /// Uploads the data in parts of `multipart_chunk_size` bytes, the upload is aborted if any
+    /// part fails so that S3 doesn't keep the parts uploaded so far.
+    async fn put_data_multipart(&self, data: Bytes, key: &str) -> Result<()> {
+        let bucket = self.get_bucket_name();
+        let upload_id = self.create_multipart_upload(&bucket, key).await?;
+        let result = self.upload_parts(&bucket, key, &upload_id, data).await;
+        self.finish_multipart_upload(&bucket, key, &upload_id, result).await
+    }
+
+    /// Uploads the stream in parts of `multipart_chunk_size` bytes, one part at a time so that
+    /// only a part is held in memory. The upload is aborted if any part fails.
+    async fn put_data_stream_multipart(&self, first_part: Bytes, data: DataStream, key: &str) -> Result<()> {
+        let bucket = self.get_bucket_name();
+        let upload_id = self.create_multipart_upload(&bucket, key).await?;
+        let result = self.upload_stream_parts(&bucket, key, &upload_id, first_part, data).await;
+        self.finish_multipart_upload(&bucket, key, &upload_id, result).await
+    }
+
+    async fn create_multipart_upload(&self, bucket: &str, key: &str) -> Result<String> {
+        let upload = self
+            .client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .content_type("application/octet-stream")
+            .set_server_side_encryption(self.server_side_encryption())
+            .set_ssekms_key_id(self.sse_kms_key_id.clone())
+            .send()
+            .await?;
+        upload.upload_id().map(str::to_string).ok_or_else(|| eyre!("S3 didn't return the id of the upload of {}", key))
+    }
+
+    /// Completes the upload once its parts are uploaded, aborts it otherwise
+    async fn finish_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Result<Vec<CompletedPart>>,
+    ) -> Result<()> {
+        match parts {
+            Ok(parts) => {
+                self.client
+                    .complete_multipart_upload()
+                    .bucket(bucket)
+                    .key(key)
+                    .upload_id(upload_id)
+                    .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+                    .send()
+                    .await?;
+                Ok(())
+            }
+            Err(e) => {
+                if let Err(abort_err) =
+                    self.client.abort_multipart_upload().bucket(bucket).key(key).upload_id(upload_id).send().await
+                {
+                    tracing::warn!("Failed to abort the multipart upload of {}: {}", key, abort_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn upload_parts(&self, bucket: &str, key: &str, upload_id: &str, data: Bytes) -> Result<Vec<CompletedPart>> {
+        let chunks: Vec<Bytes> = (0..data.len())
+            .step_by(self.multipart_chunk_size)
+            .map(|start| data.slice(start..(start + self.multipart_chunk_size).min(data.len())))
+            .collect();
+        futures::stream::iter(chunks.into_iter().enumerate())
+            // the part numbers start at 1
+            .map(|(index, chunk)| self.upload_part(bucket, key, upload_id, index as i32 + 1, chunk))
+            .buffered(MULTIPART_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+
+    /// Uploads the parts as the stream fills them, the last part being the only one allowed to be
+    /// smaller than `multipart_chunk_size`
+    async fn upload_stream_parts(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        first_part: Bytes,
+        mut data: DataStream,
+    ) -> Result<Vec<CompletedPart>> {
+        let mut parts = vec![self.upload_part(bucket, key, upload_id, 1, first_part).await?];
+        let mut buffer = BytesMut::with_capacity(self.multipart_chunk_size);
+        while let Some(chunk) = data.try_next().await? {
+            buffer.extend_from_slice(&chunk);
+            while buffer.len() >= self.multipart_chunk_size {
+                let part = buffer.split_to(self.multipart_chunk_size).freeze();
+                parts.push(self.upload_part(bucket, key, upload_id, parts.len() as i32 + 1, part).await?);
+            }
+        }
+        if !buffer.is_empty() {
+            parts.push(self.upload_part(bucket, key, upload_id, parts.len() as i32 + 1, buffer.freeze()).await?);
+        }
+        Ok(parts)
+    }
+
+    async fn upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        chunk: Bytes,
+    ) -> Result<CompletedPart> {
+        let part = self
+            .client
+            .upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(chunk))
+            .send()
+            .await?;
+        Ok(CompletedPart::builder().set_e_tag(part.e_tag).part_number(part_number).build())
+    }
+
+    /// Downloads the object in ranges of `multipart_chunk_size` bytes
+    async fn get_data_chunked(&self, key: &str, size: usize) -> Result<Bytes> {
+        let bucket = self.get_bucket_name();
+        let chunks: Vec<Bytes> = futures::stream::iter((0..size).step_by(self.multipart_chunk_size))
+            .map(|start| {
+                let range = format!("bytes={}-{}", start, (start + self.multipart_chunk_size).min(size) - 1);
+                let bucket = &bucket;
+                async move {
+                    let response = self.client.get_object().bucket(bucket).key(key).range(range).send().await?;
+                    Ok::<_, color_eyre::Report>(response.body.collect().await?.into_bytes())
+                }
+            })
+            .buffered(MULTIPART_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        let mut data = BytesMut::with_capacity(size);
+        for chunk in chunks {
+            data.extend_from_slice(&chunk);
+        }
+        Ok(data.freeze())
+    }
 }
 
 /// Return the constructed `Credentials` and `Region`
@@ -84,28 +262,107 @@ fn get_credentials_and_region_from_config(
 /// by taking the key as an argument.
 #[async_trait]
 impl DataStorage for AWSS3 {
-    /// Function to get the data from S3 bucket by Key.
+    /// Function to get the data from S3 bucket by Key. The objects bigger than one part are
+    /// downloaded in chunks, their body is dropped unread once the size is known.
     async fn get_data(&self, key: &str) -> Result<Bytes> {
         let response = self.client.get_object().bucket(self.get_bucket_name()).key(key).send().await?;
+        let size = response.content_length().unwrap_or_default().max(0) as usize;
+        if size > self.multipart_chunk_size {
+            return self.get_data_chunked(key, size).await;
+        }
+
         let data_stream = response.body.collect().await.expect("Failed to convert body into AggregatedBytes.");
         let data_bytes = data_stream.into_bytes();
         Ok(data_bytes)
     }
 
-    /// Function to put the data to S3 bucket by Key.
+    /// Function to put the data to S3 bucket by Key. The data bigger than one part is uploaded
+    /// with a multipart upload.
     async fn put_data(&self, data: Bytes, key: &str) -> Result<()> {
+        if data.len() > self.multipart_chunk_size {
+            return self.put_data_multipart(data, key).await;
+        }
+
         self.client
             .put_object()
             .bucket(self.get_bucket_name())
             .key(key)
             .body(ByteStream::from(data))
             .content_type("application/json")
+            .set_server_side_encryption(self.server_side_encryption())
+            .set_ssekms_key_id(self.sse_kms_key_id.clone())
             .send()
             .await?;
 
         Ok(())
     }
 
+    /// Function to stream the data of the S3 bucket by Key, as S3 sends it.
+    async fn get_data_stream(&self, key: &str) -> Result<DataStream> {
+        let response = self.client.get_object().bucket(self.get_bucket_name()).key(key).send().await?;
+        let stream = futures::stream::unfold(response.body, |mut body| async move {
+            body.try_next().await.map_err(color_eyre::Report::from).transpose().map(|chunk| (chunk, body))
+        });
+        Ok(stream.boxed())
+    }
+
+    /// Function to put the data of the stream to S3 bucket by Key. The data is uploaded with a
+    /// multipart upload once it's bigger than one part, without being held in memory.
+    async fn put_data_stream(&self, mut data: DataStream, key: &str) -> Result<()> {
+        let mut first_part = BytesMut::new();
+        while first_part.len() < self.multipart_chunk_size {
+            match data.try_next().await? {
+                Some(chunk) => first_part.extend_from_slice(&chunk),
+                None => return self.put_data(first_part.freeze(), key).await,
+            }
+        }
+        let rest = first_part.split_off(self.multipart_chunk_size).freeze();
+        let data = futures::stream::once(async move { Ok(rest) }).chain(data).boxed();
+        self.put_data_stream_multipart(first_part.freeze(), data, key).await
+    }
+
+    /// Function to check if an object exists in the S3 bucket by Key.
+    async fn data_exists(&self, key: &str) -> Result<bool> {
+        match self.client.head_object().bucket(self.get_bucket_name()).key(key).send().await {
+            Ok(_) => Ok(true),
+            Err(e) => match e.into_service_error() {
+                e if e.is_not_found() => Ok(false),
+                e => Err(e.into()),
+            },
+        }
+    }
+
+    /// Function to list the keys of the S3 bucket starting with the prefix, S3 returning them
+    /// by pages of up to 1000 keys.
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut pages =
+            self.client.list_objects_v2().bucket(self.get_bucket_name()).prefix(prefix).into_paginator().send();
+        let mut keys = Vec::new();
+        while let Some(page) = pages.next().await {
+            keys.extend(page?.contents().iter().filter_map(|object| object.key().map(str::to_string)));
+        }
+        Ok(keys)
+    }
+
+    /// Function to delete an object from the S3 bucket by Key.
+    async fn delete_data(&self, key: &str) -> Result<()> {
+        self.client.delete_object().bucket(self.get_bucket_name()).key(key).send().await?;
+        Ok(())
+    }
+
+    /// Function to presign a GetObject request of the S3 bucket by Key, valid for `ttl`. S3
+    /// doesn't accept more than 7 days.
+    async fn generate_presigned_get_url(&self, key: &str, ttl: Duration) -> Result<String> {
+        let request = self
+            .client
+            .get_object()
+            .bucket(self.get_bucket_name())
+            .key(key)
+            .presigned(PresigningConfig::expires_in(ttl)?)
+            .await?;
+        Ok(request.uri().to_string())
+    }
+
     #[cfg(test)]
     async fn build_test_bucket(&self, bucket_name: &str) -> Result<()> {
         self.client.create_bucket().bucket(bucket_name).send().await?;
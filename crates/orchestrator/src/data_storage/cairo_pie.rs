@@ -0,0 +1,31 @@
+use cairo_vm::vm::runners::cairo_pie::CairoPie;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+
+use crate::codec::{codec_for, CodecUsage};
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::data_storage::DataStorage;
+
+/// Key of the Cairo PIE of the block in the storage
+pub fn cairo_pie_key(block_number: &str) -> String {
+    StorageKey::new(ArtifactKind::CairoPie, block_number).to_string()
+}
+
+/// Stores the Cairo PIE of the block as a zip file compressed with the `CAIRO_PIE_CODEC` codec.
+/// The big PIEs are uploaded in parts by the storage.
+pub async fn store_cairo_pie(storage: &dyn DataStorage, block_number: &str, cairo_pie: &CairoPie) -> Result<()> {
+    let file = tempfile::NamedTempFile::new()?;
+    cairo_pie.write_zip_file(file.path())?;
+    let zip = std::fs::read(file.path())?;
+    let encoded = codec_for(CodecUsage::CairoPie)?.encode(&zip)?;
+    storage.put_data(encoded.into(), &cairo_pie_key(block_number)).await
+}
+
+/// Fetches the Cairo PIE stored by [`store_cairo_pie`] under `key`
+pub async fn fetch_cairo_pie(storage: &dyn DataStorage, key: &str) -> Result<CairoPie> {
+    let encoded = storage.get_data(key).await?;
+    let zip = codec_for(CodecUsage::CairoPie)?.decode(&encoded)?;
+    let file = tempfile::NamedTempFile::new()?;
+    std::fs::write(file.path(), zip)?;
+    CairoPie::read_zip_file(file.path()).map_err(|e| eyre!("Failed to read the Cairo PIE {}: {}", key, e))
+}
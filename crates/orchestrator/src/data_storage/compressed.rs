@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use futures::{StreamExt, TryStreamExt};
+
+use crate::codec::{codec_for, Codec, CodecKind, CodecUsage, MeteredCodec};
+use crate::data_storage::{DataStorage, DataStream};
+
+/// Marks the objects compressed by [`CompressedStorage`], followed by the name of the codec and a
+/// new line
+const COMPRESSED_HEADER_MAGIC: &[u8] = b"MADARA_ORCHESTRATOR_CODEC:";
+/// Longest codec name the header is read with
+const MAX_CODEC_NAME_LEN: usize = 16;
+
+/// Compresses the data stored by the wrapped storage with the `STORAGE_COMPRESSION_CODEC` codec.
+///
+/// The codec is recorded in a header of each object, the data storage having no metadata of its
+/// own, so that the objects are read back with the codec they were written with whatever the
+/// codec is configured to now. Objects without the header, written before the compression was
+/// enabled, are returned as they are.
+///
+/// The streams are stored as they are, compressing them would need them in memory. Compressed
+/// objects are decompressed in memory when streamed.
+pub struct CompressedStorage {
+    inner: Box<dyn DataStorage>,
+    codec: Box<dyn Codec>,
+}
+
+impl CompressedStorage {
+    pub fn new(inner: Box<dyn DataStorage>, codec: Box<dyn Codec>) -> Self {
+        Self { inner, codec }
+    }
+
+    /// Wraps the storage with the codec configured through `STORAGE_COMPRESSION_CODEC`, the
+    /// storage is returned as is when the codec is `identity`
+    pub fn from_env(inner: Box<dyn DataStorage>) -> Result<Box<dyn DataStorage>> {
+        let codec = codec_for(CodecUsage::StorageCompression)?;
+        if codec.name() == "identity" {
+            return Ok(inner);
+        }
+        Ok(Box::new(Self::new(inner, codec)))
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Bytes> {
+        let mut compressed = COMPRESSED_HEADER_MAGIC.to_vec();
+        compressed.extend_from_slice(self.codec.name().as_bytes());
+        compressed.push(b'\n');
+        compressed.extend_from_slice(&self.codec.encode(data)?);
+        Ok(compressed.into())
+    }
+
+    fn decompress(&self, key: &str, data: Bytes) -> Result<Bytes> {
+        if !data.starts_with(COMPRESSED_HEADER_MAGIC) {
+            return Ok(data);
+        }
+        let header = &data[COMPRESSED_HEADER_MAGIC.len()..];
+        let name_len = header
+            .iter()
+            .take(MAX_CODEC_NAME_LEN + 1)
+            .position(|byte| *byte == b'\n')
+            .ok_or_else(|| eyre!("Invalid compression header of {}", key))?;
+        let name = std::str::from_utf8(&header[..name_len])?;
+        let codec = MeteredCodec::new(name.parse::<CodecKind>()?.build(), CodecUsage::StorageCompression);
+        Ok(codec.decode(&header[name_len + 1..])?.into())
+    }
+}
+
+#[async_trait]
+impl DataStorage for CompressedStorage {
+    async fn get_data(&self, key: &str) -> Result<Bytes> {
+        let data = self.inner.get_data(key).await?;
+        self.decompress(key, data)
+    }
+
+    async fn put_data(&self, data: Bytes, key: &str) -> Result<()> {
+        self.inner.put_data(self.compress(&data)?, key).await
+    }
+
+    async fn get_data_stream(&self, key: &str) -> Result<DataStream> {
+        let mut stream = self.inner.get_data_stream(key).await?;
+        let mut start = BytesMut::new();
+        while start.len() < COMPRESSED_HEADER_MAGIC.len() {
+            match stream.try_next().await? {
+                Some(chunk) => start.extend_from_slice(&chunk),
+                None => break,
+            }
+        }
+        if !start.starts_with(COMPRESSED_HEADER_MAGIC) {
+            let start = start.freeze();
+            return Ok(futures::stream::once(async move { Ok(start) }).chain(stream).boxed());
+        }
+        while let Some(chunk) = stream.try_next().await? {
+            start.extend_from_slice(&chunk);
+        }
+        let data = self.decompress(key, start.freeze())?;
+        Ok(futures::stream::once(async move { Ok(data) }).boxed())
+    }
+
+    async fn put_data_stream(&self, data: DataStream, key: &str) -> Result<()> {
+        self.inner.put_data_stream(data, key).await
+    }
+
+    async fn data_exists(&self, key: &str) -> Result<bool> {
+        self.inner.data_exists(key).await
+    }
+
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        self.inner.list_keys(prefix).await
+    }
+
+    async fn delete_data(&self, key: &str) -> Result<()> {
+        self.inner.delete_data(key).await
+    }
+
+    /// The URL would hand out the compressed object, which only this storage can read
+    async fn generate_presigned_get_url(&self, key: &str, _ttl: Duration) -> Result<String> {
+        Err(eyre!("The objects of the storage are compressed, {} can't be downloaded from a presigned URL", key))
+    }
+
+    #[cfg(test)]
+    async fn build_test_bucket(&self, bucket_name: &str) -> Result<()> {
+        self.inner.build_test_bucket(bucket_name).await
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use aes_gcm::aead::{Aead, AeadCore, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_default;
+use utils::settings::SettingsProvider;
+
+use crate::data_storage::{DataStorage, DataStream};
+
+pub const STORAGE_ENCRYPTION_SETTINGS_NAME: &str = "storage_encryption_settings";
+/// Hex encoded 256-bit key the objects are encrypted with, the encryption is disabled when unset
+pub const ENV_STORAGE_ENCRYPTION_KEY: &str = "STORAGE_ENCRYPTION_KEY";
+
+/// Marks the objects encrypted by [`EncryptedStorage`], followed by the nonce and the ciphertext
+const ENCRYPTED_HEADER_MAGIC: &[u8] = b"MADARA_ORCHESTRATOR_AES256GCM:";
+const NONCE_LEN: usize = 12;
+
+/// Client-side encryption settings of the storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageEncryptionSettings {
+    /// Hex encoded 256-bit AES key, `None` to store the objects in clear
+    pub key: Option<String>,
+}
+
+impl Default for StorageEncryptionSettings {
+    /// The key is read from `STORAGE_ENCRYPTION_KEY` unless the settings provider has one
+    fn default() -> Self {
+        let key = get_env_var_or_default(ENV_STORAGE_ENCRYPTION_KEY, "");
+        Self { key: (!key.is_empty()).then_some(key) }
+    }
+}
+
+/// Encrypts the data stored by the wrapped storage with AES-256-GCM, each object with its own
+/// random nonce. The key of the object is authenticated along with the data, an object copied
+/// under another key fails to decrypt.
+///
+/// Objects without the encryption header, written before the encryption was enabled, are
+/// returned as they are. The streams are encrypted and decrypted in memory, AES-GCM
+/// authenticating the object as a whole.
+pub struct EncryptedStorage {
+    inner: Box<dyn DataStorage>,
+    cipher: Aes256Gcm,
+}
+
+impl EncryptedStorage {
+    pub fn new(inner: Box<dyn DataStorage>, key: &[u8; 32]) -> Self {
+        Self { inner, cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)) }
+    }
+
+    /// Wraps the storage with the key of the settings, the storage is returned as is when there
+    /// is no key
+    pub fn with_settings(
+        inner: Box<dyn DataStorage>,
+        settings: &impl SettingsProvider,
+    ) -> Result<Box<dyn DataStorage>> {
+        let settings: StorageEncryptionSettings = settings.get_settings(STORAGE_ENCRYPTION_SETTINGS_NAME)?;
+        let Some(key) = settings.key else {
+            return Ok(inner);
+        };
+        let key: [u8; 32] = hex::decode(key.trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| eyre!("The storage encryption key must be 32 bytes long"))?;
+        Ok(Box::new(Self::new(inner, &key)))
+    }
+
+    fn encrypt(&self, key: &str, data: &[u8]) -> Result<Bytes> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: data, aad: key.as_bytes() })
+            .map_err(|e| eyre!("Failed to encrypt {}: {}", key, e))?;
+        let mut encrypted = ENCRYPTED_HEADER_MAGIC.to_vec();
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend_from_slice(&ciphertext);
+        Ok(encrypted.into())
+    }
+
+    fn decrypt(&self, key: &str, data: Bytes) -> Result<Bytes> {
+        if !data.starts_with(ENCRYPTED_HEADER_MAGIC) {
+            return Ok(data);
+        }
+        let body = &data[ENCRYPTED_HEADER_MAGIC.len()..];
+        if body.len() < NONCE_LEN {
+            return Err(eyre!("Invalid encryption header of {}", key));
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: key.as_bytes() })
+            .map_err(|e| eyre!("Failed to decrypt {}: {}", key, e))?;
+        Ok(plaintext.into())
+    }
+}
+
+#[async_trait]
+impl DataStorage for EncryptedStorage {
+    async fn get_data(&self, key: &str) -> Result<Bytes> {
+        let data = self.inner.get_data(key).await?;
+        self.decrypt(key, data)
+    }
+
+    async fn put_data(&self, data: Bytes, key: &str) -> Result<()> {
+        self.inner.put_data(self.encrypt(key, &data)?, key).await
+    }
+
+    async fn get_data_stream(&self, key: &str) -> Result<DataStream> {
+        let mut stream = self.inner.get_data_stream(key).await?;
+        let mut data = BytesMut::new();
+        while let Some(chunk) = stream.try_next().await? {
+            data.extend_from_slice(&chunk);
+        }
+        let data = self.decrypt(key, data.freeze())?;
+        Ok(futures::stream::once(async move { Ok(data) }).boxed())
+    }
+
+    async fn put_data_stream(&self, mut data: DataStream, key: &str) -> Result<()> {
+        let mut buffer = BytesMut::new();
+        while let Some(chunk) = data.try_next().await? {
+            buffer.extend_from_slice(&chunk);
+        }
+        self.put_data(buffer.freeze(), key).await
+    }
+
+    async fn data_exists(&self, key: &str) -> Result<bool> {
+        self.inner.data_exists(key).await
+    }
+
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        self.inner.list_keys(prefix).await
+    }
+
+    async fn delete_data(&self, key: &str) -> Result<()> {
+        self.inner.delete_data(key).await
+    }
+
+    /// The URL would hand out the encrypted object, which only this storage can read
+    async fn generate_presigned_get_url(&self, key: &str, _ttl: Duration) -> Result<String> {
+        Err(eyre!("The objects of the storage are encrypted, {} can't be downloaded from a presigned URL", key))
+    }
+
+    #[cfg(test)]
+    async fn build_test_bucket(&self, bucket_name: &str) -> Result<()> {
+        self.inner.build_test_bucket(bucket_name).await
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+use std::str::FromStr;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+
+use crate::constants::{
+    BLOB_DATA_FILE_NAME, CAIRO_PIE_FILE_NAME, DA_INCLUSION_PROOF_FILE_NAME, PROGRAM_OUTPUT_FILE_NAME, PROOF_FILE_NAME,
+    SNOS_INPUT_FILE_NAME, SNOS_OUTPUT_FILE_NAME, SNOS_STDERR_FILE_NAME, SNOS_STDOUT_FILE_NAME,
+};
+use crate::data_storage::DataStorage;
+use crate::deployment::DEPLOYMENT;
+
+/// Kind of the artifacts stored for the blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    BlobData,
+    CairoPie,
+    SnosInput,
+    SnosOutput,
+    /// Output of the Cairo run of SNOS, as committed to by the proof and passed to the core contract
+    ProgramOutput,
+    SnosStdout,
+    SnosStderr,
+    DaInclusionProof,
+    Proof,
+}
+
+impl ArtifactKind {
+    pub const ALL: [ArtifactKind; 9] = [
+        ArtifactKind::BlobData,
+        ArtifactKind::CairoPie,
+        ArtifactKind::SnosInput,
+        ArtifactKind::SnosOutput,
+        ArtifactKind::ProgramOutput,
+        ArtifactKind::SnosStdout,
+        ArtifactKind::SnosStderr,
+        ArtifactKind::DaInclusionProof,
+        ArtifactKind::Proof,
+    ];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ArtifactKind::BlobData => BLOB_DATA_FILE_NAME,
+            ArtifactKind::CairoPie => CAIRO_PIE_FILE_NAME,
+            ArtifactKind::SnosInput => SNOS_INPUT_FILE_NAME,
+            ArtifactKind::SnosOutput => SNOS_OUTPUT_FILE_NAME,
+            ArtifactKind::ProgramOutput => PROGRAM_OUTPUT_FILE_NAME,
+            ArtifactKind::SnosStdout => SNOS_STDOUT_FILE_NAME,
+            ArtifactKind::SnosStderr => SNOS_STDERR_FILE_NAME,
+            ArtifactKind::DaInclusionProof => DA_INCLUSION_PROOF_FILE_NAME,
+            ArtifactKind::Proof => PROOF_FILE_NAME,
+        }
+    }
+
+    /// Version of the format the artifacts of the kind are stored with, bumped whenever the format
+    /// changes so that the artifacts of the previous format are not read as the new one
+    pub fn current_version(&self) -> u32 {
+        1
+    }
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let file_name = self.file_name();
+        write!(f, "{}", file_name.split_once('.').map_or(file_name, |(stem, _)| stem))
+    }
+}
+
+impl FromStr for ArtifactKind {
+    type Err = color_eyre::Report;
+
+    /// Parses the kind from its file stem, as displayed
+    fn from_str(s: &str) -> Result<Self> {
+        ArtifactKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == s)
+            .ok_or_else(|| eyre!("Unknown artifact kind {}", s))
+    }
+}
+
+/// Key of an artifact of a block in the storage,
+/// `<chain_id>/<artifact kind>/<block number>/v<version>/<file name>`, so that several chains
+/// can share a bucket and the formats of the artifacts can evolve.
+///
+/// The chain is the `CHAIN_ID` of the deployment and the version the current version of the
+/// artifact kind, unless set otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageKey {
+    chain_id: String,
+    kind: ArtifactKind,
+    block_number: String,
+    version: u32,
+}
+
+impl StorageKey {
+    pub fn new(kind: ArtifactKind, block_number: impl ToString) -> Self {
+        Self {
+            chain_id: DEPLOYMENT.chain_id.clone(),
+            kind,
+            block_number: block_number.to_string(),
+            version: kind.current_version(),
+        }
+    }
+
+    pub fn chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+
+    pub fn version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Prefix of the keys of the artifact of the block, whatever their version
+    pub fn block_prefix(&self) -> String {
+        format!("{}/{}/{}/", self.chain_id, self.kind, self.block_number)
+    }
+
+    /// Key the artifact was stored under before the keys were namespaced, `<block number>/<file name>`
+    pub fn flat_key(&self) -> String {
+        format!("{}/{}", self.block_number, self.kind.file_name())
+    }
+
+    /// Parses a flat key, `None` when it isn't the key of an artifact of a block
+    pub fn from_flat_key(key: &str) -> Option<Self> {
+        let (block_number, file_name) = key.split_once('/')?;
+        if block_number.is_empty() || !block_number.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let kind = ArtifactKind::ALL.into_iter().find(|kind| kind.file_name() == file_name)?;
+        Some(Self::new(kind, block_number))
+    }
+}
+
+impl fmt::Display for StorageKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}/{}/v{}/{}", self.chain_id, self.kind, self.block_number, self.version, self.kind.file_name())
+    }
+}
+
+impl From<StorageKey> for String {
+    fn from(key: StorageKey) -> Self {
+        key.to_string()
+    }
+}
+
+/// Copies the artifacts stored under the flat keys of the previous schema to their namespaced
+/// key, for the chain of the deployment, and returns the number of artifacts copied. The
+/// artifacts already copied are skipped, so the migration can be run again if interrupted.
+///
+/// The flat keys are kept: the jobs created before the migration may have recorded them in their
+/// metadata. They can be deleted once these jobs are completed.
+pub async fn migrate_flat_keys(storage: &dyn DataStorage) -> Result<usize> {
+    let mut copied = 0;
+    for flat_key in storage.list_keys("").await? {
+        let Some(key) = StorageKey::from_flat_key(&flat_key) else {
+            continue;
+        };
+        let key = key.to_string();
+        if storage.data_exists(&key).await? {
+            continue;
+        }
+        storage.put_data(storage.get_data(&flat_key).await?, &key).await?;
+        copied += 1;
+    }
+    tracing::info!("Copied {} artifacts from their flat key to their namespaced key", copied);
+    Ok(copied)
+}
@@ -1,22 +1,52 @@
 pub mod aws_s3;
+pub mod cairo_pie;
+pub mod compressed;
+pub mod encrypted;
+pub mod key;
 pub mod types;
 
+use std::time::Duration;
+
 use async_trait::async_trait;
 use bytes::Bytes;
 use color_eyre::Result;
+use futures::stream::BoxStream;
 use mockall::automock;
 
+/// Data transferred in chunks, so that the artifacts don't have to fit in memory
+pub type DataStream = BoxStream<'static, Result<Bytes>>;
+
 /// DataStorage trait contains the functions used to store and get the data from
 /// the cloud provider storage.
-/// The proposed storage format is :
+/// The artifacts of the blocks are stored under
+/// `<chain_id>/<artifact kind>/<block_number>/v<version>/<file name>`, see [`key::StorageKey`]:
 ///     ----<block_number>
+///         ----<cairo_pie.zip> (stored during the SNOS job, compressed with `CAIRO_PIE_CODEC`)
+///         ----<snos_input.json> (stored on the first attempt of the SNOS job, reused by the retries)
 ///         ----<snos_output.json> (stored during the SNOS job)
+///         ----<snos_stdout.log>, <snos_stderr.log> (stored when the SNOS run fails)
 ///         ----<blob_data.txt> (stored during the DA job)
+///         ----<da_inclusion_proof.json> (stored once the DA job is verified)
+///         ----<proof.json> (stored once the proving job is verified, by the provers handing out the proof)
 #[automock]
 #[async_trait]
 pub trait DataStorage: Send + Sync {
     async fn get_data(&self, key: &str) -> Result<Bytes>;
     async fn put_data(&self, data: Bytes, key: &str) -> Result<()>;
+    /// Streams the data stored under the key, chunk by chunk
+    async fn get_data_stream(&self, key: &str) -> Result<DataStream>;
+    /// Stores the data of the stream under the key, without holding more than a chunk of it in
+    /// memory
+    async fn put_data_stream(&self, data: DataStream, key: &str) -> Result<()>;
+    /// Whether some data is stored under the key
+    async fn data_exists(&self, key: &str) -> Result<bool>;
+    /// Keys of the data stored under the prefix
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>>;
+    /// Deletes the data stored under the key, deleting a key which isn't there is not an error
+    async fn delete_data(&self, key: &str) -> Result<()>;
+    /// URL the data stored under the key can be downloaded from without credentials for `ttl`, so
+    /// that the services fetch the big artifacts from the storage directly
+    async fn generate_presigned_get_url(&self, key: &str, ttl: Duration) -> Result<String>;
     #[cfg(test)]
     async fn build_test_bucket(&self, bucket_name: &str) -> Result<()>;
 }
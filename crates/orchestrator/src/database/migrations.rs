@@ -0,0 +1,66 @@
+//! Versioning of the schema the jobs are stored with. Every change to [`crate::jobs::types::JobItem`]
+//! which the jobs already stored wouldn't deserialize with comes with a migration, applied by each
+//! database on startup before it is used.
+
+use async_trait::async_trait;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+
+/// Change of the schema of the job storage, the version of a storage is the version of the last
+/// migration applied to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+}
+
+/// Migrations of the job storage, by increasing version. A migration is never edited once
+/// released, the changes go in a new one.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "Jobs, worker state and audit log as of the first versioned schema" },
+    Migration { version: 2, description: "History of the status transitions of the jobs" },
+    Migration { version: 3, description: "Creation time of the jobs" },
+    Migration { version: 4, description: "Archive of the old completed jobs" },
+    Migration { version: 5, description: "Creation and update times of the jobs" },
+    Migration { version: 6, description: "Jobs the jobs depend on" },
+    Migration { version: 7, description: "Priority of the jobs" },
+    Migration { version: 8, description: "Attempt counters of the jobs moved out of the metadata" },
+    Migration { version: 9, description: "Leases of the leader election" },
+    Migration { version: 10, description: "Run history of the workers" },
+    Migration { version: 11, description: "Costs attributed to the blocks" },
+    Migration { version: 12, description: "Single job per type and internal id" },
+];
+
+/// Version of the schema this orchestrator stores the jobs with
+pub fn latest_schema_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Storage the migrations are applied to, implemented by each database
+#[async_trait]
+pub trait MigrationTarget: Send + Sync {
+    /// Version of the schema of the storage, 0 when it was never migrated
+    async fn schema_version(&self) -> Result<u32>;
+
+    /// Applies the migration and records its version, atomically when the storage allows it
+    async fn apply_migration(&self, migration: &Migration) -> Result<()>;
+}
+
+/// Applies the migrations the storage is missing, in order, and returns the version it ends at.
+/// Fails if the storage was migrated by a newer orchestrator, its jobs couldn't be read.
+pub async fn run_migrations(target: &dyn MigrationTarget) -> Result<u32> {
+    let current_version = target.schema_version().await?;
+    let latest_version = latest_schema_version();
+    if current_version > latest_version {
+        return Err(eyre!(
+            "Job storage is at schema version {} but this orchestrator only knows up to version {}",
+            current_version,
+            latest_version
+        ));
+    }
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > current_version) {
+        tracing::info!("Migrating the job storage to schema version {}: {}", migration.version, migration.description);
+        target.apply_migration(migration).await?;
+    }
+    Ok(latest_version)
+}
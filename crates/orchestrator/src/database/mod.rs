@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use ::mongodb::bson::doc;
 use async_trait::async_trait;
@@ -6,10 +7,15 @@ use color_eyre::Result;
 use mockall::automock;
 use uuid::Uuid;
 
-use crate::jobs::types::{JobItem, JobStatus, JobType};
+use crate::database::types::{AuditEvent, BlockCost, DatabaseWrite, JobEvent, JobFilter, JobPage, JobStats, WorkerRun};
+use crate::jobs::types::{JobCounter, JobItem, JobStatus, JobType};
 
+pub mod migrations;
 /// MongoDB
 pub mod mongodb;
+/// SQLite, for local development
+pub mod sqlite;
+pub mod types;
 
 /// The Database trait is used to define the methods that a database
 /// should implement to be used as a storage for the orchestrator. The
@@ -20,6 +26,10 @@ pub mod mongodb;
 /// A and B and both read the same Job entry J at nearly the same time. If A updates J at
 /// time T1 and then B updates J at time T2 (T2>T1), then B's update should fail because
 /// it's version of J is outdated.
+///
+/// The updates of the status of a job must also follow [`JobStatus::can_transition_to`] from its
+/// stored status, the others fail with an
+/// [`IllegalStatusTransitionError`](crate::jobs::types::IllegalStatusTransitionError).
 #[automock]
 #[async_trait]
 pub trait Database: Send + Sync {
@@ -29,7 +39,18 @@ pub trait Database: Send + Sync {
     async fn update_job(&self, job: &JobItem) -> Result<()>;
     async fn update_job_status(&self, job: &JobItem, new_status: JobStatus) -> Result<()>;
     async fn update_metadata(&self, job: &JobItem, metadata: HashMap<String, String>) -> Result<()>;
+    /// Increments the counter of the job and returns its new value. The increment is atomic and
+    /// doesn't depend on the version of the job, the concurrent increments all count.
+    async fn increment_job_counter(&self, job: &JobItem, counter: JobCounter) -> Result<u64>;
+    /// Resets the counters of the job to 0
+    async fn reset_job_counters(&self, job: &JobItem, counters: &[JobCounter]) -> Result<()>;
+    /// Returns the job of the given type with the highest block, the last one of a block range
     async fn get_latest_job_by_type(&self, job_type: JobType) -> Result<Option<JobItem>>;
+    /// Returns the `limit` most recently created jobs of the given type, after skipping the
+    /// `offset` most recent ones
+    async fn get_latest_jobs_by_type(&self, job_type: JobType, offset: u64, limit: i64) -> Result<Vec<JobItem>>;
+    /// Returns the jobs of type A in the given status without a job of type B succeeding them: a
+    /// job of the same internal id, or whose block range covers the last block of the job A
     async fn get_jobs_without_successor(
         &self,
         job_a_type: JobType,
@@ -50,6 +71,64 @@ pub trait Database: Send + Sync {
 
     // TODO: can be extendible to support multiple status.
     async fn get_jobs_by_statuses(&self, status: Vec<JobStatus>, limit: Option<i64>) -> Result<Vec<JobItem>>;
+    /// Returns the number of jobs of each type in each status and the age of the oldest pending
+    /// job, computed by the database rather than by reading the jobs
+    async fn get_job_stats(&self) -> Result<JobStats>;
+    /// Returns the jobs in the status which weren't updated for `older_than`, e.g. the jobs left
+    /// locked for processing by a worker which crashed
+    async fn get_jobs_stuck_in_status(&self, status: JobStatus, older_than: Duration) -> Result<Vec<JobItem>>;
+    /// Returns up to `limit` jobs matching the filter, by creation order, starting after the
+    /// `cursor` returned with the previous page. The first page is fetched without a cursor.
+    async fn get_jobs_paginated(&self, filter: JobFilter, cursor: Option<String>, limit: i64) -> Result<JobPage>;
+    /// Returns the jobs depending on the job with the given id
+    async fn get_jobs_by_parent_id(&self, parent_id: Uuid) -> Result<Vec<JobItem>>;
+    /// Returns the jobs of any type covering the block, by creation order: the jobs whose internal
+    /// id is the block number or a range of blocks including it
+    async fn get_jobs_by_block(&self, block_number: u64) -> Result<Vec<JobItem>>;
+
+    /// Returns the value stored under `key` in the state of `worker`, used by workers to resume
+    /// where they left off
+    async fn get_worker_state(&self, worker: &str, key: &str) -> Result<Option<serde_json::Value>>;
+    /// Stores `value` under `key` in the state of `worker`, overwriting the previous value
+    async fn set_worker_state(&self, worker: &str, key: &str, value: serde_json::Value) -> Result<()>;
+
+    /// Takes or renews the lease `name` for `holder` until `duration` from now, returns whether
+    /// `holder` holds it. A lease held by another holder is only taken once it has expired.
+    async fn acquire_lease(&self, name: &str, holder: &str, duration: Duration) -> Result<bool>;
+
+    /// Appends the run to the run history of its worker
+    async fn record_worker_run(&self, run: WorkerRun) -> Result<()>;
+    /// Returns the `limit` most recent runs of the worker, the most recent first
+    async fn get_worker_runs(&self, worker: &str, limit: i64) -> Result<Vec<WorkerRun>>;
+    /// Returns the most recent successful run of the worker
+    async fn get_last_successful_worker_run(&self, worker: &str) -> Result<Option<WorkerRun>>;
+    /// Deletes the runs of all the workers which started before `before`, a unix timestamp in
+    /// seconds, and returns how many were deleted
+    async fn delete_worker_runs_before(&self, before: u64) -> Result<u64>;
+
+    /// Appends an event to the audit log
+    async fn record_audit_event(&self, event: AuditEvent) -> Result<()>;
+
+    /// Attributes the spend of a job to a block, overwriting what was recorded for the same job
+    /// and block so that a job verified several times only counts once
+    async fn record_block_cost(&self, cost: BlockCost) -> Result<()>;
+    /// Returns the spend attributed to the block, by job
+    async fn get_block_costs(&self, block_number: u64) -> Result<Vec<BlockCost>>;
+
+    /// Appends a status transition to the history of the job
+    async fn append_job_event(&self, event: JobEvent) -> Result<()>;
+    /// Returns the status transitions of the job, oldest first
+    async fn get_job_events(&self, job_id: Uuid) -> Result<Vec<JobEvent>>;
+
+    /// Moves the jobs to the archive, out of the jobs the orchestrator works on, and returns how
+    /// many were moved. The jobs already archived are skipped.
+    async fn archive_jobs(&self, ids: &[Uuid]) -> Result<u64>;
+    /// Returns the archived job with the given id
+    async fn get_archived_job_by_id(&self, id: Uuid) -> Result<Option<JobItem>>;
+
+    /// Applies the writes in order, all of them or none, e.g. to create several jobs which only
+    /// make sense together. A job update on an outdated version fails the whole transaction.
+    async fn run_transaction(&self, writes: Vec<DatabaseWrite>) -> Result<()>;
 }
 
 pub trait DatabaseConfig {
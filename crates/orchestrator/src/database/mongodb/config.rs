@@ -1,13 +1,31 @@
-use utils::env_utils::get_env_var_or_panic;
+use utils::env_utils::{get_env_var_or_default, get_env_var_or_panic};
 
 use crate::database::DatabaseConfig;
 
+/// Name of the MongoDB database the orchestrator stores its collections in
+pub const ENV_MONGODB_DATABASE_NAME: &str = "MONGODB_DATABASE_NAME";
+/// Prefix of the names of the collections, so that the orchestrators of several appchains can
+/// share a database
+pub const ENV_MONGODB_COLLECTION_PREFIX: &str = "MONGODB_COLLECTION_PREFIX";
+/// Allows a standalone MongoDB server, which doesn't support transactions, the writes meant to be
+/// atomic are then applied one after the other. Only meant for local development and tests.
+pub const ENV_MONGODB_ALLOW_NON_ATOMIC_TRANSACTIONS: &str = "MONGODB_ALLOW_NON_ATOMIC_TRANSACTIONS";
+
 pub struct MongoDbConfig {
     pub url: String,
+    pub database_name: String,
+    pub collection_prefix: String,
+    pub allow_non_atomic_transactions: bool,
 }
 
 impl DatabaseConfig for MongoDbConfig {
     fn new_from_env() -> Self {
-        Self { url: get_env_var_or_panic("MONGODB_CONNECTION_STRING") }
+        Self {
+            url: get_env_var_or_panic("MONGODB_CONNECTION_STRING"),
+            database_name: get_env_var_or_default(ENV_MONGODB_DATABASE_NAME, "orchestrator"),
+            collection_prefix: get_env_var_or_default(ENV_MONGODB_COLLECTION_PREFIX, ""),
+            allow_non_atomic_transactions: get_env_var_or_default(ENV_MONGODB_ALLOW_NON_ATOMIC_TRANSACTIONS, "false")
+                == "true",
+        }
     }
 }
@@ -1,28 +1,59 @@
 use async_std::stream::StreamExt;
 use futures::TryStreamExt;
 use std::collections::HashMap;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use color_eyre::eyre::eyre;
 use color_eyre::Result;
+use mongodb::bson::oid::ObjectId;
 use mongodb::bson::{Bson, Document};
-use mongodb::options::{FindOneOptions, FindOptions, UpdateOptions};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::{
+    FindOneAndUpdateOptions, FindOneOptions, FindOptions, IndexOptions, ReplaceOptions, ReturnDocument, UpdateOptions,
+};
 use mongodb::{
     bson,
     bson::doc,
     options::{ClientOptions, ServerApi, ServerApiVersion},
-    Client, Collection,
+    Client, ClientSession, Collection, IndexModel,
 };
+use serde::Deserialize;
 use uuid::Uuid;
 
-use crate::database::mongodb::config::MongoDbConfig;
+use crate::database::migrations::{run_migrations, Migration, MigrationTarget};
+use crate::database::mongodb::config::{MongoDbConfig, ENV_MONGODB_ALLOW_NON_ATOMIC_TRANSACTIONS};
+use crate::database::types::{
+    now_secs, AuditEvent, BlockCost, DatabaseWrite, JobCount, JobEvent, JobFilter, JobPage, JobStats,
+    PENDING_JOB_STATUSES, WorkerRun,
+};
 use crate::database::Database;
-use crate::jobs::types::{JobItem, JobStatus, JobType};
+use crate::jobs::constants::{
+    JOB_METADATA_PROCESS_TIMEOUTS_KEY, JOB_PROCESS_ATTEMPT_METADATA_KEY, JOB_VERIFICATION_ATTEMPT_METADATA_KEY,
+};
+use crate::jobs::types::{IllegalStatusTransitionError, JobCounter, JobItem, JobStatus, JobType};
 
 pub mod config;
 
+/// Indexes of the `jobs` collection created on startup, by name, with their keys and whether they
+/// are unique
+pub const JOB_INDEXES: [(&str, &[&str], bool); 4] = [
+    ("id", &["id"], true),
+    ("internal_id_job_type", &["internal_id", "job_type"], true),
+    ("status_job_type", &["status", "job_type"], false),
+    ("parent_ids", &["parent_ids"], false),
+];
+
+/// Id of the document of the `schema_version` collection holding the version of the job storage
+const JOBS_SCHEMA_ID: &str = "jobs";
+
 pub struct MongoDb {
     client: Client,
+    database_name: String,
+    collection_prefix: String,
+    /// Whether the deployment is a replica set or a sharded cluster, the standalone servers don't
+    /// support transactions and are only accepted with `MONGODB_ALLOW_NON_ATOMIC_TRANSACTIONS`
+    supports_transactions: bool,
 }
 
 impl MongoDb {
@@ -36,9 +67,35 @@ impl MongoDb {
         let client = Client::with_options(client_options).expect("Failed to create MongoDB client");
         // Ping the server to see if you can connect to the cluster
         client.database("admin").run_command(doc! {"ping": 1}, None).await.expect("Failed to ping MongoDB deployment");
-        println!("Pinged your deployment. You successfully connected to MongoDB!");
+        tracing::info!("Pinged your deployment. You successfully connected to MongoDB!");
+
+        let hello = client
+            .database("admin")
+            .run_command(doc! {"hello": 1}, None)
+            .await
+            .expect("Failed to describe the MongoDB deployment");
+        let supports_transactions =
+            hello.contains_key("setName") || hello.get_str("msg").is_ok_and(|msg| msg == "isdbgrid");
+        if !supports_transactions {
+            if !config.allow_non_atomic_transactions {
+                panic!(
+                    "MongoDB deployment is a standalone server which doesn't support transactions, run a replica set \
+                     or set {}=true to apply the writes of a transaction one after the other",
+                    ENV_MONGODB_ALLOW_NON_ATOMIC_TRANSACTIONS
+                );
+            }
+            tracing::warn!("MongoDB deployment is a standalone server, the writes of a transaction aren't atomic");
+        }
 
-        MongoDb { client }
+        let mongo_db = MongoDb {
+            client,
+            database_name: config.database_name,
+            collection_prefix: config.collection_prefix,
+            supports_transactions,
+        };
+        run_migrations(&mongo_db).await.expect("Failed to migrate the MongoDB job storage");
+        mongo_db.create_indexes().await.expect("Failed to create the MongoDB indexes");
+        mongo_db
     }
 
     /// Mongodb client uses Arc internally, reducing the cost of clone.
@@ -47,25 +104,219 @@ impl MongoDb {
         self.client.clone()
     }
 
+    /// Creates the indexes of [`JOB_INDEXES`] missing from the `jobs` collection, the existing
+    /// ones are left untouched. Fails if the stored jobs break a unique index.
+    pub async fn create_indexes(&self) -> Result<()> {
+        let indexes = JOB_INDEXES.iter().map(|(name, keys, unique)| {
+            let keys = keys.iter().fold(Document::new(), |mut keys, key| {
+                keys.insert(*key, 1);
+                keys
+            });
+            let options = IndexOptions::builder().name(name.to_string()).unique(*unique).build();
+            IndexModel::builder().keys(keys).options(options).build()
+        });
+        self.get_job_collection().create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    /// Names of the indexes of the `jobs` collection
+    pub async fn job_index_names(&self) -> Result<Vec<String>> {
+        Ok(self.get_job_collection().list_index_names().await?)
+    }
+
+    /// Collection of the orchestrator database, its name prefixed with the collection prefix
+    fn collection<T>(&self, name: &str) -> Collection<T> {
+        self.client.database(&self.database_name).collection(&self.collection_name(name))
+    }
+
+    /// Name of a collection prefixed with the collection prefix, e.g. for the `$lookup` stages
+    fn collection_name(&self, name: &str) -> String {
+        format!("{}{}", self.collection_prefix, name)
+    }
+
     fn get_job_collection(&self) -> Collection<JobItem> {
-        self.client.database("orchestrator").collection("jobs")
+        self.collection("jobs")
+    }
+
+    fn get_worker_state_collection(&self) -> Collection<Document> {
+        self.collection("worker_state")
+    }
+
+    fn get_audit_log_collection(&self) -> Collection<AuditEvent> {
+        self.collection("audit_log")
+    }
+
+    /// Jobs as raw documents, along with their object id
+    fn get_job_document_collection(&self) -> Collection<Document> {
+        self.collection("jobs")
+    }
+
+    /// Archived jobs, as raw documents keeping the object id they had in the `jobs` collection
+    fn get_job_archive_collection(&self) -> Collection<Document> {
+        self.collection("jobs_archive")
+    }
+
+    fn get_job_history_collection(&self) -> Collection<JobEvent> {
+        self.collection("job_history")
+    }
+
+    fn get_schema_version_collection(&self) -> Collection<Document> {
+        self.collection("schema_version")
+    }
+
+    fn get_lease_collection(&self) -> Collection<Document> {
+        self.collection("leases")
+    }
+
+    fn get_worker_run_collection(&self) -> Collection<WorkerRun> {
+        self.collection("worker_runs")
+    }
+
+    fn get_block_cost_collection(&self) -> Collection<BlockCost> {
+        self.collection("block_costs")
     }
 
     /// Updates the job in the database optimistically. This means that the job is updated only if
     /// the version of the job in the database is the same as the version of the job passed in.
-    /// If the version is different, the update fails.
-    async fn update_job_optimistically(&self, current_job: &JobItem, update: Document) -> Result<()> {
-        let filter = doc! {
+    /// If the version is different, the update fails. The update time of the job is set as well.
+    async fn update_job_optimistically(
+        &self,
+        current_job: &JobItem,
+        new_status: Option<&JobStatus>,
+        mut update: Document,
+        session: Option<&mut ClientSession>,
+    ) -> Result<()> {
+        update.get_document_mut("$set")?.insert("updated_at", i64::try_from(now_secs())?);
+        let mut filter = doc! {
             "id": current_job.id,
             "version": current_job.version,
         };
+        // the job must be able to move to the new status from the stored one
+        if let Some(new_status) = new_status {
+            let predecessors = new_status.predecessors().iter().map(bson::to_bson).collect::<Result<Vec<_>, _>>()?;
+            filter.insert("status", doc! { "$in": predecessors });
+        }
         let options = UpdateOptions::builder().upsert(false).build();
-        let result = self.get_job_collection().update_one(filter, update, options).await?;
+        let result = match session {
+            Some(session) => {
+                self.get_job_collection().update_one_with_session(filter, update, options, session).await?
+            }
+            None => self.get_job_collection().update_one(filter, update, options).await?,
+        };
         if result.modified_count == 0 {
+            // when the job is up to date, its status is what prevented the update
+            if let Some(new_status) = new_status {
+                let filter = doc! { "id": current_job.id, "version": current_job.version };
+                if let Some(stored_job) = self.get_job_collection().find_one(filter, None).await? {
+                    return Err(IllegalStatusTransitionError {
+                        id: current_job.id,
+                        from: stored_job.status,
+                        to: new_status.clone(),
+                    }
+                    .into());
+                }
+            }
             return Err(eyre!("Failed to update job. Job version is likely outdated"));
         }
         Ok(())
     }
+
+    /// Applies a write of a transaction within its session
+    async fn apply_write(&self, write: DatabaseWrite, session: &mut ClientSession) -> Result<()> {
+        match write {
+            DatabaseWrite::CreateJob(job) => {
+                self.get_job_collection().insert_one_with_session(&job, None, session).await?;
+            }
+            DatabaseWrite::UpdateJob(job) => {
+                let update = doc! { "$set": job_update_document(&job)? };
+                self.update_job_optimistically(&job, Some(&job.status), update, Some(session)).await?;
+            }
+            DatabaseWrite::AppendJobEvent(event) => {
+                self.get_job_history_collection().insert_one_with_session(&event, None, session).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MigrationTarget for MongoDb {
+    async fn schema_version(&self) -> Result<u32> {
+        let filter = doc! { "_id": JOBS_SCHEMA_ID };
+        match self.get_schema_version_collection().find_one(filter, None).await? {
+            Some(schema) => Ok(schema.get_i64("version")?.try_into()?),
+            None => Ok(0),
+        }
+    }
+
+    async fn apply_migration(&self, migration: &Migration) -> Result<()> {
+        match migration.version {
+            // the jobs stored before the schema was versioned already match it
+            1 => {}
+            // the job history collection is created by its first insert
+            2 => {}
+            // the creation time of a job is the one of its object id
+            3 => {}
+            // the archive collection is created by its first insert
+            4 => {}
+            // the update time of the jobs stored before is unknown, both are the time of their object id
+            5 => {
+                let object_id_secs = doc! { "$toLong": { "$divide": [{ "$toLong": { "$toDate": "$_id" } }, 1000] } };
+                let filter = doc! { "created_at": { "$exists": false } };
+                let update =
+                    vec![doc! { "$set": { "created_at": object_id_secs.clone(), "updated_at": object_id_secs } }];
+                self.get_job_document_collection().update_many(filter.clone(), update.clone(), None).await?;
+                self.get_job_archive_collection().update_many(filter, update, None).await?;
+            }
+            // the jobs stored before have no parents, the field defaults to none
+            6 => {}
+            // the jobs stored before have the normal priority, the field defaults to it
+            7 => {}
+            // the counters stored as strings in the metadata move to their own fields
+            8 => {
+                let counter = |key: &str| doc! { "$toLong": { "$ifNull": [format!("$metadata.{}", key), "0"] } };
+                let filter = doc! { "counters": { "$exists": false } };
+                let update = vec![
+                    doc! { "$set": { "counters": {
+                        "process_attempts": counter(JOB_PROCESS_ATTEMPT_METADATA_KEY),
+                        "verification_attempts": counter(JOB_VERIFICATION_ATTEMPT_METADATA_KEY),
+                        "process_timeouts": counter(JOB_METADATA_PROCESS_TIMEOUTS_KEY),
+                    } } },
+                    doc! { "$unset": [
+                        format!("metadata.{}", JOB_PROCESS_ATTEMPT_METADATA_KEY),
+                        format!("metadata.{}", JOB_VERIFICATION_ATTEMPT_METADATA_KEY),
+                        format!("metadata.{}", JOB_METADATA_PROCESS_TIMEOUTS_KEY),
+                    ] },
+                ];
+                self.get_job_document_collection().update_many(filter.clone(), update.clone(), None).await?;
+                self.get_job_archive_collection().update_many(filter, update, None).await?;
+            }
+            // the leases collection is created by its first write
+            9 => {}
+            // the runs are listed by worker, the most recent first, and pruned by age
+            10 => {
+                let indexes = [doc! { "worker": 1, "started_at": -1 }, doc! { "started_at": 1 }]
+                    .into_iter()
+                    .map(|keys| IndexModel::builder().keys(keys).build());
+                self.get_worker_run_collection().create_indexes(indexes, None).await?;
+            }
+            // a job is attributed once to each block, the costs are read by block
+            11 => {
+                let options = IndexOptions::builder().unique(true).build();
+                let keys = doc! { "block_number": 1, "job_id": 1 };
+                let index = IndexModel::builder().keys(keys).options(options).build();
+                self.get_block_cost_collection().create_index(index, None).await?;
+            }
+            // the jobs are unique by type and internal id through the index created on startup
+            12 => {}
+            version => return Err(eyre!("Unknown migration {} of the MongoDB job storage", version)),
+        }
+        let filter = doc! { "_id": JOBS_SCHEMA_ID };
+        let update = doc! { "$set": { "version": i64::from(migration.version) } };
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.get_schema_version_collection().update_one(filter, update, options).await?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -91,11 +342,11 @@ impl Database for MongoDb {
     }
 
     async fn update_job(&self, job: &JobItem) -> Result<()> {
-        let job_doc = bson::to_document(job)?;
+        let job_doc = job_update_document(job)?;
         let update = doc! {
             "$set": job_doc
         };
-        self.update_job_optimistically(job, update).await?;
+        self.update_job_optimistically(job, Some(&job.status), update, None).await?;
         Ok(())
     }
 
@@ -105,7 +356,7 @@ impl Database for MongoDb {
                 "status": mongodb::bson::to_bson(&new_status)?,
             }
         };
-        self.update_job_optimistically(job, update).await?;
+        self.update_job_optimistically(job, Some(&new_status), update, None).await?;
         Ok(())
     }
 
@@ -115,16 +366,64 @@ impl Database for MongoDb {
                 "metadata":  mongodb::bson::to_document(&metadata)?
             }
         };
-        self.update_job_optimistically(job, update).await?;
+        self.update_job_optimistically(job, None, update, None).await?;
+        Ok(())
+    }
+
+    async fn increment_job_counter(&self, job: &JobItem, counter: JobCounter) -> Result<u64> {
+        let mut increment = Document::new();
+        increment.insert(format!("counters.{}", counter.field()), 1_i64);
+        let update = doc! {
+            "$inc": increment,
+            "$set": { "updated_at": i64::try_from(now_secs())? },
+        };
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        let updated_job = self
+            .get_job_collection()
+            .find_one_and_update(doc! { "id": job.id }, update, options)
+            .await?
+            .ok_or_else(|| eyre!("Failed to increment the {:?} counter of job {:?}, job not found", counter, job.id))?;
+        Ok(updated_job.counters.get(counter))
+    }
+
+    async fn reset_job_counters(&self, job: &JobItem, counters: &[JobCounter]) -> Result<()> {
+        let mut reset = doc! { "updated_at": i64::try_from(now_secs())? };
+        for counter in counters {
+            reset.insert(format!("counters.{}", counter.field()), 0_i64);
+        }
+        let update = doc! { "$set": reset };
+        let result = self.get_job_collection().update_one(doc! { "id": job.id }, update, None).await?;
+        if result.matched_count == 0 {
+            return Err(eyre!("Failed to reset the counters of job {:?}, job not found", job.id));
+        }
         Ok(())
     }
 
     async fn get_latest_job_by_type(&self, job_type: JobType) -> Result<Option<JobItem>> {
+        // the blocks are compared as numbers, the internal ids which aren't blocks have a null
+        // last block sorted after them
+        let pipeline = vec![
+            doc! { "$match": { "job_type": bson::to_bson(&job_type)? } },
+            doc! { "$addFields": { "last_block": internal_id_block("$internal_id", -1) } },
+            doc! { "$sort": { "last_block": -1, "internal_id": -1 } },
+            doc! { "$limit": 1 },
+            doc! { "$project": { "last_block": 0 } },
+        ];
+        let mut cursor = self.get_job_collection().aggregate(pipeline, None).await?;
+        match cursor.next().await.transpose()? {
+            Some(document) => Ok(Some(bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_latest_jobs_by_type(&self, job_type: JobType, offset: u64, limit: i64) -> Result<Vec<JobItem>> {
         let filter = doc! {
-            "job_type": mongodb::bson::to_bson(&job_type)?,
+            "job_type": bson::to_bson(&job_type)?,
         };
-        let find_options = FindOneOptions::builder().sort(doc! { "internal_id": -1 }).build();
-        Ok(self.get_job_collection().find_one(filter, find_options).await?)
+        // the object id embeds the insertion time
+        let find_options = FindOptions::builder().sort(doc! { "_id": -1 }).skip(offset).limit(limit).build();
+        let jobs = self.get_job_collection().find(filter, find_options).await?.try_collect().await?;
+        Ok(jobs)
     }
 
     /// function to get jobs that don't have a successor job.
@@ -174,16 +473,24 @@ impl Database for MongoDb {
             // Stage 2: Lookup to find corresponding job_b_type jobs
             doc! {
                 "$lookup": {
-                    "from": "jobs",
-                    "let": { "internal_id": "$internal_id" },
+                    "from": self.collection_name("jobs"),
+                    "let": { "internal_id": "$internal_id", "last_block": internal_id_block("$internal_id", -1) },
                     "pipeline": [
                         {
                             "$match": {
                                 "$expr": {
                                     "$and": [
                                         { "$eq": ["$job_type", job_b_type_bson] },
-                                        // Conditionally match job_b_status if provided
-                                        { "$eq": ["$internal_id", "$$internal_id"] }
+                                        // same internal id, or a block range covering the last block of job A
+                                        { "$or": [
+                                            { "$eq": ["$internal_id", "$$internal_id"] },
+                                            { "$and": [
+                                                { "$ne": ["$$last_block", Bson::Null] },
+                                                { "$ne": [internal_id_block("$internal_id", 0), Bson::Null] },
+                                                { "$lte": [internal_id_block("$internal_id", 0), "$$last_block"] },
+                                                { "$gte": [internal_id_block("$internal_id", -1), "$$last_block"] },
+                                            ] },
+                                        ] },
                                     ]
                                 }
                             }
@@ -238,9 +545,9 @@ impl Database for MongoDb {
             match result {
                 Ok(document) => match bson::from_bson(Bson::Document(document)) {
                     Ok(job_item) => vec_jobs.push(job_item),
-                    Err(e) => eprintln!("Failed to deserialize JobItem: {:?}", e),
+                    Err(e) => tracing::error!("Failed to deserialize JobItem: {:?}", e),
                 },
-                Err(e) => eprintln!("Error retrieving document: {:?}", e),
+                Err(e) => tracing::error!("Error retrieving document: {:?}", e),
             }
         }
 
@@ -254,7 +561,7 @@ impl Database for MongoDb {
     ) -> Result<Option<JobItem>> {
         let filter = doc! {
             "job_type": bson::to_bson(&job_type)?,
-            "job_status": bson::to_bson(&job_status)?
+            "status": bson::to_bson(&job_status)?
         };
         let find_options = FindOneOptions::builder().sort(doc! { "internal_id": -1 }).build();
 
@@ -269,7 +576,7 @@ impl Database for MongoDb {
     ) -> Result<Vec<JobItem>> {
         let filter = doc! {
             "job_type": bson::to_bson(&job_type)?,
-            "job_status": bson::to_bson(&job_status)?,
+            "status": bson::to_bson(&job_status)?,
             "internal_id": { "$gt": internal_id }
         };
 
@@ -280,7 +587,7 @@ impl Database for MongoDb {
 
     async fn get_jobs_by_statuses(&self, job_status: Vec<JobStatus>, limit: Option<i64>) -> Result<Vec<JobItem>> {
         let filter = doc! {
-            "job_status": {
+            "status": {
                 // TODO: Check that the conversion leads to valid output!
                 "$in": job_status.iter().map(|status| bson::to_bson(status).unwrap_or(Bson::Null)).collect::<Vec<Bson>>()
             }
@@ -292,4 +599,299 @@ impl Database for MongoDb {
 
         Ok(jobs)
     }
+
+    /// The counts and the oldest pending job are two facets of a single aggregation
+    async fn get_job_stats(&self) -> Result<JobStats> {
+        let pending_statuses = PENDING_JOB_STATUSES.iter().map(bson::to_bson).collect::<Result<Vec<_>, _>>()?;
+        let pipeline = vec![doc! {
+            "$facet": {
+                "counts": [
+                    { "$group": { "_id": { "job_type": "$job_type", "status": "$status" }, "count": { "$sum": 1 } } },
+                ],
+                "oldest_pending": [
+                    { "$match": { "status": { "$in": pending_statuses } } },
+                    { "$group": { "_id": Bson::Null, "created_at": { "$min": "$created_at" } } },
+                ],
+            }
+        }];
+        let mut cursor = self.get_job_collection().aggregate(pipeline, None).await?;
+        let Some(document) = cursor.next().await.transpose()? else {
+            return Ok(JobStats::default());
+        };
+        let stats: JobStatsDocument = bson::from_document(document)?;
+
+        let counts = stats
+            .counts
+            .into_iter()
+            .map(|count| JobCount { job_type: count.key.job_type, status: count.key.status, count: count.count })
+            .collect();
+        let oldest_pending_job_age =
+            stats.oldest_pending.first().map(|oldest| now_secs().saturating_sub(oldest.created_at));
+        Ok(JobStats { counts, oldest_pending_job_age })
+    }
+
+    async fn get_jobs_stuck_in_status(&self, status: JobStatus, older_than: Duration) -> Result<Vec<JobItem>> {
+        let updated_before = now_secs().saturating_sub(older_than.as_secs());
+        let filter = doc! {
+            "status": bson::to_bson(&status)?,
+            "updated_at": { "$lt": i64::try_from(updated_before)? },
+        };
+        let find_options = FindOptions::builder().sort(doc! { "updated_at": 1 }).build();
+        Ok(self.get_job_collection().find(filter, find_options).await?.try_collect().await?)
+    }
+
+    /// The cursor is the object id of the last job of the page, the object ids increase with the
+    /// creation time
+    async fn get_jobs_paginated(&self, filter: JobFilter, cursor: Option<String>, limit: i64) -> Result<JobPage> {
+        let mut filter_doc = Document::new();
+        if let Some(job_type) = &filter.job_type {
+            filter_doc.insert("job_type", bson::to_bson(job_type)?);
+        }
+        if let Some(status) = &filter.status {
+            filter_doc.insert("status", bson::to_bson(status)?);
+        }
+        if let Some(internal_id_range) = &filter.internal_id_range {
+            // the internal ids which aren't numbers convert to null, which is below any number
+            let internal_id = doc! { "$convert": { "input": "$internal_id", "to": "long", "onError": Bson::Null } };
+            filter_doc.insert(
+                "$expr",
+                doc! { "$and": [
+                    { "$gte": [internal_id.clone(), i64::try_from(*internal_id_range.start())?] },
+                    { "$lte": [internal_id, i64::try_from(*internal_id_range.end())?] },
+                ] },
+            );
+        }
+        let mut object_id_range = Document::new();
+        if let Some(cursor) = &cursor {
+            object_id_range.insert("$gt", ObjectId::parse_str(cursor)?);
+        }
+        if let Some(created_after) = filter.created_after {
+            object_id_range.insert("$gte", object_id_at(created_after)?);
+        }
+        if let Some(created_before) = filter.created_before {
+            object_id_range.insert("$lt", object_id_at(created_before)?);
+        }
+        if !object_id_range.is_empty() {
+            filter_doc.insert("_id", object_id_range);
+        }
+
+        let find_options = FindOptions::builder().sort(doc! { "_id": 1 }).limit(limit).build();
+        let documents: Vec<Document> =
+            self.get_job_document_collection().find(filter_doc, find_options).await?.try_collect().await?;
+        let next_cursor = match documents.last() {
+            Some(last) if documents.len() as i64 == limit => Some(last.get_object_id("_id")?.to_hex()),
+            _ => None,
+        };
+        let jobs = documents.into_iter().map(bson::from_document).collect::<Result<Vec<JobItem>, _>>()?;
+        Ok(JobPage { jobs, next_cursor })
+    }
+
+    async fn get_jobs_by_parent_id(&self, parent_id: Uuid) -> Result<Vec<JobItem>> {
+        let filter = doc! { "parent_ids": parent_id };
+        Ok(self.get_job_collection().find(filter, None).await?.try_collect().await?)
+    }
+
+    async fn get_jobs_by_block(&self, block_number: u64) -> Result<Vec<JobItem>> {
+        let block_number = i64::try_from(block_number)?;
+        let filter = doc! { "$expr": { "$and": [
+            { "$ne": [internal_id_block("$internal_id", 0), Bson::Null] },
+            { "$lte": [internal_id_block("$internal_id", 0), block_number] },
+            { "$gte": [internal_id_block("$internal_id", -1), block_number] },
+        ] } };
+        let find_options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        Ok(self.get_job_collection().find(filter, find_options).await?.try_collect().await?)
+    }
+
+    async fn get_worker_state(&self, worker: &str, key: &str) -> Result<Option<serde_json::Value>> {
+        let filter = doc! { "worker": worker, "key": key };
+        match self.get_worker_state_collection().find_one(filter, None).await? {
+            Some(state) => Ok(Some(bson::from_bson(state.get("value").cloned().unwrap_or(Bson::Null))?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_worker_state(&self, worker: &str, key: &str, value: serde_json::Value) -> Result<()> {
+        let filter = doc! { "worker": worker, "key": key };
+        let update = doc! { "$set": { "value": bson::to_bson(&value)? } };
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.get_worker_state_collection().update_one(filter, update, options).await?;
+        Ok(())
+    }
+
+    async fn acquire_lease(&self, name: &str, holder: &str, duration: Duration) -> Result<bool> {
+        let now = now_secs();
+        let filter = doc! {
+            "_id": name,
+            "$or": [{ "holder": holder }, { "expires_at": { "$lte": i64::try_from(now)? } }],
+        };
+        let update = doc! { "$set": { "holder": holder, "expires_at": i64::try_from(now + duration.as_secs())? } };
+        let options = UpdateOptions::builder().upsert(true).build();
+        match self.get_lease_collection().update_one(filter, update, options).await {
+            Ok(_) => Ok(true),
+            // the lease is held by another holder, the upsert collided with its document
+            Err(e) if is_duplicate_key_error(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn record_worker_run(&self, run: WorkerRun) -> Result<()> {
+        self.get_worker_run_collection().insert_one(&run, None).await?;
+        Ok(())
+    }
+
+    async fn get_worker_runs(&self, worker: &str, limit: i64) -> Result<Vec<WorkerRun>> {
+        let filter = doc! { "worker": worker };
+        let find_options = FindOptions::builder().sort(doc! { "started_at": -1, "_id": -1 }).limit(limit).build();
+        Ok(self.get_worker_run_collection().find(filter, find_options).await?.try_collect().await?)
+    }
+
+    async fn get_last_successful_worker_run(&self, worker: &str) -> Result<Option<WorkerRun>> {
+        let filter = doc! { "worker": worker, "error": null };
+        let find_options = FindOneOptions::builder().sort(doc! { "started_at": -1, "_id": -1 }).build();
+        Ok(self.get_worker_run_collection().find_one(filter, find_options).await?)
+    }
+
+    async fn delete_worker_runs_before(&self, before: u64) -> Result<u64> {
+        let filter = doc! { "started_at": { "$lt": i64::try_from(before)? } };
+        Ok(self.get_worker_run_collection().delete_many(filter, None).await?.deleted_count)
+    }
+
+    async fn record_audit_event(&self, event: AuditEvent) -> Result<()> {
+        self.get_audit_log_collection().insert_one(&event, None).await?;
+        Ok(())
+    }
+
+    async fn record_block_cost(&self, cost: BlockCost) -> Result<()> {
+        let filter = doc! { "block_number": i64::try_from(cost.block_number)?, "job_id": cost.job_id };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.get_block_cost_collection().replace_one(filter, &cost, options).await?;
+        Ok(())
+    }
+
+    async fn get_block_costs(&self, block_number: u64) -> Result<Vec<BlockCost>> {
+        let filter = doc! { "block_number": i64::try_from(block_number)? };
+        let find_options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        Ok(self.get_block_cost_collection().find(filter, find_options).await?.try_collect().await?)
+    }
+
+    async fn append_job_event(&self, event: JobEvent) -> Result<()> {
+        self.get_job_history_collection().insert_one(&event, None).await?;
+        Ok(())
+    }
+
+    async fn get_job_events(&self, job_id: Uuid) -> Result<Vec<JobEvent>> {
+        let filter = doc! { "job_id": job_id };
+        // the object id embeds the insertion time
+        let find_options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        Ok(self.get_job_history_collection().find(filter, find_options).await?.try_collect().await?)
+    }
+
+    /// The jobs keep their object id in the archive, and with it their creation time
+    async fn archive_jobs(&self, ids: &[Uuid]) -> Result<u64> {
+        let filter = doc! { "id": { "$in": ids.to_vec() } };
+        let documents: Vec<Document> =
+            self.get_job_document_collection().find(filter.clone(), None).await?.try_collect().await?;
+        // the jobs are copied by object id, a run failing before the deletion can be retried
+        let options = ReplaceOptions::builder().upsert(true).build();
+        for document in &documents {
+            let archive_filter = doc! { "_id": document.get_object_id("_id")? };
+            self.get_job_archive_collection().replace_one(archive_filter, document, options.clone()).await?;
+        }
+        let result = self.get_job_document_collection().delete_many(filter, None).await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn get_archived_job_by_id(&self, id: Uuid) -> Result<Option<JobItem>> {
+        let filter = doc! { "id": id };
+        match self.get_job_archive_collection().find_one(filter, None).await? {
+            Some(document) => Ok(Some(bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn run_transaction(&self, writes: Vec<DatabaseWrite>) -> Result<()> {
+        if !self.supports_transactions {
+            for write in writes {
+                match write {
+                    DatabaseWrite::CreateJob(job) => {
+                        self.create_job(job).await?;
+                    }
+                    DatabaseWrite::UpdateJob(job) => self.update_job(&job).await?,
+                    DatabaseWrite::AppendJobEvent(event) => self.append_job_event(event).await?,
+                }
+            }
+            return Ok(());
+        }
+
+        let mut session = self.client.start_session(None).await?;
+        session.start_transaction(None).await?;
+        for write in writes {
+            if let Err(e) = self.apply_write(write, &mut session).await {
+                session.abort_transaction().await?;
+                return Err(e);
+            }
+        }
+        session.commit_transaction().await?;
+        Ok(())
+    }
+}
+
+/// Result of the aggregation of [`MongoDb::get_job_stats`]
+#[derive(Deserialize)]
+struct JobStatsDocument {
+    counts: Vec<JobCountDocument>,
+    oldest_pending: Vec<OldestPendingDocument>,
+}
+
+#[derive(Deserialize)]
+struct JobCountDocument {
+    #[serde(rename = "_id")]
+    key: JobCountKey,
+    count: u64,
+}
+
+#[derive(Deserialize)]
+struct JobCountKey {
+    job_type: JobType,
+    status: JobStatus,
+}
+
+#[derive(Deserialize)]
+struct OldestPendingDocument {
+    created_at: u64,
+}
+
+/// First (`index` 0) or last (`index` -1) block of the block range internal id in `field`, the
+/// same block for a single block internal id. The internal ids which aren't blocks convert to null.
+fn internal_id_block(field: &str, index: i32) -> Document {
+    doc! { "$convert": {
+        "input": { "$arrayElemAt": [{ "$split": [field, "-"] }, index] },
+        "to": "long",
+        "onError": Bson::Null,
+    } }
+}
+
+/// Smallest object id of the given unix timestamp, the object ids start with their creation time
+fn object_id_at(timestamp: u64) -> Result<ObjectId> {
+    let mut bytes = [0; 12];
+    bytes[..4].copy_from_slice(&u32::try_from(timestamp)?.to_be_bytes());
+    Ok(ObjectId::from_bytes(bytes))
+}
+
+/// Fields of the job an update sets, the counters are left as stored since they are only changed
+/// through their atomic increments and resets
+fn job_update_document(job: &JobItem) -> Result<Document> {
+    let mut job_doc = bson::to_document(job)?;
+    job_doc.remove("counters");
+    Ok(job_doc)
+}
+
+/// Whether the write failed on a unique index, e.g. an upsert inserting a document whose id exists
+fn is_duplicate_key_error(error: &mongodb::error::Error) -> bool {
+    const DUPLICATE_KEY_CODE: i32 = 11000;
+    match error.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => write_error.code == DUPLICATE_KEY_CODE,
+        ErrorKind::Command(command_error) => command_error.code == DUPLICATE_KEY_CODE,
+        _ => false,
+    }
 }
@@ -0,0 +1,16 @@
+use utils::env_utils::get_env_var_or_default;
+
+use crate::database::DatabaseConfig;
+
+/// Path of the SQLite database file, `:memory:` keeps the database in memory
+pub const ENV_SQLITE_DATABASE_PATH: &str = "SQLITE_DATABASE_PATH";
+
+pub struct SqliteDbConfig {
+    pub path: String,
+}
+
+impl DatabaseConfig for SqliteDbConfig {
+    fn new_from_env() -> Self {
+        Self { path: get_env_var_or_default(ENV_SQLITE_DATABASE_PATH, "orchestrator.db") }
+    }
+}
@@ -0,0 +1,872 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Params, Row};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::database::migrations::{run_migrations, Migration, MigrationTarget};
+use crate::database::sqlite::config::SqliteDbConfig;
+use crate::database::types::{
+    now_secs, AuditEvent, BlockCost, DatabaseWrite, JobCount, JobEvent, JobFilter, JobPage, JobStats,
+    PENDING_JOB_STATUSES, WorkerRun,
+};
+use crate::database::Database;
+use crate::jobs::types::{IllegalStatusTransitionError, JobCounter, JobCounters, JobItem, JobStatus, JobType};
+
+pub mod config;
+
+/// Tables of the first versioned schema, the databases created before it already have them
+const SCHEMA_V1: &str = "
+    CREATE TABLE IF NOT EXISTS jobs (
+        id TEXT PRIMARY KEY,
+        internal_id TEXT NOT NULL,
+        job_type TEXT NOT NULL,
+        status TEXT NOT NULL,
+        external_id TEXT NOT NULL,
+        metadata TEXT NOT NULL,
+        version INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS jobs_by_type ON jobs (job_type, internal_id);
+    CREATE TABLE IF NOT EXISTS worker_state (
+        worker TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (worker, key)
+    );
+    CREATE TABLE IF NOT EXISTS audit_log (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        details TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+";
+
+/// History of the status transitions of the jobs
+const SCHEMA_V2: &str = "
+    CREATE TABLE job_history (
+        job_id TEXT NOT NULL,
+        internal_id TEXT NOT NULL,
+        job_type TEXT NOT NULL,
+        old_status TEXT NOT NULL,
+        new_status TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        worker_id TEXT NOT NULL,
+        error TEXT
+    );
+    CREATE INDEX job_history_by_job ON job_history (job_id);
+";
+
+/// Creation time of the jobs, the jobs created before it are given the unix epoch
+const SCHEMA_V3: &str = "
+    ALTER TABLE jobs ADD COLUMN created_at INTEGER NOT NULL DEFAULT 0;
+";
+
+/// Old completed jobs, moved out of the `jobs` table
+const SCHEMA_V4: &str = "
+    CREATE TABLE jobs_archive (
+        id TEXT PRIMARY KEY,
+        internal_id TEXT NOT NULL,
+        job_type TEXT NOT NULL,
+        status TEXT NOT NULL,
+        external_id TEXT NOT NULL,
+        metadata TEXT NOT NULL,
+        version INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+";
+
+/// Update time of the jobs, the one of the jobs stored before it is unknown and their creation
+/// time is used
+const SCHEMA_V5: &str = "
+    ALTER TABLE jobs ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+    UPDATE jobs SET updated_at = created_at;
+    ALTER TABLE jobs_archive ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+    UPDATE jobs_archive SET updated_at = created_at;
+    CREATE INDEX jobs_by_status ON jobs (status, updated_at);
+";
+
+/// Jobs the jobs depend on, as a JSON array of their ids, the jobs stored before have none
+const SCHEMA_V6: &str = "
+    ALTER TABLE jobs ADD COLUMN parent_ids TEXT NOT NULL DEFAULT '[]';
+    ALTER TABLE jobs_archive ADD COLUMN parent_ids TEXT NOT NULL DEFAULT '[]';
+";
+
+/// Priority of the jobs, the jobs stored before have the normal one
+const SCHEMA_V7: &str = "
+    ALTER TABLE jobs ADD COLUMN priority TEXT NOT NULL DEFAULT 'Normal';
+    ALTER TABLE jobs_archive ADD COLUMN priority TEXT NOT NULL DEFAULT 'Normal';
+";
+
+/// Attempt counters of the jobs, moved out of the metadata so that they are incremented atomically
+const SCHEMA_V8: &str = "
+    ALTER TABLE jobs ADD COLUMN process_attempts INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE jobs ADD COLUMN verification_attempts INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE jobs ADD COLUMN process_timeouts INTEGER NOT NULL DEFAULT 0;
+    UPDATE jobs SET
+        process_attempts = CAST(COALESCE(json_extract(metadata, '$.process_attempt_no'), '0') AS INTEGER),
+        verification_attempts = CAST(COALESCE(json_extract(metadata, '$.verification_attempt_no'), '0') AS INTEGER),
+        process_timeouts = CAST(COALESCE(json_extract(metadata, '$.process_timeouts'), '0') AS INTEGER),
+        metadata = json_remove(metadata, '$.process_attempt_no', '$.verification_attempt_no', '$.process_timeouts');
+    ALTER TABLE jobs_archive ADD COLUMN process_attempts INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE jobs_archive ADD COLUMN verification_attempts INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE jobs_archive ADD COLUMN process_timeouts INTEGER NOT NULL DEFAULT 0;
+    UPDATE jobs_archive SET
+        process_attempts = CAST(COALESCE(json_extract(metadata, '$.process_attempt_no'), '0') AS INTEGER),
+        verification_attempts = CAST(COALESCE(json_extract(metadata, '$.verification_attempt_no'), '0') AS INTEGER),
+        process_timeouts = CAST(COALESCE(json_extract(metadata, '$.process_timeouts'), '0') AS INTEGER),
+        metadata = json_remove(metadata, '$.process_attempt_no', '$.verification_attempt_no', '$.process_timeouts');
+";
+
+/// Leases of the leader election, by name
+const SCHEMA_V9: &str = "
+    CREATE TABLE leases (
+        name TEXT PRIMARY KEY,
+        holder TEXT NOT NULL,
+        expires_at INTEGER NOT NULL
+    );
+";
+
+/// Run history of the workers
+const SCHEMA_V10: &str = "
+    CREATE TABLE worker_runs (
+        worker TEXT NOT NULL,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER NOT NULL,
+        jobs_created INTEGER NOT NULL,
+        error TEXT,
+        worker_id TEXT NOT NULL
+    );
+    CREATE INDEX worker_runs_by_worker ON worker_runs (worker, started_at);
+    CREATE INDEX worker_runs_by_start ON worker_runs (started_at);
+";
+
+/// Costs attributed to the blocks, one row per block and job
+const SCHEMA_V11: &str = "
+    CREATE TABLE block_costs (
+        block_number INTEGER NOT NULL,
+        job_id TEXT NOT NULL,
+        job_type TEXT NOT NULL,
+        cost_wei TEXT NOT NULL,
+        PRIMARY KEY (block_number, job_id)
+    );
+";
+
+/// Single job per type and internal id, like the unique index of the MongoDB jobs. Fails on the
+/// databases already holding duplicates, they have to be cleaned up first.
+const SCHEMA_V12: &str = "
+    DROP INDEX IF EXISTS jobs_by_type;
+    CREATE UNIQUE INDEX jobs_by_type ON jobs (job_type, internal_id);
+";
+
+const JOB_COLUMNS: &str = "id, internal_id, job_type, status, external_id, metadata, version, created_at, updated_at, \
+                           parent_ids, priority, process_attempts, verification_attempts, process_timeouts";
+
+const WORKER_RUN_COLUMNS: &str = "worker, started_at, ended_at, jobs_created, error, worker_id";
+
+/// SQL of the migration of the given version, see [`crate::database::migrations::MIGRATIONS`]
+fn migration_sql(version: u32) -> Result<&'static str> {
+    match version {
+        1 => Ok(SCHEMA_V1),
+        2 => Ok(SCHEMA_V2),
+        3 => Ok(SCHEMA_V3),
+        4 => Ok(SCHEMA_V4),
+        5 => Ok(SCHEMA_V5),
+        6 => Ok(SCHEMA_V6),
+        7 => Ok(SCHEMA_V7),
+        8 => Ok(SCHEMA_V8),
+        9 => Ok(SCHEMA_V9),
+        10 => Ok(SCHEMA_V10),
+        11 => Ok(SCHEMA_V11),
+        12 => Ok(SCHEMA_V12),
+        version => Err(eyre!("Unknown migration {} of the SQLite job storage", version)),
+    }
+}
+
+/// Database in a single SQLite file, for running the orchestrator locally without any
+/// infrastructure. The queries are short and run on the async executor behind a mutex, it isn't
+/// meant for production loads.
+///
+/// The jobs are ordered and locked the same way as with [`crate::database::mongodb::MongoDb`]:
+/// the internal ids are compared as strings and an update only applies if the version stored
+/// is the one of the job passed in.
+pub struct SqliteDb {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteDb {
+    pub async fn new(config: SqliteDbConfig) -> Self {
+        let connection = Connection::open(&config.path).expect("Failed to open the SQLite database");
+        let sqlite_db = SqliteDb { connection: Mutex::new(connection) };
+        run_migrations(&sqlite_db).await.expect("Failed to migrate the SQLite job storage");
+        sqlite_db
+    }
+
+    fn connection(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.connection.lock().map_err(|e| eyre!("SQLite connection poisoned: {}", e))
+    }
+
+    fn query_jobs(&self, sql: &str, params: impl Params) -> Result<Vec<JobItem>> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(sql)?;
+        let rows = statement.query_map(params, JobRow::read)?.collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter().map(JobRow::into_job).collect()
+    }
+
+    fn query_job(&self, sql: &str, params: impl Params) -> Result<Option<JobItem>> {
+        Ok(self.query_jobs(sql, params)?.into_iter().next())
+    }
+
+    fn query_worker_runs(&self, sql: &str, params: impl Params) -> Result<Vec<WorkerRun>> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(sql)?;
+        let rows = statement
+            .query_map(params, |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter()
+            .map(|(worker, started_at, ended_at, jobs_created, error, worker_id)| {
+                Ok(WorkerRun {
+                    worker,
+                    started_at: started_at.try_into()?,
+                    ended_at: ended_at.try_into()?,
+                    jobs_created: jobs_created.try_into()?,
+                    error,
+                    worker_id,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Updates the job in the database optimistically. This means that the job is updated only if
+/// the version of the job in the database is the same as the version of the job passed in.
+/// If the version is different, the update fails. When the update sets `new_status`, it also
+/// fails if the stored job can't move to it from its status.
+fn update_job_optimistically(
+    connection: &Connection,
+    current_job: &JobItem,
+    new_status: Option<&JobStatus>,
+    assignments: &str,
+    values: Vec<Value>,
+) -> Result<()> {
+    let mut sql = format!("UPDATE jobs SET {}, updated_at = ? WHERE id = ? AND version = ?", assignments);
+    let mut filter = vec![
+        Value::Integer(now_secs().try_into()?),
+        Value::Text(current_job.id.to_string()),
+        Value::Integer(current_job.version.into()),
+    ];
+    if let Some(new_status) = new_status {
+        let predecessors = new_status.predecessors();
+        sql.push_str(&format!(" AND status IN ({})", vec!["?"; predecessors.len()].join(", ")));
+        for status in predecessors {
+            filter.push(Value::Text(encode_variant(&status)?));
+        }
+    }
+    let updated = connection.execute(&sql, params_from_iter(values.into_iter().chain(filter)))?;
+    if updated == 0 {
+        // when the job is up to date, its status is what prevented the update
+        if let Some(new_status) = new_status {
+            let sql = "SELECT status FROM jobs WHERE id = ? AND version = ?";
+            let params = params![current_job.id.to_string(), current_job.version];
+            if let Some(status) = connection.query_row(sql, params, |row| row.get(0)).optional()? {
+                let from = decode_variant(status)?;
+                return Err(IllegalStatusTransitionError { id: current_job.id, from, to: new_status.clone() }.into());
+            }
+        }
+        return Err(eyre!("Failed to update job. Job version is likely outdated"));
+    }
+    Ok(())
+}
+
+/// Inserts the job, the writes are free functions so that a transaction can run them as well
+fn insert_job(connection: &Connection, job: &JobItem) -> Result<()> {
+    connection.execute(
+        &format!("INSERT INTO jobs ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)", JOB_COLUMNS),
+        params![
+            job.id.to_string(),
+            job.internal_id,
+            encode_variant(&job.job_type)?,
+            encode_variant(&job.status)?,
+            serde_json::to_string(&job.external_id)?,
+            serde_json::to_string(&job.metadata)?,
+            job.version,
+            i64::try_from(job.created_at)?,
+            i64::try_from(job.updated_at)?,
+            serde_json::to_string(&job.parent_ids)?,
+            encode_variant(&job.priority)?,
+            i64::try_from(job.counters.process_attempts)?,
+            i64::try_from(job.counters.verification_attempts)?,
+            i64::try_from(job.counters.process_timeouts)?,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Updates the job, the counters are left as stored since they are only changed through their
+/// atomic increments and resets
+fn update_job(connection: &Connection, job: &JobItem) -> Result<()> {
+    let assignments = "internal_id = ?, job_type = ?, status = ?, external_id = ?, metadata = ?, version = ?, \
+                       parent_ids = ?, priority = ?";
+    let values = vec![
+        Value::Text(job.internal_id.clone()),
+        Value::Text(encode_variant(&job.job_type)?),
+        Value::Text(encode_variant(&job.status)?),
+        Value::Text(serde_json::to_string(&job.external_id)?),
+        Value::Text(serde_json::to_string(&job.metadata)?),
+        Value::Integer(job.version.into()),
+        Value::Text(serde_json::to_string(&job.parent_ids)?),
+        Value::Text(encode_variant(&job.priority)?),
+    ];
+    update_job_optimistically(connection, job, Some(&job.status), assignments, values)
+}
+
+fn insert_job_event(connection: &Connection, event: &JobEvent) -> Result<()> {
+    connection.execute(
+        "INSERT INTO job_history (job_id, internal_id, job_type, old_status, new_status, timestamp, worker_id, error) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            event.job_id.to_string(),
+            event.internal_id,
+            encode_variant(&event.job_type)?,
+            encode_variant(&event.old_status)?,
+            encode_variant(&event.new_status)?,
+            event.timestamp as i64,
+            event.worker_id,
+            event.error,
+        ],
+    )?;
+    Ok(())
+}
+
+/// SQL expressions of whether the internal id in `column` is a block or a block range, and of its
+/// first and last blocks. The cast of a range to an integer is its first block, the part after the
+/// dash its last one, the whole internal id when it's a single block.
+fn internal_id_blocks_sql(column: &str) -> (String, String, String) {
+    (
+        format!("({0} GLOB '[0-9]*' AND {0} NOT GLOB '*[^0-9-]*')", column),
+        format!("CAST({} AS INTEGER)", column),
+        format!("CAST(substr({0}, instr({0}, '-') + 1) AS INTEGER)", column),
+    )
+}
+
+/// Columns of a job as stored in the `jobs` table
+struct JobRow {
+    id: String,
+    internal_id: String,
+    job_type: String,
+    status: String,
+    external_id: String,
+    metadata: String,
+    version: i32,
+    created_at: i64,
+    updated_at: i64,
+    parent_ids: String,
+    priority: String,
+    process_attempts: i64,
+    verification_attempts: i64,
+    process_timeouts: i64,
+}
+
+impl JobRow {
+    fn read(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            internal_id: row.get(1)?,
+            job_type: row.get(2)?,
+            status: row.get(3)?,
+            external_id: row.get(4)?,
+            metadata: row.get(5)?,
+            version: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+            parent_ids: row.get(9)?,
+            priority: row.get(10)?,
+            process_attempts: row.get(11)?,
+            verification_attempts: row.get(12)?,
+            process_timeouts: row.get(13)?,
+        })
+    }
+
+    fn into_job(self) -> Result<JobItem> {
+        Ok(JobItem {
+            id: Uuid::parse_str(&self.id)?,
+            internal_id: self.internal_id,
+            job_type: decode_variant(self.job_type)?,
+            status: decode_variant(self.status)?,
+            external_id: serde_json::from_str(&self.external_id)?,
+            metadata: serde_json::from_str(&self.metadata)?,
+            version: self.version,
+            created_at: self.created_at.try_into()?,
+            updated_at: self.updated_at.try_into()?,
+            parent_ids: serde_json::from_str(&self.parent_ids)?,
+            priority: decode_variant(self.priority)?,
+            counters: JobCounters {
+                process_attempts: self.process_attempts.try_into()?,
+                verification_attempts: self.verification_attempts.try_into()?,
+                process_timeouts: self.process_timeouts.try_into()?,
+            },
+        })
+    }
+}
+
+/// Name of a unit variant of an enum, as serialized by serde
+fn encode_variant<T: Serialize>(variant: &T) -> Result<String> {
+    match serde_json::to_value(variant)? {
+        serde_json::Value::String(name) => Ok(name),
+        value => Err(eyre!("{} isn't a unit variant", value)),
+    }
+}
+
+fn decode_variant<T: DeserializeOwned>(name: String) -> Result<T> {
+    Ok(serde_json::from_value(serde_json::Value::String(name))?)
+}
+
+/// The schema version is the `user_version` of the database
+#[async_trait]
+impl MigrationTarget for SqliteDb {
+    async fn schema_version(&self) -> Result<u32> {
+        Ok(self.connection()?.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
+    async fn apply_migration(&self, migration: &Migration) -> Result<()> {
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration_sql(migration.version)?)?;
+        // pragmas don't take parameters
+        transaction.execute_batch(&format!("PRAGMA user_version = {}", migration.version))?;
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Database for SqliteDb {
+    async fn create_job(&self, job: JobItem) -> Result<JobItem> {
+        insert_job(&self.connection()?, &job)?;
+        Ok(job)
+    }
+
+    async fn get_job_by_id(&self, id: Uuid) -> Result<Option<JobItem>> {
+        let sql = format!("SELECT {} FROM jobs WHERE id = ?", JOB_COLUMNS);
+        self.query_job(&sql, params![id.to_string()])
+    }
+
+    async fn get_job_by_internal_id_and_type(&self, internal_id: &str, job_type: &JobType) -> Result<Option<JobItem>> {
+        let sql = format!("SELECT {} FROM jobs WHERE internal_id = ? AND job_type = ? LIMIT 1", JOB_COLUMNS);
+        self.query_job(&sql, params![internal_id, encode_variant(job_type)?])
+    }
+
+    async fn update_job(&self, job: &JobItem) -> Result<()> {
+        update_job(&self.connection()?, job)
+    }
+
+    async fn update_job_status(&self, job: &JobItem, new_status: JobStatus) -> Result<()> {
+        let values = vec![Value::Text(encode_variant(&new_status)?)];
+        update_job_optimistically(&self.connection()?, job, Some(&new_status), "status = ?", values)
+    }
+
+    async fn update_metadata(&self, job: &JobItem, metadata: HashMap<String, String>) -> Result<()> {
+        let values = vec![Value::Text(serde_json::to_string(&metadata)?)];
+        update_job_optimistically(&self.connection()?, job, None, "metadata = ?", values)
+    }
+
+    async fn increment_job_counter(&self, job: &JobItem, counter: JobCounter) -> Result<u64> {
+        // the connection is held until the counter is read back, no other increment runs in between
+        let connection = self.connection()?;
+        let column = counter.field();
+        let sql = format!("UPDATE jobs SET {0} = {0} + 1, updated_at = ? WHERE id = ?", column);
+        if connection.execute(&sql, params![i64::try_from(now_secs())?, job.id.to_string()])? == 0 {
+            return Err(eyre!("Failed to increment the {:?} counter of job {:?}, job not found", counter, job.id));
+        }
+        let sql = format!("SELECT {} FROM jobs WHERE id = ?", column);
+        let value: i64 = connection.query_row(&sql, params![job.id.to_string()], |row| row.get(0))?;
+        Ok(value.try_into()?)
+    }
+
+    async fn reset_job_counters(&self, job: &JobItem, counters: &[JobCounter]) -> Result<()> {
+        let mut assignments: Vec<String> = counters.iter().map(|counter| format!("{} = 0", counter.field())).collect();
+        assignments.push("updated_at = ?".to_string());
+        let sql = format!("UPDATE jobs SET {} WHERE id = ?", assignments.join(", "));
+        if self.connection()?.execute(&sql, params![i64::try_from(now_secs())?, job.id.to_string()])? == 0 {
+            return Err(eyre!("Failed to reset the counters of job {:?}, job not found", job.id));
+        }
+        Ok(())
+    }
+
+    async fn get_latest_job_by_type(&self, job_type: JobType) -> Result<Option<JobItem>> {
+        // the null last block of the internal ids which aren't blocks is sorted after the blocks
+        let (is_block, _, last_block) = internal_id_blocks_sql("internal_id");
+        let sql = format!(
+            "SELECT {} FROM jobs WHERE job_type = ? ORDER BY CASE WHEN {} THEN {} END DESC, internal_id DESC LIMIT 1",
+            JOB_COLUMNS, is_block, last_block
+        );
+        self.query_job(&sql, params![encode_variant(&job_type)?])
+    }
+
+    async fn get_latest_jobs_by_type(&self, job_type: JobType, offset: u64, limit: i64) -> Result<Vec<JobItem>> {
+        // the rowid follows the insertion order
+        let sql =
+            format!("SELECT {} FROM jobs WHERE job_type = ? ORDER BY rowid DESC LIMIT ? OFFSET ?", JOB_COLUMNS);
+        self.query_jobs(&sql, params![encode_variant(&job_type)?, limit, i64::try_from(offset)?])
+    }
+
+    async fn get_jobs_without_successor(
+        &self,
+        job_a_type: JobType,
+        job_a_status: JobStatus,
+        job_b_type: JobType,
+    ) -> Result<Vec<JobItem>> {
+        // job B succeeds job A with the same internal id, or with a block range covering the last
+        // block of job A
+        let (a_is_block, _, a_last_block) = internal_id_blocks_sql("a.internal_id");
+        let (b_is_block, b_first_block, b_last_block) = internal_id_blocks_sql("b.internal_id");
+        let sql = format!(
+            "SELECT {} FROM jobs AS a WHERE job_type = ? AND status = ? AND NOT EXISTS (SELECT 1 FROM jobs AS b \
+             WHERE b.job_type = ? AND (b.internal_id = a.internal_id OR ({} AND {} AND {} BETWEEN {} AND {})))",
+            JOB_COLUMNS, a_is_block, b_is_block, a_last_block, b_first_block, b_last_block
+        );
+        let (job_a_type, job_a_status) = (encode_variant(&job_a_type)?, encode_variant(&job_a_status)?);
+        self.query_jobs(&sql, params![job_a_type, job_a_status, encode_variant(&job_b_type)?])
+    }
+
+    async fn get_latest_job_by_type_and_status(
+        &self,
+        job_type: JobType,
+        job_status: JobStatus,
+    ) -> Result<Option<JobItem>> {
+        let sql = format!(
+            "SELECT {} FROM jobs WHERE job_type = ? AND status = ? ORDER BY internal_id DESC LIMIT 1",
+            JOB_COLUMNS
+        );
+        self.query_job(&sql, params![encode_variant(&job_type)?, encode_variant(&job_status)?])
+    }
+
+    async fn get_jobs_after_internal_id_by_job_type(
+        &self,
+        job_type: JobType,
+        job_status: JobStatus,
+        internal_id: String,
+    ) -> Result<Vec<JobItem>> {
+        let sql = format!("SELECT {} FROM jobs WHERE job_type = ? AND status = ? AND internal_id > ?", JOB_COLUMNS);
+        self.query_jobs(&sql, params![encode_variant(&job_type)?, encode_variant(&job_status)?, internal_id])
+    }
+
+    async fn get_jobs_by_statuses(&self, job_status: Vec<JobStatus>, limit: Option<i64>) -> Result<Vec<JobItem>> {
+        let placeholders = vec!["?"; job_status.len()].join(", ");
+        // a negative limit is no limit for SQLite
+        let sql = format!("SELECT {} FROM jobs WHERE status IN ({}) LIMIT ?", JOB_COLUMNS, placeholders);
+        let mut values =
+            job_status.iter().map(|status| Ok(Value::Text(encode_variant(status)?))).collect::<Result<Vec<_>>>()?;
+        values.push(Value::Integer(limit.unwrap_or(-1)));
+        self.query_jobs(&sql, params_from_iter(values))
+    }
+
+    async fn get_job_stats(&self) -> Result<JobStats> {
+        let connection = self.connection()?;
+        let mut statement =
+            connection.prepare("SELECT job_type, status, COUNT(*) FROM jobs GROUP BY job_type, status")?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let counts = rows
+            .into_iter()
+            .map(|(job_type, status, count)| {
+                Ok(JobCount {
+                    job_type: decode_variant(job_type)?,
+                    status: decode_variant(status)?,
+                    count: count.try_into()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let pending_statuses = PENDING_JOB_STATUSES.iter().map(encode_variant).collect::<Result<Vec<_>>>()?;
+        let placeholders = vec!["?"; pending_statuses.len()].join(", ");
+        let oldest_created_at: Option<i64> = connection.query_row(
+            &format!("SELECT MIN(created_at) FROM jobs WHERE status IN ({})", placeholders),
+            params_from_iter(&pending_statuses),
+            |row| row.get(0),
+        )?;
+        let oldest_pending_job_age =
+            oldest_created_at.map(|created_at| now_secs().saturating_sub(created_at.try_into().unwrap_or(0)));
+        Ok(JobStats { counts, oldest_pending_job_age })
+    }
+
+    async fn get_jobs_stuck_in_status(&self, status: JobStatus, older_than: Duration) -> Result<Vec<JobItem>> {
+        let updated_before = now_secs().saturating_sub(older_than.as_secs());
+        let sql = format!("SELECT {} FROM jobs WHERE status = ? AND updated_at < ? ORDER BY updated_at", JOB_COLUMNS);
+        self.query_jobs(&sql, params![encode_variant(&status)?, i64::try_from(updated_before)?])
+    }
+
+    /// The cursor is the rowid of the last job of the page, the rowids follow the insertion order
+    async fn get_jobs_paginated(&self, filter: JobFilter, cursor: Option<String>, limit: i64) -> Result<JobPage> {
+        let mut conditions = vec!["rowid > ?".to_string()];
+        let mut values = vec![Value::Integer(cursor.map(|cursor| cursor.parse()).transpose()?.unwrap_or(0))];
+        if let Some(job_type) = &filter.job_type {
+            conditions.push("job_type = ?".to_string());
+            values.push(Value::Text(encode_variant(job_type)?));
+        }
+        if let Some(status) = &filter.status {
+            conditions.push("status = ?".to_string());
+            values.push(Value::Text(encode_variant(status)?));
+        }
+        if let Some(internal_id_range) = &filter.internal_id_range {
+            conditions.push("internal_id <> '' AND internal_id NOT GLOB '*[^0-9]*'".to_string());
+            conditions.push("CAST(internal_id AS INTEGER) BETWEEN ? AND ?".to_string());
+            values.push(Value::Integer((*internal_id_range.start()).try_into()?));
+            values.push(Value::Integer((*internal_id_range.end()).try_into()?));
+        }
+        if let Some(created_after) = filter.created_after {
+            conditions.push("created_at >= ?".to_string());
+            values.push(Value::Integer(created_after.try_into()?));
+        }
+        if let Some(created_before) = filter.created_before {
+            conditions.push("created_at < ?".to_string());
+            values.push(Value::Integer(created_before.try_into()?));
+        }
+        values.push(Value::Integer(limit));
+
+        let sql = format!(
+            "SELECT {}, rowid FROM jobs WHERE {} ORDER BY rowid LIMIT ?",
+            JOB_COLUMNS,
+            conditions.join(" AND ")
+        );
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(&sql)?;
+        let rows = statement
+            .query_map(params_from_iter(values), |row| Ok((JobRow::read(row)?, row.get::<_, i64>(11)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let next_cursor = match rows.last() {
+            Some((_, rowid)) if rows.len() as i64 == limit => Some(rowid.to_string()),
+            _ => None,
+        };
+        let jobs = rows.into_iter().map(|(row, _)| row.into_job()).collect::<Result<Vec<_>>>()?;
+        Ok(JobPage { jobs, next_cursor })
+    }
+
+    /// The parent ids are stored as a JSON array of uuids, which can't contain one another
+    async fn get_jobs_by_parent_id(&self, parent_id: Uuid) -> Result<Vec<JobItem>> {
+        let sql = format!("SELECT {} FROM jobs WHERE instr(parent_ids, ?) > 0", JOB_COLUMNS);
+        self.query_jobs(&sql, params![format!("\"{}\"", parent_id)])
+    }
+
+    async fn get_jobs_by_block(&self, block_number: u64) -> Result<Vec<JobItem>> {
+        let (is_block, first_block, last_block) = internal_id_blocks_sql("internal_id");
+        let sql = format!(
+            "SELECT {} FROM jobs WHERE {} AND ? BETWEEN {} AND {} ORDER BY rowid",
+            JOB_COLUMNS, is_block, first_block, last_block
+        );
+        self.query_jobs(&sql, params![i64::try_from(block_number)?])
+    }
+
+    async fn get_worker_state(&self, worker: &str, key: &str) -> Result<Option<serde_json::Value>> {
+        let value: Option<String> = self
+            .connection()?
+            .query_row("SELECT value FROM worker_state WHERE worker = ? AND key = ?", params![worker, key], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(value.map(|value| serde_json::from_str(&value)).transpose()?)
+    }
+
+    async fn set_worker_state(&self, worker: &str, key: &str, value: serde_json::Value) -> Result<()> {
+        self.connection()?.execute(
+            "INSERT INTO worker_state (worker, key, value) VALUES (?, ?, ?) \
+             ON CONFLICT (worker, key) DO UPDATE SET value = excluded.value",
+            params![worker, key, value.to_string()],
+        )?;
+        Ok(())
+    }
+
+    async fn acquire_lease(&self, name: &str, holder: &str, duration: Duration) -> Result<bool> {
+        let now = now_secs();
+        let expires_at = now + duration.as_secs();
+        let changed = self.connection()?.execute(
+            "INSERT INTO leases (name, holder, expires_at) VALUES (?, ?, ?) \
+             ON CONFLICT (name) DO UPDATE SET holder = excluded.holder, expires_at = excluded.expires_at \
+             WHERE leases.holder = excluded.holder OR leases.expires_at <= ?",
+            params![name, holder, i64::try_from(expires_at)?, i64::try_from(now)?],
+        )?;
+        Ok(changed == 1)
+    }
+
+    async fn record_worker_run(&self, run: WorkerRun) -> Result<()> {
+        self.connection()?.execute(
+            "INSERT INTO worker_runs (worker, started_at, ended_at, jobs_created, error, worker_id) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                run.worker,
+                i64::try_from(run.started_at)?,
+                i64::try_from(run.ended_at)?,
+                i64::try_from(run.jobs_created)?,
+                run.error,
+                run.worker_id
+            ],
+        )?;
+        Ok(())
+    }
+
+    async fn get_worker_runs(&self, worker: &str, limit: i64) -> Result<Vec<WorkerRun>> {
+        // the rowid follows the insertion order
+        let sql = format!(
+            "SELECT {} FROM worker_runs WHERE worker = ? ORDER BY started_at DESC, rowid DESC LIMIT ?",
+            WORKER_RUN_COLUMNS
+        );
+        self.query_worker_runs(&sql, params![worker, limit])
+    }
+
+    async fn get_last_successful_worker_run(&self, worker: &str) -> Result<Option<WorkerRun>> {
+        let sql = format!(
+            "SELECT {} FROM worker_runs WHERE worker = ? AND error IS NULL \
+             ORDER BY started_at DESC, rowid DESC LIMIT 1",
+            WORKER_RUN_COLUMNS
+        );
+        Ok(self.query_worker_runs(&sql, params![worker])?.into_iter().next())
+    }
+
+    async fn delete_worker_runs_before(&self, before: u64) -> Result<u64> {
+        let before = i64::try_from(before)?;
+        let deleted = self.connection()?.execute("DELETE FROM worker_runs WHERE started_at < ?", params![before])?;
+        Ok(deleted as u64)
+    }
+
+    async fn record_audit_event(&self, event: AuditEvent) -> Result<()> {
+        self.connection()?.execute(
+            "INSERT INTO audit_log (id, kind, details, created_at) VALUES (?, ?, ?, ?)",
+            params![event.id.to_string(), encode_variant(&event.kind)?, event.details, event.created_at as i64],
+        )?;
+        Ok(())
+    }
+
+    async fn record_block_cost(&self, cost: BlockCost) -> Result<()> {
+        self.connection()?.execute(
+            "INSERT INTO block_costs (block_number, job_id, job_type, cost_wei) VALUES (?, ?, ?, ?) \
+             ON CONFLICT (block_number, job_id) DO UPDATE SET \
+             job_type = excluded.job_type, cost_wei = excluded.cost_wei",
+            params![
+                i64::try_from(cost.block_number)?,
+                cost.job_id.to_string(),
+                encode_variant(&cost.job_type)?,
+                cost.cost_wei
+            ],
+        )?;
+        Ok(())
+    }
+
+    async fn get_block_costs(&self, block_number: u64) -> Result<Vec<BlockCost>> {
+        let connection = self.connection()?;
+        // the rowid follows the insertion order
+        let mut statement = connection
+            .prepare("SELECT job_id, job_type, cost_wei FROM block_costs WHERE block_number = ? ORDER BY rowid")?;
+        let rows = statement
+            .query_map(params![i64::try_from(block_number)?], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter()
+            .map(|(job_id, job_type, cost_wei)| {
+                Ok(BlockCost {
+                    block_number,
+                    job_id: Uuid::parse_str(&job_id)?,
+                    job_type: decode_variant(job_type)?,
+                    cost_wei,
+                })
+            })
+            .collect()
+    }
+
+    async fn append_job_event(&self, event: JobEvent) -> Result<()> {
+        insert_job_event(&self.connection()?, &event)
+    }
+
+    async fn get_job_events(&self, job_id: Uuid) -> Result<Vec<JobEvent>> {
+        let connection = self.connection()?;
+        // the rowid follows the insertion order
+        let mut statement = connection.prepare(
+            "SELECT internal_id, job_type, old_status, new_status, timestamp, worker_id, error FROM job_history \
+             WHERE job_id = ? ORDER BY rowid",
+        )?;
+        let rows = statement
+            .query_map(params![job_id.to_string()], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter()
+            .map(|(internal_id, job_type, old_status, new_status, timestamp, worker_id, error)| {
+                Ok(JobEvent {
+                    job_id,
+                    internal_id,
+                    job_type: decode_variant(job_type)?,
+                    old_status: decode_variant(old_status)?,
+                    new_status: decode_variant(new_status)?,
+                    timestamp: timestamp.try_into()?,
+                    worker_id,
+                    error,
+                })
+            })
+            .collect()
+    }
+
+    async fn archive_jobs(&self, ids: &[Uuid]) -> Result<u64> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let ids: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction()?;
+        transaction.execute(
+            &format!(
+                "INSERT OR REPLACE INTO jobs_archive ({0}) SELECT {0} FROM jobs WHERE id IN ({1})",
+                JOB_COLUMNS, placeholders
+            ),
+            params_from_iter(&ids),
+        )?;
+        let archived =
+            transaction.execute(&format!("DELETE FROM jobs WHERE id IN ({})", placeholders), params_from_iter(&ids))?;
+        transaction.commit()?;
+        Ok(archived as u64)
+    }
+
+    async fn get_archived_job_by_id(&self, id: Uuid) -> Result<Option<JobItem>> {
+        let sql = format!("SELECT {} FROM jobs_archive WHERE id = ?", JOB_COLUMNS);
+        self.query_job(&sql, params![id.to_string()])
+    }
+
+    async fn run_transaction(&self, writes: Vec<DatabaseWrite>) -> Result<()> {
+        let mut connection = self.connection()?;
+        // dropping the transaction on an error rolls it back
+        let transaction = connection.transaction()?;
+        for write in &writes {
+            match write {
+                DatabaseWrite::CreateJob(job) => insert_job(&transaction, job)?,
+                DatabaseWrite::UpdateJob(job) => update_job(&transaction, job)?,
+                DatabaseWrite::AppendJobEvent(event) => insert_job_event(&transaction, event)?,
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
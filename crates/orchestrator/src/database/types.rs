@@ -0,0 +1,182 @@
+use std::ops::RangeInclusive;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use mongodb::bson::serde_helpers::uuid_1_as_binary;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::jobs::types::{JobItem, JobStatus, JobType};
+
+/// Kind of the events recorded in the audit log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum AuditEventKind {
+    /// A transaction was sent by the operator account without any job or attempt referencing it
+    OrphanTransaction,
+    /// A settlement transaction of a completed job was reorged out of the settlement layer
+    SettlementReorg,
+    /// A message of a job queue wasn't a job message, it was quarantined rather than handled
+    PoisonMessage,
+}
+
+/// Entry of the audit log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    #[serde(with = "uuid_1_as_binary")]
+    pub id: Uuid,
+    pub kind: AuditEventKind,
+    pub details: String,
+    /// Unix timestamp in seconds
+    pub created_at: u64,
+}
+
+impl AuditEvent {
+    pub fn new(kind: AuditEventKind, details: String) -> Self {
+        Self { id: Uuid::new_v4(), kind, details, created_at: now_secs() }
+    }
+}
+
+/// Status transition of a job, appended to the history of the job
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JobEvent {
+    #[serde(with = "uuid_1_as_binary")]
+    pub job_id: Uuid,
+    pub internal_id: String,
+    pub job_type: JobType,
+    pub old_status: JobStatus,
+    pub new_status: JobStatus,
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+    /// Orchestrator instance which made the transition
+    pub worker_id: String,
+    /// Error which caused the transition, if any
+    pub error: Option<String>,
+}
+
+impl JobEvent {
+    pub fn new(job: &JobItem, old_status: JobStatus, new_status: JobStatus, worker_id: String) -> Self {
+        Self {
+            job_id: job.id,
+            internal_id: job.internal_id.clone(),
+            job_type: job.job_type.clone(),
+            old_status,
+            new_status,
+            timestamp: now_secs(),
+            worker_id,
+            error: None,
+        }
+    }
+
+    pub fn with_error(mut self, error: String) -> Self {
+        self.error = Some(error);
+        self
+    }
+}
+
+/// Run of a worker, appended to the run history of the workers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WorkerRun {
+    pub worker: String,
+    /// Unix timestamp in seconds
+    pub started_at: u64,
+    /// Unix timestamp in seconds
+    pub ended_at: u64,
+    pub jobs_created: u64,
+    /// Error the run failed with, `None` for a successful run
+    pub error: Option<String>,
+    /// Orchestrator instance which ran the worker
+    pub worker_id: String,
+}
+
+impl WorkerRun {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Spend of a job attributed to one of the blocks it covers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockCost {
+    pub block_number: u64,
+    #[serde(with = "uuid_1_as_binary")]
+    pub job_id: Uuid,
+    pub job_type: JobType,
+    /// Cost in wei, as a string since it can overflow the storage integers
+    pub cost_wei: String,
+}
+
+/// Criteria of a job listing, see [`crate::database::Database::get_jobs_paginated`]. The criteria
+/// left to `None` match every job.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobFilter {
+    pub job_type: Option<JobType>,
+    pub status: Option<JobStatus>,
+    /// Numeric internal ids, e.g. block numbers, both bounds included. The jobs whose internal id
+    /// isn't a number are left out.
+    pub internal_id_range: Option<RangeInclusive<u64>>,
+    /// Unix timestamp in seconds, included
+    pub created_after: Option<u64>,
+    /// Unix timestamp in seconds, excluded
+    pub created_before: Option<u64>,
+}
+
+/// Page of a job listing, by creation order
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobPage {
+    pub jobs: Vec<JobItem>,
+    /// Cursor of the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Statuses of the jobs waiting to be processed or verified
+pub const PENDING_JOB_STATUSES: [JobStatus; 4] =
+    [JobStatus::Created, JobStatus::LockedForProcessing, JobStatus::PendingVerification, JobStatus::FeeTooHigh];
+
+/// Number of jobs of a type in a status
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobCount {
+    pub job_type: JobType,
+    pub status: JobStatus,
+    pub count: u64,
+}
+
+/// Counts of the jobs, see [`crate::database::Database::get_job_stats`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobStats {
+    /// Counts by type and status, the pairs without any job are left out
+    pub counts: Vec<JobCount>,
+    /// Age in seconds of the oldest job in one of the [`PENDING_JOB_STATUSES`], `None` when there
+    /// is no pending job
+    pub oldest_pending_job_age: Option<u64>,
+}
+
+impl JobStats {
+    /// Number of jobs, of any type, in one of the statuses
+    pub fn count_in_statuses(&self, statuses: &[JobStatus]) -> u64 {
+        self.counts.iter().filter(|count| statuses.contains(&count.status)).map(|count| count.count).sum()
+    }
+
+    /// Number of jobs of the type in one of the statuses
+    pub fn count_of_type_in_statuses(&self, job_type: &JobType, statuses: &[JobStatus]) -> u64 {
+        self.counts
+            .iter()
+            .filter(|count| count.job_type == *job_type && statuses.contains(&count.status))
+            .map(|count| count.count)
+            .sum()
+    }
+}
+
+/// Write applied as part of a transaction, see [`crate::database::Database::run_transaction`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DatabaseWrite {
+    /// Inserts the job, like [`crate::database::Database::create_job`]
+    CreateJob(JobItem),
+    /// Updates the job if the stored version is its version, like [`crate::database::Database::update_job`]
+    UpdateJob(JobItem),
+    /// Appends the event to the history of its job
+    AppendJobEvent(JobEvent),
+}
+
+/// Current unix timestamp in seconds
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("System time before unix epoch").as_secs()
+}
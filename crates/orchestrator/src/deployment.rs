@@ -0,0 +1,110 @@
+use std::fmt;
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+use utils::env_utils::get_env_var_or_default;
+
+lazy_static! {
+    /// Descriptor of the running deployment, read from the environment on first use
+    pub static ref DEPLOYMENT: DeploymentDescriptor = DeploymentDescriptor::new_from_env();
+}
+
+/// Identifies the deployment the orchestrator is running in. It's attached to every
+/// metric, log line and alert so that multi-chain dashboards can be filtered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeploymentDescriptor {
+    pub chain_id: String,
+    pub environment: String,
+    pub version: String,
+    pub git_sha: String,
+}
+
+impl DeploymentDescriptor {
+    pub fn new_from_env() -> Self {
+        Self {
+            chain_id: get_env_var_or_default("CHAIN_ID", "unknown"),
+            environment: get_env_var_or_default("DEPLOYMENT_ENV", "dev"),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            // the sha can be baked in at build time or provided by the deployment
+            git_sha: get_env_var_or_default("GIT_SHA", option_env!("GIT_SHA").unwrap_or("unknown")),
+        }
+    }
+
+    /// Returns the descriptor as metric labels
+    pub fn labels(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            ("chain_id", self.chain_id.as_str()),
+            ("environment", self.environment.as_str()),
+            ("version", self.version.as_str()),
+            ("git_sha", self.git_sha.as_str()),
+        ]
+    }
+}
+
+impl fmt::Display for DeploymentDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "chain_id={} environment={} version={} git_sha={}",
+            self.chain_id, self.environment, self.version, self.git_sha
+        )
+    }
+}
+
+/// Log formatter prefixing every line with the deployment descriptor, for the text logs
+pub struct DeploymentFormat<F> {
+    inner: F,
+}
+
+impl<F> DeploymentFormat<F> {
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S, N, F> FormatEvent<S, N> for DeploymentFormat<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        write!(writer, "[{}] ", *DEPLOYMENT)?;
+        self.inner.format_event(ctx, writer, event)
+    }
+}
+
+/// Log formatter adding the deployment descriptor under `deployment` to every JSON log line
+pub struct DeploymentJsonFormat<F> {
+    inner: F,
+}
+
+impl<F> DeploymentJsonFormat<F> {
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S, N, F> FormatEvent<S, N> for DeploymentJsonFormat<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let mut line = String::new();
+        self.inner.format_event(ctx, Writer::new(&mut line), event)?;
+        match serde_json::from_str::<serde_json::Value>(&line) {
+            Ok(serde_json::Value::Object(mut fields)) => {
+                fields.insert("deployment".to_string(), serde_json::to_value(&*DEPLOYMENT).map_err(|_| fmt::Error)?);
+                writeln!(writer, "{}", serde_json::Value::Object(fields))
+            }
+            // not a JSON object, the line is written as formatted
+            _ => write!(writer, "{}", line),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_default;
+use utils::settings::default::DefaultSettingsProvider;
+use utils::settings::SettingsProvider;
+
+use crate::jobs::types::JobType;
+use crate::queue::job_queue::job_processing_env_prefix;
+
+pub const JOB_ATTEMPT_SETTINGS_NAME: &str = "job_attempt_settings";
+
+/// Retry budget and polling delay of the jobs of a type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobTypeAttempts {
+    /// Maximum number of attempts to process the job, a new attempt is made every time the
+    /// verification rejects it
+    pub max_process_attempts: u64,
+    /// Maximum number of attempts to verify the job before it times out
+    pub max_verification_attempts: u64,
+    /// Seconds to wait before polling for the verification, doubled with every attempt
+    pub verification_polling_delay_seconds: u64,
+}
+
+impl JobTypeAttempts {
+    /// The settings of the jobs of the type, each one read from
+    /// `<PREFIX>_JOB_MAX_PROCESS_ATTEMPTS`, `<PREFIX>_JOB_MAX_VERIFICATION_ATTEMPTS` and
+    /// `<PREFIX>_JOB_VERIFICATION_POLLING_DELAY_SECS` when set, see [`job_processing_env_prefix`]
+    fn from_env(job_type: &JobType, defaults: JobTypeAttempts) -> Self {
+        let prefix = job_processing_env_prefix(job_type);
+        let setting = |name: &str, default: u64| -> u64 {
+            let env_var = format!("{}_JOB_{}", prefix, name);
+            get_env_var_or_default(&env_var, &default.to_string())
+                .parse()
+                .unwrap_or_else(|_| panic!("{} must be a number", env_var))
+        };
+        Self {
+            max_process_attempts: setting("MAX_PROCESS_ATTEMPTS", defaults.max_process_attempts),
+            max_verification_attempts: setting("MAX_VERIFICATION_ATTEMPTS", defaults.max_verification_attempts),
+            verification_polling_delay_seconds: setting(
+                "VERIFICATION_POLLING_DELAY_SECS",
+                defaults.verification_polling_delay_seconds,
+            ),
+        }
+    }
+}
+
+/// Retry budgets and polling delays of the jobs, per job type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobAttemptSettings {
+    pub snos_run: JobTypeAttempts,
+    pub data_submission: JobTypeAttempts,
+    pub proof_creation: JobTypeAttempts,
+    pub proof_aggregation: JobTypeAttempts,
+    pub proof_registration: JobTypeAttempts,
+    pub state_transition: JobTypeAttempts,
+    pub message_processing: JobTypeAttempts,
+    pub data_audit: JobTypeAttempts,
+}
+
+impl JobAttemptSettings {
+    pub fn for_job_type(&self, job_type: &JobType) -> JobTypeAttempts {
+        match job_type {
+            JobType::SnosRun => self.snos_run,
+            JobType::DataSubmission => self.data_submission,
+            JobType::ProofCreation => self.proof_creation,
+            JobType::ProofAggregation => self.proof_aggregation,
+            JobType::ProofRegistration => self.proof_registration,
+            JobType::StateTransition => self.state_transition,
+            JobType::MessageProcessing => self.message_processing,
+            JobType::DataAudit => self.data_audit,
+        }
+    }
+}
+
+impl Default for JobAttemptSettings {
+    /// The values the handlers used to be built with, overridden through the environment
+    fn default() -> Self {
+        let attempts = |max_process_attempts, max_verification_attempts, verification_polling_delay_seconds| {
+            JobTypeAttempts { max_process_attempts, max_verification_attempts, verification_polling_delay_seconds }
+        };
+        Self {
+            snos_run: JobTypeAttempts::from_env(&JobType::SnosRun, attempts(2, 1, 1)),
+            data_submission: JobTypeAttempts::from_env(&JobType::DataSubmission, attempts(1, 3, 60)),
+            proof_creation: JobTypeAttempts::from_env(&JobType::ProofCreation, attempts(1, 1, 60)),
+            proof_aggregation: JobTypeAttempts::from_env(&JobType::ProofAggregation, attempts(1, 30, 60)),
+            proof_registration: JobTypeAttempts::from_env(&JobType::ProofRegistration, attempts(1, 10, 60)),
+            state_transition: JobTypeAttempts::from_env(&JobType::StateTransition, attempts(1, 10, 60)),
+            // a message is only consumed by the settlement of the block which executed it
+            message_processing: JobTypeAttempts::from_env(&JobType::MessageProcessing, attempts(1, 100, 60)),
+            data_audit: JobTypeAttempts::from_env(&JobType::DataAudit, attempts(3, 1, 60)),
+        }
+    }
+}
+
+/// Retry budget and polling delay of the jobs of the type, as configured
+pub fn job_type_attempts(job_type: &JobType) -> JobTypeAttempts {
+    let settings: JobAttemptSettings = DefaultSettingsProvider {}
+        .get_settings(JOB_ATTEMPT_SETTINGS_NAME)
+        .expect("Failed to read the job attempt settings");
+    settings.for_job_type(job_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_type_attempts_override() {
+        std::env::set_var("PROOF_AGGREGATION_JOB_MAX_VERIFICATION_ATTEMPTS", "45");
+        let settings = JobAttemptSettings::default();
+        std::env::remove_var("PROOF_AGGREGATION_JOB_MAX_VERIFICATION_ATTEMPTS");
+
+        let attempts = settings.for_job_type(&JobType::ProofAggregation);
+        assert_eq!(attempts.max_verification_attempts, 45);
+        assert_eq!(attempts.max_process_attempts, 1);
+        assert_eq!(settings.for_job_type(&JobType::SnosRun), settings.snos_run);
+    }
+}
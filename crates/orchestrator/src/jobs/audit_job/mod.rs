@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use cairo_vm::Felt252;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use settlement_client_interface::SettlementVerificationStatus;
+use snos::io::output::StarknetOsOutput;
+use starknet::core::types::{BlockId, MaybePendingStateUpdate};
+use starknet::providers::Provider;
+use uuid::Uuid;
+
+use super::attempts::job_type_attempts;
+use super::constants::JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY;
+use super::types::{JobBlockedError, JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
+use super::Job;
+use crate::config::Config;
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::database::types::now_secs;
+use crate::jobs::da_job::{block_data_to_blobs, state_update_to_blob_data};
+use crate::jobs::state_update_job::StateUpdateJob;
+
+/// Audits the blocks settled by a completed state update job, the internal id being the one of
+/// the state update job. For every block, the state diff is derived again from the L2 and the
+/// blobs sent along with the state update are downloaded back from the DA layer and compared
+/// with it. The program output of the block must end on the state root of the L2, and on the
+/// core contract `stateRoot()` while the block is the last one settled.
+///
+/// A mismatch blocks the job, which alerts the operators and halts the workers until the
+/// divergence is investigated.
+pub struct AuditJob;
+
+#[async_trait]
+impl Job for AuditJob {
+    async fn create_job(
+        &self,
+        _config: &Config,
+        internal_id: String,
+        metadata: HashMap<String, String>,
+    ) -> Result<JobItem> {
+        Ok(JobItem {
+            id: Uuid::new_v4(),
+            internal_id,
+            job_type: JobType::DataAudit,
+            status: JobStatus::Created,
+            external_id: String::new().into(),
+            // metadata must contain the blocks settled by the audited state update job
+            metadata,
+            version: 0,
+            created_at: now_secs(),
+            updated_at: now_secs(),
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
+        })
+    }
+
+    async fn process_job(&self, config: &Config, job: &mut JobItem) -> Result<String> {
+        let block_numbers = audited_block_numbers(job)?;
+        let state_update_job = config
+            .database()
+            .get_job_by_internal_id_and_type(&job.internal_id, &JobType::StateTransition)
+            .await?
+            .ok_or_else(|| eyre!("State update job #{} audited by job {:?} not found", job.internal_id, job.id))?;
+        let tx_candidates: HashMap<u64, Vec<String>> =
+            StateUpdateJob.settlement_tx_candidates(&state_update_job)?.into_iter().collect();
+
+        let mut last_snos_output = None;
+        for block_no in &block_numbers {
+            let snos = fetch_snos_output(config, *block_no).await?;
+            audit_block(config, *block_no, &snos, tx_candidates.get(block_no)).await?;
+            last_snos_output = Some(snos);
+        }
+
+        let last_block = *block_numbers.last().expect("Block numbers list should not be empty.");
+        let last_snos_output = last_snos_output.expect("Block numbers list should not be empty.");
+        // the core contract only stores the root of the last block settled, the next state
+        // updates checked they build on top of the roots of the blocks before them
+        if config.settlement_client().get_last_settled_block().await? == last_block {
+            let settled_root = Felt252::from_bytes_be(&config.settlement_client().get_state_root().await?);
+            if last_snos_output.final_root != settled_root {
+                return Err(JobBlockedError(format!(
+                    "Block #{} - Settled state root divergence: program output new root is {:#x} but core contract \
+                     stateRoot() is {:#x}",
+                    last_block, last_snos_output.final_root, settled_root
+                ))
+                .into());
+            }
+        }
+
+        Ok(last_block.to_string())
+    }
+
+    /// The blocks are audited while the job is processed
+    async fn verify_job(&self, _config: &Config, _job: &mut JobItem) -> Result<JobVerificationStatus> {
+        Ok(JobVerificationStatus::Verified)
+    }
+
+    fn max_process_attempts(&self) -> u64 {
+        job_type_attempts(&JobType::DataAudit).max_process_attempts
+    }
+
+    fn max_verification_attempts(&self) -> u64 {
+        job_type_attempts(&JobType::DataAudit).max_verification_attempts
+    }
+
+    fn verification_polling_delay_seconds(&self) -> u64 {
+        job_type_attempts(&JobType::DataAudit).verification_polling_delay_seconds
+    }
+}
+
+/// Metadata of the audit job of a state update job
+pub fn audit_metadata(state_update_job: &JobItem) -> Result<HashMap<String, String>> {
+    let blocks_to_settle = state_update_job.metadata.get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY).ok_or_else(
+        || eyre!("Block numbers to settle must be specified (state update job #{})", state_update_job.internal_id),
+    )?;
+    Ok(HashMap::from([(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY.to_string(), blocks_to_settle.clone())]))
+}
+
+fn audited_block_numbers(job: &JobItem) -> Result<Vec<u64>> {
+    let blocks_to_settle = job
+        .metadata
+        .get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY)
+        .ok_or_else(|| eyre!("Block numbers to audit must be specified (audit job #{})", job.internal_id))?;
+    let block_numbers = blocks_to_settle
+        .replace(' ', "")
+        .split(',')
+        .map(|block_no| block_no.parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|e| eyre!("Block numbers to audit list is not correctly formatted: {e}"))?;
+    if block_numbers.is_empty() {
+        return Err(eyre!("No block numbers found (audit job #{})", job.internal_id));
+    }
+    Ok(block_numbers)
+}
+
+/// Checks the state root and the published blobs of the block against its state diff, derived
+/// again from the L2
+async fn audit_block(
+    config: &Config,
+    block_no: u64,
+    snos: &StarknetOsOutput,
+    tx_candidates: Option<&Vec<String>>,
+) -> Result<()> {
+    let state_update = match config.starknet_client().get_state_update(BlockId::Number(block_no)).await? {
+        MaybePendingStateUpdate::Update(state_update) => state_update,
+        MaybePendingStateUpdate::PendingUpdate(_) => {
+            return Err(eyre!("Cannot audit block {} as it's still in pending state", block_no));
+        }
+    };
+    let l2_root = Felt252::from_bytes_be(&state_update.new_root.to_bytes_be());
+    if snos.final_root != l2_root {
+        return Err(JobBlockedError(format!(
+            "Block #{} - State root divergence: program output new root is {:#x} but the L2 state root is {:#x}",
+            block_no, snos.final_root, l2_root
+        ))
+        .into());
+    }
+
+    // nothing is published to the DA layer when the state diff is settled in calldata
+    if snos.use_kzg_da != Felt252::ONE {
+        return Ok(());
+    }
+    let Some(tx_candidates) = tx_candidates else {
+        tracing::warn!(block = block_no, "Block settled by a previous attempt of its state update job, not audited");
+        return Ok(());
+    };
+    let tx_hash = settled_tx_hash(config, tx_candidates)
+        .await?
+        .ok_or_else(|| eyre!("Block #{} - None of the settlement txs {:?} is included", block_no, tx_candidates))?;
+    let published_blobs = config
+        .da_client()
+        .get_published_blobs(&tx_hash)
+        .await?
+        .ok_or_else(|| eyre!("Block #{} - The blobs of the settlement tx {} can't be downloaded", block_no, tx_hash))?;
+
+    let state_diff = state_update_to_blob_data(block_no, state_update, config).await?;
+    let expected_blobs = block_data_to_blobs(state_diff, config).await?;
+    if published_blobs != expected_blobs {
+        return Err(JobBlockedError(format!(
+            "Block #{} - DA divergence: the {} blobs published by the settlement tx {} don't match the {} blobs of \
+             the state diff of the block",
+            block_no,
+            published_blobs.len(),
+            tx_hash,
+            expected_blobs.len()
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// The settlement tx which was included, among the tx sent and its replacements
+async fn settled_tx_hash(config: &Config, tx_candidates: &[String]) -> Result<Option<String>> {
+    for tx_hash in tx_candidates {
+        if config.settlement_client().verify_tx_inclusion(tx_hash).await? == SettlementVerificationStatus::Verified {
+            return Ok(Some(tx_hash.clone()));
+        }
+    }
+    Ok(None)
+}
+
+async fn fetch_snos_output(config: &Config, block_no: u64) -> Result<StarknetOsOutput> {
+    let key = StorageKey::new(ArtifactKind::SnosOutput, block_no).to_string();
+    let snos_output_bytes = config.storage().get_data(&key).await?;
+    Ok(serde_json::from_slice(&snos_output_bytes)?)
+}
@@ -0,0 +1,136 @@
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_default;
+use utils::settings::default::DefaultSettingsProvider;
+use utils::settings::SettingsProvider;
+
+use crate::config::config;
+use crate::database::types::PENDING_JOB_STATUSES;
+use crate::jobs::types::JobType;
+use crate::queue::job_queue::job_processing_env_prefix;
+
+pub const JOB_BACKPRESSURE_SETTINGS_NAME: &str = "job_backpressure_settings";
+
+/// Maximum number of pending jobs of each type, the workers stop creating jobs of a type once it
+/// has that many jobs waiting to be processed or verified. `None` leaves the type uncapped.
+///
+/// The caps are honoured by the workers creating jobs for a backlog of blocks: the SNOS, proving,
+/// DA and audit workers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobBackpressureSettings {
+    pub snos_run: Option<u64>,
+    pub data_submission: Option<u64>,
+    pub proof_creation: Option<u64>,
+    pub proof_aggregation: Option<u64>,
+    pub proof_registration: Option<u64>,
+    pub state_transition: Option<u64>,
+    pub message_processing: Option<u64>,
+    pub data_audit: Option<u64>,
+}
+
+impl JobBackpressureSettings {
+    pub fn for_job_type(&self, job_type: &JobType) -> Option<u64> {
+        match job_type {
+            JobType::SnosRun => self.snos_run,
+            JobType::DataSubmission => self.data_submission,
+            JobType::ProofCreation => self.proof_creation,
+            JobType::ProofAggregation => self.proof_aggregation,
+            JobType::ProofRegistration => self.proof_registration,
+            JobType::StateTransition => self.state_transition,
+            JobType::MessageProcessing => self.message_processing,
+            JobType::DataAudit => self.data_audit,
+        }
+    }
+}
+
+impl Default for JobBackpressureSettings {
+    /// Uncapped unless `<PREFIX>_JOB_MAX_PENDING` is set, see [`job_processing_env_prefix`]
+    fn default() -> Self {
+        let max_pending = |job_type: &JobType| {
+            let env_var = format!("{}_JOB_MAX_PENDING", job_processing_env_prefix(job_type));
+            Some(get_env_var_or_default(&env_var, ""))
+                .filter(|value| !value.is_empty())
+                .map(|value| value.parse().unwrap_or_else(|_| panic!("{} must be a number", env_var)))
+        };
+        Self {
+            snos_run: max_pending(&JobType::SnosRun),
+            data_submission: max_pending(&JobType::DataSubmission),
+            proof_creation: max_pending(&JobType::ProofCreation),
+            proof_aggregation: max_pending(&JobType::ProofAggregation),
+            proof_registration: max_pending(&JobType::ProofRegistration),
+            state_transition: max_pending(&JobType::StateTransition),
+            message_processing: max_pending(&JobType::MessageProcessing),
+            data_audit: max_pending(&JobType::DataAudit),
+        }
+    }
+}
+
+/// Number of jobs of a type a worker may still create in its run, so that a worker catching up
+/// on thousands of blocks doesn't flood the queues and the database. The jobs are created up to
+/// the cap and the rest is left to the next runs, as the pending jobs complete.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobCreationBudget {
+    job_type: JobType,
+    /// `None` when the type is uncapped
+    remaining: Option<u64>,
+}
+
+impl JobCreationBudget {
+    /// Budget of the run, the pending jobs of the type counted against its cap
+    pub async fn for_job_type(job_type: JobType) -> Result<Self> {
+        let settings: JobBackpressureSettings =
+            DefaultSettingsProvider {}.get_settings(JOB_BACKPRESSURE_SETTINGS_NAME)?;
+        let Some(max_pending) = settings.for_job_type(&job_type) else {
+            return Ok(Self { job_type, remaining: None });
+        };
+        let stats = config().await.database().get_job_stats().await?;
+        let pending = stats.count_of_type_in_statuses(&job_type, &PENDING_JOB_STATUSES);
+        Ok(Self { job_type, remaining: Some(max_pending.saturating_sub(pending)) })
+    }
+
+    /// Budget allowing `remaining` more jobs, `None` for an uncapped one
+    pub fn new(job_type: JobType, remaining: Option<u64>) -> Self {
+        Self { job_type, remaining }
+    }
+
+    /// Takes a job out of the budget, returns false once the budget is spent
+    pub fn try_take(&mut self) -> bool {
+        match &mut self.remaining {
+            None => true,
+            Some(0) => {
+                tracing::info!("Too many pending {:?} jobs, the next ones are created in a later run", self.job_type);
+                false
+            }
+            Some(remaining) => {
+                *remaining -= 1;
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_backpressure_override() {
+        std::env::set_var("SNOS_JOB_MAX_PENDING", "100");
+        let settings = JobBackpressureSettings::default();
+        std::env::remove_var("SNOS_JOB_MAX_PENDING");
+
+        assert_eq!(settings.for_job_type(&JobType::SnosRun), Some(100));
+        assert_eq!(settings.for_job_type(&JobType::ProofCreation), None);
+    }
+
+    #[test]
+    fn test_job_creation_budget() {
+        let mut budget = JobCreationBudget::new(JobType::SnosRun, Some(2));
+        assert!(budget.try_take());
+        assert!(budget.try_take());
+        assert!(!budget.try_take());
+
+        let mut uncapped = JobCreationBudget::new(JobType::SnosRun, None);
+        assert!((0..1000).all(|_| uncapped.try_take()));
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_default;
+
+pub const BLOCK_BATCH_SETTINGS_NAME: &str = "block_batch_settings";
+/// Maximum number of consecutive blocks run by a single SNOS job
+pub const ENV_SNOS_BATCH_SIZE: &str = "SNOS_BATCH_SIZE";
+/// Maximum number of consecutive blocks packed into a single DA job, fewer when their state diffs
+/// don't fit in the blobs of a single DA transaction
+pub const ENV_DA_BATCH_SIZE: &str = "DA_BATCH_SIZE";
+/// Maximum number of consecutive blocks settled by a single state update job
+pub const ENV_STATE_UPDATE_BATCH_SIZE: &str = "STATE_UPDATE_BATCH_SIZE";
+
+/// Contiguous blocks covered by a job, its internal id: `<start>-<end>`, or the block number
+/// alone when it covers a single block like the jobs created before the batching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl BlockRange {
+    pub fn new(start: u64, end: u64) -> Result<Self> {
+        if start > end {
+            return Err(eyre!("Invalid block range, {} is after {}", start, end));
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn single(block_number: u64) -> Self {
+        Self { start: block_number, end: block_number }
+    }
+
+    pub fn blocks(&self) -> RangeInclusive<u64> {
+        self.start..=self.end
+    }
+
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    pub fn is_single_block(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl fmt::Display for BlockRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_single_block() {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl FromStr for BlockRange {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('-') {
+            Some((start, end)) => Self::new(start.trim().parse()?, end.trim().parse()?),
+            None => Ok(Self::single(s.trim().parse()?)),
+        }
+    }
+}
+
+/// Splits the blocks in `start..=end` into ranges of at most `max_size` blocks, the last one
+/// possibly shorter
+pub fn block_ranges(start: u64, end: u64, max_size: u64) -> Vec<BlockRange> {
+    let max_size = max_size.max(1);
+    let mut ranges = Vec::new();
+    let mut range_start = start;
+    while range_start <= end {
+        let range_end = range_start.saturating_add(max_size - 1).min(end);
+        ranges.push(BlockRange { start: range_start, end: range_end });
+        match range_end.checked_add(1) {
+            Some(next_start) => range_start = next_start,
+            None => break,
+        }
+    }
+    ranges
+}
+
+/// Maximum number of blocks covered by a single job, per job type. Catching up on many blocks
+/// with bigger batches takes far fewer jobs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockBatchSettings {
+    pub snos_run: u64,
+    pub data_submission: u64,
+    pub state_transition: u64,
+}
+
+impl Default for BlockBatchSettings {
+    /// The batch sizes are read from `SNOS_BATCH_SIZE`, `DA_BATCH_SIZE` and
+    /// `STATE_UPDATE_BATCH_SIZE` unless the settings provider has them, 1 block by default. A state
+    /// update settles up to 100 consecutive proven blocks by default.
+    fn default() -> Self {
+        let batch_size = |name: &str, default: &str| -> u64 {
+            get_env_var_or_default(name, default).parse::<u64>().unwrap_or_else(|_| panic!("{} must be a number", name))
+        };
+        Self {
+            snos_run: batch_size(ENV_SNOS_BATCH_SIZE, "1"),
+            data_submission: batch_size(ENV_DA_BATCH_SIZE, "1"),
+            state_transition: batch_size(ENV_STATE_UPDATE_BATCH_SIZE, "100"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_range_internal_id() {
+        assert_eq!("7".parse::<BlockRange>().unwrap(), BlockRange::single(7));
+        assert_eq!("3-5".parse::<BlockRange>().unwrap(), BlockRange { start: 3, end: 5 });
+        assert!("5-3".parse::<BlockRange>().is_err());
+        assert_eq!(BlockRange::single(7).to_string(), "7");
+        assert_eq!(BlockRange::new(3, 5).unwrap().to_string(), "3-5");
+    }
+
+    #[test]
+    fn test_block_ranges() {
+        let ranges = block_ranges(1, 7, 3);
+        assert_eq!(ranges.iter().map(|range| range.to_string()).collect::<Vec<_>>(), vec!["1-3", "4-6", "7"]);
+        assert_eq!(block_ranges(4, 3, 3), vec![]);
+        assert_eq!(block_ranges(0, 1, 0).len(), 2);
+    }
+}
@@ -0,0 +1,34 @@
+//! Checkpoints of the long running jobs. A handler records the last step of the processing it
+//! completed, e.g. the PIE and the OS output of a block stored by SNOS, and resumes after it when
+//! the job is processed again instead of redoing hours of work.
+//!
+//! The checkpoint is persisted right away so that it survives a failed attempt, a timeout or a
+//! restart of the orchestrator. It is cleared once the job is processed, a job processed again
+//! after its verification failed starts from scratch.
+
+use color_eyre::Result;
+
+use crate::config::Config;
+use crate::jobs::constants::JOB_METADATA_CHECKPOINT_KEY;
+use crate::jobs::types::JobItem;
+
+/// Records the last step of the processing the job completed, the format of the checkpoint is up
+/// to the handler
+pub async fn save_checkpoint(config: &Config, job: &mut JobItem, checkpoint: &str) -> Result<()> {
+    let mut metadata = job.metadata.clone();
+    metadata.insert(JOB_METADATA_CHECKPOINT_KEY.to_string(), checkpoint.to_string());
+    config.database().update_metadata(job, metadata.clone()).await?;
+    job.metadata = metadata;
+    Ok(())
+}
+
+/// Last step of the processing a previous attempt completed, `None` when there is nothing to
+/// resume from
+pub fn checkpoint(job: &JobItem) -> Option<&str> {
+    job.metadata.get(JOB_METADATA_CHECKPOINT_KEY).map(String::as_str)
+}
+
+/// Forgets the progress of the processing, once the job is processed
+pub fn clear_checkpoint(job: &mut JobItem) {
+    job.metadata.remove(JOB_METADATA_CHECKPOINT_KEY);
+}
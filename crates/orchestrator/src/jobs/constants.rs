@@ -1,10 +1,71 @@
+/// Keys the attempt counters were stored under in the metadata before they moved to
+/// [`crate::jobs::types::JobCounters`], only read by the migration of the stored jobs
 pub const JOB_PROCESS_ATTEMPT_METADATA_KEY: &str = "process_attempt_no";
-
 pub const JOB_VERIFICATION_ATTEMPT_METADATA_KEY: &str = "verification_attempt_no";
+pub const JOB_METADATA_PROCESS_TIMEOUTS_KEY: &str = "process_timeouts";
+
+/// Message of the job received from the dead-letter queue, as is
+pub const JOB_METADATA_DEAD_LETTER_PAYLOAD_KEY: &str = "dead_letter_payload";
+/// Status of the job when its message was dead-lettered
+pub const JOB_METADATA_FAILED_STATUS_KEY: &str = "last_job_status";
+
+/// Last step of the processing the job completed, see [`crate::jobs::checkpoint`]
+pub const JOB_METADATA_CHECKPOINT_KEY: &str = "checkpoint";
+
+/// Prefix of the key storing the intent to submit to an external service, followed by the
+/// idempotency key of the submission, see [`crate::jobs::idempotency`]
+pub const JOB_METADATA_SUBMISSION_INTENT_PREFIX: &str = "submission_intent_";
+/// Prefix of the key storing the external id of a submission, followed by its idempotency key
+pub const JOB_METADATA_SUBMISSION_ID_PREFIX: &str = "submission_id_";
+
+/// Id following the job and the jobs it leads to, see [`crate::jobs::trace`]
+pub const JOB_METADATA_TRACE_ID_KEY: &str = "trace_id";
 
+/// Delay before processing again a job which was held back by the settlement fees caps
+pub const JOB_FEE_TOO_HIGH_RETRY_DELAY_SECS: u64 = 300;
+
+/// Spend of the job in wei, see [`crate::jobs::costs`]
+pub const JOB_METADATA_COST_WEI_KEY: &str = "cost_wei";
+
+/// Local path of the Cairo PIE to prove, the PIE stored for the block is proven when absent
 pub const JOB_METADATA_CAIRO_PIE_PATH_KEY: &str = "cairo_pie_path";
+/// Storage key of the Cairo PIE produced by the SNOS job
+pub const JOB_METADATA_CAIRO_PIE_KEY: &str = "cairo_pie_key";
+/// Hash of the OS program the SNOS job ran the block with, hex encoded, absent when the OS
+/// programs aren't versioned
+pub const JOB_METADATA_SNOS_PROGRAM_HASH_KEY: &str = "snos_program_hash";
+/// Fact of the proof of a proving job, computed from the PIE, hex encoded
+pub const JOB_METADATA_PROOF_FACT_KEY: &str = "proof_fact";
+
+/// Blocks whose proofs are aggregated by a proof aggregation job, comma separated and consecutive
+pub const JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY: &str = "blocks_to_aggregate";
 
 pub const JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY: &str = "blocks_number_to_settle";
 pub const JOB_METADATA_STATE_UPDATE_FETCH_FROM_TESTS: &str = "fetch_from_test_data";
 pub const JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX: &str = "attempt_tx_hashes_";
 pub const JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO: &str = "last_failed_block_no";
+/// Prefix of the key listing the transactions replacing a stuck settlement transaction, followed
+/// by the hash of the original transaction
+pub const JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX: &str = "replacement_tx_hashes_";
+/// Prefix of the key storing when a settlement transaction was sent, in seconds since the unix
+/// epoch, followed by the hash of the transaction
+pub const JOB_METADATA_STATE_UPDATE_SENT_AT_PREFIX: &str = "tx_sent_at_";
+
+pub const JOB_METADATA_DA_BLOCKS_TO_SUBMIT_KEY: &str = "blocks_number_to_submit";
+/// Blocks of a DA job whose state diff is empty
+pub const JOB_METADATA_DA_EMPTY_BLOCKS_KEY: &str = "empty_blocks";
+/// How the empty blocks of a DA job were handled, see [`crate::jobs::da_job::empty_blocks`]
+pub const JOB_METADATA_DA_EMPTY_BLOCK_DECISION_KEY: &str = "empty_block_decision";
+
+/// Hash of the settlement layer transaction which sent the message of a message job
+pub const JOB_METADATA_MESSAGE_TX_HASH_KEY: &str = "message_tx_hash";
+/// Settlement layer block of the transaction which sent the message of a message job
+pub const JOB_METADATA_MESSAGE_BLOCK_NUMBER_KEY: &str = "message_block_number";
+pub const JOB_METADATA_MESSAGE_FROM_ADDRESS_KEY: &str = "message_from_address";
+pub const JOB_METADATA_MESSAGE_TO_ADDRESS_KEY: &str = "message_to_address";
+pub const JOB_METADATA_MESSAGE_SELECTOR_KEY: &str = "message_selector";
+/// Payload of the message of a message job, hex encoded and comma separated
+pub const JOB_METADATA_MESSAGE_PAYLOAD_KEY: &str = "message_payload";
+pub const JOB_METADATA_MESSAGE_NONCE_KEY: &str = "message_nonce";
+/// Fee paid for the L1 handler transaction of the message of a message job, in wei
+pub const JOB_METADATA_MESSAGE_FEE_KEY: &str = "message_fee";
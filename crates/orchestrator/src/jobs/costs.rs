@@ -0,0 +1,221 @@
+use std::collections::BTreeSet;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use settlement_client_interface::FeeTooHighError;
+use utils::env_utils::{get_env_car_optional_or_panic, get_env_var_or_default};
+
+use crate::alerts::{send_alert, AlertKind};
+use crate::config::Config;
+use crate::database::types::BlockCost;
+use crate::jobs::constants::{
+    JOB_METADATA_COST_WEI_KEY, JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX,
+    JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY, JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX,
+};
+use crate::jobs::da_job::get_block_numbers_to_submit;
+use crate::jobs::types::{JobItem, JobType};
+
+/// Cap on the cumulative DA, settlement and prover spend of a block, in wei. No cap when not set.
+pub const ENV_BLOCK_COST_CAP_WEI: &str = "BLOCK_COST_CAP_WEI";
+/// Price of proving a block, in wei. The proving services don't report what they charge.
+pub const ENV_PROVER_COST_PER_BLOCK_WEI: &str = "PROVER_COST_PER_BLOCK_WEI";
+/// Wei per smallest unit of the DA layer token, 1 by default as for the Ethereum DA layer
+pub const ENV_DA_FEE_WEI_PER_UNIT: &str = "DA_FEE_WEI_PER_UNIT";
+/// Wei per smallest unit of the settlement layer fee token, 1 by default as for Ethereum
+pub const ENV_SETTLEMENT_FEE_WEI_PER_UNIT: &str = "SETTLEMENT_FEE_WEI_PER_UNIT";
+
+/// Spend of the job attributed to each of the blocks it covers, in wei.
+///
+/// A DA transaction publishes the state diffs of all the blocks of the job, its fee is split
+/// evenly between them. So are the fees of the settlement transactions of a state update job:
+/// every transaction sent counts, including the replaced and reverted ones of the previous
+/// attempts, which can't be tied back to a single block.
+pub async fn job_costs_by_block(config: &Config, job: &JobItem) -> Result<Vec<(u64, u128)>> {
+    match job.job_type {
+        JobType::DataSubmission => {
+            let block_numbers = get_block_numbers_to_submit(job)?;
+            let fee = config.da_client().get_submission_fee(job.external_id.unwrap_string()?).await?;
+            Ok(split_evenly(to_wei(fee.unwrap_or_default(), wei_per_unit(ENV_DA_FEE_WEI_PER_UNIT)?), &block_numbers))
+        }
+        JobType::StateTransition => {
+            let blocks_to_settle =
+                job.metadata.get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY).ok_or_else(|| {
+                    eyre!("Block numbers to settle must be specified (state update job #{})", job.internal_id)
+                })?;
+            let block_numbers = blocks_to_settle
+                .replace(' ', "")
+                .split(',')
+                .map(|block_no| block_no.parse::<u64>())
+                .collect::<Result<Vec<u64>, _>>()?;
+
+            let mut fees = 0u128;
+            for tx_hash in sent_tx_hashes(job) {
+                fees = fees.saturating_add(config.settlement_client().get_tx_fee(&tx_hash).await?.unwrap_or_default());
+            }
+            Ok(split_evenly(to_wei(fees, wei_per_unit(ENV_SETTLEMENT_FEE_WEI_PER_UNIT)?), &block_numbers))
+        }
+        JobType::ProofCreation => {
+            let cost: u128 = get_env_var_or_default(ENV_PROVER_COST_PER_BLOCK_WEI, "0").parse()?;
+            Ok(vec![(job.internal_id.parse()?, cost)])
+        }
+        JobType::SnosRun
+        | JobType::ProofAggregation
+        | JobType::ProofRegistration
+        | JobType::MessageProcessing
+        | JobType::DataAudit => Ok(Vec::new()),
+    }
+}
+
+/// Hashes of all the settlement transactions sent by a state update job, across the process
+/// attempts and the replacements of the stuck transactions.
+fn sent_tx_hashes(job: &JobItem) -> BTreeSet<String> {
+    job.metadata
+        .iter()
+        .filter(|(key, _)| {
+            key.starts_with(JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX)
+                || key.starts_with(JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX)
+        })
+        .flat_map(|(_, tx_hashes)| tx_hashes.replace(' ', "").split(',').map(String::from).collect::<Vec<_>>())
+        .filter(|tx_hash| !tx_hash.is_empty())
+        .collect()
+}
+
+/// Rate of conversion to wei of a fee token, read from `env`. It can be fractional, e.g. for a
+/// token whose smallest unit is worth less than a wei.
+fn wei_per_unit(env: &str) -> Result<f64> {
+    let rate: f64 = get_env_var_or_default(env, "1").parse().map_err(|e| eyre!("Failed to parse {}: {}", env, e))?;
+    if !rate.is_finite() || rate < 0.0 {
+        return Err(eyre!("{} must be a non negative number, got {}", env, rate));
+    }
+    Ok(rate)
+}
+
+/// Converts an amount of the smallest unit of a fee token to wei, exactly when the token is wei
+pub fn to_wei(amount: u128, wei_per_unit: f64) -> u128 {
+    if wei_per_unit == 1.0 {
+        return amount;
+    }
+    // saturates on overflow
+    (amount as f64 * wei_per_unit) as u128
+}
+
+/// Splits `cost` evenly between `blocks`, the remainder goes to the first block.
+pub fn split_evenly(cost: u128, blocks: &[u64]) -> Vec<(u64, u128)> {
+    if blocks.is_empty() {
+        return Vec::new();
+    }
+    let share = cost / blocks.len() as u128;
+    let remainder = cost % blocks.len() as u128;
+    blocks.iter().enumerate().map(|(i, block_no)| (*block_no, if i == 0 { share + remainder } else { share })).collect()
+}
+
+/// Records the spend of the job in its metadata and attributes it to the blocks it covers.
+/// Failing to fetch the costs is not fatal, the job is done either way.
+pub async fn record_job_costs(config: &Config, job: &mut JobItem) {
+    let costs = match job_costs_by_block(config, job).await {
+        Ok(costs) => costs,
+        Err(e) => {
+            tracing::warn!(job_id = %job.id, "Failed to fetch the costs of the job: {}", e);
+            return;
+        }
+    };
+    if costs.is_empty() {
+        return;
+    }
+    let total = costs.iter().fold(0u128, |total, (_, cost)| total.saturating_add(*cost));
+    job.metadata.insert(JOB_METADATA_COST_WEI_KEY.to_string(), total.to_string());
+
+    for (block_no, cost) in costs {
+        let block_cost = BlockCost {
+            block_number: block_no,
+            job_id: job.id,
+            job_type: job.job_type.clone(),
+            cost_wei: cost.to_string(),
+        };
+        if let Err(e) = config.database().record_block_cost(block_cost).await {
+            tracing::warn!(job_id = %job.id, block = block_no, "Failed to attribute the costs of the job: {}", e);
+        }
+    }
+}
+
+/// Returns the spend attributed to the block so far, in wei.
+pub async fn block_spend(config: &Config, block_no: u64) -> Result<u128> {
+    config.database().get_block_costs(block_no).await?.iter().try_fold(0u128, |total, block_cost| {
+        Ok(total.saturating_add(block_cost.cost_wei.parse::<u128>()?))
+    })
+}
+
+/// Fails with a [`FeeTooHighError`] when one of the blocks already spent more than
+/// `BLOCK_COST_CAP_WEI`, and raises an alert: the job is held back and processed again later,
+/// without halting the workers, until the cap is raised.
+pub async fn ensure_blocks_within_budget(config: &Config, block_numbers: &[u64]) -> Result<()> {
+    let Some(cap) = get_env_car_optional_or_panic(ENV_BLOCK_COST_CAP_WEI) else {
+        return Ok(());
+    };
+    let cap: u128 = cap.parse().map_err(|e| eyre!("Failed to parse {}: {}", ENV_BLOCK_COST_CAP_WEI, e))?;
+    for block_no in block_numbers {
+        let spend = block_spend(config, *block_no).await?;
+        if spend > cap {
+            let message = format!("Block #{} spent {} wei, above the cap of {} wei", block_no, spend, cap);
+            send_alert(AlertKind::BlockCostCap, &message).await;
+            return Err(FeeTooHighError(message).into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::jobs::types::{JobCounters, JobPriority, JobStatus};
+
+    fn state_update_job(metadata: &[(&str, &str)]) -> JobItem {
+        JobItem {
+            id: Uuid::new_v4(),
+            internal_id: "1".to_string(),
+            job_type: JobType::StateTransition,
+            status: JobStatus::PendingVerification,
+            external_id: String::new().into(),
+            metadata: metadata
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>(),
+            version: 0,
+            created_at: 0,
+            updated_at: 0,
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
+        }
+    }
+
+    #[test]
+    fn test_split_evenly() {
+        assert_eq!(split_evenly(10, &[1, 2, 3]), vec![(1, 4), (2, 3), (3, 3)]);
+        assert_eq!(split_evenly(10, &[]), vec![]);
+    }
+
+    #[test]
+    fn test_sent_tx_hashes_across_attempts_and_replacements() {
+        let job = state_update_job(&[
+            ("attempt_tx_hashes_0", "0x1, 0x2"),
+            ("attempt_tx_hashes_1", "0x3"),
+            ("replacement_tx_hashes_0x3", "0x4,0x5"),
+            ("blocks_number_to_settle", "1,2"),
+        ]);
+        let tx_hashes: Vec<String> = sent_tx_hashes(&job).into_iter().collect();
+        assert_eq!(tx_hashes, vec!["0x1", "0x2", "0x3", "0x4", "0x5"]);
+    }
+
+    #[test]
+    fn test_to_wei() {
+        assert_eq!(to_wei(u128::MAX, 1.0), u128::MAX);
+        assert_eq!(to_wei(1_000, 2_000_000.0), 2_000_000_000);
+        assert_eq!(to_wei(1_000, 0.25), 250);
+        assert_eq!(to_wei(u128::MAX, 2.0), u128::MAX);
+    }
+}
@@ -0,0 +1,49 @@
+use std::str::FromStr;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use starknet::core::types::StateDiff;
+
+use crate::jobs::da_job::state_diff_validation::{node_state_diff_counts, StateDiffCounts};
+
+/// Environment variable selecting how the blocks with an empty state diff are handled.
+pub const ENV_DA_EMPTY_BLOCK_POLICY: &str = "DA_EMPTY_BLOCK_POLICY";
+
+/// Metadata value recorded by the data submission worker for the DA jobs of empty blocks which
+/// are not published to the DA layer.
+pub const EMPTY_BLOCK_DECISION_SKIPPED: &str = "skipped";
+/// Metadata value recorded by the data submission worker for the DA jobs carrying empty blocks
+/// folded in along with the next non empty ones.
+pub const EMPTY_BLOCK_DECISION_FOLDED: &str = "folded";
+
+/// How the data submission worker handles the blocks whose state diff is empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyBlockPolicy {
+    /// Empty blocks go through the DA layer like any other block
+    #[default]
+    Process,
+    /// DA jobs made of empty blocks only complete without publishing anything
+    Skip,
+    /// Empty blocks are packed in the next DA job without counting towards the batch size
+    Fold,
+}
+
+impl FromStr for EmptyBlockPolicy {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "process" => Ok(EmptyBlockPolicy::Process),
+            "skip" => Ok(EmptyBlockPolicy::Skip),
+            "fold" => Ok(EmptyBlockPolicy::Fold),
+            _ => Err(eyre!("Unknown empty block policy {}", s)),
+        }
+    }
+}
+
+/// Returns true if the state diff doesn't change anything but the block hash written by the OS
+/// at address 0x1.
+pub fn is_empty_state_diff(state_diff: &StateDiff) -> bool {
+    node_state_diff_counts(state_diff) == StateDiffCounts::default()
+        && state_diff.deprecated_declared_classes.is_empty()
+}
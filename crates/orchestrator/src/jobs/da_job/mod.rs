@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+pub mod empty_blocks;
+pub mod state_diff_validation;
+
+use std::collections::{HashMap, HashSet};
 use std::ops::{Add, Mul, Rem};
 use std::result::Result::{Err, Ok as OtherOk};
 use std::str::FromStr;
@@ -6,19 +9,31 @@ use std::str::FromStr;
 use async_trait::async_trait;
 use color_eyre::eyre::{eyre, Ok};
 use color_eyre::Result;
+use da_client_interface::DaVerificationStatus;
 use lazy_static::lazy_static;
+use majin_blob_core::blob;
 use num_bigint::{BigUint, ToBigUint};
 use num_traits::{Num, Zero};
 //
-use starknet::core::types::{BlockId, FieldElement, MaybePendingStateUpdate, StateUpdate, StorageEntry};
+use starknet::core::types::{BlockId, FieldElement, MaybePendingStateUpdate, StateDiff, StateUpdate, StorageEntry};
 use starknet::providers::Provider;
-use tracing::log;
+use utils::env_utils::get_env_var_or_default;
 use uuid::Uuid;
 
-use super::types::{JobItem, JobStatus, JobType, JobVerificationStatus};
+use super::constants::{JOB_METADATA_DA_BLOCKS_TO_SUBMIT_KEY, JOB_METADATA_DA_EMPTY_BLOCK_DECISION_KEY};
+use super::types::{JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
 use super::Job;
+use crate::codec::{codec_for, CodecUsage};
 use crate::config::Config;
-use crate::constants::BLOB_DATA_FILE_NAME;
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::database::types::now_secs;
+use crate::jobs::attempts::job_type_attempts;
+use crate::jobs::block_range::BlockRange;
+use crate::jobs::costs::ensure_blocks_within_budget;
+use crate::jobs::da_job::empty_blocks::{is_empty_state_diff, EMPTY_BLOCK_DECISION_SKIPPED};
+use crate::jobs::da_job::state_diff_validation::{validate_state_diff_encoding, ENV_DA_VALIDATE_STATE_DIFF};
+use crate::jobs::errors::JobError;
+use crate::jobs::idempotency::{submit_once, DA_SUBMISSION_KEY};
 
 lazy_static! {
     /// EIP-4844 BLS12-381 modulus.
@@ -58,39 +73,67 @@ impl Job for DaJob {
             external_id: String::new().into(),
             metadata,
             version: 0,
+            created_at: now_secs(),
+            updated_at: now_secs(),
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
         })
     }
 
     async fn process_job(&self, config: &Config, job: &mut JobItem) -> Result<String> {
-        let block_no = job.internal_id.parse::<u64>()?;
-
-        let state_update = config.starknet_client().get_state_update(BlockId::Number(block_no)).await?;
-
-        let state_update = match state_update {
-            MaybePendingStateUpdate::PendingUpdate(_) => {
-                log::error!("Cannot process block {} for job id {} as it's still in pending state", block_no, job.id);
+        // a DA job can cover several consecutive blocks, their state diffs are packed
+        // together into a single blob set
+        let block_numbers = get_block_numbers_to_submit(job)?;
+        ensure_blocks_within_budget(config, &block_numbers).await?;
+        let validate_state_diff = get_env_var_or_default(ENV_DA_VALIDATE_STATE_DIFF, "false") == "true";
+        let skip_publication = is_publication_skipped(job);
+
+        let mut blob_data: Vec<FieldElement> = Vec::new();
+        for block_no in block_numbers {
+            let state_update = config.starknet_client().get_state_update(BlockId::Number(block_no)).await?;
+
+            let state_update = match state_update {
+                MaybePendingStateUpdate::PendingUpdate(_) => {
+                    tracing::error!(job_id = %job.id, block = block_no, "Cannot process the block, it's still pending");
+                    return Err(eyre!(
+                        "Cannot process block {} for job id {} as it's still in pending state",
+                        block_no,
+                        job.id
+                    ));
+                }
+                MaybePendingStateUpdate::Update(state_update) => state_update,
+            };
+            let state_diff = state_update.state_diff.clone();
+            if skip_publication && !is_empty_state_diff(&state_diff) {
                 return Err(eyre!(
-                    "Cannot process block {} for job id {} as it's still in pending state",
+                    "Block {} of job id {} was marked as empty but its state diff is not empty",
                     block_no,
                     job.id
                 ));
             }
-            MaybePendingStateUpdate::Update(state_update) => state_update,
-        };
-        // constructing the data from the rpc
-        let blob_data = state_update_to_blob_data(block_no, state_update, config).await?;
-        // transforming the data so that we can apply FFT on this.
-        // @note: we can skip this step if in the above step we return vec<BigUint> directly
-        let blob_data_biguint = convert_to_biguint(blob_data.clone());
-        // data transformation on the data
-        let transformed_data = fft_transformation(blob_data_biguint);
+            // constructing the data from the rpc
+            let block_blob_data = state_update_to_blob_data(block_no, state_update, config).await?;
+
+            if validate_state_diff {
+                validate_state_diff_encoding(block_no, &state_diff, &block_blob_data).map_err(|e| {
+                    tracing::error!(job_id = %job.id, block = block_no, "State diff validation failed: {}", e);
+                    eyre!("State diff validation failed for block {} and job id {}: {}", block_no, job.id, e)
+                })?;
+            }
+            blob_data.extend(block_blob_data);
+        }
+
+        if skip_publication {
+            tracing::info!(job_id = %job.id, "Skipping the publication of the empty blocks to the DA layer");
+            return Ok(EMPTY_BLOCK_DECISION_SKIPPED.to_string());
+        }
 
         let max_bytes_per_blob = config.da_client().max_bytes_per_blob().await;
         let max_blob_per_txn = config.da_client().max_blob_per_txn().await;
 
-        // converting BigUints to Vec<u8>, one Vec<u8> represents one blob data
-        let blob_array =
-            data_to_blobs(max_bytes_per_blob, transformed_data).expect("error while converting blob data to vec<u8>");
+        // converting the field elements to Vec<u8>, one Vec<u8> represents one blob data
+        let blob_array = blob_data_to_blobs(max_bytes_per_blob, blob_data.clone())?;
         let current_blob_length: u64 =
             blob_array.len().try_into().expect("Unable to convert the blob length into u64 format.");
 
@@ -100,32 +143,182 @@ impl Job for DaJob {
                 "Exceeded the maximum number of blobs per transaction: allowed {}, found {} for block {} and job id {}",
                 max_blob_per_txn,
                 current_blob_length,
-                block_no,
+                job.internal_id,
                 job.id
             ));
         }
 
-        // making the txn to the DA layer
-        let external_id = config.da_client().publish_state_diff(blob_array, &[0; 32]).await?;
+        // decoding the blobs again before posting them, corrupt DA data on-chain can't be taken back
+        validate_blobs_round_trip(max_bytes_per_blob, &blob_array, &blob_data).map_err(|e| {
+            tracing::error!(job_id = %job.id, block = %job.internal_id, "DA blob validation failed: {}", e);
+            eyre!("DA blob validation failed for block {} and job id {}: {}", job.internal_id, job.id, e)
+        })?;
+
+        // the DA payload codec must stay identity for the DA layers committing to the blobs
+        let da_payload_codec = codec_for(CodecUsage::DaPayload)?;
+        let blob_array = blob_array.iter().map(|blob| da_payload_codec.encode(blob)).collect::<Result<Vec<_>>>()?;
 
-        Ok(external_id)
+        // making the txn to the DA layer
+        submit_once(config, job, DA_SUBMISSION_KEY, move || async move {
+            config
+                .da_client()
+                .publish_state_diff(blob_array, &[0; 32])
+                .await
+                .map_err(|e| JobError::external("DA layer", e))
+        })
+        .await
     }
 
     async fn verify_job(&self, config: &Config, job: &mut JobItem) -> Result<JobVerificationStatus> {
-        Ok(config.da_client().verify_inclusion(job.external_id.unwrap_string()?).await?.into())
+        // nothing was published for the empty blocks
+        if is_publication_skipped(job) {
+            return Ok(JobVerificationStatus::Verified);
+        }
+
+        let external_id = job.external_id.unwrap_string()?;
+        let verification_status = config.da_client().verify_inclusion(external_id).await?;
+
+        if verification_status == DaVerificationStatus::Verified {
+            // persisting the inclusion proof for the state update job and auditors
+            if let Some(inclusion_proof) = config.da_client().get_inclusion_proof(external_id).await? {
+                store_inclusion_proof(inclusion_proof, &get_block_numbers_to_submit(job)?, config).await?;
+            }
+        }
+
+        Ok(verification_status.into())
     }
 
     fn max_process_attempts(&self) -> u64 {
-        1
+        job_type_attempts(&JobType::DataSubmission).max_process_attempts
     }
 
     fn max_verification_attempts(&self) -> u64 {
-        3
+        job_type_attempts(&JobType::DataSubmission).max_verification_attempts
     }
 
     fn verification_polling_delay_seconds(&self) -> u64 {
-        60
+        job_type_attempts(&JobType::DataSubmission).verification_polling_delay_seconds
+    }
+
+    fn max_backoff_seconds(&self) -> u64 {
+        600
+    }
+}
+
+/// Returns the block numbers covered by a DA job. Jobs created before batching was introduced
+/// only carry the block number in their internal id, the block range otherwise.
+pub fn get_block_numbers_to_submit(job: &JobItem) -> Result<Vec<u64>> {
+    let block_numbers = match job.metadata.get(JOB_METADATA_DA_BLOCKS_TO_SUBMIT_KEY) {
+        Some(blocks) => blocks
+            .replace(' ', "")
+            .split(',')
+            .map(|block_no| block_no.parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()
+            .map_err(|e| eyre!("Block numbers to submit list is not correctly formatted: {e}"))?,
+        None => job.internal_id.parse::<BlockRange>()?.blocks().collect(),
+    };
+    if block_numbers.is_empty() {
+        return Err(eyre!("No block numbers found for DA job #{}", job.internal_id));
+    }
+    Ok(block_numbers)
+}
+
+/// Returns true if the data submission worker decided not to publish the blocks of the job, all
+/// of them being empty.
+fn is_publication_skipped(job: &JobItem) -> bool {
+    job.metadata.get(JOB_METADATA_DA_EMPTY_BLOCK_DECISION_KEY).map(String::as_str) == Some(EMPTY_BLOCK_DECISION_SKIPPED)
+}
+
+/// Splits the encoded state diffs into chunks of `BLOB_LEN` field elements, applies the FFT
+/// transformation on each chunk and converts the result into blobs of `blob_size` bytes.
+pub fn blob_data_to_blobs(blob_size: u64, blob_data: Vec<FieldElement>) -> Result<Vec<Vec<u8>>> {
+    let mut blobs: Vec<Vec<u8>> = Vec::new();
+    for chunk in blob_data.chunks(*BLOB_LEN) {
+        // transforming the data so that we can apply FFT on this.
+        let chunk_biguint = convert_to_biguint(chunk.to_vec());
+        // data transformation on the data
+        let transformed_data = fft_transformation(chunk_biguint);
+        blobs.extend(data_to_blobs(blob_size, transformed_data)?);
     }
+    Ok(blobs)
+}
+
+/// Number of blobs [`blob_data_to_blobs`] builds out of `num_elements` field elements
+pub fn blob_count(blob_size: u64, num_elements: usize) -> u64 {
+    let blobs_per_chunk = (*BLOB_LEN * 32).div_ceil(blob_size.max(1) as usize);
+    (num_elements.div_ceil(*BLOB_LEN) * blobs_per_chunk) as u64
+}
+
+/// Number of field elements [`state_update_to_blob_data`] encodes the state diff of a block into,
+/// known without fetching the nonces of the contracts
+pub fn encoded_state_diff_len(state_diff: &StateDiff) -> usize {
+    let storage_writes: HashMap<FieldElement, usize> =
+        state_diff.storage_diffs.iter().map(|item| (item.address, item.storage_entries.len())).collect();
+    let nonces: HashMap<FieldElement, FieldElement> =
+        state_diff.nonces.iter().map(|item| (item.contract_address, item.nonce)).collect();
+    let class_updates: HashSet<FieldElement> = state_diff
+        .deployed_contracts
+        .iter()
+        .map(|item| item.address)
+        .chain(state_diff.replaced_classes.iter().map(|item| item.contract_address))
+        .collect();
+    let addresses: HashSet<&FieldElement> = storage_writes.keys().chain(nonces.keys()).chain(&class_updates).collect();
+
+    let contract_updates: usize = addresses
+        .into_iter()
+        .map(|address| {
+            let writes = storage_writes.get(address).copied().unwrap_or_default();
+            let class_update = class_updates.contains(address);
+            let nonce_update = nonces.get(address).is_some_and(|nonce| *nonce != FieldElement::ZERO);
+            // the block hash written by the OS at address 0x1 is left out
+            if *address == FieldElement::ONE && !class_update && !nonce_update && writes == 1 {
+                return 0;
+            }
+            // address, DA word, class hash, key and value of the writes
+            2 + usize::from(class_update) + 2 * writes
+        })
+        .sum();
+
+    // header of 5 elements, contract updates, number of declared classes and their hashes
+    5 + contract_updates + 1 + 2 * state_diff.declared_classes.len()
+}
+
+/// Decodes the blobs built by [`blob_data_to_blobs`] back into field elements (inverse FFT) and
+/// checks that they match the encoded state diffs. Returns a descriptive error on the first
+/// mismatch.
+pub fn validate_blobs_round_trip(blob_size: u64, blobs: &[Vec<u8>], blob_data: &[FieldElement]) -> Result<()> {
+    let chunk_len_bytes = *BLOB_LEN * 32;
+    // every chunk of `BLOB_LEN` elements is split into blobs on its own, the last one being padded
+    let blobs_per_chunk = chunk_len_bytes.div_ceil(blob_size as usize);
+    let chunks: Vec<&[FieldElement]> = blob_data.chunks(*BLOB_LEN).collect();
+
+    if blobs.len() != chunks.len() * blobs_per_chunk {
+        return Err(eyre!(
+            "Expected {} blobs for {} field elements, found {}",
+            chunks.len() * blobs_per_chunk,
+            blob_data.len(),
+            blobs.len()
+        ));
+    }
+
+    for (chunk_index, (chunk, chunk_blobs)) in chunks.iter().zip(blobs.chunks(blobs_per_chunk)).enumerate() {
+        let bytes = chunk_blobs.concat();
+        let evaluations: Vec<BigUint> = bytes[..chunk_len_bytes].chunks(32).map(BigUint::from_bytes_be).collect();
+        let recovered = blob::recover(evaluations);
+        let expected = convert_to_biguint(chunk.to_vec());
+
+        if let Some(position) = recovered.iter().zip(expected.iter()).position(|(found, expected)| found != expected) {
+            return Err(eyre!(
+                "Blob round-trip mismatch in chunk {} at element {}: encoded {} but decoded {}",
+                chunk_index,
+                position,
+                expected[position],
+                recovered[position]
+            ));
+        }
+    }
+
+    Ok(())
 }
 
 pub fn fft_transformation(elements: Vec<BigUint>) -> Vec<BigUint> {
@@ -169,6 +362,12 @@ pub fn convert_to_biguint(elements: Vec<FieldElement>) -> Vec<BigUint> {
     biguint_vec
 }
 
+/// Converts the data of a single block into the blobs sent along with its state update, the
+/// elements are stored as is, without the FFT transformation of the DA job.
+pub async fn block_data_to_blobs(blob_data: Vec<FieldElement>, config: &Config) -> Result<Vec<Vec<u8>>> {
+    data_to_blobs(config.da_client().max_bytes_per_blob().await, convert_to_biguint(blob_data))
+}
+
 fn data_to_blobs(blob_size: u64, block_data: Vec<BigUint>) -> Result<Vec<Vec<u8>>> {
     // Validate blob size
     if blob_size < 32 {
@@ -180,8 +379,17 @@ fn data_to_blobs(blob_size: u64, block_data: Vec<BigUint>) -> Result<Vec<Vec<u8>
 
     let mut blobs: Vec<Vec<u8>> = Vec::new();
 
-    // Convert all FieldElements to bytes first
-    let mut bytes: Vec<u8> = block_data.iter().flat_map(|element| element.to_bytes_be().to_vec()).collect();
+    // Convert all FieldElements to bytes first, every element takes exactly 32 bytes so that the
+    // blob can be decoded back element by element
+    let mut bytes: Vec<u8> = block_data
+        .iter()
+        .flat_map(|element| {
+            let element_bytes = element.to_bytes_be();
+            let mut padded = vec![0u8; 32usize.saturating_sub(element_bytes.len())];
+            padded.extend(element_bytes);
+            padded
+        })
+        .collect();
 
     // Process bytes in chunks of blob_size
     while bytes.len() >= blob_size as usize {
@@ -195,7 +403,7 @@ fn data_to_blobs(blob_size: u64, block_data: Vec<BigUint>) -> Result<Vec<Vec<u8>
         let mut last_blob = bytes;
         last_blob.resize(blob_size as usize, 0); // Pad with zeros
         blobs.push(last_blob);
-        println!("Warning: Remaining {} bytes not forming a complete blob were padded", remaining_bytes);
+        tracing::warn!("Remaining {} bytes not forming a complete blob were padded", remaining_bytes);
     }
 
     Ok(blobs)
@@ -207,15 +415,6 @@ pub async fn state_update_to_blob_data(
     config: &Config,
 ) -> Result<Vec<FieldElement>> {
     let state_diff = state_update.state_diff;
-    let mut blob_data: Vec<FieldElement> = vec![
-        FieldElement::from(state_diff.storage_diffs.len()),
-        // @note: won't need this if while producing the block we are attaching the block number
-        // and the block hash
-        FieldElement::ONE,
-        FieldElement::ONE,
-        FieldElement::from(block_no),
-        state_update.block_hash,
-    ];
 
     let storage_diffs: HashMap<FieldElement, &Vec<StorageEntry>> =
         state_diff.storage_diffs.iter().map(|item| (item.address, &item.storage_entries)).collect();
@@ -225,25 +424,44 @@ pub async fn state_update_to_blob_data(
         state_diff.deployed_contracts.iter().map(|item| (item.address, item.class_hash)).collect();
     let replaced_classes: HashMap<FieldElement, FieldElement> =
         state_diff.replaced_classes.iter().map(|item| (item.contract_address, item.class_hash)).collect();
-    let mut nonces: HashMap<FieldElement, FieldElement> =
+    let nonces: HashMap<FieldElement, FieldElement> =
         state_diff.nonces.iter().map(|item| (item.contract_address, item.nonce)).collect();
 
-    // Loop over storage diffs
-    for (addr, writes) in storage_diffs {
-        let class_flag = deployed_contracts.get(&addr).or_else(|| replaced_classes.get(&addr));
+    // every contract updated by the block, a contract can have its nonce updated or be deployed
+    // without any storage write
+    let addresses: HashSet<FieldElement> = storage_diffs
+        .keys()
+        .chain(nonces.keys())
+        .chain(deployed_contracts.keys())
+        .chain(replaced_classes.keys())
+        .copied()
+        .collect();
 
-        let mut nonce = nonces.remove(&addr);
+    let mut blob_data: Vec<FieldElement> = vec![
+        FieldElement::from(addresses.len()),
+        // @note: won't need this if while producing the block we are attaching the block number
+        // and the block hash
+        FieldElement::ONE,
+        FieldElement::ONE,
+        FieldElement::from(block_no),
+        state_update.block_hash,
+    ];
+
+    for addr in addresses {
+        let writes = storage_diffs.get(&addr).map(|writes| writes.as_slice()).unwrap_or_default();
+        let class_flag = deployed_contracts.get(&addr).or_else(|| replaced_classes.get(&addr));
 
-        // @note: if nonce is null and there is some len of writes, make an api call to get the contract
-        // nonce for the block
+        let mut nonce = nonces.get(&addr).copied();
 
-        if nonce.is_none() && !writes.is_empty() && addr != FieldElement::ONE {
+        // @note: if nonce is null, make an api call to get the contract nonce for the block. A
+        // contract deployed in the block without a nonce update has a nonce of 0.
+        if nonce.is_none() && addr != FieldElement::ONE && !deployed_contracts.contains_key(&addr) {
             let get_current_nonce_result = config.starknet_client().get_nonce(BlockId::Number(block_no), addr).await;
 
             nonce = match get_current_nonce_result {
                 OtherOk(get_current_nonce) => Some(get_current_nonce),
                 Err(e) => {
-                    log::error!("Failed to get nonce: {}", e);
+                    tracing::error!("Failed to get nonce: {}", e);
                     return Err(eyre!("Failed to get nonce: {}", e));
                 }
             };
@@ -283,19 +501,17 @@ pub async fn state_update_to_blob_data(
     Ok(blob_data)
 }
 
-/// To store the blob data using the storage client with path <block_number>/blob_data.txt
+/// To store the blob data using the storage client under the blob data key of the block
 async fn store_blob_data(blob_data: Vec<FieldElement>, block_number: u64, config: &Config) -> Result<()> {
     let storage_client = config.storage();
-    let key = block_number.to_string() + "/" + BLOB_DATA_FILE_NAME;
-    let data_blob_big_uint = convert_to_biguint(blob_data.clone());
+    let key = StorageKey::new(ArtifactKind::BlobData, block_number).to_string();
 
-    let blobs_array = data_to_blobs(config.da_client().max_bytes_per_blob().await, data_blob_big_uint)
-        .expect("Not able to convert the data into blobs.");
+    let blobs_array = block_data_to_blobs(blob_data, config).await.expect("Not able to convert the data into blobs.");
 
     let blob = blobs_array.clone();
 
     // converting Vec<Vec<u8> into Vec<u8>
-    let blob_vec_u8 = bincode::serialize(&blob)?;
+    let blob_vec_u8 = codec_for(CodecUsage::StorageArtifact)?.encode(&bincode::serialize(&blob)?)?;
 
     if !blobs_array.is_empty() {
         storage_client.put_data(blob_vec_u8.into(), &key).await?;
@@ -304,6 +520,17 @@ async fn store_blob_data(blob_data: Vec<FieldElement>, block_number: u64, config
     Ok(())
 }
 
+/// To store the DA inclusion proof using the storage client under the DA inclusion proof key of
+/// every block covered by the DA job
+async fn store_inclusion_proof(inclusion_proof: Vec<u8>, block_numbers: &[u64], config: &Config) -> Result<()> {
+    let storage_client = config.storage();
+    for block_number in block_numbers {
+        let key = StorageKey::new(ArtifactKind::DaInclusionProof, block_number).to_string();
+        storage_client.put_data(inclusion_proof.clone().into(), &key).await?;
+    }
+    Ok(())
+}
+
 /// DA word encoding:
 /// |---padding---|---class flag---|---new nonce---|---num changes---|
 ///     127 bits        1 bit           64 bits          64 bits
@@ -415,7 +642,7 @@ pub mod test {
         #[case] file_path: &str,
         #[case] nonce_file_path: &str,
     ) {
-        use crate::jobs::da_job::{convert_to_biguint, state_update_to_blob_data};
+        use crate::jobs::da_job::{convert_to_biguint, encoded_state_diff_len, state_update_to_blob_data};
 
         let server = MockServer::start();
         let mut da_client = MockDaClient::new();
@@ -442,9 +669,11 @@ pub mod test {
         get_nonce_attached(&server, nonce_file_path);
 
         let state_update = read_state_update_from_file(state_update_file_path).expect("issue while reading");
+        let encoded_len = encoded_state_diff_len(&state_update.state_diff);
         let blob_data = state_update_to_blob_data(block_no, state_update, &config)
             .await
             .expect("issue while converting state update to blob data");
+        assert_eq!(blob_data.len(), encoded_len);
 
         let blob_data_biguint = convert_to_biguint(blob_data);
 
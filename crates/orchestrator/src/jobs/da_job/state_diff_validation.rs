@@ -0,0 +1,177 @@
+use std::collections::{HashMap, HashSet};
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use starknet::core::types::{FieldElement, StateDiff};
+
+/// Environment variable enabling the differential validation of the encoded state diffs.
+pub const ENV_DA_VALIDATE_STATE_DIFF: &str = "DA_VALIDATE_STATE_DIFF";
+
+/// Number of field elements before the contract updates in the encoded state diff of a block:
+/// `[num contracts, 1, 1, block number, block hash]`.
+const HEADER_LEN: usize = 5;
+
+/// What a state diff is made of, as counted on both sides of the differential validation.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StateDiffCounts {
+    pub storage_writes: usize,
+    pub nonce_updates: usize,
+    pub deployed_contracts: usize,
+    pub declared_classes: usize,
+}
+
+/// A contract update decoded back from the encoded state diff.
+struct EncodedContractUpdate {
+    address: FieldElement,
+    nonce: u64,
+    num_changes: usize,
+    class_flag: bool,
+}
+
+/// Cross-checks the encoded state diff of a block against the state diff returned by the node's
+/// `starknet_getStateUpdate`.
+///
+/// The encoded data is decoded back independently of the encoder and the number of storage
+/// writes, nonce updates, deployed contracts and declared classes it carries must match the ones
+/// of the node. This guards against the encoder silently drifting from the node semantics, e.g.
+/// after a protocol upgrade.
+pub fn validate_state_diff_encoding(block_no: u64, state_diff: &StateDiff, blob_data: &[FieldElement]) -> Result<()> {
+    let (encoded_block_no, updates, declared_classes) = decode_block_blob_data(blob_data)?;
+    if encoded_block_no != FieldElement::from(block_no) {
+        return Err(eyre!("Encoded state diff is for block {:#x} instead of block {}", encoded_block_no, block_no));
+    }
+
+    let expected = node_state_diff_counts(state_diff);
+    let encoded = encoded_state_diff_counts(state_diff, &updates, declared_classes);
+    if expected != encoded {
+        return Err(eyre!(
+            "State diff encoding mismatch for block {}: node has {:?} but the encoded state diff has {:?}",
+            block_no,
+            expected,
+            encoded
+        ));
+    }
+
+    Ok(())
+}
+
+/// Counts of the state diff as returned by the node.
+pub fn node_state_diff_counts(state_diff: &StateDiff) -> StateDiffCounts {
+    let nonces: HashSet<FieldElement> = state_diff.nonces.iter().map(|item| item.contract_address).collect();
+    let deployed_contracts: HashSet<FieldElement> = state_diff
+        .deployed_contracts
+        .iter()
+        .map(|item| item.address)
+        .chain(state_diff.replaced_classes.iter().map(|item| item.contract_address))
+        .collect();
+
+    let storage_writes = state_diff
+        .storage_diffs
+        .iter()
+        // the block hash written by the OS at address 0x1 is not part of the DA
+        .filter(|item| {
+            !(item.address == FieldElement::ONE
+                && item.storage_entries.len() == 1
+                && !nonces.contains(&item.address)
+                && !deployed_contracts.contains(&item.address))
+        })
+        .map(|item| item.storage_entries.len())
+        .sum();
+
+    StateDiffCounts {
+        storage_writes,
+        nonce_updates: state_diff.nonces.len(),
+        deployed_contracts: deployed_contracts.len(),
+        declared_classes: state_diff.declared_classes.len(),
+    }
+}
+
+/// Counts of the decoded state diff. A nonce update is only counted if the encoded state diff
+/// carries the new nonce of the node for that contract.
+fn encoded_state_diff_counts(
+    state_diff: &StateDiff,
+    updates: &[EncodedContractUpdate],
+    declared_classes: usize,
+) -> StateDiffCounts {
+    let encoded_nonces: HashMap<FieldElement, u64> =
+        updates.iter().map(|update| (update.address, update.nonce)).collect();
+    let nonce_updates = state_diff
+        .nonces
+        .iter()
+        .filter(|item| {
+            encoded_nonces.get(&item.contract_address).is_some_and(|nonce| FieldElement::from(*nonce) == item.nonce)
+        })
+        .count();
+
+    StateDiffCounts {
+        storage_writes: updates.iter().map(|update| update.num_changes).sum(),
+        nonce_updates,
+        deployed_contracts: updates.iter().filter(|update| update.class_flag).count(),
+        declared_classes,
+    }
+}
+
+/// Decodes the encoded state diff of a single block into its block number, contract updates and
+/// number of declared classes.
+fn decode_block_blob_data(blob_data: &[FieldElement]) -> Result<(FieldElement, Vec<EncodedContractUpdate>, usize)> {
+    if blob_data.len() < HEADER_LEN + 1 {
+        return Err(eyre!("Encoded state diff is too short: {} elements", blob_data.len()));
+    }
+    let num_contracts = to_usize(blob_data[0])?;
+    let block_no = blob_data[3];
+
+    let mut updates = Vec::new();
+    let mut position = HEADER_LEN;
+    for i in 0..num_contracts {
+        // the header counts the block hash update at address 0x1 which the encoder may leave out
+        if i == num_contracts - 1 && is_declared_classes_section(&blob_data[position..]) {
+            break;
+        }
+        let (update, next_position) = decode_contract_update(blob_data, position)?;
+        updates.push(update);
+        position = next_position;
+    }
+
+    if !is_declared_classes_section(&blob_data[position..]) {
+        return Err(eyre!("Malformed declared classes section at position {} of the encoded state diff", position));
+    }
+    let declared_classes = to_usize(blob_data[position])?;
+
+    Ok((block_no, updates, declared_classes))
+}
+
+/// Decodes the contract update starting at `position`, returns it along with the position of the
+/// next one.
+fn decode_contract_update(blob_data: &[FieldElement], position: usize) -> Result<(EncodedContractUpdate, usize)> {
+    let (address, da_word) = match blob_data.get(position..position + 2) {
+        Some([address, da_word]) => (*address, da_word.to_bytes_be()),
+        _ => return Err(eyre!("Encoded state diff ends in the middle of a contract update")),
+    };
+
+    // |---padding---|---class flag---|---new nonce---|---num changes---|
+    //     127 bits        1 bit           64 bits          64 bits
+    let class_flag = da_word[15] & 1 == 1;
+    let nonce = u64::from_be_bytes(da_word[16..24].try_into()?);
+    let num_changes: usize = u64::from_be_bytes(da_word[24..32].try_into()?).try_into()?;
+
+    let next_position = num_changes
+        .checked_mul(2)
+        .and_then(|len| len.checked_add(position + 2 + usize::from(class_flag)))
+        .filter(|next_position| *next_position <= blob_data.len())
+        .ok_or_else(|| eyre!("Encoded state diff ends in the middle of the update of contract {:#x}", address))?;
+
+    Ok((EncodedContractUpdate { address, nonce, num_changes, class_flag }, next_position))
+}
+
+/// The declared classes section is the number of classes followed by a (class hash, compiled class
+/// hash) pair per class.
+fn is_declared_classes_section(data: &[FieldElement]) -> bool {
+    match data.first().map(|len| to_usize(*len)) {
+        Some(Ok(len)) => len.checked_mul(2).and_then(|len| len.checked_add(1)) == Some(data.len()),
+        _ => false,
+    }
+}
+
+fn to_usize(element: FieldElement) -> Result<usize> {
+    Ok(u64::try_from(element).map_err(|_| eyre!("{:#x} is not a valid length", element))?.try_into()?)
+}
@@ -0,0 +1,110 @@
+//! Dependencies between the jobs. A job created with parents waits in the
+//! [`JobStatus::PendingDependencies`] status until they are all completed, it is then released:
+//! moved to [`JobStatus::Created`] and added to the process queue like any other job.
+//!
+//! The children of a job are released as soon as it is completed, the [`DependencyWorker`]
+//! releases the ones missed, e.g. when the orchestrator stopped in between.
+//!
+//! [`DependencyWorker`]: crate::workers::dependencies::DependencyWorker
+
+use std::collections::HashMap;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use mockall_double::double;
+use uuid::Uuid;
+
+use crate::config::{config, Config};
+#[double]
+use crate::jobs::job_handler_factory::factory;
+use crate::jobs::record_status_change;
+use crate::jobs::trace::assign_trace_id;
+use crate::jobs::types::{JobItem, JobStatus, JobType};
+use crate::queue::job_queue::add_job_to_process_queue;
+use crate::workers::run_history::count_created_jobs;
+
+/// Creates a job depending on the `parents`, of one of the parent types of its type. The job is
+/// added to the process queue right away if the parents are all completed already, it waits for
+/// them otherwise. It takes the highest priority of its parents, so that the jobs following a
+/// high priority job are high priority as well.
+pub async fn create_job_with_parents(
+    job_type: JobType,
+    internal_id: String,
+    metadata: HashMap<String, String>,
+    parents: &[JobItem],
+) -> Result<()> {
+    let config = config().await;
+    if let Some(parent) = parents.iter().find(|parent| !job_type.parent_job_types().contains(&parent.job_type)) {
+        return Err(eyre!("A {:?} job can't depend on the {:?} job {:?}", job_type, parent.job_type, parent.id));
+    }
+    if config.database().get_job_by_internal_id_and_type(internal_id.as_str(), &job_type).await?.is_some() {
+        return Err(eyre!(
+            "Job already exists for internal_id {:?} and job_type {:?}. Skipping.",
+            internal_id,
+            job_type
+        ));
+    }
+
+    let job_handler = factory::get_job_handler(&job_type).await;
+    let mut job_item = job_handler.create_job(config.as_ref(), internal_id, metadata).await?;
+    job_item.parent_ids = parents.iter().map(|parent| parent.id).collect();
+    job_item.priority = parents.iter().map(|parent| parent.priority).max().unwrap_or_default();
+    assign_trace_id(&mut job_item, parents);
+    let parents_completed = parents.iter().all(|parent| parent.status == JobStatus::Completed);
+    if !parents_completed {
+        job_item.status = JobStatus::PendingDependencies;
+    }
+    config.database().create_job(job_item.clone()).await?;
+    count_created_jobs(1);
+
+    if parents_completed {
+        add_job_to_process_queue(&job_item).await?;
+    }
+    Ok(())
+}
+
+/// Releases the jobs depending on the completed job whose parents are now all completed
+pub async fn release_dependent_jobs(parent: &JobItem) -> Result<()> {
+    let config = config().await;
+    for job in config.database().get_jobs_by_parent_id(parent.id).await? {
+        release_job_if_ready(config.as_ref(), job).await?;
+    }
+    Ok(())
+}
+
+/// Releases the job if it's waiting for its parents and they are all completed, returns whether
+/// it was released
+pub async fn release_job_if_ready(config: &Config, mut job: JobItem) -> Result<bool> {
+    if job.status != JobStatus::PendingDependencies {
+        return Ok(false);
+    }
+    for parent_id in &job.parent_ids {
+        if !is_job_completed(config, *parent_id).await? {
+            return Ok(false);
+        }
+    }
+
+    if let Err(e) = config.database().update_job_status(&job, JobStatus::Created).await {
+        // the job may have been released concurrently, by the completion of another parent
+        return match config.database().get_job_by_id(job.id).await? {
+            Some(current) if current.status != JobStatus::PendingDependencies => Ok(false),
+            _ => Err(e),
+        };
+    }
+    job.status = JobStatus::Created;
+    record_status_change(config, &job, JobStatus::PendingDependencies, JobStatus::Created, None).await;
+    tracing::info!("Parents of job with id {:?} are completed, releasing it", job.id);
+    add_job_to_process_queue(&job).await?;
+    Ok(true)
+}
+
+/// Whether the job is completed, the archived jobs were all completed
+async fn is_job_completed(config: &Config, id: Uuid) -> Result<bool> {
+    match config.database().get_job_by_id(id).await? {
+        Some(job) => Ok(job.status == JobStatus::Completed),
+        None => match config.database().get_archived_job_by_id(id).await? {
+            Some(_) => Ok(true),
+            None => Err(eyre!("Failed to find the parent job with id {:?}", id)),
+        },
+    }
+}
@@ -0,0 +1,83 @@
+use color_eyre::Report;
+use prover_client_interface::ProverClientError;
+use settlement_client_interface::{FeeTooHighError, SimulationRevertedError};
+
+use crate::jobs::types::JobBlockedError;
+
+/// Error of a job handler classified by whether it may go away on its own and by where it comes
+/// from. `process_job` decides from the classification what happens to the job, see
+/// [`JobError::action`]. The errors which aren't classified are handled as internal transient
+/// ones.
+#[derive(thiserror::Error, Debug)]
+pub enum JobError {
+    /// An external service failed in a way which may not happen again, e.g. it was unreachable
+    #[error("{service} failed: {message}")]
+    ExternalTransient { service: &'static str, message: String },
+    /// An external service refused the job, e.g. the prover rejected the PIE as invalid
+    #[error("{service} refused the job: {message}")]
+    ExternalPermanent { service: &'static str, message: String },
+    /// The orchestrator failed in a way which may not happen again, e.g. a database write conflict
+    #[error("{0}")]
+    InternalTransient(String),
+    /// The job can't be processed as it is, e.g. its metadata is invalid
+    #[error("{0}")]
+    InternalPermanent(String),
+}
+
+/// What happens to a job whose processing failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobErrorAction {
+    /// The failure counts as an attempt, the job is processed again if it has attempts left
+    Retry,
+    /// The message of the job is left for the queue to deliver again, it is dead-lettered once
+    /// the queue gives up
+    DeadLetter,
+    /// The job is failed right away, retrying it would fail the same
+    Fail,
+}
+
+impl JobError {
+    pub fn is_transient(&self) -> bool {
+        matches!(self, JobError::ExternalTransient { .. } | JobError::InternalTransient(_))
+    }
+
+    pub fn is_external(&self) -> bool {
+        matches!(self, JobError::ExternalTransient { .. } | JobError::ExternalPermanent { .. })
+    }
+
+    pub fn action(&self) -> JobErrorAction {
+        match (self.is_transient(), self.is_external()) {
+            (true, true) => JobErrorAction::Retry,
+            (true, false) => JobErrorAction::DeadLetter,
+            (false, _) => JobErrorAction::Fail,
+        }
+    }
+
+    /// Classifies the error of a call to an external service as transient, unless the service
+    /// returned one of the errors `process_job` already handles on its own
+    pub fn external(service: &'static str, error: Report) -> Report {
+        if error.downcast_ref::<FeeTooHighError>().is_some()
+            || error.downcast_ref::<SimulationRevertedError>().is_some()
+            || error.downcast_ref::<JobBlockedError>().is_some()
+            || error.downcast_ref::<JobError>().is_some()
+        {
+            return error;
+        }
+        JobError::ExternalTransient { service, message: error.to_string() }.into()
+    }
+}
+
+impl From<ProverClientError> for JobError {
+    fn from(error: ProverClientError) -> Self {
+        let message = error.to_string();
+        match error {
+            ProverClientError::Internal(_) => JobError::ExternalTransient { service: "Prover", message },
+            ProverClientError::TaskInvalid(_)
+            | ProverClientError::TaskUnsupported(_)
+            | ProverClientError::PieEncoding(_) => JobError::ExternalPermanent { service: "Prover", message },
+            ProverClientError::SettingsProvider(_) | ProverClientError::FactChecker(_) => {
+                JobError::InternalPermanent(message)
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Idempotency of the external submissions of the jobs, e.g. the blobs sent to the DA layer or the
+//! tasks sent to the prover: a retry of the processing must not submit again what a previous
+//! attempt submitted already.
+//!
+//! The intent to submit is persisted under the idempotency key of the submission before calling
+//! out, and the external id returned by the submission right after. A retry finding the external
+//! id returns it, the verification of the submission resumes instead of submitting again. A retry
+//! finding only the intent means that a previous attempt was interrupted while submitting, e.g.
+//! by a timeout or a restart, and the submission may have gone through: the job is failed for the
+//! operators to check the external service, [`crate::jobs::retry_job`] then clears the intent.
+//!
+//! The submissions of a job are forgotten once their verification is rejected, the next attempt
+//! submits again.
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use color_eyre::Result;
+
+use crate::config::Config;
+use crate::database::types::now_secs;
+use crate::jobs::constants::{JOB_METADATA_SUBMISSION_ID_PREFIX, JOB_METADATA_SUBMISSION_INTENT_PREFIX};
+use crate::jobs::errors::JobError;
+use crate::jobs::types::JobItem;
+
+/// Idempotency key of the tasks submitted to the prover
+pub const PROVER_SUBMISSION_KEY: &str = "prover";
+/// Idempotency key of the state diffs published to the DA layer
+pub const DA_SUBMISSION_KEY: &str = "da";
+
+/// Submits to the external service unless a previous attempt did already, returns the external
+/// id of the submission. An error returned by `submit` means nothing was submitted.
+pub async fn submit_once<F, Fut>(config: &Config, job: &mut JobItem, key: &str, submit: F) -> Result<String>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let id_key = format!("{}{}", JOB_METADATA_SUBMISSION_ID_PREFIX, key);
+    if let Some(external_id) = job.metadata.get(&id_key) {
+        tracing::info!(job_id = %job.id, "Resuming the {} submission {} made by a previous attempt", key, external_id);
+        return Ok(external_id.clone());
+    }
+    let intent_key = format!("{}{}", JOB_METADATA_SUBMISSION_INTENT_PREFIX, key);
+    if let Some(intended_at) = job.metadata.get(&intent_key) {
+        return Err(JobError::InternalPermanent(format!(
+            "The {} submission of job #{} was interrupted (intended at {}), it may have gone through",
+            key, job.internal_id, intended_at
+        ))
+        .into());
+    }
+
+    let mut metadata = job.metadata.clone();
+    metadata.insert(intent_key.clone(), now_secs().to_string());
+    persist_metadata(config, job, metadata).await?;
+
+    let result = submit().await;
+    let mut metadata = job.metadata.clone();
+    metadata.remove(&intent_key);
+    if let Ok(external_id) = &result {
+        metadata.insert(id_key, external_id.clone());
+    }
+    persist_metadata(config, job, metadata).await?;
+    result
+}
+
+/// Forgets the submissions of the job, the next attempt submits again
+pub fn forget_submissions(job: &mut JobItem) {
+    job.metadata.retain(|key, _| {
+        !key.starts_with(JOB_METADATA_SUBMISSION_ID_PREFIX) && !key.starts_with(JOB_METADATA_SUBMISSION_INTENT_PREFIX)
+    });
+}
+
+/// Forgets the interrupted submissions of the job, once the operators checked that they didn't go
+/// through
+pub fn clear_submission_intents(job: &mut JobItem) {
+    job.metadata.retain(|key, _| !key.starts_with(JOB_METADATA_SUBMISSION_INTENT_PREFIX));
+}
+
+async fn persist_metadata(config: &Config, job: &mut JobItem, metadata: HashMap<String, String>) -> Result<()> {
+    config.database().update_metadata(job, metadata.clone()).await?;
+    job.metadata = metadata;
+    Ok(())
+}
@@ -8,7 +8,10 @@ pub mod factory {
     use mockall::automock;
 
     use crate::jobs::types::JobType;
-    use crate::jobs::{da_job, proving_job, snos_job, state_update_job, Job};
+    use crate::jobs::{
+        audit_job, da_job, message_job, proof_aggregation_job, proving_job, register_proof_job, snos_job,
+        state_update_job, Job,
+    };
 
     /// To get the job handler
     //         +-------------------+
@@ -56,8 +59,11 @@ pub mod factory {
             JobType::DataSubmission => Box::new(da_job::DaJob),
             JobType::SnosRun => Box::new(snos_job::SnosJob),
             JobType::ProofCreation => Box::new(proving_job::ProvingJob),
+            JobType::ProofAggregation => Box::new(proof_aggregation_job::ProofAggregationJob),
+            JobType::ProofRegistration => Box::new(register_proof_job::RegisterProofJob),
             JobType::StateTransition => Box::new(state_update_job::StateUpdateJob),
-            _ => unimplemented!("Job type not implemented yet."),
+            JobType::MessageProcessing => Box::new(message_job::MessageJob),
+            JobType::DataAudit => Box::new(audit_job::AuditJob),
         };
 
         Arc::new(job)
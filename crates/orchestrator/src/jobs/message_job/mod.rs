@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use settlement_client_interface::L1ToL2Message;
+use uuid::Uuid;
+
+use super::attempts::job_type_attempts;
+use super::constants::{
+    JOB_METADATA_MESSAGE_BLOCK_NUMBER_KEY, JOB_METADATA_MESSAGE_FEE_KEY, JOB_METADATA_MESSAGE_FROM_ADDRESS_KEY,
+    JOB_METADATA_MESSAGE_NONCE_KEY, JOB_METADATA_MESSAGE_PAYLOAD_KEY, JOB_METADATA_MESSAGE_SELECTOR_KEY,
+    JOB_METADATA_MESSAGE_TO_ADDRESS_KEY, JOB_METADATA_MESSAGE_TX_HASH_KEY,
+};
+use super::types::{JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
+use super::Job;
+use crate::config::Config;
+use crate::database::types::now_secs;
+
+/// Tracks a message sent to the L2 through the core contract, e.g. a deposit, until it is
+/// consumed. Madara executes the message with an L1 handler transaction on its own, the job
+/// records the message for the operators and is verified once a settled state update consumed it
+/// on the settlement layer. The internal id is the hash of the message.
+pub struct MessageJob;
+
+#[async_trait]
+impl Job for MessageJob {
+    async fn create_job(
+        &self,
+        _config: &Config,
+        internal_id: String,
+        metadata: HashMap<String, String>,
+    ) -> Result<JobItem> {
+        let job = JobItem {
+            id: Uuid::new_v4(),
+            internal_id,
+            job_type: JobType::MessageProcessing,
+            status: JobStatus::Created,
+            external_id: String::new().into(),
+            metadata,
+            version: 0,
+            created_at: now_secs(),
+            updated_at: now_secs(),
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
+        };
+        message_hash(&job)?;
+        Ok(job)
+    }
+
+    /// The message is recorded by the job itself, its hash is the external id the consumption is
+    /// verified with
+    async fn process_job(&self, _config: &Config, job: &mut JobItem) -> Result<String> {
+        Ok(format!("0x{}", hex::encode(message_hash(job)?)))
+    }
+
+    /// The message is consumed once a settled state update includes the L1 handler transaction
+    /// which executed it, which takes until the block is proven
+    async fn verify_job(&self, config: &Config, job: &mut JobItem) -> Result<JobVerificationStatus> {
+        if config.settlement_client().is_message_to_l2_consumed(message_hash(job)?).await? {
+            Ok(JobVerificationStatus::Verified)
+        } else {
+            Ok(JobVerificationStatus::Pending)
+        }
+    }
+
+    fn max_process_attempts(&self) -> u64 {
+        job_type_attempts(&JobType::MessageProcessing).max_process_attempts
+    }
+
+    fn max_verification_attempts(&self) -> u64 {
+        job_type_attempts(&JobType::MessageProcessing).max_verification_attempts
+    }
+
+    fn verification_polling_delay_seconds(&self) -> u64 {
+        job_type_attempts(&JobType::MessageProcessing).verification_polling_delay_seconds
+    }
+
+    fn max_backoff_seconds(&self) -> u64 {
+        600
+    }
+}
+
+/// Internal id of the job tracking the message, its hash hex encoded
+pub fn message_internal_id(message: &L1ToL2Message) -> String {
+    format!("0x{}", hex::encode(message.message_hash))
+}
+
+/// Metadata of the job tracking the message, the values are hex encoded but for the block
+/// number and the fee
+pub fn message_metadata(message: &L1ToL2Message) -> HashMap<String, String> {
+    let hex = |value: &[u8; 32]| format!("0x{}", hex::encode(value));
+    HashMap::from([
+        (JOB_METADATA_MESSAGE_TX_HASH_KEY.to_string(), message.tx_hash.clone()),
+        (JOB_METADATA_MESSAGE_BLOCK_NUMBER_KEY.to_string(), message.block_number.to_string()),
+        (JOB_METADATA_MESSAGE_FROM_ADDRESS_KEY.to_string(), hex(&message.from_address)),
+        (JOB_METADATA_MESSAGE_TO_ADDRESS_KEY.to_string(), hex(&message.to_address)),
+        (JOB_METADATA_MESSAGE_SELECTOR_KEY.to_string(), hex(&message.selector)),
+        (JOB_METADATA_MESSAGE_PAYLOAD_KEY.to_string(), message.payload.iter().map(hex).collect::<Vec<_>>().join(",")),
+        (JOB_METADATA_MESSAGE_NONCE_KEY.to_string(), hex(&message.nonce)),
+        (JOB_METADATA_MESSAGE_FEE_KEY.to_string(), message.fee.to_string()),
+    ])
+}
+
+/// Hash of the message of the job, from its internal id
+fn message_hash(job: &JobItem) -> Result<[u8; 32]> {
+    let bytes = hex::decode(job.internal_id.trim_start_matches("0x"))
+        .map_err(|e| eyre!("Invalid message hash (message job #{}): {}", job.internal_id, e))?;
+    bytes.try_into().map_err(|_| eyre!("Message hash must be 32 bytes (message job #{})", job.internal_id))
+}
@@ -6,23 +6,51 @@ use color_eyre::eyre::eyre;
 use color_eyre::Result;
 use mockall::automock;
 use mockall_double::double;
-use tracing::log;
+use settlement_client_interface::{FeeTooHighError, SimulationRevertedError};
+use utils::env_utils::get_env_var_or_default;
 use uuid::Uuid;
 
+use crate::alerts::{send_alert, AlertKind};
 use crate::config::{config, Config};
-use crate::jobs::constants::{JOB_PROCESS_ATTEMPT_METADATA_KEY, JOB_VERIFICATION_ATTEMPT_METADATA_KEY};
+use crate::database::types::{DatabaseWrite, JobEvent};
+use crate::jobs::checkpoint::clear_checkpoint;
+use crate::jobs::constants::{
+    JOB_FEE_TOO_HIGH_RETRY_DELAY_SECS, JOB_METADATA_DEAD_LETTER_PAYLOAD_KEY, JOB_METADATA_FAILED_STATUS_KEY,
+};
+use crate::jobs::errors::{JobError, JobErrorAction};
+use crate::jobs::idempotency::{clear_submission_intents, forget_submissions};
 #[double]
 use crate::jobs::job_handler_factory::factory;
-use crate::jobs::types::{JobItem, JobStatus, JobType, JobVerificationStatus};
-use crate::queue::job_queue::{add_job_to_process_queue, add_job_to_verification_queue};
-
+use crate::jobs::trace::{assign_trace_id, record_job_fields};
+use crate::jobs::types::{
+    JobBlockedError, JobCounter, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus,
+};
+use crate::metrics::JOB_PROCESS_TIMEOUTS;
+use crate::queue::job_queue::{
+    add_job_to_process_queue, add_job_to_process_queue_with_backoff, add_job_to_process_queue_with_delay,
+    add_job_to_verification_queue, job_processing_env_prefix, JobBackoff,
+};
+use crate::workers::run_history::count_created_jobs;
+
+pub mod attempts;
+pub mod audit_job;
+pub mod backpressure;
+pub mod block_range;
+pub mod checkpoint;
 pub mod constants;
+pub mod costs;
 pub mod da_job;
+pub mod dependencies;
+pub mod errors;
+pub mod idempotency;
 pub mod job_handler_factory;
+pub mod message_job;
+pub mod proof_aggregation_job;
 pub mod proving_job;
 pub mod register_proof_job;
 pub mod snos_job;
 pub mod state_update_job;
+pub mod trace;
 
 /// The Job trait is used to define the methods that a job
 /// should implement to be used as a job for the orchestrator. The orchestrator automatically
@@ -46,23 +74,47 @@ pub trait Job: Send + Sync {
     /// the status of the verification.
     async fn verify_job(&self, config: &Config, job: &mut JobItem) -> Result<JobVerificationStatus>;
     /// Should return the maximum number of attempts to process the job. A new attempt is made
-    /// every time the verification returns `JobVerificationStatus::Rejected`
+    /// every time the verification returns `JobVerificationStatus::Rejected`. The handlers read
+    /// the attempts and the polling delay from the [`attempts::JobAttemptSettings`]
     fn max_process_attempts(&self) -> u64;
     /// Should return the maximum number of attempts to verify the job. A new attempt is made
     /// every few seconds depending on the result `verification_polling_delay_seconds`
     fn max_verification_attempts(&self) -> u64;
     /// Should return the number of seconds to wait before polling for verification
     fn verification_polling_delay_seconds(&self) -> u64;
+    /// Should return the maximum number of seconds to wait before polling for verification or
+    /// retrying the processing. The delay starts at `verification_polling_delay_seconds` and
+    /// doubles with every attempt up to this cap.
+    fn max_backoff_seconds(&self) -> u64;
 }
 
 pub mod types;
 
+/// Identifies the orchestrator instance in the history of the jobs, the host name by default
+pub const ENV_ORCHESTRATOR_WORKER_ID: &str = "ORCHESTRATOR_WORKER_ID";
+/// Seconds the processing of a job may take before it is abandoned and retried, overridden per
+/// job type with `<PREFIX>_JOB_PROCESS_TIMEOUT_SECS`, see [`job_processing_env_prefix`]
+pub const ENV_JOB_PROCESS_TIMEOUT_SECS: &str = "JOB_PROCESS_TIMEOUT_SECS";
+const DEFAULT_JOB_PROCESS_TIMEOUT_SECS: &str = "3600";
+
 /// Creates the job in the DB in the created state and adds it to the process queue
 pub async fn create_job(job_type: JobType, internal_id: String, metadata: HashMap<String, String>) -> Result<()> {
+    create_job_with_priority(job_type, internal_id, metadata, JobPriority::Normal).await
+}
+
+/// Creates the job like [`create_job`] does, processed from the queue of the `priority`. The
+/// operators create the jobs they want done right away, e.g. the settlement of a block, with the
+/// high priority so that they don't wait behind the backlog.
+pub async fn create_job_with_priority(
+    job_type: JobType,
+    internal_id: String,
+    metadata: HashMap<String, String>,
+    priority: JobPriority,
+) -> Result<()> {
     let config = config().await;
     let existing_job = config.database().get_job_by_internal_id_and_type(internal_id.as_str(), &job_type).await?;
     if existing_job.is_some() {
-        log::debug!("Job already exists for internal_id {:?} and job_type {:?}. Skipping.", internal_id, job_type);
+        tracing::debug!("Job already exists for internal_id {:?} and job_type {:?}. Skipping.", internal_id, job_type);
         return Err(eyre!(
             "Job already exists for internal_id {:?} and job_type {:?}. Skipping.",
             internal_id,
@@ -71,10 +123,43 @@ pub async fn create_job(job_type: JobType, internal_id: String, metadata: HashMa
     }
 
     let job_handler = factory::get_job_handler(&job_type).await;
-    let job_item = job_handler.create_job(config.as_ref(), internal_id, metadata).await?;
+    let mut job_item = job_handler.create_job(config.as_ref(), internal_id, metadata).await?;
+    job_item.priority = priority;
+    assign_trace_id(&mut job_item, &[]);
     config.database().create_job(job_item.clone()).await?;
+    count_created_jobs(1);
+
+    add_job_to_process_queue(&job_item).await?;
+    Ok(())
+}
+
+/// Creates the jobs in the DB in the created state, all of them or none, and adds them to the
+/// process queue. Nothing is created if one of the jobs already exists.
+pub async fn create_jobs(job_type: JobType, jobs: Vec<(String, HashMap<String, String>)>) -> Result<()> {
+    if jobs.is_empty() {
+        return Ok(());
+    }
+    let config = config().await;
+    let job_handler = factory::get_job_handler(&job_type).await;
+    let mut job_items = Vec::with_capacity(jobs.len());
+    for (internal_id, metadata) in jobs {
+        if config.database().get_job_by_internal_id_and_type(internal_id.as_str(), &job_type).await?.is_some() {
+            return Err(eyre!(
+                "Job already exists for internal_id {:?} and job_type {:?}. Skipping.",
+                internal_id,
+                job_type
+            ));
+        }
+        let mut job_item = job_handler.create_job(config.as_ref(), internal_id, metadata).await?;
+        assign_trace_id(&mut job_item, &[]);
+        job_items.push(job_item);
+    }
+    config.database().run_transaction(job_items.iter().cloned().map(DatabaseWrite::CreateJob).collect()).await?;
+    count_created_jobs(job_items.len() as u64);
 
-    add_job_to_process_queue(job_item.id).await?;
+    for job_item in &job_items {
+        add_job_to_process_queue(job_item).await?;
+    }
     Ok(())
 }
 
@@ -84,14 +169,21 @@ pub async fn process_job(id: Uuid) -> Result<()> {
     let config = config().await;
     let mut job = get_job(id).await?;
 
+    let old_status = job.status.clone();
     match job.status {
         // we only want to process jobs that are in the created or verification failed state.
-        // verification failed state means that the previous processing failed and we want to retry
-        JobStatus::Created | JobStatus::VerificationFailed => {
-            log::info!("Processing job with id {:?}", id);
+        // verification failed state means that the previous processing failed and we want to retry,
+        // fee too high means that the previous processing was held back by the settlement fees
+        JobStatus::Created | JobStatus::VerificationFailed | JobStatus::FeeTooHigh => {
+            tracing::info!("Processing job with id {:?}", id);
+        }
+        // its message was already queued when it was cancelled, the message is consumed
+        JobStatus::Cancelled => {
+            tracing::info!("Job with id {:?} is cancelled, not processing it", id);
+            return Ok(());
         }
         _ => {
-            log::error!("Invalid status {:?} for job with id {:?}. Cannot process.", id, job.status);
+            tracing::error!("Invalid status {:?} for job with id {:?}. Cannot process.", id, job.status);
             return Err(eyre!("Invalid status {:?} for job with id {:?}. Cannot process.", id, job.status));
         }
     }
@@ -99,23 +191,189 @@ pub async fn process_job(id: Uuid) -> Result<()> {
     // the same job, it would fail to update the job in the database because the version would be
     // outdated
     config.database().update_job_status(&job, JobStatus::LockedForProcessing).await?;
+    record_status_change(config.as_ref(), &job, old_status, JobStatus::LockedForProcessing, None).await;
 
     let job_handler = factory::get_job_handler(&job.job_type).await;
-    let external_id = job_handler.process_job(config.as_ref(), &mut job).await?;
-    let metadata = increment_key_in_metadata(&job.metadata, JOB_PROCESS_ATTEMPT_METADATA_KEY)?;
+    let timeout = job_process_timeout(&job.job_type)?;
+    let process_result = tokio::time::timeout(timeout, job_handler.process_job(config.as_ref(), &mut job)).await;
+    let Ok(process_result) = process_result else {
+        return handle_process_timeout(config.as_ref(), &mut job, &**job_handler, timeout).await;
+    };
+    let external_id = match process_result {
+        Ok(external_id) => external_id,
+        Err(e) => {
+            // retrying a blocked job would only hide the inconsistency, it is parked for investigation
+            if let Some(blocked) = e.downcast_ref::<JobBlockedError>() {
+                tracing::error!("Job with id {:?} is blocked: {}", id, blocked);
+                job.status = JobStatus::Blocked;
+                job.metadata.insert("error".to_string(), blocked.to_string());
+                config.database().update_job(&job).await?;
+                record_status_change(
+                    config.as_ref(),
+                    &job,
+                    JobStatus::LockedForProcessing,
+                    JobStatus::Blocked,
+                    Some(blocked.to_string()),
+                )
+                .await;
+                // a blocked audit job found the settled blocks diverging from the L2
+                let kind = match job.job_type {
+                    JobType::DataAudit => AlertKind::AuditMismatch,
+                    _ => AlertKind::JobFailed,
+                };
+                send_alert(
+                    kind,
+                    &format!("{:?} job #{} ({}) is blocked: {}", job.job_type, job.internal_id, job.id, blocked),
+                )
+                .await;
+                return Ok(());
+            }
+            // the settlement transaction was held back rather than overpaying, or the blocks went
+            // over their cost cap. the job is processed again once the fees had time to come down
+            if let Some(fee_too_high) = e.downcast_ref::<FeeTooHighError>() {
+                tracing::warn!("Job with id {:?} is held back by the fees: {}", id, fee_too_high);
+                job.status = JobStatus::FeeTooHigh;
+                job.metadata.insert("error".to_string(), fee_too_high.to_string());
+                config.database().update_job(&job).await?;
+                record_status_change(
+                    config.as_ref(),
+                    &job,
+                    JobStatus::LockedForProcessing,
+                    JobStatus::FeeTooHigh,
+                    Some(fee_too_high.to_string()),
+                )
+                .await;
+                add_job_to_process_queue_with_delay(&job, Duration::from_secs(JOB_FEE_TOO_HIGH_RETRY_DELAY_SECS))
+                    .await?;
+                return Ok(());
+            }
+            // the settlement transaction would have reverted, it was not sent. the job is rejected
+            // with the revert reason rather than retried as is
+            if let Some(reverted) = e.downcast_ref::<SimulationRevertedError>() {
+                tracing::error!("Job with id {:?} failed the settlement simulation: {}", id, reverted);
+                job.status = JobStatus::VerificationFailed;
+                job.metadata.insert("error".to_string(), reverted.to_string());
+                config.database().update_job(&job).await?;
+                record_status_change(
+                    config.as_ref(),
+                    &job,
+                    JobStatus::LockedForProcessing,
+                    JobStatus::VerificationFailed,
+                    Some(reverted.to_string()),
+                )
+                .await;
+                return Ok(());
+            }
+            if let Some(job_error) = e.downcast_ref::<JobError>() {
+                match job_error.action() {
+                    JobErrorAction::Retry => {
+                        tracing::warn!("Processing of job with id {:?} failed transiently: {}", id, job_error);
+                        return release_failed_attempt(config.as_ref(), &mut job, &**job_handler, job_error.to_string())
+                            .await;
+                    }
+                    JobErrorAction::Fail => {
+                        tracing::error!("Processing of job with id {:?} failed permanently: {}", id, job_error);
+                        return fail_job(config.as_ref(), &mut job, job_error.to_string()).await;
+                    }
+                    JobErrorAction::DeadLetter => {}
+                }
+            }
+            return Err(e);
+        }
+    };
+    job.counters.process_attempts = config.database().increment_job_counter(&job, JobCounter::ProcessAttempts).await?;
 
     job.external_id = external_id.into();
+    clear_checkpoint(&mut job);
     job.status = JobStatus::PendingVerification;
-    job.metadata = metadata;
 
     config.database().update_job(&job).await?;
+    record_status_change(config.as_ref(), &job, JobStatus::LockedForProcessing, JobStatus::PendingVerification, None)
+        .await;
 
-    add_job_to_verification_queue(job.id, Duration::from_secs(job_handler.verification_polling_delay_seconds()))
-        .await?;
+    add_job_to_verification_queue(&job, &job_backoff(&**job_handler), 0).await?;
 
     Ok(())
 }
 
+/// Releases the lock of a job whose processing timed out, e.g. on a hung call to an external
+/// service. The timed out processing counts as a failed attempt.
+async fn handle_process_timeout(
+    config: &Config,
+    job: &mut JobItem,
+    job_handler: &dyn Job,
+    timeout: Duration,
+) -> Result<()> {
+    let error = format!("Processing timed out after {} seconds", timeout.as_secs());
+    tracing::error!("{} for job with id {:?}", error, job.id);
+    JOB_PROCESS_TIMEOUTS.with_label_values(&[&format!("{:?}", job.job_type)]).inc();
+
+    job.counters.process_timeouts = config.database().increment_job_counter(job, JobCounter::ProcessTimeouts).await?;
+    release_failed_attempt(config, job, job_handler, error).await
+}
+
+/// Releases the lock of a job whose processing failed, the failure counting as an attempt. The
+/// job is processed again if it has attempts left, it is left `VerificationFailed` otherwise.
+async fn release_failed_attempt(
+    config: &Config,
+    job: &mut JobItem,
+    job_handler: &dyn Job,
+    error: String,
+) -> Result<()> {
+    job.counters.process_attempts = config.database().increment_job_counter(job, JobCounter::ProcessAttempts).await?;
+    job.metadata.insert("error".to_string(), error.clone());
+    job.status = JobStatus::VerificationFailed;
+    config.database().update_job(job).await?;
+    record_status_change(
+        config,
+        job,
+        JobStatus::LockedForProcessing,
+        JobStatus::VerificationFailed,
+        Some(error.clone()),
+    )
+    .await;
+
+    let process_attempts = job.counters.process_attempts;
+    if process_attempts < job_handler.max_process_attempts() {
+        add_job_to_process_queue_with_backoff(job, &job_backoff(job_handler), process_attempts.saturating_sub(1))
+            .await?;
+    } else {
+        send_alert(
+            AlertKind::JobFailed,
+            &format!(
+                "{:?} job #{} ({}) failed after {} process attempts: {}",
+                job.job_type, job.internal_id, job.id, process_attempts, error
+            ),
+        )
+        .await;
+    }
+    Ok(())
+}
+
+/// Fails the job right away, its processing would fail the same if retried. The job can be retried
+/// with [`retry_job`] once the cause is fixed.
+async fn fail_job(config: &Config, job: &mut JobItem, error: String) -> Result<()> {
+    job.status = JobStatus::Failed;
+    job.metadata.insert("error".to_string(), error.clone());
+    job.metadata.insert(JOB_METADATA_FAILED_STATUS_KEY.to_string(), format!("{:?}", JobStatus::LockedForProcessing));
+    config.database().update_job(job).await?;
+    record_status_change(config, job, JobStatus::LockedForProcessing, JobStatus::Failed, Some(error.clone())).await;
+    send_alert(
+        AlertKind::JobFailed,
+        &format!("{:?} job #{} ({}) failed: {}", job.job_type, job.internal_id, job.id, error),
+    )
+    .await;
+    Ok(())
+}
+
+/// Time the processing of a job of the type may take, see [`ENV_JOB_PROCESS_TIMEOUT_SECS`]
+pub fn job_process_timeout(job_type: &JobType) -> Result<Duration> {
+    let env_var = format!("{}_{}", job_processing_env_prefix(job_type), ENV_JOB_PROCESS_TIMEOUT_SECS);
+    let default_timeout = get_env_var_or_default(ENV_JOB_PROCESS_TIMEOUT_SECS, DEFAULT_JOB_PROCESS_TIMEOUT_SECS);
+    let timeout = get_env_var_or_default(&env_var, &default_timeout);
+    Ok(Duration::from_secs(timeout.parse().map_err(|e| eyre!("Invalid {}: {}", env_var, e))?))
+}
+
 /// Verifies the job and updates the status of the job in the DB. If the verification fails, it
 /// retries processing the job if the max attempts have not been exceeded. If the max attempts have
 /// been exceeded, it marks the job as timedout. If the verification is still pending, it pushes the
@@ -126,10 +384,14 @@ pub async fn verify_job(id: Uuid) -> Result<()> {
 
     match job.status {
         JobStatus::PendingVerification => {
-            log::info!("Verifying job with id {:?}", id);
+            tracing::info!("Verifying job with id {:?}", id);
+        }
+        JobStatus::Cancelled => {
+            tracing::info!("Job with id {:?} is cancelled, not verifying it", id);
+            return Ok(());
         }
         _ => {
-            log::error!("Invalid status {:?} for job with id {:?}. Cannot verify.", id, job.status);
+            tracing::error!("Invalid status {:?} for job with id {:?}. Cannot verify.", id, job.status);
             return Err(eyre!("Invalid status {:?} for job with id {:?}. Cannot verify.", id, job.status));
         }
     }
@@ -139,60 +401,251 @@ pub async fn verify_job(id: Uuid) -> Result<()> {
 
     match verification_status {
         JobVerificationStatus::Verified => {
-            config.database().update_job_status(&job, JobStatus::Completed).await?;
+            costs::record_job_costs(config.as_ref(), &mut job).await;
+            job.status = JobStatus::Completed;
+            config.database().update_job(&job).await?;
+            record_status_change(config.as_ref(), &job, JobStatus::PendingVerification, JobStatus::Completed, None)
+                .await;
+            // the dependency worker releases them later otherwise, the job is completed either way
+            if let Err(e) = dependencies::release_dependent_jobs(&job).await {
+                tracing::error!("Failed to release the jobs depending on job with id {:?}: {}", id, e);
+            }
         }
         JobVerificationStatus::Rejected(e) => {
+            // the rejected attempt may still have cost, e.g. a reverted settlement transaction
+            costs::record_job_costs(config.as_ref(), &mut job).await;
             let mut new_job = job.clone();
-            new_job.metadata.insert("error".to_string(), e);
+            new_job.metadata.insert("error".to_string(), e.clone());
+            // the rejected submissions are made again by the next attempt
+            forget_submissions(&mut new_job);
             new_job.status = JobStatus::VerificationFailed;
 
             config.database().update_job(&new_job).await?;
+            record_status_change(
+                config.as_ref(),
+                &new_job,
+                JobStatus::PendingVerification,
+                JobStatus::VerificationFailed,
+                Some(e),
+            )
+            .await;
 
-            log::error!("Verification failed for job with id {:?}. Cannot verify.", id);
+            tracing::error!("Verification failed for job with id {:?}. Cannot verify.", id);
 
             // retry job processing if we haven't exceeded the max limit
-            let process_attempts = get_u64_from_metadata(&job.metadata, JOB_PROCESS_ATTEMPT_METADATA_KEY)?;
+            let process_attempts = job.counters.process_attempts;
             if process_attempts < job_handler.max_process_attempts() {
-                log::info!(
+                tracing::info!(
                     "Verification failed for job {}. Retrying processing attempt {}.",
                     job.id,
                     process_attempts + 1
                 );
-                add_job_to_process_queue(job.id).await?;
+                add_job_to_process_queue_with_backoff(
+                    &new_job,
+                    &job_backoff(&**job_handler),
+                    process_attempts.saturating_sub(1),
+                )
+                .await?;
                 return Ok(());
             } else {
-                // TODO: send alert
+                send_alert(
+                    AlertKind::JobFailed,
+                    &format!(
+                        "{:?} job #{} ({}) failed verification after {} process attempts",
+                        job.job_type, job.internal_id, job.id, process_attempts
+                    ),
+                )
+                .await;
             }
         }
         JobVerificationStatus::Pending => {
-            log::info!("Inclusion is still pending for job {}. Pushing back to queue.", job.id);
-            let verify_attempts = get_u64_from_metadata(&job.metadata, JOB_VERIFICATION_ATTEMPT_METADATA_KEY)?;
+            tracing::info!("Inclusion is still pending for job {}. Pushing back to queue.", job.id);
+            let verify_attempts = job.counters.verification_attempts;
             if verify_attempts >= job_handler.max_verification_attempts() {
-                // TODO: send alert
-                log::info!("Verification attempts exceeded for job {}. Marking as timed out.", job.id);
+                tracing::info!("Verification attempts exceeded for job {}. Marking as timed out.", job.id);
                 config.database().update_job_status(&job, JobStatus::VerificationTimeout).await?;
+                record_status_change(
+                    config.as_ref(),
+                    &job,
+                    JobStatus::PendingVerification,
+                    JobStatus::VerificationTimeout,
+                    Some(format!("Verification attempts exceeded ({})", verify_attempts)),
+                )
+                .await;
+                send_alert(
+                    AlertKind::JobFailed,
+                    &format!(
+                        "{:?} job #{} ({}) timed out after {} verification attempts",
+                        job.job_type, job.internal_id, job.id, verify_attempts
+                    ),
+                )
+                .await;
                 return Ok(());
             }
-            let metadata = increment_key_in_metadata(&job.metadata, JOB_VERIFICATION_ATTEMPT_METADATA_KEY)?;
-            config.database().update_metadata(&job, metadata).await?;
-            add_job_to_verification_queue(
-                job.id,
-                Duration::from_secs(job_handler.verification_polling_delay_seconds()),
-            )
-            .await?;
+            job.counters.verification_attempts =
+                config.database().increment_job_counter(&job, JobCounter::VerificationAttempts).await?;
+            add_job_to_verification_queue(&job, &job_backoff(&**job_handler), job.counters.verification_attempts)
+                .await?;
         }
     };
 
     Ok(())
 }
 
+/// Marks the job as failed once its message was dead-lettered by the queue, keeping the message
+/// and the status the job was in for the investigation, and alerts the operators. Completed
+/// jobs are left as they are, their message failed after the job went through.
+pub async fn handle_job_failure(id: Uuid, payload: String) -> Result<()> {
+    let config = config().await;
+    let mut job = get_job(id).await?;
+    let old_status = job.status.clone();
+
+    // a blocked job is parked for investigation, it was alerted on already
+    if matches!(old_status, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled | JobStatus::Blocked) {
+        tracing::warn!("Job with id {:?} is already {:?}, ignoring its dead-lettered message", id, old_status);
+        return Ok(());
+    }
+
+    job.status = JobStatus::Failed;
+    job.metadata.insert(JOB_METADATA_FAILED_STATUS_KEY.to_string(), format!("{:?}", old_status));
+    job.metadata.insert(JOB_METADATA_DEAD_LETTER_PAYLOAD_KEY.to_string(), payload);
+    config.database().update_job(&job).await?;
+    record_status_change(config.as_ref(), &job, old_status.clone(), JobStatus::Failed, None).await;
+    send_alert(
+        AlertKind::JobFailed,
+        &format!(
+            "{:?} job #{} ({}) failed in status {:?}, its message was dead-lettered",
+            job.job_type, job.internal_id, job.id, old_status
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Retries a job needing manual intervention, once the cause of its failure has been fixed. The
+/// attempt counters are reset so that the job gets its full budget of attempts again.
+///
+/// A job that timed out in verification, or whose message was dead-lettered while it was pending
+/// verification, was processed already: it goes back to `PendingVerification` and its
+/// verification is polled again. Any other failed job goes back to `Created` and is processed
+/// again. The jobs in any other status are not retried.
+pub async fn retry_job(id: Uuid) -> Result<()> {
+    let config = config().await;
+    let mut job = get_job(id).await?;
+    let old_status = job.status.clone();
+
+    if !old_status.is_retryable() {
+        tracing::error!("Invalid status {:?} for job with id {:?}. Cannot retry.", old_status, id);
+        return Err(eyre!("Invalid status {:?} for job with id {:?}. Cannot retry.", old_status, id));
+    }
+    let failed_in_verification = old_status == JobStatus::VerificationTimeout
+        || job
+            .metadata
+            .get(JOB_METADATA_FAILED_STATUS_KEY)
+            .is_some_and(|status| *status == format!("{:?}", JobStatus::PendingVerification));
+
+    job.metadata.remove(JOB_METADATA_FAILED_STATUS_KEY);
+    job.metadata.remove(JOB_METADATA_DEAD_LETTER_PAYLOAD_KEY);
+    clear_submission_intents(&mut job);
+    let mut counters = vec![JobCounter::VerificationAttempts];
+    if !failed_in_verification {
+        counters.push(JobCounter::ProcessAttempts);
+    }
+    config.database().reset_job_counters(&job, &counters).await?;
+    for counter in counters {
+        job.counters.set(counter, 0);
+    }
+    job.status = if failed_in_verification { JobStatus::PendingVerification } else { JobStatus::Created };
+    config.database().update_job(&job).await?;
+    record_status_change(config.as_ref(), &job, old_status, job.status.clone(), None).await;
+    tracing::info!("Retrying job with id {:?} from {:?}", id, job.status);
+
+    if failed_in_verification {
+        let job_handler = factory::get_job_handler(&job.job_type).await;
+        add_job_to_verification_queue(&job, &job_backoff(&**job_handler), 0).await?;
+    } else {
+        add_job_to_process_queue(&job).await?;
+    }
+
+    Ok(())
+}
+
+/// Cancels a job which isn't completed nor being processed or verified, e.g. a job created for
+/// the wrong blocks. The messages of the job still queued are consumed without processing it. The
+/// workers don't create the job again: the blocks it covered are left to the operator.
+pub async fn cancel_job(id: Uuid) -> Result<()> {
+    let config = config().await;
+    let mut job = get_job(id).await?;
+    let old_status = job.status.clone();
+
+    if !old_status.is_cancellable() {
+        tracing::error!("Invalid status {:?} for job with id {:?}. Cannot cancel.", old_status, id);
+        return Err(eyre!("Invalid status {:?} for job with id {:?}. Cannot cancel.", old_status, id));
+    }
+
+    job.status = JobStatus::Cancelled;
+    config.database().update_job(&job).await?;
+    record_status_change(config.as_ref(), &job, old_status, JobStatus::Cancelled, None).await;
+    tracing::info!(job_id = %job.id, "Cancelled job");
+
+    Ok(())
+}
+
+/// Appends the status transition of the job to its history. The history is there for the
+/// investigations, failing to append to it doesn't fail the transition.
+pub async fn record_status_change(
+    config: &Config,
+    job: &JobItem,
+    old_status: JobStatus,
+    new_status: JobStatus,
+    error: Option<String>,
+) {
+    let event = status_change_event(job, old_status, new_status, error);
+    if let Err(e) = config.database().append_job_event(event).await {
+        tracing::error!("Failed to append to the history of job {:?}: {}", job.id, e);
+    }
+}
+
+/// Event of the history of the job for its status transition, attributed to this orchestrator
+pub fn status_change_event(
+    job: &JobItem,
+    old_status: JobStatus,
+    new_status: JobStatus,
+    error: Option<String>,
+) -> JobEvent {
+    let event = JobEvent::new(job, old_status, new_status, worker_id());
+    match error {
+        Some(error) => event.with_error(error),
+        None => event,
+    }
+}
+
+/// Backoff of the verification polls and the processing retries of the jobs of the handler
+fn job_backoff(job_handler: &dyn Job) -> JobBackoff {
+    JobBackoff {
+        initial_delay_seconds: job_handler.verification_polling_delay_seconds(),
+        max_delay_seconds: job_handler.max_backoff_seconds(),
+    }
+}
+
+/// Identifier of this orchestrator instance, see [`ENV_ORCHESTRATOR_WORKER_ID`]
+pub fn worker_id() -> String {
+    std::env::var(ENV_ORCHESTRATOR_WORKER_ID)
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| format!("pid-{}", std::process::id()))
+}
+
 async fn get_job(id: Uuid) -> Result<JobItem> {
     let config = config().await;
     let job = config.database().get_job_by_id(id).await?;
     match job {
-        Some(job) => Ok(job),
+        Some(job) => {
+            record_job_fields(&job);
+            Ok(job)
+        }
         None => {
-            log::error!("Failed to find job with id {:?}", id);
+            tracing::error!("Failed to find job with id {:?}", id);
             Err(eyre!("Failed to process job with id {:?}", id))
         }
     }
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use prover_client_interface::{Task, TaskStatus};
+use uuid::Uuid;
+
+use super::attempts::job_type_attempts;
+use super::constants::JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY;
+use super::errors::JobError;
+use super::types::{JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
+use super::Job;
+use crate::config::Config;
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::database::types::now_secs;
+use crate::jobs::idempotency::{submit_once, PROVER_SUBMISSION_KEY};
+
+/// Combines the proofs of consecutive blocks into a single recursive proof, so that a single
+/// proof is verified on the settlement layer for all of them. The internal id is the first block.
+pub struct ProofAggregationJob;
+
+#[async_trait]
+impl Job for ProofAggregationJob {
+    async fn create_job(
+        &self,
+        _config: &Config,
+        internal_id: String,
+        metadata: HashMap<String, String>,
+    ) -> Result<JobItem> {
+        let job = JobItem {
+            id: Uuid::new_v4(),
+            internal_id,
+            job_type: JobType::ProofAggregation,
+            status: JobStatus::Created,
+            external_id: String::new().into(),
+            metadata,
+            version: 0,
+            created_at: now_secs(),
+            updated_at: now_secs(),
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
+        };
+        let blocks = blocks_to_aggregate(&job)?;
+        if blocks.first().map(|block_no| block_no.to_string()) != Some(job.internal_id.clone()) {
+            return Err(eyre!("Blocks to aggregate must start at block #{}", job.internal_id));
+        }
+        Ok(job)
+    }
+
+    /// Submits the proofs of the blocks, stored by their proving jobs, to the prover
+    async fn process_job(&self, config: &Config, job: &mut JobItem) -> Result<String> {
+        let mut proofs = Vec::new();
+        for block_no in blocks_to_aggregate(job)? {
+            let key = StorageKey::new(ArtifactKind::Proof, block_no).to_string();
+            proofs.push(config.storage().get_data(&key).await?.to_vec());
+        }
+        submit_once(config, job, PROVER_SUBMISSION_KEY, move || async move {
+            Ok(config.prover_client().submit_task(Task::AggregateProofs(proofs)).await.map_err(JobError::from)?)
+        })
+        .await
+    }
+
+    /// The job is verified once the task succeeded. The aggregated proof isn't stored, nothing
+    /// consumes it yet: the prover keeps it with the task.
+    async fn verify_job(&self, config: &Config, job: &mut JobItem) -> Result<JobVerificationStatus> {
+        let task_id: String = job.external_id.unwrap_string()?.into();
+        match config.prover_client().get_task_status(&task_id).await? {
+            TaskStatus::Processing => Ok(JobVerificationStatus::Pending),
+            TaskStatus::Succeeded => Ok(JobVerificationStatus::Verified),
+            TaskStatus::Failed(err) => {
+                tracing::error!(job_id = %job.id, block = %job.internal_id, "Proof aggregation failed: {}", err);
+                Ok(JobVerificationStatus::Rejected(format!(
+                    "Proof aggregation job #{} failed with error: {}",
+                    job.internal_id, err
+                )))
+            }
+        }
+    }
+
+    fn max_process_attempts(&self) -> u64 {
+        job_type_attempts(&JobType::ProofAggregation).max_process_attempts
+    }
+
+    fn max_verification_attempts(&self) -> u64 {
+        job_type_attempts(&JobType::ProofAggregation).max_verification_attempts
+    }
+
+    fn verification_polling_delay_seconds(&self) -> u64 {
+        job_type_attempts(&JobType::ProofAggregation).verification_polling_delay_seconds
+    }
+
+    fn max_backoff_seconds(&self) -> u64 {
+        600
+    }
+}
+
+/// Blocks whose proofs are aggregated by the job, they must be consecutive
+pub fn blocks_to_aggregate(job: &JobItem) -> Result<Vec<u64>> {
+    let blocks = job
+        .metadata
+        .get(JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY)
+        .ok_or_else(|| eyre!("Blocks to aggregate are not specified (proof aggregation job #{})", job.internal_id))?
+        .replace(' ', "")
+        .split(',')
+        .map(|block_no| block_no.parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()?;
+    if blocks.len() < 2 || blocks.windows(2).any(|pair| pair[1] != pair[0] + 1) {
+        return Err(eyre!(
+            "Blocks to aggregate must be at least two consecutive blocks (proof aggregation job #{})",
+            job.internal_id
+        ));
+    }
+    Ok(blocks)
+}
@@ -2,19 +2,31 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use alloy::primitives::B256;
 use async_trait::async_trait;
 use cairo_vm::vm::runners::cairo_pie::CairoPie;
 use color_eyre::eyre::eyre;
 use color_eyre::Result;
+use gps_fact_checker::fact_info::get_fact_info;
 use prover_client_interface::{Task, TaskStatus};
-use tracing::log::log;
-use tracing::log::Level::Error;
+use utils::env_utils::get_env_var_or_default;
 use uuid::Uuid;
 
-use super::constants::JOB_METADATA_CAIRO_PIE_PATH_KEY;
-use super::types::{JobItem, JobStatus, JobType, JobVerificationStatus};
+use super::attempts::job_type_attempts;
+use super::constants::{JOB_METADATA_CAIRO_PIE_KEY, JOB_METADATA_CAIRO_PIE_PATH_KEY, JOB_METADATA_PROOF_FACT_KEY};
+use super::errors::JobError;
+use super::types::{JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
 use super::Job;
 use crate::config::Config;
+use crate::data_storage::cairo_pie::{cairo_pie_key, fetch_cairo_pie};
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::database::types::now_secs;
+use crate::jobs::idempotency::{submit_once, PROVER_SUBMISSION_KEY};
+
+/// Whether the fact of a proof is checked on the settlement layer before the proving job is
+/// completed, off by default. Only turn it on with a prover registering the proofs onchain and a
+/// settlement layer with a fact registry, i.e. Ethereum with `ETHEREUM_FACT_REGISTRY_ADDRESS`.
+pub const ENV_PROOF_FACT_VERIFICATION: &str = "PROOF_FACT_VERIFICATION";
 
 pub struct ProvingJob;
 
@@ -26,9 +38,6 @@ impl Job for ProvingJob {
         internal_id: String,
         metadata: HashMap<String, String>,
     ) -> Result<JobItem> {
-        if !metadata.contains_key(JOB_METADATA_CAIRO_PIE_PATH_KEY) {
-            return Err(eyre!("Cairo PIE path is not specified (prover job #{})", internal_id));
-        }
         Ok(JobItem {
             id: Uuid::new_v4(),
             internal_id,
@@ -37,29 +46,60 @@ impl Job for ProvingJob {
             external_id: String::new().into(),
             metadata,
             version: 0,
+            created_at: now_secs(),
+            updated_at: now_secs(),
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
         })
     }
 
     async fn process_job(&self, config: &Config, job: &mut JobItem) -> Result<String> {
-        // TODO: allow to download PIE from storage
-        let cairo_pie_path: PathBuf = job
-            .metadata
-            .get(JOB_METADATA_CAIRO_PIE_PATH_KEY)
-            .map(|s| PathBuf::from_str(s))
-            .ok_or_else(|| eyre!("Cairo PIE path is not specified (prover job #{})", job.internal_id))??;
-        let cairo_pie = CairoPie::read_zip_file(&cairo_pie_path)
-            .expect("Not able to read the cairo PIE file from the zip file provided.");
-        let external_id = config.prover_client().submit_task(Task::CairoPie(cairo_pie)).await?;
-        Ok(external_id)
+        // a local PIE takes precedence over the one stored by the SNOS job
+        let cairo_pie = match job.metadata.get(JOB_METADATA_CAIRO_PIE_PATH_KEY) {
+            Some(cairo_pie_path) => CairoPie::read_zip_file(&PathBuf::from_str(cairo_pie_path)?).map_err(|e| {
+                eyre!("Failed to read the Cairo PIE {} (prover job #{}): {}", cairo_pie_path, job.internal_id, e)
+            })?,
+            None => {
+                let key = job
+                    .metadata
+                    .get(JOB_METADATA_CAIRO_PIE_KEY)
+                    .cloned()
+                    .unwrap_or_else(|| cairo_pie_key(&job.internal_id));
+                fetch_cairo_pie(config.storage(), &key).await?
+            }
+        };
+        let fact_info = get_fact_info(&cairo_pie, None)?;
+        job.metadata.insert(JOB_METADATA_PROOF_FACT_KEY.to_string(), fact_info.fact.to_string());
+        submit_once(config, job, PROVER_SUBMISSION_KEY, move || async move {
+            Ok(config.prover_client().submit_task(Task::CairoPie(cairo_pie)).await.map_err(JobError::from)?)
+        })
+        .await
     }
 
+    /// Once the task succeeded, the fact of the proof must be registered on the settlement layer.
+    /// The proof is then stored under the proof key of the block when the prover hands it out.
     async fn verify_job(&self, config: &Config, job: &mut JobItem) -> Result<JobVerificationStatus> {
         let task_id: String = job.external_id.unwrap_string()?.into();
         match config.prover_client().get_task_status(&task_id).await? {
             TaskStatus::Processing => Ok(JobVerificationStatus::Pending),
-            TaskStatus::Succeeded => Ok(JobVerificationStatus::Verified),
+            TaskStatus::Succeeded => {
+                if let Some(fact) = fact_to_verify(config, job)? {
+                    if !config.settlement_client().verify_fact(fact.0).await? {
+                        return Ok(JobVerificationStatus::Rejected(format!(
+                            "Fact {} of prover job #{} is not registered on the settlement layer",
+                            fact, job.internal_id
+                        )));
+                    }
+                }
+                if let Some(proof) = config.prover_client().get_proof(&task_id).await? {
+                    let key = StorageKey::new(ArtifactKind::Proof, &job.internal_id).to_string();
+                    config.storage().put_data(proof.into(), &key).await?;
+                }
+                Ok(JobVerificationStatus::Verified)
+            }
             TaskStatus::Failed(err) => {
-                log!(Error, "Prover job #{} failed: {}", job.internal_id, err);
+                tracing::error!(job_id = %job.id, block = %job.internal_id, "Proving failed: {}", err);
                 Ok(JobVerificationStatus::Rejected(format!(
                     "Prover job #{} failed with error: {}",
                     job.internal_id, err
@@ -69,14 +109,35 @@ impl Job for ProvingJob {
     }
 
     fn max_process_attempts(&self) -> u64 {
-        1
+        job_type_attempts(&JobType::ProofCreation).max_process_attempts
     }
 
     fn max_verification_attempts(&self) -> u64 {
-        1
+        job_type_attempts(&JobType::ProofCreation).max_verification_attempts
     }
 
     fn verification_polling_delay_seconds(&self) -> u64 {
-        60
+        job_type_attempts(&JobType::ProofCreation).verification_polling_delay_seconds
     }
+
+    fn max_backoff_seconds(&self) -> u64 {
+        900
+    }
+}
+
+/// Fact of the proof to check on the settlement layer, `None` when the verification isn't turned on,
+/// the prover doesn't register the facts (e.g. the mock prover) or the job was processed before the
+/// facts were recorded
+fn fact_to_verify(config: &Config, job: &JobItem) -> Result<Option<B256>> {
+    if get_env_var_or_default(ENV_PROOF_FACT_VERIFICATION, "false") != "true"
+        || !config.prover_client().registers_facts()
+    {
+        return Ok(None);
+    }
+    job.metadata
+        .get(JOB_METADATA_PROOF_FACT_KEY)
+        .map(|fact| {
+            B256::from_str(fact).map_err(|e| eyre!("Invalid fact {} of prover job #{}: {}", fact, job.internal_id, e))
+        })
+        .transpose()
 }
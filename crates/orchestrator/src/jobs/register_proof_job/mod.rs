@@ -5,7 +5,9 @@ use color_eyre::Result;
 use uuid::Uuid;
 
 use crate::config::Config;
-use crate::jobs::types::{JobItem, JobStatus, JobType, JobVerificationStatus};
+use crate::database::types::now_secs;
+use crate::jobs::attempts::job_type_attempts;
+use crate::jobs::types::{JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
 use crate::jobs::Job;
 
 pub struct RegisterProofJob;
@@ -28,6 +30,11 @@ impl Job for RegisterProofJob {
             // this will allow state update jobs to be created for each block
             metadata,
             version: 0,
+            created_at: now_secs(),
+            updated_at: now_secs(),
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
         })
     }
 
@@ -44,14 +51,18 @@ impl Job for RegisterProofJob {
     }
 
     fn max_process_attempts(&self) -> u64 {
-        todo!()
+        job_type_attempts(&JobType::ProofRegistration).max_process_attempts
     }
 
     fn max_verification_attempts(&self) -> u64 {
-        todo!()
+        job_type_attempts(&JobType::ProofRegistration).max_verification_attempts
     }
 
     fn verification_polling_delay_seconds(&self) -> u64 {
-        todo!()
+        job_type_attempts(&JobType::ProofRegistration).verification_polling_delay_seconds
+    }
+
+    fn max_backoff_seconds(&self) -> u64 {
+        600
     }
 }
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use cairo_vm::Felt252;
+use snos::io::output::StarknetOsOutput;
+use starknet::core::types::{FieldElement, StateUpdate};
+
+/// Number of differences listed in a report, the others are only counted
+const MAX_REPORTED_DIFFERENCES: usize = 50;
+
+/// Cross-checks the output of SNOS against the state update the node returns for the block and
+/// returns the differences, empty when they agree.
+///
+/// The state roots are always compared. The contract and class changes are only compared when
+/// the DA is done in calldata, with blobs the OS output doesn't carry them.
+pub fn diff_snos_output(block_no: u64, snos_output: &StarknetOsOutput, state_update: &StateUpdate) -> Vec<String> {
+    let mut differences = Vec::new();
+    let mut compare = |what: String, snos: Felt252, node: Felt252| {
+        if snos != node {
+            differences.push(format!("{}: {:#x} in the SNOS output, {:#x} in the state update", what, snos, node));
+        }
+    };
+
+    compare("block number".to_string(), snos_output.block_number, Felt252::from(block_no));
+    compare("old root".to_string(), snos_output.initial_root, to_felt(&state_update.old_root));
+    compare("new root".to_string(), snos_output.final_root, to_felt(&state_update.new_root));
+    if snos_output.use_kzg_da != Felt252::ZERO {
+        return differences;
+    }
+
+    let state_diff = &state_update.state_diff;
+    let contracts: HashMap<Felt252, _> =
+        snos_output.contracts.iter().map(|contract| (contract.addr, contract)).collect();
+    for storage_diff in &state_diff.storage_diffs {
+        let address = to_felt(&storage_diff.address);
+        for entry in &storage_diff.storage_entries {
+            let snos_value = contracts
+                .get(&address)
+                .and_then(|contract| contract.storage_changes.get(&to_felt(&entry.key)))
+                .copied()
+                .unwrap_or(Felt252::ZERO);
+            compare(format!("contract {:#x} storage key {:#x}", address, entry.key), snos_value, to_felt(&entry.value));
+        }
+    }
+    for nonce in &state_diff.nonces {
+        let address = to_felt(&nonce.contract_address);
+        let snos_nonce = contracts.get(&address).map_or(Felt252::ZERO, |contract| contract.nonce);
+        compare(format!("contract {:#x} nonce", address), snos_nonce, to_felt(&nonce.nonce));
+    }
+    let class_updates = state_diff
+        .deployed_contracts
+        .iter()
+        .map(|item| (item.address, item.class_hash))
+        .chain(state_diff.replaced_classes.iter().map(|item| (item.contract_address, item.class_hash)));
+    for (address, class_hash) in class_updates {
+        let address = to_felt(&address);
+        let snos_class_hash =
+            contracts.get(&address).and_then(|contract| contract.class_hash).unwrap_or(Felt252::ZERO);
+        compare(format!("contract {:#x} class hash", address), snos_class_hash, to_felt(&class_hash));
+    }
+    for declared_class in &state_diff.declared_classes {
+        let class_hash = to_felt(&declared_class.class_hash);
+        let snos_compiled_class_hash = snos_output.classes.get(&class_hash).copied().unwrap_or(Felt252::ZERO);
+        compare(
+            format!("class {:#x} compiled class hash", class_hash),
+            snos_compiled_class_hash,
+            to_felt(&declared_class.compiled_class_hash),
+        );
+    }
+    if snos_output.classes.len() != state_diff.declared_classes.len() {
+        differences.push(format!(
+            "declared classes: {} in the SNOS output, {} in the state update",
+            snos_output.classes.len(),
+            state_diff.declared_classes.len()
+        ));
+    }
+
+    differences
+}
+
+/// Report of the differences found by [`diff_snos_output`], one per line
+pub fn diff_report(block_no: u64, differences: &[String]) -> String {
+    let mut report = format!("SNOS output of block {} doesn't match the state update:", block_no);
+    for difference in differences.iter().take(MAX_REPORTED_DIFFERENCES) {
+        report.push_str("\n- ");
+        report.push_str(difference);
+    }
+    if differences.len() > MAX_REPORTED_DIFFERENCES {
+        report.push_str(&format!("\n- and {} more", differences.len() - MAX_REPORTED_DIFFERENCES));
+    }
+    report
+}
+
+fn to_felt(field_element: &FieldElement) -> Felt252 {
+    Felt252::from_bytes_be(&field_element.to_bytes_be())
+}
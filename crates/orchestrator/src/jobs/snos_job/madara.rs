@@ -0,0 +1,60 @@
+use blockifier::transaction::objects::TransactionExecutionInfo;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::value::RawValue;
+use snos::io::input::StarknetOsInput;
+use utils::{build_http_client, trace_id_header};
+
+/// JSON-RPC method of Madara returning the input of SNOS for a block
+pub const GET_SNOS_INPUT_METHOD: &str = "madara_getSnosInput";
+
+/// Input of SNOS for a block, as returned by Madara
+#[derive(Debug, Deserialize)]
+pub struct SnosInput {
+    pub os_input: StarknetOsInput,
+    /// Execution of the transactions of the block, replayed by the OS
+    pub tx_execution_infos: Vec<TransactionExecutionInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+/// Fetches the input of SNOS for the block from the Madara node at `rpc_url`, as the raw JSON of
+/// a [`SnosInput`] which is only parsed by the SNOS run
+pub async fn fetch_snos_input(rpc_url: &str, block_number: u64) -> Result<Vec<u8>> {
+    let http_client = build_http_client!(reqwest)?;
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": GET_SNOS_INPUT_METHOD,
+        "params": [block_number],
+    });
+    let response: JsonRpcResponse<Box<RawValue>> = trace_id_header!(http_client.post(rpc_url).json(&body))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    match (response.result, response.error) {
+        (Some(snos_input), _) => Ok(snos_input.get().as_bytes().to_vec()),
+        (None, Some(error)) => Err(eyre!(
+            "{} failed for block {}: {} (code {})",
+            GET_SNOS_INPUT_METHOD,
+            block_number,
+            error.message,
+            error.code
+        )),
+        (None, None) => Err(eyre!("{} returned no input for block {}", GET_SNOS_INPUT_METHOD, block_number)),
+    }
+}
@@ -1,13 +1,44 @@
+pub mod consistency;
+pub mod madara;
+pub mod os_program;
+pub mod pool;
+pub mod sandbox;
+
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use cairo_vm::types::layout_name::LayoutName;
+use cairo_vm::vm::runners::cairo_pie::CairoPie;
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
+use gps_fact_checker::fact_info::get_program_output;
+use snos::execution::helper::ExecutionHelperWrapper;
+use snos::io::output::StarknetOsOutput;
+use starknet::core::types::{BlockId, MaybePendingStateUpdate};
+use starknet::providers::Provider;
+use utils::env_utils::get_env_var_or_panic;
 use uuid::Uuid;
 
 use crate::config::Config;
-use crate::jobs::types::{JobItem, JobStatus, JobType, JobVerificationStatus};
+use crate::data_storage::cairo_pie::{cairo_pie_key, store_cairo_pie};
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::database::types::now_secs;
+use crate::jobs::attempts::job_type_attempts;
+use crate::jobs::block_range::BlockRange;
+use crate::jobs::checkpoint::{checkpoint, save_checkpoint};
+use crate::jobs::constants::{JOB_METADATA_CAIRO_PIE_KEY, JOB_METADATA_SNOS_PROGRAM_HASH_KEY};
+use crate::jobs::snos_job::consistency::{diff_report, diff_snos_output};
+use crate::jobs::snos_job::madara::{fetch_snos_input, SnosInput};
+use crate::jobs::snos_job::os_program::{os_program_for_block, split_at_os_upgrades};
+use crate::jobs::snos_job::pool::SNOS_POOL;
+use crate::jobs::snos_job::sandbox::{run_snos_sandboxed, snos_runner_binary, SnosLimits, SnosRunFailure};
+use crate::jobs::types::{JobBlockedError, JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
 use crate::jobs::Job;
 
+/// Path of the compiled Starknet OS program, as built by the SNOS repository, running every block
+/// when no versions are configured, see [`os_program::ENV_SNOS_OS_PROGRAMS`]
+pub const ENV_SNOS_COMPILED_OS_PATH: &str = "SNOS_COMPILED_OS_PATH";
+
 pub struct SnosJob;
 
 #[async_trait]
@@ -26,31 +57,145 @@ impl Job for SnosJob {
             external_id: String::new().into(),
             metadata,
             version: 0,
+            created_at: now_secs(),
+            updated_at: now_secs(),
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
         })
     }
 
-    async fn process_job(&self, _config: &Config, _job: &mut JobItem) -> Result<String> {
-        // 1. Fetch SNOS input data from Madara
-        // 2. Import SNOS in Rust and execute it with the input data
-        // 3. Store the received PIE in DB
-        todo!()
+    /// Runs SNOS on the input Madara gives for each block of the job, with the OS program of the
+    /// block. The PIEs are stored for the proving jobs and the OS outputs for the state update
+    /// jobs, the key of the PIE is recorded in the metadata when the job runs a single block. The
+    /// hash of the program, when configured, is recorded in the metadata to be checked against
+    /// the core contract at settlement time. The blocks of a job must run the same program, the
+    /// SNOS worker splits the ranges at the OS upgrades.
+    ///
+    /// The last block whose PIE and OS output are stored is checkpointed, a retry resumes after it.
+    async fn process_job(&self, config: &Config, job: &mut JobItem) -> Result<String> {
+        let range: BlockRange = job.internal_id.parse()?;
+        if split_at_os_upgrades(range)?.len() > 1 {
+            return Err(JobBlockedError(format!(
+                "The blocks {} run different OS programs, they must be run by separate jobs",
+                range
+            ))
+            .into());
+        }
+        let program_hash = os_program_for_block(range.start)?.program_hash;
+
+        let last_stored_block = checkpoint(job).map(|block_number| block_number.parse::<u64>()).transpose()?;
+        for block_number in range.blocks() {
+            if last_stored_block.is_some_and(|last_stored_block| block_number <= last_stored_block) {
+                tracing::info!(block = block_number, "Reusing the SNOS run stored by a previous attempt");
+                continue;
+            }
+            run_block(config, block_number).await?;
+            save_checkpoint(config, job, &block_number.to_string()).await?;
+        }
+
+        if range.is_single_block() {
+            job.metadata.insert(JOB_METADATA_CAIRO_PIE_KEY.to_string(), cairo_pie_key(&job.internal_id));
+        }
+        if let Some(program_hash) = program_hash {
+            job.metadata.insert(JOB_METADATA_SNOS_PROGRAM_HASH_KEY.to_string(), program_hash);
+        }
+        Ok(String::new())
     }
 
+    /// SNOS runs within the processing, there is nothing to wait for
     async fn verify_job(&self, _config: &Config, _job: &mut JobItem) -> Result<JobVerificationStatus> {
-        // No need for verification as of now. If we later on decide to outsource SNOS run
-        // to another servicehow a, verify_job can be used to poll on the status of the job
-        todo!()
+        Ok(JobVerificationStatus::Verified)
     }
 
     fn max_process_attempts(&self) -> u64 {
-        todo!()
+        job_type_attempts(&JobType::SnosRun).max_process_attempts
     }
 
     fn max_verification_attempts(&self) -> u64 {
-        todo!()
+        job_type_attempts(&JobType::SnosRun).max_verification_attempts
     }
 
     fn verification_polling_delay_seconds(&self) -> u64 {
-        todo!()
+        job_type_attempts(&JobType::SnosRun).verification_polling_delay_seconds
+    }
+
+    fn max_backoff_seconds(&self) -> u64 {
+        30
+    }
+}
+
+/// Runs SNOS on the block with its OS program, stores its PIE and its OS output
+async fn run_block(config: &Config, block_number: u64) -> Result<()> {
+    let snos_input = pinned_snos_input(config, block_number).await?;
+    let os_program = os_program_for_block(block_number)?;
+
+    // the OS runs for minutes on big blocks, in a child process of the pool
+    let (runner_binary, limits) = (snos_runner_binary()?, SnosLimits::from_env());
+    let run = SNOS_POOL.run(run_snos_sandboxed(&runner_binary, &os_program.path, &snos_input, &limits)).await?;
+    let (cairo_pie, snos_output) = match run {
+        Ok(run) => run,
+        Err(failure) => {
+            store_run_logs(config, &block_number.to_string(), &failure).await?;
+            return Err(eyre!("SNOS run of block {} failed: {}", block_number, failure));
+        }
+    };
+    validate_snos_output(config, block_number, &snos_output).await?;
+
+    store_cairo_pie(config.storage(), &block_number.to_string(), &cairo_pie).await?;
+    let snos_output_key = StorageKey::new(ArtifactKind::SnosOutput, block_number).to_string();
+    config.storage().put_data(serde_json::to_vec(&snos_output)?.into(), &snos_output_key).await?;
+    // the core contract takes the raw program output, e.g. to read the KZG point of the blobs
+    let program_output = get_program_output(&cairo_pie)?;
+    let program_output_key = StorageKey::new(ArtifactKind::ProgramOutput, block_number).to_string();
+    config.storage().put_data(serde_json::to_vec(&program_output)?.into(), &program_output_key).await?;
+    Ok(())
+}
+
+/// Input of SNOS for the block, fetched from Madara on the first attempt and stored so that the
+/// retries run on the exact same input even if the node state moved in between
+pub async fn pinned_snos_input(config: &Config, block_number: u64) -> Result<Vec<u8>> {
+    let key = StorageKey::new(ArtifactKind::SnosInput, block_number).to_string();
+    if config.storage().data_exists(&key).await? {
+        tracing::info!(block = block_number, "Reusing the SNOS input stored by a previous attempt");
+        return Ok(config.storage().get_data(&key).await?.to_vec());
+    }
+    let snos_input = fetch_snos_input(&get_env_var_or_panic("MADARA_RPC_URL"), block_number).await?;
+    config.storage().put_data(snos_input.clone().into(), &key).await?;
+    Ok(snos_input)
+}
+
+/// Checks the SNOS output against the state update of the node before anything is built on it, a
+/// divergence means Madara and SNOS don't agree on the protocol and the job gets blocked.
+async fn validate_snos_output(config: &Config, block_number: u64, snos_output: &StarknetOsOutput) -> Result<()> {
+    let state_update = match config.starknet_client().get_state_update(BlockId::Number(block_number)).await? {
+        MaybePendingStateUpdate::Update(state_update) => state_update,
+        MaybePendingStateUpdate::PendingUpdate(_) => {
+            return Err(eyre!("Block {} is still pending, its SNOS output can't be checked", block_number));
+        }
+    };
+    let differences = diff_snos_output(block_number, snos_output, &state_update);
+    if !differences.is_empty() {
+        return Err(JobBlockedError(diff_report(block_number, &differences)).into());
     }
+    Ok(())
+}
+
+/// Stores what the failed SNOS run printed, for the block to be investigated
+async fn store_run_logs(config: &Config, block_number: &str, failure: &SnosRunFailure) -> Result<()> {
+    let stdout_key = StorageKey::new(ArtifactKind::SnosStdout, block_number).to_string();
+    config.storage().put_data(failure.stdout.clone().into(), &stdout_key).await?;
+    let stderr_key = StorageKey::new(ArtifactKind::SnosStderr, block_number).to_string();
+    config.storage().put_data(failure.stderr.clone().into(), &stderr_key).await
+}
+
+/// Runs the Starknet OS on the input of a block, returns the PIE of the run along with the output
+/// of the OS. Only called in the child process, see [`sandbox`].
+fn run_snos(compiled_os: Vec<u8>, snos_input: SnosInput) -> Result<(CairoPie, StarknetOsOutput)> {
+    let SnosInput { os_input, tx_execution_infos } = snos_input;
+    let block_context = os_input.general_config.empty_block_context();
+    let execution_helper = ExecutionHelperWrapper::new(tx_execution_infos, &block_context);
+    let (cairo_pie, snos_output) =
+        snos::run_os(compiled_os, LayoutName::all_cairo, os_input, block_context, execution_helper)?;
+    Ok((cairo_pie, snos_output))
 }
@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use starknet::core::types::FieldElement;
+use utils::env_utils::get_env_var_or_panic;
+
+use crate::jobs::block_range::BlockRange;
+use crate::jobs::snos_job::ENV_SNOS_COMPILED_OS_PATH;
+
+/// Versions of the Starknet OS program the blocks run with, across the protocol upgrades, as
+/// `<first block>:<program hash>:<path>` entries separated by commas. Each program runs the blocks
+/// from its first block to the first block of the next one. When unset, every block runs with
+/// the program at `SNOS_COMPILED_OS_PATH` and no program hash is recorded.
+pub const ENV_SNOS_OS_PROGRAMS: &str = "SNOS_OS_PROGRAMS";
+
+/// Compiled Starknet OS program running the blocks from `first_block` onwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OsProgram {
+    pub first_block: u64,
+    /// Hash of the program, hex encoded, which the core contract must accept at settlement time
+    pub program_hash: Option<String>,
+    pub path: PathBuf,
+}
+
+/// Parses the value of `SNOS_OS_PROGRAMS`, the programs are returned by increasing first block
+pub fn parse_os_programs(os_programs: &str) -> Result<Vec<OsProgram>> {
+    let mut programs = os_programs
+        .split(',')
+        .map(|entry| {
+            let parts: Vec<&str> = entry.trim().splitn(3, ':').collect();
+            let [first_block, program_hash, path] = parts[..] else {
+                return Err(eyre!("OS program {} isn't formatted as <first block>:<program hash>:<path>", entry));
+            };
+            let program_hash = FieldElement::from_hex_be(program_hash)
+                .map_err(|e| eyre!("Invalid hash of the OS program {}: {}", entry, e))?;
+            Ok(OsProgram {
+                first_block: first_block.parse().map_err(|e| eyre!("Invalid first block of {}: {}", entry, e))?,
+                program_hash: Some(format!("{:#x}", program_hash)),
+                path: PathBuf::from(path),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    programs.sort_by_key(|program| program.first_block);
+    if programs.windows(2).any(|pair| pair[0].first_block == pair[1].first_block) {
+        return Err(eyre!("Several OS programs start at the same block"));
+    }
+    Ok(programs)
+}
+
+/// OS program the block runs with, as configured through the environment
+pub fn os_program_for_block(block_number: u64) -> Result<OsProgram> {
+    match std::env::var(ENV_SNOS_OS_PROGRAMS) {
+        Ok(os_programs) => select_os_program(&parse_os_programs(&os_programs)?, block_number),
+        Err(_) => Ok(OsProgram {
+            first_block: 0,
+            program_hash: None,
+            path: PathBuf::from(get_env_var_or_panic(ENV_SNOS_COMPILED_OS_PATH)),
+        }),
+    }
+}
+
+/// Last of the programs, sorted by first block, starting at or before the block
+pub fn select_os_program(programs: &[OsProgram], block_number: u64) -> Result<OsProgram> {
+    programs
+        .iter()
+        .rev()
+        .find(|program| program.first_block <= block_number)
+        .cloned()
+        .ok_or_else(|| eyre!("No OS program configured for block {}", block_number))
+}
+
+/// Splits the range at the blocks from which another OS program runs, as configured through the
+/// environment: the blocks of a SNOS job must all run the same program
+pub fn split_at_os_upgrades(range: BlockRange) -> Result<Vec<BlockRange>> {
+    match std::env::var(ENV_SNOS_OS_PROGRAMS) {
+        Ok(os_programs) => split_at_program_starts(&parse_os_programs(&os_programs)?, range),
+        Err(_) => Ok(vec![range]),
+    }
+}
+
+/// Splits the range at the first blocks of the programs, sorted by first block
+pub fn split_at_program_starts(programs: &[OsProgram], range: BlockRange) -> Result<Vec<BlockRange>> {
+    let mut ranges = Vec::new();
+    let mut start = range.start;
+    let upgrades = programs.iter().map(|program| program.first_block);
+    for first_block in upgrades.filter(|first_block| (range.start + 1..=range.end).contains(first_block)) {
+        ranges.push(BlockRange::new(start, first_block - 1)?);
+        start = first_block;
+    }
+    ranges.push(BlockRange::new(start, range.end)?);
+    Ok(ranges)
+}
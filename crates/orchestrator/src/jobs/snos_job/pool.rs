@@ -0,0 +1,49 @@
+use std::future::Future;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use prometheus::{register_int_gauge, IntGauge};
+use tokio::sync::Semaphore;
+use utils::env_utils::get_env_var_or_default;
+
+/// Number of SNOS runs executing at the same time, the other jobs wait for a free slot
+pub const ENV_SNOS_MAX_CONCURRENT_RUNS: &str = "SNOS_MAX_CONCURRENT_RUNS";
+
+lazy_static! {
+    /// Pool shared by all the SNOS jobs of the process
+    pub static ref SNOS_POOL: SnosPool = SnosPool::new(
+        get_env_var_or_default(ENV_SNOS_MAX_CONCURRENT_RUNS, "1")
+            .parse()
+            .expect("Failed to parse SNOS_MAX_CONCURRENT_RUNS")
+    );
+    /// SNOS runs waiting for a free slot of the pool
+    pub static ref SNOS_RUNS_QUEUED: IntGauge =
+        register_int_gauge!("snos_runs_queued", "SNOS runs waiting for a free slot of the pool").unwrap();
+}
+
+/// Bounds the number of SNOS runs executing at the same time, a run takes gigabytes of memory on
+/// big blocks. The runs waiting for a slot are queued and start in the order they arrived.
+pub struct SnosPool {
+    slots: Semaphore,
+}
+
+impl SnosPool {
+    pub fn new(max_concurrent_runs: usize) -> Self {
+        assert!(max_concurrent_runs > 0, "SNOS_MAX_CONCURRENT_RUNS must be at least 1");
+        Self { slots: Semaphore::new(max_concurrent_runs) }
+    }
+
+    /// Runs `run` once a slot is free, the slot is held until `run` completes
+    pub async fn run<F, T>(&self, run: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        SNOS_RUNS_QUEUED.inc();
+        let slot = self.slots.acquire().await;
+        SNOS_RUNS_QUEUED.dec();
+        let _slot = slot.map_err(|e| eyre!("SNOS pool is closed: {}", e))?;
+
+        run.await
+    }
+}
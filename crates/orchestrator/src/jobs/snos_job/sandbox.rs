@@ -0,0 +1,167 @@
+//! SNOS runs in a child process of the orchestrator, with memory, CPU and time limits, so that a
+//! pathological block fails its own job instead of taking the whole orchestrator down.
+//!
+//! The child is the orchestrator binary itself started with the `snos-run <dir> <compiled os>`
+//! subcommand: it reads the input of the run from the directory, runs the given OS program on it
+//! and writes the PIE and the OS output back to the directory.
+
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use cairo_vm::vm::runners::cairo_pie::CairoPie;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use snos::io::output::StarknetOsOutput;
+use tokio::process::Command;
+use utils::env_utils::get_env_var_or_default;
+
+use crate::jobs::snos_job::madara::SnosInput;
+use crate::jobs::snos_job::run_snos;
+
+/// Subcommand of the orchestrator binary running SNOS in the directory given as argument
+pub const SNOS_RUN_SUBCOMMAND: &str = "snos-run";
+/// Binary started for the SNOS runs, the orchestrator binary itself by default
+pub const ENV_SNOS_RUNNER_BINARY: &str = "SNOS_RUNNER_BINARY";
+/// Address space of a SNOS run, 0 for no limit
+pub const ENV_SNOS_MEMORY_LIMIT_BYTES: &str = "SNOS_MEMORY_LIMIT_BYTES";
+/// CPU time of a SNOS run, 0 for no limit
+pub const ENV_SNOS_CPU_LIMIT_SECS: &str = "SNOS_CPU_LIMIT_SECS";
+/// Wall clock time of a SNOS run, the child is killed past it
+pub const ENV_SNOS_TIMEOUT_SECS: &str = "SNOS_TIMEOUT_SECS";
+
+const INPUT_FILE_NAME: &str = "snos_input.json";
+const PIE_FILE_NAME: &str = "cairo_pie.zip";
+const OUTPUT_FILE_NAME: &str = "snos_output.json";
+
+/// Resource limits of the SNOS runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnosLimits {
+    pub memory_bytes: u64,
+    pub cpu_secs: u64,
+    pub timeout: Duration,
+}
+
+impl SnosLimits {
+    /// Limits from the environment: 16 GiB of memory and an hour of CPU and wall clock time by
+    /// default
+    pub fn from_env() -> Self {
+        let parse = |name: &str, default: &str| -> u64 {
+            get_env_var_or_default(name, default).parse().unwrap_or_else(|_| panic!("Failed to parse {}", name))
+        };
+        Self {
+            memory_bytes: parse(ENV_SNOS_MEMORY_LIMIT_BYTES, "17179869184"),
+            cpu_secs: parse(ENV_SNOS_CPU_LIMIT_SECS, "3600"),
+            timeout: Duration::from_secs(parse(ENV_SNOS_TIMEOUT_SECS, "3600")),
+        }
+    }
+}
+
+/// SNOS run which didn't produce a PIE, along with what the child printed
+#[derive(Debug, thiserror::Error)]
+#[error("{reason}")]
+pub struct SnosRunFailure {
+    pub reason: String,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Binary started for the SNOS runs, as configured through the environment
+pub fn snos_runner_binary() -> Result<PathBuf> {
+    match std::env::var(ENV_SNOS_RUNNER_BINARY) {
+        Ok(runner_binary) => Ok(PathBuf::from(runner_binary)),
+        Err(_) => Ok(std::env::current_exe()?),
+    }
+}
+
+/// Runs the OS program at `compiled_os` on the raw input of `madara_getSnosInput` in a child
+/// process within the limits. The child is `runner_binary` started with `snos-run <dir> <compiled os>`.
+pub async fn run_snos_sandboxed(
+    runner_binary: &Path,
+    compiled_os: &Path,
+    snos_input: &[u8],
+    limits: &SnosLimits,
+) -> Result<Result<(CairoPie, StarknetOsOutput), SnosRunFailure>> {
+    let run_dir = tempfile::TempDir::new()?;
+    tokio::fs::write(run_dir.path().join(INPUT_FILE_NAME), snos_input).await?;
+
+    let mut command = Command::new(runner_binary);
+    command
+        .arg(SNOS_RUN_SUBCOMMAND)
+        .arg(run_dir.path())
+        .arg(compiled_os)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let (memory_bytes, cpu_secs) = (limits.memory_bytes, limits.cpu_secs);
+    // SAFETY: only async-signal-safe calls between the fork and the exec
+    unsafe {
+        command.pre_exec(move || {
+            let set_limit = |resource, limit: u64| {
+                let rlimit = libc::rlimit { rlim_cur: limit as libc::rlim_t, rlim_max: limit as libc::rlim_t };
+                if limit > 0 && libc::setrlimit(resource, &rlimit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            };
+            set_limit(libc::RLIMIT_AS, memory_bytes)?;
+            set_limit(libc::RLIMIT_CPU, cpu_secs)
+        });
+    }
+
+    // the child is killed when its output isn't awaited anymore
+    let output = match tokio::time::timeout(limits.timeout, command.spawn()?.wait_with_output()).await {
+        Ok(output) => output?,
+        Err(_) => {
+            return Ok(Err(SnosRunFailure {
+                reason: format!("SNOS run timed out after {}s", limits.timeout.as_secs()),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            }));
+        }
+    };
+    if !output.status.success() {
+        let reason = match output.status.signal() {
+            Some(libc::SIGXCPU) => "SNOS run exceeded its CPU time limit".to_string(),
+            Some(signal) => format!("SNOS run was killed by signal {}", signal),
+            None => format!("SNOS run exited with {}", output.status),
+        };
+        return Ok(Err(SnosRunFailure { reason, stdout: output.stdout, stderr: output.stderr }));
+    }
+
+    let cairo_pie = CairoPie::read_zip_file(&run_dir.path().join(PIE_FILE_NAME))
+        .map_err(|e| eyre!("Failed to read the PIE of the SNOS run: {}", e))?;
+    let snos_output = serde_json::from_slice(&tokio::fs::read(run_dir.path().join(OUTPUT_FILE_NAME)).await?)?;
+    Ok(Ok((cairo_pie, snos_output)))
+}
+
+/// Entry point of the child process: runs SNOS when the orchestrator is started with the
+/// `snos-run` subcommand and returns the exit code of the run, `None` otherwise.
+pub fn run_snos_subcommand() -> Option<i32> {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some(SNOS_RUN_SUBCOMMAND) {
+        return None;
+    }
+    let (Some(run_dir), Some(compiled_os)) = (args.next(), args.next()) else {
+        eprintln!("Usage: orchestrator {} <run directory> <compiled os>", SNOS_RUN_SUBCOMMAND);
+        return Some(2);
+    };
+    match run_snos_in_dir(Path::new(&run_dir), Path::new(&compiled_os)) {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("SNOS run failed: {:?}", e);
+            Some(1)
+        }
+    }
+}
+
+fn run_snos_in_dir(run_dir: &Path, compiled_os: &Path) -> Result<()> {
+    let snos_input: SnosInput = serde_json::from_slice(&std::fs::read(run_dir.join(INPUT_FILE_NAME))?)?;
+    let compiled_os = std::fs::read(compiled_os)?;
+    let (cairo_pie, snos_output) = run_snos(compiled_os, snos_input)?;
+    cairo_pie.write_zip_file(&run_dir.join(PIE_FILE_NAME))?;
+    std::fs::write(run_dir.join(OUTPUT_FILE_NAME), serde_json::to_vec(&snos_output)?)?;
+    Ok(())
+}
@@ -1,8 +1,10 @@
 pub mod utils;
 
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use ::utils::collections::{has_dup, is_sorted};
+use ::utils::env_utils::get_env_var_or_default;
 use async_trait::async_trait;
 use cairo_vm::Felt252;
 use color_eyre::eyre::eyre;
@@ -10,20 +12,32 @@ use color_eyre::Result;
 use snos::io::output::StarknetOsOutput;
 use uuid::Uuid;
 
-use settlement_client_interface::SettlementVerificationStatus;
+use settlement_client_interface::{SettlementClient, SettlementOperation, SettlementVerificationStatus};
 
 use super::constants::{
     JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX, JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO,
-    JOB_PROCESS_ATTEMPT_METADATA_KEY,
+    JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX, JOB_METADATA_STATE_UPDATE_SENT_AT_PREFIX,
 };
 
 use crate::config::{config, Config};
-use crate::constants::SNOS_OUTPUT_FILE_NAME;
-use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY;
-use crate::jobs::state_update_job::utils::fetch_blob_data_for_block;
-use crate::jobs::types::{JobItem, JobStatus, JobType, JobVerificationStatus};
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::database::types::now_secs;
+use crate::jobs::attempts::job_type_attempts;
+use crate::jobs::constants::{JOB_METADATA_SNOS_PROGRAM_HASH_KEY, JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY};
+use crate::jobs::costs::ensure_blocks_within_budget;
+use crate::jobs::state_update_job::utils::{
+    fetch_blob_data_for_block, fetch_onchain_data_for_block, fetch_program_output_for_block,
+};
+use crate::jobs::types::{JobBlockedError, JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
 use crate::jobs::Job;
 
+/// Time a settlement transaction can stay unmined before it is replaced with bumped fees.
+pub const ENV_SETTLEMENT_TX_STUCK_TIMEOUT_SECS: &str = "SETTLEMENT_TX_STUCK_TIMEOUT_SECS";
+const DEFAULT_SETTLEMENT_TX_STUCK_TIMEOUT_SECS: &str = "600";
+/// When `true`, the blocks of a job with DA in calldata are settled in a single transaction, see
+/// [`SettlementClient::settle_batch`].
+pub const ENV_SETTLEMENT_BATCH_STATE_UPDATES: &str = "SETTLEMENT_BATCH_STATE_UPDATES";
+
 pub struct StateUpdateJob;
 #[async_trait]
 impl Job for StateUpdateJob {
@@ -43,37 +57,75 @@ impl Job for StateUpdateJob {
             // we don't do one job per state update as that makes nonce management complicated
             metadata,
             version: 0,
+            created_at: now_secs(),
+            updated_at: now_secs(),
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
         })
     }
 
     async fn process_job(&self, config: &Config, job: &mut JobItem) -> Result<String> {
-        let attempt_no =
-            job.metadata.get(JOB_PROCESS_ATTEMPT_METADATA_KEY).expect("Could not find current attempt number.").clone();
+        let attempt_no = job.counters.process_attempts;
 
         // Read the metadata to get the blocks for which state update will be performed.
         // We assume that blocks nbrs are formatted as follow: "2,3,4,5,6".
         let mut block_numbers = self.get_block_numbers_from_metadata(job)?;
-        self.validate_block_numbers(config, &block_numbers).await?;
 
-        // If we had a block state update failing last run, we recover from this block
+        // If we had a block state update failing last run, or reorged out, we recover from this block
         if let Some(last_failed_block) = job.metadata.get(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO) {
             let last_failed_block: u64 =
                 last_failed_block.parse().expect("last_failed_block should be a positive number");
             block_numbers = block_numbers.into_iter().filter(|&block| block >= last_failed_block).collect::<Vec<u64>>();
         }
+        self.validate_block_numbers(config, &block_numbers).await?;
+        self.validate_program_hash(config, &block_numbers).await?;
 
-        let mut sent_tx_hashes: Vec<String> = Vec::with_capacity(block_numbers.len());
+        ensure_blocks_within_budget(config, &block_numbers).await?;
+
+        let mut snos_outputs = Vec::with_capacity(block_numbers.len());
         for block_no in block_numbers.iter() {
-            let snos = self.fetch_snos_for_block(*block_no).await;
+            snos_outputs.push(self.fetch_snos_for_block(*block_no).await);
+        }
+        self.validate_state_roots(config, &block_numbers, &snos_outputs).await.map_err(|e| {
+            job.metadata.insert(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO.into(), block_numbers[0].to_string());
+            self.insert_attempts_into_metadata(job, attempt_no, &[]);
+            e
+        })?;
+
+        let settle_in_batch = get_env_var_or_default(ENV_SETTLEMENT_BATCH_STATE_UPDATES, "false") == "true"
+            && block_numbers.len() > 1
+            && snos_outputs.iter().all(|snos| snos.use_kzg_da == Felt252::ZERO);
+        if settle_in_batch {
+            let first_block = block_numbers[0];
+            let tx_hash = self.update_state_for_blocks_in_batch(config, &block_numbers).await.map_err(|e| {
+                job.metadata.insert(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO.into(), first_block.to_string());
+                self.insert_attempts_into_metadata(job, attempt_no, &[]);
+                let message =
+                    format!("Block #{first_block} onwards - Error occured during the batched state update: {e}");
+                e.wrap_err(message)
+            })?;
+            record_tx_sent_at(job, &tx_hash);
+            // every block is settled by the batch transaction
+            let sent_tx_hashes = vec![tx_hash; block_numbers.len()];
+            self.insert_attempts_into_metadata(job, attempt_no, &sent_tx_hashes);
+            return Ok(block_numbers.last().expect("Block numbers list should not be empty.").to_string());
+        }
+
+        let mut sent_tx_hashes: Vec<String> = Vec::with_capacity(block_numbers.len());
+        for (block_no, snos) in block_numbers.iter().zip(snos_outputs) {
             let tx_hash = self.update_state_for_block(config, *block_no, snos).await.map_err(|e| {
                 job.metadata.insert(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO.into(), block_no.to_string());
-                self.insert_attempts_into_metadata(job, &attempt_no, &sent_tx_hashes);
-                eyre!("Block #{block_no} - Error occured during the state update: {e}")
+                self.insert_attempts_into_metadata(job, attempt_no, &sent_tx_hashes);
+                // wrapping keeps the error downcastable, e.g. to hold the job back on high fees
+                let message = format!("Block #{block_no} - Error occured during the state update: {e}");
+                e.wrap_err(message)
             })?;
+            record_tx_sent_at(job, &tx_hash);
             sent_tx_hashes.push(tx_hash);
         }
 
-        self.insert_attempts_into_metadata(job, &attempt_no, &sent_tx_hashes);
+        self.insert_attempts_into_metadata(job, attempt_no, &sent_tx_hashes);
 
         // external_id returned corresponds to the last block number settled
         Ok(block_numbers.last().expect("Last number in block_numbers array returned as None. Possible Error : Delay in job processing or Failed job execution.").to_string())
@@ -83,22 +135,31 @@ impl Job for StateUpdateJob {
     /// Status will be verified if:
     /// 1. the last settlement tx hash is successful,
     /// 2. the expected last settled block from our configuration is indeed the one found in the provider.
+    ///
+    /// A settlement tx which stays unmined past `SETTLEMENT_TX_STUCK_TIMEOUT_SECS` is replaced with
+    /// bumped fees when the settlement client supports it, the replacements are tracked in the
+    /// metadata and the first one mined wins.
     async fn verify_job(&self, config: &Config, job: &mut JobItem) -> Result<JobVerificationStatus> {
-        let attempt_no =
-            job.metadata.get(JOB_PROCESS_ATTEMPT_METADATA_KEY).expect("Could not find current attempt number.").clone();
+        let attempt_no = last_process_attempt(job);
         let metadata_tx_hashes = job
             .metadata
             .get(&format!("{}{}", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX, attempt_no))
-            .expect("Could not find tx hashes metadata for the current attempt")
-            .clone()
+            .ok_or_else(|| eyre!("No tx hashes recorded for the attempt {} of job #{}", attempt_no, job.internal_id))?
             .replace(' ', "");
 
         let tx_hashes: Vec<&str> = metadata_tx_hashes.split(',').collect();
         let block_numbers = self.get_block_numbers_from_metadata(job)?;
         let settlement_client = config.settlement_client();
+        let stuck_timeout = Duration::from_secs(
+            get_env_var_or_default(ENV_SETTLEMENT_TX_STUCK_TIMEOUT_SECS, DEFAULT_SETTLEMENT_TX_STUCK_TIMEOUT_SECS)
+                .parse()?,
+        );
 
         for (tx_hash, block_no) in tx_hashes.iter().zip(block_numbers.iter()) {
-            let tx_inclusion_status = settlement_client.verify_tx_inclusion(tx_hash).await?;
+            let replacements_key = format!("{}{}", JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX, tx_hash);
+            let mut candidate_tx_hashes = candidate_tx_hashes(job, tx_hash);
+
+            let tx_inclusion_status = self.verify_candidates_inclusion(settlement_client, &candidate_tx_hashes).await?;
             match tx_inclusion_status {
                 SettlementVerificationStatus::Rejected(_) => {
                     job.metadata.insert(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO.into(), block_no.to_string());
@@ -106,17 +167,41 @@ impl Job for StateUpdateJob {
                 }
                 // If the tx is still pending, we wait for it to be finalized and check again the status.
                 SettlementVerificationStatus::Pending => {
-                    settlement_client.wait_for_tx_finality(tx_hash).await?;
-                    let new_status = settlement_client.verify_tx_inclusion(tx_hash).await?;
+                    // the latest replacement is the one competing for the nonce
+                    let latest_tx_hash =
+                        candidate_tx_hashes.last().expect("Candidate tx hashes are never empty").clone();
+                    let finality = match time_before_stuck(job, &latest_tx_hash, stuck_timeout) {
+                        Some(remaining) => {
+                            tokio::time::timeout(remaining, settlement_client.wait_for_tx_finality(&latest_tx_hash))
+                                .await
+                                .ok()
+                        }
+                        None => None,
+                    };
+                    match finality {
+                        Some(result) => result?,
+                        None => {
+                            if settlement_client.supports_tx_replacement() {
+                                self.replace_stuck_tx(
+                                    settlement_client,
+                                    job,
+                                    &replacements_key,
+                                    &mut candidate_tx_hashes,
+                                )
+                                .await;
+                            }
+                            return Ok(JobVerificationStatus::Pending);
+                        }
+                    }
+                    let new_status = self.verify_candidates_inclusion(settlement_client, &candidate_tx_hashes).await?;
                     match new_status {
                         SettlementVerificationStatus::Rejected(_) => {
                             job.metadata
                                 .insert(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO.into(), block_no.to_string());
                             return Ok(new_status.into());
                         }
-                        SettlementVerificationStatus::Pending => {
-                            return Err(eyre!("Tx {tx_hash} should not be pending."))
-                        }
+                        // the settlement client doesn't wait for txs it doesn't execute, e.g. Safe transactions
+                        SettlementVerificationStatus::Pending => return Ok(JobVerificationStatus::Pending),
                         SettlementVerificationStatus::Verified => {}
                     }
                 }
@@ -138,15 +223,19 @@ impl Job for StateUpdateJob {
     }
 
     fn max_process_attempts(&self) -> u64 {
-        1
+        job_type_attempts(&JobType::StateTransition).max_process_attempts
     }
 
     fn max_verification_attempts(&self) -> u64 {
-        10
+        job_type_attempts(&JobType::StateTransition).max_verification_attempts
     }
 
     fn verification_polling_delay_seconds(&self) -> u64 {
-        60
+        job_type_attempts(&JobType::StateTransition).verification_polling_delay_seconds
+    }
+
+    fn max_backoff_seconds(&self) -> u64 {
+        600
     }
 }
 
@@ -159,6 +248,26 @@ impl StateUpdateJob {
         self.parse_block_numbers(blocks_to_settle)
     }
 
+    /// Settlement txs which may have settled each block of the job: the tx sent by the latest
+    /// process attempt and the txs which replaced it with bumped fees. The latest attempt resumed
+    /// from the block which failed last, the blocks settled by the previous attempts are omitted.
+    pub fn settlement_tx_candidates(&self, job: &JobItem) -> Result<Vec<(u64, Vec<String>)>> {
+        let attempt_no = last_process_attempt(job);
+        let tx_hashes = job
+            .metadata
+            .get(&format!("{}{}", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX, attempt_no))
+            .ok_or_else(|| eyre!("No tx hashes recorded for the attempt {} of job #{}", attempt_no, job.internal_id))?
+            .replace(' ', "");
+        let block_numbers = self.get_block_numbers_from_metadata(job)?;
+        Ok(block_numbers
+            .into_iter()
+            .rev()
+            .zip(tx_hashes.split(',').filter(|tx_hash| !tx_hash.is_empty()).rev())
+            .map(|(block_no, tx_hash)| (block_no, candidate_tx_hashes(job, tx_hash)))
+            .rev()
+            .collect())
+    }
+
     /// Parse a list of blocks comma separated
     fn parse_block_numbers(&self, blocks_to_settle: &str) -> Result<Vec<u64>> {
         let sanitized_blocks = blocks_to_settle.replace(' ', "");
@@ -189,36 +298,269 @@ impl StateUpdateJob {
         Ok(())
     }
 
+    /// Validate that the blocks were run with the OS program the core contract accepts, as
+    /// recorded by their SNOS jobs. A protocol upgrade of the core contract which isn't matched by
+    /// the OS programs configured, or the reverse, would get every update rejected, so the job
+    /// gets blocked instead.
+    async fn validate_program_hash(&self, config: &Config, block_numbers: &[u64]) -> Result<()> {
+        // only fetched once a block with a recorded program hash is found
+        let mut core_program_hash = None;
+        for block_no in block_numbers {
+            let snos_job = snos_job_of_block(config, *block_no).await?;
+            // the blocks run without versioned OS programs have nothing to check
+            let recorded_hash = snos_job.and_then(|job| job.metadata.get(JOB_METADATA_SNOS_PROGRAM_HASH_KEY).cloned());
+            let Some(program_hash) = recorded_hash else {
+                continue;
+            };
+            let settled_program_hash = match core_program_hash {
+                Some(hash) => hash,
+                None => {
+                    let hash = Felt252::from_bytes_be(&config.settlement_client().get_program_hash().await?);
+                    *core_program_hash.insert(hash)
+                }
+            };
+            let program_hash =
+                Felt252::from_hex(&program_hash).map_err(|e| eyre!("Invalid program hash {program_hash}: {e}"))?;
+            if program_hash != settled_program_hash {
+                return Err(JobBlockedError(format!(
+                    "Block #{} - OS program mismatch: the block was run with the program {:#x} but core contract \
+                     programHash() is {:#x}",
+                    block_no, program_hash, settled_program_hash
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate that the program outputs of the blocks build on top of the state root currently
+    /// stored in the core contract. Only the first block is checked against the core contract, the
+    /// root doesn't move until its transaction is mined, the next ones must build on the new root of
+    /// the block before them. Submitting them otherwise would revert and mask a divergence between
+    /// the orchestrator and the settlement layer, so the job gets blocked instead.
+    async fn validate_state_roots(
+        &self,
+        config: &Config,
+        block_numbers: &[u64],
+        snos_outputs: &[StarknetOsOutput],
+    ) -> Result<()> {
+        let settled_root = Felt252::from_bytes_be(&config.settlement_client().get_state_root().await?);
+        if snos_outputs[0].initial_root != settled_root {
+            return Err(JobBlockedError(format!(
+                "Block #{} - State root divergence: program output old root is {:#x} but core contract stateRoot() \
+                 is {:#x}",
+                block_numbers[0], snos_outputs[0].initial_root, settled_root
+            ))
+            .into());
+        }
+        for (block_no, snos) in block_numbers.iter().skip(1).zip(snos_outputs.windows(2)) {
+            let (previous, current) = (&snos[0], &snos[1]);
+            if current.initial_root != previous.final_root {
+                return Err(JobBlockedError(format!(
+                    "Block #{} - State root divergence: program output old root is {:#x} but the new root of the \
+                     previous block is {:#x}",
+                    block_no, current.initial_root, previous.final_root
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
     /// Update the state for the corresponding block using the settlement layer.
     async fn update_state_for_block(&self, config: &Config, block_no: u64, snos: StarknetOsOutput) -> Result<String> {
         let settlement_client = config.settlement_client();
+        let program_output = fetch_program_output_for_block(config, block_no).await?;
         let last_tx_hash_executed = if snos.use_kzg_da == Felt252::ZERO {
-            unimplemented!("update_state_for_block not implemented as of now for calldata DA.")
+            let (onchain_data_hash, onchain_data_size) = fetch_onchain_data_for_block(config, block_no).await?;
+
+            settlement_client.update_state_calldata(program_output, onchain_data_hash, onchain_data_size).await?
         } else if snos.use_kzg_da == Felt252::ONE {
             let blob_data = fetch_blob_data_for_block(block_no).await?;
 
             // Sending update_state transaction from the settlement client
-            settlement_client.update_state_with_blobs(vec![], blob_data).await?
+            settlement_client.update_state_with_blobs(program_output, blob_data).await?
         } else {
             return Err(eyre!("Block #{} - SNOS error, [use_kzg_da] should be either 0 or 1.", block_no));
         };
         Ok(last_tx_hash_executed)
     }
 
+    /// Update the state for all the blocks, whose DA is done in calldata, in a single transaction.
+    async fn update_state_for_blocks_in_batch(&self, config: &Config, block_numbers: &[u64]) -> Result<String> {
+        let mut operations = Vec::with_capacity(block_numbers.len());
+        for block_no in block_numbers {
+            let program_output = fetch_program_output_for_block(config, *block_no).await?;
+            let (onchain_data_hash, onchain_data_size) = fetch_onchain_data_for_block(config, *block_no).await?;
+            operations.push(SettlementOperation::UpdateStateCalldata {
+                program_output,
+                onchain_data_hash,
+                onchain_data_size,
+            });
+        }
+        config.settlement_client().settle_batch(operations).await
+    }
+
     /// Retrieves the SNOS output for the corresponding block.
     async fn fetch_snos_for_block(&self, block_no: u64) -> StarknetOsOutput {
         let config = config().await;
         let storage_client = config.storage();
-        let key = block_no.to_string() + "/" + SNOS_OUTPUT_FILE_NAME;
+        let key = StorageKey::new(ArtifactKind::SnosOutput, block_no).to_string();
         let snos_output_bytes = storage_client.get_data(&key).await.expect("Unable to fetch snos output for block");
         serde_json::from_slice(snos_output_bytes.iter().as_slice())
             .expect("Unable to convert the data into snos output")
     }
 
+    /// Checks that the last settlement tx of a completed job is still included in the settlement
+    /// layer. Returns the first block of the job to settle again when the tx was reorged out, the
+    /// blocks settled by the txs which survived the reorg are skipped.
+    pub async fn find_reorged_block(&self, config: &Config, job: &JobItem) -> Result<Option<u64>> {
+        let Some(last_tx_hash) = last_sent_tx_hash(job) else {
+            return Ok(None);
+        };
+        let settlement_client = config.settlement_client();
+        match self.verify_candidates_inclusion(settlement_client, &candidate_tx_hashes(job, &last_tx_hash)).await? {
+            // a tx back in the mempool after a reorg is mined again, nothing to do
+            SettlementVerificationStatus::Verified | SettlementVerificationStatus::Pending => return Ok(None),
+            SettlementVerificationStatus::Rejected(_) => {}
+        }
+        let last_settled_block = settlement_client.get_last_settled_block().await?;
+        let block_numbers = self.get_block_numbers_from_metadata(job)?;
+        Ok(block_numbers.into_iter().find(|block_no| *block_no > last_settled_block))
+    }
+
+    /// Returns the inclusion status of a settlement tx and its replacements: verified as soon as
+    /// one of them is, pending while one of them can still be mined, else rejected.
+    async fn verify_candidates_inclusion(
+        &self,
+        settlement_client: &dyn SettlementClient,
+        candidate_tx_hashes: &[String],
+    ) -> Result<SettlementVerificationStatus> {
+        let mut status = None;
+        for tx_hash in candidate_tx_hashes {
+            match settlement_client.verify_tx_inclusion(tx_hash).await? {
+                SettlementVerificationStatus::Verified => return Ok(SettlementVerificationStatus::Verified),
+                SettlementVerificationStatus::Pending => status = Some(SettlementVerificationStatus::Pending),
+                rejected @ SettlementVerificationStatus::Rejected(_) => {
+                    if status != Some(SettlementVerificationStatus::Pending) {
+                        status = Some(rejected);
+                    }
+                }
+            }
+        }
+        Ok(status.expect("Candidate tx hashes are never empty"))
+    }
+
+    /// Replaces the latest of the candidate txs, which stayed unmined past the timeout, with a tx
+    /// with bumped fees and records it in the metadata. A failure to replace it is not fatal, the
+    /// stuck tx may still be mined.
+    async fn replace_stuck_tx(
+        &self,
+        settlement_client: &dyn SettlementClient,
+        job: &mut JobItem,
+        replacements_key: &str,
+        candidate_tx_hashes: &mut Vec<String>,
+    ) {
+        let stuck_tx_hash = candidate_tx_hashes.last().expect("Candidate tx hashes are never empty").clone();
+        match settlement_client.resubmit_tx_with_bumped_fees(&stuck_tx_hash).await {
+            Ok(replacement_tx_hash) => {
+                tracing::warn!(
+                    job_id = %job.id,
+                    "Settlement tx {} is stuck, replaced by tx {} with bumped fees",
+                    stuck_tx_hash,
+                    replacement_tx_hash
+                );
+                record_tx_sent_at(job, &replacement_tx_hash);
+                candidate_tx_hashes.push(replacement_tx_hash);
+                job.metadata.insert(replacements_key.to_string(), candidate_tx_hashes[1..].join(","));
+            }
+            Err(e) => {
+                tracing::warn!(
+                    job_id = %job.id,
+                    "Settlement tx {} is stuck and couldn't be replaced: {}",
+                    stuck_tx_hash,
+                    e
+                );
+            }
+        }
+    }
+
     /// Insert the tx hashes into the the metadata for the attempt number - will be used later by
     /// verify_job to make sure that all tx are successful.
-    fn insert_attempts_into_metadata(&self, job: &mut JobItem, attempt_no: &str, tx_hashes: &[String]) {
+    fn insert_attempts_into_metadata(&self, job: &mut JobItem, attempt_no: u64, tx_hashes: &[String]) {
         let new_attempt_metadata_key = format!("{}{}", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX, attempt_no);
         job.metadata.insert(new_attempt_metadata_key, tx_hashes.join(","));
     }
 }
+
+/// The settlement tx and the txs which replaced it with bumped fees, any of them can be mined.
+fn candidate_tx_hashes(job: &JobItem, tx_hash: &str) -> Vec<String> {
+    let replacements_key = format!("{}{}", JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX, tx_hash);
+    let mut candidate_tx_hashes = vec![tx_hash.to_string()];
+    if let Some(replacements) = job.metadata.get(&replacements_key) {
+        candidate_tx_hashes.extend(replacements.split(',').map(String::from));
+    }
+    candidate_tx_hashes
+}
+
+/// Attempt of the last processing of the job which went through. `process_job` records the tx
+/// hashes under the attempt before the counter of the process attempts is incremented.
+fn last_process_attempt(job: &JobItem) -> u64 {
+    job.counters.process_attempts.saturating_sub(1)
+}
+
+/// Hash of the last settlement tx sent by the job, the one settling its last block: the last tx
+/// of the latest process attempt which sent any.
+fn last_sent_tx_hash(job: &JobItem) -> Option<String> {
+    job.metadata
+        .iter()
+        .filter_map(|(key, tx_hashes)| {
+            let attempt_no: u64 = key.strip_prefix(JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX)?.parse().ok()?;
+            let last_tx_hash = tx_hashes.replace(' ', "").split(',').filter(|tx_hash| !tx_hash.is_empty()).last()?;
+            Some((attempt_no, last_tx_hash.to_string()))
+        })
+        .max_by_key(|(attempt_no, _)| *attempt_no)
+        .map(|(_, tx_hash)| tx_hash)
+}
+
+/// Records in the metadata when the settlement tx was sent.
+fn record_tx_sent_at(job: &mut JobItem, tx_hash: &str) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).expect("System time before unix epoch").as_secs();
+    job.metadata.insert(format!("{}{}", JOB_METADATA_STATE_UPDATE_SENT_AT_PREFIX, tx_hash), now.to_string());
+}
+
+/// Time left before the settlement tx is considered stuck, `None` if it already is. Txs sent
+/// before the sending time was recorded get the full timeout.
+fn time_before_stuck(job: &JobItem, tx_hash: &str, stuck_timeout: Duration) -> Option<Duration> {
+    let sent_at = job
+        .metadata
+        .get(&format!("{}{}", JOB_METADATA_STATE_UPDATE_SENT_AT_PREFIX, tx_hash))
+        .and_then(|sent_at| sent_at.parse::<u64>().ok());
+    let Some(sent_at) = sent_at else {
+        return Some(stuck_timeout);
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).expect("System time before unix epoch").as_secs();
+    stuck_timeout.checked_sub(Duration::from_secs(now.saturating_sub(sent_at))).filter(|remaining| !remaining.is_zero())
+}
+
+/// SNOS job which ran the block. The block has its own SNOS job unless it was run in a range, the
+/// SNOS job of the range is then the parent of the proving job of the block.
+async fn snos_job_of_block(config: &Config, block_no: u64) -> Result<Option<JobItem>> {
+    let internal_id = block_no.to_string();
+    if let Some(snos_job) = config.database().get_job_by_internal_id_and_type(&internal_id, &JobType::SnosRun).await? {
+        return Ok(Some(snos_job));
+    }
+    let Some(proving_job) =
+        config.database().get_job_by_internal_id_and_type(&internal_id, &JobType::ProofCreation).await?
+    else {
+        return Ok(None);
+    };
+    for parent_id in &proving_job.parent_ids {
+        if let Some(parent) = config.database().get_job_by_id(*parent_id).await? {
+            if parent.job_type == JobType::SnosRun {
+                return Ok(Some(parent));
+            }
+        }
+    }
+    Ok(None)
+}
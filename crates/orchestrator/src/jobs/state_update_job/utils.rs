@@ -1,21 +1,54 @@
-use crate::config::config;
-use crate::constants::BLOB_DATA_FILE_NAME;
+use crate::codec::{codec_for, CodecUsage};
+use crate::config::{config, Config};
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::jobs::da_job::state_update_to_blob_data;
+use cairo_vm::Felt252;
 use color_eyre::eyre::eyre;
+use starknet::core::crypto::compute_hash_on_elements;
+use starknet::core::types::{BlockId, FieldElement, MaybePendingStateUpdate};
+use starknet::providers::Provider;
 
 /// Fetching the blob data (stored in remote storage during DA job) for a particular block
 pub async fn fetch_blob_data_for_block(block_number: u64) -> color_eyre::Result<Vec<Vec<u8>>> {
     let config = config().await;
     let storage_client = config.storage();
-    let key = block_number.to_string() + "/" + BLOB_DATA_FILE_NAME;
-    let blob_data = storage_client.get_data(&key).await?;
+    let key = StorageKey::new(ArtifactKind::BlobData, block_number).to_string();
+    let blob_data = codec_for(CodecUsage::StorageArtifact)?.decode(&storage_client.get_data(&key).await?)?;
     let blob_vec_data: Vec<Vec<u8>> =
         bincode::deserialize(&blob_data).expect("Not able to convert Vec<u8> to Vec<Vec<u8>> during deserialization.");
     Ok(blob_vec_data)
 }
 
+/// Fetching the program output of the Cairo run of SNOS (stored in remote storage during SNOS job)
+/// for a particular block, as expected by the core contract
+pub async fn fetch_program_output_for_block(config: &Config, block_number: u64) -> color_eyre::Result<Vec<[u8; 32]>> {
+    let key = StorageKey::new(ArtifactKind::ProgramOutput, block_number).to_string();
+    let program_output: Vec<Felt252> = serde_json::from_slice(&config.storage().get_data(&key).await?)?;
+    Ok(program_output.iter().map(|felt| felt.to_bytes_be()).collect())
+}
+
+/// Fetching the state diff of a block, encoded as in the DA job, and returning its hash and size
+/// as expected by the core contract when DA is done in calldata
+pub async fn fetch_onchain_data_for_block(config: &Config, block_number: u64) -> color_eyre::Result<([u8; 32], usize)> {
+    let state_update = match config.starknet_client().get_state_update(BlockId::Number(block_number)).await? {
+        MaybePendingStateUpdate::Update(state_update) => state_update,
+        MaybePendingStateUpdate::PendingUpdate(_) => {
+            return Err(eyre!("Cannot settle block {} as it's still in pending state", block_number));
+        }
+    };
+    let onchain_data = state_update_to_blob_data(block_number, state_update, config).await?;
+    Ok(onchain_data_hash_and_size(&onchain_data))
+}
+
 // Util Functions
 // ===============
 
+/// Util function to compute the on-chain data hash (pedersen hash chain) and size (number of
+/// field elements) of the data posted in calldata
+pub fn onchain_data_hash_and_size(onchain_data: &[FieldElement]) -> ([u8; 32], usize) {
+    (compute_hash_on_elements(onchain_data).to_bytes_be(), onchain_data.len())
+}
+
 /// Util function to convert hex string data into Vec<u8>
 pub fn hex_string_to_u8_vec(hex_str: &str) -> color_eyre::Result<Vec<u8>> {
     // Remove any spaces or non-hex characters from the input string
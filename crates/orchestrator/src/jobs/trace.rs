@@ -0,0 +1,54 @@
+//! Trace id of the jobs, to follow a block across its SNOS, proving, DA and settlement jobs. The
+//! trace id is attached to the job when it's created, a job created after its parents taking the
+//! one of its first parent. It's carried by the queue messages of the job and set while the job
+//! is handled: the log lines are emitted within a span holding it and the outbound HTTP requests
+//! carry it in the [`TRACE_ID_HEADER`](utils::trace::TRACE_ID_HEADER).
+
+use std::future::Future;
+
+use tracing::Instrument;
+use utils::trace::with_trace_id;
+use uuid::Uuid;
+
+use crate::jobs::constants::JOB_METADATA_TRACE_ID_KEY;
+use crate::jobs::types::JobItem;
+
+/// Attaches a trace id to the job being created, unless its metadata has one already
+pub fn assign_trace_id(job: &mut JobItem, parents: &[JobItem]) {
+    let parent_trace_id = parents.first().and_then(job_trace_id).map(str::to_string);
+    job.metadata
+        .entry(JOB_METADATA_TRACE_ID_KEY.to_string())
+        .or_insert_with(|| parent_trace_id.unwrap_or_else(|| Uuid::new_v4().to_string()));
+}
+
+/// Trace id of the job, absent for the jobs created before the trace ids were introduced
+pub fn job_trace_id(job: &JobItem) -> Option<&str> {
+    job.metadata.get(JOB_METADATA_TRACE_ID_KEY).map(String::as_str)
+}
+
+/// Runs the handling of the job within its trace. The type and the block of the job are filled in
+/// once the job is read, see [`record_job_fields`].
+pub async fn in_job_trace<F: Future>(job_id: Uuid, trace_id: Option<String>, future: F) -> F::Output {
+    let span = tracing::info_span!(
+        "job",
+        job_id = %job_id,
+        job_type = tracing::field::Empty,
+        block = tracing::field::Empty,
+        trace_id = tracing::field::Empty
+    );
+    match trace_id {
+        Some(trace_id) => {
+            span.record("trace_id", trace_id.as_str());
+            with_trace_id(trace_id, future).instrument(span).await
+        }
+        None => future.instrument(span).await,
+    }
+}
+
+/// Sets the type and the block, the internal id, of the job handled on the span of its trace, the
+/// log lines emitted while handling the job carry them as fields
+pub fn record_job_fields(job: &JobItem) {
+    let span = tracing::Span::current();
+    span.record("job_type", tracing::field::debug(&job.job_type));
+    span.record("block", job.internal_id.as_str());
+}
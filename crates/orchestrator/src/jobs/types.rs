@@ -77,10 +77,45 @@ pub enum JobType {
     DataSubmission,
     /// Getting a proof from the proving service
     ProofCreation,
+    /// Combining the proofs of consecutive blocks into a single recursive proof
+    ProofAggregation,
     /// Verifying the proof on the base layer
     ProofRegistration,
     /// Updaing the state root on the base layer
     StateTransition,
+    /// Tracking a message sent to the L2 through the core contract until it is consumed
+    MessageProcessing,
+    /// Checking the data settled on the base layer against the blocks once their state is updated
+    DataAudit,
+}
+
+impl JobType {
+    /// Every job type
+    pub const ALL: [JobType; 8] = [
+        JobType::SnosRun,
+        JobType::DataSubmission,
+        JobType::ProofCreation,
+        JobType::ProofAggregation,
+        JobType::ProofRegistration,
+        JobType::StateTransition,
+        JobType::MessageProcessing,
+        JobType::DataAudit,
+    ];
+
+    /// Types of the jobs a job of this type can depend on, the edges of the dependency graph of
+    /// the jobs. A job is only processed once the jobs it depends on are completed.
+    pub fn parent_job_types(&self) -> &'static [JobType] {
+        match self {
+            JobType::SnosRun => &[],
+            JobType::ProofCreation => &[JobType::SnosRun],
+            JobType::ProofAggregation => &[JobType::ProofCreation],
+            JobType::ProofRegistration => &[JobType::ProofCreation, JobType::ProofAggregation],
+            JobType::DataSubmission => &[JobType::ProofCreation],
+            JobType::StateTransition => &[JobType::ProofCreation, JobType::DataSubmission],
+            JobType::MessageProcessing => &[],
+            JobType::DataAudit => &[JobType::StateTransition],
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
@@ -99,6 +134,184 @@ pub enum JobStatus {
     VerificationTimeout,
     /// The job failed processing
     VerificationFailed,
+    /// The job can't be processed until an inconsistency it detected is investigated, e.g.
+    /// its inputs diverge from the state of the settlement layer. Needs manual intervention.
+    Blocked,
+    /// The settlement fees were above the configured caps when the job was processed, it is
+    /// processed again later rather than overpaying
+    FeeTooHigh,
+    /// The messages of the job were dead-lettered by the queue after failing repeatedly. Needs
+    /// manual intervention.
+    Failed,
+    /// The job waits for the jobs it depends on to be completed, it then goes to `Created`
+    PendingDependencies,
+    /// The job was cancelled by an operator, it isn't processed nor verified anymore
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Every job status
+    pub const ALL: [JobStatus; 11] = [
+        JobStatus::Created,
+        JobStatus::LockedForProcessing,
+        JobStatus::PendingVerification,
+        JobStatus::Completed,
+        JobStatus::VerificationTimeout,
+        JobStatus::VerificationFailed,
+        JobStatus::Blocked,
+        JobStatus::FeeTooHigh,
+        JobStatus::Failed,
+        JobStatus::PendingDependencies,
+        JobStatus::Cancelled,
+    ];
+
+    /// Whether a job may move from this status to `new_status`. The database rejects the updates
+    /// of the jobs which don't follow these transitions. Staying in the same status is always
+    /// allowed, e.g. to update the metadata.
+    pub fn can_transition_to(&self, new_status: &JobStatus) -> bool {
+        if self == new_status {
+            return true;
+        }
+        // the messages of any job not completed can be dead-lettered, and the permanent failures
+        // of the processing fail the job right away
+        if *new_status == JobStatus::Failed {
+            return !matches!(self, JobStatus::Completed | JobStatus::Cancelled);
+        }
+        // the jobs not being processed nor verified can be cancelled, see `cancel_job`
+        if *new_status == JobStatus::Cancelled {
+            return self.is_cancellable();
+        }
+        match self {
+            JobStatus::PendingDependencies => *new_status == JobStatus::Created,
+            JobStatus::Created | JobStatus::VerificationFailed | JobStatus::FeeTooHigh => {
+                *new_status == JobStatus::LockedForProcessing
+            }
+            // back to `Created` when recovered after the crash of the worker processing it
+            JobStatus::LockedForProcessing => matches!(
+                new_status,
+                JobStatus::PendingVerification
+                    | JobStatus::VerificationFailed
+                    | JobStatus::FeeTooHigh
+                    | JobStatus::Blocked
+                    | JobStatus::Created
+            ),
+            JobStatus::PendingVerification => matches!(
+                new_status,
+                JobStatus::Completed | JobStatus::VerificationFailed | JobStatus::VerificationTimeout
+            ),
+            // the settlement of the job was reorged out, it is settled again, see `ReorgMonitorWorker`
+            JobStatus::Completed => *new_status == JobStatus::Created,
+            // retried manually, see `retry_job`
+            JobStatus::VerificationTimeout => *new_status == JobStatus::PendingVerification,
+            JobStatus::Failed => matches!(new_status, JobStatus::PendingVerification | JobStatus::Created),
+            JobStatus::Blocked | JobStatus::Cancelled => false,
+        }
+    }
+
+    /// Whether a job in this status can be retried, see `retry_job`
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, JobStatus::VerificationTimeout | JobStatus::Failed)
+    }
+
+    /// Whether a job in this status can be cancelled: it isn't completed, nor in the hands of a
+    /// worker processing or verifying it
+    pub fn is_cancellable(&self) -> bool {
+        !matches!(
+            self,
+            JobStatus::LockedForProcessing
+                | JobStatus::PendingVerification
+                | JobStatus::Completed
+                | JobStatus::Cancelled
+        )
+    }
+
+    /// Statuses a job can move to this status from, this status included
+    pub fn predecessors(&self) -> Vec<JobStatus> {
+        JobStatus::ALL.into_iter().filter(|status| status.can_transition_to(self)).collect()
+    }
+}
+
+/// Error returned by the database when an update would move a job to a status it can't move to
+/// from its current one, see [`JobStatus::can_transition_to`]
+#[derive(thiserror::Error, Debug)]
+#[error("Illegal transition of job {id} from {from:?} to {to:?}")]
+pub struct IllegalStatusTransitionError {
+    pub id: Uuid,
+    pub from: JobStatus,
+    pub to: JobStatus,
+}
+
+/// Priority of a job. The high priority jobs are processed from their own queues, so that they
+/// don't wait behind the backlog of the routine jobs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum JobPriority {
+    /// The jobs created by the workers
+    #[default]
+    Normal,
+    /// The jobs an operator wants done right away, e.g. the settlement of a block
+    High,
+}
+
+impl JobPriority {
+    /// Every job priority
+    pub const ALL: [JobPriority; 2] = [JobPriority::Normal, JobPriority::High];
+}
+
+/// Counter of a job, incremented atomically by the database, see
+/// [`Database::increment_job_counter`](crate::database::Database::increment_job_counter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobCounter {
+    /// Processing attempts of the job which went through or failed, the timed out ones included
+    ProcessAttempts,
+    /// Verifications of the job which found it still pending
+    VerificationAttempts,
+    /// Processing attempts of the job which timed out
+    ProcessTimeouts,
+}
+
+impl JobCounter {
+    /// Every job counter
+    pub const ALL: [JobCounter; 3] =
+        [JobCounter::ProcessAttempts, JobCounter::VerificationAttempts, JobCounter::ProcessTimeouts];
+
+    /// Field of [`JobCounters`] the counter is stored in
+    pub fn field(&self) -> &'static str {
+        match self {
+            JobCounter::ProcessAttempts => "process_attempts",
+            JobCounter::VerificationAttempts => "verification_attempts",
+            JobCounter::ProcessTimeouts => "process_timeouts",
+        }
+    }
+}
+
+/// Counters of a job. They are only written through the database increments and resets, the
+/// updates of the job leave them as stored so that the concurrent increments aren't lost.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JobCounters {
+    #[serde(default)]
+    pub process_attempts: u64,
+    #[serde(default)]
+    pub verification_attempts: u64,
+    #[serde(default)]
+    pub process_timeouts: u64,
+}
+
+impl JobCounters {
+    pub fn get(&self, counter: JobCounter) -> u64 {
+        match counter {
+            JobCounter::ProcessAttempts => self.process_attempts,
+            JobCounter::VerificationAttempts => self.verification_attempts,
+            JobCounter::ProcessTimeouts => self.process_timeouts,
+        }
+    }
+
+    pub fn set(&mut self, counter: JobCounter, value: u64) {
+        match counter {
+            JobCounter::ProcessAttempts => self.process_attempts = value,
+            JobCounter::VerificationAttempts => self.verification_attempts = value,
+            JobCounter::ProcessTimeouts => self.process_timeouts = value,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -119,8 +332,46 @@ pub struct JobItem {
     pub metadata: HashMap<String, String>,
     /// helps to keep track of the version of the item for optimistic locking
     pub version: i32,
+    /// unix timestamp in seconds of the creation of the job, 0 for the jobs created before it was
+    /// recorded
+    #[serde(default)]
+    pub created_at: u64,
+    /// unix timestamp in seconds of the last update of the job, set by the database on every update
+    #[serde(default)]
+    pub updated_at: u64,
+    /// ids of the jobs this job depends on, it is only processed once they are all completed
+    #[serde(default, with = "uuid_1_vec_as_binary")]
+    pub parent_ids: Vec<Uuid>,
+    /// priority of the job, the queue it is processed from
+    #[serde(default)]
+    pub priority: JobPriority,
+    /// attempt counters of the job, see [`JobCounters`]
+    #[serde(default)]
+    pub counters: JobCounters,
 }
 
+/// Stores the uuids as binaries like [`uuid_1_as_binary`] does for a single one, so that the
+/// jobs can be queried by their parent ids
+mod uuid_1_vec_as_binary {
+    use mongodb::bson::Uuid as BsonUuid;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use uuid::Uuid;
+
+    pub fn serialize<S: Serializer>(ids: &[Uuid], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(ids.iter().map(|id| BsonUuid::from_uuid_1(*id)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Uuid>, D::Error> {
+        Ok(Vec::<BsonUuid>::deserialize(deserializer)?.into_iter().map(BsonUuid::to_uuid_1).collect())
+    }
+}
+
+/// Error returned by a job handler when the job must not be retried. The job is moved to the
+/// `Blocked` status and an alert is raised.
+#[derive(thiserror::Error, Debug)]
+#[error("{0}")]
+pub struct JobBlockedError(pub String);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum JobVerificationStatus {
     #[allow(dead_code)]
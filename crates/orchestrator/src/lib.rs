@@ -1,3 +1,9 @@
+/// Contains the alerting hooks used to notify the operators
+pub mod alerts;
+/// HTTP client of the AWS clients, going through the outbound proxy
+pub mod aws_http_client;
+/// Compression codecs of the DA payloads and storage artifacts
+pub mod codec;
 /// Config of the service. Contains configurations for DB, Queues and other services.
 pub mod config;
 pub mod constants;
@@ -8,10 +14,16 @@ pub mod controllers;
 pub mod data_storage;
 /// Contains the trait that all database clients must implement
 pub mod database;
+/// Descriptor of the deployment attached to metrics, logs and alerts
+pub mod deployment;
 /// Contains the trait that all jobs must implement. Also
 /// contains the root level functions for which detect the job
 /// type and call the corresponding job
 pub mod jobs;
+/// Log lines of the service, as text or JSON
+pub mod logging;
+/// Prometheus metrics of the service
+pub mod metrics;
 /// Contains the trait that all queues must implement
 pub mod queue;
 /// Contains the routes for the service
@@ -0,0 +1,66 @@
+use std::str::FromStr;
+
+use color_eyre::eyre::eyre;
+use tracing_subscriber::fmt::format::JsonFields;
+use tracing_subscriber::EnvFilter;
+use utils::env_utils::get_env_var_or_default;
+
+use crate::deployment::{DeploymentFormat, DeploymentJsonFormat};
+
+/// Format of the log lines, `text` by default or `json`
+pub const ENV_LOG_FORMAT: &str = "LOG_FORMAT";
+/// Levels of the log lines, per module, e.g. `info,orchestrator::queue=debug`, `info` by default
+pub const ENV_LOG_LEVEL: &str = "RUST_LOG";
+
+/// Format of the log lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// A line of text prefixed with the deployment, for humans
+    Text,
+    /// A JSON object per line with the fields of the event and of its spans, e.g. the id, the type
+    /// and the block of the job handled, for the log aggregation systems
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> color_eyre::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(eyre!("Unknown log format {}", s)),
+        }
+    }
+}
+
+/// Installs the subscriber emitting the log lines of the orchestrator, in the format of
+/// `LOG_FORMAT` and at the levels of `RUST_LOG`
+pub fn init_logging() {
+    let format: LogFormat = get_env_var_or_default(ENV_LOG_FORMAT, "text")
+        .parse()
+        .unwrap_or_else(|e| panic!("{}: {}", ENV_LOG_FORMAT, e));
+    let filter = EnvFilter::try_from_env(ENV_LOG_LEVEL).unwrap_or_else(|_| EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Text => {
+            subscriber.event_format(DeploymentFormat::new(tracing_subscriber::fmt::format().with_target(false))).init()
+        }
+        LogFormat::Json => {
+            let json = tracing_subscriber::fmt::format().json().flatten_event(true).with_span_list(false);
+            subscriber.fmt_fields(JsonFields::new()).event_format(DeploymentJsonFormat::new(json)).init()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("Text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+}
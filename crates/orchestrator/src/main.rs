@@ -1,23 +1,48 @@
 use dotenvy::dotenv;
 use orchestrator::config::config;
+use orchestrator::data_storage::key::migrate_flat_keys;
+use orchestrator::jobs::snos_job::sandbox::run_snos_subcommand;
+use orchestrator::logging::init_logging;
 use orchestrator::queue::init_consumers;
 use orchestrator::routes::app_router;
+use orchestrator::workers::audit::AuditWorker;
+use orchestrator::workers::balance_monitor::BalanceMonitorWorker;
+use orchestrator::workers::da_backfill::DaBackfillWorker;
 use orchestrator::workers::data_submission_worker::DataSubmissionWorker;
+use orchestrator::workers::dependencies::DependencyWorker;
+use orchestrator::workers::job_archival::JobArchivalWorker;
+use orchestrator::workers::leader::LEADER_ELECTION;
+use orchestrator::workers::messaging::MessagingWorker;
+use orchestrator::workers::orphan_tx_watchdog::OrphanTxWatchdogWorker;
+use orchestrator::workers::pipeline_monitor::PipelineMonitorWorker;
+use orchestrator::workers::proof_aggregation::ProofAggregationWorker;
 use orchestrator::workers::proof_registration::ProofRegistrationWorker;
 use orchestrator::workers::proving::ProvingWorker;
+use orchestrator::workers::reorg_monitor::ReorgMonitorWorker;
+use orchestrator::workers::schedule::worker_schedule_settings;
+use orchestrator::workers::scheduler::WORKER_SCHEDULER;
 use orchestrator::workers::snos::SnosWorker;
+use orchestrator::workers::storage_gc::StorageGcWorker;
+use orchestrator::workers::stuck_jobs::StuckJobRecoveryWorker;
 use orchestrator::workers::update_state::UpdateStateWorker;
-use orchestrator::workers::*;
 use utils::env_utils::get_env_var_or_default;
 
 /// Start the server
 #[tokio::main]
 async fn main() {
     dotenv().ok();
-    tracing_subscriber::fmt().with_max_level(tracing::Level::INFO).with_target(false).init();
+    // the SNOS runs are child processes of the orchestrator started with the `snos-run` subcommand
+    if let Some(exit_code) = run_snos_subcommand() {
+        std::process::exit(exit_code);
+    }
+    init_logging();
 
     // initial config setup
-    config().await;
+    let config = config().await;
+    // the artifacts stored before the keys were namespaced are copied to their new key once
+    if get_env_var_or_default("STORAGE_MIGRATE_FLAT_KEYS", "false") == "true" {
+        migrate_flat_keys(config.storage()).await.expect("Failed to migrate the flat storage keys");
+    }
     let host = get_env_var_or_default("HOST", "127.0.0.1");
     let port = get_env_var_or_default("PORT", "3000").parse::<u16>().expect("PORT must be a u16");
     let address = format!("{}:{}", host, port);
@@ -30,19 +55,26 @@ async fn main() {
     // spawn a thread for each workers
     // changes in rollup mode - sovereign, validity, validiums etc.
     // will likely involve changes in these workers as well
-    tokio::spawn(start_cron(Box::new(SnosWorker), 60));
-    tokio::spawn(start_cron(Box::new(ProvingWorker), 60));
-    tokio::spawn(start_cron(Box::new(ProofRegistrationWorker), 60));
-    tokio::spawn(start_cron(Box::new(UpdateStateWorker), 60));
-    tokio::spawn(start_cron(Box::new(DataSubmissionWorker), 60));
+    LEADER_ELECTION.start().await;
+    let schedules = worker_schedule_settings();
+    WORKER_SCHEDULER.spawn("snos", Box::new(SnosWorker), schedules.snos);
+    WORKER_SCHEDULER.spawn("proving", Box::new(ProvingWorker), schedules.proving);
+    WORKER_SCHEDULER.spawn("proof_registration", Box::new(ProofRegistrationWorker), schedules.proof_registration);
+    WORKER_SCHEDULER.spawn("update_state", Box::new(UpdateStateWorker), schedules.update_state);
+    WORKER_SCHEDULER.spawn("data_submission", Box::new(DataSubmissionWorker), schedules.data_submission);
+    WORKER_SCHEDULER.spawn("dependencies", Box::new(DependencyWorker), schedules.dependencies);
+    WORKER_SCHEDULER.spawn("stuck_jobs", Box::new(StuckJobRecoveryWorker), schedules.stuck_jobs);
+    WORKER_SCHEDULER.spawn("da_backfill", Box::new(DaBackfillWorker), schedules.da_backfill);
+    WORKER_SCHEDULER.spawn("orphan_tx_watchdog", Box::new(OrphanTxWatchdogWorker), schedules.orphan_tx_watchdog);
+    WORKER_SCHEDULER.spawn("balance_monitor", Box::new(BalanceMonitorWorker::default()), schedules.balance_monitor);
+    WORKER_SCHEDULER.spawn("reorg_monitor", Box::new(ReorgMonitorWorker), schedules.reorg_monitor);
+    WORKER_SCHEDULER.spawn("proof_aggregation", Box::new(ProofAggregationWorker), schedules.proof_aggregation);
+    WORKER_SCHEDULER.spawn("messaging", Box::new(MessagingWorker), schedules.messaging);
+    WORKER_SCHEDULER.spawn("data_audit", Box::new(AuditWorker), schedules.data_audit);
+    WORKER_SCHEDULER.spawn("job_archival", Box::new(JobArchivalWorker), schedules.job_archival);
+    WORKER_SCHEDULER.spawn("storage_gc", Box::new(StorageGcWorker), schedules.storage_gc);
+    WORKER_SCHEDULER.spawn("pipeline_monitor", Box::new(PipelineMonitorWorker::default()), schedules.pipeline_monitor);
 
     tracing::info!("Listening on http://{}", address);
     axum::serve(listener, app).await.expect("Failed to start axum server");
 }
-
-async fn start_cron(worker: Box<dyn Worker>, interval: u64) {
-    loop {
-        worker.run_worker_if_enabled().await.expect("Error in running the worker.");
-        tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
-    }
-}
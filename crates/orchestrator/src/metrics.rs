@@ -0,0 +1,69 @@
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use lazy_static::lazy_static;
+use prometheus::proto::{LabelPair, MetricFamily};
+use prometheus::{
+    register_gauge, register_gauge_vec, register_int_counter_vec, Encoder, Gauge, GaugeVec, IntCounterVec, TextEncoder,
+};
+
+use crate::config::config;
+use crate::controllers::errors::AppError;
+use crate::database::types::JobStats;
+use crate::deployment::{DeploymentDescriptor, DEPLOYMENT};
+
+lazy_static! {
+    /// Number of jobs of each type in each status
+    pub static ref JOBS: GaugeVec =
+        register_gauge_vec!("jobs", "Number of jobs of each type in each status", &["job_type", "status"]).unwrap();
+    /// Age in seconds of the oldest job waiting to be processed or verified, 0 without any
+    pub static ref OLDEST_PENDING_JOB_AGE: Gauge = register_gauge!(
+        "oldest_pending_job_age_seconds",
+        "Age in seconds of the oldest job waiting to be processed or verified"
+    )
+    .unwrap();
+    /// Number of job processings which timed out, by job type
+    pub static ref JOB_PROCESS_TIMEOUTS: IntCounterVec = register_int_counter_vec!(
+        "job_process_timeouts_total",
+        "Number of job processings which timed out",
+        &["job_type"]
+    )
+    .unwrap();
+}
+
+/// Renders all the metrics registered in the default registry, including the ones
+/// recorded by the clients, in the Prometheus text format
+pub async fn metrics_handler() -> Result<impl IntoResponse, AppError> {
+    record_job_stats(&config().await.database().get_job_stats().await?);
+    let encoder = TextEncoder::new();
+    let mut metric_families = prometheus::gather();
+    add_deployment_labels(&mut metric_families, &DEPLOYMENT);
+
+    let mut buffer = Vec::new();
+    encoder.encode(&metric_families, &mut buffer).map_err(|e| AppError::InternalServerError(e.into()))?;
+    Ok(([(CONTENT_TYPE, encoder.format_type().to_string())], buffer))
+}
+
+/// Sets the job gauges to the stats, the pairs of type and status without any job anymore are
+/// dropped
+pub fn record_job_stats(stats: &JobStats) {
+    JOBS.reset();
+    for count in &stats.counts {
+        let (job_type, status) = (format!("{:?}", count.job_type), format!("{:?}", count.status));
+        JOBS.with_label_values(&[&job_type, &status]).set(count.count as f64);
+    }
+    OLDEST_PENDING_JOB_AGE.set(stats.oldest_pending_job_age.unwrap_or(0) as f64);
+}
+
+/// Adds the deployment descriptor to the label set of every metric
+pub fn add_deployment_labels(metric_families: &mut [MetricFamily], deployment: &DeploymentDescriptor) {
+    for family in metric_families.iter_mut() {
+        for metric in family.mut_metric().iter_mut() {
+            for (name, value) in deployment.labels() {
+                let mut label = LabelPair::new();
+                label.set_name(name.to_string());
+                label.set_value(value.to_string());
+                metric.mut_label().push(label);
+            }
+        }
+    }
+}
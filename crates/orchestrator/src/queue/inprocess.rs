@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use omniqueue::{Delivery, QueueError};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::time::sleep;
+
+use crate::queue::job_queue::{job_processing_queues, JOB_VERIFICATION_QUEUE};
+use crate::queue::{MessageGroup, QueueProvider};
+
+/// Queue-less mode meant for small devnets. Messages never leave the process, they are handed
+/// over through tokio channels to the consumers spawned in `init_consumers`.
+pub struct InProcessQueue {
+    senders: HashMap<String, UnboundedSender<String>>,
+    receivers: Mutex<HashMap<String, UnboundedReceiver<String>>>,
+}
+
+impl InProcessQueue {
+    pub fn new() -> Self {
+        let mut senders = HashMap::new();
+        let mut receivers = HashMap::new();
+        for queue in job_processing_queues().chain([JOB_VERIFICATION_QUEUE]) {
+            let (sender, receiver) = unbounded_channel();
+            senders.insert(queue.to_string(), sender);
+            receivers.insert(queue.to_string(), receiver);
+        }
+        Self { senders, receivers: Mutex::new(receivers) }
+    }
+}
+
+impl Default for InProcessQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl QueueProvider for InProcessQueue {
+    async fn send_message_to_queue(
+        &self,
+        queue: String,
+        payload: String,
+        delay: Option<Duration>,
+        _group: Option<MessageGroup>,
+    ) -> Result<()> {
+        let sender = self.senders.get(&queue).ok_or_else(|| eyre!("Unknown in-process queue {}", queue))?.clone();
+
+        match delay {
+            Some(d) => {
+                tokio::spawn(async move {
+                    sleep(d).await;
+                    // the receiver is only dropped on shutdown, nothing left to deliver to
+                    let _ = sender.send(payload);
+                });
+            }
+            None => sender.send(payload).map_err(|e| eyre!("Failed to send message to queue {}: {}", queue, e))?,
+        }
+
+        Ok(())
+    }
+
+    /// Messages are pushed to the in-process consumers, there is nothing to poll.
+    async fn consume_message_from_queue(&self, _queue: String) -> std::result::Result<Delivery, QueueError> {
+        Err(QueueError::NoData)
+    }
+
+    fn take_receiver(&self, queue: &str) -> Option<UnboundedReceiver<String>> {
+        self.receivers.lock().expect("Failed to lock in-process queue receivers").remove(queue)
+    }
+}
@@ -1,41 +1,231 @@
 use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
+use bytes::Bytes;
 use color_eyre::eyre::eyre;
 use color_eyre::Result;
-use omniqueue::QueueError;
+use omniqueue::{Delivery, QueueError};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::Semaphore;
 use tokio::time::sleep;
-use tracing::log;
+use utils::env_utils::get_env_var_or_default;
 use uuid::Uuid;
 
 use crate::config::config;
-use crate::jobs::{process_job, verify_job};
+use crate::database::types::{now_secs, AuditEvent, AuditEventKind};
+use crate::jobs::trace::{in_job_trace, job_trace_id};
+use crate::jobs::types::{JobItem, JobPriority, JobType};
+use crate::jobs::{handle_job_failure, process_job, verify_job};
+use crate::queue::MessageGroup;
 
-pub const JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_job_processing_queue";
+pub const SNOS_JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_snos_job_processing_queue";
+pub const DA_JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_da_job_processing_queue";
+pub const PROVING_JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_proving_job_processing_queue";
+pub const PROOF_AGGREGATION_JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_proof_aggregation_job_processing_queue";
+pub const PROOF_REGISTRATION_JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_proof_registration_job_processing_queue";
+pub const STATE_UPDATE_JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_state_update_job_processing_queue";
+pub const MESSAGE_JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_message_job_processing_queue";
+pub const AUDIT_JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_audit_job_processing_queue";
+pub const SNOS_JOB_PRIORITY_PROCESSING_QUEUE: &str = "madara_orchestrator_snos_job_priority_processing_queue";
+pub const DA_JOB_PRIORITY_PROCESSING_QUEUE: &str = "madara_orchestrator_da_job_priority_processing_queue";
+pub const PROVING_JOB_PRIORITY_PROCESSING_QUEUE: &str = "madara_orchestrator_proving_job_priority_processing_queue";
+pub const PROOF_AGGREGATION_JOB_PRIORITY_PROCESSING_QUEUE: &str =
+    "madara_orchestrator_proof_aggregation_job_priority_processing_queue";
+pub const PROOF_REGISTRATION_JOB_PRIORITY_PROCESSING_QUEUE: &str =
+    "madara_orchestrator_proof_registration_job_priority_processing_queue";
+pub const STATE_UPDATE_JOB_PRIORITY_PROCESSING_QUEUE: &str =
+    "madara_orchestrator_state_update_job_priority_processing_queue";
+pub const MESSAGE_JOB_PRIORITY_PROCESSING_QUEUE: &str = "madara_orchestrator_message_job_priority_processing_queue";
+pub const AUDIT_JOB_PRIORITY_PROCESSING_QUEUE: &str = "madara_orchestrator_audit_job_priority_processing_queue";
 pub const JOB_VERIFICATION_QUEUE: &str = "madara_orchestrator_job_verification_queue";
+/// Dead-letter queue of the job queues, the queue moves the messages there once they failed
+/// too many times
+pub const JOB_HANDLE_FAILURE_QUEUE: &str = "madara_orchestrator_job_handle_failure_queue";
+
+/// Longest visibility timeout of an SQS message, a message held back for longer is received again
+/// and held back once more
+const MAX_VISIBILITY_TIMEOUT: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Maximum number of messages a consumer receives at once
+pub const ENV_JOB_CONSUMER_BATCH_SIZE: &str = "JOB_CONSUMER_BATCH_SIZE";
+/// Maximum number of jobs a consumer handles at once
+pub const ENV_JOB_CONSUMER_MAX_PARALLEL_JOBS: &str = "JOB_CONSUMER_MAX_PARALLEL_JOBS";
+/// Visibility timeout of the messages of the verification queue, in seconds. The queue's own
+/// timeout is kept when unset or 0.
+pub const ENV_JOB_VERIFICATION_VISIBILITY_TIMEOUT_SECS: &str = "JOB_VERIFICATION_VISIBILITY_TIMEOUT_SECS";
+/// Prefix of the keys of the data storage the quarantined messages are stored under
+pub const QUARANTINED_MESSAGES_STORAGE_PREFIX: &str = "quarantine/messages";
+
+/// Each job type is processed from its own queue, so that a backlog of slow jobs doesn't hold
+/// back the jobs of the other types
+pub fn job_processing_queue(job_type: &JobType) -> &'static str {
+    match job_type {
+        JobType::SnosRun => SNOS_JOB_PROCESSING_QUEUE,
+        JobType::DataSubmission => DA_JOB_PROCESSING_QUEUE,
+        JobType::ProofCreation => PROVING_JOB_PROCESSING_QUEUE,
+        JobType::ProofAggregation => PROOF_AGGREGATION_JOB_PROCESSING_QUEUE,
+        JobType::ProofRegistration => PROOF_REGISTRATION_JOB_PROCESSING_QUEUE,
+        JobType::StateTransition => STATE_UPDATE_JOB_PROCESSING_QUEUE,
+        JobType::MessageProcessing => MESSAGE_JOB_PROCESSING_QUEUE,
+        JobType::DataAudit => AUDIT_JOB_PROCESSING_QUEUE,
+    }
+}
+
+/// Queue the jobs of `job_type` with the `priority` are processed from. The high priority jobs
+/// have a queue of their own per job type, consumed separately, so that they don't wait behind
+/// the backlog of the normal queue.
+pub fn job_processing_queue_with_priority(job_type: &JobType, priority: JobPriority) -> &'static str {
+    match (priority, job_type) {
+        (JobPriority::Normal, _) => job_processing_queue(job_type),
+        (JobPriority::High, JobType::SnosRun) => SNOS_JOB_PRIORITY_PROCESSING_QUEUE,
+        (JobPriority::High, JobType::DataSubmission) => DA_JOB_PRIORITY_PROCESSING_QUEUE,
+        (JobPriority::High, JobType::ProofCreation) => PROVING_JOB_PRIORITY_PROCESSING_QUEUE,
+        (JobPriority::High, JobType::ProofAggregation) => PROOF_AGGREGATION_JOB_PRIORITY_PROCESSING_QUEUE,
+        (JobPriority::High, JobType::ProofRegistration) => PROOF_REGISTRATION_JOB_PRIORITY_PROCESSING_QUEUE,
+        (JobPriority::High, JobType::StateTransition) => STATE_UPDATE_JOB_PRIORITY_PROCESSING_QUEUE,
+        (JobPriority::High, JobType::MessageProcessing) => MESSAGE_JOB_PRIORITY_PROCESSING_QUEUE,
+        (JobPriority::High, JobType::DataAudit) => AUDIT_JOB_PRIORITY_PROCESSING_QUEUE,
+    }
+}
+
+/// Every processing queue, of every job type and priority
+pub fn job_processing_queues() -> impl Iterator<Item = &'static str> {
+    JobType::ALL.into_iter().flat_map(|job_type| {
+        JobPriority::ALL.into_iter().map(move |priority| job_processing_queue_with_priority(&job_type, priority))
+    })
+}
+
+/// Prefix of the settings of the processing queue of `job_type`:
+/// - `<PREFIX>_JOB_PROCESSING_CONSUMERS`, the number of consumers polling the queue, 1 by default
+/// - `<PREFIX>_JOB_PROCESSING_VISIBILITY_TIMEOUT_SECS`, the visibility timeout of its messages,
+///   the queue's own timeout is kept when unset or 0
+pub fn job_processing_env_prefix(job_type: &JobType) -> &'static str {
+    match job_type {
+        JobType::SnosRun => "SNOS",
+        JobType::DataSubmission => "DA",
+        JobType::ProofCreation => "PROVING",
+        JobType::ProofAggregation => "PROOF_AGGREGATION",
+        JobType::ProofRegistration => "PROOF_REGISTRATION",
+        JobType::StateTransition => "STATE_UPDATE",
+        JobType::MessageProcessing => "MESSAGE",
+        JobType::DataAudit => "AUDIT",
+    }
+}
+
+/// Number of consumers polling the processing queue of `job_type`
+fn job_processing_consumers(job_type: &JobType) -> Result<usize> {
+    let env_var = format!("{}_JOB_PROCESSING_CONSUMERS", job_processing_env_prefix(job_type));
+    get_env_var_or_default(&env_var, "1").parse().map_err(|e| eyre!("Invalid {}: {}", env_var, e))
+}
+
+/// Visibility timeout of the messages of the processing queue of `job_type`
+fn job_processing_visibility_timeout(job_type: &JobType) -> Result<Option<Duration>> {
+    let env_var = format!("{}_JOB_PROCESSING_VISIBILITY_TIMEOUT_SECS", job_processing_env_prefix(job_type));
+    visibility_timeout_from_env(&env_var)
+}
+
+fn visibility_timeout_from_env(env_var: &str) -> Result<Option<Duration>> {
+    match get_env_var_or_default(env_var, "").as_str() {
+        "" | "0" => Ok(None),
+        secs => Ok(Some(Duration::from_secs(secs.parse().map_err(|e| eyre!("Invalid {}: {}", env_var, e))?))),
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JobQueueMessage {
     pub(crate) id: Uuid,
+    /// Trace id of the job, see [`crate::jobs::trace`]. Absent from the messages sent before the
+    /// trace ids were introduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) trace_id: Option<String>,
+    /// Unix timestamp, in seconds, before which the job isn't handled. The queues which can't
+    /// delay a single message, i.e. the SQS FIFO queues, get the delayed messages right away and
+    /// the consumer keeps them invisible until then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) not_before: Option<u64>,
+}
+
+/// Exponential backoff of the jobs sent back to a queue, the delay doubles with every attempt up
+/// to a cap. It's jittered so that the jobs failing together don't all come back at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobBackoff {
+    /// Delay before the first attempt
+    pub initial_delay_seconds: u64,
+    /// Upper bound of the delay
+    pub max_delay_seconds: u64,
 }
 
-pub async fn add_job_to_process_queue(id: Uuid) -> Result<()> {
-    log::info!("Adding job with id {:?} to processing queue", id);
-    add_job_to_queue(id, JOB_PROCESSING_QUEUE.to_string(), None).await
+impl JobBackoff {
+    /// Returns the delay to wait before `attempt`, counted from 0. The delay is drawn uniformly
+    /// between half and the whole of the exponential backoff.
+    pub fn delay(&self, attempt: u64) -> Duration {
+        let exponent = u32::try_from(attempt).unwrap_or(u32::MAX);
+        let backoff_ms = self
+            .initial_delay_seconds
+            .saturating_mul(1000)
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(self.max_delay_seconds.saturating_mul(1000));
+        Duration::from_millis(rand::thread_rng().gen_range(backoff_ms / 2..=backoff_ms))
+    }
 }
 
-pub async fn add_job_to_verification_queue(id: Uuid, delay: Duration) -> Result<()> {
-    log::info!("Adding job with id {:?} to verification queue", id);
-    add_job_to_queue(id, JOB_VERIFICATION_QUEUE.to_string(), Some(delay)).await
+/// Adds the job to the processing queue of its type and priority
+pub async fn add_job_to_process_queue(job: &JobItem) -> Result<()> {
+    tracing::info!(
+        job_id = %job.id,
+        job_type = ?job.job_type,
+        block = %job.internal_id,
+        "Adding job to {:?} priority processing queue",
+        job.priority
+    );
+    add_job_to_queue(job, job_processing_queue_with_priority(&job.job_type, job.priority).to_string(), None).await
 }
 
-pub async fn consume_job_from_queue<F, Fut>(queue: String, handler: F) -> Result<()>
+pub async fn add_job_to_process_queue_with_delay(job: &JobItem, delay: Duration) -> Result<()> {
+    tracing::info!(
+        job_id = %job.id,
+        job_type = ?job.job_type,
+        block = %job.internal_id,
+        "Adding job to {:?} priority processing queue with a delay of {:?}",
+        job.priority,
+        delay
+    );
+    let queue = job_processing_queue_with_priority(&job.job_type, job.priority).to_string();
+    add_job_to_queue(job, queue, Some(delay)).await
+}
+
+/// Adds the job back to the processing queue after the backoff of its `attempt`
+pub async fn add_job_to_process_queue_with_backoff(job: &JobItem, backoff: &JobBackoff, attempt: u64) -> Result<()> {
+    add_job_to_process_queue_with_delay(job, backoff.delay(attempt)).await
+}
+
+/// Adds the job to the verification queue, to be verified after the backoff of its `attempt`
+pub async fn add_job_to_verification_queue(job: &JobItem, backoff: &JobBackoff, attempt: u64) -> Result<()> {
+    let delay = backoff.delay(attempt);
+    tracing::info!(
+        job_id = %job.id,
+        job_type = ?job.job_type,
+        block = %job.internal_id,
+        "Adding job to verification queue with a delay of {:?}",
+        delay
+    );
+    add_job_to_queue(job, JOB_VERIFICATION_QUEUE.to_string(), Some(delay)).await
+}
+
+/// Consumes a message of the queue and hands its job over to `handler`, see [`handle_delivery`]
+pub async fn consume_job_from_queue<F, Fut>(
+    queue: String,
+    visibility_timeout: Option<Duration>,
+    handler: F,
+) -> Result<()>
 where
     F: FnOnce(Uuid) -> Fut,
     Fut: Future<Output = Result<()>>,
 {
-    log::info!("Consuming from queue {:?}", queue);
+    tracing::info!("Consuming from queue {:?}", queue);
     let config = config().await;
     let delivery = match config.queue().consume_message_from_queue(queue.clone()).await {
         Ok(d) => d,
@@ -46,44 +236,248 @@ where
             return Err(eyre!("Failed to consume message from queue, error {}", e));
         }
     };
-    let job_message: Option<JobQueueMessage> = delivery.payload_serde_json()?;
+    handle_delivery(&queue, delivery, visibility_timeout, handler).await
+}
 
-    match job_message {
-        Some(job_message) => {
-            log::info!("Handling job with id {:?} for queue {:?}", job_message.id, queue);
-            match handler(job_message.id).await {
-                Ok(_) => delivery.ack().await.map_err(|(e, _)| e)?,
-                Err(e) => {
-                    log::error!("Failed to handle job with id {:?}. Error: {:?}", job_message.id, e);
+/// Consumes up to `batch_size` messages of the queue and hands their jobs over to `handler`, in
+/// parallel, as many at once as the `semaphore` allows. Returns the number of messages consumed
+/// once they are all dispatched, without waiting for them to be handled.
+pub async fn consume_jobs_from_queue<F, Fut>(
+    queue: &'static str,
+    batch_size: usize,
+    semaphore: &Arc<Semaphore>,
+    visibility_timeout: Option<Duration>,
+    handler: F,
+) -> Result<usize>
+where
+    F: FnOnce(Uuid) -> Fut + Copy + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    tracing::info!("Consuming up to {} messages from queue {:?}", batch_size, queue);
+    let config = config().await;
+    let deliveries = match config.queue().consume_messages_from_queue(queue.to_string(), batch_size).await {
+        Ok(deliveries) => deliveries,
+        Err(QueueError::NoData) => {
+            return Ok(0);
+        }
+        Err(e) => {
+            return Err(eyre!("Failed to consume messages from queue, error {}", e));
+        }
+    };
+
+    let consumed = deliveries.len();
+    for delivery in deliveries {
+        let permit = Arc::clone(semaphore).acquire_owned().await?;
+        tokio::spawn(async move {
+            if let Err(e) = handle_delivery(queue, delivery, visibility_timeout, handler).await {
+                tracing::error!("Failed to handle message from queue {:?}. Error: {:?}", queue, e);
+            }
+            drop(permit);
+        });
+    }
+    Ok(consumed)
+}
+
+/// Hands the job of the message over to `handler`, acknowledging the message once it's handled.
+/// With a `visibility_timeout`, the message is kept invisible to the other consumers while the
+/// handler runs, its visibility being extended every half timeout, so that long jobs aren't
+/// redelivered mid-processing.
+async fn handle_delivery<F, Fut>(
+    queue: &str,
+    mut delivery: Delivery,
+    visibility_timeout: Option<Duration>,
+    handler: F,
+) -> Result<()>
+where
+    F: FnOnce(Uuid) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let Some(job_message) = read_job_message(queue, &delivery).await? else {
+        delivery.ack().await.map_err(|(e, _)| e)?;
+        return Ok(());
+    };
+    // the message was sent right away by a queue which can't delay it, it's left unacknowledged
+    // and invisible until the job is due
+    if let Some(remaining) = job_message.not_before.map(|not_before| not_before.saturating_sub(now_secs())) {
+        if remaining > 0 {
+            tracing::debug!("Holding back the message of job {:?} for {}s", job_message.id, remaining);
+            extend_visibility(&mut delivery, Duration::from_secs(remaining).min(MAX_VISIBILITY_TIMEOUT), job_message.id)
+                .await;
+            return Ok(());
+        }
+    }
 
-                    // if the queue as a retry logic at the source, it will be attempted
-                    // after the nack
-                    delivery.nack().await.map_err(|(e, _)| e)?;
+    let result = in_job_trace(job_message.id, job_message.trace_id.clone(), async {
+        tracing::info!("Handling job with id {:?} for queue {:?}", job_message.id, queue);
+        match visibility_timeout {
+            Some(timeout) => {
+                extend_visibility(&mut delivery, timeout, job_message.id).await;
+                let handling = handler(job_message.id);
+                tokio::pin!(handling);
+                loop {
+                    tokio::select! {
+                        result = &mut handling => break result,
+                        _ = sleep(timeout / 2) => extend_visibility(&mut delivery, timeout, job_message.id).await,
+                    }
                 }
-            };
+            }
+            None => handler(job_message.id).await,
+        }
+    })
+    .await;
+    match result {
+        Ok(_) => delivery.ack().await.map_err(|(e, _)| e)?,
+        Err(e) => {
+            tracing::error!("Failed to handle job with id {:?}. Error: {:?}", job_message.id, e);
+
+            // if the queue as a retry logic at the source, it will be attempted
+            // after the nack
+            delivery.nack().await.map_err(|(e, _)| e)?;
+        }
+    };
+
+    Ok(())
+}
+
+/// Reads the job message of the delivery. A payload which isn't a job message would fail on
+/// every delivery, it is quarantined instead and `None` is returned, the message is to be
+/// acknowledged so that the queue doesn't deliver it again.
+async fn read_job_message(queue: &str, delivery: &Delivery) -> Result<Option<JobQueueMessage>> {
+    let error = match delivery.payload_serde_json::<JobQueueMessage>() {
+        Ok(Some(job_message)) => return Ok(Some(job_message)),
+        Ok(None) => "the message has no payload".to_string(),
+        Err(e) => e.to_string(),
+    };
+    quarantine_message(queue, delivery.borrow_payload().unwrap_or_default(), &error).await?;
+    Ok(None)
+}
+
+/// Stores the payload of a message which isn't a job message to the data storage, for the
+/// investigation, and records it in the audit log
+async fn quarantine_message(queue: &str, payload: &[u8], error: &str) -> Result<()> {
+    let config = config().await;
+    let key = format!("{}/{}/{}", QUARANTINED_MESSAGES_STORAGE_PREFIX, queue, Uuid::new_v4());
+    config.storage().put_data(Bytes::copy_from_slice(payload), &key).await?;
+
+    let details = format!("Quarantined a message of queue {} which isn't a job message ({}) to {}", queue, error, key);
+    tracing::error!("{}", details);
+    config.database().record_audit_event(AuditEvent::new(AuditEventKind::PoisonMessage, details)).await?;
+    Ok(())
+}
+
+/// Keeps the message invisible to the other consumers for `timeout` from now. Failing to do so
+/// only risks a redelivery, which the job locking handles, so it's not an error.
+async fn extend_visibility(delivery: &mut Delivery, timeout: Duration, id: Uuid) {
+    if let Err(e) = delivery.set_ack_deadline(timeout).await {
+        tracing::warn!("Failed to extend the visibility of the message of job {:?}: {}", id, e);
+    }
+}
+
+/// Consumes a message of the dead-letter queue and marks its job as failed, with the message
+/// recorded in the metadata of the job
+pub async fn consume_dead_letter_from_queue(queue: String) -> Result<()> {
+    tracing::info!("Consuming from queue {:?}", queue);
+    let config = config().await;
+    let delivery = match config.queue().consume_message_from_queue(queue.clone()).await {
+        Ok(d) => d,
+        Err(QueueError::NoData) => {
+            return Ok(());
+        }
+        Err(e) => {
+            return Err(eyre!("Failed to consume message from queue, error {}", e));
+        }
+    };
+    let Some(job_message) = read_job_message(&queue, &delivery).await? else {
+        delivery.ack().await.map_err(|(e, _)| e)?;
+        return Ok(());
+    };
+    let payload = String::from_utf8_lossy(delivery.borrow_payload().unwrap_or_default()).to_string();
+
+    let result = in_job_trace(job_message.id, job_message.trace_id.clone(), async {
+        tracing::error!("Job with id {:?} was dead-lettered", job_message.id);
+        handle_job_failure(job_message.id, payload).await
+    })
+    .await;
+    match result {
+        Ok(_) => delivery.ack().await.map_err(|(e, _)| e)?,
+        Err(e) => {
+            tracing::error!("Failed to handle the failure of job with id {:?}. Error: {:?}", job_message.id, e);
+            delivery.nack().await.map_err(|(e, _)| e)?;
         }
-        None => return Ok(()),
     };
 
     Ok(())
 }
 
 pub async fn init_consumers() -> Result<()> {
+    let config = config().await;
+    let batch_size: usize = get_env_var_or_default(ENV_JOB_CONSUMER_BATCH_SIZE, "1").parse()?;
+    let max_parallel_jobs: usize = get_env_var_or_default(ENV_JOB_CONSUMER_MAX_PARALLEL_JOBS, "1").parse()?;
+
+    // in-process queues push messages to the consumers, no polling required. A queue handles as
+    // many jobs at once as its consumers would in the queue mode
+    if let Some(verification_receiver) = config.queue().take_receiver(JOB_VERIFICATION_QUEUE) {
+        for job_type in JobType::ALL {
+            let consumers = job_processing_consumers(&job_type)?;
+            for priority in JobPriority::ALL {
+                let queue = job_processing_queue_with_priority(&job_type, priority);
+                let processing_receiver =
+                    config.queue().take_receiver(queue).ok_or_else(|| eyre!("No receiver for the queue {}", queue))?;
+                let parallel_jobs = match priority {
+                    JobPriority::Normal => consumers * max_parallel_jobs,
+                    JobPriority::High => max_parallel_jobs,
+                };
+                tokio::spawn(consume_jobs_in_process(queue, processing_receiver, parallel_jobs, process_job));
+            }
+        }
+        tokio::spawn(consume_jobs_in_process(
+            JOB_VERIFICATION_QUEUE,
+            verification_receiver,
+            max_parallel_jobs,
+            verify_job,
+        ));
+        return Ok(());
+    }
+
+
     // TODO: figure out a way to generalize this
+    for job_type in JobType::ALL {
+        let visibility_timeout = job_processing_visibility_timeout(&job_type)?;
+        let queue = job_processing_queue(&job_type);
+        for _ in 0..job_processing_consumers(&job_type)? {
+            spawn_processing_consumer(queue, batch_size, max_parallel_jobs, visibility_timeout);
+        }
+        // the high priority jobs are few, a single consumer keeps them from waiting
+        let priority_queue = job_processing_queue_with_priority(&job_type, JobPriority::High);
+        spawn_processing_consumer(priority_queue, batch_size, max_parallel_jobs, visibility_timeout);
+    }
+    let visibility_timeout = visibility_timeout_from_env(ENV_JOB_VERIFICATION_VISIBILITY_TIMEOUT_SECS)?;
+    let semaphore = Arc::new(Semaphore::new(max_parallel_jobs));
     tokio::spawn(async move {
         loop {
-            match consume_job_from_queue(JOB_PROCESSING_QUEUE.to_string(), process_job).await {
+            match consume_jobs_from_queue(
+                JOB_VERIFICATION_QUEUE,
+                batch_size,
+                &semaphore,
+                visibility_timeout,
+                verify_job,
+            )
+            .await
+            {
+                Ok(consumed) if consumed > 0 => continue,
                 Ok(_) => {}
-                Err(e) => log::error!("Failed to consume from queue {:?}. Error: {:?}", JOB_PROCESSING_QUEUE, e),
+                Err(e) => tracing::error!("Failed to consume from queue {:?}. Error: {:?}", JOB_VERIFICATION_QUEUE, e),
             }
             sleep(Duration::from_secs(1)).await;
         }
     });
     tokio::spawn(async move {
         loop {
-            match consume_job_from_queue(JOB_VERIFICATION_QUEUE.to_string(), verify_job).await {
+            match consume_dead_letter_from_queue(JOB_HANDLE_FAILURE_QUEUE.to_string()).await {
                 Ok(_) => {}
-                Err(e) => log::error!("Failed to consume from queue {:?}. Error: {:?}", JOB_VERIFICATION_QUEUE, e),
+                Err(e) => {
+                    tracing::error!("Failed to consume from queue {:?}. Error: {:?}", JOB_HANDLE_FAILURE_QUEUE, e)
+                }
             }
             sleep(Duration::from_secs(1)).await;
         }
@@ -91,9 +485,85 @@ pub async fn init_consumers() -> Result<()> {
     Ok(())
 }
 
-async fn add_job_to_queue(id: Uuid, queue: String, delay: Option<Duration>) -> Result<()> {
+/// Spawns a consumer processing the jobs of the queue, until the orchestrator stops
+fn spawn_processing_consumer(
+    queue: &'static str,
+    batch_size: usize,
+    max_parallel_jobs: usize,
+    visibility_timeout: Option<Duration>,
+) {
+    let semaphore = Arc::new(Semaphore::new(max_parallel_jobs));
+    tokio::spawn(async move {
+        loop {
+            match consume_jobs_from_queue(queue, batch_size, &semaphore, visibility_timeout, process_job).await {
+                // catching up, the next messages are consumed right away
+                Ok(consumed) if consumed > 0 => continue,
+                Ok(_) => {}
+                Err(e) => tracing::error!("Failed to consume from queue {:?}. Error: {:?}", queue, e),
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+    });
+}
+
+/// Hands the jobs of the messages of an in-process queue over to `handler`, as they arrive, up to
+/// `max_parallel_jobs` at once so that a slow job doesn't hold back the next ones
+pub async fn consume_jobs_in_process<F, Fut>(
+    queue: &'static str,
+    mut receiver: UnboundedReceiver<String>,
+    max_parallel_jobs: usize,
+    handler: F,
+) where
+    F: FnOnce(Uuid) -> Fut + Copy + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    let semaphore = Arc::new(Semaphore::new(max_parallel_jobs));
+    while let Some(payload) = receiver.recv().await {
+        let job_message: JobQueueMessage = match serde_json::from_str(&payload) {
+            Ok(job_message) => job_message,
+            Err(e) => {
+                if let Err(e) = quarantine_message(queue, payload.as_bytes(), &e.to_string()).await {
+                    tracing::error!(
+                        "Failed to quarantine message {:?} from queue {:?}. Error: {:?}",
+                        payload,
+                        queue,
+                        e
+                    );
+                }
+                continue;
+            }
+        };
+        let permit = Arc::clone(&semaphore).acquire_owned().await.expect("The semaphore is never closed");
+        tokio::spawn(in_job_trace(job_message.id, job_message.trace_id.clone(), async move {
+            tracing::info!("Handling job with id {:?} for queue {:?}", job_message.id, queue);
+            if let Err(e) = handler(job_message.id).await {
+                tracing::error!("Failed to handle job with id {:?}. Error: {:?}", job_message.id, e);
+            }
+            drop(permit);
+        }));
+    }
+}
+
+async fn add_job_to_queue(job: &JobItem, queue: String, delay: Option<Duration>) -> Result<()> {
     let config = config().await;
-    let message = JobQueueMessage { id };
-    config.queue().send_message_to_queue(queue, serde_json::to_string(&message)?, delay).await?;
+    let message = JobQueueMessage {
+        id: job.id,
+        trace_id: job_trace_id(job).map(str::to_string),
+        not_before: delay.map(|delay| now_secs() + delay.as_secs()),
+    };
+    let payload = serde_json::to_string(&message)?;
+    config.queue().send_message_to_queue(queue, payload, delay, Some(job_message_group(job))).await?;
     Ok(())
 }
+
+/// The messages of a job are grouped together, delivered in order by the FIFO queues. A message
+/// is a duplicate of another when sent for the same status and attempts of the job.
+pub fn job_message_group(job: &JobItem) -> MessageGroup {
+    MessageGroup {
+        group_id: format!("{:?}_{}", job.job_type, job.internal_id),
+        deduplication_id: format!(
+            "{}_{:?}_{}_{}",
+            job.id, job.status, job.counters.process_attempts, job.counters.verification_attempts
+        ),
+    }
+}
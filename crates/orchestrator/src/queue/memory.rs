@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use omniqueue::backends::{InMemoryBackend, InMemoryConsumer, InMemoryProducer};
+use omniqueue::{Delivery, QueueError};
+use tokio::sync::Mutex;
+
+use crate::queue::job_queue::{job_processing_queues, JOB_HANDLE_FAILURE_QUEUE, JOB_VERIFICATION_QUEUE};
+use crate::queue::{MessageGroup, QueueProvider};
+
+/// Queue kept in the memory of the process, for local development and tests. Unlike the
+/// in-process queue, the messages are polled by the consumers like with SQS, so it can stand in
+/// for it anywhere. Messages are lost on restart.
+pub struct InMemoryQueue {
+    queues: HashMap<String, (InMemoryProducer, Mutex<InMemoryConsumer>)>,
+}
+
+impl InMemoryQueue {
+    pub async fn new() -> Result<Self> {
+        let mut queues = HashMap::new();
+        for queue in job_processing_queues().chain([JOB_VERIFICATION_QUEUE, JOB_HANDLE_FAILURE_QUEUE]) {
+            let (producer, consumer) = InMemoryBackend::builder().build_pair().await?;
+            queues.insert(queue.to_string(), (producer, Mutex::new(consumer)));
+        }
+        Ok(Self { queues })
+    }
+}
+
+#[async_trait]
+impl QueueProvider for InMemoryQueue {
+    async fn send_message_to_queue(
+        &self,
+        queue: String,
+        payload: String,
+        delay: Option<Duration>,
+        _group: Option<MessageGroup>,
+    ) -> Result<()> {
+        let (producer, _) = self.queues.get(&queue).ok_or_else(|| eyre!("Unknown in-memory queue {}", queue))?;
+
+        match delay {
+            Some(d) => producer.send_raw_scheduled(payload.as_bytes(), d).await?,
+            None => producer.send_raw(payload.as_bytes()).await?,
+        }
+
+        Ok(())
+    }
+
+    /// Returns the first message available without waiting, `QueueError::NoData` when there is none
+    async fn consume_message_from_queue(&self, queue: String) -> std::result::Result<Delivery, QueueError> {
+        let (_, consumer) = self
+            .queues
+            .get(&queue)
+            .ok_or_else(|| QueueError::Generic(eyre!("Unknown in-memory queue {}", queue).into()))?;
+        let mut deliveries = consumer.lock().await.receive_all(1, Duration::ZERO).await?;
+        deliveries.pop().ok_or(QueueError::NoData)
+    }
+
+    async fn consume_messages_from_queue(
+        &self,
+        queue: String,
+        max_messages: usize,
+    ) -> std::result::Result<Vec<Delivery>, QueueError> {
+        let (_, consumer) = self
+            .queues
+            .get(&queue)
+            .ok_or_else(|| QueueError::Generic(eyre!("Unknown in-memory queue {}", queue).into()))?;
+        let deliveries = consumer.lock().await.receive_all(max_messages, Duration::ZERO).await?;
+        if deliveries.is_empty() {
+            return Err(QueueError::NoData);
+        }
+        Ok(deliveries)
+    }
+}
@@ -1,4 +1,6 @@
+pub mod inprocess;
 pub mod job_queue;
+pub mod memory;
 pub mod sqs;
 
 use std::time::Duration;
@@ -7,6 +9,16 @@ use async_trait::async_trait;
 use color_eyre::Result;
 use mockall::automock;
 use omniqueue::{Delivery, QueueError};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Ordering and deduplication keys of a message, used by the FIFO queues and ignored by the others
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageGroup {
+    /// The messages of a group are delivered in order, one at a time
+    pub group_id: String,
+    /// The messages sent again with the same id are dropped by the queue
+    pub deduplication_id: String,
+}
 
 /// The QueueProvider trait is used to define the methods that a queue
 /// should implement to be used as a queue for the orchestrator. The
@@ -14,8 +26,30 @@ use omniqueue::{Delivery, QueueError};
 #[automock]
 #[async_trait]
 pub trait QueueProvider: Send + Sync {
-    async fn send_message_to_queue(&self, queue: String, payload: String, delay: Option<Duration>) -> Result<()>;
+    async fn send_message_to_queue(
+        &self,
+        queue: String,
+        payload: String,
+        delay: Option<Duration>,
+        group: Option<MessageGroup>,
+    ) -> Result<()>;
     async fn consume_message_from_queue(&self, queue: String) -> std::result::Result<Delivery, QueueError>;
+
+    /// Consumes up to `max_messages` messages at once, fails with `QueueError::NoData` when there
+    /// are none. Providers without batched receive consume a single message.
+    async fn consume_messages_from_queue(
+        &self,
+        queue: String,
+        _max_messages: usize,
+    ) -> std::result::Result<Vec<Delivery>, QueueError> {
+        Ok(vec![self.consume_message_from_queue(queue).await?])
+    }
+
+    /// Returns the receiving end of `queue` for providers that deliver messages in-process.
+    /// Providers backed by an external queue are polled instead and return `None`.
+    fn take_receiver(&self, _queue: &str) -> Option<UnboundedReceiver<String>> {
+        None
+    }
 }
 
 pub async fn init_consumers() -> Result<()> {
@@ -1,19 +1,44 @@
 use std::time::Duration;
 
-use crate::queue::job_queue::JOB_PROCESSING_QUEUE;
+use crate::aws_http_client::aws_http_client;
+use crate::queue::job_queue::{JOB_HANDLE_FAILURE_QUEUE, JOB_VERIFICATION_QUEUE};
 use async_trait::async_trait;
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
 use omniqueue::backends::{SqsBackend, SqsConfig, SqsConsumer, SqsProducer};
 use omniqueue::{Delivery, QueueError};
-use utils::env_utils::get_env_var_or_panic;
+use utils::env_utils::{get_env_var_or_default, get_env_var_or_panic};
+
+use crate::queue::{MessageGroup, QueueProvider};
+
+/// Whether the job queues are SQS FIFO queues
+pub const ENV_SQS_FIFO_QUEUES: &str = "SQS_FIFO_QUEUES";
+
+/// SQS doesn't return more messages than this at once
+const SQS_MAX_RECEIVED_MESSAGES: usize = 10;
+/// How long a batched receive waits for messages to arrive
+const SQS_RECEIVE_WAIT_TIME: Duration = Duration::from_secs(1);
 
-use crate::queue::QueueProvider;
 pub struct SqsQueue;
 
 #[async_trait]
 impl QueueProvider for SqsQueue {
-    async fn send_message_to_queue(&self, queue: String, payload: String, delay: Option<Duration>) -> Result<()> {
+    async fn send_message_to_queue(
+        &self,
+        queue: String,
+        payload: String,
+        delay: Option<Duration>,
+        group: Option<MessageGroup>,
+    ) -> Result<()> {
         let queue_url = get_queue_url(queue);
+
+        if fifo_queues() {
+            let group = group.ok_or_else(|| eyre!("Messages sent to the FIFO queue {} need a group", queue_url))?;
+            // FIFO queues can't delay a single message, it's sent right away and the consumer holds
+            // it back until the `not_before` of the job message
+            return send_to_fifo_queue(&queue_url, payload, group).await;
+        }
+
         let producer = get_producer(queue_url).await?;
 
         match delay {
@@ -29,25 +54,85 @@ impl QueueProvider for SqsQueue {
         let mut consumer = get_consumer(queue_url).await?;
         consumer.receive().await
     }
+
+    async fn consume_messages_from_queue(
+        &self,
+        queue: String,
+        max_messages: usize,
+    ) -> std::result::Result<Vec<Delivery>, QueueError> {
+        let queue_url = get_queue_url(queue);
+        let mut consumer = get_consumer(queue_url).await?;
+        let max_messages = max_messages.min(SQS_MAX_RECEIVED_MESSAGES);
+        let deliveries = consumer.receive_all(max_messages, SQS_RECEIVE_WAIT_TIME).await?;
+        if deliveries.is_empty() {
+            return Err(QueueError::NoData);
+        }
+        Ok(deliveries)
+    }
 }
 
+/// The urls of the processing queues of the job types are derived from `SQS_JOB_PROCESSING_QUEUES_BASE_URL`,
+/// followed by the name of the queue, and `.fifo` for FIFO queues
 fn get_queue_url(queue_name: String) -> String {
-    if queue_name == JOB_PROCESSING_QUEUE {
-        get_env_var_or_panic("SQS_JOB_PROCESSING_QUEUE_URL")
-    } else {
+    if queue_name == JOB_VERIFICATION_QUEUE {
         get_env_var_or_panic("SQS_JOB_VERIFICATION_QUEUE_URL")
+    } else if queue_name == JOB_HANDLE_FAILURE_QUEUE {
+        get_env_var_or_panic("SQS_JOB_HANDLE_FAILURE_QUEUE_URL")
+    } else {
+        let base_url = get_env_var_or_panic("SQS_JOB_PROCESSING_QUEUES_BASE_URL");
+        let suffix = if fifo_queues() { ".fifo" } else { "" };
+        format!("{}/{}{}", base_url.trim_end_matches('/'), queue_name, suffix)
+    }
+}
+
+/// Whether the queues are FIFO queues, set with `SQS_FIFO_QUEUES`. The messages of a job are then
+/// delivered in order and the duplicates are dropped, so that two consumers rarely race on a job.
+fn fifo_queues() -> bool {
+    get_env_var_or_default(ENV_SQS_FIFO_QUEUES, "false") == "true"
+}
+
+/// omniqueue doesn't set the group and deduplication ids the FIFO queues require, the message is
+/// sent with the SQS client directly
+async fn send_to_fifo_queue(queue_url: &str, payload: String, group: MessageGroup) -> Result<()> {
+    let client = aws_sdk_sqs::Client::from_conf(sqs_config(None).await);
+    client
+        .send_message()
+        .queue_url(queue_url)
+        .message_body(payload)
+        .message_group_id(group.group_id)
+        .message_deduplication_id(group.deduplication_id)
+        .send()
+        .await?;
+    Ok(())
+}
+
+/// Config of the SQS clients from the environment, with the outbound HTTP client
+async fn sqs_config(endpoint_url: Option<&str>) -> aws_sdk_sqs::Config {
+    let mut loader = aws_config::from_env();
+    if let Some(endpoint_url) = endpoint_url {
+        loader = loader.endpoint_url(endpoint_url);
+    }
+    if let Some(http_client) = aws_http_client() {
+        loader = loader.http_client(http_client);
     }
+    aws_sdk_sqs::Config::from(&loader.load().await)
 }
 
 // TODO: store the producer and consumer in memory to avoid creating a new one every time
 async fn get_producer(queue: String) -> Result<SqsProducer> {
-    let (producer, _) =
-        SqsBackend::builder(SqsConfig { queue_dsn: queue, override_endpoint: true }).build_pair().await?;
+    let sqs_config = sqs_config(Some(&queue)).await;
+    let (producer, _) = SqsBackend::builder(SqsConfig { queue_dsn: queue, override_endpoint: true })
+        .sqs_config(sqs_config)
+        .build_pair()
+        .await?;
     Ok(producer)
 }
 
 async fn get_consumer(queue: String) -> std::result::Result<SqsConsumer, QueueError> {
-    let (_, consumer) =
-        SqsBackend::builder(SqsConfig { queue_dsn: queue, override_endpoint: true }).build_pair().await?;
+    let sqs_config = sqs_config(Some(&queue)).await;
+    let (_, consumer) = SqsBackend::builder(SqsConfig { queue_dsn: queue, override_endpoint: true })
+        .sqs_config(sqs_config)
+        .build_pair()
+        .await?;
     Ok(consumer)
 }
@@ -1,16 +1,39 @@
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::routing::get;
-use axum::Router;
+use axum::{Json, Router};
+use serde_json::json;
+
+use crate::controllers::admin::admin_router;
+use crate::controllers::blocks::blocks_router;
+use crate::controllers::errors::AppError;
+use crate::metrics::metrics_handler;
+use crate::workers::run_history::workers_health;
 
 pub fn app_router() -> Router {
-    Router::new().route("/health", get(root)).nest("/v1/dev", dev_routes()).fallback(handler_404)
+    Router::new()
+        .route("/health", get(root))
+        .route("/health/workers", get(workers_health_handler))
+        .route("/metrics", get(metrics_handler))
+        .nest("/blocks", blocks_router())
+        .nest("/v1/admin", admin_router())
+        .nest("/v1/dev", dev_routes())
+        .fallback(handler_404)
 }
 
 async fn root() -> &'static str {
     "UP"
 }
 
+/// Health of the workers from their run history, 503 once one of them went too long without a
+/// successful run
+async fn workers_health_handler() -> Result<impl IntoResponse, AppError> {
+    let workers = workers_health().await?;
+    let healthy = workers.iter().all(|worker| worker.healthy);
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    Ok((status, Json(json!({ "healthy": healthy, "workers": workers }))))
+}
+
 async fn handler_404() -> impl IntoResponse {
     (StatusCode::NOT_FOUND, "The requested resource was not found")
 }
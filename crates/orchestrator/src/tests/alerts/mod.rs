@@ -0,0 +1,88 @@
+use httpmock::prelude::*;
+use rstest::rstest;
+
+use crate::alerts::{
+    dispatch_alert, Alert, AlertKind, AlertSeverity, AlertSink, PagerDutyAlertSink, SlackAlertSink, WebhookAlertSink,
+};
+use crate::deployment::DEPLOYMENT;
+
+fn alert(kind: AlertKind, message: &str) -> Alert<'_> {
+    Alert { kind, severity: kind.severity(), message, deployment: &DEPLOYMENT }
+}
+
+#[rstest]
+#[case(AlertKind::JobFailed, AlertSeverity::Critical)]
+#[case(AlertKind::WorkerHalted, AlertSeverity::Critical)]
+#[case(AlertKind::BlockLag, AlertSeverity::Critical)]
+#[case(AlertKind::AuditMismatch, AlertSeverity::Critical)]
+#[case(AlertKind::LowBalance, AlertSeverity::Warning)]
+#[case(AlertKind::OrphanTransaction, AlertSeverity::Warning)]
+#[case(AlertKind::BlockCostCap, AlertSeverity::Warning)]
+fn test_alert_severity(#[case] kind: AlertKind, #[case] severity: AlertSeverity) {
+    assert_eq!(kind.severity(), severity);
+}
+
+/// Tests that each sink posts the alert in the format of its service.
+#[rstest]
+#[tokio::test]
+async fn test_alert_sinks() {
+    let server = MockServer::start();
+    let slack_mock = server.mock(|when, then| {
+        when.method(POST).path("/slack").body_contains("WorkerHalted").body_contains("Worker snos is halted");
+        then.status(200);
+    });
+    let pagerduty_mock = server.mock(|when, then| {
+        when.method(POST).path("/pagerduty").json_body_partial(
+            r#"{
+                "routing_key": "routing-key",
+                "event_action": "trigger",
+                "payload": { "summary": "Worker snos is halted", "severity": "critical", "component": "worker_halted" }
+            }"#,
+        );
+        then.status(202);
+    });
+    let webhook_mock = server.mock(|when, then| {
+        when.method(POST).path("/webhook").json_body_partial(
+            r#"{ "kind": "worker_halted", "severity": "critical", "message": "Worker snos is halted" }"#,
+        );
+        then.status(200);
+    });
+
+    let sinks: Vec<Box<dyn AlertSink>> = vec![
+        Box::new(SlackAlertSink::new(server.url("/slack"))),
+        Box::new(PagerDutyAlertSink::new("routing-key".to_string(), server.url("/pagerduty"))),
+        Box::new(WebhookAlertSink::new(server.url("/webhook"))),
+    ];
+    dispatch_alert(&alert(AlertKind::WorkerHalted, "Worker snos is halted"), &sinks).await;
+
+    slack_mock.assert();
+    pagerduty_mock.assert();
+    webhook_mock.assert();
+}
+
+/// Tests that a sink failing to take the alert doesn't keep it from the other sinks.
+#[rstest]
+#[tokio::test]
+async fn test_alert_sink_failure() {
+    let server = MockServer::start();
+    let failing_mock = server.mock(|when, then| {
+        when.method(POST).path("/failing");
+        then.status(500);
+    });
+    let webhook_mock = server.mock(|when, then| {
+        when.method(POST).path("/webhook").json_body_partial(r#"{ "kind": "block_lag" }"#);
+        then.status(200);
+    });
+
+    let sinks: Vec<Box<dyn AlertSink>> = vec![
+        Box::new(WebhookAlertSink::new(server.url("/failing"))),
+        Box::new(WebhookAlertSink::new(server.url("/webhook"))),
+    ];
+    let failing_sink_result = sinks[0].send(&alert(AlertKind::BlockLag, "Settlement is lagging")).await;
+    assert!(failing_sink_result.is_err());
+
+    dispatch_alert(&alert(AlertKind::BlockLag, "Settlement is lagging"), &sinks).await;
+
+    failing_mock.assert_hits(2);
+    webhook_mock.assert();
+}
@@ -0,0 +1,56 @@
+use hyper::service::Service;
+use hyper::Uri;
+use rstest::rstest;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::aws_http_client::ProxyTunnelConnector;
+
+/// Proxy answering the first `CONNECT` with `response`, then echoing what goes through the tunnel.
+/// Returns its url and the request it received.
+async fn start_proxy(response: &'static str) -> (Uri, tokio::task::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap()).parse().unwrap();
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut byte = [0u8; 1];
+        while !request.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).await.unwrap();
+            request.push(byte[0]);
+        }
+        stream.write_all(response.as_bytes()).await.unwrap();
+        let mut tunnelled = [0u8; 4];
+        if stream.read_exact(&mut tunnelled).await.is_ok() {
+            stream.write_all(&tunnelled).await.unwrap();
+        }
+        String::from_utf8(request).unwrap()
+    });
+    (url, handle)
+}
+
+#[rstest]
+#[case("https://bucket.s3.amazonaws.com/key", "bucket.s3.amazonaws.com:443")]
+#[case("http://localhost.localstack.cloud:4566", "localhost.localstack.cloud:4566")]
+#[tokio::test]
+async fn test_proxy_tunnel(#[case] destination: &str, #[case] target: &str) {
+    let (proxy, handle) = start_proxy("HTTP/1.1 200 Connection established\r\n\r\n").await;
+
+    let mut stream = ProxyTunnelConnector::new(proxy).call(destination.parse().unwrap()).await.unwrap();
+    stream.write_all(b"ping").await.unwrap();
+    let mut echoed = [0u8; 4];
+    stream.read_exact(&mut echoed).await.unwrap();
+
+    assert_eq!(&echoed, b"ping");
+    assert_eq!(handle.await.unwrap(), format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", target));
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_proxy_tunnel_refused() {
+    let (proxy, _handle) = start_proxy("HTTP/1.1 407 Proxy Authentication Required\r\n\r\n").await;
+
+    let error = ProxyTunnelConnector::new(proxy).call("https://sqs.us-east-1.amazonaws.com".parse().unwrap()).await;
+
+    assert!(error.unwrap_err().to_string().contains("407 Proxy Authentication Required"));
+}
@@ -22,13 +22,37 @@ use crate::data_storage::aws_s3::AWSS3;
 use crate::data_storage::{DataStorage, DataStorageConfig, MockDataStorage};
 use crate::database::mongodb::config::MongoDbConfig;
 use crate::database::mongodb::MongoDb;
-use crate::database::{DatabaseConfig, MockDatabase};
+use crate::database::{Database, DatabaseConfig, MockDatabase};
 use crate::jobs::types::JobStatus::Created;
 use crate::jobs::types::JobType::DataSubmission;
-use crate::jobs::types::{ExternalId, JobItem};
-use crate::queue::job_queue::{JOB_PROCESSING_QUEUE, JOB_VERIFICATION_QUEUE};
+use crate::jobs::types::{ExternalId, JobCounters, JobItem, JobPriority};
+use crate::queue::job_queue::{job_processing_queues, JOB_HANDLE_FAILURE_QUEUE, JOB_VERIFICATION_QUEUE};
 use crate::queue::MockQueueProvider;
 
+/// Sets an environment variable for the scope of a test, the previous value is restored when the
+/// guard is dropped, even if the test panics
+pub struct EnvVarGuard {
+    name: &'static str,
+    previous: Option<String>,
+}
+
+impl EnvVarGuard {
+    pub fn set(name: &'static str, value: &str) -> Self {
+        let previous = std::env::var(name).ok();
+        std::env::set_var(name, value);
+        Self { name, previous }
+    }
+}
+
+impl Drop for EnvVarGuard {
+    fn drop(&mut self) {
+        match &self.previous {
+            Some(value) => std::env::set_var(self.name, value),
+            None => std::env::remove_var(self.name),
+        }
+    }
+}
+
 pub async fn init_config(
     rpc_url: Option<String>,
     database: Option<MockDatabase>,
@@ -37,11 +61,26 @@ pub async fn init_config(
     prover_client: Option<MockProverClient>,
     settlement_client: Option<MockSettlementClient>,
     storage_client: Option<MockDataStorage>,
+) -> Config {
+    let database = Box::new(database.unwrap_or_default());
+    init_config_with_database(rpc_url, database, queue, da_client, prover_client, settlement_client, storage_client)
+        .await
+}
+
+/// Same as [`init_config`] with a real database, e.g. an in-memory SQLite one, for the tests
+/// going through the database updates
+pub async fn init_config_with_database(
+    rpc_url: Option<String>,
+    database: Box<dyn Database>,
+    queue: Option<MockQueueProvider>,
+    da_client: Option<MockDaClient>,
+    prover_client: Option<MockProverClient>,
+    settlement_client: Option<MockSettlementClient>,
+    storage_client: Option<MockDataStorage>,
 ) -> Config {
     let _ = tracing_subscriber::fmt().with_max_level(tracing::Level::INFO).with_target(false).try_init();
 
     let rpc_url = rpc_url.unwrap_or(MADARA_RPC_URL.to_string());
-    let database = database.unwrap_or_default();
     let queue = queue.unwrap_or_default();
     let da_client = da_client.unwrap_or_default();
     let prover_client = prover_client.unwrap_or_default();
@@ -56,7 +95,7 @@ pub async fn init_config(
         Box::new(da_client),
         Box::new(prover_client),
         Box::new(settlement_client),
-        Box::new(database),
+        database,
         Box::new(queue),
         Box::new(storage_client),
     )
@@ -72,6 +111,11 @@ pub fn default_job_item() -> JobItem {
         external_id: ExternalId::String("0".to_string().into_boxed_str()),
         metadata: HashMap::new(),
         version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
     }
 }
 
@@ -84,11 +128,12 @@ pub fn custom_job_item(default_job_item: JobItem, #[default(String::from("0"))]
 }
 
 pub async fn drop_database() -> color_eyre::Result<()> {
+    let database_name = MongoDbConfig::new_from_env().database_name;
     let db_client: Client = MongoDb::new(MongoDbConfig::new_from_env()).await.client();
     // dropping all the collection.
     // use .collection::<JobItem>("<collection_name>")
     // if only particular collection is to be dropped
-    db_client.database("orchestrator").drop(None).await?;
+    db_client.database(&database_name).drop(None).await?;
     Ok(())
 }
 
@@ -100,17 +145,20 @@ pub async fn create_sqs_queues() -> color_eyre::Result<()> {
     // Dropping sqs queues
     let list_queues_output = sqs_client.list_queues().send().await?;
     let queue_urls = list_queues_output.queue_urls();
-    log::debug!("Found {} queues", queue_urls.len());
+    tracing::debug!("Found {} queues", queue_urls.len());
     for queue_url in queue_urls {
         match sqs_client.delete_queue().queue_url(queue_url).send().await {
-            Ok(_) => log::debug!("Successfully deleted queue: {}", queue_url),
+            Ok(_) => tracing::debug!("Successfully deleted queue: {}", queue_url),
             Err(e) => eprintln!("Error deleting queue {}: {:?}", queue_url, e),
         }
     }
 
     // Creating SQS queues
-    sqs_client.create_queue().queue_name(JOB_PROCESSING_QUEUE).send().await?;
+    for queue in job_processing_queues() {
+        sqs_client.create_queue().queue_name(queue).send().await?;
+    }
     sqs_client.create_queue().queue_name(JOB_VERIFICATION_QUEUE).send().await?;
+    sqs_client.create_queue().queue_name(JOB_HANDLE_FAILURE_QUEUE).send().await?;
     Ok(())
 }
 
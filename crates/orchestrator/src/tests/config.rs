@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
-use crate::config::{build_da_client, build_prover_service, build_settlement_client, config_force_init, Config};
+use crate::config::{
+    build_da_client, build_prover_service, build_queue_client, build_settlement_client, config_force_init, Config,
+};
 use crate::data_storage::DataStorage;
 use da_client_interface::DaClient;
 use httpmock::MockServer;
@@ -9,13 +11,12 @@ use prover_client_interface::ProverClient;
 use settlement_client_interface::SettlementClient;
 use starknet::providers::jsonrpc::HttpTransport;
 use starknet::providers::{JsonRpcClient, Url};
-use utils::env_utils::get_env_var_or_panic;
+use utils::env_utils::{get_env_var_or_default, get_env_var_or_panic};
 use utils::settings::default::DefaultSettingsProvider;
 
 use crate::database::mongodb::config::MongoDbConfig;
 use crate::database::mongodb::MongoDb;
 use crate::database::{Database, DatabaseConfig};
-use crate::queue::sqs::SqsQueue;
 use crate::queue::QueueProvider;
 use crate::tests::common::{create_sqs_queues, drop_database, get_storage_client};
 
@@ -84,7 +85,7 @@ impl TestConfigBuilder {
 
         // init the DA client
         if self.da_client.is_none() {
-            self.da_client = Some(build_da_client().await);
+            self.da_client = Some(build_da_client(&settings_provider).await);
         }
 
         // init the Settings client
@@ -107,8 +108,14 @@ impl TestConfigBuilder {
             }
         }
 
-        // Deleting and Creating the queues in sqs.
-        create_sqs_queues().await.expect("Not able to delete and create the queues.");
+        // init the queue client
+        if self.queue.is_none() {
+            self.queue = Some(build_queue_client().await);
+            if get_env_var_or_default("QUEUE", "sqs") == "sqs" {
+                // Deleting and Creating the queues in sqs.
+                create_sqs_queues().await.expect("Not able to delete and create the queues.");
+            }
+        }
         // Deleting the database
         drop_database().await.expect("Unable to drop the database.");
 
@@ -123,7 +130,7 @@ impl TestConfigBuilder {
             self.prover_client.unwrap_or_else(|| build_prover_service(&settings_provider)),
             self.settlement_client.unwrap(),
             self.database.unwrap(),
-            self.queue.unwrap_or_else(|| Box::new(SqsQueue {})),
+            self.queue.unwrap(),
             self.storage.unwrap(),
         );
 
@@ -1,4 +1,4 @@
-use crate::data_storage::aws_s3::config::{AWSS3ConfigType, S3LocalStackConfig};
+use crate::data_storage::aws_s3::config::{AWSS3ConfigType, S3LocalStackConfig, DEFAULT_MULTIPART_CHUNK_SIZE_BYTES};
 use crate::data_storage::aws_s3::AWSS3;
 use crate::data_storage::{DataStorage, DataStorageConfig};
 use crate::tests::config::TestConfigBuilder;
@@ -41,3 +41,228 @@ async fn test_put_and_get_data_s3() -> color_eyre::Result<()> {
 
     Ok(())
 }
+
+/// The data bigger than one part is uploaded with a multipart upload and downloaded in chunks,
+/// the parts must come back in order.
+#[rstest]
+#[tokio::test]
+async fn test_put_and_get_multipart_data_s3() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+
+    dotenvy::from_filename("../.env.test")?;
+
+    let config = S3LocalStackConfig::new_from_env();
+    let s3_client = AWSS3::new(AWSS3ConfigType::WithEndpoint(config)).await;
+    s3_client.build_test_bucket(&get_env_var_or_panic("AWS_S3_BUCKET_NAME")).await.unwrap();
+
+    let data: Vec<u8> = (0..2 * DEFAULT_MULTIPART_CHUNK_SIZE_BYTES + 1).map(|i| (i % 251) as u8).collect();
+    let key = "test_multipart_data.bin";
+
+    s3_client.put_data(Bytes::from(data.clone()), key).await.expect("Unable to put data into the bucket.");
+    let received = s3_client.get_data(key).await.expect("Unable to get the data from the bucket.");
+
+    assert_eq!(received.len(), data.len());
+    assert!(received.as_ref() == data.as_slice());
+
+    Ok(())
+}
+
+/// The keys are listed across the pages S3 returns them in, and are gone once deleted.
+#[rstest]
+#[tokio::test]
+async fn test_list_and_delete_data_s3() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+
+    dotenvy::from_filename("../.env.test")?;
+
+    let config = S3LocalStackConfig::new_from_env();
+    let s3_client = AWSS3::new(AWSS3ConfigType::WithEndpoint(config)).await;
+    s3_client.build_test_bucket(&get_env_var_or_panic("AWS_S3_BUCKET_NAME")).await.unwrap();
+
+    // more keys than S3 returns in one page
+    let keys: Vec<String> = (0..1001).map(|i| format!("test_list/{:04}", i)).collect();
+    futures::future::try_join_all(keys.iter().map(|key| s3_client.put_data(Bytes::from_static(b"data"), key))).await?;
+    s3_client.put_data(Bytes::from_static(b"data"), "test_list_other").await?;
+
+    let mut listed = s3_client.list_keys("test_list/").await?;
+    listed.sort();
+    assert_eq!(listed, keys);
+
+    assert!(s3_client.data_exists(&keys[0]).await?);
+    s3_client.delete_data(&keys[0]).await?;
+    assert!(!s3_client.data_exists(&keys[0]).await?);
+    // deleting it again is not an error
+    s3_client.delete_data(&keys[0]).await?;
+    assert_eq!(s3_client.list_keys("test_list/").await?.len(), keys.len() - 1);
+
+    Ok(())
+}
+
+/// The streamed data bigger than one part is uploaded part by part, in chunks which don't match
+/// the parts, and comes back whole when streamed back.
+#[rstest]
+#[case(100)]
+#[case(2 * DEFAULT_MULTIPART_CHUNK_SIZE_BYTES + 1)]
+#[tokio::test]
+async fn test_put_and_get_data_stream_s3(#[case] size: usize) -> color_eyre::Result<()> {
+    use futures::{StreamExt, TryStreamExt};
+
+    TestConfigBuilder::new().build().await;
+
+    dotenvy::from_filename("../.env.test")?;
+
+    let config = S3LocalStackConfig::new_from_env();
+    let s3_client = AWSS3::new(AWSS3ConfigType::WithEndpoint(config)).await;
+    s3_client.build_test_bucket(&get_env_var_or_panic("AWS_S3_BUCKET_NAME")).await.unwrap();
+
+    let data: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+    let chunks: Vec<color_eyre::Result<Bytes>> =
+        data.chunks(1024 * 1024 + 7).map(|chunk| Ok(Bytes::copy_from_slice(chunk))).collect();
+    let key = format!("test_stream_data_{}.bin", size);
+
+    s3_client.put_data_stream(futures::stream::iter(chunks).boxed(), &key).await?;
+    let received: Vec<Bytes> = s3_client.get_data_stream(&key).await?.try_collect().await?;
+
+    assert!(received.concat() == data);
+
+    Ok(())
+}
+
+/// The objects are compressed on put and decompressed on get, the objects written before the
+/// compression was enabled being read as they are.
+#[rstest]
+#[tokio::test]
+async fn test_compressed_storage_round_trip() -> color_eyre::Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    use crate::codec::CodecKind;
+    use crate::data_storage::compressed::CompressedStorage;
+    use crate::data_storage::MockDataStorage;
+
+    let stored = Arc::new(Mutex::new(Bytes::new()));
+    let mut inner = MockDataStorage::new();
+    let stored_clone = Arc::clone(&stored);
+    inner.expect_put_data().times(1).returning(move |data, _| {
+        *stored_clone.lock().unwrap() = data;
+        Ok(())
+    });
+    let stored_clone = Arc::clone(&stored);
+    inner
+        .expect_get_data()
+        .withf(|key| key == "compressed")
+        .returning(move |_| Ok(stored_clone.lock().unwrap().clone()));
+    inner.expect_get_data().withf(|key| key == "legacy").returning(|_| Ok(Bytes::from_static(b"legacy data")));
+
+    let storage = CompressedStorage::new(Box::new(inner), "zstd".parse::<CodecKind>()?.build());
+    let data: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+
+    storage.put_data(Bytes::from(data.clone()), "compressed").await?;
+    assert!(stored.lock().unwrap().len() < data.len());
+    assert!(storage.get_data("compressed").await? == data);
+    assert_eq!(storage.get_data("legacy").await?, Bytes::from_static(b"legacy data"));
+
+    Ok(())
+}
+
+/// The objects are encrypted on put and decrypted on get, bound to the key they are stored under.
+#[rstest]
+#[tokio::test]
+async fn test_encrypted_storage_round_trip() -> color_eyre::Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    use crate::data_storage::encrypted::EncryptedStorage;
+    use crate::data_storage::MockDataStorage;
+
+    let stored = Arc::new(Mutex::new(Bytes::new()));
+    let mut inner = MockDataStorage::new();
+    let stored_clone = Arc::clone(&stored);
+    inner.expect_put_data().times(1).returning(move |data, _| {
+        *stored_clone.lock().unwrap() = data;
+        Ok(())
+    });
+    let stored_clone = Arc::clone(&stored);
+    inner.expect_get_data().returning(move |_| Ok(stored_clone.lock().unwrap().clone()));
+
+    let storage = EncryptedStorage::new(Box::new(inner), &[7u8; 32]);
+    let data = Bytes::from_static(b"proof of the block");
+
+    storage.put_data(data.clone(), "1/proof.json").await?;
+    assert!(!stored.lock().unwrap().windows(data.len()).any(|window| window == data.as_ref()));
+    assert_eq!(storage.get_data("1/proof.json").await?, data);
+    // the object doesn't decrypt under another key
+    assert!(storage.get_data("2/proof.json").await.is_err());
+
+    Ok(())
+}
+
+/// The data is downloaded from the presigned URL without the credentials of the storage.
+#[rstest]
+#[tokio::test]
+async fn test_presigned_get_url_s3() -> color_eyre::Result<()> {
+    use std::time::Duration;
+
+    TestConfigBuilder::new().build().await;
+
+    dotenvy::from_filename("../.env.test")?;
+
+    let config = S3LocalStackConfig::new_from_env();
+    let s3_client = AWSS3::new(AWSS3ConfigType::WithEndpoint(config)).await;
+    s3_client.build_test_bucket(&get_env_var_or_panic("AWS_S3_BUCKET_NAME")).await.unwrap();
+
+    let key = "test_presigned_data.txt";
+    s3_client.put_data(Bytes::from_static(b"presigned data"), key).await?;
+
+    let url = s3_client.generate_presigned_get_url(key, Duration::from_secs(60)).await?;
+    let downloaded = reqwest::get(url).await?.error_for_status()?.bytes().await?;
+    assert_eq!(downloaded.as_ref(), b"presigned data");
+
+    Ok(())
+}
+
+#[rstest]
+fn test_storage_key_schema() {
+    use crate::data_storage::key::{ArtifactKind, StorageKey};
+
+    let key = StorageKey::new(ArtifactKind::CairoPie, 42).chain_id("SN_SEPOLIA");
+    assert_eq!(key.to_string(), "SN_SEPOLIA/cairo_pie/42/v1/cairo_pie.zip");
+    assert_eq!(key.clone().version(2).to_string(), "SN_SEPOLIA/cairo_pie/42/v2/cairo_pie.zip");
+    assert_eq!(key.flat_key(), "42/cairo_pie.zip");
+
+    // the keys of two chains don't collide
+    assert_ne!(key.to_string(), key.clone().chain_id("SN_MAIN").to_string());
+
+    assert_eq!(StorageKey::from_flat_key("42/proof.json"), Some(StorageKey::new(ArtifactKind::Proof, 42)));
+    assert_eq!(StorageKey::from_flat_key("archive/jobs/1.jsonl"), None);
+    assert_eq!(StorageKey::from_flat_key("42/unknown.json"), None);
+}
+
+/// The artifacts under a flat key are copied to their namespaced key, once.
+#[rstest]
+#[tokio::test]
+async fn test_migrate_flat_keys() -> color_eyre::Result<()> {
+    use mockall::predicate::eq;
+
+    use crate::data_storage::key::{migrate_flat_keys, ArtifactKind, StorageKey};
+    use crate::data_storage::MockDataStorage;
+
+    let proof_key = StorageKey::new(ArtifactKind::Proof, 1).to_string();
+    let pie_key = StorageKey::new(ArtifactKind::CairoPie, 1).to_string();
+
+    let mut storage = MockDataStorage::new();
+    storage.expect_list_keys().with(eq("")).returning(|_| {
+        Ok(vec!["1/proof.json".to_string(), "1/cairo_pie.zip".to_string(), "archive/jobs/1.jsonl".to_string()])
+    });
+    storage.expect_data_exists().with(eq(proof_key.clone())).returning(|_| Ok(false));
+    // already copied by a previous run
+    storage.expect_data_exists().with(eq(pie_key)).returning(|_| Ok(true));
+    storage.expect_get_data().with(eq("1/proof.json")).times(1).returning(|_| Ok(Bytes::from_static(b"proof")));
+    storage
+        .expect_put_data()
+        .with(eq(Bytes::from_static(b"proof")), eq(proof_key))
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    assert_eq!(migrate_flat_keys(&storage).await?, 1);
+
+    Ok(())
+}
@@ -1,9 +1,23 @@
 use crate::config::{config, Config};
-use crate::jobs::types::{ExternalId, JobItem, JobStatus, JobType};
+use crate::database::migrations::{latest_schema_version, run_migrations, MigrationTarget};
+use crate::database::mongodb::config::MongoDbConfig;
+use crate::database::mongodb::{MongoDb, JOB_INDEXES};
+use crate::database::sqlite::config::SqliteDbConfig;
+use crate::database::sqlite::SqliteDb;
+use crate::database::types::{
+    now_secs, BlockCost, DatabaseWrite, JobEvent, JobFilter, JobPage, JobStats, WorkerRun,
+};
+use crate::database::Database;
+use crate::jobs::types::{
+    ExternalId, IllegalStatusTransitionError, JobCounter, JobCounters, JobItem, JobPriority, JobStatus, JobType,
+};
 use crate::tests::config::TestConfigBuilder;
+use crate::workers::state::WorkerState;
 use arc_swap::Guard;
 use rstest::*;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
 #[rstest]
@@ -51,6 +65,679 @@ async fn test_database_create_job(#[future] get_config: Guard<Arc<Config>>) -> c
     Ok(())
 }
 
+/// Tests that the indexes of the jobs are created on startup and that a job can't be created
+/// twice for the same internal id and type.
+#[rstest]
+#[tokio::test]
+async fn test_database_job_indexes() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+    // the test config drops the database once connected, the indexes are created again
+    let database_client = MongoDb::new(MongoDbConfig::new_from_env()).await;
+
+    let index_names = database_client.job_index_names().await?;
+    for (name, _, _) in JOB_INDEXES {
+        assert!(index_names.contains(&name.to_string()), "missing index {}", name);
+    }
+
+    database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Created, 1)).await?;
+    assert!(database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Created, 1)).await.is_err());
+    database_client.create_job(build_job_item(JobType::ProofCreation, JobStatus::Created, 1)).await?;
+
+    Ok(())
+}
+
+/// Tests that the orchestrators sharing a database with different collection prefixes don't see
+/// each other's jobs.
+#[rstest]
+#[tokio::test]
+async fn test_database_collection_prefix() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+    let config_a = MongoDbConfig { collection_prefix: "appchain_a_".to_string(), ..MongoDbConfig::new_from_env() };
+    let appchain_a = MongoDb::new(config_a).await;
+    let config_b = MongoDbConfig { collection_prefix: "appchain_b_".to_string(), ..MongoDbConfig::new_from_env() };
+    let appchain_b = MongoDb::new(config_b).await;
+
+    let job = build_job_item(JobType::SnosRun, JobStatus::Created, 1);
+    appchain_a.create_job(job.clone()).await?;
+    assert_eq!(appchain_a.get_job_by_id(job.id).await?, Some(job.clone()));
+    assert_eq!(appchain_b.get_job_by_id(job.id).await?, None);
+    // the same block has a job for each appchain
+    appchain_b.create_job(build_job_item(JobType::SnosRun, JobStatus::Created, 1)).await?;
+
+    // the successors are looked up in the collection of the appchain
+    appchain_a.create_job(build_job_item(JobType::SnosRun, JobStatus::Completed, 2)).await?;
+    appchain_a.create_job(build_job_item(JobType::ProofCreation, JobStatus::Created, 2)).await?;
+    appchain_a.create_job(build_job_item(JobType::SnosRun, JobStatus::Completed, 3)).await?;
+    let without_successor =
+        appchain_a.get_jobs_without_successor(JobType::SnosRun, JobStatus::Completed, JobType::ProofCreation).await?;
+    assert_eq!(without_successor.iter().map(|job| job.internal_id.as_str()).collect::<Vec<_>>(), vec!["3"]);
+
+    Ok(())
+}
+
+/// Tests that the worker state is stored per worker and key, and that values are overwritten.
+#[rstest]
+#[tokio::test]
+async fn test_database_worker_state() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+
+    let snos_state = WorkerState::new("snos");
+    let da_state = WorkerState::new("data_submission");
+
+    assert_eq!(snos_state.last_processed_block().await?, None);
+
+    snos_state.set_last_processed_block(10).await?;
+    da_state.set_last_processed_block(4).await?;
+    snos_state.set_last_processed_block(11).await?;
+    snos_state.set("pending_blocks", &vec![12_u64, 13]).await?;
+
+    assert_eq!(snos_state.last_processed_block().await?, Some(11));
+    assert_eq!(da_state.last_processed_block().await?, Some(4));
+    assert_eq!(snos_state.get::<Vec<u64>>("pending_blocks").await?, Some(vec![12, 13]));
+    assert_eq!(da_state.get::<Vec<u64>>("pending_blocks").await?, None);
+
+    Ok(())
+}
+
+/// Tests that the SQLite database round-trips the jobs and rejects the updates of outdated jobs.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_optimistic_locking() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+
+    let mut job = build_job_item(JobType::SnosRun, JobStatus::LockedForProcessing, 1);
+    job.metadata.insert("key".to_string(), "value".to_string());
+    database_client.create_job(job.clone()).await?;
+    assert_eq!(database_client.get_job_by_id(job.id).await?, Some(job.clone()));
+
+    let mut updated_job = job.clone();
+    updated_job.status = JobStatus::PendingVerification;
+    updated_job.version = 1;
+    database_client.update_job(&updated_job).await?;
+
+    // the job read before the update is outdated
+    assert!(database_client.update_job_status(&job, JobStatus::Completed).await.is_err());
+    database_client.update_job_status(&updated_job, JobStatus::Completed).await?;
+    let stored_job = database_client.get_job_by_internal_id_and_type("1", &JobType::SnosRun).await?.unwrap();
+    assert_eq!(stored_job.status, JobStatus::Completed);
+    assert_eq!(stored_job.version, 1);
+
+    Ok(())
+}
+
+/// Tests that the SQLite database can't hold two jobs of the same type and internal id.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_job_unique_by_type_and_internal_id() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+
+    database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Created, 1)).await?;
+    assert!(database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Created, 1)).await.is_err());
+    database_client.create_job(build_job_item(JobType::ProofCreation, JobStatus::Created, 1)).await?;
+
+    Ok(())
+}
+
+/// Tests that the SQLite database refuses the updates moving a job to a status it can't reach
+/// from its stored one.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_illegal_status_transition() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    let job = build_job_item(JobType::SnosRun, JobStatus::Completed, 1);
+    database_client.create_job(job.clone()).await?;
+
+    let error = database_client.update_job_status(&job, JobStatus::LockedForProcessing).await.unwrap_err();
+    let error = error.downcast_ref::<IllegalStatusTransitionError>().expect("the transition should be illegal");
+    assert_eq!((error.from.clone(), error.to.clone()), (JobStatus::Completed, JobStatus::LockedForProcessing));
+    let stored_job = database_client.get_job_by_id(job.id).await?.unwrap();
+    assert_eq!(stored_job.status, JobStatus::Completed);
+
+    // the metadata updates don't move the job
+    database_client.update_metadata(&job, HashMap::from([("key".to_string(), "value".to_string())])).await?;
+    database_client.update_job_status(&job, JobStatus::Created).await?;
+    let stored_job = database_client.get_job_by_id(job.id).await?.unwrap();
+    assert_eq!(stored_job.status, JobStatus::Created);
+
+    Ok(())
+}
+
+/// Tests the job queries of the workers on the SQLite database.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_job_queries() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+
+    for internal_id in 1..=3 {
+        database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Completed, internal_id)).await?;
+    }
+    database_client.create_job(build_job_item(JobType::ProofCreation, JobStatus::Created, 2)).await?;
+
+    let without_successor = database_client
+        .get_jobs_without_successor(JobType::SnosRun, JobStatus::Completed, JobType::ProofCreation)
+        .await?;
+    let internal_ids: Vec<&str> = without_successor.iter().map(|job| job.internal_id.as_str()).collect();
+    assert_eq!(internal_ids, vec!["1", "3"]);
+
+    let latest_job = database_client.get_latest_job_by_type(JobType::SnosRun).await?.unwrap();
+    assert_eq!(latest_job.internal_id, "3");
+    let latest_jobs = database_client.get_latest_jobs_by_type(JobType::SnosRun, 0, 2).await?;
+    assert_eq!(latest_jobs.iter().map(|job| job.internal_id.as_str()).collect::<Vec<_>>(), vec!["3", "2"]);
+    let older_jobs = database_client.get_latest_jobs_by_type(JobType::SnosRun, 2, 2).await?;
+    assert_eq!(older_jobs.iter().map(|job| job.internal_id.as_str()).collect::<Vec<_>>(), vec!["1"]);
+    assert_eq!(database_client.get_jobs_by_statuses(vec![JobStatus::Created], None).await?.len(), 1);
+    assert_eq!(database_client.get_jobs_by_statuses(vec![JobStatus::Completed], Some(2)).await?.len(), 2);
+
+    database_client.set_worker_state("snos", "last_processed_block", 3.into()).await?;
+    database_client.set_worker_state("snos", "last_processed_block", 4.into()).await?;
+    assert_eq!(database_client.get_worker_state("snos", "last_processed_block").await?, Some(4.into()));
+    assert_eq!(database_client.get_worker_state("snos", "pending_blocks").await?, None);
+
+    Ok(())
+}
+
+/// Tests that the history of a job is returned in the order it was appended, with its errors.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_job_history() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    let job = build_job_item(JobType::SnosRun, JobStatus::Created, 1);
+
+    let locked = JobEvent::new(&job, JobStatus::Created, JobStatus::LockedForProcessing, "worker".to_string());
+    let failed = JobEvent::new(&job, JobStatus::LockedForProcessing, JobStatus::Blocked, "worker".to_string())
+        .with_error("SNOS output diverges".to_string());
+    database_client.append_job_event(locked.clone()).await?;
+    database_client.append_job_event(failed.clone()).await?;
+
+    assert_eq!(database_client.get_job_events(job.id).await?, vec![locked, failed]);
+    assert!(database_client.get_job_events(Uuid::new_v4()).await?.is_empty());
+
+    Ok(())
+}
+
+/// Tests that the job listing pages through the jobs matching the filter, in creation order.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_jobs_paginated() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    for internal_id in [8, 9, 10, 11] {
+        database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Completed, internal_id)).await?;
+    }
+    database_client.create_job(build_job_item(JobType::ProofCreation, JobStatus::Completed, 9)).await?;
+    let mut non_numeric_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 0);
+    non_numeric_job.internal_id = "0xbeef".to_string();
+    database_client.create_job(non_numeric_job).await?;
+
+    let filter = JobFilter { job_type: Some(JobType::SnosRun), internal_id_range: Some(9..=11), ..Default::default() };
+    let first_page = database_client.get_jobs_paginated(filter.clone(), None, 2).await?;
+    let second_page = database_client.get_jobs_paginated(filter.clone(), first_page.next_cursor.clone(), 2).await?;
+
+    let internal_ids = |page: &JobPage| page.jobs.iter().map(|job| job.internal_id.clone()).collect::<Vec<_>>();
+    assert_eq!(internal_ids(&first_page), vec!["9", "10"]);
+    assert_eq!(internal_ids(&second_page), vec!["11"]);
+    assert_eq!(second_page.next_cursor, None);
+
+    let created_later = JobFilter { created_after: Some(now_secs() + 60), ..Default::default() };
+    assert!(database_client.get_jobs_paginated(created_later, None, 10).await?.jobs.is_empty());
+    let created_status = JobFilter { status: Some(JobStatus::Created), ..Default::default() };
+    assert!(database_client.get_jobs_paginated(created_status, None, 10).await?.jobs.is_empty());
+
+    Ok(())
+}
+
+/// Tests the counts by type and status and the age of the oldest pending job.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_job_stats() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    assert_eq!(database_client.get_job_stats().await?, JobStats::default());
+
+    let mut oldest_pending_job = build_job_item(JobType::SnosRun, JobStatus::Created, 1);
+    oldest_pending_job.created_at = now_secs() - 600;
+    let mut old_completed_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 2);
+    old_completed_job.created_at = now_secs() - 6000;
+    database_client.create_job(oldest_pending_job).await?;
+    database_client.create_job(old_completed_job).await?;
+    database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Created, 3)).await?;
+    database_client.create_job(build_job_item(JobType::ProofCreation, JobStatus::Blocked, 1)).await?;
+
+    let stats = database_client.get_job_stats().await?;
+    let count = |job_type: JobType, status: JobStatus| {
+        stats.counts.iter().find(|count| count.job_type == job_type && count.status == status).map(|count| count.count)
+    };
+    assert_eq!(stats.counts.len(), 3);
+    assert_eq!(count(JobType::SnosRun, JobStatus::Created), Some(2));
+    assert_eq!(count(JobType::SnosRun, JobStatus::Completed), Some(1));
+    assert_eq!(count(JobType::ProofCreation, JobStatus::Blocked), Some(1));
+    assert_eq!(stats.count_in_statuses(&[JobStatus::Blocked, JobStatus::VerificationFailed]), 1);
+    assert!(stats.oldest_pending_job_age.is_some_and(|age| (600..610).contains(&age)));
+
+    Ok(())
+}
+
+/// Tests that the jobs not updated for a while are stuck and that an update refreshes them.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_jobs_stuck_in_status() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    let mut stuck_job = build_job_item(JobType::SnosRun, JobStatus::LockedForProcessing, 1);
+    stuck_job.updated_at = now_secs() - 600;
+    let recent_job = build_job_item(JobType::SnosRun, JobStatus::LockedForProcessing, 2);
+    database_client.create_job(stuck_job.clone()).await?;
+    database_client.create_job(recent_job).await?;
+    database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Created, 3)).await?;
+
+    let older_than = Duration::from_secs(300);
+    let stuck_jobs = database_client.get_jobs_stuck_in_status(JobStatus::LockedForProcessing, older_than).await?;
+    assert_eq!(stuck_jobs, vec![stuck_job.clone()]);
+
+    database_client.update_job_status(&stuck_job, JobStatus::LockedForProcessing).await?;
+    let stored_job = database_client.get_job_by_id(stuck_job.id).await?.unwrap();
+    assert!(stored_job.updated_at >= now_secs() - 1);
+    assert_eq!(stored_job.created_at, stuck_job.created_at);
+    assert!(database_client.get_jobs_stuck_in_status(JobStatus::LockedForProcessing, older_than).await?.is_empty());
+
+    Ok(())
+}
+
+/// Tests that the archived jobs move out of the jobs and that archiving them again is a no-op.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_archive_jobs() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    let archived_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 1);
+    let kept_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 2);
+    database_client.create_job(archived_job.clone()).await?;
+    database_client.create_job(kept_job.clone()).await?;
+
+    assert_eq!(database_client.archive_jobs(&[archived_job.id]).await?, 1);
+    assert_eq!(database_client.archive_jobs(&[archived_job.id]).await?, 0);
+
+    assert_eq!(database_client.get_job_by_id(archived_job.id).await?, None);
+    assert_eq!(database_client.get_archived_job_by_id(archived_job.id).await?, Some(archived_job));
+    assert_eq!(database_client.get_job_by_id(kept_job.id).await?, Some(kept_job.clone()));
+    assert_eq!(database_client.get_archived_job_by_id(kept_job.id).await?, None);
+
+    Ok(())
+}
+
+/// Tests that the jobs are found by any of their parents, and only by them.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_jobs_by_parent_id() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    let snos_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 1);
+    let proving_job = build_job_item(JobType::ProofCreation, JobStatus::Completed, 1);
+    let mut state_update_job = build_job_item(JobType::StateTransition, JobStatus::PendingDependencies, 1);
+    state_update_job.parent_ids = vec![proving_job.id, snos_job.id];
+    database_client.create_job(snos_job.clone()).await?;
+    database_client.create_job(proving_job.clone()).await?;
+    database_client.create_job(state_update_job.clone()).await?;
+
+    assert_eq!(database_client.get_jobs_by_parent_id(snos_job.id).await?, vec![state_update_job.clone()]);
+    assert_eq!(database_client.get_jobs_by_parent_id(proving_job.id).await?, vec![state_update_job]);
+    assert_eq!(database_client.get_jobs_by_parent_id(Uuid::new_v4()).await?, vec![]);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_database_jobs_by_block() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    assert_jobs_by_block(config.database()).await
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_jobs_by_block() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    assert_jobs_by_block(&database_client).await
+}
+
+/// Asserts that the jobs are found by the blocks of their internal id, a single block or a range.
+async fn assert_jobs_by_block(database_client: &dyn Database) -> color_eyre::Result<()> {
+    let mut snos_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 0);
+    snos_job.internal_id = "10-12".to_string();
+    let proving_job = build_job_item(JobType::ProofCreation, JobStatus::Completed, 11);
+    let other_proving_job = build_job_item(JobType::ProofCreation, JobStatus::Created, 13);
+    let mut state_update_job = build_job_item(JobType::StateTransition, JobStatus::Created, 0);
+    state_update_job.internal_id = "9-11".to_string();
+    let mut non_block_job = build_job_item(JobType::SnosRun, JobStatus::Created, 0);
+    non_block_job.internal_id = "0x11".to_string();
+    for job in [&snos_job, &proving_job, &other_proving_job, &state_update_job, &non_block_job] {
+        database_client.create_job(job.clone()).await?;
+    }
+
+    assert_eq!(
+        database_client.get_jobs_by_block(11).await?,
+        vec![snos_job.clone(), proving_job, state_update_job.clone()]
+    );
+    assert_eq!(database_client.get_jobs_by_block(12).await?, vec![snos_job]);
+    assert_eq!(database_client.get_jobs_by_block(9).await?, vec![state_update_job]);
+    assert_eq!(database_client.get_jobs_by_block(14).await?, vec![]);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_database_latest_job_by_type() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    assert_latest_job_by_type(config.database()).await
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_latest_job_by_type() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    assert_latest_job_by_type(&database_client).await
+}
+
+/// Asserts that the latest job is the one of the highest block, the blocks being compared as
+/// numbers, across a digit boundary.
+async fn assert_latest_job_by_type(database_client: &dyn Database) -> color_eyre::Result<()> {
+    for internal_id in ["91-99", "100-199", "9", "0x1"] {
+        let mut job = build_job_item(JobType::StateTransition, JobStatus::Completed, 0);
+        job.internal_id = internal_id.to_string();
+        database_client.create_job(job).await?;
+    }
+    database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Completed, 200)).await?;
+
+    let latest_job = database_client.get_latest_job_by_type(JobType::StateTransition).await?;
+    assert_eq!(latest_job.map(|job| job.internal_id), Some("100-199".to_string()));
+    assert_eq!(database_client.get_latest_job_by_type(JobType::DataSubmission).await?, None);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_database_jobs_by_status() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    assert_jobs_by_status(config.database()).await
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_jobs_by_status() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    assert_jobs_by_status(&database_client).await
+}
+
+/// Asserts that the jobs are filtered on their status, e.g. for the latest completed block of a
+/// stage of the pipeline monitor.
+async fn assert_jobs_by_status(database_client: &dyn Database) -> color_eyre::Result<()> {
+    for internal_id in 1..=2 {
+        database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Completed, internal_id)).await?;
+    }
+    database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Created, 3)).await?;
+
+    let latest_completed_job =
+        database_client.get_latest_job_by_type_and_status(JobType::SnosRun, JobStatus::Completed).await?;
+    assert_eq!(latest_completed_job.map(|job| job.internal_id), Some("2".to_string()));
+    let completed_jobs = database_client
+        .get_jobs_after_internal_id_by_job_type(JobType::SnosRun, JobStatus::Completed, "1".to_string())
+        .await?;
+    assert_eq!(completed_jobs.iter().map(|job| job.internal_id.as_str()).collect::<Vec<_>>(), vec!["2"]);
+    let created_jobs = database_client.get_jobs_by_statuses(vec![JobStatus::Created], None).await?;
+    assert_eq!(created_jobs.iter().map(|job| job.internal_id.as_str()).collect::<Vec<_>>(), vec!["3"]);
+    assert_eq!(database_client.get_latest_job_by_type_and_status(JobType::SnosRun, JobStatus::Failed).await?, None);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_database_jobs_without_successor() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    assert_jobs_without_successor(config.database()).await
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_jobs_without_successor() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    assert_jobs_without_successor(&database_client).await
+}
+
+/// Asserts that a block range job is listed without successor until the last of its blocks has
+/// a successor, as when its blocks are proven one by one.
+async fn assert_jobs_without_successor(database_client: &dyn Database) -> color_eyre::Result<()> {
+    let mut partly_proven_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 0);
+    partly_proven_job.internal_id = "1-3".to_string();
+    let mut proven_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 0);
+    proven_job.internal_id = "4-5".to_string();
+    let unproven_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 6);
+    let mut non_block_job = build_job_item(JobType::SnosRun, JobStatus::Completed, 0);
+    non_block_job.internal_id = "0x5".to_string();
+    for job in [&partly_proven_job, &proven_job, &unproven_job, &non_block_job] {
+        database_client.create_job(job.clone()).await?;
+    }
+    for internal_id in [1, 2, 4, 5] {
+        database_client.create_job(build_job_item(JobType::ProofCreation, JobStatus::Created, internal_id)).await?;
+    }
+
+    let without_successor = database_client
+        .get_jobs_without_successor(JobType::SnosRun, JobStatus::Completed, JobType::ProofCreation)
+        .await?;
+    let mut internal_ids: Vec<&str> = without_successor.iter().map(|job| job.internal_id.as_str()).collect();
+    internal_ids.sort();
+    assert_eq!(internal_ids, vec!["0x5", "1-3", "6"]);
+
+    Ok(())
+}
+
+/// Tests that the writes of a transaction are applied together and that a failing write rolls
+/// back the ones before it.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_run_transaction() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    let first_job = build_job_item(JobType::StateTransition, JobStatus::Created, 1);
+    let second_job = build_job_item(JobType::StateTransition, JobStatus::Created, 2);
+    let writes = vec![DatabaseWrite::CreateJob(first_job.clone()), DatabaseWrite::CreateJob(second_job.clone())];
+    database_client.run_transaction(writes).await?;
+    assert_eq!(database_client.get_job_by_id(first_job.id).await?, Some(first_job.clone()));
+    assert_eq!(database_client.get_job_by_id(second_job.id).await?, Some(second_job.clone()));
+
+    // the update is on an outdated version, the job created before it is rolled back
+    let third_job = build_job_item(JobType::StateTransition, JobStatus::Created, 3);
+    let mut outdated_job = first_job.clone();
+    outdated_job.version = 1;
+    let event = JobEvent::new(&third_job, JobStatus::Created, JobStatus::LockedForProcessing, "worker".to_string());
+    let result = database_client
+        .run_transaction(vec![
+            DatabaseWrite::CreateJob(third_job.clone()),
+            DatabaseWrite::AppendJobEvent(event),
+            DatabaseWrite::UpdateJob(outdated_job),
+        ])
+        .await;
+    assert!(result.is_err());
+    assert_eq!(database_client.get_job_by_id(third_job.id).await?, None);
+    assert!(database_client.get_job_events(third_job.id).await?.is_empty());
+
+    Ok(())
+}
+
+/// Tests that the increments of a job counter made concurrently all count, and that the updates
+/// of the job read before them don't overwrite them.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_job_counters() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    let job = build_job_item(JobType::SnosRun, JobStatus::Created, 1);
+    database_client.create_job(job.clone()).await?;
+
+    // the two workers increment the counter of the same job
+    let (first, second) = tokio::join!(
+        database_client.increment_job_counter(&job, JobCounter::ProcessAttempts),
+        database_client.increment_job_counter(&job, JobCounter::ProcessAttempts)
+    );
+    let mut increments = vec![first?, second?];
+    increments.sort();
+    assert_eq!(increments, vec![1, 2]);
+
+    // the job read before the increments is updated, its counters are left as stored
+    database_client.update_job(&job).await?;
+    database_client.increment_job_counter(&job, JobCounter::VerificationAttempts).await?;
+    let stored_job = database_client.get_job_by_id(job.id).await?.unwrap();
+    assert_eq!(stored_job.counters, JobCounters { process_attempts: 2, verification_attempts: 1, process_timeouts: 0 });
+
+    database_client.reset_job_counters(&job, &[JobCounter::VerificationAttempts]).await?;
+    let stored_job = database_client.get_job_by_id(job.id).await?.unwrap();
+    assert_eq!(stored_job.counters.process_attempts, 2);
+    assert_eq!(stored_job.counters.verification_attempts, 0);
+
+    Ok(())
+}
+
+/// Tests that a lease is held by a single holder until it expires.
+#[rstest]
+#[tokio::test]
+async fn test_database_leases() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    assert_leases(config.database()).await
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_leases() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    assert_leases(&database_client).await
+}
+
+async fn assert_leases(database_client: &dyn Database) -> color_eyre::Result<()> {
+    let lease = Duration::from_secs(60);
+    assert!(database_client.acquire_lease("workers", "instance-a", lease).await?);
+    assert!(!database_client.acquire_lease("workers", "instance-b", lease).await?);
+    // the holder renews its lease, the other leases are independent
+    assert!(database_client.acquire_lease("workers", "instance-a", lease).await?);
+    assert!(database_client.acquire_lease("other", "instance-b", lease).await?);
+
+    // a lease renewed for no time is expired right away and taken over
+    assert!(database_client.acquire_lease("workers", "instance-a", Duration::ZERO).await?);
+    assert!(database_client.acquire_lease("workers", "instance-b", lease).await?);
+    assert!(!database_client.acquire_lease("workers", "instance-a", lease).await?);
+
+    Ok(())
+}
+
+/// Tests that the runs of a worker are listed the most recent first and pruned by age.
+#[rstest]
+#[tokio::test]
+async fn test_database_worker_runs() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    assert_worker_runs(config.database()).await
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_worker_runs() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    assert_worker_runs(&database_client).await
+}
+
+async fn assert_worker_runs(database_client: &dyn Database) -> color_eyre::Result<()> {
+    let run = |worker: &str, started_at: u64, error: Option<&str>| WorkerRun {
+        worker: worker.to_string(),
+        started_at,
+        ended_at: started_at + 5,
+        jobs_created: 2,
+        error: error.map(str::to_string),
+        worker_id: "instance-a".to_string(),
+    };
+    database_client.record_worker_run(run("snos", 100, None)).await?;
+    database_client.record_worker_run(run("snos", 200, Some("RPC unreachable"))).await?;
+    database_client.record_worker_run(run("proving", 300, None)).await?;
+
+    let runs = database_client.get_worker_runs("snos", 10).await?;
+    assert_eq!(runs, vec![run("snos", 200, Some("RPC unreachable")), run("snos", 100, None)]);
+    assert_eq!(database_client.get_worker_runs("snos", 1).await?.len(), 1);
+    assert_eq!(database_client.get_last_successful_worker_run("snos").await?, Some(run("snos", 100, None)));
+    assert_eq!(database_client.get_last_successful_worker_run("update_state").await?, None);
+
+    assert_eq!(database_client.delete_worker_runs_before(200).await?, 1);
+    assert_eq!(database_client.get_last_successful_worker_run("snos").await?, None);
+    assert_eq!(database_client.get_worker_runs("proving", 10).await?, vec![run("proving", 300, None)]);
+
+    Ok(())
+}
+
+/// Tests that the cost of a job is attributed once to each block, recording it again overwrites it.
+#[rstest]
+#[tokio::test]
+async fn test_database_block_costs() -> color_eyre::Result<()> {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    assert_block_costs(config.database()).await
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_block_costs() -> color_eyre::Result<()> {
+    let database_client = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    assert_block_costs(&database_client).await
+}
+
+async fn assert_block_costs(database_client: &dyn Database) -> color_eyre::Result<()> {
+    let da_job_id = Uuid::new_v4();
+    let state_update_job_id = Uuid::new_v4();
+    let cost = |block_number: u64, job_id: Uuid, job_type: JobType, cost_wei: u128| BlockCost {
+        block_number,
+        job_id,
+        job_type,
+        cost_wei: cost_wei.to_string(),
+    };
+    database_client.record_block_cost(cost(1, da_job_id, JobType::DataSubmission, 5)).await?;
+    database_client.record_block_cost(cost(1, da_job_id, JobType::DataSubmission, 7)).await?;
+    database_client.record_block_cost(cost(1, state_update_job_id, JobType::StateTransition, u128::MAX)).await?;
+    database_client.record_block_cost(cost(2, da_job_id, JobType::DataSubmission, 3)).await?;
+
+    assert_eq!(
+        database_client.get_block_costs(1).await?,
+        vec![
+            cost(1, da_job_id, JobType::DataSubmission, 7),
+            cost(1, state_update_job_id, JobType::StateTransition, u128::MAX)
+        ]
+    );
+    assert_eq!(database_client.get_block_costs(2).await?, vec![cost(2, da_job_id, JobType::DataSubmission, 3)]);
+    assert!(database_client.get_block_costs(3).await?.is_empty());
+
+    Ok(())
+}
+
+/// Tests that the migrations are applied once and that a storage migrated by a newer orchestrator
+/// is refused.
+#[rstest]
+#[tokio::test]
+async fn test_sqlite_migrations() -> color_eyre::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let path = dir.path().join("orchestrator.db").to_string_lossy().to_string();
+
+    let database_client = SqliteDb::new(SqliteDbConfig { path: path.clone() }).await;
+    assert_eq!(database_client.schema_version().await?, latest_schema_version());
+    database_client.create_job(build_job_item(JobType::SnosRun, JobStatus::Created, 1)).await?;
+    drop(database_client);
+
+    // reopening the database keeps the jobs and applies nothing
+    let database_client = SqliteDb::new(SqliteDbConfig { path: path.clone() }).await;
+    assert!(database_client.get_job_by_internal_id_and_type("1", &JobType::SnosRun).await?.is_some());
+    assert_eq!(run_migrations(&database_client).await?, latest_schema_version());
+
+    let newer_version = latest_schema_version() + 1;
+    rusqlite::Connection::open(&path)?.execute_batch(&format!("PRAGMA user_version = {}", newer_version))?;
+    assert!(run_migrations(&database_client).await.is_err());
+
+    Ok(())
+}
+
 // Test Util Functions
 // ==========================================
 
@@ -63,5 +750,10 @@ fn build_job_item(job_type: JobType, job_status: JobStatus, internal_id: u64) ->
         external_id: ExternalId::Number(0),
         metadata: Default::default(),
         version: 0,
+        created_at: now_secs(),
+        updated_at: now_secs(),
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
     }
 }
@@ -1,16 +1,26 @@
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::data_storage::MockDataStorage;
+use crate::jobs::constants::{JOB_METADATA_DA_BLOCKS_TO_SUBMIT_KEY, JOB_METADATA_DA_EMPTY_BLOCK_DECISION_KEY};
+use crate::jobs::da_job::empty_blocks::{is_empty_state_diff, EMPTY_BLOCK_DECISION_SKIPPED};
+use crate::jobs::da_job::state_diff_validation::validate_state_diff_encoding;
 use crate::jobs::da_job::test::{get_nonce_attached, read_state_update_from_file};
-use crate::jobs::da_job::DaJob;
-use crate::jobs::types::{ExternalId, JobItem, JobStatus, JobType};
-use crate::tests::common::drop_database;
+use crate::jobs::da_job::{
+    blob_data_to_blobs, encoded_state_diff_len, state_update_to_blob_data, validate_blobs_round_trip, DaJob,
+};
+use crate::jobs::types::{ExternalId, JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
+use crate::tests::common::{drop_database, init_config};
 use crate::tests::config::TestConfigBuilder;
 use crate::{config::config, jobs::Job};
 use assert_matches::assert_matches;
 use color_eyre::eyre::eyre;
-use da_client_interface::MockDaClient;
-use mockall::predicate::always;
+use da_client_interface::{DaVerificationStatus, MockDaClient};
+use mockall::predicate::{always, eq};
 use rstest::rstest;
 use serde_json::json;
-use starknet_core::types::{FieldElement, MaybePendingStateUpdate, PendingStateUpdate, StateDiff};
+use starknet_core::types::{
+    ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, FieldElement, MaybePendingStateUpdate,
+    NonceUpdate, PendingStateUpdate, StateDiff, StateUpdate, StorageEntry,
+};
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -67,6 +77,11 @@ async fn test_da_job_process_job_failure_on_small_blob_size(
                 external_id: ExternalId::String(internal_id.to_string().into_boxed_str()),
                 metadata: HashMap::default(),
                 version: 0,
+                created_at: 0,
+                updated_at: 0,
+                parent_ids: Vec::new(),
+                priority: JobPriority::Normal,
+                counters: JobCounters::default(),
             },
         )
         .await;
@@ -131,6 +146,11 @@ async fn test_da_job_process_job_failure_on_pending_block() {
                 external_id: ExternalId::String("1".to_string().into_boxed_str()),
                 metadata: HashMap::default(),
                 version: 0,
+                created_at: 0,
+                updated_at: 0,
+                parent_ids: Vec::new(),
+                priority: JobPriority::Normal,
+                counters: JobCounters::default(),
             },
         )
         .await;
@@ -197,27 +217,305 @@ async fn test_da_job_process_job_success(
         then.status(200).body(serde_json::to_vec(&response).unwrap());
     });
 
-    let response = DaJob
-        .process_job(
+    let mut job_item = JobItem {
+        id: Uuid::default(),
+        internal_id: internal_id.to_string(),
+        job_type: JobType::DataSubmission,
+        status: JobStatus::Created,
+        external_id: ExternalId::String(internal_id.to_string().into_boxed_str()),
+        metadata: HashMap::default(),
+        version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
+    };
+    // the submission is recorded in the metadata of the job
+    config.database().create_job(job_item.clone()).await.unwrap();
+    let response = DaJob.process_job(config.as_ref(), &mut job_item).await;
+
+    assert_matches!(response,
+        Ok(msg) => {
+            assert_eq!(msg, eyre!("Done").to_string());
+        }
+    );
+
+    state_update_mock.assert();
+    let _ = drop_database().await;
+}
+
+/// Tests that the DA inclusion proof is stored for every block of the job once
+/// the DA job is verified.
+#[rstest]
+#[tokio::test]
+async fn test_da_job_verify_job_stores_inclusion_proof() {
+    let mut da_client = MockDaClient::new();
+    da_client.expect_verify_inclusion().with(eq("0xabcd")).returning(|_| Ok(DaVerificationStatus::Verified));
+    da_client.expect_get_inclusion_proof().with(eq("0xabcd")).returning(|_| Ok(Some(b"proof".to_vec())));
+
+    let _server = TestConfigBuilder::new().mock_da_client(Box::new(da_client)).build().await;
+    let config = config().await;
+
+    let mut metadata = HashMap::new();
+    metadata.insert(JOB_METADATA_DA_BLOCKS_TO_SUBMIT_KEY.to_string(), "1,2".to_string());
+
+    let verification_status = DaJob
+        .verify_job(
             config.as_ref(),
             &mut JobItem {
                 id: Uuid::default(),
-                internal_id: internal_id.to_string(),
+                internal_id: "1".to_string(),
                 job_type: JobType::DataSubmission,
-                status: JobStatus::Created,
-                external_id: ExternalId::String(internal_id.to_string().into_boxed_str()),
-                metadata: HashMap::default(),
+                status: JobStatus::PendingVerification,
+                external_id: ExternalId::String("0xabcd".to_string().into_boxed_str()),
+                metadata,
                 version: 0,
+                created_at: 0,
+                updated_at: 0,
+                parent_ids: Vec::new(),
+                priority: JobPriority::Normal,
+                counters: JobCounters::default(),
             },
         )
-        .await;
+        .await
+        .unwrap();
 
-    assert_matches!(response,
-        Ok(msg) => {
-            assert_eq!(msg, eyre!("Done").to_string());
-        }
-    );
+    assert_eq!(verification_status, JobVerificationStatus::Verified);
+    for block_number in [1, 2] {
+        let key = StorageKey::new(ArtifactKind::DaInclusionProof, block_number).to_string();
+        assert_eq!(config.storage().get_data(&key).await.unwrap().as_ref(), b"proof");
+    }
+}
+
+/// Tests that a DA job whose empty blocks were skipped is verified without querying the DA layer.
+#[rstest]
+#[tokio::test]
+async fn test_da_job_verify_job_skipped_empty_blocks() {
+    // no expectations, the DA client must not be called
+    let da_client = MockDaClient::new();
+
+    let _server = TestConfigBuilder::new().mock_da_client(Box::new(da_client)).build().await;
+    let config = config().await;
+
+    let mut metadata = HashMap::new();
+    metadata.insert(JOB_METADATA_DA_BLOCKS_TO_SUBMIT_KEY.to_string(), "1,2".to_string());
+    metadata.insert(JOB_METADATA_DA_EMPTY_BLOCK_DECISION_KEY.to_string(), EMPTY_BLOCK_DECISION_SKIPPED.to_string());
+
+    let verification_status = DaJob
+        .verify_job(
+            config.as_ref(),
+            &mut JobItem {
+                id: Uuid::default(),
+                internal_id: "1".to_string(),
+                job_type: JobType::DataSubmission,
+                status: JobStatus::PendingVerification,
+                external_id: ExternalId::String(EMPTY_BLOCK_DECISION_SKIPPED.to_string().into_boxed_str()),
+                metadata,
+                version: 0,
+                created_at: 0,
+                updated_at: 0,
+                parent_ids: Vec::new(),
+                priority: JobPriority::Normal,
+                counters: JobCounters::default(),
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(verification_status, JobVerificationStatus::Verified);
+}
+
+/// Tests that blobs built from the encoded state diffs of a block decode back to the same data,
+/// and that a corrupted blob is rejected before it could be submitted.
+#[rstest]
+#[case(
+    "src/tests/jobs/da_job/test_data/state_update/631861.txt",
+    "src/tests/jobs/da_job/test_data/nonces/631861.txt",
+    631861
+)]
+#[tokio::test]
+async fn test_validate_blobs_round_trip(
+    #[case] state_update_file: String,
+    #[case] nonces_file: String,
+    #[case] block_no: u64,
+) {
+    let mut da_client = MockDaClient::new();
+    da_client.expect_max_bytes_per_blob().with().returning(|| 131072);
+
+    let server = TestConfigBuilder::new().mock_da_client(Box::new(da_client)).build().await;
+    let config = config().await;
+    get_nonce_attached(&server, nonces_file.as_str());
+
+    let state_update = read_state_update_from_file(state_update_file.as_str()).expect("issue while reading");
+    let blob_data = state_update_to_blob_data(block_no, state_update, config.as_ref()).await.unwrap();
+
+    let mut blobs = blob_data_to_blobs(131072, blob_data.clone()).unwrap();
+    assert!(validate_blobs_round_trip(131072, &blobs, &blob_data).is_ok());
+
+    // flipping a single bit of the first field element
+    blobs[0][31] ^= 1;
+    let result = validate_blobs_round_trip(131072, &blobs, &blob_data);
+    assert_matches!(result, Err(e) => {
+        assert!(e.to_string().starts_with("Blob round-trip mismatch in chunk 0"));
+    });
 
-    state_update_mock.assert();
     let _ = drop_database().await;
 }
+
+/// Tests that the encoded state diff of a block is accepted when it carries the storage writes,
+/// nonce updates, deployed contracts and declared classes of the node's state diff, and rejected
+/// as soon as one of them is missing.
+#[rstest]
+fn test_validate_state_diff_encoding() {
+    let block_no = 42;
+    let contract = FieldElement::from(0x1234_u64);
+    let felt = |value: u64| FieldElement::from(value);
+
+    let state_diff = StateDiff {
+        storage_diffs: vec![
+            // block hash written by the OS, left out by the encoder
+            ContractStorageDiffItem {
+                address: FieldElement::ONE,
+                storage_entries: vec![StorageEntry { key: felt(block_no - 10), value: felt(0xb10c) }],
+            },
+            ContractStorageDiffItem {
+                address: contract,
+                storage_entries: vec![
+                    StorageEntry { key: felt(1), value: felt(10) },
+                    StorageEntry { key: felt(2), value: felt(20) },
+                ],
+            },
+        ],
+        deprecated_declared_classes: vec![],
+        declared_classes: vec![DeclaredClassItem { class_hash: felt(0xc1a55), compiled_class_hash: felt(0xcc1a55) }],
+        deployed_contracts: vec![DeployedContractItem { address: contract, class_hash: felt(0xc1a55) }],
+        replaced_classes: vec![],
+        nonces: vec![NonceUpdate { contract_address: contract, nonce: felt(1) }],
+    };
+
+    // class flag set, nonce 1 and 2 storage writes
+    let da_word = FieldElement::from_hex_be("0x100000000000000010000000000000002").unwrap();
+    let blob_data = vec![
+        felt(2),
+        FieldElement::ONE,
+        FieldElement::ONE,
+        felt(block_no),
+        felt(0xb10c),
+        contract,
+        da_word,
+        felt(0xc1a55),
+        felt(1),
+        felt(10),
+        felt(2),
+        felt(20),
+        felt(1),
+        felt(0xc1a55),
+        felt(0xcc1a55),
+    ];
+    assert!(validate_state_diff_encoding(block_no, &state_diff, &blob_data).is_ok());
+
+    // encoded for another block
+    assert!(validate_state_diff_encoding(block_no + 1, &state_diff, &blob_data).is_err());
+
+    // nonce update lost by the encoder
+    let mut missing_nonce = blob_data.clone();
+    missing_nonce[6] = FieldElement::from_hex_be("0x100000000000000000000000000000002").unwrap();
+    let result = validate_state_diff_encoding(block_no, &state_diff, &missing_nonce);
+    assert_matches!(result, Err(e) => {
+        assert!(e.to_string().starts_with("State diff encoding mismatch for block 42"));
+    });
+
+    // declared class lost by the encoder
+    let missing_declared_class = [&blob_data[..12], &[FieldElement::ZERO]].concat();
+    assert!(validate_state_diff_encoding(block_no, &state_diff, &missing_declared_class).is_err());
+
+    // truncated storage writes
+    let truncated = [&blob_data[..10], &blob_data[12..]].concat();
+    assert!(validate_state_diff_encoding(block_no, &state_diff, &truncated).is_err());
+}
+
+/// Tests that a contract updated without any storage write, only its nonce updated or only
+/// deployed, is encoded in the state diff of the block.
+#[rstest]
+#[case::nonce_only(
+    vec![NonceUpdate { contract_address: FieldElement::from(0x1234_u64), nonce: FieldElement::from(3_u64) }],
+    vec![],
+    // nonce 3 and no storage writes
+    vec![FieldElement::from_hex_be("0x30000000000000000").unwrap()]
+)]
+#[case::deployed_only(
+    vec![],
+    vec![DeployedContractItem { address: FieldElement::from(0x1234_u64), class_hash: FieldElement::from(0xc1a55_u64) }],
+    // class flag set, nonce 0 and no storage writes, followed by the class hash
+    vec![FieldElement::from_hex_be("0x100000000000000000000000000000000").unwrap(), FieldElement::from(0xc1a55_u64)]
+)]
+#[tokio::test]
+async fn test_state_update_to_blob_data_without_storage_writes(
+    #[case] nonces: Vec<NonceUpdate>,
+    #[case] deployed_contracts: Vec<DeployedContractItem>,
+    #[case] expected_contract_update: Vec<FieldElement>,
+) {
+    let block_no = 42_u64;
+    let block_hash = FieldElement::from(0xb10c_u64);
+
+    let mut da_client = MockDaClient::new();
+    da_client.expect_max_bytes_per_blob().returning(|| 131072);
+    let mut storage_client = MockDataStorage::new();
+    storage_client.expect_put_data().times(1).returning(|_, _| Ok(()));
+    let config = init_config(None, None, None, Some(da_client), None, None, Some(storage_client)).await;
+
+    let state_diff = StateDiff {
+        // block hash written by the OS, left out by the encoder
+        storage_diffs: vec![ContractStorageDiffItem {
+            address: FieldElement::ONE,
+            storage_entries: vec![StorageEntry { key: FieldElement::from(block_no - 10), value: block_hash }],
+        }],
+        deprecated_declared_classes: vec![],
+        declared_classes: vec![],
+        deployed_contracts,
+        replaced_classes: vec![],
+        nonces,
+    };
+    let state_update = StateUpdate {
+        block_hash,
+        new_root: FieldElement::ZERO,
+        old_root: FieldElement::ZERO,
+        state_diff: state_diff.clone(),
+    };
+
+    let blob_data = state_update_to_blob_data(block_no, state_update, &config).await.unwrap();
+
+    let expected = [
+        vec![FieldElement::from(2_u64), FieldElement::ONE, FieldElement::ONE, FieldElement::from(block_no), block_hash],
+        vec![FieldElement::from(0x1234_u64)],
+        expected_contract_update,
+        // no declared classes
+        vec![FieldElement::ZERO],
+    ]
+    .concat();
+    assert_eq!(blob_data, expected);
+    assert_eq!(encoded_state_diff_len(&state_diff), blob_data.len());
+    assert!(validate_state_diff_encoding(block_no, &state_diff, &blob_data).is_ok());
+}
+
+/// Tests that a state diff only carrying the block hash written by the OS is considered empty.
+#[rstest]
+fn test_is_empty_state_diff() {
+    let block_hash_write = ContractStorageDiffItem {
+        address: FieldElement::ONE,
+        storage_entries: vec![StorageEntry { key: FieldElement::from(32_u64), value: FieldElement::from(0xb10c_u64) }],
+    };
+    let mut state_diff = StateDiff {
+        storage_diffs: vec![block_hash_write],
+        deprecated_declared_classes: vec![],
+        declared_classes: vec![],
+        deployed_contracts: vec![],
+        replaced_classes: vec![],
+        nonces: vec![],
+    };
+    assert!(is_empty_state_diff(&state_diff));
+
+    state_diff.nonces.push(NonceUpdate { contract_address: FieldElement::from(0x1234_u64), nonce: FieldElement::ONE });
+    assert!(!is_empty_state_diff(&state_diff));
+}
@@ -1,30 +1,54 @@
 #[cfg(test)]
 pub mod da_job;
 
+#[cfg(test)]
+pub mod proof_aggregation_job;
+
 #[cfg(test)]
 pub mod proving_job;
 
+#[cfg(test)]
+pub mod snos_job;
+
 #[cfg(test)]
 pub mod state_update_job;
 
 use assert_matches::assert_matches;
+use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use color_eyre::eyre::eyre;
 use mockall::predicate::eq;
 use mongodb::bson::doc;
 use omniqueue::QueueError;
 use rstest::rstest;
+use settlement_client_interface::{FeeTooHighError, SimulationRevertedError};
 use tokio::time::sleep;
 use uuid::Uuid;
 
-use crate::config::config;
-use crate::jobs::constants::{JOB_PROCESS_ATTEMPT_METADATA_KEY, JOB_VERIFICATION_ATTEMPT_METADATA_KEY};
+use crate::config::{config, Config};
+use crate::jobs::constants::{
+    JOB_METADATA_DEAD_LETTER_PAYLOAD_KEY, JOB_METADATA_FAILED_STATUS_KEY, JOB_METADATA_SUBMISSION_INTENT_PREFIX,
+    JOB_METADATA_TRACE_ID_KEY,
+};
+use crate::jobs::dependencies::{create_job_with_parents, release_dependent_jobs};
+use crate::jobs::errors::JobError;
+use crate::jobs::idempotency::{forget_submissions, submit_once, PROVER_SUBMISSION_KEY};
 use crate::jobs::job_handler_factory::mock_factory;
-use crate::jobs::types::{ExternalId, JobItem, JobStatus, JobType, JobVerificationStatus};
-use crate::jobs::{create_job, increment_key_in_metadata, process_job, verify_job, Job, MockJob};
-use crate::queue::job_queue::{JOB_PROCESSING_QUEUE, JOB_VERIFICATION_QUEUE};
+use crate::jobs::types::{
+    ExternalId, JobBlockedError, JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus,
+};
+use crate::jobs::{
+    cancel_job, create_job, create_job_with_priority, handle_job_failure, process_job, retry_job, verify_job, Job,
+    MockJob,
+};
+use crate::queue::job_queue::{
+    consume_dead_letter_from_queue, job_processing_queue, JobQueueMessage, DA_JOB_PROCESSING_QUEUE,
+    JOB_HANDLE_FAILURE_QUEUE, JOB_VERIFICATION_QUEUE, PROVING_JOB_PROCESSING_QUEUE, SNOS_JOB_PRIORITY_PROCESSING_QUEUE,
+    SNOS_JOB_PROCESSING_QUEUE,
+};
 use crate::tests::common::MessagePayloadType;
 use crate::tests::config::TestConfigBuilder;
 
@@ -49,21 +73,18 @@ async fn create_job_job_does_not_exists_in_db_works() {
 
     assert!(create_job(JobType::SnosRun, "0".to_string(), HashMap::new()).await.is_ok());
 
-    let mut hashmap: HashMap<String, String> = HashMap::new();
-    hashmap.insert(JOB_PROCESS_ATTEMPT_METADATA_KEY.to_string(), "0".to_string());
-    hashmap.insert(JOB_VERIFICATION_ATTEMPT_METADATA_KEY.to_string(), "0".to_string());
-
     // Db checks.
     let job_in_db = config.database().get_job_by_id(job_item.id).await.unwrap().unwrap();
     assert_eq!(job_in_db.id, job_item.id);
     assert_eq!(job_in_db.internal_id, job_item.internal_id);
-    assert_eq!(job_in_db.metadata, hashmap);
-
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
+    let mut metadata = job_in_db.metadata.clone();
+    assert!(metadata.remove(JOB_METADATA_TRACE_ID_KEY).is_some());
+    assert_eq!(metadata, job_item.metadata);
+    assert_eq!(job_in_db.counters, JobCounters::default());
 
     // Queue checks.
-    let consumed_messages = config.queue().consume_message_from_queue(JOB_PROCESSING_QUEUE.to_string()).await.unwrap();
+    let consumed_messages =
+        config.queue().consume_message_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string()).await.unwrap();
     let consumed_message_payload: MessagePayloadType = consumed_messages.payload_serde_json().unwrap().unwrap();
     assert_eq!(consumed_message_payload.id, job_item.id);
 }
@@ -82,15 +103,44 @@ async fn create_job_job_exists_in_db_works() {
 
     assert!(create_job(JobType::ProofCreation, "0".to_string(), HashMap::new()).await.is_err());
 
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
-
     // Queue checks.
     let consumed_messages =
-        config.queue().consume_message_from_queue(JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
+        config.queue().consume_message_from_queue(PROVING_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
     assert_matches!(consumed_messages, QueueError::NoData);
 }
 
+/// Tests that a high priority job is stored with its priority and goes to the priority queue of
+/// its type, not to the queue of the routine jobs.
+#[rstest]
+#[tokio::test]
+async fn create_job_with_priority_uses_priority_queue() {
+    let job_item = build_job_item_by_type_and_status(JobType::SnosRun, JobStatus::Created, "0".to_string());
+    let mut job_handler = MockJob::new();
+    let job_item_clone = job_item.clone();
+    job_handler.expect_create_job().times(1).returning(move |_, _, _| Ok(job_item_clone.clone()));
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::SnosRun)).return_once(move |_| Arc::clone(&job_handler));
+
+    create_job_with_priority(JobType::SnosRun, "0".to_string(), HashMap::new(), JobPriority::High).await.unwrap();
+
+    let job_in_db = config.database().get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.priority, JobPriority::High);
+
+    let consumed_message =
+        config.queue().consume_message_from_queue(SNOS_JOB_PRIORITY_PROCESSING_QUEUE.to_string()).await.unwrap();
+    let consumed_message_payload: MessagePayloadType = consumed_message.payload_serde_json().unwrap().unwrap();
+    assert_eq!(consumed_message_payload.id, job_item.id);
+    assert_matches!(
+        config.queue().consume_message_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err(),
+        QueueError::NoData
+    );
+}
+
 /// Tests `create_job` function when job handler is not implemented in the `get_job_handler`
 /// This test should fail as job handler is not implemented in the `factory.rs`
 #[rstest]
@@ -106,12 +156,9 @@ async fn create_job_job_handler_is_not_implemented_panics() {
 
     assert!(create_job(JobType::ProofCreation, "0".to_string(), HashMap::new()).await.is_err());
 
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
-
     // Queue checks.
     let consumed_messages =
-        config.queue().consume_message_from_queue(JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
+        config.queue().consume_message_from_queue(PROVING_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
     assert_matches!(consumed_messages, QueueError::NoData);
 }
 
@@ -139,6 +186,7 @@ async fn process_job_with_job_exists_in_db_and_valid_job_processing_status_works
     // Expecting process job function in job processor to return the external ID.
     job_handler.expect_process_job().times(1).returning(move |_, _| Ok("0xbeef".to_string()));
     job_handler.expect_verification_polling_delay_seconds().return_const(1u64);
+    job_handler.expect_max_backoff_seconds().return_const(1u64);
 
     // Mocking the `get_job_handler` call in create_job function.
     let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
@@ -151,10 +199,25 @@ async fn process_job_with_job_exists_in_db_and_valid_job_processing_status_works
     // checking if job_status is updated in db
     assert_eq!(updated_job.status, JobStatus::PendingVerification);
     assert_eq!(updated_job.external_id, ExternalId::String(Box::from("0xbeef")));
-    assert_eq!(updated_job.metadata.get(JOB_PROCESS_ATTEMPT_METADATA_KEY).unwrap(), "1");
+    assert_eq!(updated_job.counters.process_attempts, 1);
+    // both transitions are in the history of the job
+    let transitions: Vec<(JobStatus, JobStatus)> = database_client
+        .get_job_events(job_item.id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|event| (event.old_status, event.new_status))
+        .collect();
+    assert_eq!(
+        transitions,
+        vec![
+            (job_status, JobStatus::LockedForProcessing),
+            (JobStatus::LockedForProcessing, JobStatus::PendingVerification)
+        ]
+    );
 
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
+    // Waiting for the verification polling delay for the message to be delivered
+    sleep(Duration::from_secs(2)).await;
 
     // Queue checks
     let consumed_messages =
@@ -185,9 +248,6 @@ async fn process_job_with_job_exists_in_db_with_invalid_job_processing_status_er
     // Job should be untouched in db.
     assert_eq!(job_in_db, job_item);
 
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
-
     // Queue checks.
     let consumed_messages =
         config.queue().consume_message_from_queue(JOB_VERIFICATION_QUEUE.to_string()).await.unwrap_err();
@@ -208,9 +268,6 @@ async fn process_job_job_does_not_exists_in_db_works() {
 
     assert!(process_job(job_item.id).await.is_err());
 
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
-
     // Queue checks.
     let consumed_messages =
         config.queue().consume_message_from_queue(JOB_VERIFICATION_QUEUE.to_string()).await.unwrap_err();
@@ -228,6 +285,7 @@ async fn process_job_two_workers_process_same_job_works() {
     // Expecting process job function in job processor to return the external ID.
     job_handler.expect_process_job().times(1).returning(move |_, _| Ok("0xbeef".to_string()));
     job_handler.expect_verification_polling_delay_seconds().return_const(1u64);
+    job_handler.expect_max_backoff_seconds().return_const(1u64);
 
     // Mocking the `get_job_handler` call in create_job function.
     let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
@@ -264,6 +322,256 @@ async fn process_job_two_workers_process_same_job_works() {
     assert_eq!(final_job_in_db.status, JobStatus::PendingVerification);
 }
 
+/// Tests `process_job` function when the job handler reports the job as blocked.
+/// The job should be moved to the `Blocked` status, its message consumed and not be pushed to the
+/// verification queue. A dead-lettered message of the job doesn't move it out of `Blocked`.
+#[rstest]
+#[tokio::test]
+async fn process_job_blocked_by_handler_marks_job_blocked() {
+    let job_item = build_job_item_by_type_and_status(JobType::StateTransition, JobStatus::Created, "1".to_string());
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    let mut job_handler = MockJob::new();
+    job_handler
+        .expect_process_job()
+        .times(1)
+        .returning(|_, _| Err(JobBlockedError("State root divergence".to_string()).into()));
+
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::StateTransition)).returning(move |_| Arc::clone(&job_handler));
+
+    assert!(process_job(job_item.id).await.is_ok());
+
+    let job_in_db = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.status, JobStatus::Blocked);
+    assert_eq!(job_in_db.metadata.get("error").unwrap(), "State root divergence");
+
+    handle_job_failure(job_item.id, String::new()).await.unwrap();
+    let job_in_db = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.status, JobStatus::Blocked);
+
+    // Queue checks.
+    let consumed_messages =
+        config.queue().consume_message_from_queue(JOB_VERIFICATION_QUEUE.to_string()).await.unwrap_err();
+    assert_matches!(consumed_messages, QueueError::NoData);
+}
+
+/// Tests `process_job` function when the job handler is held back by the settlement fees.
+/// The job should be moved to the `FeeTooHigh` status to be processed again later, and not be
+/// pushed to the verification queue.
+#[rstest]
+#[tokio::test]
+async fn process_job_fee_too_high_holds_job_back() {
+    let job_item = build_job_item_by_type_and_status(JobType::StateTransition, JobStatus::Created, "1".to_string());
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    let mut job_handler = MockJob::new();
+    job_handler
+        .expect_process_job()
+        .times(1)
+        .returning(|_, _| Err(FeeTooHighError("blob base fee exceeds the cap".to_string()).into()));
+
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::StateTransition)).returning(move |_| Arc::clone(&job_handler));
+
+    assert!(process_job(job_item.id).await.is_ok());
+
+    let job_in_db = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.status, JobStatus::FeeTooHigh);
+    assert_eq!(job_in_db.metadata.get("error").unwrap(), "Fee too high: blob base fee exceeds the cap");
+
+    // Queue checks.
+    let consumed_messages =
+        config.queue().consume_message_from_queue(JOB_VERIFICATION_QUEUE.to_string()).await.unwrap_err();
+    assert_matches!(consumed_messages, QueueError::NoData);
+}
+
+/// Tests `process_job` function when the settlement transaction of the job fails its simulation.
+/// The job should be rejected with the revert reason, and not be pushed to the verification queue.
+#[rstest]
+#[tokio::test]
+async fn process_job_simulation_reverted_rejects_job() {
+    let job_item = build_job_item_by_type_and_status(JobType::StateTransition, JobStatus::Created, "1".to_string());
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    let mut job_handler = MockJob::new();
+    job_handler
+        .expect_process_job()
+        .times(1)
+        .returning(|_, _| Err(SimulationRevertedError("INVALID_PREVIOUS_ROOT".to_string()).into()));
+
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::StateTransition)).returning(move |_| Arc::clone(&job_handler));
+
+    assert!(process_job(job_item.id).await.is_ok());
+
+    let job_in_db = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.status, JobStatus::VerificationFailed);
+    assert_eq!(job_in_db.metadata.get("error").unwrap(), "Transaction would revert: INVALID_PREVIOUS_ROOT");
+
+    // Queue checks.
+    let consumed_messages =
+        config.queue().consume_message_from_queue(JOB_VERIFICATION_QUEUE.to_string()).await.unwrap_err();
+    assert_matches!(consumed_messages, QueueError::NoData);
+}
+
+/// Job handler whose processing hangs, like a call to a prover which never answers
+struct HangingJob;
+
+#[async_trait]
+impl Job for HangingJob {
+    async fn create_job(
+        &self,
+        _config: &Config,
+        _internal_id: String,
+        _metadata: HashMap<String, String>,
+    ) -> color_eyre::Result<JobItem> {
+        unreachable!("Only processed")
+    }
+
+    async fn process_job(&self, _config: &Config, _job: &mut JobItem) -> color_eyre::Result<String> {
+        sleep(Duration::from_secs(3600)).await;
+        Ok("never".to_string())
+    }
+
+    async fn verify_job(&self, _config: &Config, _job: &mut JobItem) -> color_eyre::Result<JobVerificationStatus> {
+        unreachable!("Only processed")
+    }
+
+    fn max_process_attempts(&self) -> u64 {
+        2
+    }
+
+    fn max_verification_attempts(&self) -> u64 {
+        1
+    }
+
+    fn verification_polling_delay_seconds(&self) -> u64 {
+        1
+    }
+
+    fn max_backoff_seconds(&self) -> u64 {
+        1
+    }
+}
+
+/// Tests `process_job` function when the processing of the job hangs. The job should be released
+/// once the timeout of its type expires, with the timeout recorded, and processed again.
+#[rstest]
+#[tokio::test]
+async fn process_job_timeout_releases_job() {
+    let job_item = build_job_item_by_type_and_status(JobType::ProofRegistration, JobStatus::Created, "1".to_string());
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(HangingJob));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::ProofRegistration)).returning(move |_| Arc::clone(&job_handler));
+
+    std::env::set_var("PROOF_REGISTRATION_JOB_PROCESS_TIMEOUT_SECS", "1");
+    let result = process_job(job_item.id).await;
+    std::env::remove_var("PROOF_REGISTRATION_JOB_PROCESS_TIMEOUT_SECS");
+    assert!(result.is_ok());
+
+    let job_in_db = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.status, JobStatus::VerificationFailed);
+    assert_eq!(job_in_db.counters.process_timeouts, 1);
+    assert_eq!(job_in_db.counters.process_attempts, 1);
+    assert_eq!(job_in_db.metadata.get("error").unwrap(), "Processing timed out after 1 seconds");
+
+    // the job has an attempt left, it is processed again
+    sleep(Duration::from_secs(2)).await;
+    let consumed_message = config
+        .queue()
+        .consume_message_from_queue(job_processing_queue(&JobType::ProofRegistration).to_string())
+        .await
+        .unwrap();
+    let consumed_message_payload: MessagePayloadType = consumed_message.payload_serde_json().unwrap().unwrap();
+    assert_eq!(consumed_message_payload.id, job_item.id);
+}
+
+/// Tests `process_job` function when an external service fails transiently. The failure should
+/// count as an attempt and the lock of the job be released, the job being out of attempts it is
+/// not processed again.
+#[rstest]
+#[tokio::test]
+async fn process_job_transient_external_error_releases_job() {
+    let job_item = build_job_item_by_type_and_status(JobType::ProofCreation, JobStatus::Created, "1".to_string());
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    let mut job_handler = MockJob::new();
+    job_handler.expect_process_job().times(1).returning(|_, _| {
+        Err(JobError::ExternalTransient { service: "Prover", message: "connection reset".to_string() }.into())
+    });
+    job_handler.expect_max_process_attempts().returning(|| 1u64);
+
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::ProofCreation)).returning(move |_| Arc::clone(&job_handler));
+
+    assert!(process_job(job_item.id).await.is_ok());
+
+    let job_in_db = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.status, JobStatus::VerificationFailed);
+    assert_eq!(job_in_db.counters.process_attempts, 1);
+    assert_eq!(job_in_db.metadata.get("error").unwrap(), "Prover failed: connection reset");
+
+    let consumed_messages =
+        config.queue().consume_message_from_queue(PROVING_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
+    assert_matches!(consumed_messages, QueueError::NoData);
+}
+
+/// Tests `process_job` function when an external service refuses the job. The job should be
+/// failed right away, with the status to retry it from.
+#[rstest]
+#[tokio::test]
+async fn process_job_permanent_error_fails_job() {
+    let job_item = build_job_item_by_type_and_status(JobType::ProofCreation, JobStatus::Created, "1".to_string());
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    let mut job_handler = MockJob::new();
+    job_handler.expect_process_job().times(1).returning(|_, _| {
+        Err(JobError::ExternalPermanent { service: "Prover", message: "invalid PIE".to_string() }.into())
+    });
+
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::ProofCreation)).returning(move |_| Arc::clone(&job_handler));
+
+    assert!(process_job(job_item.id).await.is_ok());
+
+    let job_in_db = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.status, JobStatus::Failed);
+    assert_eq!(job_in_db.metadata.get("error").unwrap(), "Prover refused the job: invalid PIE");
+    assert_eq!(job_in_db.metadata.get(JOB_METADATA_FAILED_STATUS_KEY).unwrap(), "LockedForProcessing");
+}
+
 /// Tests `verify_job` function when job is having expected status
 /// and returns a `Verified` verification status.
 #[rstest]
@@ -296,15 +604,12 @@ async fn verify_job_with_verified_status_works() {
     let updated_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
     assert_eq!(updated_job.status, JobStatus::Completed);
 
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
-
     // Queue checks.
     let consumed_messages_verification_queue =
         config.queue().consume_message_from_queue(JOB_VERIFICATION_QUEUE.to_string()).await.unwrap_err();
     assert_matches!(consumed_messages_verification_queue, QueueError::NoData);
     let consumed_messages_processing_queue =
-        config.queue().consume_message_from_queue(JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
+        config.queue().consume_message_from_queue(DA_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
     assert_matches!(consumed_messages_processing_queue, QueueError::NoData);
 }
 
@@ -327,6 +632,8 @@ async fn verify_job_with_rejected_status_adds_to_queue_works() {
     database_client.create_job(job_item.clone()).await.unwrap();
     job_handler.expect_verify_job().times(1).returning(move |_, _| Ok(JobVerificationStatus::Rejected("".to_string())));
     job_handler.expect_max_process_attempts().returning(move || 2u64);
+    job_handler.expect_verification_polling_delay_seconds().return_const(1u64);
+    job_handler.expect_max_backoff_seconds().return_const(1u64);
 
     let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
     let ctx = mock_factory::get_job_handler_context();
@@ -339,11 +646,12 @@ async fn verify_job_with_rejected_status_adds_to_queue_works() {
     let updated_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
     assert_eq!(updated_job.status, JobStatus::VerificationFailed);
 
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
+    // Waiting for the backoff of the processing retry for the message to be delivered
+    sleep(Duration::from_secs(2)).await;
 
     // Queue checks.
-    let consumed_messages = config.queue().consume_message_from_queue(JOB_PROCESSING_QUEUE.to_string()).await.unwrap();
+    let consumed_messages =
+        config.queue().consume_message_from_queue(DA_JOB_PROCESSING_QUEUE.to_string()).await.unwrap();
     let consumed_message_payload: MessagePayloadType = consumed_messages.payload_serde_json().unwrap().unwrap();
     assert_eq!(consumed_message_payload.id, job_item.id);
 }
@@ -357,9 +665,8 @@ async fn verify_job_with_rejected_status_works() {
     let mut job_item =
         build_job_item_by_type_and_status(JobType::DataSubmission, JobStatus::PendingVerification, "1".to_string());
 
-    // increasing JOB_VERIFICATION_ATTEMPT_METADATA_KEY to simulate max. attempts reached.
-    let metadata = increment_key_in_metadata(&job_item.metadata, JOB_PROCESS_ATTEMPT_METADATA_KEY).unwrap();
-    job_item.metadata = metadata;
+    // increasing the process attempts to simulate max. attempts reached.
+    job_item.counters.process_attempts = 1;
 
     // building config
     TestConfigBuilder::new().build().await;
@@ -384,14 +691,11 @@ async fn verify_job_with_rejected_status_works() {
     // DB checks.
     let updated_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
     assert_eq!(updated_job.status, JobStatus::VerificationFailed);
-    assert_eq!(updated_job.metadata.get(JOB_PROCESS_ATTEMPT_METADATA_KEY).unwrap(), "1");
-
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
+    assert_eq!(updated_job.counters.process_attempts, 1);
 
     // Queue checks.
     let consumed_messages_processing_queue =
-        config.queue().consume_message_from_queue(JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
+        config.queue().consume_message_from_queue(DA_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
     assert_matches!(consumed_messages_processing_queue, QueueError::NoData);
 }
 
@@ -416,6 +720,7 @@ async fn verify_job_with_pending_status_adds_to_queue_works() {
     job_handler.expect_verify_job().times(1).returning(move |_, _| Ok(JobVerificationStatus::Pending));
     job_handler.expect_max_verification_attempts().returning(move || 2u64);
     job_handler.expect_verification_polling_delay_seconds().returning(move || 2u64);
+    job_handler.expect_max_backoff_seconds().returning(move || 2u64);
 
     let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
     let ctx = mock_factory::get_job_handler_context();
@@ -426,11 +731,11 @@ async fn verify_job_with_pending_status_adds_to_queue_works() {
 
     // DB checks.
     let updated_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
-    assert_eq!(updated_job.metadata.get(JOB_VERIFICATION_ATTEMPT_METADATA_KEY).unwrap(), "1");
+    assert_eq!(updated_job.counters.verification_attempts, 1);
     assert_eq!(updated_job.status, JobStatus::PendingVerification);
 
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
+    // Waiting for the verification polling delay for the message to be delivered
+    sleep(Duration::from_secs(3)).await;
 
     // Queue checks
     let consumed_messages =
@@ -448,9 +753,8 @@ async fn verify_job_with_pending_status_works() {
     let mut job_item =
         build_job_item_by_type_and_status(JobType::DataSubmission, JobStatus::PendingVerification, "1".to_string());
 
-    // increasing JOB_VERIFICATION_ATTEMPT_METADATA_KEY to simulate max. attempts reached.
-    let metadata = increment_key_in_metadata(&job_item.metadata, JOB_VERIFICATION_ATTEMPT_METADATA_KEY).unwrap();
-    job_item.metadata = metadata;
+    // increasing the verification attempts to simulate max. attempts reached.
+    job_item.counters.verification_attempts = 1;
 
     // building config
     TestConfigBuilder::new().build().await;
@@ -465,6 +769,7 @@ async fn verify_job_with_pending_status_works() {
     job_handler.expect_verify_job().times(1).returning(move |_, _| Ok(JobVerificationStatus::Pending));
     job_handler.expect_max_verification_attempts().returning(move || 1u64);
     job_handler.expect_verification_polling_delay_seconds().returning(move || 2u64);
+    job_handler.expect_max_backoff_seconds().returning(move || 2u64);
 
     let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
     let ctx = mock_factory::get_job_handler_context();
@@ -476,10 +781,7 @@ async fn verify_job_with_pending_status_works() {
     // DB checks.
     let updated_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
     assert_eq!(updated_job.status, JobStatus::VerificationTimeout);
-    assert_eq!(updated_job.metadata.get(JOB_VERIFICATION_ATTEMPT_METADATA_KEY).unwrap(), "1");
-
-    // Waiting for 5 secs for message to be passed into the queue
-    sleep(Duration::from_secs(5)).await;
+    assert_eq!(updated_job.counters.verification_attempts, 1);
 
     // Queue checks.
     let consumed_messages_verification_queue =
@@ -487,17 +789,293 @@ async fn verify_job_with_pending_status_works() {
     assert_matches!(consumed_messages_verification_queue, QueueError::NoData);
 }
 
+/// Tests the consumer of the dead-letter queue, the job of a dead-lettered message is marked as
+/// failed with the message recorded in its metadata.
+#[rstest]
+#[tokio::test]
+async fn consume_dead_letter_from_queue_marks_job_failed() {
+    let job_item =
+        build_job_item_by_type_and_status(JobType::DataSubmission, JobStatus::PendingVerification, "1".to_string());
+
+    // building config
+    TestConfigBuilder::new().build().await;
+
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    let message = JobQueueMessage { id: job_item.id, trace_id: None, not_before: None };
+    let payload = serde_json::to_string(&message).unwrap();
+    config
+        .queue()
+        .send_message_to_queue(JOB_HANDLE_FAILURE_QUEUE.to_string(), payload.clone(), None, None)
+        .await
+        .unwrap();
+    consume_dead_letter_from_queue(JOB_HANDLE_FAILURE_QUEUE.to_string()).await.unwrap();
+
+    // DB checks.
+    let updated_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(updated_job.status, JobStatus::Failed);
+    assert_eq!(updated_job.metadata.get(JOB_METADATA_DEAD_LETTER_PAYLOAD_KEY).unwrap(), &payload);
+    assert_eq!(updated_job.metadata.get(JOB_METADATA_FAILED_STATUS_KEY).unwrap(), "PendingVerification");
+
+    // Queue checks.
+    let consumed_messages =
+        config.queue().consume_message_from_queue(JOB_HANDLE_FAILURE_QUEUE.to_string()).await.unwrap_err();
+    assert_matches!(consumed_messages, QueueError::NoData);
+}
+
+/// Tests `retry_job` function on a job which failed while being processed, the job is created
+/// again with its attempts reset and added to the process queue.
+#[rstest]
+#[tokio::test]
+async fn retry_job_failed_job_adds_to_process_queue_works() {
+    let mut job_item = build_job_item_by_type_and_status(JobType::DataSubmission, JobStatus::Failed, "1".to_string());
+    job_item.counters.process_attempts = 3;
+    job_item.metadata.insert(JOB_METADATA_FAILED_STATUS_KEY.to_string(), "LockedForProcessing".to_string());
+    job_item.metadata.insert(JOB_METADATA_DEAD_LETTER_PAYLOAD_KEY.to_string(), "payload".to_string());
+
+    // building config
+    TestConfigBuilder::new().build().await;
+
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    assert!(retry_job(job_item.id).await.is_ok());
+
+    // DB checks.
+    let updated_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(updated_job.status, JobStatus::Created);
+    assert_eq!(updated_job.counters.process_attempts, 0);
+    assert!(updated_job.metadata.get(JOB_METADATA_FAILED_STATUS_KEY).is_none());
+    assert!(updated_job.metadata.get(JOB_METADATA_DEAD_LETTER_PAYLOAD_KEY).is_none());
+
+    // Queue checks.
+    let consumed_messages =
+        config.queue().consume_message_from_queue(DA_JOB_PROCESSING_QUEUE.to_string()).await.unwrap();
+    let consumed_message_payload: MessagePayloadType = consumed_messages.payload_serde_json().unwrap().unwrap();
+    assert_eq!(consumed_message_payload.id, job_item.id);
+}
+
+/// Tests `retry_job` function on a job which timed out in verification, the job goes back to
+/// pending verification with its verification attempts reset and is added to the verification
+/// queue.
+#[rstest]
+#[tokio::test]
+async fn retry_job_verification_timeout_adds_to_verification_queue_works() {
+    let mut job_item =
+        build_job_item_by_type_and_status(JobType::DataSubmission, JobStatus::VerificationTimeout, "1".to_string());
+    job_item.counters.process_attempts = 1;
+    job_item.counters.verification_attempts = 5;
+
+    // building config
+    TestConfigBuilder::new().build().await;
+
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    let mut job_handler = MockJob::new();
+    job_handler.expect_verification_polling_delay_seconds().returning(move || 0u64);
+    job_handler.expect_max_backoff_seconds().returning(move || 0u64);
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::DataSubmission)).returning(move |_| Arc::clone(&job_handler));
+
+    assert!(retry_job(job_item.id).await.is_ok());
+
+    // DB checks.
+    let updated_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(updated_job.status, JobStatus::PendingVerification);
+    assert_eq!(updated_job.counters.process_attempts, 1);
+    assert_eq!(updated_job.counters.verification_attempts, 0);
+
+    // Queue checks.
+    let consumed_messages =
+        config.queue().consume_message_from_queue(JOB_VERIFICATION_QUEUE.to_string()).await.unwrap();
+    let consumed_message_payload: MessagePayloadType = consumed_messages.payload_serde_json().unwrap().unwrap();
+    assert_eq!(consumed_message_payload.id, job_item.id);
+}
+
+/// Tests `cancel_job` function on a created job, the job is cancelled and its message still
+/// queued is consumed without processing it.
+#[rstest]
+#[tokio::test]
+async fn cancel_job_created_job_works() {
+    let job_item = build_job_item_by_type_and_status(JobType::SnosRun, JobStatus::Created, "1".to_string());
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    assert!(cancel_job(job_item.id).await.is_ok());
+    let cancelled_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(cancelled_job.status, JobStatus::Cancelled);
+
+    // the job handler isn't called, no mock is set
+    assert!(process_job(job_item.id).await.is_ok());
+    let cancelled_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(cancelled_job.status, JobStatus::Cancelled);
+}
+
+/// Tests `cancel_job` function on jobs which can't be cancelled: completed or in the hands of a
+/// worker.
+#[rstest]
+#[case(JobStatus::Completed)]
+#[case(JobStatus::LockedForProcessing)]
+#[case(JobStatus::PendingVerification)]
+#[tokio::test]
+async fn cancel_job_invalid_status_fails(#[case] status: JobStatus) {
+    let job_item = build_job_item_by_type_and_status(JobType::SnosRun, status.clone(), "1".to_string());
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    assert!(cancel_job(job_item.id).await.is_err());
+    let job_in_db = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.status, status);
+}
+
+/// Tests that a job created with a parent which isn't completed waits for it, and is added to the
+/// process queue once the parent is completed.
+#[rstest]
+#[tokio::test]
+async fn create_job_with_parents_waits_for_parents_works() {
+    let snos_job = build_job_item_by_type_and_status(JobType::SnosRun, JobStatus::PendingVerification, "1".to_string());
+    let proving_job = build_job_item_by_type_and_status(JobType::ProofCreation, JobStatus::Created, "1".to_string());
+
+    // building config
+    TestConfigBuilder::new().build().await;
+
+    let config = config().await;
+    let database_client = config.database();
+    database_client.create_job(snos_job.clone()).await.unwrap();
+
+    let mut job_handler = MockJob::new();
+    let proving_job_clone = proving_job.clone();
+    job_handler.expect_create_job().times(1).returning(move |_, _, _| Ok(proving_job_clone.clone()));
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::ProofCreation)).returning(move |_| Arc::clone(&job_handler));
+
+    create_job_with_parents(JobType::ProofCreation, "1".to_string(), HashMap::new(), &[snos_job.clone()])
+        .await
+        .unwrap();
+
+    // the proving job waits for the SNOS job
+    let waiting_job = database_client.get_job_by_id(proving_job.id).await.unwrap().unwrap();
+    assert_eq!(waiting_job.status, JobStatus::PendingDependencies);
+    assert_eq!(waiting_job.parent_ids, vec![snos_job.id]);
+    let consumed_messages =
+        config.queue().consume_message_from_queue(PROVING_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err();
+    assert_matches!(consumed_messages, QueueError::NoData);
+
+    // the SNOS job completes and releases the proving job
+    database_client.update_job_status(&snos_job, JobStatus::Completed).await.unwrap();
+    release_dependent_jobs(&snos_job).await.unwrap();
+
+    let released_job = database_client.get_job_by_id(proving_job.id).await.unwrap().unwrap();
+    assert_eq!(released_job.status, JobStatus::Created);
+    let consumed_messages =
+        config.queue().consume_message_from_queue(PROVING_JOB_PROCESSING_QUEUE.to_string()).await.unwrap();
+    let consumed_message_payload: MessagePayloadType = consumed_messages.payload_serde_json().unwrap().unwrap();
+    assert_eq!(consumed_message_payload.id, proving_job.id);
+}
+
+/// Tests that a job created after its parent takes the trace id of the parent, and that the trace
+/// id is carried by the message of the job.
+#[rstest]
+#[tokio::test]
+async fn create_job_with_parents_inherits_trace_id_works() {
+    let mut snos_job = build_job_item_by_type_and_status(JobType::SnosRun, JobStatus::Completed, "1".to_string());
+    snos_job.metadata.insert(JOB_METADATA_TRACE_ID_KEY.to_string(), "trace".to_string());
+    let proving_job = build_job_item_by_type_and_status(JobType::ProofCreation, JobStatus::Created, "1".to_string());
+
+    // building config
+    TestConfigBuilder::new().build().await;
+
+    let config = config().await;
+    config.database().create_job(snos_job.clone()).await.unwrap();
+
+    let mut job_handler = MockJob::new();
+    let proving_job_clone = proving_job.clone();
+    job_handler.expect_create_job().times(1).returning(move |_, _, _| Ok(proving_job_clone.clone()));
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::ProofCreation)).returning(move |_| Arc::clone(&job_handler));
+
+    create_job_with_parents(JobType::ProofCreation, "1".to_string(), HashMap::new(), &[snos_job]).await.unwrap();
+
+    let created_job = config.database().get_job_by_id(proving_job.id).await.unwrap().unwrap();
+    assert_eq!(created_job.metadata.get(JOB_METADATA_TRACE_ID_KEY), Some(&"trace".to_string()));
+    let consumed_messages =
+        config.queue().consume_message_from_queue(PROVING_JOB_PROCESSING_QUEUE.to_string()).await.unwrap();
+    let consumed_message_payload: JobQueueMessage = consumed_messages.payload_serde_json().unwrap().unwrap();
+    assert_eq!(consumed_message_payload.id, proving_job.id);
+    assert_eq!(consumed_message_payload.trace_id, Some("trace".to_string()));
+}
+
+/// Tests that a submission made by a previous attempt is resumed rather than made again, and that
+/// a submission interrupted before its external id was recorded fails the job.
+#[rstest]
+#[tokio::test]
+async fn submit_once_resumes_previous_submission_works() {
+    let job_item = build_job_item_by_type_and_status(JobType::ProofCreation, JobStatus::Created, "1".to_string());
+
+    // building config
+    TestConfigBuilder::new().build().await;
+
+    let config = config().await;
+    config.database().create_job(job_item.clone()).await.unwrap();
+
+    let mut first_attempt = job_item.clone();
+    let external_id = submit_once(config.as_ref(), &mut first_attempt, PROVER_SUBMISSION_KEY, || async {
+        Ok("task_id".to_string())
+    })
+    .await
+    .unwrap();
+    assert_eq!(external_id, "task_id");
+
+    // the retry reads the job from the database and doesn't submit again
+    let mut retry = config.database().get_job_by_id(job_item.id).await.unwrap().unwrap();
+    let external_id = submit_once(config.as_ref(), &mut retry, PROVER_SUBMISSION_KEY, || async {
+        Err(eyre!("The task was submitted again"))
+    })
+    .await
+    .unwrap();
+    assert_eq!(external_id, "task_id");
+
+    // an attempt interrupted while submitting leaves the intent only
+    let mut interrupted = retry.clone();
+    forget_submissions(&mut interrupted);
+    interrupted
+        .metadata
+        .insert(format!("{}{}", JOB_METADATA_SUBMISSION_INTENT_PREFIX, PROVER_SUBMISSION_KEY), "0".to_string());
+    let error = submit_once(config.as_ref(), &mut interrupted, PROVER_SUBMISSION_KEY, || async {
+        Err(eyre!("The task was submitted again"))
+    })
+    .await
+    .unwrap_err();
+    assert_matches!(error.downcast_ref::<JobError>(), Some(JobError::InternalPermanent(_)));
+}
+
 fn build_job_item_by_type_and_status(job_type: JobType, job_status: JobStatus, internal_id: String) -> JobItem {
-    let mut hashmap: HashMap<String, String> = HashMap::new();
-    hashmap.insert(JOB_PROCESS_ATTEMPT_METADATA_KEY.to_string(), "0".to_string());
-    hashmap.insert(JOB_VERIFICATION_ATTEMPT_METADATA_KEY.to_string(), "0".to_string());
     JobItem {
         id: Uuid::new_v4(),
         internal_id,
         job_type,
         status: job_status,
         external_id: ExternalId::Number(0),
-        metadata: hashmap,
+        metadata: HashMap::new(),
         version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
     }
 }
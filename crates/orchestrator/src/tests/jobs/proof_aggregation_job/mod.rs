@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use mockall::predicate::eq;
+use prover_client_interface::{MockProverClient, Task, TaskStatus};
+use rstest::*;
+
+use super::super::common::{default_job_item, init_config};
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::data_storage::MockDataStorage;
+use crate::database::MockDatabase;
+use crate::jobs::constants::JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY;
+use crate::jobs::proof_aggregation_job::ProofAggregationJob;
+use crate::jobs::types::{ExternalId, JobItem, JobType, JobVerificationStatus};
+use crate::jobs::Job;
+
+fn aggregation_metadata(blocks: &str) -> HashMap<String, String> {
+    HashMap::from([(JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY.to_string(), blocks.to_string())])
+}
+
+#[rstest]
+#[case("3,4,5", true)]
+#[case("4,5", false)]
+#[case("3,5", false)]
+#[case("3", false)]
+#[tokio::test]
+async fn test_create_job(#[case] blocks: &str, #[case] valid: bool) {
+    let config = init_config(None, None, None, None, None, None, None).await;
+    let job = ProofAggregationJob.create_job(&config, String::from("3"), aggregation_metadata(blocks)).await;
+
+    assert_eq!(job.is_ok(), valid);
+    if let Ok(job) = job {
+        assert_eq!(job.job_type, JobType::ProofAggregation);
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_process_job_submits_proofs_in_order(#[from(default_job_item)] mut job_item: JobItem) {
+    job_item.internal_id = String::from("3");
+    job_item.metadata = aggregation_metadata("3,4");
+
+    let mut storage_client = MockDataStorage::new();
+    for block_no in [3, 4] {
+        storage_client
+            .expect_get_data()
+            .with(eq(StorageKey::new(ArtifactKind::Proof, block_no).to_string()))
+            .times(1)
+            .returning(move |_| Ok(Bytes::from(format!("proof {}", block_no))));
+    }
+
+    let mut prover_client = MockProverClient::new();
+    prover_client
+        .expect_submit_task()
+        .withf(|task| {
+            matches!(task, Task::AggregateProofs(proofs) if *proofs == vec![b"proof 3".to_vec(), b"proof 4".to_vec()])
+        })
+        .times(1)
+        .returning(|_| Ok("task_id".to_string()));
+    let mut db = MockDatabase::new();
+    db.expect_update_metadata().times(2).returning(|_, _| Ok(()));
+
+    let config = init_config(None, Some(db), None, None, Some(prover_client), None, Some(storage_client)).await;
+
+    assert_eq!(ProofAggregationJob.process_job(&config, &mut job_item).await.unwrap(), "task_id");
+}
+
+#[rstest]
+#[case(TaskStatus::Processing, JobVerificationStatus::Pending)]
+#[case(TaskStatus::Succeeded, JobVerificationStatus::Verified)]
+#[case(
+    TaskStatus::Failed("out of memory".to_string()),
+    JobVerificationStatus::Rejected("Proof aggregation job #3 failed with error: out of memory".to_string())
+)]
+#[tokio::test]
+async fn test_verify_job(
+    #[from(default_job_item)] mut job_item: JobItem,
+    #[case] task_status: TaskStatus,
+    #[case] expected_status: JobVerificationStatus,
+) {
+    job_item.internal_id = String::from("3");
+    job_item.external_id = ExternalId::String("task_id".to_string().into_boxed_str());
+
+    let mut prover_client = MockProverClient::new();
+    prover_client
+        .expect_get_task_status()
+        .with(eq("task_id".to_string()))
+        .times(1)
+        .return_once(move |_| Ok(task_status));
+
+    let config = init_config(None, None, None, None, Some(prover_client), None, None).await;
+
+    assert_eq!(ProofAggregationJob.verify_job(&config, &mut job_item).await.unwrap(), expected_status);
+}
@@ -2,14 +2,20 @@ use std::collections::HashMap;
 
 use crate::config::{config, config_force_init};
 use httpmock::prelude::*;
+use mockall::predicate::eq;
 use prover_client_interface::{MockProverClient, TaskStatus};
 use rstest::*;
+use settlement_client_interface::MockSettlementClient;
 use uuid::Uuid;
 
-use super::super::common::{default_job_item, init_config};
-use crate::jobs::constants::JOB_METADATA_CAIRO_PIE_PATH_KEY;
-use crate::jobs::proving_job::ProvingJob;
-use crate::jobs::types::{JobItem, JobStatus, JobType};
+use super::super::common::{default_job_item, init_config, EnvVarGuard};
+use crate::codec::{codec_for, CodecUsage};
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::data_storage::MockDataStorage;
+use crate::database::MockDatabase;
+use crate::jobs::constants::{JOB_METADATA_CAIRO_PIE_PATH_KEY, JOB_METADATA_PROOF_FACT_KEY};
+use crate::jobs::proving_job::{ProvingJob, ENV_PROOF_FACT_VERIFICATION};
+use crate::jobs::types::{JobCounters, JobItem, JobPriority, JobStatus, JobType, JobVerificationStatus};
 use crate::jobs::Job;
 
 #[rstest]
@@ -40,11 +46,105 @@ async fn test_create_job() {
 async fn test_verify_job(#[from(default_job_item)] mut job_item: JobItem) {
     let mut prover_client = MockProverClient::new();
     prover_client.expect_get_task_status().times(1).returning(|_| Ok(TaskStatus::Succeeded));
+    prover_client.expect_get_proof().times(1).returning(|_| Ok(None));
 
     let config = init_config(None, None, None, None, Some(prover_client), None, None).await;
     assert!(ProvingJob.verify_job(&config, &mut job_item).await.is_ok());
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_stores_proof(#[from(default_job_item)] mut job_item: JobItem) {
+    let mut prover_client = MockProverClient::new();
+    prover_client.expect_get_task_status().times(1).returning(|_| Ok(TaskStatus::Succeeded));
+    prover_client.expect_get_proof().times(1).returning(|_| Ok(Some(b"proof".to_vec())));
+
+    let mut storage_client = MockDataStorage::new();
+    let key = StorageKey::new(ArtifactKind::Proof, &job_item.internal_id).to_string();
+    storage_client
+        .expect_put_data()
+        .with(eq(bytes::Bytes::from_static(b"proof")), eq(key))
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    let config = init_config(None, None, None, None, Some(prover_client), None, Some(storage_client)).await;
+    assert_eq!(ProvingJob.verify_job(&config, &mut job_item).await.unwrap(), JobVerificationStatus::Verified);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_checks_fact(#[from(default_job_item)] mut job_item: JobItem) {
+    let _fact_verification = EnvVarGuard::set(ENV_PROOF_FACT_VERIFICATION, "true");
+    let fact = [1u8; 32];
+    job_item.metadata.insert(JOB_METADATA_PROOF_FACT_KEY.into(), format!("0x{}", hex::encode(fact)));
+
+    let mut prover_client = MockProverClient::new();
+    prover_client.expect_get_task_status().times(1).returning(|_| Ok(TaskStatus::Succeeded));
+    prover_client.expect_get_proof().times(1).returning(|_| Ok(None));
+    prover_client.expect_registers_facts().return_const(true);
+    let mut settlement_client = MockSettlementClient::new();
+    settlement_client.expect_verify_fact().with(eq(fact)).times(1).returning(|_| Ok(true));
+
+    let config = init_config(None, None, None, None, Some(prover_client), Some(settlement_client), None).await;
+    assert_eq!(ProvingJob.verify_job(&config, &mut job_item).await.unwrap(), JobVerificationStatus::Verified);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_skips_fact_by_default(#[from(default_job_item)] mut job_item: JobItem) {
+    job_item.metadata.insert(JOB_METADATA_PROOF_FACT_KEY.into(), format!("0x{}", hex::encode([1u8; 32])));
+
+    let mut prover_client = MockProverClient::new();
+    prover_client.expect_get_task_status().times(1).returning(|_| Ok(TaskStatus::Succeeded));
+    prover_client.expect_get_proof().times(1).returning(|_| Ok(None));
+    let mut settlement_client = MockSettlementClient::new();
+    settlement_client.expect_verify_fact().never();
+
+    let config = init_config(None, None, None, None, Some(prover_client), Some(settlement_client), None).await;
+    assert_eq!(ProvingJob.verify_job(&config, &mut job_item).await.unwrap(), JobVerificationStatus::Verified);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_skips_fact_of_prover_not_registering_them(#[from(default_job_item)] mut job_item: JobItem) {
+    let _fact_verification = EnvVarGuard::set(ENV_PROOF_FACT_VERIFICATION, "true");
+    job_item.metadata.insert(JOB_METADATA_PROOF_FACT_KEY.into(), format!("0x{}", hex::encode([1u8; 32])));
+
+    let mut prover_client = MockProverClient::new();
+    prover_client.expect_get_task_status().times(1).returning(|_| Ok(TaskStatus::Succeeded));
+    prover_client.expect_get_proof().times(1).returning(|_| Ok(Some(b"proof".to_vec())));
+    prover_client.expect_registers_facts().return_const(false);
+    let mut settlement_client = MockSettlementClient::new();
+    settlement_client.expect_verify_fact().never();
+    let mut storage_client = MockDataStorage::new();
+    storage_client.expect_put_data().times(1).returning(|_, _| Ok(()));
+
+    let config =
+        init_config(None, None, None, None, Some(prover_client), Some(settlement_client), Some(storage_client)).await;
+    assert_eq!(ProvingJob.verify_job(&config, &mut job_item).await.unwrap(), JobVerificationStatus::Verified);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_rejects_unregistered_fact(#[from(default_job_item)] mut job_item: JobItem) {
+    let _fact_verification = EnvVarGuard::set(ENV_PROOF_FACT_VERIFICATION, "true");
+    let fact = [1u8; 32];
+    job_item.metadata.insert(JOB_METADATA_PROOF_FACT_KEY.into(), format!("0x{}", hex::encode(fact)));
+
+    let mut prover_client = MockProverClient::new();
+    prover_client.expect_get_task_status().times(1).returning(|_| Ok(TaskStatus::Succeeded));
+    prover_client.expect_get_proof().times(0);
+    prover_client.expect_registers_facts().return_const(true);
+    let mut settlement_client = MockSettlementClient::new();
+    settlement_client.expect_verify_fact().with(eq(fact)).times(1).returning(|_| Ok(false));
+
+    let config = init_config(None, None, None, None, Some(prover_client), Some(settlement_client), None).await;
+    assert!(matches!(
+        ProvingJob.verify_job(&config, &mut job_item).await.unwrap(),
+        JobVerificationStatus::Rejected(_)
+    ));
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_process_job() {
@@ -52,10 +152,13 @@ async fn test_process_job() {
 
     let mut prover_client = MockProverClient::new();
     prover_client.expect_submit_task().times(1).returning(|_| Ok("task_id".to_string()));
+    // the intent to submit and the external id are persisted around the submission
+    let mut db = MockDatabase::new();
+    db.expect_update_metadata().times(2).returning(|_, _| Ok(()));
 
     let config_init = init_config(
         Some(format!("http://localhost:{}", server.port())),
-        None,
+        Some(db),
         None,
         None,
         Some(prover_client),
@@ -68,22 +171,43 @@ async fn test_process_job() {
 
     let cairo_pie_path = format!("{}/src/tests/artifacts/fibonacci.zip", env!("CARGO_MANIFEST_DIR"));
 
-    assert_eq!(
-        ProvingJob
-            .process_job(
-                config().await.as_ref(),
-                &mut JobItem {
-                    id: Uuid::default(),
-                    internal_id: "0".into(),
-                    job_type: JobType::ProofCreation,
-                    status: JobStatus::Created,
-                    external_id: String::new().into(),
-                    metadata: HashMap::from([(JOB_METADATA_CAIRO_PIE_PATH_KEY.into(), cairo_pie_path)]),
-                    version: 0,
-                }
-            )
-            .await
-            .unwrap(),
-        "task_id".to_string()
-    );
+    let mut job_item = JobItem {
+        id: Uuid::default(),
+        internal_id: "0".into(),
+        job_type: JobType::ProofCreation,
+        status: JobStatus::Created,
+        external_id: String::new().into(),
+        metadata: HashMap::from([(JOB_METADATA_CAIRO_PIE_PATH_KEY.into(), cairo_pie_path)]),
+        version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
+    };
+    assert_eq!(ProvingJob.process_job(config().await.as_ref(), &mut job_item).await.unwrap(), "task_id".to_string());
+    // the fact of the proof is recorded to be checked on the settlement layer once proven
+    assert!(job_item.metadata.contains_key(JOB_METADATA_PROOF_FACT_KEY));
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_process_job_fetches_stored_pie(#[from(default_job_item)] mut job_item: JobItem) {
+    let cairo_pie_path = format!("{}/src/tests/artifacts/fibonacci.zip", env!("CARGO_MANIFEST_DIR"));
+    let encoded_pie = codec_for(CodecUsage::CairoPie).unwrap().encode(&std::fs::read(cairo_pie_path).unwrap()).unwrap();
+
+    let mut storage_client = MockDataStorage::new();
+    let key = StorageKey::new(ArtifactKind::CairoPie, &job_item.internal_id).to_string();
+    storage_client
+        .expect_get_data()
+        .with(eq(key))
+        .times(1)
+        .returning(move |_| Ok(bytes::Bytes::from(encoded_pie.clone())));
+    let mut prover_client = MockProverClient::new();
+    prover_client.expect_submit_task().times(1).returning(|_| Ok("task_id".to_string()));
+    let mut db = MockDatabase::new();
+    db.expect_update_metadata().times(2).returning(|_, _| Ok(()));
+
+    let config = init_config(None, Some(db), None, None, Some(prover_client), None, Some(storage_client)).await;
+    assert_eq!(ProvingJob.process_job(&config, &mut job_item).await.unwrap(), "task_id".to_string());
 }
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use httpmock::prelude::*;
+use mockall::predicate::eq;
+use rstest::*;
+use serde_json::json;
+use snos::io::output::StarknetOsOutput;
+use starknet::core::types::{FieldElement, NonceUpdate, StateDiff, StateUpdate};
+
+use super::super::common::{default_job_item, init_config};
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::data_storage::MockDataStorage;
+use crate::jobs::block_range::BlockRange;
+use crate::jobs::constants::{JOB_METADATA_CHECKPOINT_KEY, JOB_METADATA_SNOS_PROGRAM_HASH_KEY};
+use crate::jobs::snos_job::consistency::{diff_report, diff_snos_output};
+use crate::jobs::snos_job::madara::{fetch_snos_input, GET_SNOS_INPUT_METHOD};
+use crate::jobs::snos_job::os_program::{
+    parse_os_programs, select_os_program, split_at_program_starts, ENV_SNOS_OS_PROGRAMS,
+};
+use crate::jobs::snos_job::pool::SnosPool;
+use crate::jobs::snos_job::sandbox::{run_snos_sandboxed, SnosLimits};
+use crate::jobs::snos_job::{pinned_snos_input, SnosJob};
+use crate::jobs::types::{JobItem, JobStatus, JobType, JobVerificationStatus};
+use crate::jobs::Job;
+
+#[rstest]
+#[tokio::test]
+async fn test_create_job() {
+    let config = init_config(None, None, None, None, None, None, None).await;
+    let job = SnosJob.create_job(&config, String::from("0"), HashMap::new()).await.unwrap();
+
+    assert_eq!(job.job_type, JobType::SnosRun, "job_type should be SnosRun");
+    assert!(!(job.id.is_nil()), "id should not be nil");
+    assert_eq!(job.status, JobStatus::Created, "status should be Created");
+    assert_eq!(job.version, 0_i32, "version should be 0");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_job(#[from(default_job_item)] mut job_item: JobItem) {
+    let config = init_config(None, None, None, None, None, None, None).await;
+    assert_eq!(SnosJob.verify_job(&config, &mut job_item).await.unwrap(), JobVerificationStatus::Verified);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_fetch_snos_input_rpc_error() {
+    let server = MockServer::start();
+    let rpc_mock = server.mock(|when, then| {
+        when.method(POST).path("/").body_contains(GET_SNOS_INPUT_METHOD);
+        then.status(200).json_body(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": 24, "message": "Block not found" }
+        }));
+    });
+
+    let err = fetch_snos_input(&server.base_url(), 7).await.unwrap_err();
+
+    assert!(err.to_string().contains("Block not found"), "unexpected error: {}", err);
+    rpc_mock.assert();
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_snos_pool_bounds_concurrent_runs() {
+    let pool = Arc::new(SnosPool::new(2));
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+
+    let runs = (0..6).map(|run| {
+        let (pool, running, max_running) = (pool.clone(), running.clone(), max_running.clone());
+        tokio::spawn(async move {
+            pool.run(async move {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now_running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(run)
+            })
+            .await
+        })
+    });
+    let results: Vec<usize> =
+        futures::future::join_all(runs).await.into_iter().map(|run| run.unwrap().unwrap()).collect();
+
+    assert_eq!(results, (0..6).collect::<Vec<_>>());
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+}
+
+/// Runner binary running `script`, it gets `snos-run <run directory>`
+fn snos_runner(dir: &tempfile::TempDir, script: &str) -> PathBuf {
+    let runner_binary = dir.path().join("snos-runner.sh");
+    std::fs::write(&runner_binary, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    std::fs::set_permissions(&runner_binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+    runner_binary
+}
+
+fn snos_limits(timeout: Duration) -> SnosLimits {
+    SnosLimits { memory_bytes: 0, cpu_secs: 0, timeout }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sandboxed_snos_run_failure_keeps_output() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let runner_binary = snos_runner(&dir, "echo 'running block'; echo 'out of memory' >&2; exit 1");
+
+    let limits = snos_limits(Duration::from_secs(10));
+    let failure = run_snos_sandboxed(&runner_binary, Path::new("os.json"), b"{}", &limits)
+        .await
+        .unwrap()
+        .expect_err("SNOS run should fail");
+
+    assert!(failure.reason.contains("exited"), "unexpected reason: {}", failure.reason);
+    assert_eq!(failure.stdout, b"running block\n");
+    assert_eq!(failure.stderr, b"out of memory\n");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sandboxed_snos_run_times_out() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let runner_binary = snos_runner(&dir, "sleep 10");
+
+    let limits = snos_limits(Duration::from_millis(200));
+    let failure = run_snos_sandboxed(&runner_binary, Path::new("os.json"), b"{}", &limits)
+        .await
+        .unwrap()
+        .expect_err("SNOS run should time out");
+
+    assert!(failure.reason.contains("timed out"), "unexpected reason: {}", failure.reason);
+}
+
+fn snos_output(use_kzg_da: &str) -> StarknetOsOutput {
+    serde_json::from_value(json!({
+        "initial_root": "0x1",
+        "final_root": "0x2",
+        "block_number": "0x7",
+        "block_hash": "0x3",
+        "starknet_os_config_hash": "0x4",
+        "use_kzg_da": use_kzg_da,
+        "messages_to_l1": [],
+        "messages_to_l2": [],
+        "contracts": [],
+        "classes": {}
+    }))
+    .unwrap()
+}
+
+fn state_update(new_root: u64, nonces: Vec<NonceUpdate>) -> StateUpdate {
+    StateUpdate {
+        block_hash: FieldElement::from(3u64),
+        old_root: FieldElement::ONE,
+        new_root: FieldElement::from(new_root),
+        state_diff: StateDiff {
+            storage_diffs: vec![],
+            deprecated_declared_classes: vec![],
+            declared_classes: vec![],
+            deployed_contracts: vec![],
+            replaced_classes: vec![],
+            nonces,
+        },
+    }
+}
+
+#[rstest]
+fn test_snos_output_matches_state_update() {
+    assert!(diff_snos_output(7, &snos_output("0x0"), &state_update(2, vec![])).is_empty());
+}
+
+#[rstest]
+fn test_snos_output_root_divergence() {
+    let differences = diff_snos_output(7, &snos_output("0x1"), &state_update(5, vec![]));
+
+    assert_eq!(differences, vec!["new root: 0x2 in the SNOS output, 0x5 in the state update".to_string()]);
+    assert!(diff_report(7, &differences).contains("SNOS output of block 7 doesn't match the state update"));
+}
+
+#[rstest]
+fn test_snos_output_da_segment_divergence() {
+    let nonce = NonceUpdate { contract_address: FieldElement::from(0x10u64), nonce: FieldElement::ONE };
+
+    // with blobs the OS output doesn't carry the contract changes
+    assert!(diff_snos_output(7, &snos_output("0x1"), &state_update(2, vec![nonce.clone()])).is_empty());
+    assert_eq!(
+        diff_snos_output(7, &snos_output("0x0"), &state_update(2, vec![nonce])),
+        vec!["contract 0x10 nonce: 0x0 in the SNOS output, 0x1 in the state update".to_string()]
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_snos_input_is_reused_by_retries() {
+    let key = StorageKey::new(ArtifactKind::SnosInput, 7).to_string();
+    let mut storage_client = MockDataStorage::new();
+    storage_client.expect_data_exists().with(eq(key.clone())).times(1).returning(|_| Ok(true));
+    storage_client.expect_get_data().with(eq(key)).times(1).returning(|_| Ok(bytes::Bytes::from_static(b"{}")));
+    storage_client.expect_put_data().times(0);
+
+    let config = init_config(None, None, None, None, None, None, Some(storage_client)).await;
+    assert_eq!(pinned_snos_input(&config, 7).await.unwrap(), b"{}".to_vec());
+}
+
+#[rstest]
+fn test_os_program_selected_by_block_range() {
+    let programs = parse_os_programs("650000:0x02:/os/v1.json, 0:0x1:/os/v0.json").unwrap();
+
+    assert_eq!(select_os_program(&programs, 649999).unwrap().path, PathBuf::from("/os/v0.json"));
+    let upgraded = select_os_program(&programs, 650000).unwrap();
+    assert_eq!(upgraded.path, PathBuf::from("/os/v1.json"));
+    assert_eq!(upgraded.program_hash.as_deref(), Some("0x2"));
+}
+
+#[rstest]
+#[case("0:0x1")]
+#[case("zero:0x1:/os/v0.json")]
+#[case("0:0x1:/os/v0.json,0:0x2:/os/v1.json")]
+fn test_invalid_os_programs(#[case] os_programs: &str) {
+    assert!(parse_os_programs(os_programs).is_err());
+}
+
+#[rstest]
+#[case::before_upgrades((5, 9), vec![(5, 9)])]
+#[case::starting_at_upgrade((10, 12), vec![(10, 12)])]
+#[case::ending_at_upgrade((8, 10), vec![(8, 9), (10, 10)])]
+#[case::across_upgrades((9, 21), vec![(9, 9), (10, 19), (20, 21)])]
+fn test_split_at_os_upgrades(#[case] range: (u64, u64), #[case] expected: Vec<(u64, u64)>) {
+    let programs = parse_os_programs("0:0x1:/os/v0.json,10:0x2:/os/v1.json,20:0x3:/os/v2.json").unwrap();
+    let ranges = split_at_program_starts(&programs, BlockRange::new(range.0, range.1).unwrap()).unwrap();
+    assert_eq!(ranges.iter().map(|range| (range.start, range.end)).collect::<Vec<_>>(), expected);
+}
+
+#[rstest]
+fn test_no_os_program_before_first_block() {
+    let programs = parse_os_programs("10:0x1:/os/v1.json").unwrap();
+    assert!(select_os_program(&programs, 9).is_err());
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_process_job_resumes_after_checkpoint(#[from(default_job_item)] mut job_item: JobItem) {
+    std::env::set_var(ENV_SNOS_OS_PROGRAMS, "0:0x1:/os/v0.json");
+    // the blocks up to the checkpoint were run by a previous attempt, nothing is run again
+    let mut storage_client = MockDataStorage::new();
+    storage_client.expect_put_data().times(0);
+    let config = init_config(None, None, None, None, None, None, Some(storage_client)).await;
+
+    job_item.internal_id = "5-6".to_string();
+    job_item.metadata.insert(JOB_METADATA_CHECKPOINT_KEY.to_string(), "6".to_string());
+    SnosJob.process_job(&config, &mut job_item).await.unwrap();
+
+    assert_eq!(job_item.metadata.get(JOB_METADATA_SNOS_PROGRAM_HASH_KEY), Some(&"0x1".to_string()));
+}
@@ -1,25 +1,39 @@
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use bytes::Bytes;
+use cairo_vm::Felt252;
+use ethereum_settlement_client::kzg::{build_kzg_proof, x_0_point, X_0_POINT_INDEX};
 use httpmock::prelude::*;
 use lazy_static::lazy_static;
 use mockall::predicate::eq;
 use rstest::*;
-use settlement_client_interface::MockSettlementClient;
+use serde_json::json;
+use settlement_client_interface::{
+    FeeTooHighError, MockSettlementClient, SettlementOperation, SettlementVerificationStatus,
+};
+use starknet::core::crypto::compute_hash_on_elements;
+use starknet::core::types::FieldElement;
 
-use super::super::common::init_config;
+use super::super::common::{init_config, EnvVarGuard};
 use crate::config::{config, config_force_init};
 use crate::constants::{BLOB_DATA_FILE_NAME, SNOS_OUTPUT_FILE_NAME};
+use crate::data_storage::key::{ArtifactKind, StorageKey};
 use crate::data_storage::MockDataStorage;
 use crate::jobs::constants::{
+    JOB_METADATA_SNOS_PROGRAM_HASH_KEY, JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX,
     JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY, JOB_METADATA_STATE_UPDATE_FETCH_FROM_TESTS,
-    JOB_PROCESS_ATTEMPT_METADATA_KEY,
+    JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO, JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX,
+    JOB_METADATA_STATE_UPDATE_SENT_AT_PREFIX,
 };
-use crate::jobs::state_update_job::utils::hex_string_to_u8_vec;
-use crate::jobs::state_update_job::StateUpdateJob;
-use crate::jobs::types::{JobStatus, JobType};
+use crate::jobs::da_job::test::{get_nonce_attached, read_state_update_from_file};
+use crate::jobs::snos_job::SnosJob;
+use crate::jobs::state_update_job::utils::{hex_string_to_u8_vec, onchain_data_hash_and_size};
+use crate::jobs::state_update_job::{StateUpdateJob, ENV_SETTLEMENT_BATCH_STATE_UPDATES};
+use crate::jobs::types::{JobBlockedError, JobStatus, JobType, JobVerificationStatus};
 use crate::jobs::Job;
 
 lazy_static! {
@@ -55,14 +69,22 @@ async fn test_process_job() {
 
     // Mock the latest block settled
     settlement_client.expect_get_last_settled_block().returning(|| Ok(651052_u64));
+    // The core contract state root doesn't move until the transactions are mined, the blocks after
+    // the first one are checked against the block before them
+    let settled_root = snos_initial_root(
+        &fs::read_to_string(
+            CURRENT_PATH.join(format!("src/tests/jobs/state_update_job/test_data/651053/{}", SNOS_OUTPUT_FILE_NAME)),
+        )
+        .expect("Failed to read the snos output data json file"),
+    );
+    settlement_client.expect_get_state_root().times(1).returning(move || Ok(settled_root));
 
     // TODO: have tests for update_state_calldata, only kzg for now
     let block_numbers = ["651053", "651054", "651055", "651056"];
     for block_no in block_numbers {
-        let program_output: Vec<[u8; 32]> = vec![];
         let state_diff: Vec<Vec<u8>> = load_state_diff_file(block_no.parse::<u64>().unwrap()).await;
 
-        let snos_output_key = block_no.to_owned() + "/" + SNOS_OUTPUT_FILE_NAME;
+        let snos_output_key = StorageKey::new(ArtifactKind::SnosOutput, block_no).to_string();
         let snos_output_data = fs::read_to_string(
             CURRENT_PATH
                 .join(format!("src/tests/jobs/state_update_job/test_data/{}/{}", block_no, SNOS_OUTPUT_FILE_NAME)),
@@ -73,7 +95,7 @@ async fn test_process_job() {
             .with(eq(snos_output_key))
             .returning(move |_| Ok(Bytes::from(snos_output_data.clone())));
 
-        let blob_data_key = block_no.to_owned() + "/" + BLOB_DATA_FILE_NAME;
+        let blob_data_key = StorageKey::new(ArtifactKind::BlobData, block_no).to_string();
         let blob_data = fs::read_to_string(
             CURRENT_PATH
                 .join(format!("src/tests/jobs/state_update_job/test_data/{}/{}", block_no, BLOB_DATA_FILE_NAME)),
@@ -86,16 +108,14 @@ async fn test_process_job() {
             .with(eq(blob_data_key))
             .returning(move |_| Ok(Bytes::from(blob_serialized.clone())));
 
-        let x_0_key = block_no.to_owned() + "/" + X_0_FILE_NAME;
-        let x_0 = fs::read_to_string(
-            CURRENT_PATH.join(format!("src/tests/jobs/state_update_job/test_data/{}/{}", block_no, X_0_FILE_NAME)),
-        )
-        .expect("Failed to read the blob data txt file");
-        storage_client.expect_get_data().with(eq(x_0_key)).returning(move |_| Ok(Bytes::from(x_0.clone())));
+        // the program output commits to the KZG point of the blob
+        let block_no = block_no.parse::<u64>().unwrap();
+        let mut program_output = vec![Felt252::ZERO; X_0_POINT_INDEX + 1];
+        program_output[X_0_POINT_INDEX] = Felt252::from_bytes_be_slice(&read_test_data_hex(block_no, X_0_FILE_NAME));
+        let program_output = mock_program_output(&mut storage_client, block_no, program_output);
 
         settlement_client
             .expect_update_state_with_blobs()
-            // TODO: vec![] is program_output
             .with(eq(program_output), eq(state_diff))
             .returning(|_, _| Ok(String::from("0x5d17fac98d9454030426606019364f6e68d915b91f6210ef1e2628cd6987442")));
     }
@@ -115,7 +135,6 @@ async fn test_process_job() {
     let mut metadata: HashMap<String, String> = HashMap::new();
     metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_FETCH_FROM_TESTS), String::from("TRUE"));
     metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), block_numbers.join(","));
-    metadata.insert(String::from(JOB_PROCESS_ATTEMPT_METADATA_KEY), String::from("0"));
 
     let mut job =
         StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
@@ -145,7 +164,6 @@ async fn test_process_job_invalid_inputs(#[case] block_numbers_to_settle: String
 
     let mut metadata: HashMap<String, String> = HashMap::new();
     metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), block_numbers_to_settle);
-    metadata.insert(String::from(JOB_PROCESS_ATTEMPT_METADATA_KEY), String::from("0"));
 
     let mut job = StateUpdateJob.create_job(&config, String::from("internal_id"), metadata).await.unwrap();
     let status = StateUpdateJob.process_job(&config, &mut job).await;
@@ -185,15 +203,619 @@ async fn test_process_job_invalid_input_gap() {
 
     let mut metadata: HashMap<String, String> = HashMap::new();
     metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("6, 7, 8"));
-    metadata.insert(String::from(JOB_PROCESS_ATTEMPT_METADATA_KEY), String::from("0"));
 
     let mut job =
         StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
     let _ = StateUpdateJob.process_job(config().await.as_ref(), &mut job).await.unwrap();
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_process_job_blocked_on_state_root_divergence() {
+    let server = MockServer::start();
+    let mut settlement_client = MockSettlementClient::new();
+    let mut storage_client = MockDataStorage::new();
+
+    settlement_client.expect_get_last_settled_block().returning(|| Ok(651052_u64));
+    // The core contract root doesn't match the old root of block 651053
+    settlement_client.expect_get_state_root().times(1).returning(|| Ok([1; 32]));
+    settlement_client.expect_update_state_with_blobs().never();
+
+    let snos_output_data = fs::read_to_string(
+        CURRENT_PATH.join(format!("src/tests/jobs/state_update_job/test_data/651053/{}", SNOS_OUTPUT_FILE_NAME)),
+    )
+    .expect("Failed to read the snos output data json file");
+    storage_client
+        .expect_get_data()
+        .with(eq(StorageKey::new(ArtifactKind::SnosOutput, 651053).to_string()))
+        .returning(move |_| Ok(Bytes::from(snos_output_data.clone())));
+    mock_snos_output(&mut storage_client, 651054, 651054);
+
+    let config_init = init_config(
+        Some(format!("http://localhost:{}", server.port())),
+        None,
+        None,
+        None,
+        None,
+        Some(settlement_client),
+        Some(storage_client),
+    )
+    .await;
+    config_force_init(config_init).await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("651053, 651054"));
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    let error = StateUpdateJob.process_job(config().await.as_ref(), &mut job).await.unwrap_err();
+
+    assert!(error.downcast_ref::<JobBlockedError>().is_some(), "divergence should block the job");
+    assert!(error.to_string().contains("Block #651053 - State root divergence"));
+    assert_eq!(job.metadata.get(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO).unwrap(), "651053");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_process_job_blocked_on_state_root_chain_break() {
+    let server = MockServer::start();
+    let mut settlement_client = MockSettlementClient::new();
+    let mut storage_client = MockDataStorage::new();
+
+    let settled_root = snos_initial_root(
+        &fs::read_to_string(
+            CURRENT_PATH.join(format!("src/tests/jobs/state_update_job/test_data/651053/{}", SNOS_OUTPUT_FILE_NAME)),
+        )
+        .expect("Failed to read the snos output data json file"),
+    );
+    settlement_client.expect_get_last_settled_block().returning(|| Ok(651052_u64));
+    settlement_client.expect_get_state_root().times(1).returning(move || Ok(settled_root));
+    settlement_client.expect_update_state_with_blobs().never();
+
+    // The program output of block 651054 doesn't build on the new root of block 651053
+    mock_snos_output(&mut storage_client, 651053, 651053);
+    mock_snos_output(&mut storage_client, 651054, 651055);
+
+    let config_init = init_config(
+        Some(format!("http://localhost:{}", server.port())),
+        None,
+        None,
+        None,
+        None,
+        Some(settlement_client),
+        Some(storage_client),
+    )
+    .await;
+    config_force_init(config_init).await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("651053, 651054"));
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    let error = StateUpdateJob.process_job(config().await.as_ref(), &mut job).await.unwrap_err();
+
+    assert!(error.downcast_ref::<JobBlockedError>().is_some(), "divergence should block the job");
+    assert!(error.to_string().contains("Block #651054 - State root divergence"));
+    // nothing was sent, the job restarts from the first block
+    assert_eq!(job.metadata.get(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO).unwrap(), "651053");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_process_job_blocked_on_program_hash_mismatch() {
+    let server = MockServer::start();
+    let mut settlement_client = MockSettlementClient::new();
+
+    settlement_client.expect_get_last_settled_block().returning(|| Ok(651052_u64));
+    // The core contract was upgraded to a program the block didn't run with
+    settlement_client.expect_get_program_hash().times(1).returning(|| Ok([2; 32]));
+    settlement_client.expect_get_state_root().never();
+
+    let config_init = init_config(
+        Some(format!("http://localhost:{}", server.port())),
+        None,
+        None,
+        None,
+        None,
+        Some(settlement_client),
+        None,
+    )
+    .await;
+    config_force_init(config_init).await;
+
+    let snos_metadata = HashMap::from([(JOB_METADATA_SNOS_PROGRAM_HASH_KEY.to_string(), "0x1".to_string())]);
+    let snos_job = SnosJob.create_job(config().await.as_ref(), String::from("651053"), snos_metadata).await.unwrap();
+    config().await.database().create_job(snos_job).await.unwrap();
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("651053"));
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    let error = StateUpdateJob.process_job(config().await.as_ref(), &mut job).await.unwrap_err();
+
+    assert!(error.downcast_ref::<JobBlockedError>().is_some(), "program mismatch should block the job");
+    assert!(error.to_string().contains("Block #651053 - OS program mismatch"));
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_process_job_fee_too_high() {
+    let server = MockServer::start();
+    let mut settlement_client = MockSettlementClient::new();
+    let mut storage_client = MockDataStorage::new();
+
+    let snos_output_data = fs::read_to_string(
+        CURRENT_PATH.join(format!("src/tests/jobs/state_update_job/test_data/651053/{}", SNOS_OUTPUT_FILE_NAME)),
+    )
+    .expect("Failed to read the snos output data json file");
+    let initial_root = snos_initial_root(&snos_output_data);
+
+    settlement_client.expect_get_last_settled_block().returning(|| Ok(651052_u64));
+    settlement_client.expect_get_state_root().times(1).returning(move || Ok(initial_root));
+    settlement_client
+        .expect_update_state_with_blobs()
+        .times(1)
+        .returning(|_, _| Err(FeeTooHighError("blob base fee exceeds the cap".to_string()).into()));
+
+    storage_client
+        .expect_get_data()
+        .with(eq(StorageKey::new(ArtifactKind::SnosOutput, 651053).to_string()))
+        .returning(move |_| Ok(Bytes::from(snos_output_data.clone())));
+    mock_snos_output(&mut storage_client, 651054, 651054);
+    mock_program_output(&mut storage_client, 651053, vec![Felt252::ONE]);
+    let blob_serialized = bincode::serialize(&load_state_diff_file(651053).await).unwrap();
+    storage_client
+        .expect_get_data()
+        .with(eq(StorageKey::new(ArtifactKind::BlobData, 651053).to_string()))
+        .returning(move |_| Ok(Bytes::from(blob_serialized.clone())));
+
+    let config_init = init_config(
+        Some(format!("http://localhost:{}", server.port())),
+        None,
+        None,
+        None,
+        None,
+        Some(settlement_client),
+        Some(storage_client),
+    )
+    .await;
+    config_force_init(config_init).await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("651053, 651054"));
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    let error = StateUpdateJob.process_job(config().await.as_ref(), &mut job).await.unwrap_err();
+
+    assert!(error.downcast_ref::<FeeTooHighError>().is_some(), "high fees should hold the job back");
+    assert_eq!(job.metadata.get(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO).unwrap(), "651053");
+}
+
+/// Tests that a settlement tx unmined past the timeout is replaced with bumped fees and that the
+/// replacement is tracked in the metadata.
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_replaces_stuck_tx() {
+    let mut settlement_client = MockSettlementClient::new();
+    settlement_client
+        .expect_verify_tx_inclusion()
+        .with(eq("0xaaa"))
+        .returning(|_| Ok(SettlementVerificationStatus::Pending));
+    settlement_client.expect_wait_for_tx_finality().never();
+    settlement_client.expect_supports_tx_replacement().return_const(true);
+    settlement_client
+        .expect_resubmit_tx_with_bumped_fees()
+        .with(eq("0xaaa"))
+        .times(1)
+        .returning(|_| Ok(String::from("0xbbb")));
+
+    let config_init = init_config(None, None, None, None, None, Some(settlement_client), None).await;
+    config_force_init(config_init).await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("651053"));
+    metadata.insert(format!("{}0", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX), String::from("0xaaa"));
+    // sent long before the stuck timeout
+    metadata.insert(format!("{}0xaaa", JOB_METADATA_STATE_UPDATE_SENT_AT_PREFIX), String::from("0"));
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    let status = StateUpdateJob.verify_job(config().await.as_ref(), &mut job).await.unwrap();
+
+    assert_eq!(status, JobVerificationStatus::Pending);
+    assert_eq!(job.metadata.get(&format!("{}0xaaa", JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX)).unwrap(), "0xbbb");
+    assert!(job.metadata.contains_key(&format!("{}0xbbb", JOB_METADATA_STATE_UPDATE_SENT_AT_PREFIX)));
+}
+
+/// Tests that a stuck settlement tx is left pending when the settlement client can't replace it,
+/// e.g. a Safe transaction waiting for its owners.
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_keeps_stuck_tx_without_replacement() {
+    let mut settlement_client = MockSettlementClient::new();
+    settlement_client
+        .expect_verify_tx_inclusion()
+        .with(eq("0xaaa"))
+        .returning(|_| Ok(SettlementVerificationStatus::Pending));
+    settlement_client.expect_wait_for_tx_finality().never();
+    settlement_client.expect_supports_tx_replacement().return_const(false);
+    settlement_client.expect_resubmit_tx_with_bumped_fees().never();
+
+    let config_init = init_config(None, None, None, None, None, Some(settlement_client), None).await;
+    config_force_init(config_init).await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("651053"));
+    metadata.insert(format!("{}0", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX), String::from("0xaaa"));
+    // sent long before the stuck timeout
+    metadata.insert(format!("{}0xaaa", JOB_METADATA_STATE_UPDATE_SENT_AT_PREFIX), String::from("0"));
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    let status = StateUpdateJob.verify_job(config().await.as_ref(), &mut job).await.unwrap();
+
+    assert_eq!(status, JobVerificationStatus::Pending);
+    assert!(!job.metadata.contains_key(&format!("{}0xaaa", JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX)));
+}
+
+/// Tests that a settlement tx still pending once the settlement client stopped waiting for it
+/// keeps the job pending.
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_pending_after_finality_wait() {
+    let mut settlement_client = MockSettlementClient::new();
+    settlement_client
+        .expect_verify_tx_inclusion()
+        .with(eq("0xaaa"))
+        .times(2)
+        .returning(|_| Ok(SettlementVerificationStatus::Pending));
+    settlement_client.expect_wait_for_tx_finality().with(eq("0xaaa")).times(1).returning(|_| Ok(()));
+    settlement_client.expect_get_last_settled_block().never();
+
+    let config_init = init_config(None, None, None, None, None, Some(settlement_client), None).await;
+    config_force_init(config_init).await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("651053"));
+    metadata.insert(format!("{}0", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX), String::from("0xaaa"));
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    let status = StateUpdateJob.verify_job(config().await.as_ref(), &mut job).await.unwrap();
+
+    assert_eq!(status, JobVerificationStatus::Pending);
+}
+
+/// Tests that the verification follows the replacement of a settlement tx once it is mined.
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_follows_replacement_tx() {
+    let mut settlement_client = MockSettlementClient::new();
+    settlement_client
+        .expect_verify_tx_inclusion()
+        .with(eq("0xaaa"))
+        .returning(|_| Ok(SettlementVerificationStatus::Rejected(String::from("Could not find status of tx"))));
+    settlement_client
+        .expect_verify_tx_inclusion()
+        .with(eq("0xbbb"))
+        .returning(|_| Ok(SettlementVerificationStatus::Verified));
+    settlement_client.expect_get_last_settled_block().returning(|| Ok(651053_u64));
+
+    let config_init = init_config(None, None, None, None, None, Some(settlement_client), None).await;
+    config_force_init(config_init).await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("651053"));
+    metadata.insert(format!("{}0", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX), String::from("0xaaa"));
+    metadata.insert(format!("{}0xaaa", JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX), String::from("0xbbb"));
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    let status = StateUpdateJob.verify_job(config().await.as_ref(), &mut job).await.unwrap();
+
+    assert_eq!(status, JobVerificationStatus::Verified);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_process_job_calldata_da() {
+    let server = MockServer::start();
+    let mut settlement_client = MockSettlementClient::new();
+    let mut storage_client = MockDataStorage::new();
+    let block_no = 631861_u64;
+
+    // SNOS output of a block settled with DA in calldata
+    let mut snos_output: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(
+            CURRENT_PATH.join(format!("src/tests/jobs/state_update_job/test_data/651053/{}", SNOS_OUTPUT_FILE_NAME)),
+        )
+        .expect("Failed to read the snos output data json file"),
+    )
+    .unwrap();
+    snos_output["use_kzg_da"] = json!("0x0");
+    let snos_output_data = snos_output.to_string();
+    let initial_root = snos_initial_root(&snos_output_data);
+
+    settlement_client.expect_get_last_settled_block().returning(move || Ok(block_no - 1));
+    settlement_client.expect_get_state_root().times(1).returning(move || Ok(initial_root));
+    let program_output = mock_program_output(&mut storage_client, block_no, vec![Felt252::ONE, Felt252::TWO]);
+    settlement_client
+        .expect_update_state_calldata()
+        .times(1)
+        .withf(move |sent_program_output, onchain_data_hash, onchain_data_size| {
+            *sent_program_output == program_output && *onchain_data_hash != [0; 32] && *onchain_data_size > 0
+        })
+        .returning(|_, _, _| Ok(String::from("0x5d17fac98d9454030426606019364f6e68d915b91f6210ef1e2628cd6987442")));
+    settlement_client.expect_update_state_with_blobs().never();
+
+    storage_client
+        .expect_get_data()
+        .with(eq(StorageKey::new(ArtifactKind::SnosOutput, block_no).to_string()))
+        .returning(move |_| Ok(Bytes::from(snos_output_data.clone())));
+    storage_client.expect_put_data().returning(|_, _| Ok(()));
+
+    // Mocking the state update of the block on the Starknet RPC
+    let state_update = read_state_update_from_file("src/tests/jobs/da_job/test_data/state_update/631861.txt")
+        .expect("issue while reading");
+    let response = json!({ "id": 1,"jsonrpc":"2.0","result": serde_json::to_value(&state_update).unwrap() });
+    server.mock(|when, then| {
+        when.path("/").body_contains("starknet_getStateUpdate");
+        then.status(200).body(serde_json::to_vec(&response).unwrap());
+    });
+    get_nonce_attached(&server, "src/tests/jobs/da_job/test_data/nonces/631861.txt");
+
+    let config_init = init_config(
+        Some(format!("http://localhost:{}", server.port())),
+        None,
+        None,
+        None,
+        None,
+        Some(settlement_client),
+        Some(storage_client),
+    )
+    .await;
+    config_force_init(config_init).await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), block_no.to_string());
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    assert_eq!(StateUpdateJob.process_job(config().await.as_ref(), &mut job).await.unwrap(), block_no.to_string());
+}
+
+/// Tests that the verification finds the tx hashes recorded by the processing once the process
+/// attempts counter was incremented, as `jobs::process_job` does in between.
+#[rstest]
+#[tokio::test]
+async fn test_verify_job_after_process_job() {
+    let server = MockServer::start();
+    let mut settlement_client = MockSettlementClient::new();
+    let mut storage_client = MockDataStorage::new();
+    let block_no = 631861_u64;
+    let tx_hash = "0x5d17fac98d9454030426606019364f6e68d915b91f6210ef1e2628cd6987442";
+
+    let mut snos_output: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(
+            CURRENT_PATH.join(format!("src/tests/jobs/state_update_job/test_data/651053/{}", SNOS_OUTPUT_FILE_NAME)),
+        )
+        .expect("Failed to read the snos output data json file"),
+    )
+    .unwrap();
+    snos_output["use_kzg_da"] = json!("0x0");
+    let snos_output_data = snos_output.to_string();
+    let initial_root = snos_initial_root(&snos_output_data);
+
+    // the block is settled once the state update tx is sent
+    let last_settled_block = Arc::new(AtomicU64::new(block_no - 1));
+    let settled_block = last_settled_block.clone();
+    settlement_client.expect_get_last_settled_block().returning(move || Ok(settled_block.load(Ordering::SeqCst)));
+    settlement_client.expect_get_state_root().times(1).returning(move || Ok(initial_root));
+    mock_program_output(&mut storage_client, block_no, vec![Felt252::ONE, Felt252::TWO]);
+    settlement_client.expect_update_state_calldata().times(1).returning(move |_, _, _| {
+        last_settled_block.store(block_no, Ordering::SeqCst);
+        Ok(String::from(tx_hash))
+    });
+    settlement_client
+        .expect_verify_tx_inclusion()
+        .with(eq(tx_hash))
+        .times(1)
+        .returning(|_| Ok(SettlementVerificationStatus::Verified));
+
+    storage_client
+        .expect_get_data()
+        .with(eq(StorageKey::new(ArtifactKind::SnosOutput, block_no).to_string()))
+        .returning(move |_| Ok(Bytes::from(snos_output_data.clone())));
+    storage_client.expect_put_data().returning(|_, _| Ok(()));
+
+    let state_update = read_state_update_from_file("src/tests/jobs/da_job/test_data/state_update/631861.txt")
+        .expect("issue while reading");
+    let response = json!({ "id": 1,"jsonrpc":"2.0","result": serde_json::to_value(&state_update).unwrap() });
+    server.mock(|when, then| {
+        when.path("/").body_contains("starknet_getStateUpdate");
+        then.status(200).body(serde_json::to_vec(&response).unwrap());
+    });
+    get_nonce_attached(&server, "src/tests/jobs/da_job/test_data/nonces/631861.txt");
+
+    let config_init = init_config(
+        Some(format!("http://localhost:{}", server.port())),
+        None,
+        None,
+        None,
+        None,
+        Some(settlement_client),
+        Some(storage_client),
+    )
+    .await;
+    config_force_init(config_init).await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), block_no.to_string());
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    StateUpdateJob.process_job(config().await.as_ref(), &mut job).await.unwrap();
+    job.counters.process_attempts += 1;
+    let status = StateUpdateJob.verify_job(config().await.as_ref(), &mut job).await.unwrap();
+
+    assert_eq!(status, JobVerificationStatus::Verified);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_process_job_calldata_da_in_batch() {
+    let server = MockServer::start();
+    let mut settlement_client = MockSettlementClient::new();
+    let mut storage_client = MockDataStorage::new();
+    let block_numbers = [631861_u64, 631862_u64];
+
+    // SNOS outputs of consecutive blocks settled with DA in calldata
+    let mut snos_output: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(
+            CURRENT_PATH.join(format!("src/tests/jobs/state_update_job/test_data/651053/{}", SNOS_OUTPUT_FILE_NAME)),
+        )
+        .expect("Failed to read the snos output data json file"),
+    )
+    .unwrap();
+    snos_output["use_kzg_da"] = json!("0x0");
+    let first_snos_output_data = snos_output.to_string();
+    let initial_root = snos_initial_root(&first_snos_output_data);
+    snos_output["initial_root"] = snos_output["final_root"].clone();
+    let second_snos_output_data = snos_output.to_string();
+
+    let snos_outputs_data = [first_snos_output_data, second_snos_output_data];
+    let mut program_outputs = Vec::new();
+    for (block_no, snos_output_data) in block_numbers.into_iter().zip(snos_outputs_data) {
+        storage_client
+            .expect_get_data()
+            .with(eq(StorageKey::new(ArtifactKind::SnosOutput, block_no).to_string()))
+            .returning(move |_| Ok(Bytes::from(snos_output_data.clone())));
+        program_outputs.push(mock_program_output(&mut storage_client, block_no, vec![Felt252::from(block_no)]));
+    }
+    storage_client.expect_put_data().returning(|_, _| Ok(()));
+
+    settlement_client.expect_get_last_settled_block().returning(move || Ok(block_numbers[0] - 1));
+    // only the first block is checked against the core contract
+    settlement_client.expect_get_state_root().times(1).returning(move || Ok(initial_root));
+    settlement_client
+        .expect_settle_batch()
+        .times(1)
+        .withf(move |operations| {
+            operations.len() == 2
+                && operations.iter().zip(&program_outputs).all(|(operation, expected_program_output)| match operation {
+                    SettlementOperation::UpdateStateCalldata { program_output, onchain_data_size, .. } => {
+                        program_output == expected_program_output && *onchain_data_size > 0
+                    }
+                    SettlementOperation::RegisterMemoryPages(_) => false,
+                })
+        })
+        .returning(|_| Ok(String::from("0xbatch")));
+    settlement_client.expect_update_state_calldata().never();
+
+    // Mocking the state update of the blocks on the Starknet RPC
+    let state_update = read_state_update_from_file("src/tests/jobs/da_job/test_data/state_update/631861.txt")
+        .expect("issue while reading");
+    let response = json!({ "id": 1,"jsonrpc":"2.0","result": serde_json::to_value(&state_update).unwrap() });
+    server.mock(|when, then| {
+        when.path("/").body_contains("starknet_getStateUpdate");
+        then.status(200).body(serde_json::to_vec(&response).unwrap());
+    });
+    get_nonce_attached(&server, "src/tests/jobs/da_job/test_data/nonces/631861.txt");
+
+    let config_init = init_config(
+        Some(format!("http://localhost:{}", server.port())),
+        None,
+        None,
+        None,
+        None,
+        Some(settlement_client),
+        Some(storage_client),
+    )
+    .await;
+    config_force_init(config_init).await;
+    let _batch_state_updates = EnvVarGuard::set(ENV_SETTLEMENT_BATCH_STATE_UPDATES, "true");
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), String::from("631861,631862"));
+
+    let mut job =
+        StateUpdateJob.create_job(config().await.as_ref(), String::from("internal_id"), metadata).await.unwrap();
+    let external_id = StateUpdateJob.process_job(config().await.as_ref(), &mut job).await.unwrap();
+
+    assert_eq!(external_id, "631862");
+    // both blocks are settled by the batch transaction
+    assert_eq!(job.metadata.get(&format!("{}0", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX)).unwrap(), "0xbatch,0xbatch");
+}
+
+#[rstest]
+fn test_onchain_data_hash_and_size() {
+    let onchain_data = vec![FieldElement::ONE, FieldElement::TWO, FieldElement::THREE];
+    let (onchain_data_hash, onchain_data_size) = onchain_data_hash_and_size(&onchain_data);
+
+    assert_eq!(onchain_data_size, 3);
+    assert_eq!(onchain_data_hash, compute_hash_on_elements(&onchain_data).to_bytes_be());
+    assert_ne!(onchain_data_hash, onchain_data_hash_and_size(&onchain_data[..2]).0);
+}
+
+#[rstest]
+#[case(651053)]
+#[case(651054)]
+#[case(651055)]
+#[case(651056)]
+#[tokio::test]
+async fn test_build_kzg_proof(#[case] block_no: u64) {
+    let blob_data = load_state_diff_file(block_no).await;
+    let mut program_output = vec![[0; 32]; X_0_POINT_INDEX + 1];
+    program_output[X_0_POINT_INDEX] = read_test_data_hex(block_no, X_0_FILE_NAME).try_into().unwrap();
+
+    let kzg_proof = build_kzg_proof(&blob_data, x_0_point(&program_output).unwrap()).unwrap();
+
+    assert_eq!(kzg_proof.to_bytes().into_inner().to_vec(), read_test_data_hex(block_no, "kzg_proof.txt"));
+}
+
 // ==================== Utility functions ===========================
 
+fn read_test_data_hex(block_no: u64, file_name: &str) -> Vec<u8> {
+    let file_path = format!("src/tests/jobs/state_update_job/test_data/{}/{}", block_no, file_name);
+    let file_data = fs::read_to_string(file_path).expect("Unable to read test data file").replace("0x", "");
+    hex_string_to_u8_vec(&file_data).unwrap()
+}
+
+/// Mocks the program output of the block, returns it as passed to the settlement client
+fn mock_program_output(
+    storage_client: &mut MockDataStorage,
+    block_no: u64,
+    program_output: Vec<Felt252>,
+) -> Vec<[u8; 32]> {
+    let program_output_data = serde_json::to_vec(&program_output).unwrap();
+    storage_client
+        .expect_get_data()
+        .with(eq(StorageKey::new(ArtifactKind::ProgramOutput, block_no).to_string()))
+        .returning(move |_| Ok(Bytes::from(program_output_data.clone())));
+    program_output.iter().map(|felt| felt.to_bytes_be()).collect()
+}
+
+/// Mocks the SNOS output of the block with the one of the test data of `test_data_block`
+fn mock_snos_output(storage_client: &mut MockDataStorage, block_no: u64, test_data_block: u64) {
+    let snos_output_data = fs::read_to_string(CURRENT_PATH.join(format!(
+        "src/tests/jobs/state_update_job/test_data/{}/{}",
+        test_data_block, SNOS_OUTPUT_FILE_NAME
+    )))
+    .expect("Failed to read the snos output data json file");
+    storage_client
+        .expect_get_data()
+        .with(eq(StorageKey::new(ArtifactKind::SnosOutput, block_no).to_string()))
+        .returning(move |_| Ok(Bytes::from(snos_output_data.clone())));
+}
+
+fn snos_initial_root(snos_output_data: &str) -> [u8; 32] {
+    let snos_output: serde_json::Value = serde_json::from_str(snos_output_data).unwrap();
+    Felt252::from_hex(snos_output["initial_root"].as_str().unwrap()).unwrap().to_bytes_be()
+}
+
 async fn load_state_diff_file(block_no: u64) -> Vec<Vec<u8>> {
     let mut state_diff_vec: Vec<Vec<u8>> = Vec::new();
     let file_path = format!("src/tests/jobs/state_update_job/test_data/{}/{}", block_no, BLOB_DATA_FILE_NAME);
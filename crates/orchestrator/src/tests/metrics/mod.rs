@@ -0,0 +1,55 @@
+use prometheus::core::Collector;
+use prometheus::{IntCounter, Registry};
+use rstest::rstest;
+
+use crate::database::types::{JobCount, JobStats};
+use crate::deployment::DeploymentDescriptor;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::metrics::{add_deployment_labels, record_job_stats, JOBS, OLDEST_PENDING_JOB_AGE};
+
+#[rstest]
+fn test_add_deployment_labels() {
+    let registry = Registry::new();
+    let counter = IntCounter::new("test_counter", "test counter").unwrap();
+    registry.register(Box::new(counter.clone())).unwrap();
+    counter.inc();
+
+    let deployment = DeploymentDescriptor {
+        chain_id: "MADARA_DEVNET".to_string(),
+        environment: "staging".to_string(),
+        version: "0.1.0".to_string(),
+        git_sha: "abcdef".to_string(),
+    };
+    let mut metric_families = registry.gather();
+    add_deployment_labels(&mut metric_families, &deployment);
+
+    let labels: Vec<(String, String)> = metric_families[0].get_metric()[0]
+        .get_label()
+        .iter()
+        .map(|label| (label.get_name().to_string(), label.get_value().to_string()))
+        .collect();
+    assert_eq!(
+        labels,
+        vec![
+            ("chain_id".to_string(), "MADARA_DEVNET".to_string()),
+            ("environment".to_string(), "staging".to_string()),
+            ("version".to_string(), "0.1.0".to_string()),
+            ("git_sha".to_string(), "abcdef".to_string()),
+        ]
+    );
+}
+
+#[rstest]
+fn test_record_job_stats() {
+    let snos_created = JobCount { job_type: JobType::SnosRun, status: JobStatus::Created, count: 2 };
+    let proving_blocked = JobCount { job_type: JobType::ProofCreation, status: JobStatus::Blocked, count: 1 };
+    record_job_stats(&JobStats { counts: vec![snos_created, proving_blocked], oldest_pending_job_age: Some(30) });
+    assert_eq!(JOBS.with_label_values(&["SnosRun", "Created"]).get(), 2.0);
+    assert_eq!(JOBS.with_label_values(&["ProofCreation", "Blocked"]).get(), 1.0);
+    assert_eq!(OLDEST_PENDING_JOB_AGE.get(), 30.0);
+
+    // the jobs which aren't there anymore are dropped
+    record_job_stats(&JobStats::default());
+    assert!(JOBS.collect()[0].get_metric().is_empty());
+    assert_eq!(OLDEST_PENDING_JOB_AGE.get(), 0.0);
+}
@@ -1,8 +1,12 @@
+pub mod alerts;
+pub mod aws_http_client;
 pub mod config;
 pub mod database;
 
 pub mod jobs;
 
+pub mod metrics;
+
 pub mod server;
 
 pub mod queue;
@@ -5,3 +5,385 @@ use rstest::*;
 async fn test_queue() {
     // TODO: write test case
 }
+
+#[rstest]
+#[tokio::test]
+async fn test_in_process_queue_delivers_messages() {
+    use std::time::Duration;
+
+    use crate::queue::inprocess::InProcessQueue;
+    use crate::queue::job_queue::{JOB_VERIFICATION_QUEUE, SNOS_JOB_PROCESSING_QUEUE};
+    use crate::queue::QueueProvider;
+
+    let queue = InProcessQueue::new();
+    let mut processing_receiver = queue.take_receiver(SNOS_JOB_PROCESSING_QUEUE).unwrap();
+    let mut verification_receiver = queue.take_receiver(JOB_VERIFICATION_QUEUE).unwrap();
+    // receivers can only be taken once
+    assert!(queue.take_receiver(SNOS_JOB_PROCESSING_QUEUE).is_none());
+
+    queue
+        .send_message_to_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), "processing".to_string(), None, None)
+        .await
+        .unwrap();
+    queue
+        .send_message_to_queue(
+            JOB_VERIFICATION_QUEUE.to_string(),
+            "verification".to_string(),
+            Some(Duration::from_millis(100)),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(processing_receiver.recv().await.unwrap(), "processing");
+    assert!(verification_receiver.try_recv().is_err());
+    assert_eq!(verification_receiver.recv().await.unwrap(), "verification");
+
+    assert!(queue.send_message_to_queue("unknown_queue".to_string(), String::new(), None, None).await.is_err());
+}
+
+/// Tests that the in-process consumer handles the next jobs while a job hangs.
+#[rstest]
+#[tokio::test]
+async fn test_consume_jobs_in_process_does_not_wait_for_hung_job() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use uuid::Uuid;
+
+    use crate::queue::inprocess::InProcessQueue;
+    use crate::queue::job_queue::{consume_jobs_in_process, JobQueueMessage, SNOS_JOB_PROCESSING_QUEUE};
+    use crate::queue::QueueProvider;
+
+    static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+    let queue = InProcessQueue::new();
+    let receiver = queue.take_receiver(SNOS_JOB_PROCESSING_QUEUE).unwrap();
+    let hung_job_id = Uuid::new_v4();
+    let handler = move |job_id| async move {
+        if job_id == hung_job_id {
+            std::future::pending::<()>().await;
+        }
+        HANDLED.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    };
+    tokio::spawn(consume_jobs_in_process(SNOS_JOB_PROCESSING_QUEUE, receiver, 2, handler));
+
+    for id in [hung_job_id, Uuid::new_v4()] {
+        let payload = serde_json::to_string(&JobQueueMessage { id, trace_id: None, not_before: None }).unwrap();
+        queue.send_message_to_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), payload, None, None).await.unwrap();
+    }
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while HANDLED.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the job after the hung one should be handled");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_in_memory_queue_delivers_messages() {
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+    use omniqueue::QueueError;
+
+    use crate::queue::job_queue::{JOB_VERIFICATION_QUEUE, SNOS_JOB_PROCESSING_QUEUE};
+    use crate::queue::memory::InMemoryQueue;
+    use crate::queue::QueueProvider;
+
+    let queue = InMemoryQueue::new().await.unwrap();
+
+    queue
+        .send_message_to_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), "\"processing\"".to_string(), None, None)
+        .await
+        .unwrap();
+    queue
+        .send_message_to_queue(
+            JOB_VERIFICATION_QUEUE.to_string(),
+            "\"verification\"".to_string(),
+            Some(Duration::from_millis(100)),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let delivery = queue.consume_message_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string()).await.unwrap();
+    assert_eq!(delivery.payload_serde_json::<String>().unwrap().unwrap(), "processing");
+    delivery.ack().await.map_err(|(e, _)| e).unwrap();
+    assert_matches!(
+        queue.consume_message_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err(),
+        QueueError::NoData
+    );
+
+    // delayed messages are only delivered once the delay has passed
+    assert_matches!(
+        queue.consume_message_from_queue(JOB_VERIFICATION_QUEUE.to_string()).await.unwrap_err(),
+        QueueError::NoData
+    );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let delivery = queue.consume_message_from_queue(JOB_VERIFICATION_QUEUE.to_string()).await.unwrap();
+    assert_eq!(delivery.payload_serde_json::<String>().unwrap().unwrap(), "verification");
+
+    assert!(queue.send_message_to_queue("unknown_queue".to_string(), String::new(), None, None).await.is_err());
+}
+
+#[rstest]
+#[case(0, 5, 10)]
+#[case(1, 10, 20)]
+#[case(3, 40, 60)]
+#[case(100, 30, 60)]
+fn test_job_backoff_delay(#[case] attempt: u64, #[case] min_seconds: u64, #[case] max_seconds: u64) {
+    use std::time::Duration;
+
+    use crate::queue::job_queue::JobBackoff;
+
+    let backoff = JobBackoff { initial_delay_seconds: 10, max_delay_seconds: 60 };
+    for _ in 0..100 {
+        let delay = backoff.delay(attempt);
+        assert!(delay >= Duration::from_secs(min_seconds) && delay <= Duration::from_secs(max_seconds));
+    }
+}
+
+#[rstest]
+fn test_job_processing_queues_are_distinct() {
+    use std::collections::HashSet;
+
+    use crate::jobs::types::{JobPriority, JobType};
+    use crate::queue::job_queue::{job_processing_queues, JOB_HANDLE_FAILURE_QUEUE, JOB_VERIFICATION_QUEUE};
+
+    let queues: HashSet<&str> = job_processing_queues().collect();
+    assert_eq!(queues.len(), JobType::ALL.len() * JobPriority::ALL.len());
+    assert!(!queues.contains(JOB_VERIFICATION_QUEUE) && !queues.contains(JOB_HANDLE_FAILURE_QUEUE));
+}
+
+#[rstest]
+fn test_job_message_group() {
+    use std::collections::HashMap;
+
+    use uuid::Uuid;
+
+    use crate::jobs::types::{ExternalId, JobCounters, JobItem, JobPriority, JobStatus, JobType};
+    use crate::queue::job_queue::job_message_group;
+
+    let mut job = JobItem {
+        id: Uuid::new_v4(),
+        internal_id: "1".to_string(),
+        job_type: JobType::SnosRun,
+        status: JobStatus::Created,
+        external_id: ExternalId::Number(0),
+        metadata: HashMap::new(),
+        version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
+    };
+    let created = job_message_group(&job);
+    assert_eq!(created.group_id, "SnosRun_1");
+
+    // the messages of the same job share the group, the retries are not deduplicated
+    job.status = JobStatus::VerificationFailed;
+    job.counters.process_attempts = 1;
+    let retry = job_message_group(&job);
+    assert_eq!(retry.group_id, created.group_id);
+    assert_ne!(retry.deduplication_id, created.deduplication_id);
+    assert_eq!(job_message_group(&job), retry);
+
+    job.id = Uuid::new_v4();
+    assert_ne!(job_message_group(&job).deduplication_id, retry.deduplication_id);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_consume_job_from_queue_extends_visibility_while_processing() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+    use omniqueue::QueueError;
+    use uuid::Uuid;
+
+    use crate::config::config;
+    use crate::queue::job_queue::{consume_job_from_queue, JobQueueMessage, SNOS_JOB_PROCESSING_QUEUE};
+    use crate::tests::config::TestConfigBuilder;
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+
+    let id = Uuid::new_v4();
+    let payload = serde_json::to_string(&JobQueueMessage { id, trace_id: None, not_before: None }).unwrap();
+    config.queue().send_message_to_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), payload, None, None).await.unwrap();
+
+    // the handler outlives the visibility timeout several times
+    let handled = Arc::new(AtomicBool::new(false));
+    let handled_clone = Arc::clone(&handled);
+    let handler = |job_id| async move {
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        assert_eq!(job_id, id);
+        handled_clone.store(true, Ordering::SeqCst);
+        Ok(())
+    };
+    consume_job_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), Some(Duration::from_millis(100)), handler)
+        .await
+        .unwrap();
+
+    assert!(handled.load(Ordering::SeqCst));
+    // the message was acknowledged once handled
+    assert_matches!(
+        config.queue().consume_message_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err(),
+        QueueError::NoData
+    );
+}
+
+/// Tests that the message of a job not due yet, as sent to a FIFO queue without a delay, is held
+/// back invisible rather than handled, and that a due one is handled.
+#[rstest]
+#[tokio::test]
+async fn test_consume_job_from_queue_holds_back_message_not_due() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use assert_matches::assert_matches;
+    use omniqueue::QueueError;
+    use uuid::Uuid;
+
+    use crate::config::config;
+    use crate::database::types::now_secs;
+    use crate::queue::job_queue::{consume_job_from_queue, JobQueueMessage, SNOS_JOB_PROCESSING_QUEUE};
+    use crate::tests::config::TestConfigBuilder;
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+
+    let handled = Arc::new(AtomicUsize::new(0));
+    let handler = |_| {
+        let handled = Arc::clone(&handled);
+        async move {
+            handled.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    };
+
+    let message = JobQueueMessage { id: Uuid::new_v4(), trace_id: None, not_before: Some(now_secs() + 60) };
+    let payload = serde_json::to_string(&message).unwrap();
+    config.queue().send_message_to_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), payload, None, None).await.unwrap();
+    consume_job_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), None, handler).await.unwrap();
+    assert_eq!(handled.load(Ordering::SeqCst), 0);
+    // the message is kept invisible until it's due
+    assert_matches!(
+        config.queue().consume_message_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err(),
+        QueueError::NoData
+    );
+
+    let message = JobQueueMessage { id: Uuid::new_v4(), trace_id: None, not_before: Some(now_secs()) };
+    let payload = serde_json::to_string(&message).unwrap();
+    config.queue().send_message_to_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), payload, None, None).await.unwrap();
+    consume_job_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), None, handler).await.unwrap();
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_consume_jobs_from_queue_dispatches_in_parallel() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::sync::Semaphore;
+    use uuid::Uuid;
+
+    use crate::config::config;
+    use crate::queue::job_queue::{consume_jobs_from_queue, JobQueueMessage, DA_JOB_PROCESSING_QUEUE};
+    use crate::tests::config::TestConfigBuilder;
+
+    static HANDLED: AtomicUsize = AtomicUsize::new(0);
+    static RUNNING: AtomicUsize = AtomicUsize::new(0);
+    static MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+
+    for _ in 0..5 {
+        let message = JobQueueMessage { id: Uuid::new_v4(), trace_id: None, not_before: None };
+        let payload = serde_json::to_string(&message).unwrap();
+        config.queue().send_message_to_queue(DA_JOB_PROCESSING_QUEUE.to_string(), payload, None, None).await.unwrap();
+    }
+
+    let handler = |_| async {
+        let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+        MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+        HANDLED.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    };
+    let semaphore = Arc::new(Semaphore::new(2));
+    let consumed = consume_jobs_from_queue(DA_JOB_PROCESSING_QUEUE, 4, &semaphore, None, handler).await.unwrap();
+    assert_eq!(consumed, 4);
+
+    // waiting for the dispatched jobs to be handled
+    drop(semaphore.acquire_many(2).await.unwrap());
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 4);
+    assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 2);
+
+    // the rest of the messages is left for the next batch
+    let consumed = consume_jobs_from_queue(DA_JOB_PROCESSING_QUEUE, 4, &semaphore, None, handler).await.unwrap();
+    assert_eq!(consumed, 1);
+    drop(semaphore.acquire_many(2).await.unwrap());
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 5);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_consume_job_from_queue_quarantines_poison_message() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use assert_matches::assert_matches;
+    use omniqueue::QueueError;
+
+    use crate::config::config;
+    use crate::database::types::AuditEventKind;
+    use crate::database::MockDatabase;
+    use crate::queue::job_queue::{
+        consume_job_from_queue, QUARANTINED_MESSAGES_STORAGE_PREFIX, SNOS_JOB_PROCESSING_QUEUE,
+    };
+    use crate::tests::config::TestConfigBuilder;
+
+    let mut db = MockDatabase::new();
+    db.expect_record_audit_event()
+        .withf(|event| {
+            event.kind == AuditEventKind::PoisonMessage && event.details.contains(QUARANTINED_MESSAGES_STORAGE_PREFIX)
+        })
+        .times(1)
+        .returning(|_| Ok(()));
+    TestConfigBuilder::new().mock_db_client(Box::new(db)).build().await;
+    let config = config().await;
+
+    config
+        .queue()
+        .send_message_to_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), "not a job".to_string(), None, None)
+        .await
+        .unwrap();
+
+    let handled = Arc::new(AtomicBool::new(false));
+    let handled_clone = Arc::clone(&handled);
+    let handler = |_| async move {
+        handled_clone.store(true, Ordering::SeqCst);
+        Ok(())
+    };
+    consume_job_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string(), None, handler).await.unwrap();
+
+    assert!(!handled.load(Ordering::SeqCst));
+    // the message was acknowledged once quarantined, it isn't delivered again
+    assert_matches!(
+        config.queue().consume_message_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string()).await.unwrap_err(),
+        QueueError::NoData
+    );
+}
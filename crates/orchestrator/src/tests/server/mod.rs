@@ -7,9 +7,14 @@ use hyper::{Body, Request};
 use rstest::*;
 use utils::env_utils::get_env_var_or_default;
 
-use super::common::init_config;
+use super::common::{default_job_item, init_config};
+use crate::config::config;
+use crate::controllers::admin::{admin_router, JobResponse, ENV_ADMIN_API_TOKEN};
+use crate::controllers::blocks::block_status;
+use crate::jobs::types::{ExternalId, JobItem, JobStatus, JobType};
 use crate::queue::init_consumers;
 use crate::routes::app_router;
+use crate::tests::config::TestConfigBuilder;
 
 #[fixture]
 pub async fn setup_server() -> SocketAddr {
@@ -56,3 +61,102 @@ async fn test_health_endpoint(#[future] setup_server: SocketAddr) {
 async fn test_init_consumer() {
     assert!(init_consumers().await.is_ok());
 }
+
+/// Tests that the admin API refuses the requests without its token, and cancels a job.
+#[rstest]
+#[tokio::test]
+async fn test_admin_api(default_job_item: JobItem) {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    config.database().create_job(default_job_item.clone()).await.unwrap();
+
+    std::env::set_var(ENV_ADMIN_API_TOKEN, "admin-token");
+    let app = admin_router();
+    std::env::remove_var(ENV_ADMIN_API_TOKEN);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to get listener");
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("Failed to start axum server");
+    });
+
+    let client = hyper::Client::new();
+    let request = |method: &str, path: &str, token: Option<&str>| {
+        let mut builder = Request::builder().method(method).uri(format!("http://{}{}", addr, path));
+        if let Some(token) = token {
+            builder = builder.header("Authorization", format!("Bearer {}", token));
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let job_path = format!("/jobs/{}", default_job_item.id);
+
+    let response = client.request(request("GET", &job_path, None)).await.unwrap();
+    assert_eq!(response.status(), hyper::StatusCode::UNAUTHORIZED);
+    let response = client.request(request("GET", &job_path, Some("wrong-token"))).await.unwrap();
+    assert_eq!(response.status(), hyper::StatusCode::UNAUTHORIZED);
+
+    let response = client.request(request("GET", &job_path, Some("admin-token"))).await.unwrap();
+    assert_eq!(response.status(), hyper::StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let job: JobResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(job, JobResponse::from(default_job_item.clone()));
+
+    // the job isn't failed, it can't be retried
+    let response = client.request(request("POST", &format!("{}/retry", job_path), Some("admin-token"))).await.unwrap();
+    assert_eq!(response.status(), hyper::StatusCode::BAD_REQUEST);
+
+    let response = client.request(request("POST", &format!("{}/cancel", job_path), Some("admin-token"))).await.unwrap();
+    assert_eq!(response.status(), hyper::StatusCode::OK);
+    let job_in_db = config.database().get_job_by_id(default_job_item.id).await.unwrap().unwrap();
+    assert_eq!(job_in_db.status, JobStatus::Cancelled);
+
+    let response = client.request(request("POST", "/workers/unknown/trigger", Some("admin-token"))).await.unwrap();
+    assert_eq!(response.status(), hyper::StatusCode::NOT_FOUND);
+}
+
+/// Tests that the status of a block reports the latest job of each stage covering it, and the
+/// stages without a job yet.
+#[rstest]
+#[tokio::test]
+async fn test_block_status(default_job_item: JobItem) {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let job = |job_type: JobType, internal_id: &str, status: JobStatus| {
+        let mut job = default_job_item.clone();
+        job.id = uuid::Uuid::new_v4();
+        job.job_type = job_type;
+        job.internal_id = internal_id.to_string();
+        job.status = status;
+        job
+    };
+    let snos_job = job(JobType::SnosRun, "4-6", JobStatus::Completed);
+    let mut proving_job = job(JobType::ProofCreation, "5", JobStatus::PendingVerification);
+    proving_job.external_id = ExternalId::String("sharp-task".into());
+    let cancelled_state_update_job = job(JobType::StateTransition, "3-5", JobStatus::Cancelled);
+    let mut state_update_job = job(JobType::StateTransition, "5-6", JobStatus::VerificationFailed);
+    state_update_job.metadata.insert("error".to_string(), "Transaction reverted".to_string());
+    for job in [&snos_job, &proving_job, &cancelled_state_update_job, &state_update_job] {
+        config.database().create_job(job.clone()).await.unwrap();
+    }
+
+    let status = block_status(5).await.unwrap();
+
+    assert_eq!(status.block_number, 5);
+    assert!(!status.settled);
+    let stages: Vec<(JobType, Option<(uuid::Uuid, JobStatus)>)> = status
+        .stages
+        .iter()
+        .map(|stage| (stage.job_type.clone(), stage.job.as_ref().map(|job| (job.id, job.status.clone()))))
+        .collect();
+    // the stages are listed in the order of the job types
+    assert_eq!(
+        stages,
+        vec![
+            (JobType::SnosRun, Some((snos_job.id, JobStatus::Completed))),
+            (JobType::DataSubmission, None),
+            (JobType::ProofCreation, Some((proving_job.id, JobStatus::PendingVerification))),
+            (JobType::StateTransition, Some((state_update_job.id, JobStatus::VerificationFailed))),
+        ]
+    );
+    assert_eq!(status.stages[2].job.as_ref().unwrap().external_id, ExternalId::String("sharp-task".into()));
+    assert_eq!(status.stages[3].job.as_ref().unwrap().error.as_deref(), Some("Transaction reverted"));
+}
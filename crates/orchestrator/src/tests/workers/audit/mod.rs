@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use mockall::predicate::eq;
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::config::config_force_init;
+use crate::database::MockDatabase;
+use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY;
+use crate::jobs::job_handler_factory::mock_factory;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::jobs::{Job, MockJob};
+use crate::queue::MockQueueProvider;
+use crate::queue::job_queue::AUDIT_JOB_PROCESSING_QUEUE;
+use crate::tests::common::init_config;
+use crate::tests::workers::utils::get_job_item_mock_by_id;
+use crate::workers::Worker;
+use crate::workers::audit::AuditWorker;
+
+#[rstest]
+#[tokio::test]
+async fn test_audit_worker() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut queue = MockQueueProvider::new();
+    let mut job_handler = MockJob::new();
+
+    let mut state_update_job = get_job_item_mock_by_id("3".to_string(), Uuid::new_v4());
+    state_update_job.job_type = JobType::StateTransition;
+    state_update_job.status = JobStatus::Completed;
+    state_update_job.metadata =
+        HashMap::from([(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY.to_string(), "3,4".to_string())]);
+    let state_update_job_id = state_update_job.id;
+    db.expect_get_jobs_without_successor()
+        .with(eq(JobType::StateTransition), eq(JobStatus::Completed), eq(JobType::DataAudit))
+        .times(1)
+        .returning(move |_, _, _| Ok(vec![state_update_job.clone()]));
+    db.expect_get_job_by_internal_id_and_type()
+        .with(eq("3"), eq(JobType::DataAudit))
+        .times(1)
+        .returning(|_, _| Ok(None));
+
+    let mut audit_job = get_job_item_mock_by_id("3".to_string(), Uuid::new_v4());
+    audit_job.job_type = JobType::DataAudit;
+    job_handler
+        .expect_create_job()
+        .withf(|_, internal_id, metadata| {
+            internal_id == "3"
+                && metadata.get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY) == Some(&"3,4".to_string())
+        })
+        .times(1)
+        .returning(move |_, _, _| Ok(audit_job.clone()));
+    db.expect_create_job()
+        .withf(move |job| job.parent_ids == vec![state_update_job_id] && job.status == JobStatus::Created)
+        .times(1)
+        .returning(|job| Ok(job));
+
+    let y: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::DataAudit)).returning(move |_| Arc::clone(&y));
+
+    queue
+        .expect_send_message_to_queue()
+        .times(1)
+        .returning(|_, _, _, _| Ok(()))
+        .withf(|queue, _payload, _delay, _group| queue == AUDIT_JOB_PROCESSING_QUEUE);
+
+    let config = init_config(None, Some(db), Some(queue), None, None, None, None).await;
+    config_force_init(config).await;
+
+    AuditWorker {}.run_worker().await?;
+
+    Ok(())
+}
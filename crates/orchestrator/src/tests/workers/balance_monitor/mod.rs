@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use da_client_interface::MockDaClient;
+use rstest::rstest;
+use settlement_client_interface::MockSettlementClient;
+
+use crate::alerts::{Alert, AlertKind, AlertSink};
+use crate::config::config_force_init;
+use crate::tests::common::{init_config, EnvVarGuard};
+use crate::workers::balance_monitor::{
+    BalanceMonitorWorker, ENV_DA_BALANCE_ALERT_THRESHOLD, ENV_SETTLEMENT_BALANCE_ALERT_THRESHOLD, OPERATOR_BALANCE,
+};
+use crate::workers::Worker;
+
+/// Sink recording the alerts sent to it
+struct RecordingAlertSink {
+    alerts: Arc<Mutex<Vec<(AlertKind, String)>>>,
+}
+
+#[async_trait]
+impl AlertSink for RecordingAlertSink {
+    async fn send(&self, alert: &Alert<'_>) -> color_eyre::Result<()> {
+        self.alerts.lock().unwrap().push((alert.kind, alert.message.to_string()));
+        Ok(())
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_balance_monitor_worker() -> Result<(), Box<dyn Error>> {
+    let mut settlement_client = MockSettlementClient::new();
+    let mut da_client = MockDaClient::new();
+
+    // below the threshold, still below, funded back above it, below again
+    let settlement_balances = [5, 5, 12, 5];
+    let run = AtomicUsize::new(0);
+    settlement_client
+        .expect_get_operator_balance()
+        .times(settlement_balances.len())
+        .returning(move || Ok(settlement_balances[run.fetch_add(1, Ordering::SeqCst)]));
+    da_client.expect_get_operator_balance().times(settlement_balances.len()).returning(|| Ok(Some(7)));
+
+    let config = init_config(None, None, None, Some(da_client), None, Some(settlement_client), None).await;
+    config_force_init(config).await;
+
+    let _settlement_threshold = EnvVarGuard::set(ENV_SETTLEMENT_BALANCE_ALERT_THRESHOLD, "10");
+    let _da_threshold = EnvVarGuard::set(ENV_DA_BALANCE_ALERT_THRESHOLD, "7");
+
+    let alerts = Arc::new(Mutex::new(Vec::new()));
+    let sinks: Vec<Box<dyn AlertSink>> = vec![Box::new(RecordingAlertSink { alerts: alerts.clone() })];
+    let worker = BalanceMonitorWorker::with_alert_sinks(Box::leak(sinks.into_boxed_slice()));
+
+    worker.run_worker().await?;
+    worker.run_worker().await?;
+    assert_eq!(OPERATOR_BALANCE.with_label_values(&["settlement"]).get(), 5.0);
+    assert_eq!(OPERATOR_BALANCE.with_label_values(&["da"]).get(), 7.0);
+    // the alert is raised once while the balance stays low, the DA balance at its threshold isn't low
+    assert_eq!(
+        *alerts.lock().unwrap(),
+        vec![(
+            AlertKind::LowBalance,
+            "Balance of the settlement operator account is 5, below the threshold of 10".to_string()
+        )]
+    );
+
+    // funded then low again, the alert is raised again
+    worker.run_worker().await?;
+    assert_eq!(alerts.lock().unwrap().len(), 1);
+    worker.run_worker().await?;
+    assert_eq!(alerts.lock().unwrap().len(), 2);
+
+    Ok(())
+}
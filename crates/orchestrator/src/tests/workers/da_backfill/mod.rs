@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use mockall::predicate::eq;
+use rstest::rstest;
+use serde_json::json;
+use settlement_client_interface::MockSettlementClient;
+use uuid::Uuid;
+
+use crate::config::config_force_init;
+use crate::database::MockDatabase;
+use crate::jobs::job_handler_factory::mock_factory;
+use crate::jobs::types::JobType;
+use crate::jobs::{Job, MockJob};
+use crate::queue::job_queue::DA_JOB_PROCESSING_QUEUE;
+use crate::queue::MockQueueProvider;
+use crate::tests::common::init_config;
+use crate::tests::workers::utils::get_job_item_mock_by_id;
+use crate::workers::da_backfill::{DaBackfillWorker, DA_BACKFILL_WORKER};
+use crate::workers::state::LAST_PROCESSED_BLOCK_KEY;
+use crate::workers::Worker;
+
+#[rstest]
+#[tokio::test]
+async fn test_da_backfill_worker() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut queue = MockQueueProvider::new();
+    let mut settlement_client = MockSettlementClient::new();
+    let mut job_handler = MockJob::new();
+
+    // blocks 2 to 5 are settled, blocks 3 and 4 were submitted by the same DA job and block 2
+    // only has a SNOS job
+    settlement_client.expect_get_last_settled_block().times(1).returning(|| Ok(5));
+    db.expect_get_worker_state()
+        .with(eq(DA_BACKFILL_WORKER), eq(LAST_PROCESSED_BLOCK_KEY))
+        .times(1)
+        .returning(|_, _| Ok(Some(json!(1))));
+    db.expect_get_jobs_by_block()
+        .with(eq(2))
+        .times(1)
+        .returning(|_| Ok(vec![get_job_item_mock_by_id("2".to_string(), Uuid::new_v4())]));
+    db.expect_get_jobs_by_block().withf(|block_number| [3, 4].contains(block_number)).times(2).returning(|_| {
+        let mut da_job = get_job_item_mock_by_id("3-4".to_string(), Uuid::new_v4());
+        da_job.job_type = JobType::DataSubmission;
+        Ok(vec![da_job])
+    });
+    db.expect_get_jobs_by_block().with(eq(5)).times(1).returning(|_| Ok(vec![]));
+
+    for block_number in [2, 5] {
+        let mut job_item = get_job_item_mock_by_id(block_number.to_string(), Uuid::new_v4());
+        job_item.job_type = JobType::DataSubmission;
+        let job_item_cloned = job_item.clone();
+        job_handler
+            .expect_create_job()
+            .withf(move |_, internal_id, _| internal_id == &block_number.to_string())
+            .times(1)
+            .returning(move |_, _, _| Ok(job_item.clone()));
+        db.expect_create_job()
+            .withf(move |item| item.internal_id == block_number.to_string())
+            .times(1)
+            .returning(move |_| Ok(job_item_cloned.clone()));
+    }
+    for block_number in 2..6 {
+        db.expect_set_worker_state()
+            .with(eq(DA_BACKFILL_WORKER), eq(LAST_PROCESSED_BLOCK_KEY), eq(json!(block_number)))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+    }
+
+    let y: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(2).with(eq(JobType::DataSubmission)).returning(move |_| Arc::clone(&y));
+
+    queue
+        .expect_send_message_to_queue()
+        .times(2)
+        .returning(|_, _, _, _| Ok(()))
+        .withf(|queue, _payload, _delay, _group| queue == DA_JOB_PROCESSING_QUEUE);
+
+    let config = init_config(None, Some(db), Some(queue), None, None, Some(settlement_client), None).await;
+    config_force_init(config).await;
+
+    DaBackfillWorker {}.run_worker().await?;
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use rstest::rstest;
+
+use crate::workers::data_submission_worker::{DaBatcher, DaBlock};
+
+/// Blob of 4096 field elements
+const BLOB_SIZE: u64 = 131072;
+
+/// Batches of the blocks `(number, encoded length, empty)` pushed in order
+fn da_batches(
+    blocks: &[(u64, usize, bool)],
+    max_blocks: u64,
+    fold_empty_blocks: bool,
+    max_blobs: u64,
+) -> Vec<Vec<u64>> {
+    let mut batcher = DaBatcher::new(max_blocks, fold_empty_blocks, BLOB_SIZE, max_blobs);
+    blocks
+        .iter()
+        .flat_map(|&(number, encoded_len, empty)| batcher.push(DaBlock { number, encoded_len, empty }))
+        .collect()
+}
+
+fn small_blocks(start: u64, end: u64) -> Vec<(u64, usize, bool)> {
+    (start..=end).map(|number| (number, 10, false)).collect()
+}
+
+#[rstest]
+#[case(1, 5, 1, vec![vec![1], vec![2], vec![3], vec![4], vec![5]])]
+#[case(1, 6, 3, vec![vec![1, 2, 3], vec![4, 5, 6]])]
+#[case(1, 7, 3, vec![vec![1, 2, 3], vec![4, 5, 6]])]
+#[case(4, 5, 3, vec![])]
+#[case(6, 5, 1, vec![])]
+#[case(1, 2, 0, vec![vec![1], vec![2]])]
+fn test_da_batches_by_block_count(
+    #[case] start: u64,
+    #[case] end: u64,
+    #[case] max_blocks: u64,
+    #[case] expected: Vec<Vec<u64>>,
+) {
+    assert_eq!(da_batches(&small_blocks(start, end), max_blocks, false, 6), expected);
+}
+
+#[rstest]
+#[case(1, 5, 1, vec![], vec![vec![1], vec![2], vec![3], vec![4], vec![5]])]
+#[case(1, 5, 1, vec![2, 3], vec![vec![1], vec![2, 3, 4], vec![5]])]
+#[case(1, 7, 2, vec![1, 4], vec![vec![1, 2, 3], vec![4, 5, 6]])]
+#[case(1, 3, 1, vec![3], vec![vec![1], vec![2]])]
+#[case(1, 3, 1, vec![1, 2, 3], vec![])]
+fn test_da_batches_folding_empty_blocks(
+    #[case] start: u64,
+    #[case] end: u64,
+    #[case] max_blocks: u64,
+    #[case] empty_blocks: Vec<u64>,
+    #[case] expected: Vec<Vec<u64>>,
+) {
+    let blocks: Vec<(u64, usize, bool)> =
+        (start..=end).map(|number| (number, 10, empty_blocks.contains(&number))).collect();
+    assert_eq!(da_batches(&blocks, max_blocks, true, 6), expected);
+}
+
+/// Tests that a batch is closed before the block which would take it over the blobs of a single
+/// DA transaction, whatever its number of blocks.
+#[rstest]
+// 2 blobs hold 8192 field elements
+#[case(
+    vec![(1, 3000, false), (2, 3000, false), (3, 3000, false), (4, 5000, false), (5, 10, false), (6, 500, false)],
+    vec![vec![1, 2], vec![3, 4, 5]]
+)]
+// a block too big for a single transaction goes alone
+#[case(vec![(1, 10, false), (2, 9000, false), (3, 10, false)], vec![vec![1], vec![2]])]
+fn test_da_batches_by_blob_count(#[case] blocks: Vec<(u64, usize, bool)>, #[case] expected: Vec<Vec<u64>>) {
+    assert_eq!(da_batches(&blocks, 10, false, 2), expected);
+}
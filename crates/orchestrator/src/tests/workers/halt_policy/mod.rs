@@ -0,0 +1,45 @@
+use std::error::Error;
+
+use rstest::rstest;
+
+use crate::config::config_force_init;
+use crate::database::types::{JobCount, JobStats};
+use crate::database::MockDatabase;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::tests::common::init_config;
+use crate::workers::job_archival::JobArchivalWorker;
+use crate::workers::proving::ProvingWorker;
+use crate::workers::snos::SnosWorker;
+use crate::workers::update_state::UpdateStateWorker;
+use crate::workers::{HaltPolicy, Worker};
+
+/// Tests that a failed DA job halts every worker with the global policy, and only the workers of
+/// its type and of its children types with the job type policy.
+#[rstest]
+#[tokio::test]
+async fn test_halt_policy() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    db.expect_get_job_stats().returning(|| {
+        Ok(JobStats {
+            counts: vec![
+                JobCount { job_type: JobType::DataSubmission, status: JobStatus::VerificationFailed, count: 1 },
+                JobCount { job_type: JobType::SnosRun, status: JobStatus::Completed, count: 10 },
+            ],
+            oldest_pending_job_age: None,
+        })
+    });
+    let config = init_config(None, Some(db), None, None, None, None, None).await;
+    config_force_init(config).await;
+
+    assert!(!SnosWorker.is_worker_enabled(HaltPolicy::Global).await?);
+    assert!(!ProvingWorker.is_worker_enabled(HaltPolicy::Global).await?);
+
+    assert!(SnosWorker.is_worker_enabled(HaltPolicy::JobType).await?);
+    assert!(ProvingWorker.is_worker_enabled(HaltPolicy::JobType).await?);
+    // the state transitions depend on the DA jobs
+    assert!(!UpdateStateWorker.is_worker_enabled(HaltPolicy::JobType).await?);
+    // the workers which don't create jobs are halted by any failed job
+    assert!(!JobArchivalWorker.is_worker_enabled(HaltPolicy::JobType).await?);
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::error::Error;
+
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::config::config_force_init;
+use crate::data_storage::MockDataStorage;
+use crate::database::types::JobPage;
+use crate::database::MockDatabase;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::tests::common::init_config;
+use crate::tests::workers::utils::get_job_item_mock_by_id;
+use crate::workers::job_archival::{JobArchivalWorker, ENV_JOB_ARCHIVE_EXPORT, JOB_ARCHIVE_STORAGE_PREFIX};
+use crate::workers::Worker;
+
+#[rstest]
+#[tokio::test]
+async fn test_job_archival_worker() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut storage = MockDataStorage::new();
+
+    let mut old_job = get_job_item_mock_by_id("1".to_string(), Uuid::new_v4());
+    old_job.status = JobStatus::Completed;
+    let mut latest_job = get_job_item_mock_by_id("2".to_string(), Uuid::new_v4());
+    latest_job.status = JobStatus::Completed;
+    let old_job_id = old_job.id;
+
+    // both jobs are old enough but the latest SNOS job is kept
+    let latest_snos_job = latest_job.clone();
+    db.expect_get_latest_job_by_type().returning(move |job_type| {
+        Ok(if job_type == JobType::SnosRun { Some(latest_snos_job.clone()) } else { None })
+    });
+    db.expect_get_jobs_paginated()
+        .withf(|filter, cursor, _| {
+            filter.status == Some(JobStatus::Completed) && filter.created_before.is_some() && cursor.is_none()
+        })
+        .times(1)
+        .returning(move |_, _, _| Ok(JobPage { jobs: vec![old_job.clone(), latest_job.clone()], next_cursor: None }));
+    db.expect_archive_jobs().withf(move |ids| ids.to_vec() == vec![old_job_id]).times(1).returning(|_| Ok(1));
+    storage
+        .expect_put_data()
+        .withf(move |_, key| key == format!("{}/{}.jsonl", JOB_ARCHIVE_STORAGE_PREFIX, old_job_id))
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    let config = init_config(None, Some(db), None, None, None, None, Some(storage)).await;
+    config_force_init(config).await;
+
+    std::env::set_var(ENV_JOB_ARCHIVE_EXPORT, "true");
+    JobArchivalWorker.run_worker().await?;
+
+    Ok(())
+}
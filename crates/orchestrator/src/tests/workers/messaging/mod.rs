@@ -0,0 +1,107 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use mockall::predicate::eq;
+use rstest::rstest;
+use serde_json::json;
+use settlement_client_interface::{L1ToL2Message, MockSettlementClient};
+use uuid::Uuid;
+
+use crate::config::config_force_init;
+use crate::database::MockDatabase;
+use crate::jobs::constants::{JOB_METADATA_MESSAGE_PAYLOAD_KEY, JOB_METADATA_MESSAGE_TX_HASH_KEY};
+use crate::jobs::job_handler_factory::mock_factory;
+use crate::jobs::types::JobType;
+use crate::jobs::{Job, MockJob};
+use crate::queue::MockQueueProvider;
+use crate::queue::job_queue::MESSAGE_JOB_PROCESSING_QUEUE;
+use crate::tests::common::init_config;
+use crate::tests::workers::utils::get_job_item_mock_by_id;
+use crate::workers::Worker;
+use crate::workers::messaging::{MESSAGING_WORKER, MessagingWorker};
+use crate::workers::state::LAST_PROCESSED_BLOCK_KEY;
+
+fn message(hash_byte: u8, block_number: u64) -> L1ToL2Message {
+    L1ToL2Message {
+        message_hash: [hash_byte; 32],
+        tx_hash: format!("0x{:x}", block_number),
+        block_number,
+        from_address: [1; 32],
+        to_address: [2; 32],
+        selector: [3; 32],
+        payload: vec![[4; 32], [5; 32]],
+        nonce: [6; 32],
+        fee: 1000,
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_messaging_worker() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut queue = MockQueueProvider::new();
+    let mut settlement_client = MockSettlementClient::new();
+    let mut job_handler = MockJob::new();
+
+    // blocks 6 to 10 are scanned, the message 0xaa.. is tracked already
+    let tracked_id = format!("0x{}", "aa".repeat(32));
+    let new_id = format!("0x{}", "bb".repeat(32));
+    settlement_client.expect_get_latest_block_number().times(1).returning(|| Ok(10));
+    db.expect_get_worker_state()
+        .with(eq(MESSAGING_WORKER), eq(LAST_PROCESSED_BLOCK_KEY))
+        .times(1)
+        .returning(|_, _| Ok(Some(json!(5))));
+    settlement_client
+        .expect_get_messages_to_l2()
+        .with(eq(6), eq(10))
+        .times(1)
+        .returning(|_, _| Ok(vec![message(0xaa, 7), message(0xbb, 9)]));
+
+    let tracked_id_clone = tracked_id.clone();
+    db.expect_get_job_by_internal_id_and_type()
+        .withf(move |internal_id, job_type| internal_id == tracked_id_clone && job_type == &JobType::MessageProcessing)
+        .times(1)
+        .returning(|internal_id, _| Ok(Some(get_job_item_mock_by_id(internal_id.to_string(), Uuid::new_v4()))));
+    let new_id_clone = new_id.clone();
+    db.expect_get_job_by_internal_id_and_type()
+        .withf(move |internal_id, job_type| internal_id == new_id_clone && job_type == &JobType::MessageProcessing)
+        .times(2)
+        .returning(|_, _| Ok(None));
+
+    let mut job_item = get_job_item_mock_by_id(new_id.clone(), Uuid::new_v4());
+    job_item.job_type = JobType::MessageProcessing;
+    let job_item_cloned = job_item.clone();
+    let new_id_clone = new_id.clone();
+    job_handler
+        .expect_create_job()
+        .withf(move |_, internal_id, metadata| {
+            internal_id == &new_id_clone
+                && metadata.get(JOB_METADATA_MESSAGE_TX_HASH_KEY) == Some(&"0x9".to_string())
+                && metadata.get(JOB_METADATA_MESSAGE_PAYLOAD_KEY)
+                    == Some(&format!("0x{},0x{}", "04".repeat(32), "05".repeat(32)))
+        })
+        .times(1)
+        .returning(move |_, _, _| Ok(job_item.clone()));
+    db.expect_create_job().times(1).returning(move |_| Ok(job_item_cloned.clone()));
+    db.expect_set_worker_state()
+        .with(eq(MESSAGING_WORKER), eq(LAST_PROCESSED_BLOCK_KEY), eq(json!(10)))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let y: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::MessageProcessing)).returning(move |_| Arc::clone(&y));
+
+    queue
+        .expect_send_message_to_queue()
+        .times(1)
+        .returning(|_, _, _, _| Ok(()))
+        .withf(|queue, _payload, _delay, _group| queue == MESSAGE_JOB_PROCESSING_QUEUE);
+
+    let config = init_config(None, Some(db), Some(queue), None, None, Some(settlement_client), None).await;
+    config_force_init(config).await;
+
+    MessagingWorker {}.run_worker().await?;
+
+    Ok(())
+}
@@ -1,6 +1,36 @@
 #[cfg(test)]
+mod audit;
+#[cfg(test)]
+mod balance_monitor;
+#[cfg(test)]
+mod da_backfill;
+#[cfg(test)]
+mod data_submission;
+#[cfg(test)]
+mod halt_policy;
+#[cfg(test)]
+mod job_archival;
+#[cfg(test)]
+mod messaging;
+#[cfg(test)]
+mod orphan_tx_watchdog;
+#[cfg(test)]
+mod pipeline_monitor;
+#[cfg(test)]
+mod proof_aggregation;
+#[cfg(test)]
 pub mod proving;
 #[cfg(test)]
+mod reorg_monitor;
+#[cfg(test)]
+mod run_history;
+#[cfg(test)]
+mod scheduler;
+#[cfg(test)]
 pub mod snos;
+#[cfg(test)]
+mod storage_gc;
+#[cfg(test)]
+mod stuck_jobs;
 mod update_state;
 mod utils;
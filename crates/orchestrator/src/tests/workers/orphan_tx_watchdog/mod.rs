@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use mockall::predicate::eq;
+use rstest::rstest;
+use serde_json::json;
+use settlement_client_interface::MockSettlementClient;
+use uuid::Uuid;
+
+use crate::config::config_force_init;
+use crate::database::types::AuditEventKind;
+use crate::database::MockDatabase;
+use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX;
+use crate::jobs::types::{ExternalId, JobCounters, JobItem, JobPriority, JobStatus, JobType};
+use crate::tests::common::{init_config, EnvVarGuard};
+use crate::workers::orphan_tx_watchdog::{
+    OrphanTxWatchdogWorker, ENV_ORPHAN_TX_WATCHDOG_JOBS_PAGE_SIZE, ORPHAN_TX_WATCHDOG_WORKER,
+};
+use crate::workers::state::LAST_PROCESSED_BLOCK_KEY;
+use crate::workers::Worker;
+
+fn state_update_job(internal_id: &str, tx_hashes: &str) -> JobItem {
+    let mut metadata = HashMap::new();
+    metadata.insert(format!("{}1", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX), tx_hashes.to_string());
+    JobItem {
+        id: Uuid::new_v4(),
+        internal_id: internal_id.to_string(),
+        job_type: JobType::StateTransition,
+        status: JobStatus::Completed,
+        external_id: ExternalId::String(internal_id.to_string().into_boxed_str()),
+        metadata,
+        version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_orphan_tx_watchdog_worker() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut settlement_client = MockSettlementClient::new();
+
+    // blocks 6 to 10 are scanned, only 0xc isn't referenced by any job
+    settlement_client.expect_get_latest_block_number().times(1).returning(|| Ok(10));
+    db.expect_get_worker_state()
+        .with(eq(ORPHAN_TX_WATCHDOG_WORKER), eq(LAST_PROCESSED_BLOCK_KEY))
+        .times(1)
+        .returning(|_, _| Ok(Some(json!(5))));
+    settlement_client
+        .expect_get_operator_transactions()
+        .with(eq(6), eq(10))
+        .times(1)
+        .returning(|_, _| Ok(vec!["0x0a".to_string(), "0xb".to_string(), "0xc".to_string()]));
+
+    db.expect_get_latest_jobs_by_type()
+        .with(eq(JobType::StateTransition), eq(0), eq(100))
+        .times(1)
+        .returning(|_, _, _| Ok(vec![state_update_job("1", "0xA,0xb")]));
+    db.expect_get_latest_jobs_by_type()
+        .withf(|job_type, offset, _| job_type != &JobType::StateTransition && *offset == 0)
+        .times(2)
+        .returning(|_, _, _| Ok(vec![]));
+
+    db.expect_record_audit_event()
+        .withf(|event| event.kind == AuditEventKind::OrphanTransaction && event.details.contains("0xc"))
+        .times(1)
+        .returning(|_| Ok(()));
+    db.expect_set_worker_state()
+        .with(eq(ORPHAN_TX_WATCHDOG_WORKER), eq(LAST_PROCESSED_BLOCK_KEY), eq(json!(10)))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let config = init_config(None, Some(db), None, None, None, Some(settlement_client), None).await;
+    config_force_init(config).await;
+
+    OrphanTxWatchdogWorker {}.run_worker().await?;
+
+    Ok(())
+}
+
+/// Tests that the jobs older than the first page are read until every operator transaction is
+/// matched, and no further.
+#[rstest]
+#[tokio::test]
+async fn test_orphan_tx_watchdog_worker_reads_older_jobs() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut settlement_client = MockSettlementClient::new();
+
+    settlement_client.expect_get_latest_block_number().times(1).returning(|| Ok(10));
+    db.expect_get_worker_state().times(1).returning(|_, _| Ok(Some(json!(9))));
+    settlement_client
+        .expect_get_operator_transactions()
+        .with(eq(10), eq(10))
+        .times(1)
+        .returning(|_, _| Ok(vec!["0xa".to_string(), "0xb".to_string()]));
+
+    // no DA nor proof registration jobs, the transactions are referenced by the 2nd and 3rd most
+    // recent state update jobs
+    db.expect_get_latest_jobs_by_type()
+        .withf(|job_type, offset, _| job_type != &JobType::StateTransition && *offset == 0)
+        .times(2)
+        .returning(|_, _, _| Ok(vec![]));
+    let state_update_jobs = [state_update_job("3", "0xd"), state_update_job("2", "0xb"), state_update_job("1", "0xa")];
+    for (offset, job) in (0..).zip(state_update_jobs) {
+        db.expect_get_latest_jobs_by_type()
+            .with(eq(JobType::StateTransition), eq(offset), eq(1))
+            .times(1)
+            .returning(move |_, _, _| Ok(vec![job.clone()]));
+    }
+    db.expect_set_worker_state().times(1).returning(|_, _, _| Ok(()));
+
+    let config = init_config(None, Some(db), None, None, None, Some(settlement_client), None).await;
+    config_force_init(config).await;
+
+    let _page_size = EnvVarGuard::set(ENV_ORPHAN_TX_WATCHDOG_JOBS_PAGE_SIZE, "1");
+    OrphanTxWatchdogWorker {}.run_worker().await?;
+
+    Ok(())
+}
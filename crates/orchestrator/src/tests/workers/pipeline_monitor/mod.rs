@@ -0,0 +1,69 @@
+use std::error::Error;
+
+use httpmock::MockServer;
+use mockall::predicate::eq;
+use rstest::rstest;
+use serde_json::json;
+use settlement_client_interface::MockSettlementClient;
+use uuid::Uuid;
+
+use crate::config::config_force_init;
+use crate::database::MockDatabase;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::tests::common::init_config;
+use crate::tests::workers::utils::get_job_item_mock_by_id;
+use crate::workers::pipeline_monitor::{
+    PipelineMonitorWorker, ENV_PIPELINE_MONITOR_MAX_LAG_BLOCKS, PIPELINE_LATEST_BLOCK, STAGE_DA_SUBMITTED,
+    STAGE_MADARA, STAGE_SETTLED, STAGE_SNOS_COMPLETED,
+};
+use crate::workers::Worker;
+
+#[rstest]
+#[tokio::test]
+async fn test_pipeline_monitor_worker() -> Result<(), Box<dyn Error>> {
+    let server = MockServer::start();
+    let mut db = MockDatabase::new();
+    let mut settlement_client = MockSettlementClient::new();
+
+    // the SNOS jobs run ranges of blocks, the DA jobs single blocks
+    db.expect_get_latest_job_by_type_and_status()
+        .with(eq(JobType::SnosRun), eq(JobStatus::Completed))
+        .times(2)
+        .returning(|_, _| Ok(Some(get_job_item_mock_by_id("91-95".to_string(), Uuid::new_v4()))));
+    db.expect_get_latest_job_by_type_and_status()
+        .with(eq(JobType::DataSubmission), eq(JobStatus::Completed))
+        .times(2)
+        .returning(|_, _| Ok(Some(get_job_item_mock_by_id("90".to_string(), Uuid::new_v4()))));
+    settlement_client.expect_get_last_settled_block().times(2).returning(|| Ok(80));
+    let block_number_mock = server.mock(|when, then| {
+        when.path("/").body_contains("starknet_blockNumber");
+        then.status(200).body(serde_json::to_vec(&json!({ "id": 1, "jsonrpc": "2.0", "result": 100 })).unwrap());
+    });
+
+    let config = init_config(
+        Some(format!("http://localhost:{}", server.port())),
+        Some(db),
+        None,
+        None,
+        None,
+        Some(settlement_client),
+        None,
+    )
+    .await;
+    config_force_init(config).await;
+
+    // the settled blocks lag 20 blocks behind, the alert is raised on the first run only
+    std::env::set_var(ENV_PIPELINE_MONITOR_MAX_LAG_BLOCKS, "10");
+    let worker = PipelineMonitorWorker::default();
+    worker.run_worker().await?;
+    worker.run_worker().await?;
+    std::env::remove_var(ENV_PIPELINE_MONITOR_MAX_LAG_BLOCKS);
+
+    block_number_mock.assert_hits(2);
+    assert_eq!(PIPELINE_LATEST_BLOCK.with_label_values(&[STAGE_MADARA]).get(), 100);
+    assert_eq!(PIPELINE_LATEST_BLOCK.with_label_values(&[STAGE_SNOS_COMPLETED]).get(), 95);
+    assert_eq!(PIPELINE_LATEST_BLOCK.with_label_values(&[STAGE_DA_SUBMITTED]).get(), 90);
+    assert_eq!(PIPELINE_LATEST_BLOCK.with_label_values(&[STAGE_SETTLED]).get(), 80);
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use mockall::predicate::eq;
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::config::config_force_init;
+use crate::database::MockDatabase;
+use crate::jobs::constants::JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY;
+use crate::jobs::job_handler_factory::mock_factory;
+use crate::jobs::types::{ExternalId, JobCounters, JobItem, JobPriority, JobStatus, JobType};
+use crate::jobs::{Job, MockJob};
+use crate::queue::MockQueueProvider;
+use crate::tests::common::init_config;
+use crate::tests::workers::utils::get_job_by_mock_id_vector;
+use crate::workers::proof_aggregation::{ProofAggregationWorker, ENV_PROOF_AGGREGATION_SIZE};
+use crate::workers::Worker;
+
+fn aggregation_job(internal_id: &str, blocks: &str) -> JobItem {
+    JobItem {
+        id: Uuid::new_v4(),
+        internal_id: internal_id.to_string(),
+        job_type: JobType::ProofAggregation,
+        status: JobStatus::Completed,
+        external_id: ExternalId::Number(0),
+        metadata: HashMap::from([(JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY.to_string(), blocks.to_string())]),
+        version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
+    }
+}
+
+/// Blocks 1 and 2 are aggregated, blocks 3 to 5 and 7 are proven: only blocks 3 and 4 make a
+/// complete aggregation, block 6 isn't proven so block 7 waits.
+#[rstest]
+#[tokio::test]
+async fn test_proof_aggregation_worker() -> Result<(), Box<dyn Error>> {
+    std::env::set_var(ENV_PROOF_AGGREGATION_SIZE, "2");
+    let mut db = MockDatabase::new();
+    let mut queue = MockQueueProvider::new();
+
+    db.expect_get_latest_job_by_type()
+        .with(eq(JobType::ProofAggregation))
+        .times(1)
+        .returning(|_| Ok(Some(aggregation_job("1", "1,2"))));
+    db.expect_get_jobs_after_internal_id_by_job_type()
+        .with(eq(JobType::ProofCreation), eq(JobStatus::Completed), eq("2".to_string()))
+        .times(1)
+        .returning(|_, _, _| {
+            Ok(get_job_by_mock_id_vector(JobType::ProofCreation, JobStatus::Completed, 5, 3)
+                .into_iter()
+                .filter(|job| job.internal_id != "6")
+                .collect())
+        });
+
+    db.expect_get_job_by_internal_id_and_type()
+        .with(eq("3".to_string()), eq(JobType::ProofAggregation))
+        .times(1)
+        .returning(|_, _| Ok(None));
+    let mut job_handler = MockJob::new();
+    job_handler
+        .expect_create_job()
+        .withf(|_, internal_id, metadata| {
+            internal_id == "3" && metadata.get(JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY) == Some(&"3,4".to_string())
+        })
+        .times(1)
+        .returning(|_, _, _| Ok(aggregation_job("3", "3,4")));
+    db.expect_create_job().times(1).returning(Ok);
+    queue.expect_send_message_to_queue().times(1).returning(|_, _, _, _| Ok(()));
+
+    let config = init_config(None, Some(db), Some(queue), None, None, None, None).await;
+    config_force_init(config).await;
+
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).with(eq(JobType::ProofAggregation)).returning(move |_| Arc::clone(&job_handler));
+
+    ProofAggregationWorker.run_worker().await?;
+
+    Ok(())
+}
@@ -30,8 +30,6 @@ async fn test_proving_worker(#[case] incomplete_runs: bool) -> Result<(), Box<dy
     let mut prover_client = MockProverClient::new();
     let settlement_client = MockSettlementClient::new();
 
-    const JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_job_processing_queue";
-
     // Mocking Prover Client
 
     // Mocking the get_job_handler function.
@@ -40,7 +38,7 @@ async fn test_proving_worker(#[case] incomplete_runs: bool) -> Result<(), Box<dy
     // incomplete_runs : This refers to if there are incomplete runs in the previous job which is
     // `snos_job` in this case.
     if incomplete_runs {
-        let jobs_vec_temp: Vec<JobItem> = get_job_by_mock_id_vector(JobType::ProofCreation, JobStatus::Created, 5, 1)
+        let jobs_vec_temp: Vec<JobItem> = get_job_by_mock_id_vector(JobType::SnosRun, JobStatus::Completed, 5, 1)
             .into_iter()
             .filter(|val| val.internal_id != "3")
             .collect();
@@ -62,8 +60,8 @@ async fn test_proving_worker(#[case] incomplete_runs: bool) -> Result<(), Box<dy
         queue
             .expect_send_message_to_queue()
             .times(4)
-            .returning(|_, _, _| Ok(()))
-            .withf(|queue, _payload, _delay| queue == JOB_PROCESSING_QUEUE);
+            .returning(|_, _, _, _| Ok(()))
+            .withf(|queue, _payload, _delay, _group| queue == PROVING_JOB_PROCESSING_QUEUE);
     } else {
         for i in 1..5 + 1 {
             db_checks_proving_worker(i, &mut db, &mut job_handler);
@@ -73,7 +71,7 @@ async fn test_proving_worker(#[case] incomplete_runs: bool) -> Result<(), Box<dy
         db.expect_get_jobs_without_successor()
             .times(1)
             .withf(|_, _, _| true)
-            .returning(move |_, _, _| Ok(get_job_by_mock_id_vector(JobType::ProofCreation, JobStatus::Created, 5, 1)));
+            .returning(move |_, _, _| Ok(get_job_by_mock_id_vector(JobType::SnosRun, JobStatus::Completed, 5, 1)));
 
         prover_client.expect_submit_task().times(5).returning(|_| Ok("task_id".to_string()));
 
@@ -81,8 +79,8 @@ async fn test_proving_worker(#[case] incomplete_runs: bool) -> Result<(), Box<dy
         queue
             .expect_send_message_to_queue()
             .times(5)
-            .returning(|_, _, _| Ok(()))
-            .withf(|queue, _payload, _delay| queue == JOB_PROCESSING_QUEUE);
+            .returning(|_, _, _, _| Ok(()))
+            .withf(|queue, _payload, _delay, _group| queue == PROVING_JOB_PROCESSING_QUEUE);
     }
 
     let config = init_config(
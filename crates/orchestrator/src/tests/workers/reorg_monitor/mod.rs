@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use mockall::predicate::eq;
+use rstest::rstest;
+use settlement_client_interface::{MockSettlementClient, SettlementVerificationStatus};
+use uuid::Uuid;
+
+use crate::config::{config, config_force_init};
+use crate::database::sqlite::config::SqliteDbConfig;
+use crate::database::sqlite::SqliteDb;
+use crate::database::types::{AuditEventKind, DatabaseWrite};
+use crate::database::{Database, MockDatabase};
+use crate::jobs::constants::{
+    JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX, JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY,
+    JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO, JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX,
+};
+use crate::jobs::types::{ExternalId, JobCounters, JobItem, JobPriority, JobStatus, JobType};
+use crate::queue::job_queue::STATE_UPDATE_JOB_PROCESSING_QUEUE;
+use crate::queue::MockQueueProvider;
+use crate::tests::common::{init_config, init_config_with_database};
+use crate::workers::reorg_monitor::ReorgMonitorWorker;
+use crate::workers::Worker;
+
+fn completed_state_update_job(internal_id: &str, blocks: &str, attempts: &[(&str, &str)]) -> JobItem {
+    let mut metadata = HashMap::new();
+    metadata.insert(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY.to_string(), blocks.to_string());
+    for (attempt_no, tx_hashes) in attempts {
+        metadata.insert(format!("{}{}", JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX, attempt_no), tx_hashes.to_string());
+    }
+    JobItem {
+        id: Uuid::new_v4(),
+        internal_id: internal_id.to_string(),
+        job_type: JobType::StateTransition,
+        status: JobStatus::Completed,
+        external_id: ExternalId::String(internal_id.to_string().into_boxed_str()),
+        metadata,
+        version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_reorg_monitor_worker_settles_reorged_job_again() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut queue = MockQueueProvider::new();
+    let mut settlement_client = MockSettlementClient::new();
+
+    // job #3 settled blocks 3 and 4, the tx of block 4 and its replacement were reorged out
+    let mut reorged_job = completed_state_update_job("3", "3,4", &[("0", "0xa"), ("1", "0xb")]);
+    reorged_job.metadata.insert(format!("{}0xb", JOB_METADATA_STATE_UPDATE_REPLACEMENT_PREFIX), "0xc".to_string());
+    let reorged_job_id = reorged_job.id;
+    let jobs = vec![completed_state_update_job("5", "5", &[("0", "0xd")]), reorged_job];
+    db.expect_get_latest_jobs_by_type()
+        .with(eq(JobType::StateTransition), eq(0), eq(10))
+        .times(1)
+        .returning(move |_, _, _| Ok(jobs.clone()));
+
+    for tx_hash in ["0xb", "0xc"] {
+        settlement_client
+            .expect_verify_tx_inclusion()
+            .with(eq(tx_hash))
+            .times(1)
+            .returning(|_| Ok(SettlementVerificationStatus::Rejected("Could not find status of tx".to_string())));
+    }
+    settlement_client.expect_get_last_settled_block().times(1).returning(|| Ok(3));
+
+    // the job and its history are updated in a single transaction
+    db.expect_run_transaction()
+        .withf(move |writes| match &writes[..] {
+            [DatabaseWrite::UpdateJob(job), DatabaseWrite::AppendJobEvent(event)] => {
+                job.id == reorged_job_id
+                    && job.status == JobStatus::Created
+                    && job.metadata.get(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO) == Some(&"4".to_string())
+                    && event.job_id == reorged_job_id
+                    && event.old_status == JobStatus::Completed
+                    && event.new_status == JobStatus::Created
+            }
+            _ => false,
+        })
+        .times(1)
+        .returning(|_| Ok(()));
+    db.expect_record_audit_event()
+        .withf(|event| event.kind == AuditEventKind::SettlementReorg)
+        .times(1)
+        .returning(|_| Ok(()));
+    queue
+        .expect_send_message_to_queue()
+        .withf(|queue, _payload, _delay, _group| queue == STATE_UPDATE_JOB_PROCESSING_QUEUE)
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    let config = init_config(None, Some(db), Some(queue), None, None, Some(settlement_client), None).await;
+    config_force_init(config).await;
+
+    ReorgMonitorWorker.run_worker().await?;
+
+    Ok(())
+}
+
+/// Tests that the database accepts the move of the reorged job back to `Created`, from where it
+/// is processed again.
+#[rstest]
+#[tokio::test]
+async fn test_reorg_monitor_worker_moves_reorged_job_in_database() -> Result<(), Box<dyn Error>> {
+    let database = SqliteDb::new(SqliteDbConfig { path: ":memory:".to_string() }).await;
+    let mut queue = MockQueueProvider::new();
+    let mut settlement_client = MockSettlementClient::new();
+
+    let reorged_job = completed_state_update_job("3", "3,4", &[("0", "0xa")]);
+    database.create_job(reorged_job.clone()).await?;
+
+    settlement_client
+        .expect_verify_tx_inclusion()
+        .with(eq("0xa"))
+        .times(1)
+        .returning(|_| Ok(SettlementVerificationStatus::Rejected("Could not find status of tx".to_string())));
+    settlement_client.expect_get_last_settled_block().times(1).returning(|| Ok(2));
+    queue
+        .expect_send_message_to_queue()
+        .withf(|queue, _payload, _delay, _group| queue == STATE_UPDATE_JOB_PROCESSING_QUEUE)
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    let test_config = init_config_with_database(
+        None,
+        Box::new(database),
+        Some(queue),
+        None,
+        None,
+        Some(settlement_client),
+        None,
+    )
+    .await;
+    config_force_init(test_config).await;
+
+    ReorgMonitorWorker.run_worker().await?;
+
+    let config = config().await;
+    let stored_job = config.database().get_job_by_id(reorged_job.id).await?.unwrap();
+    assert_eq!(stored_job.status, JobStatus::Created);
+    assert_eq!(stored_job.metadata.get(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO), Some(&"3".to_string()));
+    let events = config.database().get_job_events(reorged_job.id).await?;
+    let event = events.last().unwrap();
+    assert_eq!((event.old_status.clone(), event.new_status.clone()), (JobStatus::Completed, JobStatus::Created));
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use std::error::Error;
+
+use rstest::rstest;
+
+use crate::config::config_force_init;
+use crate::database::MockDatabase;
+use crate::tests::common::init_config;
+use crate::workers::run_history::{count_created_jobs, record_run, skip_run};
+
+/// Tests that a run is recorded with the jobs it created and the error it failed with, and that a
+/// skipped run isn't recorded.
+#[rstest]
+#[tokio::test]
+async fn test_record_run() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    db.expect_record_worker_run()
+        .withf(|run| run.worker == "snos" && run.jobs_created == 3 && run.succeeded())
+        .times(1)
+        .returning(|_| Ok(()));
+    db.expect_record_worker_run()
+        .withf(|run| {
+            run.worker == "proving" && run.jobs_created == 1 && run.error.as_deref() == Some("RPC unreachable")
+        })
+        .times(1)
+        .returning(|_| Ok(()));
+    db.expect_delete_worker_runs_before().times(2).returning(|_| Ok(0));
+    let config = init_config(None, Some(db), None, None, None, None, None).await;
+    config_force_init(config).await;
+
+    let result = record_run("snos", async {
+        count_created_jobs(1);
+        count_created_jobs(2);
+        Ok::<(), Box<dyn Error>>(())
+    })
+    .await;
+    assert_eq!(result, Ok(()));
+
+    let result = record_run("proving", async {
+        count_created_jobs(1);
+        Err::<(), Box<dyn Error>>("RPC unreachable".into())
+    })
+    .await;
+    assert_eq!(result, Err("RPC unreachable".to_string()));
+
+    // the instance isn't the leader
+    let result = record_run("update_state", async {
+        skip_run();
+        Ok::<(), Box<dyn Error>>(())
+    })
+    .await;
+    assert_eq!(result, Ok(()));
+
+    Ok(())
+}
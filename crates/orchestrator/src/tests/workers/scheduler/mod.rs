@@ -0,0 +1,75 @@
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::workers::schedule::WorkerSchedule;
+use crate::workers::scheduler::WorkerScheduler;
+use crate::workers::{run_history, HaltPolicy, Worker};
+
+struct CountingWorker {
+    runs: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Worker for CountingWorker {
+    async fn run_worker_if_enabled(&self, _halt_policy: HaltPolicy) -> Result<(), Box<dyn Error>> {
+        // the tests have no database to record the runs in
+        run_history::skip_run();
+        self.run_worker().await
+    }
+
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        self.runs.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_trigger_runs_the_worker_now() {
+    let scheduler = WorkerScheduler::default();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let schedule =
+        WorkerSchedule { enabled: true, interval_seconds: 3600, cron: None, halt_policy: HaltPolicy::Global };
+    scheduler.spawn("counting", Box::new(CountingWorker { runs: runs.clone() }), schedule);
+
+    // the interval workers run once when they start
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    scheduler.trigger("counting").unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_cron_worker_waits_for_its_time() {
+    let scheduler = WorkerScheduler::default();
+    let runs = Arc::new(AtomicUsize::new(0));
+    // every year on January 1st
+    let schedule = WorkerSchedule {
+        enabled: true,
+        interval_seconds: 60,
+        cron: Some("0 0 0 1 1 *".to_string()),
+        halt_policy: HaltPolicy::Global,
+    };
+    scheduler.spawn("counting", Box::new(CountingWorker { runs: runs.clone() }), schedule);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 0);
+    assert_eq!(scheduler.workers(), vec!["counting".to_string()]);
+}
+
+#[tokio::test]
+async fn test_disabled_and_unknown_workers_cannot_be_triggered() {
+    let scheduler = WorkerScheduler::default();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let schedule = WorkerSchedule { enabled: false, interval_seconds: 60, cron: None, halt_policy: HaltPolicy::Global };
+    scheduler.spawn("disabled", Box::new(CountingWorker { runs: runs.clone() }), schedule);
+
+    assert!(scheduler.trigger("disabled").is_err());
+    assert!(scheduler.trigger("unknown").is_err());
+    assert_eq!(runs.load(Ordering::SeqCst), 0);
+}
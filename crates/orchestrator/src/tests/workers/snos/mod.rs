@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 
 use da_client_interface::MockDaClient;
@@ -13,11 +14,12 @@ use crate::database::MockDatabase;
 use crate::jobs::job_handler_factory::mock_factory;
 use crate::jobs::types::{JobStatus, JobType};
 use crate::jobs::{Job, MockJob};
-use crate::queue::job_queue::JOB_PROCESSING_QUEUE;
+use crate::queue::job_queue::SNOS_JOB_PROCESSING_QUEUE;
 use crate::queue::MockQueueProvider;
 use crate::tests::common::init_config;
 use crate::tests::workers::utils::get_job_item_mock_by_id;
-use crate::workers::snos::SnosWorker;
+use crate::workers::snos::{ChainHeadTracking, SnosWorker, SNOS_WORKER};
+use crate::workers::state::LAST_PROCESSED_BLOCK_KEY;
 use crate::workers::Worker;
 
 #[rstest]
@@ -36,6 +38,11 @@ async fn test_snos_worker(#[case] db_val: bool) -> Result<(), Box<dyn Error>> {
     let mut job_handler = MockJob::new();
 
     // Mocking db function expectations
+    // no block stored in the worker state, the worker falls back to the latest SNOS job
+    db.expect_get_worker_state()
+        .with(eq(SNOS_WORKER), eq(LAST_PROCESSED_BLOCK_KEY))
+        .times(1)
+        .returning(|_, _| Ok(None));
     if !db_val {
         db.expect_get_latest_job_by_type_and_status()
             .times(1)
@@ -72,6 +79,11 @@ async fn test_snos_worker(#[case] db_val: bool) -> Result<(), Box<dyn Error>> {
             .times(1)
             .withf(move |item| item.internal_id == i.clone().to_string())
             .returning(move |_| Ok(job_item.clone()));
+
+        db.expect_set_worker_state()
+            .times(1)
+            .with(eq(SNOS_WORKER), eq(LAST_PROCESSED_BLOCK_KEY), eq(json!(i)))
+            .returning(|_, _, _| Ok(()));
     }
 
     let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
@@ -82,8 +94,8 @@ async fn test_snos_worker(#[case] db_val: bool) -> Result<(), Box<dyn Error>> {
     // Queue function call simulations
     queue
         .expect_send_message_to_queue()
-        .returning(|_, _, _| Ok(()))
-        .withf(|queue, _payload, _delay| queue == JOB_PROCESSING_QUEUE);
+        .returning(|_, _, _, _| Ok(()))
+        .withf(|queue, _payload, _delay, _group| queue == SNOS_JOB_PROCESSING_QUEUE);
 
     // mock block number (madara) : 5
     let rpc_response_block_number = block;
@@ -113,3 +125,25 @@ async fn test_snos_worker(#[case] db_val: bool) -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[rstest]
+// the first run starts at the start block
+#[case(None, 100, 0, None, Some(1..=100))]
+// the next runs resume after the last block processed, however far behind the head
+#[case(Some(40), 100, 0, Some(10), Some(41..=100))]
+// the blocks within the confirmation lag of the head are left out
+#[case(Some(40), 100, 5, None, Some(41..=95))]
+#[case(Some(95), 100, 5, None, None)]
+#[case(None, 3, 5, None, None)]
+// the first run starts at most the max blocks behind the confirmed head
+#[case(None, 100, 5, Some(10), Some(85..=95))]
+fn test_snos_blocks_to_process(
+    #[case] last_processed_block: Option<u64>,
+    #[case] head: u64,
+    #[case] confirmation_lag: u64,
+    #[case] max_blocks_behind_head: Option<u64>,
+    #[case] expected: Option<RangeInclusive<u64>>,
+) {
+    let tracking = ChainHeadTracking { start_block: 1, max_blocks_behind_head, confirmation_lag };
+    assert_eq!(tracking.blocks_to_process(last_processed_block, head), expected);
+}
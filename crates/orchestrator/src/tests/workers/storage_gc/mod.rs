@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use mockall::predicate::eq;
+use rstest::rstest;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::config::config_force_init;
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::data_storage::MockDataStorage;
+use crate::database::types::{now_secs, JobPage};
+use crate::database::MockDatabase;
+use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY;
+use crate::jobs::types::{ExternalId, JobCounters, JobItem, JobPriority, JobStatus, JobType};
+use crate::tests::common::init_config;
+use crate::workers::state::LAST_PROCESSED_BLOCK_KEY;
+use crate::workers::storage_gc::{StorageGcWorker, STORAGE_GC_WORKER};
+use crate::workers::Worker;
+
+fn state_transition_job(blocks: &str, updated_at: u64) -> JobItem {
+    JobItem {
+        id: Uuid::new_v4(),
+        internal_id: blocks.to_string(),
+        job_type: JobType::StateTransition,
+        status: JobStatus::Completed,
+        external_id: ExternalId::Number(0),
+        metadata: HashMap::from([(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY.to_string(), blocks.to_string())]),
+        version: 0,
+        created_at: 0,
+        updated_at,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_storage_gc_worker() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut storage = MockDataStorage::new();
+
+    // block 1 was collected by the previous run and block 3 was settled too recently, the
+    // artifacts of blocks 2 and 4 are deleted but only block 2 is recorded as collected
+    db.expect_get_worker_state()
+        .with(eq(STORAGE_GC_WORKER), eq(LAST_PROCESSED_BLOCK_KEY))
+        .times(1)
+        .returning(|_, _| Ok(Some(json!(1))));
+    db.expect_get_jobs_paginated()
+        .withf(|filter, cursor, _| {
+            filter.job_type == Some(JobType::StateTransition)
+                && filter.status == Some(JobStatus::Completed)
+                && cursor.is_none()
+        })
+        .times(1)
+        .returning(|_, _, _| {
+            let recent_job = state_transition_job("3", now_secs());
+            let jobs = vec![state_transition_job("1,2", 0), recent_job, state_transition_job("4", 0)];
+            Ok(JobPage { jobs, next_cursor: None })
+        });
+
+    let collected_prefixes: Vec<String> = [2, 4]
+        .into_iter()
+        .flat_map(|block| {
+            [ArtifactKind::SnosInput, ArtifactKind::CairoPie].map(|kind| StorageKey::new(kind, block).block_prefix())
+        })
+        .collect();
+    storage
+        .expect_list_keys()
+        .withf(move |prefix| collected_prefixes.iter().any(|collected| collected == prefix))
+        .times(4)
+        .returning(|prefix| Ok(vec![format!("{}v1", prefix)]));
+    let flat_key = StorageKey::new(ArtifactKind::SnosInput, 2).flat_key();
+    let other_flat_key = flat_key.clone();
+    storage.expect_data_exists().with(eq(flat_key.clone())).times(1).returning(|_| Ok(true));
+    storage.expect_data_exists().withf(move |key| key != other_flat_key).times(3).returning(|_| Ok(false));
+    storage.expect_delete_data().with(eq(flat_key)).times(1).returning(|_| Ok(()));
+    storage.expect_delete_data().withf(|key| key.ends_with("/v1")).times(4).returning(|_| Ok(()));
+
+    db.expect_set_worker_state()
+        .with(eq(STORAGE_GC_WORKER), eq(LAST_PROCESSED_BLOCK_KEY), eq(json!(2)))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let config = init_config(None, Some(db), None, None, None, None, Some(storage)).await;
+    config_force_init(config).await;
+
+    StorageGcWorker.run_worker().await?;
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use std::error::Error;
+
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::config::config;
+use crate::database::types::now_secs;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::queue::job_queue::SNOS_JOB_PROCESSING_QUEUE;
+use crate::tests::common::MessagePayloadType;
+use crate::tests::config::TestConfigBuilder;
+use crate::tests::workers::utils::get_job_item_mock_by_id;
+use crate::workers::stuck_jobs::StuckJobRecoveryWorker;
+use crate::workers::Worker;
+
+/// Tests that a job locked for processing past its process timeout is moved back to `Created`
+/// and processed again, while a job locked recently is left to the worker processing it.
+#[rstest]
+#[tokio::test]
+async fn test_stuck_job_recovery_worker() -> Result<(), Box<dyn Error>> {
+    TestConfigBuilder::new().build().await;
+    let config = config().await;
+    let database = config.database();
+
+    let mut stuck_job = get_job_item_mock_by_id("1".to_string(), Uuid::new_v4());
+    stuck_job.job_type = JobType::SnosRun;
+    stuck_job.status = JobStatus::LockedForProcessing;
+    stuck_job.updated_at = now_secs() - 2 * 24 * 3600;
+    let mut processing_job = get_job_item_mock_by_id("2".to_string(), Uuid::new_v4());
+    processing_job.job_type = JobType::SnosRun;
+    processing_job.status = JobStatus::LockedForProcessing;
+    processing_job.updated_at = now_secs();
+    database.create_job(stuck_job.clone()).await?;
+    database.create_job(processing_job.clone()).await?;
+
+    StuckJobRecoveryWorker.run_worker().await?;
+
+    assert_eq!(database.get_job_by_id(stuck_job.id).await?.unwrap().status, JobStatus::Created);
+    assert_eq!(database.get_job_by_id(processing_job.id).await?.unwrap().status, JobStatus::LockedForProcessing);
+
+    let message = config.queue().consume_message_from_queue(SNOS_JOB_PROCESSING_QUEUE.to_string()).await?;
+    let payload: MessagePayloadType = message.payload_serde_json()?.unwrap();
+    assert_eq!(payload.id, stuck_job.id);
+
+    Ok(())
+}
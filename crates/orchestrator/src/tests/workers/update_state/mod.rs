@@ -5,13 +5,17 @@ use da_client_interface::MockDaClient;
 use httpmock::MockServer;
 use mockall::predicate::eq;
 use rstest::rstest;
+use settlement_client_interface::MockSettlementClient;
 use uuid::Uuid;
 
 use crate::config::config_force_init;
+use crate::database::types::JobPage;
 use crate::database::MockDatabase;
+use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY;
 use crate::jobs::job_handler_factory::mock_factory;
-use crate::jobs::types::{JobStatus, JobType};
+use crate::jobs::types::{JobItem, JobStatus, JobType};
 use crate::jobs::{Job, MockJob};
+use crate::queue::job_queue::STATE_UPDATE_JOB_PROCESSING_QUEUE;
 use crate::queue::MockQueueProvider;
 use crate::tests::common::init_config;
 use crate::tests::workers::utils::{
@@ -20,99 +24,181 @@ use crate::tests::workers::utils::{
 use crate::workers::update_state::UpdateStateWorker;
 use crate::workers::Worker;
 
+fn completed_state_update_job(internal_id: &str) -> JobItem {
+    let mut job = get_job_item_mock_by_id(internal_id.to_string(), Uuid::new_v4());
+    job.job_type = JobType::StateTransition;
+    job.status = JobStatus::Completed;
+    job
+}
+
+/// Tests that a single state update job settles the consecutive proven blocks after the last
+/// settled block, the first one after the last state update job or the first one of the chain.
 #[rstest]
-#[case(false, 0)]
-#[case(true, 5)]
+// no state update job yet, blocks 1 to 3 are proven
+#[case(None, 0, vec![1, 2, 3], Some("1-3"))]
+// blocks 2 to 6 are proven after the last state update job
+#[case(Some("1"), 1, vec![2, 3, 4, 5, 6], Some("2-6"))]
+// block 4 isn't proven yet, the blocks after it wait
+#[case(Some("1"), 1, vec![2, 3, 5, 6], Some("2-3"))]
+// block 2 isn't proven yet
+#[case(Some("1"), 1, vec![3], None)]
 #[tokio::test]
 async fn test_update_state_worker(
-    #[case] last_successful_job_exists: bool,
-    #[case] number_of_processed_jobs: usize,
+    #[case] last_state_update_job: Option<&'static str>,
+    #[case] last_settled_block: u64,
+    #[case] proven_blocks: Vec<u64>,
+    #[case] expected_job: Option<&'static str>,
 ) -> Result<(), Box<dyn Error>> {
     let server = MockServer::start();
     let da_client = MockDaClient::new();
     let mut db = MockDatabase::new();
     let mut queue = MockQueueProvider::new();
-
-    const JOB_PROCESSING_QUEUE: &str = "madara_orchestrator_job_processing_queue";
-
-    // Mocking the get_job_handler function.
+    let mut settlement_client = MockSettlementClient::new();
     let mut job_handler = MockJob::new();
 
-    // Mocking db function expectations
-    // If no successful state update jobs exist
-    if !last_successful_job_exists {
-        db.expect_get_latest_job_by_type_and_status()
-            .with(eq(JobType::StateTransition), eq(JobStatus::Completed))
-            .times(1)
-            .returning(|_, _| Ok(None));
-    } else {
-        // if successful state update job exists
+    db.expect_get_latest_job_by_type()
+        .with(eq(JobType::StateTransition))
+        .times(1)
+        .returning(move |_| Ok(last_state_update_job.map(completed_state_update_job)));
+    settlement_client.expect_get_last_settled_block().times(1).returning(move || Ok(last_settled_block));
 
-        // mocking the return value of first function call (getting last successful jobs):
-        db.expect_get_latest_job_by_type_and_status()
-            .with(eq(JobType::StateTransition), eq(JobStatus::Completed))
-            .times(1)
-            .returning(|_, _| Ok(Some(get_job_item_mock_by_id("1".to_string(), Uuid::new_v4()))));
-
-        // mocking the return values of second function call (getting completed proving worker jobs)
-        db.expect_get_jobs_after_internal_id_by_job_type()
-            .with(eq(JobType::ProofCreation), eq(JobStatus::Completed), eq("1".to_string()))
-            .returning(move |_, _, _| {
-                Ok(get_job_by_mock_id_vector(
-                    JobType::ProofCreation,
-                    JobStatus::Completed,
-                    number_of_processed_jobs as u64,
-                    2,
-                ))
-            });
-
-        // mocking getting of the jobs (when there is a safety check for any pre-existing job during job
-        // creation)
-        let completed_jobs =
-            get_job_by_mock_id_vector(JobType::ProofCreation, JobStatus::Completed, number_of_processed_jobs as u64, 2);
-        for job in completed_jobs {
-            db.expect_get_job_by_internal_id_and_type()
-                .times(1)
-                .with(eq(job.internal_id.to_string()), eq(JobType::StateTransition))
-                .returning(|_, _| Ok(None));
-        }
-
-        // mocking the creation of jobs
-        db_create_job_expectations_update_state_worker(
-            &mut db,
-            get_job_by_mock_id_vector(JobType::ProofCreation, JobStatus::Completed, number_of_processed_jobs as u64, 2),
-            &mut job_handler,
-        );
-    }
+    db.expect_get_jobs_paginated()
+        .withf(move |filter, cursor, _| {
+            filter.job_type == Some(JobType::ProofCreation)
+                && filter.status == Some(JobStatus::Completed)
+                && filter.internal_id_range.as_ref().map(|range| *range.start()) == Some(last_settled_block + 1)
+                && cursor.is_none()
+        })
+        .times(1)
+        .returning(move |_, _, _| {
+            // the jobs are listed by creation order, not by block
+            let jobs = proven_blocks
+                .iter()
+                .rev()
+                .flat_map(|block| get_job_by_mock_id_vector(JobType::ProofCreation, JobStatus::Completed, 1, *block))
+                .collect();
+            Ok(JobPage { jobs, next_cursor: None })
+        });
 
-    let y: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
     let ctx = mock_factory::get_job_handler_context();
-    // Mocking the `get_job_handler` call in create_job function.
-    if last_successful_job_exists {
-        ctx.expect().times(5).with(eq(JobType::StateTransition)).returning(move |_| Arc::clone(&y));
+    if let Some(expected_job) = expected_job {
+        db_create_job_expectations_update_state_worker(&mut db, expected_job, &mut job_handler);
+        let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+        ctx.expect().times(1).with(eq(JobType::StateTransition)).returning(move |_| Arc::clone(&job_handler));
+        queue
+            .expect_send_message_to_queue()
+            .times(1)
+            .withf(|queue, _payload, _delay, _group| queue == STATE_UPDATE_JOB_PROCESSING_QUEUE)
+            .returning(|_, _, _, _| Ok(()));
+    } else {
+        db.expect_create_job().never();
     }
 
-    // Queue function call simulations
-    queue
-        .expect_send_message_to_queue()
-        .returning(|_, _, _| Ok(()))
-        .withf(|queue, _payload, _delay| queue == JOB_PROCESSING_QUEUE);
-
-    // mock block number (madara) : 5
     let config = init_config(
         Some(format!("http://localhost:{}", server.port())),
         Some(db),
         Some(queue),
         Some(da_client),
         None,
-        None,
+        Some(settlement_client),
         None,
     )
     .await;
     config_force_init(config).await;
 
-    let update_state_worker = UpdateStateWorker {};
-    update_state_worker.run_worker().await?;
+    UpdateStateWorker {}.run_worker().await?;
+
+    Ok(())
+}
+
+/// Tests that no state update job is created while the last one is still in progress, it
+/// settles the blocks before the next ones.
+#[rstest]
+#[tokio::test]
+async fn test_update_state_worker_waits_for_pending_job() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut settlement_client = MockSettlementClient::new();
+
+    db.expect_get_latest_job_by_type().with(eq(JobType::StateTransition)).times(1).returning(|_| {
+        let mut job = completed_state_update_job("2-3");
+        job.status = JobStatus::PendingVerification;
+        Ok(Some(job))
+    });
+    settlement_client.expect_get_last_settled_block().never();
+    db.expect_get_jobs_paginated().never();
+    db.expect_create_job().never();
+
+    let config = init_config(None, Some(db), None, None, None, Some(settlement_client), None).await;
+    config_force_init(config).await;
+
+    UpdateStateWorker {}.run_worker().await?;
+
+    Ok(())
+}
+
+/// Tests that no state update job is created when the settlement layer doesn't agree with the
+/// database on the last settled block.
+#[rstest]
+#[tokio::test]
+async fn test_update_state_worker_settlement_layer_mismatch() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut settlement_client = MockSettlementClient::new();
+
+    db.expect_get_latest_job_by_type()
+        .with(eq(JobType::StateTransition))
+        .times(1)
+        .returning(|_| Ok(Some(completed_state_update_job("1"))));
+    settlement_client.expect_get_last_settled_block().times(1).returning(|| Ok(3));
+    db.expect_get_jobs_paginated().never();
+    db.expect_create_job().never();
+
+    let config = init_config(None, Some(db), None, None, None, Some(settlement_client), None).await;
+    config_force_init(config).await;
+
+    UpdateStateWorker {}.run_worker().await?;
+
+    Ok(())
+}
+
+/// Tests that the blocks to settle of the state update job are the blocks of its range.
+#[rstest]
+#[tokio::test]
+async fn test_update_state_worker_blocks_to_settle() -> Result<(), Box<dyn Error>> {
+    let mut db = MockDatabase::new();
+    let mut settlement_client = MockSettlementClient::new();
+    let mut queue = MockQueueProvider::new();
+    let mut job_handler = MockJob::new();
+
+    db.expect_get_latest_job_by_type().times(1).returning(|_| Ok(Some(completed_state_update_job("4"))));
+    settlement_client.expect_get_last_settled_block().times(1).returning(|| Ok(4));
+    db.expect_get_jobs_paginated().times(1).returning(|_, _, _| {
+        let jobs = get_job_by_mock_id_vector(JobType::ProofCreation, JobStatus::Completed, 3, 5);
+        Ok(JobPage { jobs, next_cursor: None })
+    });
+    db.expect_get_job_by_internal_id_and_type().times(1).returning(|_, _| Ok(None));
+    job_handler
+        .expect_create_job()
+        .withf(|_, internal_id, metadata| {
+            internal_id == "5-7"
+                && metadata.get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY).map(String::as_str) == Some("5,6,7")
+        })
+        .times(1)
+        .returning(|_, internal_id, metadata| {
+            let mut job = completed_state_update_job(&internal_id);
+            job.status = JobStatus::Created;
+            job.metadata = metadata;
+            Ok(job)
+        });
+    db.expect_create_job().times(1).returning(Ok);
+    queue.expect_send_message_to_queue().times(1).returning(|_, _, _, _| Ok(()));
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().times(1).returning(move |_| Arc::clone(&job_handler));
+
+    let config = init_config(None, Some(db), Some(queue), None, None, Some(settlement_client), None).await;
+    config_force_init(config).await;
+
+    UpdateStateWorker {}.run_worker().await?;
 
     Ok(())
 }
@@ -1,6 +1,6 @@
 use crate::database::MockDatabase;
 use crate::jobs::constants::JOB_METADATA_CAIRO_PIE_PATH_KEY;
-use crate::jobs::types::{ExternalId, JobItem, JobStatus, JobType};
+use crate::jobs::types::{ExternalId, JobCounters, JobItem, JobPriority, JobStatus, JobType};
 use crate::jobs::MockJob;
 use mockall::predicate::eq;
 use std::collections::HashMap;
@@ -15,6 +15,11 @@ pub fn get_job_item_mock_by_id(id: String, uuid: Uuid) -> JobItem {
         external_id: ExternalId::Number(0),
         metadata: HashMap::new(),
         version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
     }
 }
 
@@ -47,6 +52,11 @@ pub fn get_job_by_mock_id_vector(
             external_id: ExternalId::Number(0),
             metadata: get_hashmap(),
             version: 0,
+            created_at: 0,
+            updated_at: 0,
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
         })
     }
 
@@ -55,29 +65,34 @@ pub fn get_job_by_mock_id_vector(
 
 pub fn db_create_job_expectations_update_state_worker(
     db: &mut MockDatabase,
-    proof_creation_jobs: Vec<JobItem>,
+    internal_id: &'static str,
     mock_job: &mut MockJob,
 ) {
-    for job in proof_creation_jobs {
-        let internal_id = job.internal_id.clone();
-        let job_item = JobItem {
-            id: Uuid::new_v4(),
-            internal_id: internal_id.clone(),
-            job_type: JobType::StateTransition,
-            status: JobStatus::Created,
-            external_id: ExternalId::Number(0),
-            metadata: get_hashmap(),
-            version: 0,
-        };
-        let job_item_cloned = job_item.clone();
-
-        mock_job.expect_create_job().times(1).returning(move |_, _, _| Ok(job_item.clone()));
+    let job_item = JobItem {
+        id: Uuid::new_v4(),
+        internal_id: internal_id.to_string(),
+        job_type: JobType::StateTransition,
+        status: JobStatus::Created,
+        external_id: ExternalId::Number(0),
+        metadata: get_hashmap(),
+        version: 0,
+        created_at: 0,
+        updated_at: 0,
+        parent_ids: Vec::new(),
+        priority: JobPriority::Normal,
+        counters: JobCounters::default(),
+    };
 
-        db.expect_create_job()
-            .times(1)
-            .withf(move |item| item.internal_id == job.internal_id)
-            .returning(move |_| Ok(job_item_cloned.clone()));
-    }
+    db.expect_get_job_by_internal_id_and_type()
+        .times(1)
+        .withf(move |id, job_type| id == internal_id && job_type == &JobType::StateTransition)
+        .returning(|_, _| Ok(None));
+    mock_job
+        .expect_create_job()
+        .times(1)
+        .withf(move |_, id, _| id == internal_id)
+        .returning(move |_, _, _| Ok(job_item.clone()));
+    db.expect_create_job().times(1).withf(move |item| item.internal_id == internal_id).returning(Ok);
 }
 
 pub fn db_checks_proving_worker(id: i32, db: &mut MockDatabase, mock_job: &mut MockJob) {
@@ -91,6 +106,11 @@ pub fn db_checks_proving_worker(id: i32, db: &mut MockDatabase, mock_job: &mut M
             external_id: ExternalId::Number(0),
             metadata: get_hashmap(),
             version: 0,
+            created_at: 0,
+            updated_at: 0,
+            parent_ids: Vec::new(),
+            priority: JobPriority::Normal,
+            counters: JobCounters::default(),
         }
     }
 
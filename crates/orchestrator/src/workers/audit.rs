@@ -0,0 +1,43 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+
+use crate::config::config;
+use crate::jobs::audit_job::audit_metadata;
+use crate::jobs::backpressure::JobCreationBudget;
+use crate::jobs::dependencies::create_job_with_parents;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::workers::Worker;
+
+/// Creates an audit job for every completed state update job, checking the data settled on the
+/// base layer against the blocks. A divergence blocks the audit job and halts the workers.
+pub struct AuditWorker;
+
+#[async_trait]
+impl Worker for AuditWorker {
+    fn job_type(&self) -> Option<JobType> {
+        Some(JobType::DataAudit)
+    }
+
+    /// 1. Fetch all completed state update jobs that don't have an audit job
+    /// 2. Create an audit job for each of them, depending on it, auditing the blocks it settled,
+    ///    until the pending audit jobs reach their cap
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let settled_jobs = config
+            .database()
+            .get_jobs_without_successor(JobType::StateTransition, JobStatus::Completed, JobType::DataAudit)
+            .await?;
+
+        let mut budget = JobCreationBudget::for_job_type(JobType::DataAudit).await?;
+        for job in settled_jobs {
+            if !budget.try_take() {
+                break;
+            }
+            let metadata = audit_metadata(&job)?;
+            create_job_with_parents(JobType::DataAudit, job.internal_id.clone(), metadata, &[job]).await?;
+        }
+
+        Ok(())
+    }
+}
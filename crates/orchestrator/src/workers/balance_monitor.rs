@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use prometheus::{register_gauge_vec, GaugeVec};
+use utils::env_utils::get_env_car_optional_or_panic;
+
+use crate::alerts::{dispatch_alert, Alert, AlertKind, AlertSink, ALERT_SINKS};
+use crate::config::config;
+use crate::workers::{HaltPolicy, Worker};
+
+/// Balance of the settlement operator account under which an alert is raised, in the smallest
+/// unit of the settlement layer fee token (wei for Ethereum, fri or wei for Starknet)
+pub const ENV_SETTLEMENT_BALANCE_ALERT_THRESHOLD: &str = "SETTLEMENT_BALANCE_ALERT_THRESHOLD";
+/// Balance of the DA operator account under which an alert is raised, in the smallest unit of
+/// the DA layer token
+pub const ENV_DA_BALANCE_ALERT_THRESHOLD: &str = "DA_BALANCE_ALERT_THRESHOLD";
+
+const SETTLEMENT_ACCOUNT: &str = "settlement";
+const DA_ACCOUNT: &str = "da";
+
+lazy_static! {
+    /// Balance of the operator accounts, in the smallest unit of their token
+    pub static ref OPERATOR_BALANCE: GaugeVec = register_gauge_vec!(
+        "operator_balance",
+        "Balance of the operator accounts, in the smallest unit of their token",
+        &["account"]
+    )
+    .unwrap();
+}
+
+/// Monitors the balances of the operator accounts paying for the settlement and DA transactions.
+/// The balances are exported as gauges and an alert is raised when one drops below its threshold,
+/// before the submissions start failing for lack of funds.
+pub struct BalanceMonitorWorker {
+    /// Accounts already below their threshold, the alert is raised once until they are funded
+    low_balance_accounts: Mutex<HashSet<&'static str>>,
+    /// Sinks the low balance alerts are sent to, the [`ALERT_SINKS`] of the environment by default
+    alert_sinks: &'static [Box<dyn AlertSink>],
+}
+
+impl Default for BalanceMonitorWorker {
+    fn default() -> Self {
+        Self::with_alert_sinks(&ALERT_SINKS)
+    }
+}
+
+#[async_trait]
+impl Worker for BalanceMonitorWorker {
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+
+        let settlement_balance = config.settlement_client().get_operator_balance().await?;
+        self.check_balance(SETTLEMENT_ACCOUNT, settlement_balance, ENV_SETTLEMENT_BALANCE_ALERT_THRESHOLD).await?;
+
+        if let Some(da_balance) = config.da_client().get_operator_balance().await? {
+            self.check_balance(DA_ACCOUNT, da_balance, ENV_DA_BALANCE_ALERT_THRESHOLD).await?;
+        }
+
+        Ok(())
+    }
+
+    /// The balances have to be watched even more when the jobs are failing
+    async fn is_worker_enabled(&self, _halt_policy: HaltPolicy) -> Result<bool, Box<dyn Error>> {
+        Ok(true)
+    }
+}
+
+impl BalanceMonitorWorker {
+    pub fn with_alert_sinks(alert_sinks: &'static [Box<dyn AlertSink>]) -> Self {
+        Self { low_balance_accounts: Mutex::default(), alert_sinks }
+    }
+
+    /// Exports the balance of the account and raises an alert when it drops below the threshold
+    /// set in `threshold_env`. No alert is raised when the threshold isn't set.
+    async fn check_balance(
+        &self,
+        account: &'static str,
+        balance: u128,
+        threshold_env: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        OPERATOR_BALANCE.with_label_values(&[account]).set(balance as f64);
+
+        let Some(threshold) = get_env_car_optional_or_panic(threshold_env) else {
+            return Ok(());
+        };
+        let threshold: u128 = threshold.parse()?;
+
+        let newly_low = {
+            let mut low_balance_accounts = self.low_balance_accounts.lock().expect("Failed to lock low balances");
+            if balance < threshold {
+                low_balance_accounts.insert(account)
+            } else {
+                if low_balance_accounts.remove(account) {
+                    tracing::info!("Balance of the {} operator account is back above the threshold", account);
+                }
+                false
+            }
+        };
+        if newly_low {
+            let message = format!(
+                "Balance of the {} operator account is {}, below the threshold of {}",
+                account, balance, threshold
+            );
+            dispatch_alert(&Alert::new(AlertKind::LowBalance, &message), self.alert_sinks).await;
+        }
+        Ok(())
+    }
+}
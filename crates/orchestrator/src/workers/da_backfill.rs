@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use async_trait::async_trait;
+use utils::env_utils::get_env_var_or_default;
+
+use crate::config::config;
+use crate::jobs::create_job;
+use crate::jobs::types::JobType;
+use crate::workers::state::WorkerState;
+use crate::workers::Worker;
+
+/// Name of the worker state storing the last block visited by the backfill
+pub const DA_BACKFILL_WORKER: &str = "da_backfill";
+/// First block to backfill when no block has been visited yet
+pub const ENV_DA_BACKFILL_START_BLOCK: &str = "DA_BACKFILL_START_BLOCK";
+/// Maximum number of DA jobs created by a single run of the worker
+pub const ENV_DA_BACKFILL_MAX_JOBS_PER_RUN: &str = "DA_BACKFILL_MAX_JOBS_PER_RUN";
+
+/// Retro-publishes the DA of blocks which were settled without any DA job, for chains
+/// which settled historically without public DA.
+pub struct DaBackfillWorker;
+
+#[async_trait]
+impl Worker for DaBackfillWorker {
+    fn job_type(&self) -> Option<JobType> {
+        Some(JobType::DataSubmission)
+    }
+
+    /// 1. Fetch the last settled block from the settlement layer
+    /// 2. Resume from the last visited block or from `DA_BACKFILL_START_BLOCK`
+    /// 3. Create DA jobs for the settled blocks that don't have one, at most
+    ///    `DA_BACKFILL_MAX_JOBS_PER_RUN` per run, storing the last visited block as we go
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let max_jobs_per_run: u64 = get_env_var_or_default(ENV_DA_BACKFILL_MAX_JOBS_PER_RUN, "10").parse()?;
+
+        let worker_state = WorkerState::new(DA_BACKFILL_WORKER);
+
+        let last_settled_block = config.settlement_client().get_last_settled_block().await?;
+        let start_block = match worker_state.last_processed_block().await? {
+            Some(last_visited_block) => last_visited_block + 1,
+            None => get_env_var_or_default(ENV_DA_BACKFILL_START_BLOCK, "0").parse()?,
+        };
+
+        let mut jobs_created = 0;
+        for block_number in start_block..last_settled_block + 1 {
+            if jobs_created >= max_jobs_per_run {
+                break;
+            }
+
+            // blocks submitted as part of a batch are covered by the range of its DA job
+            let da_job = config
+                .database()
+                .get_jobs_by_block(block_number)
+                .await?
+                .into_iter()
+                .find(|job| job.job_type == JobType::DataSubmission);
+            if da_job.is_none() {
+                create_job(JobType::DataSubmission, block_number.to_string(), HashMap::new()).await?;
+                jobs_created += 1;
+            }
+
+            worker_state.set_last_processed_block(block_number).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,21 +1,55 @@
-use crate::config::config;
+use crate::config::{config, Config};
+use crate::jobs::backpressure::JobCreationBudget;
+use crate::jobs::block_range::{BlockBatchSettings, BlockRange, BLOCK_BATCH_SETTINGS_NAME};
+use crate::jobs::constants::{
+    JOB_METADATA_DA_BLOCKS_TO_SUBMIT_KEY, JOB_METADATA_DA_EMPTY_BLOCKS_KEY, JOB_METADATA_DA_EMPTY_BLOCK_DECISION_KEY,
+};
 use crate::jobs::create_job;
+use crate::jobs::da_job::empty_blocks::{
+    is_empty_state_diff, EmptyBlockPolicy, EMPTY_BLOCK_DECISION_FOLDED, EMPTY_BLOCK_DECISION_SKIPPED,
+    ENV_DA_EMPTY_BLOCK_POLICY,
+};
+use crate::jobs::da_job::{blob_count, encoded_state_diff_len, get_block_numbers_to_submit};
 use crate::jobs::types::{JobStatus, JobType};
+use crate::workers::state::WorkerState;
 use crate::workers::Worker;
 use async_trait::async_trait;
-use std::collections::HashMap;
+use color_eyre::eyre::eyre;
+use starknet::core::types::{BlockId, MaybePendingStateUpdate, StateDiff};
+use starknet::providers::Provider;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use utils::env_utils::get_env_var_or_default;
+use utils::settings::default::DefaultSettingsProvider;
+use utils::settings::SettingsProvider;
+
+/// Name of the worker state storing the last block covered by a DA job
+pub const DATA_SUBMISSION_WORKER: &str = "data_submission";
 
 pub struct DataSubmissionWorker;
 
 #[async_trait]
 impl Worker for DataSubmissionWorker {
+    fn job_type(&self) -> Option<JobType> {
+        Some(JobType::DataSubmission)
+    }
+
     // 0. All ids are assumed to be block numbers.
     // 1. Fetch the latest completed Proving job.
-    // 2. Fetch the latest DA job creation.
-    // 3. Create jobs from after the lastest DA job already created till latest completed proving job.
+    // 2. Fetch the last block covered by a DA job, from the worker state or else from the latest
+    //    DA job.
+    // 3. Create jobs from after the lastest DA job already created till latest completed proving job,
+    //    each job covers up to `DA_BATCH_SIZE` consecutive blocks whose encoded state diffs fit in
+    //    the blobs of a single DA transaction, its internal id being their range.
+    // 4. Unless `DA_EMPTY_BLOCK_POLICY` is `process`, the blocks with an empty state diff are either
+    //    skipped or folded into the next batch, the decision is recorded in the job metadata.
+    // 5. Stop once the pending DA jobs reach their cap, the next blocks are left to a later run.
     async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
         let config = config().await;
+        let worker_state = WorkerState::new(DATA_SUBMISSION_WORKER);
+        let settings: BlockBatchSettings = DefaultSettingsProvider {}.get_settings(BLOCK_BATCH_SETTINGS_NAME)?;
+        let empty_block_policy: EmptyBlockPolicy =
+            get_env_var_or_default(ENV_DA_EMPTY_BLOCK_POLICY, "process").parse()?;
 
         // provides latest completed proof creation job id
         let latest_proven_job_id = config
@@ -26,23 +60,152 @@ impl Worker for DataSubmissionWorker {
             .map(|item| item.internal_id)
             .unwrap_or("0".to_string());
 
-        // provides latest triggered data submission job id
-        let latest_data_submission_job_id = config
-            .database()
-            .get_latest_job_by_type(JobType::DataSubmission)
-            .await
-            .unwrap()
-            .map(|item| item.internal_id)
-            .unwrap_or("0".to_string());
-
-        let latest_data_submission_id: u64 = latest_data_submission_job_id.parse()?;
+        // provides the last block covered by the latest triggered data submission job
+        let latest_data_submission_id = match worker_state.last_processed_block().await? {
+            Some(block_number) => block_number,
+            None => match config.database().get_latest_job_by_type(JobType::DataSubmission).await? {
+                Some(job) => {
+                    *get_block_numbers_to_submit(&job)?.last().expect("DA job should cover at least one block")
+                }
+                None => 0,
+            },
+        };
         let latest_proven_id: u64 = latest_proven_job_id.parse()?;
 
+        let mut batcher = DaBatcher::new(
+            settings.data_submission,
+            empty_block_policy == EmptyBlockPolicy::Fold,
+            config.da_client().max_bytes_per_blob().await,
+            config.da_client().max_blob_per_txn().await,
+        );
+        let mut empty_blocks = HashSet::new();
+
         // creating data submission jobs for latest blocks that don't have existing data submission jobs yet.
-        for new_job_id in latest_data_submission_id + 1..latest_proven_id + 1 {
-            create_job(JobType::DataSubmission, new_job_id.to_string(), HashMap::new()).await?;
+        let mut budget = JobCreationBudget::for_job_type(JobType::DataSubmission).await?;
+        'blocks: for block_no in latest_data_submission_id + 1..=latest_proven_id {
+            let state_diff = get_state_diff(config, block_no).await?;
+            let empty = empty_block_policy != EmptyBlockPolicy::Process && is_empty_state_diff(&state_diff);
+            if empty {
+                empty_blocks.insert(block_no);
+            }
+            let block = DaBlock { number: block_no, encoded_len: encoded_state_diff_len(&state_diff), empty };
+            for blocks in batcher.push(block) {
+                if !budget.try_take() {
+                    break 'blocks;
+                }
+                let metadata = da_job_metadata(&blocks, &empty_blocks, empty_block_policy);
+                let last_block = *blocks.last().expect("Batches are never empty");
+                create_job(JobType::DataSubmission, BlockRange::new(blocks[0], last_block)?.to_string(), metadata)
+                    .await?;
+                worker_state.set_last_processed_block(last_block).await?;
+            }
         }
 
         Ok(())
     }
 }
+
+/// Metadata of the DA job of the blocks: the blocks, the empty ones among them and what was
+/// decided for them
+fn da_job_metadata(
+    blocks: &[u64],
+    empty_blocks: &HashSet<u64>,
+    empty_block_policy: EmptyBlockPolicy,
+) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    metadata.insert(JOB_METADATA_DA_BLOCKS_TO_SUBMIT_KEY.to_string(), join_block_numbers(blocks));
+
+    let batch_empty_blocks: Vec<u64> =
+        blocks.iter().filter(|block_no| empty_blocks.contains(block_no)).copied().collect();
+    if !batch_empty_blocks.is_empty() {
+        metadata.insert(JOB_METADATA_DA_EMPTY_BLOCKS_KEY.to_string(), join_block_numbers(&batch_empty_blocks));
+        let decision = match empty_block_policy {
+            EmptyBlockPolicy::Skip if batch_empty_blocks.len() == blocks.len() => Some(EMPTY_BLOCK_DECISION_SKIPPED),
+            EmptyBlockPolicy::Fold => Some(EMPTY_BLOCK_DECISION_FOLDED),
+            _ => None,
+        };
+        if let Some(decision) = decision {
+            metadata.insert(JOB_METADATA_DA_EMPTY_BLOCK_DECISION_KEY.to_string(), decision.to_string());
+        }
+    }
+    metadata
+}
+
+/// Returns the state diff of the block, which has to be past the pending state.
+async fn get_state_diff(config: &Config, block_no: u64) -> color_eyre::Result<StateDiff> {
+    match config.starknet_client().get_state_update(BlockId::Number(block_no)).await? {
+        MaybePendingStateUpdate::Update(state_update) => Ok(state_update.state_diff),
+        MaybePendingStateUpdate::PendingUpdate(_) => {
+            Err(eyre!("Cannot check the state diff of block {} as it's still in pending state", block_no))
+        }
+    }
+}
+
+fn join_block_numbers(blocks: &[u64]) -> String {
+    blocks.iter().map(|block_no| block_no.to_string()).collect::<Vec<String>>().join(",")
+}
+
+/// Block to be packed in a DA job
+#[derive(Debug, Clone, Copy)]
+pub struct DaBlock {
+    pub number: u64,
+    /// Number of field elements its state diff is encoded into
+    pub encoded_len: usize,
+    /// Whether its state diff is empty, only set when the empty blocks aren't processed as is
+    pub empty: bool,
+}
+
+/// Packs consecutive blocks into the batches of the DA jobs. A batch is closed once it has
+/// `max_blocks` blocks, the empty blocks not counting when they are folded, or before the block
+/// whose state diff wouldn't fit in the blobs of a single DA transaction. The batch being filled
+/// is left to be completed once more blocks are proven.
+pub struct DaBatcher {
+    max_blocks: usize,
+    fold_empty_blocks: bool,
+    blob_size: u64,
+    max_blobs: u64,
+    batch: Vec<u64>,
+    batch_len: usize,
+    counted_blocks: usize,
+}
+
+impl DaBatcher {
+    pub fn new(max_blocks: u64, fold_empty_blocks: bool, blob_size: u64, max_blobs: u64) -> Self {
+        Self {
+            max_blocks: max_blocks.max(1) as usize,
+            fold_empty_blocks,
+            blob_size,
+            max_blobs,
+            batch: Vec::new(),
+            batch_len: 0,
+            counted_blocks: 0,
+        }
+    }
+
+    /// Adds the next block to the batch being filled, returns the batches this closed
+    pub fn push(&mut self, block: DaBlock) -> Vec<Vec<u64>> {
+        let mut batches = Vec::new();
+        if !self.batch.is_empty() && blob_count(self.blob_size, self.batch_len + block.encoded_len) > self.max_blobs {
+            batches.push(self.take_batch());
+        }
+        if blob_count(self.blob_size, block.encoded_len) > self.max_blobs {
+            tracing::warn!(block = block.number, "State diff of the block doesn't fit in a single DA transaction");
+        }
+
+        self.batch.push(block.number);
+        self.batch_len += block.encoded_len;
+        if !(self.fold_empty_blocks && block.empty) {
+            self.counted_blocks += 1;
+        }
+        if self.counted_blocks == self.max_blocks {
+            batches.push(self.take_batch());
+        }
+        batches
+    }
+
+    fn take_batch(&mut self) -> Vec<u64> {
+        self.batch_len = 0;
+        self.counted_blocks = 0;
+        std::mem::take(&mut self.batch)
+    }
+}
@@ -0,0 +1,44 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+
+use crate::config::config;
+use crate::database::types::JobFilter;
+use crate::jobs::dependencies::release_job_if_ready;
+use crate::jobs::types::JobStatus;
+use crate::workers::Worker;
+
+/// Number of jobs waiting for their parents fetched at once
+const DEPENDENCY_BATCH_SIZE: i64 = 100;
+
+/// Releases the jobs waiting for their parents whose parents are all completed. The jobs are
+/// released when their last parent completes, this catches the ones which weren't, e.g. when
+/// the orchestrator stopped in between.
+pub struct DependencyWorker;
+
+#[async_trait]
+impl Worker for DependencyWorker {
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let filter = JobFilter { status: Some(JobStatus::PendingDependencies), ..Default::default() };
+        let mut cursor = None;
+        let mut released = 0;
+        loop {
+            let page = config.database().get_jobs_paginated(filter.clone(), cursor, DEPENDENCY_BATCH_SIZE).await?;
+            for job in page.jobs {
+                if release_job_if_ready(config.as_ref(), job).await? {
+                    released += 1;
+                }
+            }
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+
+        if released > 0 {
+            tracing::info!("Released {} jobs whose parents were completed", released);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+use std::error::Error;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use utils::env_utils::get_env_var_or_default;
+use uuid::Uuid;
+
+use crate::config::config;
+use crate::database::types::{now_secs, JobFilter};
+use crate::jobs::types::{JobItem, JobStatus, JobType};
+use crate::workers::Worker;
+
+/// Age in seconds, from their creation, after which the completed jobs are archived
+pub const ENV_JOB_ARCHIVE_AFTER_SECS: &str = "JOB_ARCHIVE_AFTER_SECS";
+/// Number of jobs archived at once
+pub const ENV_JOB_ARCHIVE_BATCH_SIZE: &str = "JOB_ARCHIVE_BATCH_SIZE";
+/// Whether the archived jobs are exported to the data storage as well, as JSON lines
+pub const ENV_JOB_ARCHIVE_EXPORT: &str = "JOB_ARCHIVE_EXPORT";
+/// Prefix of the keys of the data storage the archived jobs are exported under
+pub const JOB_ARCHIVE_STORAGE_PREFIX: &str = "archive/jobs";
+
+/// Moves the completed jobs older than `JOB_ARCHIVE_AFTER_SECS` out of the jobs the
+/// orchestrator works on, into the archive of the database, so that they don't accumulate.
+///
+/// The latest job of each type is kept, the workers create the next jobs from it. Like the other
+/// workers, it stops while jobs are failing: the jobs waiting on a completed job to be created
+/// would miss it once archived.
+pub struct JobArchivalWorker;
+
+#[async_trait]
+impl Worker for JobArchivalWorker {
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let archive_after: u64 = get_env_var_or_default(ENV_JOB_ARCHIVE_AFTER_SECS, "2592000").parse()?;
+        let batch_size: i64 = get_env_var_or_default(ENV_JOB_ARCHIVE_BATCH_SIZE, "100").parse()?;
+        let export = get_env_var_or_default(ENV_JOB_ARCHIVE_EXPORT, "false") == "true";
+
+        let Some(created_before) = now_secs().checked_sub(archive_after) else {
+            return Ok(());
+        };
+
+        let mut latest_jobs = HashSet::new();
+        for job_type in JobType::ALL {
+            if let Some(job) = config.database().get_latest_job_by_type(job_type).await? {
+                latest_jobs.insert(job.id);
+            }
+        }
+
+        let filter = JobFilter {
+            status: Some(JobStatus::Completed),
+            created_before: Some(created_before),
+            ..Default::default()
+        };
+        let mut cursor = None;
+        let mut archived = 0;
+        loop {
+            let page = config.database().get_jobs_paginated(filter.clone(), cursor, batch_size).await?;
+            let jobs: Vec<JobItem> = page.jobs.into_iter().filter(|job| !latest_jobs.contains(&job.id)).collect();
+            if !jobs.is_empty() {
+                if export {
+                    export_jobs(&jobs).await?;
+                }
+                let ids: Vec<Uuid> = jobs.iter().map(|job| job.id).collect();
+                archived += config.database().archive_jobs(&ids).await?;
+            }
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+
+        if archived > 0 {
+            tracing::info!("Archived {} completed jobs created before {}", archived, created_before);
+        }
+        Ok(())
+    }
+}
+
+/// Stores the jobs in the data storage, one JSON object per line, under a key derived from the
+/// first job so that exporting the same batch again overwrites it
+async fn export_jobs(jobs: &[JobItem]) -> Result<(), Box<dyn Error>> {
+    let config = config().await;
+    let mut lines = String::new();
+    for job in jobs {
+        lines.push_str(&serde_json::to_string(job)?);
+        lines.push('\n');
+    }
+    let key = format!("{}/{}.jsonl", JOB_ARCHIVE_STORAGE_PREFIX, jobs[0].id);
+    config.storage().put_data(Bytes::from(lines), &key).await?;
+    Ok(())
+}
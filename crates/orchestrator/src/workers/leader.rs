@@ -0,0 +1,98 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use prometheus::{register_int_gauge, IntGauge};
+use utils::env_utils::get_env_var_or_default;
+
+use crate::config::config;
+use crate::database::types::now_secs;
+use crate::jobs::worker_id;
+
+/// Whether the instances elect a leader running the workers, only needed with several instances
+pub const ENV_LEADER_ELECTION: &str = "LEADER_ELECTION";
+/// Seconds the lease of the leader lasts, renewed every third of it
+pub const ENV_LEADER_LEASE_SECS: &str = "LEADER_LEASE_SECS";
+/// Name of the lease held by the instance running the workers
+pub const WORKERS_LEASE: &str = "workers";
+
+lazy_static! {
+    /// Election of the instance running the workers
+    pub static ref LEADER_ELECTION: LeaderElection = LeaderElection::new(
+        get_env_var_or_default(ENV_LEADER_ELECTION, "false") == "true",
+        worker_id(),
+        Duration::from_secs(
+            get_env_var_or_default(ENV_LEADER_LEASE_SECS, "30").parse().expect("Failed to parse LEADER_LEASE_SECS")
+        ),
+    );
+    /// 1 while this instance is the leader running the workers, 0 otherwise
+    pub static ref IS_LEADER: IntGauge =
+        register_int_gauge!("is_leader", "1 while this instance is the leader running the workers").unwrap();
+}
+
+/// Elects the single instance running the workers when several orchestrators share the database.
+/// The workers scan the database and create jobs, two instances running them would race on the
+/// same blocks, while the queue consumers scale out. The leader holds a lease in the database
+/// which it renews, another instance takes over once the lease expires.
+///
+/// The leader stops considering itself as such when its lease would expire without a renewal,
+/// the clocks of the instances are assumed to be in sync within a few seconds.
+pub struct LeaderElection {
+    enabled: bool,
+    holder: String,
+    lease: Duration,
+    /// Unix time in seconds until which this instance holds the lease, 0 when it doesn't
+    held_until: AtomicU64,
+}
+
+impl LeaderElection {
+    pub fn new(enabled: bool, holder: String, lease: Duration) -> Self {
+        assert!(lease.as_secs() >= 3, "LEADER_LEASE_SECS must be at least 3");
+        Self { enabled, holder, lease, held_until: AtomicU64::new(0) }
+    }
+
+    /// Whether this instance runs the workers, always when the election is disabled
+    pub fn is_leader(&self) -> bool {
+        !self.enabled || now_secs() < self.held_until.load(Ordering::SeqCst)
+    }
+
+    /// Takes or renews the lease, returns whether this instance is the leader
+    pub async fn renew(&self) -> Result<bool> {
+        // the lease is considered held from before the request, it can't outlive the stored one
+        let requested_at = now_secs();
+        let held = config().await.database().acquire_lease(WORKERS_LEASE, &self.holder, self.lease).await;
+        let held_until = match held {
+            Ok(true) => requested_at + self.lease.as_secs(),
+            _ => 0,
+        };
+        let was_leader = self.is_leader();
+        self.held_until.store(held_until, Ordering::SeqCst);
+        IS_LEADER.set(i64::from(self.is_leader()));
+        if was_leader != self.is_leader() {
+            tracing::info!("Instance {} is the leader running the workers: {}", self.holder, self.is_leader());
+        }
+        held
+    }
+
+    /// Takes part in the election, the lease is requested once before returning and then kept
+    /// renewed in the background, every third of its duration
+    pub async fn start(&'static self) {
+        if !self.enabled {
+            return;
+        }
+        renew_or_log(self).await;
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(self.lease / 3).await;
+                renew_or_log(self).await;
+            }
+        });
+    }
+}
+
+async fn renew_or_log(election: &LeaderElection) {
+    if let Err(e) = election.renew().await {
+        tracing::error!("Failed to renew the lease of the leader: {:?}", e);
+    }
+}
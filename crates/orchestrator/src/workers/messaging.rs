@@ -0,0 +1,67 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use utils::env_utils::get_env_var_or_default;
+
+use crate::config::config;
+use crate::jobs::create_job;
+use crate::jobs::message_job::{message_internal_id, message_metadata};
+use crate::jobs::types::JobType;
+use crate::workers::state::WorkerState;
+use crate::workers::Worker;
+
+/// Name of the worker state storing the last settlement layer block scanned for messages
+pub const MESSAGING_WORKER: &str = "messaging";
+/// First settlement layer block to scan when no block has been scanned yet, the latest block by
+/// default
+pub const ENV_MESSAGING_START_BLOCK: &str = "MESSAGING_START_BLOCK";
+/// Maximum number of settlement layer blocks scanned by a single run of the worker
+pub const ENV_MESSAGING_MAX_BLOCKS: &str = "MESSAGING_MAX_BLOCKS";
+
+/// Creates a message job for every message sent to the L2 through the core contract, so that the
+/// deposits are tracked until they are consumed without running scripts on the side.
+pub struct MessagingWorker;
+
+#[async_trait]
+impl Worker for MessagingWorker {
+    fn job_type(&self) -> Option<JobType> {
+        Some(JobType::MessageProcessing)
+    }
+
+    /// 1. Resume from the last scanned block or from `MESSAGING_START_BLOCK`
+    /// 2. Fetch the messages sent to the L2 in at most `MESSAGING_MAX_BLOCKS` blocks
+    /// 3. Create a message job for each of the messages which doesn't have one yet, storing the
+    ///    last scanned block once they are all created
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let max_blocks: u64 = get_env_var_or_default(ENV_MESSAGING_MAX_BLOCKS, "1000").parse::<u64>()?.max(1);
+
+        let worker_state = WorkerState::new(MESSAGING_WORKER);
+
+        let latest_block = config.settlement_client().get_latest_block_number().await?;
+        let from_block = match worker_state.last_processed_block().await? {
+            Some(last_scanned_block) => last_scanned_block + 1,
+            None => match get_env_var_or_default(ENV_MESSAGING_START_BLOCK, "").as_str() {
+                "" => latest_block,
+                start_block => start_block.parse()?,
+            },
+        };
+        if from_block > latest_block {
+            return Ok(());
+        }
+        let to_block = latest_block.min(from_block + max_blocks - 1);
+
+        for message in config.settlement_client().get_messages_to_l2(from_block, to_block).await? {
+            let internal_id = message_internal_id(&message);
+            let message_job =
+                config.database().get_job_by_internal_id_and_type(&internal_id, &JobType::MessageProcessing).await?;
+            if message_job.is_none() {
+                create_job(JobType::MessageProcessing, internal_id, message_metadata(&message)).await?;
+            }
+        }
+
+        worker_state.set_last_processed_block(to_block).await?;
+
+        Ok(())
+    }
+}
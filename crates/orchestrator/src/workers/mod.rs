@@ -1,17 +1,72 @@
+use crate::jobs::types::JobType;
+use crate::workers::leader::LEADER_ELECTION;
 use crate::{config::config, jobs::types::JobStatus};
 use async_trait::async_trait;
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::str::FromStr;
 
+pub mod audit;
+pub mod balance_monitor;
+pub mod da_backfill;
 pub mod data_submission_worker;
+pub mod dependencies;
+pub mod job_archival;
+pub mod leader;
+pub mod messaging;
+pub mod orphan_tx_watchdog;
+pub mod pipeline_monitor;
+pub mod proof_aggregation;
 pub mod proof_registration;
 pub mod proving;
+pub mod reorg_monitor;
+pub mod run_history;
+pub mod schedule;
+pub mod scheduler;
 pub mod snos;
+pub mod state;
+pub mod storage_gc;
+pub mod stuck_jobs;
 pub mod update_state;
 
+/// Failed jobs which halt a worker, see [`Worker::is_worker_enabled`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HaltPolicy {
+    /// A failed job of any type halts the worker
+    #[default]
+    Global,
+    /// Only a failed job of the type the worker creates, or of one of its parent types, halts the
+    /// worker. The workers which don't create jobs are halted by any failed job.
+    JobType,
+}
+
+impl FromStr for HaltPolicy {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> color_eyre::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "global" => Ok(HaltPolicy::Global),
+            "job_type" => Ok(HaltPolicy::JobType),
+            _ => Err(eyre!("Unknown halt policy {}", s)),
+        }
+    }
+}
+
+/// Statuses of the failed jobs, which wait for a manual intervention
+pub const HALTING_JOB_STATUSES: [JobStatus; 4] =
+    [JobStatus::VerificationFailed, JobStatus::VerificationTimeout, JobStatus::Blocked, JobStatus::Failed];
+
 #[async_trait]
 pub trait Worker: Send + Sync {
-    async fn run_worker_if_enabled(&self) -> Result<(), Box<dyn Error>> {
-        if !self.is_worker_enabled().await? {
+    async fn run_worker_if_enabled(&self, halt_policy: HaltPolicy) -> Result<(), Box<dyn Error>> {
+        // with several instances, only the leader scans the database and creates jobs
+        if !LEADER_ELECTION.is_leader() {
+            run_history::skip_run();
+            return Ok(());
+        }
+        if !self.is_worker_enabled(halt_policy).await? {
+            run_history::halt_run();
             return Ok(());
         }
         self.run_worker().await
@@ -19,6 +74,11 @@ pub trait Worker: Send + Sync {
 
     async fn run_worker(&self) -> Result<(), Box<dyn Error>>;
 
+    /// Type of the jobs the worker creates, `None` for the workers which don't create jobs
+    fn job_type(&self) -> Option<JobType> {
+        None
+    }
+
     // Assumption
     // If say a job for block X fails, we don't want the worker to respawn another job for the same block
     // we will resolve the existing failed job first.
@@ -27,17 +87,23 @@ pub trait Worker: Send + Sync {
     // as soon as it fails we currently halt any more execution and wait for manual intervention.
 
     // Checks if any of the jobs have failed
-    // Failure : JobStatus::VerificationFailed, JobStatus::VerificationTimeout, JobStatus::Failed
-    // Halts any new job creation till all the count of failed jobs is not Zero.
-    async fn is_worker_enabled(&self) -> Result<bool, Box<dyn Error>> {
+    // Failure : JobStatus::VerificationFailed, JobStatus::VerificationTimeout, JobStatus::Blocked,
+    // JobStatus::Failed
+    // Halts any new job creation till all the count of failed jobs is not Zero. With the job type
+    // halt policy, only the failed jobs of the type of the worker and of its parent types count.
+    async fn is_worker_enabled(&self, halt_policy: HaltPolicy) -> Result<bool, Box<dyn Error>> {
         let config = config().await;
 
-        let failed_jobs = config
-            .database()
-            .get_jobs_by_statuses(vec![JobStatus::VerificationFailed, JobStatus::VerificationTimeout], Some(1))
-            .await?;
+        let stats = config.database().get_job_stats().await?;
+        let failed_jobs = match (halt_policy, self.job_type()) {
+            (HaltPolicy::JobType, Some(job_type)) => std::iter::once(&job_type)
+                .chain(job_type.parent_job_types())
+                .map(|job_type| stats.count_of_type_in_statuses(job_type, &HALTING_JOB_STATUSES))
+                .sum(),
+            _ => stats.count_in_statuses(&HALTING_JOB_STATUSES),
+        };
 
-        if !failed_jobs.is_empty() {
+        if failed_jobs > 0 {
             return Ok(false);
         }
 
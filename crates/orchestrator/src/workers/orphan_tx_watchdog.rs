@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use std::error::Error;
+
+use async_trait::async_trait;
+use utils::env_utils::get_env_var_or_default;
+
+use crate::alerts::{send_alert, AlertKind};
+use crate::config::config;
+use crate::database::types::{AuditEvent, AuditEventKind};
+use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX;
+use crate::jobs::types::{JobItem, JobType};
+use crate::workers::state::WorkerState;
+use crate::workers::Worker;
+
+/// Name of the worker state storing the last settlement layer block scanned by the watchdog
+pub const ORPHAN_TX_WATCHDOG_WORKER: &str = "orphan_tx_watchdog";
+/// Maximum number of settlement layer blocks scanned by a single run of the worker
+pub const ENV_ORPHAN_TX_WATCHDOG_MAX_BLOCKS: &str = "ORPHAN_TX_WATCHDOG_MAX_BLOCKS";
+/// Number of jobs of each type read at once when looking for the jobs referencing the transactions
+pub const ENV_ORPHAN_TX_WATCHDOG_JOBS_PAGE_SIZE: &str = "ORPHAN_TX_WATCHDOG_JOBS_PAGE_SIZE";
+
+/// Flags the transactions sent by the operator account which don't correspond to any known
+/// job or attempt, as they may be the sign of a key compromise or of a manual interference.
+/// Blobs are published by the settlement client when Ethereum is used for DA, so scanning
+/// the settlement layer covers the DA transactions as well.
+pub struct OrphanTxWatchdogWorker;
+
+#[async_trait]
+impl Worker for OrphanTxWatchdogWorker {
+    /// 1. Fetch the operator transactions sent since the last scanned block
+    /// 2. Read the jobs from the most recent one, page by page, until each transaction is matched
+    ///    to a job. The recent jobs normally reference all of them, all the jobs are only read
+    ///    when a transaction is orphaned.
+    /// 3. Alert on and record in the audit log every operator transaction that isn't referenced
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let max_blocks: u64 = get_env_var_or_default(ENV_ORPHAN_TX_WATCHDOG_MAX_BLOCKS, "100").parse::<u64>()?.max(1);
+        let page_size: i64 =
+            get_env_var_or_default(ENV_ORPHAN_TX_WATCHDOG_JOBS_PAGE_SIZE, "100").parse::<i64>()?.max(1);
+
+        let worker_state = WorkerState::new(ORPHAN_TX_WATCHDOG_WORKER);
+
+        let latest_block = config.settlement_client().get_latest_block_number().await?;
+        let from_block = match worker_state.last_processed_block().await? {
+            Some(last_scanned_block) => last_scanned_block + 1,
+            None => latest_block.saturating_sub(max_blocks - 1),
+        };
+        if from_block > latest_block {
+            return Ok(());
+        }
+        let to_block = latest_block.min(from_block + max_blocks - 1);
+
+        let operator_txs = config.settlement_client().get_operator_transactions(from_block, to_block).await?;
+        let mut unknown_txs: HashSet<String> = operator_txs.iter().map(|tx_hash| normalize_tx_hash(tx_hash)).collect();
+        for job_type in [JobType::DataSubmission, JobType::ProofRegistration, JobType::StateTransition] {
+            let mut offset = 0;
+            while !unknown_txs.is_empty() {
+                let jobs = config.database().get_latest_jobs_by_type(job_type, offset, page_size).await?;
+                for tx_hash in jobs.iter().flat_map(job_tx_hashes) {
+                    unknown_txs.remove(&normalize_tx_hash(tx_hash));
+                }
+                if (jobs.len() as i64) < page_size {
+                    break;
+                }
+                offset += jobs.len() as u64;
+            }
+        }
+
+        for tx_hash in operator_txs.iter().filter(|tx_hash| unknown_txs.contains(&normalize_tx_hash(tx_hash))) {
+            let details = format!(
+                "Transaction {} sent by the operator account in blocks {}-{} doesn't match any job",
+                tx_hash, from_block, to_block
+            );
+            send_alert(AlertKind::OrphanTransaction, &details).await;
+            config.database().record_audit_event(AuditEvent::new(AuditEventKind::OrphanTransaction, details)).await?;
+        }
+
+        worker_state.set_last_processed_block(to_block).await?;
+
+        Ok(())
+    }
+}
+
+/// Transactions referenced by the job: its external id and the transactions of its attempts
+fn job_tx_hashes(job: &JobItem) -> impl Iterator<Item = &str> {
+    let attempts = job
+        .metadata
+        .iter()
+        .filter(|(key, _)| key.starts_with(JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX))
+        .flat_map(|(_, tx_hashes)| tx_hashes.split(','));
+    job.external_id.unwrap_string().ok().into_iter().chain(attempts)
+}
+
+/// Tx hashes are formatted differently across clients (padding, case), compare them on the raw value
+fn normalize_tx_hash(tx_hash: &str) -> String {
+    tx_hash.trim().trim_start_matches("0x").trim_start_matches('0').to_lowercase()
+}
@@ -0,0 +1,103 @@
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use prometheus::{register_int_gauge_vec, IntGaugeVec};
+use starknet::providers::Provider;
+use utils::env_utils::get_env_car_optional_or_panic;
+
+use crate::alerts::{send_alert, AlertKind};
+use crate::config::{config, Config};
+use crate::jobs::block_range::BlockRange;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::workers::{HaltPolicy, Worker};
+
+/// Blocks the settled blocks may lag the head of the chain before an alert is raised
+pub const ENV_PIPELINE_MONITOR_MAX_LAG_BLOCKS: &str = "PIPELINE_MONITOR_MAX_LAG_BLOCKS";
+
+/// Head of the chain on Madara
+pub const STAGE_MADARA: &str = "madara";
+/// Last block of the latest completed SNOS job
+pub const STAGE_SNOS_COMPLETED: &str = "snos_completed";
+/// Last block of the latest completed DA job
+pub const STAGE_DA_SUBMITTED: &str = "da_submitted";
+/// Last block settled on the settlement layer, its `stateBlockNumber`
+pub const STAGE_SETTLED: &str = "settled";
+
+lazy_static! {
+    /// Latest block reached by each stage of the block pipeline, the lag of a stage is its
+    /// distance to the Madara head
+    pub static ref PIPELINE_LATEST_BLOCK: IntGaugeVec = register_int_gauge_vec!(
+        "pipeline_latest_block",
+        "Latest block reached by each stage of the block pipeline",
+        &["stage"]
+    )
+    .unwrap();
+}
+
+/// Monitors how far each stage of the block pipeline lags the head of the chain. The latest block
+/// of each stage is exported as a gauge, and an alert is raised when the settlement lags by more
+/// than `PIPELINE_MONITOR_MAX_LAG_BLOCKS`, whichever stage is holding the blocks back.
+#[derive(Default)]
+pub struct PipelineMonitorWorker {
+    /// The lag is already above the threshold, the alert is raised once until it's back under
+    lagging: AtomicBool,
+}
+
+#[async_trait]
+impl Worker for PipelineMonitorWorker {
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let latest_block = config.starknet_client().block_number().await?;
+        set_latest_block(STAGE_MADARA, latest_block);
+
+        if let Some(block) = latest_completed_block(config.as_ref(), JobType::SnosRun).await? {
+            set_latest_block(STAGE_SNOS_COMPLETED, block);
+        }
+        if let Some(block) = latest_completed_block(config.as_ref(), JobType::DataSubmission).await? {
+            set_latest_block(STAGE_DA_SUBMITTED, block);
+        }
+
+        let last_settled_block = config.settlement_client().get_last_settled_block().await?;
+        set_latest_block(STAGE_SETTLED, last_settled_block);
+        let lag = latest_block.saturating_sub(last_settled_block);
+
+        let Some(max_lag) = get_env_car_optional_or_panic(ENV_PIPELINE_MONITOR_MAX_LAG_BLOCKS) else {
+            return Ok(());
+        };
+        let max_lag: u64 = max_lag.parse()?;
+
+        if lag > max_lag {
+            if !self.lagging.swap(true, Ordering::SeqCst) {
+                send_alert(
+                    AlertKind::BlockLag,
+                    &format!(
+                        "Block {} is the last settled block, {} blocks behind the chain head {} and over the \
+                         threshold of {}",
+                        last_settled_block, lag, latest_block, max_lag
+                    ),
+                )
+                .await;
+            }
+        } else if self.lagging.swap(false, Ordering::SeqCst) {
+            tracing::info!(lag, "The settled blocks are back within the lag threshold");
+        }
+        Ok(())
+    }
+
+    /// The lag grows the most while the workers are halted
+    async fn is_worker_enabled(&self, _halt_policy: HaltPolicy) -> Result<bool, Box<dyn Error>> {
+        Ok(true)
+    }
+}
+
+/// Last block of the latest completed job of the type, `None` before the first one completes
+async fn latest_completed_block(config: &Config, job_type: JobType) -> color_eyre::Result<Option<u64>> {
+    let latest_job = config.database().get_latest_job_by_type_and_status(job_type, JobStatus::Completed).await?;
+    Ok(latest_job.map(|job| job.internal_id.parse::<BlockRange>()).transpose()?.map(|range| range.end))
+}
+
+fn set_latest_block(stage: &str, block: u64) {
+    PIPELINE_LATEST_BLOCK.with_label_values(&[stage]).set(i64::try_from(block).unwrap_or(i64::MAX));
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use async_trait::async_trait;
+use utils::env_utils::get_env_var_or_default;
+
+use crate::config::config;
+use crate::jobs::constants::JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY;
+use crate::jobs::create_job;
+use crate::jobs::proof_aggregation_job::blocks_to_aggregate;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::workers::Worker;
+
+/// Number of consecutive blocks whose proofs are aggregated into a single proof
+pub const ENV_PROOF_AGGREGATION_SIZE: &str = "PROOF_AGGREGATION_SIZE";
+
+pub struct ProofAggregationWorker;
+
+#[async_trait]
+impl Worker for ProofAggregationWorker {
+    fn job_type(&self) -> Option<JobType> {
+        Some(JobType::ProofAggregation)
+    }
+
+    /// 1. Fetch the last proof aggregation job, the next aggregation starts after its last block
+    /// 2. Fetch all successful proving jobs after it
+    /// 3. Create a proof aggregation job for each `PROOF_AGGREGATION_SIZE` consecutive proven blocks,
+    ///    a gap stops the creation so the aggregations stay consecutive
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let aggregation_size: usize = get_env_var_or_default(ENV_PROOF_AGGREGATION_SIZE, "10").parse()?;
+        if aggregation_size < 2 {
+            return Err(format!("{} must be at least 2", ENV_PROOF_AGGREGATION_SIZE).into());
+        }
+
+        let last_aggregated_block = match config.database().get_latest_job_by_type(JobType::ProofAggregation).await? {
+            Some(job) => blocks_to_aggregate(&job)?.last().copied(),
+            None => None,
+        };
+        let mut proven_blocks: Vec<u64> = config
+            .database()
+            .get_jobs_after_internal_id_by_job_type(
+                JobType::ProofCreation,
+                JobStatus::Completed,
+                last_aggregated_block.map(|block_no| block_no.to_string()).unwrap_or_default(),
+            )
+            .await?
+            .iter()
+            .filter_map(|job| job.internal_id.parse().ok())
+            .filter(|block_no| last_aggregated_block.map_or(true, |last| *block_no > last))
+            .collect();
+        proven_blocks.sort();
+
+        let first_block = last_aggregated_block.map(|last| last + 1).or(proven_blocks.first().copied());
+        let Some(mut next_block) = first_block else {
+            return Ok(());
+        };
+        let mut blocks = Vec::with_capacity(aggregation_size);
+        for block_no in proven_blocks {
+            if block_no != next_block {
+                tracing::info!(
+                    block = next_block,
+                    "Block isn't proven yet, not aggregating the proofs after it (next proven block is #{})",
+                    block_no
+                );
+                break;
+            }
+            blocks.push(block_no);
+            next_block += 1;
+            if blocks.len() == aggregation_size {
+                let blocks_metadata = blocks.iter().map(|block_no| block_no.to_string()).collect::<Vec<_>>().join(",");
+                let metadata =
+                    HashMap::from([(JOB_METADATA_PROOF_AGGREGATION_BLOCKS_KEY.to_string(), blocks_metadata)]);
+                create_job(JobType::ProofAggregation, blocks[0].to_string(), metadata).await?;
+                blocks.clear();
+            }
+        }
+
+        Ok(())
+    }
+}
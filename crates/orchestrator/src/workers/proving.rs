@@ -1,5 +1,9 @@
 use crate::config::config;
-use crate::jobs::create_job;
+use crate::data_storage::cairo_pie::cairo_pie_key;
+use crate::jobs::backpressure::JobCreationBudget;
+use crate::jobs::block_range::BlockRange;
+use crate::jobs::constants::JOB_METADATA_CAIRO_PIE_KEY;
+use crate::jobs::dependencies::create_job_with_parents;
 use crate::jobs::types::{JobStatus, JobType};
 use crate::workers::Worker;
 use async_trait::async_trait;
@@ -9,8 +13,14 @@ pub struct ProvingWorker;
 
 #[async_trait]
 impl Worker for ProvingWorker {
+    fn job_type(&self) -> Option<JobType> {
+        Some(JobType::ProofCreation)
+    }
+
     /// 1. Fetch all successful SNOS job runs that don't have a proving job
-    /// 2. Create a proving job for each SNOS job run
+    /// 2. Create a proving job for each SNOS job run, depending on it. The blocks of a SNOS job
+    ///    running several blocks are each proven by their own job.
+    /// 3. Stop once the pending proving jobs reach their cap, the rest is created in a later run
     async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
         let config = config().await;
         let successful_snos_jobs = config
@@ -18,8 +28,32 @@ impl Worker for ProvingWorker {
             .get_jobs_without_successor(JobType::SnosRun, JobStatus::Completed, JobType::ProofCreation)
             .await?;
 
+        let mut budget = JobCreationBudget::for_job_type(JobType::ProofCreation).await?;
         for job in successful_snos_jobs {
-            create_job(JobType::ProofCreation, job.internal_id.to_string(), job.metadata).await?
+            let range: BlockRange = job.internal_id.parse()?;
+            if range.is_single_block() {
+                if !budget.try_take() {
+                    return Ok(());
+                }
+                let metadata = job.metadata.clone();
+                create_job_with_parents(JobType::ProofCreation, job.internal_id.to_string(), metadata, &[job]).await?;
+                continue;
+            }
+            // the SNOS job is listed until its last block is proven, the blocks already proven are skipped
+            for block_number in range.blocks().map(|block_number| block_number.to_string()) {
+                let proving_job =
+                    config.database().get_job_by_internal_id_and_type(&block_number, &JobType::ProofCreation).await?;
+                if proving_job.is_some() {
+                    continue;
+                }
+                if !budget.try_take() {
+                    return Ok(());
+                }
+                let mut metadata = job.metadata.clone();
+                metadata.insert(JOB_METADATA_CAIRO_PIE_KEY.to_string(), cairo_pie_key(&block_number));
+                create_job_with_parents(JobType::ProofCreation, block_number, metadata, std::slice::from_ref(&job))
+                    .await?;
+            }
         }
 
         Ok(())
@@ -0,0 +1,79 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use utils::env_utils::get_env_var_or_default;
+
+use crate::alerts::{send_alert, AlertKind};
+use crate::config::config;
+use crate::database::types::{AuditEvent, AuditEventKind, DatabaseWrite};
+use crate::jobs::block_range::BlockRange;
+use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO;
+use crate::jobs::state_update_job::StateUpdateJob;
+use crate::jobs::status_change_event;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::queue::job_queue::add_job_to_process_queue;
+use crate::workers::{HaltPolicy, Worker};
+
+/// Number of recent state update jobs whose settlement transactions are checked
+pub const ENV_REORG_MONITOR_JOBS_LIMIT: &str = "REORG_MONITOR_JOBS_LIMIT";
+
+/// Detects the state updates reorged out of the settlement layer. The settlement transaction of
+/// the last block of the recently completed state update jobs must still be included, the first
+/// job whose transaction disappeared is moved back to `Created` and processed again from the first
+/// block the core contract doesn't have anymore. A reorg is recovered from without an operator, the
+/// job isn't moved to a failed status which would halt the workers.
+///
+/// The later jobs build on the state of the reorged one, their transactions are reorged out or
+/// revert as well. They are handled one at a time, in order, on the next runs.
+pub struct ReorgMonitorWorker;
+
+#[async_trait]
+impl Worker for ReorgMonitorWorker {
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let jobs_limit: i64 = get_env_var_or_default(ENV_REORG_MONITOR_JOBS_LIMIT, "10").parse()?;
+
+        let mut completed_jobs: Vec<_> = config
+            .database()
+            .get_latest_jobs_by_type(JobType::StateTransition, 0, jobs_limit)
+            .await?
+            .into_iter()
+            .filter(|job| job.status == JobStatus::Completed)
+            .collect();
+        completed_jobs.sort_by_key(|job| job.internal_id.parse::<BlockRange>().map_or(u64::MAX, |range| range.start));
+
+        for mut job in completed_jobs {
+            let Some(reorged_block) = StateUpdateJob.find_reorged_block(config.as_ref(), &job).await? else {
+                continue;
+            };
+
+            let details = format!(
+                "Settlement of state update job #{} ({}) was reorged out, settling it again from block #{}",
+                job.internal_id, job.id, reorged_block
+            );
+            tracing::warn!("{}", details);
+
+            job.metadata.insert(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO.to_string(), reorged_block.to_string());
+            job.metadata.insert("error".to_string(), details.clone());
+            job.status = JobStatus::Created;
+            // the job and its history are updated together, the history tells why the job is settled again
+            let event = status_change_event(&job, JobStatus::Completed, JobStatus::Created, Some(details.clone()));
+            config
+                .database()
+                .run_transaction(vec![DatabaseWrite::UpdateJob(job.clone()), DatabaseWrite::AppendJobEvent(event)])
+                .await?;
+
+            send_alert(AlertKind::SettlementReorg, &details).await;
+            config.database().record_audit_event(AuditEvent::new(AuditEventKind::SettlementReorg, details)).await?;
+            add_job_to_process_queue(&job).await?;
+            break;
+        }
+
+        Ok(())
+    }
+
+    /// The reorged jobs have to be settled again whatever the state of the other jobs
+    async fn is_worker_enabled(&self, _halt_policy: HaltPolicy) -> Result<bool, Box<dyn Error>> {
+        Ok(true)
+    }
+}
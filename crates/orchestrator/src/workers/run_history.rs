@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use prometheus::{register_int_gauge_vec, IntGaugeVec};
+use serde::Serialize;
+use utils::env_utils::get_env_var_or_default;
+
+use crate::alerts::{send_alert, AlertKind};
+use crate::config::config;
+use crate::database::types::{now_secs, WorkerRun};
+use crate::jobs::worker_id;
+use crate::workers::scheduler::WORKER_SCHEDULER;
+
+/// Days the runs of the workers are kept, 7 by default
+pub const ENV_WORKER_RUNS_RETENTION_DAYS: &str = "WORKER_RUNS_RETENTION_DAYS";
+/// Runs a worker may miss before it's reported unhealthy, 3 by default
+pub const ENV_WORKER_HEALTH_MISSED_RUNS: &str = "WORKER_HEALTH_MISSED_RUNS";
+
+lazy_static! {
+    /// Unix time in seconds at which each worker last completed a run successfully
+    pub static ref WORKER_LAST_SUCCESS: IntGaugeVec = register_int_gauge_vec!(
+        "worker_last_success_timestamp_seconds",
+        "Unix time at which the worker last completed a run successfully",
+        &["worker"]
+    )
+    .unwrap();
+
+    /// Workers halted by failed jobs in their last run, the alert is raised once until they resume
+    static ref HALTED_WORKERS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+tokio::task_local! {
+    /// Run of a worker in progress, see [`record_run`]
+    static CURRENT_RUN: Arc<RunTracker>;
+}
+
+#[derive(Default)]
+struct RunTracker {
+    jobs_created: AtomicU64,
+    /// The instance isn't the leader, the workers don't run on it
+    skipped: AtomicBool,
+    /// The worker is halted by failed jobs
+    halted: AtomicBool,
+}
+
+/// Counts the jobs created by the worker running, if any
+pub fn count_created_jobs(count: u64) {
+    let _ = CURRENT_RUN.try_with(|run| run.jobs_created.fetch_add(count, Ordering::SeqCst));
+}
+
+/// Marks the run of the worker as skipped, it isn't recorded
+pub fn skip_run() {
+    let _ = CURRENT_RUN.try_with(|run| run.skipped.store(true, Ordering::SeqCst));
+}
+
+/// Marks the worker running as halted by failed jobs, the run is recorded as failed
+pub fn halt_run() {
+    let _ = CURRENT_RUN.try_with(|run| run.halted.store(true, Ordering::SeqCst));
+}
+
+/// Runs the worker and appends the run to its run history, with the jobs it created and the error
+/// it failed with. The error is returned as a string, the errors of the workers can't be held
+/// across the recording.
+pub async fn record_run<F>(worker: &str, run: F) -> Result<(), String>
+where
+    F: Future<Output = Result<(), Box<dyn Error>>>,
+{
+    let tracker = Arc::new(RunTracker::default());
+    let started_at = now_secs();
+    let result = CURRENT_RUN.scope(tracker.clone(), run).await.map_err(|e| e.to_string());
+    if tracker.skipped.load(Ordering::SeqCst) {
+        return result;
+    }
+
+    let halted = tracker.halted.load(Ordering::SeqCst);
+    let newly_halted = {
+        let mut halted_workers = HALTED_WORKERS.lock().expect("Failed to lock halted workers");
+        if halted {
+            halted_workers.insert(worker.to_string())
+        } else {
+            if halted_workers.remove(worker) {
+                tracing::info!(worker, "Worker resumed, the failed jobs halting it were resolved");
+            }
+            false
+        }
+    };
+    if newly_halted {
+        send_alert(
+            AlertKind::WorkerHalted,
+            &format!("Worker {} is halted by failed jobs, it creates no jobs until they are resolved", worker),
+        )
+        .await;
+    }
+
+    let error = match &result {
+        Err(e) => Some(e.clone()),
+        Ok(()) if halted => Some("Halted by failed jobs".to_string()),
+        Ok(()) => None,
+    };
+    let run = WorkerRun {
+        worker: worker.to_string(),
+        started_at,
+        ended_at: now_secs(),
+        jobs_created: tracker.jobs_created.load(Ordering::SeqCst),
+        error,
+        worker_id: worker_id(),
+    };
+    if run.succeeded() {
+        WORKER_LAST_SUCCESS.with_label_values(&[worker]).set(i64::try_from(run.ended_at).unwrap_or(i64::MAX));
+    }
+    if let Err(e) = store_run(run).await {
+        tracing::error!("Failed to record the run of the worker {}: {:?}", worker, e);
+    }
+    result
+}
+
+async fn store_run(run: WorkerRun) -> Result<()> {
+    let retention_days: u64 = get_env_var_or_default(ENV_WORKER_RUNS_RETENTION_DAYS, "7").parse()?;
+    let config = config().await;
+    config.database().record_worker_run(run).await?;
+    config.database().delete_worker_runs_before(now_secs().saturating_sub(retention_days * 24 * 3600)).await?;
+    Ok(())
+}
+
+/// Health of a worker, from its run history
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkerHealth {
+    pub worker: String,
+    /// Whether the worker completed a run successfully within the runs it may miss
+    pub healthy: bool,
+    /// Seconds since the worker last completed a run successfully, `None` if it never did
+    pub last_success_age_seconds: Option<u64>,
+    pub last_run: Option<WorkerRun>,
+}
+
+/// Health of the workers scheduled in this instance. The runs are read from the database, the
+/// instances which aren't the leader report the runs of the leader.
+///
+/// A worker is unhealthy once it went `WORKER_HEALTH_MISSED_RUNS` of its periods without a
+/// successful run, counted from the later of its last successful run and the start of the
+/// scheduler.
+pub async fn workers_health() -> Result<Vec<WorkerHealth>> {
+    let missed_runs: u32 = get_env_var_or_default(ENV_WORKER_HEALTH_MISSED_RUNS, "3").parse()?;
+    let config = config().await;
+    let database = config.database();
+    let now = now_secs();
+    let mut health = Vec::new();
+    for scheduled in WORKER_SCHEDULER.scheduled_workers() {
+        let last_run = database.get_worker_runs(&scheduled.name, 1).await?.into_iter().next();
+        let last_success = match &last_run {
+            Some(run) if run.succeeded() => Some(run.clone()),
+            _ => database.get_last_successful_worker_run(&scheduled.name).await?,
+        };
+        let last_success_at = last_success.map(|run| run.ended_at);
+        let since = last_success_at.unwrap_or(0).max(scheduled.started_at);
+        let max_age = scheduled.schedule.period().saturating_mul(missed_runs);
+        health.push(WorkerHealth {
+            worker: scheduled.name,
+            healthy: Duration::from_secs(now.saturating_sub(since)) <= max_age,
+            last_success_age_seconds: last_success_at.map(|ended_at| now.saturating_sub(ended_at)),
+            last_run,
+        });
+    }
+    Ok(health)
+}
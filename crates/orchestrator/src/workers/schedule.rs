@@ -0,0 +1,199 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::Utc;
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_default;
+use utils::settings::default::DefaultSettingsProvider;
+use utils::settings::SettingsProvider;
+
+use crate::workers::HaltPolicy;
+
+pub const WORKER_SCHEDULE_SETTINGS_NAME: &str = "worker_schedule_settings";
+/// Halt policy of the workers without one of their own, see [`HaltPolicy`]
+pub const ENV_WORKER_HALT_POLICY: &str = "WORKER_HALT_POLICY";
+
+/// Whether a worker runs and how often
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerSchedule {
+    pub enabled: bool,
+    /// Seconds to wait between two runs of the worker
+    pub interval_seconds: u64,
+    /// Cron expression with seconds, e.g. `0 0 2 * * *` for every night at 2, replacing the interval when set
+    pub cron: Option<String>,
+    /// Failed jobs which halt the worker
+    pub halt_policy: HaltPolicy,
+}
+
+impl WorkerSchedule {
+    /// The schedule of the worker, read from `<NAME>_WORKER_ENABLED`, `<NAME>_WORKER_INTERVAL_SECS`,
+    /// `<NAME>_WORKER_CRON` and `<NAME>_WORKER_HALT_POLICY` when set
+    fn from_env(name: &str, defaults: WorkerSchedule) -> Self {
+        let enabled_env_var = format!("{}_WORKER_ENABLED", name);
+        let interval_env_var = format!("{}_WORKER_INTERVAL_SECS", name);
+        let enabled = get_env_var_or_default(&enabled_env_var, &defaults.enabled.to_string())
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be true or false", enabled_env_var));
+        let interval_seconds = get_env_var_or_default(&interval_env_var, &defaults.interval_seconds.to_string())
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a number", interval_env_var));
+        if interval_seconds == 0 {
+            panic!("{} must be greater than 0", interval_env_var);
+        }
+        let cron_env_var = format!("{}_WORKER_CRON", name);
+        let cron = Some(get_env_var_or_default(&cron_env_var, "")).filter(|cron| !cron.is_empty()).or(defaults.cron);
+        if let Some(cron) = &cron {
+            Schedule::from_str(cron)
+                .unwrap_or_else(|e| panic!("{} is not a valid cron expression: {}", cron_env_var, e));
+        }
+        let halt_policy_env_var = format!("{}_WORKER_HALT_POLICY", name);
+        let halt_policy = match get_env_var_or_default(&halt_policy_env_var, "").as_str() {
+            "" => defaults.halt_policy,
+            halt_policy => halt_policy.parse().unwrap_or_else(|e| panic!("{}: {}", halt_policy_env_var, e)),
+        };
+        Self { enabled, interval_seconds, cron, halt_policy }
+    }
+
+    /// Time to wait before the next run of the worker
+    pub fn next_delay(&self) -> Duration {
+        let Some(cron) = &self.cron else {
+            return Duration::from_secs(self.interval_seconds);
+        };
+        let schedule = Schedule::from_str(cron).expect("Invalid cron expression of the worker");
+        match schedule.upcoming(Utc).next() {
+            Some(next_run) => (next_run - Utc::now()).to_std().unwrap_or(Duration::ZERO),
+            // the expression has no upcoming time left, e.g. a past year
+            None => Duration::MAX,
+        }
+    }
+
+    /// Time between two runs of the worker, the time between its next two runs for a cron
+    /// expression
+    pub fn period(&self) -> Duration {
+        let Some(cron) = &self.cron else {
+            return Duration::from_secs(self.interval_seconds);
+        };
+        let schedule = Schedule::from_str(cron).expect("Invalid cron expression of the worker");
+        let mut upcoming = schedule.upcoming(Utc);
+        match (upcoming.next(), upcoming.next()) {
+            (Some(next_run), Some(following_run)) => (following_run - next_run).to_std().unwrap_or(Duration::ZERO),
+            _ => Duration::MAX,
+        }
+    }
+}
+
+/// Schedules of the workers, per worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerScheduleSettings {
+    pub snos: WorkerSchedule,
+    pub proving: WorkerSchedule,
+    pub proof_registration: WorkerSchedule,
+    pub update_state: WorkerSchedule,
+    pub data_submission: WorkerSchedule,
+    pub dependencies: WorkerSchedule,
+    pub stuck_jobs: WorkerSchedule,
+    pub da_backfill: WorkerSchedule,
+    pub orphan_tx_watchdog: WorkerSchedule,
+    pub balance_monitor: WorkerSchedule,
+    pub reorg_monitor: WorkerSchedule,
+    pub proof_aggregation: WorkerSchedule,
+    pub messaging: WorkerSchedule,
+    pub data_audit: WorkerSchedule,
+    pub job_archival: WorkerSchedule,
+    pub storage_gc: WorkerSchedule,
+    pub pipeline_monitor: WorkerSchedule,
+}
+
+impl Default for WorkerScheduleSettings {
+    /// The schedules the workers used to be started with, overridden through the environment.
+    /// The optional workers are enabled by their own flag as well, e.g. `DA_BACKFILL`, and the halt
+    /// policy of all the workers is set by `WORKER_HALT_POLICY`.
+    fn default() -> Self {
+        let halt_policy: HaltPolicy = get_env_var_or_default(ENV_WORKER_HALT_POLICY, "global")
+            .parse()
+            .unwrap_or_else(|e| panic!("{}: {}", ENV_WORKER_HALT_POLICY, e));
+        let always = |interval_seconds| WorkerSchedule { enabled: true, interval_seconds, cron: None, halt_policy };
+        let flag = |env_var: &str, interval_seconds| WorkerSchedule {
+            enabled: get_env_var_or_default(env_var, "false") == "true",
+            interval_seconds,
+            cron: None,
+            halt_policy,
+        };
+        Self {
+            snos: WorkerSchedule::from_env("SNOS", always(60)),
+            proving: WorkerSchedule::from_env("PROVING", always(60)),
+            proof_registration: WorkerSchedule::from_env("PROOF_REGISTRATION", always(60)),
+            update_state: WorkerSchedule::from_env("UPDATE_STATE", always(60)),
+            data_submission: WorkerSchedule::from_env("DATA_SUBMISSION", always(60)),
+            dependencies: WorkerSchedule::from_env("DEPENDENCY", always(60)),
+            stuck_jobs: WorkerSchedule::from_env("STUCK_JOB_RECOVERY", always(60)),
+            da_backfill: WorkerSchedule::from_env("DA_BACKFILL", flag("DA_BACKFILL", 60)),
+            orphan_tx_watchdog: WorkerSchedule::from_env("ORPHAN_TX_WATCHDOG", flag("ORPHAN_TX_WATCHDOG", 60)),
+            balance_monitor: WorkerSchedule::from_env("BALANCE_MONITOR", flag("BALANCE_MONITOR", 60)),
+            reorg_monitor: WorkerSchedule::from_env("REORG_MONITOR", flag("REORG_MONITOR", 60)),
+            proof_aggregation: WorkerSchedule::from_env("PROOF_AGGREGATION", flag("PROOF_AGGREGATION", 60)),
+            messaging: WorkerSchedule::from_env("MESSAGING", flag("MESSAGING", 60)),
+            data_audit: WorkerSchedule::from_env("DATA_AUDIT", flag("DATA_AUDIT", 60)),
+            job_archival: WorkerSchedule::from_env("JOB_ARCHIVAL", flag("JOB_ARCHIVAL", 3600)),
+            storage_gc: WorkerSchedule::from_env("STORAGE_GC", flag("STORAGE_GC", 3600)),
+            pipeline_monitor: WorkerSchedule::from_env("PIPELINE_MONITOR", always(60)),
+        }
+    }
+}
+
+/// Schedules of the workers, as configured
+pub fn worker_schedule_settings() -> WorkerScheduleSettings {
+    DefaultSettingsProvider {}
+        .get_settings(WORKER_SCHEDULE_SETTINGS_NAME)
+        .expect("Failed to read the worker schedule settings")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_schedule_override() {
+        std::env::set_var("PROOF_REGISTRATION_WORKER_ENABLED", "false");
+        std::env::set_var("SNOS_WORKER_INTERVAL_SECS", "10");
+        std::env::set_var("STORAGE_GC", "true");
+        let settings = WorkerScheduleSettings::default();
+        std::env::remove_var("PROOF_REGISTRATION_WORKER_ENABLED");
+        std::env::remove_var("SNOS_WORKER_INTERVAL_SECS");
+        std::env::remove_var("STORAGE_GC");
+
+        assert!(!settings.proof_registration.enabled);
+        assert_eq!(settings.proof_registration.interval_seconds, 60);
+        assert!(settings.snos.enabled);
+        assert_eq!(settings.snos.interval_seconds, 10);
+        assert!(settings.storage_gc.enabled);
+        assert_eq!(settings.storage_gc.interval_seconds, 3600);
+        assert!(!settings.job_archival.enabled);
+        assert!(settings.pipeline_monitor.enabled);
+    }
+
+    #[test]
+    fn test_worker_schedule_cron() {
+        std::env::set_var("JOB_ARCHIVAL_WORKER_CRON", "0 0 2 * * *");
+        let settings = WorkerScheduleSettings::default();
+        std::env::remove_var("JOB_ARCHIVAL_WORKER_CRON");
+
+        assert_eq!(settings.job_archival.cron.as_deref(), Some("0 0 2 * * *"));
+        // the next run is at most a day away, whatever the interval
+        assert!(settings.job_archival.next_delay() <= Duration::from_secs(24 * 3600));
+        assert_eq!(settings.job_archival.period(), Duration::from_secs(24 * 3600));
+        assert_eq!(settings.messaging.period(), Duration::from_secs(60));
+        assert_eq!(settings.messaging.next_delay(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_worker_halt_policy() {
+        std::env::set_var("PROVING_WORKER_HALT_POLICY", "job_type");
+        let settings = WorkerScheduleSettings::default();
+        std::env::remove_var("PROVING_WORKER_HALT_POLICY");
+
+        assert_eq!(settings.proving.halt_policy, HaltPolicy::JobType);
+        assert_eq!(settings.snos.halt_policy, HaltPolicy::Global);
+    }
+}
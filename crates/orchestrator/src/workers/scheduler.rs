@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use tokio::sync::Notify;
+
+use crate::database::types::now_secs;
+use crate::workers::run_history::record_run;
+use crate::workers::schedule::WorkerSchedule;
+use crate::workers::Worker;
+
+lazy_static! {
+    /// Scheduler running the workers of the process
+    pub static ref WORKER_SCHEDULER: WorkerScheduler = WorkerScheduler::default();
+}
+
+/// Runs each worker on its schedule, every interval or at the times of its cron expression. A
+/// worker can also be triggered to run right away, its schedule resuming after that run.
+#[derive(Default)]
+pub struct WorkerScheduler {
+    workers: Mutex<HashMap<String, ScheduledWorker>>,
+}
+
+/// Worker started by the scheduler
+#[derive(Debug, Clone)]
+pub struct ScheduledWorker {
+    pub name: String,
+    pub schedule: WorkerSchedule,
+    /// Unix timestamp in seconds
+    pub started_at: u64,
+    trigger: Arc<Notify>,
+}
+
+impl WorkerScheduler {
+    /// Starts the worker on its schedule, unless it's disabled
+    pub fn spawn(&self, name: &str, worker: Box<dyn Worker>, schedule: WorkerSchedule) {
+        if !schedule.enabled {
+            tracing::info!(worker = name, "Worker is disabled");
+            return;
+        }
+        let trigger = Arc::new(Notify::new());
+        let scheduled = ScheduledWorker {
+            name: name.to_string(),
+            schedule: schedule.clone(),
+            started_at: now_secs(),
+            trigger: trigger.clone(),
+        };
+        self.workers.lock().expect("Workers lock poisoned").insert(name.to_string(), scheduled);
+        tokio::spawn(run_on_schedule(name.to_string(), worker, schedule, trigger));
+    }
+
+    /// Runs the worker now instead of waiting for its next scheduled run. A worker triggered while
+    /// it's running runs once more right after.
+    pub fn trigger(&self, name: &str) -> Result<()> {
+        let workers = self.workers.lock().expect("Workers lock poisoned");
+        let scheduled = workers.get(name).ok_or_else(|| eyre!("No worker {} is running", name))?;
+        scheduled.trigger.notify_one();
+        Ok(())
+    }
+
+    /// Names of the workers running
+    pub fn workers(&self) -> Vec<String> {
+        self.scheduled_workers().into_iter().map(|scheduled| scheduled.name).collect()
+    }
+
+    /// Workers running, by name
+    pub fn scheduled_workers(&self) -> Vec<ScheduledWorker> {
+        let mut workers: Vec<ScheduledWorker> =
+            self.workers.lock().expect("Workers lock poisoned").values().cloned().collect();
+        workers.sort_by(|a, b| a.name.cmp(&b.name));
+        workers
+    }
+}
+
+/// Runs the worker of the process named `name` now, see [`WorkerScheduler::trigger`]
+pub fn trigger_worker_now(name: &str) -> Result<()> {
+    WORKER_SCHEDULER.trigger(name)
+}
+
+async fn run_on_schedule(name: String, worker: Box<dyn Worker>, schedule: WorkerSchedule, trigger: Arc<Notify>) {
+    // the interval workers run as soon as they start, the cron ones wait for their first time
+    if schedule.cron.is_none() {
+        run_once(&name, worker.as_ref(), &schedule).await;
+    }
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(schedule.next_delay()) => {}
+            _ = trigger.notified() => {}
+        }
+        run_once(&name, worker.as_ref(), &schedule).await;
+    }
+}
+
+async fn run_once(name: &str, worker: &dyn Worker, schedule: &WorkerSchedule) {
+    if let Err(e) = record_run(name, worker.run_worker_if_enabled(schedule.halt_policy)).await {
+        panic!("Error in running the worker {}: {}", name, e);
+    }
+}
@@ -1,44 +1,117 @@
 use std::collections::HashMap;
 use std::error::Error;
+use std::ops::RangeInclusive;
 
 use async_trait::async_trait;
 use starknet::providers::Provider;
+use utils::env_utils::get_env_var_or_default;
+use utils::settings::default::DefaultSettingsProvider;
+use utils::settings::SettingsProvider;
 
 use crate::config::config;
+use crate::jobs::backpressure::JobCreationBudget;
+use crate::jobs::block_range::{block_ranges, BlockBatchSettings, BlockRange, BLOCK_BATCH_SETTINGS_NAME};
 use crate::jobs::create_job;
+use crate::jobs::snos_job::os_program::split_at_os_upgrades;
 use crate::jobs::types::{JobStatus, JobType};
+use crate::workers::state::WorkerState;
 use crate::workers::Worker;
 
+/// Name of the worker state storing the last block a SNOS job was created for
+pub const SNOS_WORKER: &str = "snos";
+/// First block to run SNOS on when no SNOS job was created yet, 1 by default
+pub const ENV_SNOS_START_BLOCK: &str = "SNOS_START_BLOCK";
+/// Maximum number of blocks behind the head the first SNOS job starts at, the older blocks are
+/// never run. Unset by default, the worker starts at `SNOS_START_BLOCK` however far the head is.
+pub const ENV_SNOS_MAX_BLOCKS_BEHIND_HEAD: &str = "SNOS_MAX_BLOCKS_BEHIND_HEAD";
+/// Number of blocks below the head left out until they are confirmed, Madara might still reorg
+/// them. 0 by default.
+pub const ENV_SNOS_CONFIRMATION_LAG: &str = "SNOS_CONFIRMATION_LAG";
+
+/// How the SNOS worker follows the head of the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainHeadTracking {
+    pub start_block: u64,
+    pub max_blocks_behind_head: Option<u64>,
+    pub confirmation_lag: u64,
+}
+
+impl ChainHeadTracking {
+    pub fn from_env() -> color_eyre::Result<Self> {
+        let max_blocks_behind_head = match get_env_var_or_default(ENV_SNOS_MAX_BLOCKS_BEHIND_HEAD, "").as_str() {
+            "" => None,
+            max_blocks_behind_head => Some(max_blocks_behind_head.parse()?),
+        };
+        Ok(Self {
+            start_block: get_env_var_or_default(ENV_SNOS_START_BLOCK, "1").parse()?,
+            max_blocks_behind_head,
+            confirmation_lag: get_env_var_or_default(ENV_SNOS_CONFIRMATION_LAG, "0").parse()?,
+        })
+    }
+
+    /// Blocks to create SNOS jobs for, after the last block processed and up to the confirmed
+    /// head. `None` when the worker has caught up with it.
+    pub fn blocks_to_process(&self, last_processed_block: Option<u64>, head: u64) -> Option<RangeInclusive<u64>> {
+        let confirmed_head = head.checked_sub(self.confirmation_lag)?;
+        let first_block = match last_processed_block {
+            Some(block_number) => block_number + 1,
+            None => match self.max_blocks_behind_head {
+                Some(max_behind) => self.start_block.max(confirmed_head.saturating_sub(max_behind)),
+                None => self.start_block,
+            },
+        };
+        (first_block <= confirmed_head).then_some(first_block..=confirmed_head)
+    }
+}
+
 pub struct SnosWorker;
 
 #[async_trait]
 impl Worker for SnosWorker {
+    fn job_type(&self) -> Option<JobType> {
+        Some(JobType::SnosRun)
+    }
+
     /// 1. Fetch the latest completed block from the Starknet chain
-    /// 2. Fetch the last block that had a SNOS job run.
-    /// 3. Create SNOS run jobs for all the remaining blocks
+    /// 2. Fetch the last block that had a SNOS job created, from the worker state or else from
+    ///    the last SNOS job run. Without any, start at `SNOS_START_BLOCK`, at most
+    ///    `SNOS_MAX_BLOCKS_BEHIND_HEAD` blocks behind the head.
+    /// 3. Create SNOS run jobs for all the remaining blocks but the last `SNOS_CONFIRMATION_LAG`
+    ///    ones, each running up to `SNOS_BATCH_SIZE` consecutive blocks with the same OS program,
+    ///    until the pending SNOS jobs reach their cap. The last block a job was created for is
+    ///    stored as we go, the next runs resume from it.
     async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
         let config = config().await;
+        let worker_state = WorkerState::new(SNOS_WORKER);
+        let settings: BlockBatchSettings = DefaultSettingsProvider {}.get_settings(BLOCK_BATCH_SETTINGS_NAME)?;
+        let tracking = ChainHeadTracking::from_env()?;
         let provider = config.starknet_client();
         let latest_block_number = provider.block_number().await?;
-        let latest_block_processed_data = config
-            .database()
-            .get_latest_job_by_type_and_status(JobType::SnosRun, JobStatus::Completed)
-            .await
-            .unwrap()
-            .map(|item| item.internal_id)
-            .unwrap_or("0".to_string());
+        let latest_block_processed = match worker_state.last_processed_block().await? {
+            Some(block_number) => Some(block_number),
+            None => config
+                .database()
+                .get_latest_job_by_type_and_status(JobType::SnosRun, JobStatus::Completed)
+                .await?
+                .map(|item| item.internal_id.parse::<BlockRange>())
+                .transpose()?
+                .map(|range| range.end),
+        };
 
-        let latest_block_processed: u64 = latest_block_processed_data.parse()?;
-
-        let block_diff = latest_block_number - latest_block_processed;
-
-        // if all blocks are processed
-        if block_diff == 0 {
+        // if all the confirmed blocks are processed
+        let Some(blocks) = tracking.blocks_to_process(latest_block_processed, latest_block_number) else {
             return Ok(());
-        }
+        };
 
-        for x in latest_block_processed + 1..latest_block_number + 1 {
-            create_job(JobType::SnosRun, x.to_string(), HashMap::new()).await?;
+        let mut budget = JobCreationBudget::for_job_type(JobType::SnosRun).await?;
+        for batch in block_ranges(*blocks.start(), *blocks.end(), settings.snos_run) {
+            for range in split_at_os_upgrades(batch)? {
+                if !budget.try_take() {
+                    return Ok(());
+                }
+                create_job(JobType::SnosRun, range.to_string(), HashMap::new()).await?;
+                worker_state.set_last_processed_block(range.end).await?;
+            }
         }
 
         Ok(())
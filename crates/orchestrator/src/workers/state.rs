@@ -0,0 +1,43 @@
+use color_eyre::Result;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::config::config;
+
+/// Key under which workers store the last block they processed
+pub const LAST_PROCESSED_BLOCK_KEY: &str = "last_processed_block";
+
+/// Typed access to the incremental state a worker persists in the database between runs, so
+/// that it doesn't have to recompute where it left off from the jobs every run.
+pub struct WorkerState {
+    worker: &'static str,
+}
+
+impl WorkerState {
+    pub const fn new(worker: &'static str) -> Self {
+        Self { worker }
+    }
+
+    /// Returns the value stored under `key`, `None` if nothing has been stored yet
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let config = config().await;
+        match config.database().get_worker_state(self.worker, key).await? {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores `value` under `key`, overwriting the previous value
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        let config = config().await;
+        config.database().set_worker_state(self.worker, key, serde_json::to_value(value)?).await
+    }
+
+    pub async fn last_processed_block(&self) -> Result<Option<u64>> {
+        self.get(LAST_PROCESSED_BLOCK_KEY).await
+    }
+
+    pub async fn set_last_processed_block(&self, block_number: u64) -> Result<()> {
+        self.set(LAST_PROCESSED_BLOCK_KEY, &block_number).await
+    }
+}
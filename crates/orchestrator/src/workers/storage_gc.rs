@@ -0,0 +1,149 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_default;
+use utils::settings::default::DefaultSettingsProvider;
+use utils::settings::SettingsProvider;
+
+use crate::config::config;
+use crate::data_storage::key::{ArtifactKind, StorageKey};
+use crate::data_storage::DataStorage;
+use crate::database::types::{now_secs, JobFilter};
+use crate::jobs::block_range::BlockRange;
+use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY;
+use crate::jobs::types::{JobItem, JobStatus, JobType};
+use crate::workers::state::WorkerState;
+use crate::workers::Worker;
+
+pub const STORAGE_GC_WORKER: &str = "storage_gc";
+pub const STORAGE_RETENTION_SETTINGS_NAME: &str = "storage_retention_settings";
+/// Days the artifacts of a block are kept once the state transition of the block is completed
+pub const ENV_STORAGE_RETENTION_DAYS: &str = "STORAGE_RETENTION_DAYS";
+/// Comma separated kinds of the artifacts deleted once the retention period is over
+pub const ENV_STORAGE_GC_ARTIFACTS: &str = "STORAGE_GC_ARTIFACTS";
+/// Number of state transition jobs fetched at once
+const STORAGE_GC_BATCH_SIZE: i64 = 100;
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Retention policy of the artifacts stored for the blocks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageRetentionSettings {
+    /// Days the artifacts are kept once the state transition of their block is completed
+    pub retention_days: u64,
+    /// Kinds of the artifacts deleted, as their file stem, e.g. `snos_input`
+    pub artifacts: Vec<String>,
+}
+
+impl Default for StorageRetentionSettings {
+    /// The policy is read from `STORAGE_RETENTION_DAYS` and `STORAGE_GC_ARTIFACTS` unless the
+    /// settings provider has one
+    fn default() -> Self {
+        let retention_days = get_env_var_or_default(ENV_STORAGE_RETENTION_DAYS, "30")
+            .parse()
+            .expect("STORAGE_RETENTION_DAYS must be a number of days");
+        let artifacts = get_env_var_or_default(ENV_STORAGE_GC_ARTIFACTS, "snos_input,cairo_pie")
+            .split(',')
+            .map(|kind| kind.trim().to_string())
+            .filter(|kind| !kind.is_empty())
+            .collect();
+        Self { retention_days, artifacts }
+    }
+}
+
+/// Deletes the intermediate artifacts of the blocks, the SNOS inputs and the PIEs by default,
+/// once the state transition of the block has been completed for longer than the retention
+/// period. The artifacts are only needed to run the jobs of the block again, which won't happen
+/// once its state is settled.
+///
+/// The blocks collected are recorded in the worker state so that the storage isn't listed again
+/// for them every run. Only the blocks below the first one still retained are recorded, a state
+/// transition completed out of order is collected in a later run.
+pub struct StorageGcWorker;
+
+#[async_trait]
+impl Worker for StorageGcWorker {
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let settings: StorageRetentionSettings =
+            DefaultSettingsProvider {}.get_settings(STORAGE_RETENTION_SETTINGS_NAME)?;
+        let kinds = settings.artifacts.iter().map(|kind| kind.parse()).collect::<Result<Vec<ArtifactKind>>>()?;
+
+        let Some(completed_before) = now_secs().checked_sub(settings.retention_days * SECS_PER_DAY) else {
+            return Ok(());
+        };
+        let worker_state = WorkerState::new(STORAGE_GC_WORKER);
+        let last_collected_block = worker_state.last_processed_block().await?;
+
+        // a job can't have been completed before it was created
+        let filter = JobFilter {
+            job_type: Some(JobType::StateTransition),
+            status: Some(JobStatus::Completed),
+            created_before: Some(completed_before),
+            ..Default::default()
+        };
+        let mut cursor = None;
+        let mut collected_blocks = Vec::new();
+        let mut first_retained_block = u64::MAX;
+        let mut deleted = 0;
+        loop {
+            let page = config.database().get_jobs_paginated(filter.clone(), cursor, STORAGE_GC_BATCH_SIZE).await?;
+            for job in page.jobs {
+                let blocks = settled_blocks(&job)?;
+                if job.updated_at >= completed_before {
+                    first_retained_block = blocks.into_iter().fold(first_retained_block, u64::min);
+                    continue;
+                }
+                let uncollected = |block: &u64| last_collected_block.map_or(true, |last| *block > last);
+                for block in blocks.into_iter().filter(uncollected) {
+                    deleted += collect_block(config.storage(), &kinds, block).await?;
+                    collected_blocks.push(block);
+                }
+            }
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+
+        if deleted > 0 {
+            tracing::info!("Deleted {} artifacts of the blocks settled before {}", deleted, completed_before);
+        }
+        let collected_up_to = collected_blocks.into_iter().filter(|block| *block < first_retained_block).max();
+        if let Some(block) = collected_up_to.filter(|block| last_collected_block.map_or(true, |last| *block > last)) {
+            worker_state.set_last_processed_block(block).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Blocks settled by the state transition job
+fn settled_blocks(job: &JobItem) -> Result<Vec<u64>> {
+    match job.metadata.get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY) {
+        Some(blocks) => Ok(blocks
+            .split(',')
+            .map(|block| block.trim().parse::<u64>())
+            .collect::<std::result::Result<Vec<u64>, _>>()?),
+        None => Ok(job.internal_id.parse::<BlockRange>()?.blocks().collect()),
+    }
+}
+
+/// Deletes the artifacts of the kinds stored for the block, of every version and under the flat
+/// key of the previous schema, and returns the number of artifacts deleted
+async fn collect_block(storage: &dyn DataStorage, kinds: &[ArtifactKind], block: u64) -> Result<usize> {
+    let mut deleted = 0;
+    for kind in kinds {
+        let key = StorageKey::new(*kind, block);
+        for versioned_key in storage.list_keys(&key.block_prefix()).await? {
+            storage.delete_data(&versioned_key).await?;
+            deleted += 1;
+        }
+        let flat_key = key.flat_key();
+        if storage.data_exists(&flat_key).await? {
+            storage.delete_data(&flat_key).await?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use utils::env_utils::get_env_var_or_default;
+
+use crate::config::config;
+use crate::database::types::now_secs;
+use crate::jobs::types::{JobStatus, JobType};
+use crate::jobs::{job_process_timeout, record_status_change};
+use crate::queue::job_queue::add_job_to_process_queue;
+use crate::workers::{HaltPolicy, Worker};
+
+/// Seconds a job may stay locked for processing past the process timeout of its type before it
+/// is recovered
+pub const ENV_STUCK_JOB_GRACE_SECS: &str = "STUCK_JOB_GRACE_SECS";
+
+/// Recovers the jobs left locked for processing by an orchestrator which crashed mid-processing,
+/// which would stay locked forever otherwise.
+///
+/// A worker processing a job releases it once the process timeout of its type expires, so a job
+/// locked for longer than the timeout and the grace period isn't held by any worker anymore. The
+/// job is moved back to `Created` and added to the process queue, the version check of the
+/// update making sure that it wasn't recovered or released concurrently.
+pub struct StuckJobRecoveryWorker;
+
+#[async_trait]
+impl Worker for StuckJobRecoveryWorker {
+    async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
+        let config = config().await;
+        let grace = Duration::from_secs(get_env_var_or_default(ENV_STUCK_JOB_GRACE_SECS, "300").parse()?);
+        // the jobs locked for the shortest time of all the types are fetched, then filtered by type
+        let mut min_locked_for = Duration::MAX;
+        for job_type in JobType::ALL {
+            min_locked_for = min_locked_for.min(job_process_timeout(&job_type)? + grace);
+        }
+
+        let database = config.database();
+        for mut job in database.get_jobs_stuck_in_status(JobStatus::LockedForProcessing, min_locked_for).await? {
+            let locked_for = now_secs().saturating_sub(job.updated_at);
+            if locked_for < (job_process_timeout(&job.job_type)? + grace).as_secs() {
+                continue;
+            }
+            if let Err(e) = database.update_job_status(&job, JobStatus::Created).await {
+                tracing::warn!(job_id = %job.id, "Job was updated while being recovered, skipping it: {}", e);
+                continue;
+            }
+            job.status = JobStatus::Created;
+            let reason = format!("Recovered after being locked for processing for {} seconds", locked_for);
+            tracing::warn!(job_id = %job.id, job_type = ?job.job_type, block = %job.internal_id, "{}", reason);
+            let old_status = JobStatus::LockedForProcessing;
+            record_status_change(config.as_ref(), &job, old_status, JobStatus::Created, Some(reason)).await;
+            add_job_to_process_queue(&job).await?;
+        }
+        Ok(())
+    }
+
+    /// The jobs already created have to go through whatever the state of the other jobs
+    async fn is_worker_enabled(&self, _halt_policy: HaltPolicy) -> Result<bool, Box<dyn Error>> {
+        Ok(true)
+    }
+}
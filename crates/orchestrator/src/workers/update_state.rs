@@ -1,47 +1,121 @@
+use std::collections::HashMap;
 use std::error::Error;
 
 use async_trait::async_trait;
+use utils::settings::default::DefaultSettingsProvider;
+use utils::settings::SettingsProvider;
 
+use crate::alerts::{send_alert, AlertKind};
 use crate::config::config;
+use crate::database::types::JobFilter;
+use crate::jobs::block_range::{BlockBatchSettings, BlockRange, BLOCK_BATCH_SETTINGS_NAME};
+use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY;
 use crate::jobs::create_job;
-use crate::jobs::types::{JobStatus, JobType};
+use crate::jobs::types::{JobItem, JobStatus, JobType};
 use crate::workers::Worker;
 
 pub struct UpdateStateWorker;
 
 #[async_trait]
 impl Worker for UpdateStateWorker {
-    /// 1. Fetch the last successful state update job
-    /// 2. Fetch all successful proving jobs covering blocks after the last state update
-    /// 3. Create state updates for all the blocks that don't have a state update job
+    fn job_type(&self) -> Option<JobType> {
+        Some(JobType::StateTransition)
+    }
+
+    /// 1. Fetch the last state update job, wait for it to complete if it's still in progress
+    /// 2. Check that the last block it settled is the last block settled on the settlement layer,
+    ///    without any state update job the blocks are settled from after that last settled block
+    /// 3. Fetch the successful proving jobs of the blocks after the last settled block
+    /// 4. Create a single state update job for the consecutive proven blocks following the last
+    ///    settled block, up to `STATE_UPDATE_BATCH_SIZE` blocks. A gap stops the batch, the blocks
+    ///    are never settled out of order: the core contract reverts on them.
     async fn run_worker(&self) -> Result<(), Box<dyn Error>> {
         let config = config().await;
-        let latest_successful_job =
-            config.database().get_latest_job_by_type_and_status(JobType::StateTransition, JobStatus::Completed).await?;
-
-        match latest_successful_job {
+        let last_settled_block = match config.database().get_latest_job_by_type(JobType::StateTransition).await? {
+            Some(job) if job.status != JobStatus::Completed => {
+                tracing::info!(
+                    job_id = %job.id,
+                    block = %job.internal_id,
+                    "State update job is {:?}, waiting for it before settling the next blocks",
+                    job.status
+                );
+                return Ok(());
+            }
             Some(job) => {
-                let latest_successful_job_internal_id = job.internal_id;
-
-                let successful_proving_jobs = config
-                    .database()
-                    .get_jobs_after_internal_id_by_job_type(
-                        JobType::ProofCreation,
-                        JobStatus::Completed,
-                        latest_successful_job_internal_id,
-                    )
-                    .await?;
-
-                for job in successful_proving_jobs {
-                    create_job(JobType::StateTransition, job.internal_id, job.metadata).await?;
+                let last_settled_block = config.settlement_client().get_last_settled_block().await?;
+                let last_job_block = last_block_settled_by(&job)?;
+                if last_settled_block != last_job_block {
+                    let message = format!(
+                        "Settlement layer last settled block #{} doesn't match the block #{} of the last successful \
+                         state update job #{}, not creating state update jobs",
+                        last_settled_block, last_job_block, job.internal_id
+                    );
+                    send_alert(AlertKind::SettlementMismatch, &message).await;
+                    return Ok(());
                 }
-
-                Ok(())
+                last_settled_block
             }
             None => {
-                log::info!("No successful state update jobs found");
-                return Ok(());
+                let last_settled_block = config.settlement_client().get_last_settled_block().await?;
+                tracing::info!(block = last_settled_block, "No state update job found, settling the blocks after it");
+                last_settled_block
+            }
+        };
+
+        let settings: BlockBatchSettings = DefaultSettingsProvider {}.get_settings(BLOCK_BATCH_SETTINGS_NAME)?;
+        let first_block = last_settled_block + 1;
+        let batch_size = settings.state_transition.max(1);
+        let filter = JobFilter {
+            job_type: Some(JobType::ProofCreation),
+            status: Some(JobStatus::Completed),
+            internal_id_range: Some(first_block..=first_block.saturating_add(batch_size - 1)),
+            ..Default::default()
+        };
+        // the proving jobs have unique internal ids, the range holds at most a batch of them
+        let mut proven_blocks: Vec<u64> = config
+            .database()
+            .get_jobs_paginated(filter, None, i64::try_from(batch_size)?)
+            .await?
+            .jobs
+            .iter()
+            .filter_map(|job| job.internal_id.parse().ok())
+            .collect();
+        proven_blocks.sort();
+
+        let mut next_block = first_block;
+        for block_no in proven_blocks {
+            if block_no != next_block {
+                tracing::info!(
+                    block = next_block,
+                    "Block isn't proven yet, not settling the blocks after it (next proven block is #{})",
+                    block_no
+                );
+                break;
             }
+            next_block += 1;
         }
+        if next_block == first_block {
+            return Ok(());
+        }
+
+        let range = BlockRange::new(first_block, next_block - 1)?;
+        let blocks_to_settle = range.blocks().map(|block| block.to_string()).collect::<Vec<_>>();
+        let metadata =
+            HashMap::from([(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY.to_string(), blocks_to_settle.join(","))]);
+        create_job(JobType::StateTransition, range.to_string(), metadata).await?;
+
+        Ok(())
     }
 }
+
+/// Last block settled by a state update job: the last of its blocks to settle, or the last block of
+/// the range of its internal id.
+fn last_block_settled_by(job: &JobItem) -> Result<u64, Box<dyn Error>> {
+    let last_block = match job.metadata.get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY) {
+        Some(blocks_to_settle) => {
+            blocks_to_settle.split(',').last().map(|block_no| block_no.trim().to_string()).unwrap_or_default()
+        }
+        None => return Ok(job.internal_id.parse::<BlockRange>()?.end),
+    };
+    Ok(last_block.parse()?)
+}
@@ -0,0 +1,98 @@
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use url::Url;
+use utils::{build_http_client, trace_id_header};
+
+use crate::error::AtlanticError;
+
+/// Atlantic API endpoint
+pub const DEFAULT_ATLANTIC_URL: &str = "https://atlantic.api.herodotus.cloud";
+/// Bucket the proofs of the Atlantic queries are published in
+pub const DEFAULT_ATLANTIC_PROOFS_URL: &str = "https://atlantic-queries.s3.nl-ams.scw.cloud";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtlanticAddJobResponse {
+    pub atlantic_query_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtlanticQueryResponse {
+    pub atlantic_query: AtlanticQuery,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AtlanticQuery {
+    pub id: String,
+    pub status: AtlanticQueryStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AtlanticQueryStatus {
+    Received,
+    InProgress,
+    Done,
+    Failed,
+    /// Statuses added to the API after this client was written
+    #[serde(other)]
+    Unknown,
+}
+
+/// Atlantic API async wrapper
+pub struct AtlanticClient {
+    base_url: Url,
+    proofs_url: Url,
+    api_key: String,
+    layout: String,
+    client: reqwest::Client,
+}
+
+impl AtlanticClient {
+    pub fn new(base_url: Url, proofs_url: Url, api_key: String, layout: String) -> Self {
+        let client = build_http_client!(reqwest).expect("Failed to build the Atlantic HTTP client");
+        Self { base_url, proofs_url, api_key, layout, client }
+    }
+
+    /// Submits a PIE, zipped, to be proven through SHARP
+    pub async fn add_job(&self, pie_zip: Vec<u8>) -> Result<AtlanticAddJobResponse, AtlanticError> {
+        let form = Form::new()
+            .part("pieFile", Part::bytes(pie_zip).file_name("pie.zip"))
+            .text("layout", self.layout.clone())
+            .text("prover", "starkware_sharp");
+        let mut url = self.base_url.join("v1/proof-generation").unwrap();
+        url.query_pairs_mut().append_pair("apiKey", &self.api_key);
+        let res = trace_id_header!(self.client.post(url).multipart(form))
+            .send()
+            .await
+            .map_err(AtlanticError::AddJobFailure)?;
+
+        match res.status() {
+            code if code.is_success() => res.json().await.map_err(AtlanticError::AddJobFailure),
+            code => Err(AtlanticError::AtlanticService(code)),
+        }
+    }
+
+    pub async fn get_job_status(&self, query_id: &str) -> Result<AtlanticQueryResponse, AtlanticError> {
+        let url = self.base_url.join(&format!("v1/atlantic-query/{}", query_id)).unwrap();
+        let res = trace_id_header!(self.client.get(url)).send().await.map_err(AtlanticError::GetJobStatusFailure)?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => res.json().await.map_err(AtlanticError::GetJobStatusFailure),
+            code => Err(AtlanticError::AtlanticService(code)),
+        }
+    }
+
+    /// Proof of a query, only published once the query is done
+    pub async fn get_proof(&self, query_id: &str) -> Result<Vec<u8>, AtlanticError> {
+        let url = self.proofs_url.join(&format!("sharp_queries/query_{}/proof.json", query_id)).unwrap();
+        let res = trace_id_header!(self.client.get(url)).send().await.map_err(AtlanticError::GetProofFailure)?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => Ok(res.bytes().await.map_err(AtlanticError::GetProofFailure)?.to_vec()),
+            code => Err(AtlanticError::AtlanticService(code)),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use utils::env_utils::{get_env_var_or_default, get_env_var_or_panic};
+
+use crate::client::{DEFAULT_ATLANTIC_PROOFS_URL, DEFAULT_ATLANTIC_URL};
+
+pub const ENV_ATLANTIC_API_KEY: &str = "ATLANTIC_API_KEY";
+pub const ENV_ATLANTIC_SERVICE_URL: &str = "ATLANTIC_SERVICE_URL";
+/// Cairo layout the PIEs are proven with, `auto` (default) lets Atlantic pick the smallest one
+pub const ENV_ATLANTIC_LAYOUT: &str = "ATLANTIC_LAYOUT";
+pub const ENV_ATLANTIC_PROOFS_URL: &str = "ATLANTIC_PROOFS_URL";
+/// EVM RPC node of the chain the SHARP verifier checking the Atlantic facts is deployed on
+pub const ENV_ATLANTIC_RPC_NODE_URL: &str = "ATLANTIC_RPC_NODE_URL";
+pub const ENV_ATLANTIC_VERIFIER_ADDRESS: &str = "ATLANTIC_VERIFIER_ADDRESS";
+
+/// Atlantic proving service configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtlanticConfig {
+    /// Atlantic API url
+    pub service_url: Url,
+    /// Bucket the proofs of the Atlantic queries are published in
+    pub proofs_url: Url,
+    /// Atlantic API key
+    pub api_key: String,
+    /// Cairo layout the PIEs are proven with
+    pub layout: String,
+    /// EVM RPC node url
+    pub rpc_node_url: Url,
+    /// GPS verifier contract address (implements FactRegistry), Atlantic proves through SHARP
+    pub verifier_address: Address,
+}
+
+impl Default for AtlanticConfig {
+    /// Config read from the environment, the API url and the proofs bucket default to the public
+    /// Atlantic ones
+    fn default() -> Self {
+        Self {
+            service_url: get_env_var_or_default(ENV_ATLANTIC_SERVICE_URL, DEFAULT_ATLANTIC_URL)
+                .parse()
+                .unwrap_or_else(|_| panic!("Failed to parse {}", ENV_ATLANTIC_SERVICE_URL)),
+            proofs_url: get_env_var_or_default(ENV_ATLANTIC_PROOFS_URL, DEFAULT_ATLANTIC_PROOFS_URL)
+                .parse()
+                .unwrap_or_else(|_| panic!("Failed to parse {}", ENV_ATLANTIC_PROOFS_URL)),
+            api_key: get_env_var_or_panic(ENV_ATLANTIC_API_KEY),
+            layout: get_env_var_or_default(ENV_ATLANTIC_LAYOUT, "auto"),
+            rpc_node_url: get_env_var_or_panic(ENV_ATLANTIC_RPC_NODE_URL)
+                .parse()
+                .unwrap_or_else(|_| panic!("Failed to parse {}", ENV_ATLANTIC_RPC_NODE_URL)),
+            verifier_address: get_env_var_or_panic(ENV_ATLANTIC_VERIFIER_ADDRESS)
+                .parse()
+                .unwrap_or_else(|_| panic!("Failed to parse {}", ENV_ATLANTIC_VERIFIER_ADDRESS)),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use alloy::primitives::hex::FromHexError;
+use gps_fact_checker::error::FactCheckerError;
+use prover_client_interface::ProverClientError;
+use reqwest::StatusCode;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AtlanticError {
+    #[error("Failed to add Atlantic query: {0}")]
+    AddJobFailure(#[source] reqwest::Error),
+    #[error("Failed to get status of an Atlantic query: {0}")]
+    GetJobStatusFailure(#[source] reqwest::Error),
+    #[error("Failed to get the proof of an Atlantic query: {0}")]
+    GetProofFailure(#[source] reqwest::Error),
+    #[error("Fact checker error: {0}")]
+    FactChecker(#[from] FactCheckerError),
+    #[error("Atlantic service returned an error {0}")]
+    AtlanticService(StatusCode),
+    #[error("Failed to parse fact: {0}")]
+    FactParse(FromHexError),
+    #[error("Failed to split task id into query id and fact")]
+    TaskIdSplit,
+    #[error("Failed to encode PIE: {0}")]
+    PieEncode(#[source] std::io::Error),
+}
+
+impl From<AtlanticError> for ProverClientError {
+    fn from(value: AtlanticError) -> Self {
+        Self::Internal(Box::new(value))
+    }
+}
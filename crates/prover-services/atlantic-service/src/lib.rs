@@ -0,0 +1,165 @@
+pub mod client;
+pub mod config;
+pub mod error;
+
+use std::str::FromStr;
+
+use alloy::primitives::B256;
+use async_trait::async_trait;
+use cairo_vm::vm::runners::cairo_pie::CairoPie;
+use gps_fact_checker::fact_info::get_fact_info;
+use gps_fact_checker::FactChecker;
+use prover_client_interface::{ProverClient, ProverClientError, Task, TaskId, TaskStatus};
+use utils::settings::SettingsProvider;
+
+use crate::client::{AtlanticClient, AtlanticQueryStatus};
+use crate::config::AtlanticConfig;
+use crate::error::AtlanticError;
+
+pub const ATLANTIC_SETTINGS_NAME: &str = "atlantic";
+
+/// Atlantic is a proving service hosted by Herodotus, the PIEs are proven through SHARP and the
+/// facts registered in the SHARP verifier.
+pub struct AtlanticProverService {
+    atlantic_client: AtlanticClient,
+    fact_checker: FactChecker,
+}
+
+#[async_trait]
+impl ProverClient for AtlanticProverService {
+    async fn submit_task(&self, task: Task) -> Result<TaskId, ProverClientError> {
+        match task {
+            Task::CairoPie(cairo_pie) => {
+                let fact_info = get_fact_info(&cairo_pie, None)?;
+                let pie_zip = zip_pie(&cairo_pie)?;
+                let res = self.atlantic_client.add_job(pie_zip).await?;
+                Ok(combine_task_id(&res.atlantic_query_id, &fact_info.fact))
+            }
+            Task::AggregateProofs(_) => {
+                Err(ProverClientError::TaskUnsupported("Atlantic doesn't aggregate proofs".into()))
+            }
+        }
+    }
+
+    async fn get_task_status(&self, task_id: &TaskId) -> Result<TaskStatus, ProverClientError> {
+        let (query_id, fact) = split_task_id(task_id)?;
+        let res = self.atlantic_client.get_job_status(&query_id).await?;
+        match res.atlantic_query.status {
+            AtlanticQueryStatus::Failed => Ok(TaskStatus::Failed(res.atlantic_query.error.unwrap_or_default())),
+            AtlanticQueryStatus::Received | AtlanticQueryStatus::InProgress | AtlanticQueryStatus::Unknown => {
+                Ok(TaskStatus::Processing)
+            }
+            AtlanticQueryStatus::Done => {
+                if self.fact_checker.is_valid(&fact).await? {
+                    Ok(TaskStatus::Succeeded)
+                } else {
+                    Ok(TaskStatus::Failed(format!("Fact {} is not valid or not registed", hex::encode(fact))))
+                }
+            }
+        }
+    }
+
+    async fn get_proof(&self, task_id: &TaskId) -> Result<Option<Vec<u8>>, ProverClientError> {
+        let (query_id, _) = split_task_id(task_id)?;
+        Ok(Some(self.atlantic_client.get_proof(&query_id).await?))
+    }
+
+    fn registers_facts(&self) -> bool {
+        true
+    }
+}
+
+impl AtlanticProverService {
+    pub fn new(atlantic_client: AtlanticClient, fact_checker: FactChecker) -> Self {
+        Self { atlantic_client, fact_checker }
+    }
+
+    pub fn with_settings(settings: &impl SettingsProvider) -> Self {
+        let atlantic_cfg: AtlanticConfig = settings.get_settings(ATLANTIC_SETTINGS_NAME).unwrap();
+        let atlantic_client = AtlanticClient::new(
+            atlantic_cfg.service_url,
+            atlantic_cfg.proofs_url,
+            atlantic_cfg.api_key,
+            atlantic_cfg.layout,
+        );
+        let fact_checker = FactChecker::new(atlantic_cfg.rpc_node_url, atlantic_cfg.verifier_address);
+        Self::new(atlantic_client, fact_checker)
+    }
+}
+
+/// Atlantic takes the PIEs as zip files
+fn zip_pie(cairo_pie: &CairoPie) -> Result<Vec<u8>, AtlanticError> {
+    let file = tempfile::NamedTempFile::new().map_err(AtlanticError::PieEncode)?;
+    cairo_pie.write_zip_file(file.path()).map_err(AtlanticError::PieEncode)?;
+    std::fs::read(file.path()).map_err(AtlanticError::PieEncode)
+}
+
+/// Construct Atlantic specific task ID from query id and proof fact
+pub fn combine_task_id(query_id: &str, fact: &B256) -> TaskId {
+    format!("{}:{}", query_id, fact)
+}
+
+/// Split task ID into Atlantic query id and proof fact
+pub fn split_task_id(task_id: &TaskId) -> Result<(String, B256), AtlanticError> {
+    let (query_id, fact_str) = task_id.rsplit_once(':').ok_or(AtlanticError::TaskIdSplit)?;
+    let fact = B256::from_str(fact_str).map_err(AtlanticError::FactParse)?;
+    Ok((query_id.to_string(), fact))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, B256};
+    use gps_fact_checker::FactChecker;
+    use httpmock::prelude::*;
+    use prover_client_interface::{ProverClient, TaskStatus};
+    use serde_json::json;
+
+    use crate::client::AtlanticClient;
+    use crate::{combine_task_id, split_task_id, AtlanticProverService};
+
+    fn atlantic_service(server: &MockServer) -> AtlanticProverService {
+        let url: url::Url = server.base_url().parse().unwrap();
+        let atlantic_client = AtlanticClient::new(url.clone(), url.clone(), "api_key".into(), "auto".into());
+        AtlanticProverService::new(atlantic_client, FactChecker::new(url, Address::ZERO))
+    }
+
+    #[test]
+    fn atlantic_task_id_roundtrip() {
+        let fact = B256::repeat_byte(0xab);
+        let task_id = combine_task_id("01J9A6Y6XRN3CQ3SBVB5P2VQ0F", &fact);
+        assert_eq!(split_task_id(&task_id).unwrap(), ("01J9A6Y6XRN3CQ3SBVB5P2VQ0F".to_string(), fact));
+    }
+
+    #[tokio::test]
+    async fn atlantic_task_status() {
+        let server = MockServer::start();
+        let service = atlantic_service(&server);
+        for (query_id, status, expected) in [
+            ("in_progress", "IN_PROGRESS", TaskStatus::Processing),
+            ("new_status", "PENDING_VERIFICATION", TaskStatus::Processing),
+            ("failed", "FAILED", TaskStatus::Failed("out of memory".into())),
+        ] {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/v1/atlantic-query/{}", query_id));
+                then.status(200).json_body(json!({
+                    "atlanticQuery": { "id": query_id, "status": status, "error": "out of memory" }
+                }));
+            });
+            let task_id = combine_task_id(query_id, &B256::ZERO);
+            assert_eq!(service.get_task_status(&task_id).await.unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn atlantic_proof() {
+        let server = MockServer::start();
+        let service = atlantic_service(&server);
+        server.mock(|when, then| {
+            when.method(GET).path("/sharp_queries/query_done/proof.json");
+            then.status(200).body("{\"proof\": []}");
+        });
+
+        let proof = service.get_proof(&combine_task_id("done", &B256::ZERO)).await.unwrap();
+        assert_eq!(proof, Some(b"{\"proof\": []}".to_vec()));
+    }
+}
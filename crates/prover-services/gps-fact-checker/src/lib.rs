@@ -5,9 +5,11 @@ pub mod fact_topology;
 
 use alloy::primitives::{Address, B256};
 use alloy::providers::{ProviderBuilder, RootProvider};
+use alloy::rpc::client::RpcClient;
 use alloy::sol;
 use alloy::transports::http::{Client, Http};
 use url::Url;
+use utils::build_http_client;
 
 use self::error::FactCheckerError;
 
@@ -27,7 +29,10 @@ type ProviderT = RootProvider<TransportT>;
 
 impl FactChecker {
     pub fn new(rpc_node_url: Url, verifier_address: Address) -> Self {
-        let provider = ProviderBuilder::new().on_http(rpc_node_url);
+        let http_client = build_http_client!(alloy::transports::http::reqwest)
+            .expect("Failed to build the fact registry HTTP client");
+        let provider =
+            ProviderBuilder::new().on_client(RpcClient::new(Http::with_client(http_client, rpc_node_url), false));
         let fact_registry = FactRegistry::new(verifier_address, provider);
         Self { fact_registry }
     }
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_default;
+
+pub const ENV_MOCK_PROVER_DELAY_SECS: &str = "MOCK_PROVER_DELAY_SECS";
+pub const ENV_MOCK_PROVER_FAILURE_RATE: &str = "MOCK_PROVER_FAILURE_RATE";
+pub const ENV_MOCK_PROVER_SEED: &str = "MOCK_PROVER_SEED";
+
+/// Mock prover configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockProverConfig {
+    /// Time a task spends processing before its outcome is known
+    pub delay_secs: u64,
+    /// Share of the tasks failing, between 0 and 1
+    pub failure_rate: f64,
+    /// Seed picking the failing tasks, the same tasks fail for the same seed
+    pub seed: u64,
+}
+
+impl Default for MockProverConfig {
+    /// Config from the environment, every task succeeds right away by default
+    fn default() -> Self {
+        Self {
+            delay_secs: get_env_var_or_default(ENV_MOCK_PROVER_DELAY_SECS, "0")
+                .parse()
+                .expect("Failed to parse MOCK_PROVER_DELAY_SECS"),
+            failure_rate: get_env_var_or_default(ENV_MOCK_PROVER_FAILURE_RATE, "0")
+                .parse()
+                .expect("Failed to parse MOCK_PROVER_FAILURE_RATE"),
+            seed: get_env_var_or_default(ENV_MOCK_PROVER_SEED, "0").parse().expect("Failed to parse MOCK_PROVER_SEED"),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use prover_client_interface::ProverClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MockProverError {
+    #[error("Failed to parse task id: {0}")]
+    TaskIdParse(String),
+    #[error("Failure rate must be between 0 and 1, got {0}")]
+    InvalidFailureRate(f64),
+}
+
+impl From<MockProverError> for ProverClientError {
+    fn from(value: MockProverError) -> Self {
+        Self::Internal(Box::new(value))
+    }
+}
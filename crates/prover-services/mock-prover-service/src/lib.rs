@@ -0,0 +1,160 @@
+pub mod config;
+pub mod error;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::{keccak256, B256};
+use async_trait::async_trait;
+use gps_fact_checker::fact_info::get_fact_info;
+use prover_client_interface::{ProverClient, ProverClientError, Task, TaskId, TaskStatus};
+use utils::settings::SettingsProvider;
+
+use crate::config::MockProverConfig;
+use crate::error::MockProverError;
+
+pub const MOCK_PROVER_SETTINGS_NAME: &str = "mock_prover";
+
+/// Prover for the tests, proving nothing. The fact of a task is the one of its PIE (or the hash of
+/// the proofs it aggregates) so the same inputs always get the same fact, and whether a task fails
+/// only depends on the fact and the seed.
+///
+/// The task id is `<fact>:<submission timestamp>`, the status of a task is derived from its id
+/// alone and survives restarts.
+pub struct MockProverService {
+    config: MockProverConfig,
+}
+
+#[async_trait]
+impl ProverClient for MockProverService {
+    async fn submit_task(&self, task: Task) -> Result<TaskId, ProverClientError> {
+        let fact = match task {
+            Task::CairoPie(cairo_pie) => get_fact_info(&cairo_pie, None)?.fact,
+            Task::AggregateProofs(proofs) => keccak256(proofs.concat()),
+        };
+        let task_id = format!("{}:{}", fact, now_secs());
+        tracing::info!("Mock prover: task {} submitted", task_id);
+        Ok(task_id)
+    }
+
+    async fn get_task_status(&self, task_id: &TaskId) -> Result<TaskStatus, ProverClientError> {
+        let (fact, submitted_at) = split_task_id(task_id)?;
+        if now_secs() < submitted_at.saturating_add(self.config.delay_secs) {
+            return Ok(TaskStatus::Processing);
+        }
+        if self.fails(&fact) {
+            return Ok(TaskStatus::Failed(format!("Mock prover failed task {}", task_id)));
+        }
+        Ok(TaskStatus::Succeeded)
+    }
+
+    /// Fake proof naming the fact of the task
+    async fn get_proof(&self, task_id: &TaskId) -> Result<Option<Vec<u8>>, ProverClientError> {
+        match self.get_task_status(task_id).await? {
+            TaskStatus::Succeeded => {
+                let (fact, _) = split_task_id(task_id)?;
+                Ok(Some(format!("{{\"mock_proof\": \"{}\"}}", fact).into_bytes()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The facts of the mock proofs aren't registered anywhere
+    fn registers_facts(&self) -> bool {
+        false
+    }
+}
+
+impl MockProverService {
+    pub fn new(config: MockProverConfig) -> Result<Self, MockProverError> {
+        if !(0.0..=1.0).contains(&config.failure_rate) {
+            return Err(MockProverError::InvalidFailureRate(config.failure_rate));
+        }
+        Ok(Self { config })
+    }
+
+    pub fn with_settings(settings: &impl SettingsProvider) -> Self {
+        let mock_prover_cfg: MockProverConfig = settings.get_settings(MOCK_PROVER_SETTINGS_NAME).unwrap();
+        Self::new(mock_prover_cfg).expect("Invalid mock prover config")
+    }
+
+    /// Draws the outcome of the task from the fact and the seed
+    fn fails(&self, fact: &B256) -> bool {
+        let draw = keccak256([self.config.seed.to_be_bytes().as_slice(), fact.as_slice()].concat());
+        let draw = u64::from_be_bytes(draw[..8].try_into().expect("Hash is 32 bytes long"));
+        (draw as f64 / u64::MAX as f64) < self.config.failure_rate
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("System time is before the UNIX epoch").as_secs()
+}
+
+fn split_task_id(task_id: &TaskId) -> Result<(B256, u64), MockProverError> {
+    let (fact, submitted_at) =
+        task_id.split_once(':').ok_or_else(|| MockProverError::TaskIdParse(task_id.clone()))?;
+    let fact = fact.parse().map_err(|_| MockProverError::TaskIdParse(task_id.clone()))?;
+    let submitted_at = submitted_at.parse().map_err(|_| MockProverError::TaskIdParse(task_id.clone()))?;
+    Ok((fact, submitted_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use cairo_vm::vm::runners::cairo_pie::CairoPie;
+    use prover_client_interface::{ProverClient, Task, TaskStatus};
+
+    use crate::config::MockProverConfig;
+    use crate::{now_secs, MockProverService};
+
+    fn mock_prover(delay_secs: u64, failure_rate: f64) -> MockProverService {
+        MockProverService::new(MockProverConfig { delay_secs, failure_rate, seed: 0 }).unwrap()
+    }
+
+    fn fibonacci_pie() -> CairoPie {
+        let cairo_pie_path: PathBuf =
+            [env!("CARGO_MANIFEST_DIR"), "..", "sharp-service", "tests", "artifacts", "fibonacci.zip"]
+                .iter()
+                .collect();
+        CairoPie::read_zip_file(Path::new(&cairo_pie_path)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn mock_prover_facts_are_deterministic() {
+        let service = mock_prover(0, 0.0);
+
+        let task_id = service.submit_task(Task::CairoPie(fibonacci_pie())).await.unwrap();
+        let other_task_id = service.submit_task(Task::CairoPie(fibonacci_pie())).await.unwrap();
+
+        assert_eq!(task_id.split_once(':').unwrap().0, other_task_id.split_once(':').unwrap().0);
+        assert_eq!(service.get_task_status(&task_id).await.unwrap(), TaskStatus::Succeeded);
+        assert!(service.get_proof(&task_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn mock_prover_task_processes_for_the_delay() {
+        let service = mock_prover(60, 0.0);
+
+        let task_id = service.submit_task(Task::AggregateProofs(vec![b"proof".to_vec()])).await.unwrap();
+        assert_eq!(service.get_task_status(&task_id).await.unwrap(), TaskStatus::Processing);
+        assert_eq!(service.get_proof(&task_id).await.unwrap(), None);
+
+        let (fact, _) = task_id.split_once(':').unwrap();
+        let submitted_earlier = format!("{}:{}", fact, now_secs() - 60);
+        assert_eq!(service.get_task_status(&submitted_earlier).await.unwrap(), TaskStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn mock_prover_fails_with_failure_rate() {
+        let service = mock_prover(0, 1.0);
+
+        let task_id = service.submit_task(Task::CairoPie(fibonacci_pie())).await.unwrap();
+        assert!(matches!(service.get_task_status(&task_id).await.unwrap(), TaskStatus::Failed(_)));
+        assert_eq!(service.get_proof(&task_id).await.unwrap(), None);
+    }
+
+    #[test]
+    fn mock_prover_rejects_invalid_failure_rate() {
+        assert!(MockProverService::new(MockProverConfig { delay_secs: 0, failure_rate: 1.5, seed: 0 }).is_err());
+    }
+}
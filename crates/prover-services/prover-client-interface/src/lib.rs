@@ -15,10 +15,17 @@ use mockall::automock;
 pub trait ProverClient: Send + Sync {
     async fn submit_task(&self, task: Task) -> Result<TaskId, ProverClientError>;
     async fn get_task_status(&self, task_id: &TaskId) -> Result<TaskStatus, ProverClientError>;
+    /// Proof of a succeeded task, `None` for the services which register the proof onchain
+    /// themselves without handing it out (e.g. SHARP)
+    async fn get_proof(&self, task_id: &TaskId) -> Result<Option<Vec<u8>>, ProverClientError>;
+    /// Whether the facts of the proofs end up registered onchain, and can be checked there
+    fn registers_facts(&self) -> bool;
 }
 
 pub enum Task {
     CairoPie(CairoPie),
+    /// Proofs of consecutive blocks, in order, to combine into a single recursive proof
+    AggregateProofs(Vec<Vec<u8>>),
 }
 
 pub type TaskId = String;
@@ -38,6 +45,8 @@ pub enum ProverClientError {
     SettingsProvider(#[from] utils::settings::SettingsProviderError),
     #[error("Task is invalid: {0}")]
     TaskInvalid(TaskId),
+    #[error("Task is not supported by the prover: {0}")]
+    TaskUnsupported(String),
     #[error("Fact checker error: {0}")]
     FactChecker(#[from] gps_fact_checker::error::FactCheckerError),
     #[error("Failed to encode Cairo PIE: {0}")]
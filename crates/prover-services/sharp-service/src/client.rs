@@ -1,6 +1,7 @@
 use serde_json::json;
 use snos::sharp::{CairoJobResponse, CairoStatusResponse};
 use url::Url;
+use utils::{build_http_client, trace_id_header};
 use uuid::Uuid;
 
 use crate::error::SharpError;
@@ -16,13 +17,14 @@ pub struct SharpClient {
 
 impl SharpClient {
     pub fn new(url: Url) -> Self {
-        Self { base_url: url, client: reqwest::Client::new() }
+        let client = build_http_client!(reqwest).expect("Failed to build the SHARP HTTP client");
+        Self { base_url: url, client }
     }
 
     pub async fn add_job(&self, encoded_pie: &str) -> Result<CairoJobResponse, SharpError> {
         let data = json!({ "action": "add_job", "request": { "cairo_pie": encoded_pie } });
         let url = self.base_url.join("add_job").unwrap();
-        let res = self.client.post(url).json(&data).send().await.map_err(SharpError::AddJobFailure)?;
+        let res = trace_id_header!(self.client.post(url).json(&data)).send().await.map_err(SharpError::AddJobFailure)?;
 
         match res.status() {
             reqwest::StatusCode::OK => res.json().await.map_err(SharpError::AddJobFailure),
@@ -33,7 +35,8 @@ impl SharpClient {
     pub async fn get_job_status(&self, job_key: &Uuid) -> Result<CairoStatusResponse, SharpError> {
         let data = json!({ "action": "get_status", "request": { "cairo_job_key": job_key } });
         let url = self.base_url.join("get_status").unwrap();
-        let res = self.client.post(url).json(&data).send().await.map_err(SharpError::GetJobStatusFailure)?;
+        let res =
+            trace_id_header!(self.client.post(url).json(&data)).send().await.map_err(SharpError::GetJobStatusFailure)?;
 
         match res.status() {
             reqwest::StatusCode::OK => res.json().await.map_err(SharpError::GetJobStatusFailure),
@@ -40,6 +40,9 @@ impl ProverClient for SharpProverService {
                     Err(ProverClientError::TaskInvalid(res.error_message.unwrap_or_default()))
                 }
             }
+            Task::AggregateProofs(_) => {
+                Err(ProverClientError::TaskUnsupported("SHARP doesn't aggregate proofs".into()))
+            }
         }
     }
 
@@ -64,6 +67,15 @@ impl ProverClient for SharpProverService {
             }
         }
     }
+
+    /// SHARP registers the proof onchain, it isn't handed out
+    async fn get_proof(&self, _task_id: &TaskId) -> Result<Option<Vec<u8>>, ProverClientError> {
+        Ok(None)
+    }
+
+    fn registers_facts(&self) -> bool {
+        true
+    }
 }
 
 impl SharpProverService {
@@ -99,8 +111,6 @@ mod tests {
 
     use cairo_vm::vm::runners::cairo_pie::CairoPie;
     use prover_client_interface::{ProverClient, Task, TaskStatus};
-    use tracing::log::log;
-    use tracing::log::Level::{Error, Info};
     use utils::settings::default::DefaultSettingsProvider;
 
     use crate::SharpProverService;
@@ -116,25 +126,25 @@ mod tests {
         let cairo_pie = CairoPie::read_zip_file(&cairo_pie_path).unwrap();
         // Submit task to the testnet prover
         let task_id = sharp_service.submit_task(Task::CairoPie(cairo_pie)).await.unwrap();
-        log!(Info, "SHARP: task {} submitted", task_id);
+        tracing::info!("SHARP: task {} submitted", task_id);
         for attempt in 0..10 {
             tokio::time::sleep(Duration::from_millis((attempt + 1) * 1000)).await;
             match sharp_service.get_task_status(&task_id).await.unwrap() {
                 TaskStatus::Failed(err) => {
-                    log!(Error, "SHARP: task failed with {}", err);
+                    tracing::error!("SHARP: task failed with {}", err);
                     panic!("Task failed");
                 }
                 TaskStatus::Processing => {
-                    log!(Info, "SHARP: task is processing (attempt {})", attempt);
+                    tracing::info!("SHARP: task is processing (attempt {})", attempt);
                     continue;
                 }
                 TaskStatus::Succeeded => {
-                    log!(Info, "SHARP: task is completed");
+                    tracing::info!("SHARP: task is completed");
                     return;
                 }
             }
         }
-        log!(Error, "SHARP: waiting timeout");
+        tracing::error!("SHARP: waiting timeout");
         panic!("Out of attempts");
     }
 }
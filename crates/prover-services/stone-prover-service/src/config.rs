@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_default;
+
+pub const ENV_STONE_PROVER_BINARY: &str = "STONE_PROVER_BINARY";
+/// Space separated
+pub const ENV_STONE_PROVER_ARGS: &str = "STONE_PROVER_ARGS";
+pub const ENV_STONE_PROVER_WORKING_DIR: &str = "STONE_PROVER_WORKING_DIR";
+
+/// Local Stone prover configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoneConfig {
+    /// Binary proving a Cairo PIE with the Stone prover (running the bootloader, then
+    /// `cpu_air_prover`). It is called with `--cairo_pie <pie.zip> --output <proof.json>`, or
+    /// `--proof <proof.json>... --output <proof.json>` to aggregate proofs recursively, followed by
+    /// `prover_args`.
+    pub prover_binary: PathBuf,
+    /// Additional arguments of the prover binary, e.g. the prover config and parameter files
+    pub prover_args: Vec<String>,
+    /// Directory the PIE and the proof of each task are written in
+    pub working_dir: PathBuf,
+}
+
+impl Default for StoneConfig {
+    /// Config from the environment, the binary is looked up in the `PATH` by default
+    fn default() -> Self {
+        let default_working_dir = std::env::temp_dir().join("stone-prover");
+        Self {
+            prover_binary: get_env_var_or_default(ENV_STONE_PROVER_BINARY, "stone-prover").into(),
+            prover_args: get_env_var_or_default(ENV_STONE_PROVER_ARGS, "")
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            working_dir: get_env_var_or_default(
+                ENV_STONE_PROVER_WORKING_DIR,
+                default_working_dir.to_str().expect("Temporary directory is not valid UTF-8"),
+            )
+            .into(),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use prover_client_interface::ProverClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StoneError {
+    #[error("Failed to prepare the directory of the task: {0}")]
+    TaskDir(#[source] std::io::Error),
+    #[error("Failed to write the Cairo PIE: {0}")]
+    PieWrite(#[source] std::io::Error),
+    #[error("Failed to spawn the Stone prover: {0}")]
+    Spawn(#[source] std::io::Error),
+    #[error("Failed to read the proof: {0}")]
+    ProofRead(#[source] std::io::Error),
+    #[error("Failed to parse task id: {0}")]
+    TaskIdParse(uuid::Error),
+}
+
+impl From<StoneError> for ProverClientError {
+    fn from(value: StoneError) -> Self {
+        Self::Internal(Box::new(value))
+    }
+}
@@ -0,0 +1,227 @@
+pub mod config;
+pub mod error;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use prover_client_interface::{ProverClient, ProverClientError, Task, TaskId, TaskStatus};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use utils::settings::SettingsProvider;
+use uuid::Uuid;
+
+use crate::config::StoneConfig;
+use crate::error::StoneError;
+
+pub const STONE_SETTINGS_NAME: &str = "stone";
+
+const PIE_FILE_NAME: &str = "cairo_pie.zip";
+const PROOF_FILE_NAME: &str = "proof.json";
+
+/// Status of a prover run, only known by the process which spawned it
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RunStatus {
+    Running,
+    Succeeded,
+    Failed(String),
+}
+
+/// Self-hosted alternative to SHARP: proves the PIEs with the Stone prover running on this
+/// machine. The proofs aren't registered onchain, they are handed out with `get_proof`.
+pub struct StoneProverService {
+    config: StoneConfig,
+    runs: Arc<Mutex<HashMap<TaskId, RunStatus>>>,
+}
+
+#[async_trait]
+impl ProverClient for StoneProverService {
+    /// Writes the inputs in the directory of a new task and spawns the prover on them, the task is
+    /// processing until the prover exits.
+    async fn submit_task(&self, task: Task) -> Result<TaskId, ProverClientError> {
+        let task_id = Uuid::new_v4().to_string();
+        let task_dir = self.task_dir(&task_id)?;
+        tokio::fs::create_dir_all(&task_dir).await.map_err(StoneError::TaskDir)?;
+
+        let mut args: Vec<OsString> = Vec::new();
+        match task {
+            Task::CairoPie(cairo_pie) => {
+                let pie_path = task_dir.join(PIE_FILE_NAME);
+                cairo_pie.write_zip_file(&pie_path).map_err(StoneError::PieWrite)?;
+                args.push("--cairo_pie".into());
+                args.push(pie_path.into_os_string());
+            }
+            Task::AggregateProofs(proofs) => {
+                for (index, proof) in proofs.into_iter().enumerate() {
+                    let proof_path = task_dir.join(format!("proof_{}.json", index));
+                    tokio::fs::write(&proof_path, proof).await.map_err(StoneError::TaskDir)?;
+                    args.push("--proof".into());
+                    args.push(proof_path.into_os_string());
+                }
+            }
+        }
+        args.push("--output".into());
+        args.push(task_dir.join(PROOF_FILE_NAME).into_os_string());
+
+        let child = Command::new(&self.config.prover_binary)
+            .args(args)
+            .args(&self.config.prover_args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(StoneError::Spawn)?;
+        self.runs.lock().await.insert(task_id.clone(), RunStatus::Running);
+
+        let runs = self.runs.clone();
+        let run_id = task_id.clone();
+        tokio::spawn(async move {
+            let status = match child.wait_with_output().await {
+                Ok(output) if output.status.success() => RunStatus::Succeeded,
+                Ok(output) => RunStatus::Failed(format!(
+                    "Stone prover exited with {}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )),
+                Err(e) => RunStatus::Failed(format!("Failed to wait for the Stone prover: {}", e)),
+            };
+            runs.lock().await.insert(run_id, status);
+        });
+        tracing::info!("Stone prover: task {} started", task_id);
+        Ok(task_id)
+    }
+
+    async fn get_task_status(&self, task_id: &TaskId) -> Result<TaskStatus, ProverClientError> {
+        let run_status = self.runs.lock().await.get(task_id).cloned();
+        match run_status {
+            Some(RunStatus::Running) => Ok(TaskStatus::Processing),
+            Some(RunStatus::Failed(err)) => Ok(TaskStatus::Failed(err)),
+            // a task unknown to this process may have been proven before a restart
+            Some(RunStatus::Succeeded) | None => {
+                if tokio::fs::try_exists(self.task_dir(task_id)?.join(PROOF_FILE_NAME)).await.unwrap_or(false) {
+                    Ok(TaskStatus::Succeeded)
+                } else if run_status.is_some() {
+                    Ok(TaskStatus::Failed(format!("Stone prover didn't write the proof of task {}", task_id)))
+                } else {
+                    Ok(TaskStatus::Failed(format!("Task not found: {}", task_id)))
+                }
+            }
+        }
+    }
+
+    async fn get_proof(&self, task_id: &TaskId) -> Result<Option<Vec<u8>>, ProverClientError> {
+        match tokio::fs::read(self.task_dir(task_id)?.join(PROOF_FILE_NAME)).await {
+            Ok(proof) => Ok(Some(proof)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(StoneError::ProofRead(e).into()),
+        }
+    }
+
+    fn registers_facts(&self) -> bool {
+        false
+    }
+}
+
+impl StoneProverService {
+    pub fn new(config: StoneConfig) -> Self {
+        Self { config, runs: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    pub fn with_settings(settings: &impl SettingsProvider) -> Self {
+        let stone_cfg: StoneConfig = settings.get_settings(STONE_SETTINGS_NAME).unwrap();
+        Self::new(stone_cfg)
+    }
+
+    /// Directory of the task, the task id must be one handed out by `submit_task`
+    fn task_dir(&self, task_id: &TaskId) -> Result<PathBuf, StoneError> {
+        let task_id = Uuid::parse_str(task_id).map_err(StoneError::TaskIdParse)?;
+        Ok(self.config.working_dir.join(task_id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use cairo_vm::vm::runners::cairo_pie::CairoPie;
+    use prover_client_interface::{ProverClient, Task, TaskId, TaskStatus};
+    use tempfile::TempDir;
+
+    use crate::config::StoneConfig;
+    use crate::StoneProverService;
+
+    /// Service running `script` as the prover binary, it gets `--cairo_pie <pie> --output <proof>`
+    fn stone_service(working_dir: &TempDir, script: &str) -> StoneProverService {
+        let prover_binary = working_dir.path().join("prover.sh");
+        std::fs::write(&prover_binary, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&prover_binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        StoneProverService::new(StoneConfig {
+            prover_binary,
+            prover_args: vec![],
+            working_dir: working_dir.path().to_path_buf(),
+        })
+    }
+
+    fn fibonacci_pie() -> CairoPie {
+        let cairo_pie_path: PathBuf =
+            [env!("CARGO_MANIFEST_DIR"), "..", "sharp-service", "tests", "artifacts", "fibonacci.zip"]
+                .iter()
+                .collect();
+        CairoPie::read_zip_file(Path::new(&cairo_pie_path)).unwrap()
+    }
+
+    async fn wait_for_task(service: &StoneProverService, task_id: &TaskId) -> TaskStatus {
+        for _ in 0..50 {
+            match service.get_task_status(task_id).await.unwrap() {
+                TaskStatus::Processing => tokio::time::sleep(Duration::from_millis(100)).await,
+                status => return status,
+            }
+        }
+        panic!("Stone prover task {} is still processing", task_id);
+    }
+
+    #[tokio::test]
+    async fn stone_task_succeeds_with_proof() {
+        let working_dir = TempDir::new().unwrap();
+        let service = stone_service(&working_dir, r#"echo '{"proof_hex": "0x1"}' > "$4""#);
+
+        let task_id = service.submit_task(Task::CairoPie(fibonacci_pie())).await.unwrap();
+
+        assert_eq!(wait_for_task(&service, &task_id).await, TaskStatus::Succeeded);
+        assert_eq!(service.get_proof(&task_id).await.unwrap(), Some(b"{\"proof_hex\": \"0x1\"}\n".to_vec()));
+        // the proof outlives the process which ran the prover
+        let restarted = stone_service(&working_dir, "exit 1");
+        assert_eq!(restarted.get_task_status(&task_id).await.unwrap(), TaskStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn stone_aggregation_gets_the_proofs_in_order() {
+        let working_dir = TempDir::new().unwrap();
+        let service = stone_service(&working_dir, r#"cat "$2" "$4" > "$6""#);
+
+        let task = Task::AggregateProofs(vec![b"proof 1\n".to_vec(), b"proof 2\n".to_vec()]);
+        let task_id = service.submit_task(task).await.unwrap();
+
+        assert_eq!(wait_for_task(&service, &task_id).await, TaskStatus::Succeeded);
+        assert_eq!(service.get_proof(&task_id).await.unwrap(), Some(b"proof 1\nproof 2\n".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn stone_task_fails_with_prover() {
+        let working_dir = TempDir::new().unwrap();
+        let service = stone_service(&working_dir, "echo 'out of memory' >&2; exit 1");
+
+        let task_id = service.submit_task(Task::CairoPie(fibonacci_pie())).await.unwrap();
+
+        match wait_for_task(&service, &task_id).await {
+            TaskStatus::Failed(err) => assert!(err.contains("out of memory"), "unexpected error: {}", err),
+            status => panic!("Expected the task to fail, got {:?}", status),
+        }
+        assert_eq!(service.get_proof(&task_id).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use alloy::{network::Ethereum, primitives::Address, transports::http::Http};
+
+use crate::clients::interfaces::batcher_interface::SettlementBatcher;
+use crate::types::LocalWalletSignerMiddleware;
+
+/// Client to interact with the settlement batcher, the contract batching the settlement
+/// operations on behalf of its owner
+pub struct SettlementBatcherClient {
+    batcher:
+        SettlementBatcher::SettlementBatcherInstance<Http<reqwest::Client>, Arc<LocalWalletSignerMiddleware>, Ethereum>,
+}
+
+impl SettlementBatcherClient {
+    pub fn new(address: Address, client: Arc<LocalWalletSignerMiddleware>) -> Self {
+        Self { batcher: SettlementBatcher::new(address, client.clone()) }
+    }
+    pub fn contract_address(&self) -> Address {
+        *self.batcher.address()
+    }
+}
+
+impl
+    AsRef<
+        SettlementBatcher::SettlementBatcherInstance<
+            Http<reqwest::Client>,
+            Arc<LocalWalletSignerMiddleware>,
+            Ethereum,
+        >,
+    > for SettlementBatcherClient
+{
+    fn as_ref(
+        &self,
+    ) -> &SettlementBatcher::SettlementBatcherInstance<
+        Http<reqwest::Client>,
+        Arc<LocalWalletSignerMiddleware>,
+        Ethereum,
+    > {
+        &self.batcher
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use alloy::{network::Ethereum, primitives::Address, transports::http::Http};
+
+use crate::clients::interfaces::fact_registry_interface::FactRegistry;
+use crate::types::LocalWalletSignerMiddleware;
+
+// TODO: should be moved to Zaun:
+// https://github.com/keep-starknet-strange/zaun
+
+/// Client to interact with the fact registry of the SHARP verifier
+pub struct FactRegistryClient {
+    registry: FactRegistry::FactRegistryInstance<Http<reqwest::Client>, Arc<LocalWalletSignerMiddleware>, Ethereum>,
+}
+
+impl FactRegistryClient {
+    pub fn new(address: Address, client: Arc<LocalWalletSignerMiddleware>) -> Self {
+        Self { registry: FactRegistry::new(address, client.clone()) }
+    }
+}
+
+impl AsRef<FactRegistry::FactRegistryInstance<Http<reqwest::Client>, Arc<LocalWalletSignerMiddleware>, Ethereum>>
+    for FactRegistryClient
+{
+    fn as_ref(
+        &self,
+    ) -> &FactRegistry::FactRegistryInstance<Http<reqwest::Client>, Arc<LocalWalletSignerMiddleware>, Ethereum> {
+        &self.registry
+    }
+}
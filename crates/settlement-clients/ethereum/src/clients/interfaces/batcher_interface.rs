@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, B256},
+    sol,
+    transports::http::Http,
+};
+
+use crate::fees::Eip1559Fees;
+use crate::types::LocalWalletSignerMiddleware;
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface SettlementBatcher {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        /// Same as the `aggregate3` of Multicall3, reverts unless called by the owner
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+
+        function owner() external view returns (address);
+    }
+}
+
+#[async_trait]
+pub trait SettlementBatcherTrait {
+    /// Account the batcher executes the calls of, the others are rejected
+    async fn owner(&self) -> Result<Address, alloy::contract::Error>;
+
+    /// Simulates `aggregate3` from `from` with an `eth_call`, fails if the transaction would revert.
+    async fn simulate_aggregate3(
+        &self,
+        from: Address,
+        calls: Vec<SettlementBatcher::Call3>,
+    ) -> Result<(), alloy::contract::Error>;
+
+    /// Sends the calls in a single transaction from `from`, with the given nonce and fees. Returns
+    /// the hash of the transaction without waiting for it to be mined.
+    async fn aggregate3(
+        &self,
+        from: Address,
+        calls: Vec<SettlementBatcher::Call3>,
+        nonce: u64,
+        fees: Eip1559Fees,
+    ) -> Result<B256, alloy::contract::Error>;
+}
+
+#[async_trait]
+impl<T> SettlementBatcherTrait for T
+where
+    T: AsRef<
+            SettlementBatcher::SettlementBatcherInstance<
+                Http<reqwest::Client>,
+                Arc<LocalWalletSignerMiddleware>,
+                Ethereum,
+            >,
+        > + Send
+        + Sync,
+{
+    async fn owner(&self) -> Result<Address, alloy::contract::Error> {
+        Ok(self.as_ref().owner().call().await?._0)
+    }
+
+    async fn simulate_aggregate3(
+        &self,
+        from: Address,
+        calls: Vec<SettlementBatcher::Call3>,
+    ) -> Result<(), alloy::contract::Error> {
+        self.as_ref().aggregate3(calls).from(from).call().await?;
+        Ok(())
+    }
+
+    async fn aggregate3(
+        &self,
+        from: Address,
+        calls: Vec<SettlementBatcher::Call3>,
+        nonce: u64,
+        fees: Eip1559Fees,
+    ) -> Result<B256, alloy::contract::Error> {
+        let gas = self.as_ref().aggregate3(calls.clone()).from(from).estimate_gas().await?;
+        let pending_tx = self
+            .as_ref()
+            .aggregate3(calls)
+            .from(from)
+            .nonce(nonce)
+            .gas(gas)
+            .max_fee_per_gas(fees.max_fee_per_gas)
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .send()
+            .await?;
+        Ok(*pending_tx.tx_hash())
+    }
+}
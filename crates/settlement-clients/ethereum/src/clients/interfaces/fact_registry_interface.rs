@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use alloy::{network::Ethereum, primitives::B256, sol, transports::http::Http};
+
+use crate::types::LocalWalletSignerMiddleware;
+
+// TODO: should be moved to Zaun:
+// https://github.com/keep-starknet-strange/zaun
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface FactRegistry {
+        function isValid(bytes32 fact) external view returns (bool);
+    }
+}
+
+#[async_trait]
+pub trait FactRegistryTrait {
+    /// Whether the fact is registered, i.e. the proof it attests was verified
+    async fn is_valid(&self, fact: B256) -> Result<bool, alloy::contract::Error>;
+}
+
+#[async_trait]
+impl<T> FactRegistryTrait for T
+where
+    T: AsRef<FactRegistry::FactRegistryInstance<Http<reqwest::Client>, Arc<LocalWalletSignerMiddleware>, Ethereum>>
+        + Send
+        + Sync,
+{
+    async fn is_valid(&self, fact: B256) -> Result<bool, alloy::contract::Error> {
+        Ok(self.as_ref().isValid(fact).call().await?._0)
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, B256},
+    sol,
+    transports::http::Http,
+};
+
+use crate::fees::Eip1559Fees;
+use crate::types::LocalWalletSignerMiddleware;
+
+// TODO: should be moved to Zaun:
+// https://github.com/keep-starknet-strange/zaun
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface MemoryPageFactRegistry {
+        struct MemoryPageEntry {
+            uint256 startAddr;
+            uint256[] values;
+            uint256 z;
+            uint256 alpha;
+            uint256 prime;
+        }
+
+        function registerContinuousMemoryPage(uint256 startAddr, uint256[] memory values, uint256 z, uint256 alpha, uint256 prime) external returns (bytes32 factHash, uint256 memoryHash, uint256 prod);
+        function registerContinuousPageBatch(MemoryPageEntry[] calldata memoryPageEntries) external;
+    }
+}
+
+#[async_trait]
+pub trait MemoryPageFactRegistryTrait {
+    /// Simulates `registerContinuousPageBatch` from `from` with an `eth_call`, fails if the
+    /// transaction would revert.
+    async fn simulate_register_continuous_page_batch(
+        &self,
+        from: Address,
+        entries: Vec<MemoryPageFactRegistry::MemoryPageEntry>,
+    ) -> Result<(), alloy::contract::Error>;
+
+    /// Registers the memory pages in a single transaction, sent from `from` with the given nonce
+    /// and fees. Returns the hash of the transaction without waiting for it to be mined.
+    async fn register_continuous_page_batch(
+        &self,
+        from: Address,
+        entries: Vec<MemoryPageFactRegistry::MemoryPageEntry>,
+        nonce: u64,
+        fees: Eip1559Fees,
+    ) -> Result<B256, alloy::contract::Error>;
+}
+
+#[async_trait]
+impl<T> MemoryPageFactRegistryTrait for T
+where
+    T: AsRef<
+            MemoryPageFactRegistry::MemoryPageFactRegistryInstance<
+                Http<reqwest::Client>,
+                Arc<LocalWalletSignerMiddleware>,
+                Ethereum,
+            >,
+        > + Send
+        + Sync,
+{
+    async fn simulate_register_continuous_page_batch(
+        &self,
+        from: Address,
+        entries: Vec<MemoryPageFactRegistry::MemoryPageEntry>,
+    ) -> Result<(), alloy::contract::Error> {
+        self.as_ref().registerContinuousPageBatch(entries).from(from).call().await?;
+        Ok(())
+    }
+
+    async fn register_continuous_page_batch(
+        &self,
+        from: Address,
+        entries: Vec<MemoryPageFactRegistry::MemoryPageEntry>,
+        nonce: u64,
+        fees: Eip1559Fees,
+    ) -> Result<B256, alloy::contract::Error> {
+        let gas = self.as_ref().registerContinuousPageBatch(entries.clone()).from(from).estimate_gas().await?;
+        let pending_tx = self
+            .as_ref()
+            .registerContinuousPageBatch(entries)
+            .from(from)
+            .nonce(nonce)
+            .gas(gas)
+            .max_fee_per_gas(fees.max_fee_per_gas)
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .send()
+            .await?;
+        Ok(*pending_tx.tx_hash())
+    }
+}
@@ -1 +1,4 @@
+pub mod batcher_interface;
+pub mod fact_registry_interface;
+pub mod memory_pages_interface;
 pub mod validity_interface;
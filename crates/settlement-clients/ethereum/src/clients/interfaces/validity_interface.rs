@@ -1,18 +1,25 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 
 use alloy::{
+    eips::BlockId,
     network::Ethereum,
-    primitives::{I256, U256},
-    providers::Provider,
-    rpc::types::eth::TransactionReceipt,
+    primitives::{Address, B256, I256, U256},
+    rpc::types::Log,
     sol,
-    transports::{http::Http, RpcError, TransportErrorKind},
+    transports::http::Http,
 };
 
+use crate::fees::Eip1559Fees;
 use crate::types::LocalWalletSignerMiddleware;
 
+/// How long a state update waits for its receipt: the following state updates are simulated
+/// against the state it leaves. Past it the transaction is left to the stuck transaction
+/// replacement.
+const STATE_UPDATE_RECEIPT_TIMEOUT: Duration = Duration::from_secs(120);
+
 // TODO: should be moved to Zaun:
 // https://github.com/keep-starknet-strange/zaun
 
@@ -27,6 +34,8 @@ sol! {
         function programHash() public view returns (uint256);
         function configHash() public view returns (uint256);
 
+        function isOperator(address user) public view returns (bool);
+
         function identify() external pure override returns (string memory);
         function stateRoot() external view returns (uint256);
         function stateBlockNumber() external view returns (int256);
@@ -34,6 +43,26 @@ sol! {
 
         function updateState(uint256[] calldata programOutput, uint256 onchainDataHash, uint256 onchainDataSize) external onlyOperator;
         function updateStateKzgDA(uint256[] calldata programOutput, bytes calldata kzgProof) external onlyOperator;
+
+        function l1ToL2Messages(bytes32 msgHash) external view returns (uint256);
+
+        event LogStateUpdate(uint256 globalRoot, int256 blockNumber, uint256 blockHash);
+        event LogMessageToL2(
+            address indexed fromAddress,
+            uint256 indexed toAddress,
+            uint256 indexed selector,
+            uint256[] payload,
+            uint256 nonce,
+            uint256 fee
+        );
+    }
+}
+
+sol! {
+    /// Core contract from Starknet v0.13.3, taking a KZG proof per blob
+    #[allow(missing_docs)]
+    interface StarknetValidityContractMultiBlob {
+        function updateStateKzgDA(uint256[] calldata programOutput, bytes[] calldata kzgProofs) external onlyOperator;
     }
 }
 
@@ -42,19 +71,70 @@ pub trait StarknetValidityContractTrait {
     /// Retrieves the last block number settled
     async fn state_block_number(&self) -> Result<I256, alloy::contract::Error>;
 
-    /// Update the L1 state
+    /// Retrieves the last block number settled as of the given settlement layer block
+    async fn state_block_number_at(&self, block_number: u64) -> Result<I256, alloy::contract::Error>;
+
+    /// Retrieves the state root of the last block settled
+    async fn state_root(&self) -> Result<U256, alloy::contract::Error>;
+
+    /// Retrieves the hash of the OS program the program outputs are accepted from
+    async fn program_hash(&self) -> Result<U256, alloy::contract::Error>;
+
+    /// Whether the account is allowed to update the state
+    async fn is_operator(&self, account: Address) -> Result<bool, alloy::contract::Error>;
+
+    /// Retrieves the fee plus one of the message sent to the L2, 0 once it is consumed
+    async fn l1_to_l2_messages(&self, message_hash: B256) -> Result<U256, alloy::contract::Error>;
+
+    /// Retrieves the messages sent to the L2 between the given blocks (both included), with the
+    /// logs they were emitted in
+    async fn messages_to_l2(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<(StarknetValidityContract::LogMessageToL2, Log)>, alloy::contract::Error>;
+
+    /// Simulates `updateState` from `from` with an `eth_call`, fails if the transaction would
+    /// revert.
+    async fn simulate_update_state(
+        &self,
+        from: Address,
+        program_output: Vec<U256>,
+        onchain_data_hash: U256,
+        onchain_data_size: U256,
+    ) -> Result<(), alloy::contract::Error>;
+
+    /// Simulates `updateStateKzgDA` from `from` with an `eth_call`, fails if the transaction would
+    /// revert.
+    async fn simulate_update_state_kzg(
+        &self,
+        from: Address,
+        program_output: Vec<U256>,
+        kzg_proof: [u8; 48],
+    ) -> Result<(), alloy::contract::Error>;
+
+    /// Update the L1 state, sending the transaction from `from` with the given nonce and fees.
+    /// Waits for the receipt up to `STATE_UPDATE_RECEIPT_TIMEOUT` and returns the hash of the
+    /// transaction, mined or not. Fails only if the transaction wasn't sent.
     async fn update_state(
         &self,
+        from: Address,
         program_output: Vec<U256>,
         onchain_data_hash: U256,
         onchain_data_size: U256,
-    ) -> Result<TransactionReceipt, RpcError<TransportErrorKind>>;
+        nonce: u64,
+        fees: Eip1559Fees,
+    ) -> Result<B256, alloy::contract::Error>;
 
+    /// Same as `update_state` with `updateStateKzgDA`
     async fn update_state_kzg(
         &self,
+        from: Address,
         program_output: Vec<U256>,
         kzg_proof: [u8; 48],
-    ) -> Result<TransactionReceipt, RpcError<TransportErrorKind>>;
+        nonce: u64,
+        fees: Eip1559Fees,
+    ) -> Result<B256, alloy::contract::Error>;
 }
 
 #[async_trait]
@@ -73,40 +153,113 @@ where
         Ok(self.as_ref().stateBlockNumber().call().await?._0)
     }
 
+    async fn state_block_number_at(&self, block_number: u64) -> Result<I256, alloy::contract::Error> {
+        Ok(self.as_ref().stateBlockNumber().block(BlockId::number(block_number)).call().await?._0)
+    }
+
+    async fn state_root(&self) -> Result<U256, alloy::contract::Error> {
+        Ok(self.as_ref().stateRoot().call().await?._0)
+    }
+
+    async fn program_hash(&self) -> Result<U256, alloy::contract::Error> {
+        Ok(self.as_ref().programHash().call().await?._0)
+    }
+
+    async fn is_operator(&self, account: Address) -> Result<bool, alloy::contract::Error> {
+        Ok(self.as_ref().isOperator(account).call().await?._0)
+    }
+
+    async fn l1_to_l2_messages(&self, message_hash: B256) -> Result<U256, alloy::contract::Error> {
+        Ok(self.as_ref().l1ToL2Messages(message_hash).call().await?._0)
+    }
+
+    async fn messages_to_l2(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<(StarknetValidityContract::LogMessageToL2, Log)>, alloy::contract::Error> {
+        self.as_ref().LogMessageToL2_filter().from_block(from_block).to_block(to_block).query().await
+    }
+
+    async fn simulate_update_state(
+        &self,
+        from: Address,
+        program_output: Vec<U256>,
+        onchain_data_hash: U256,
+        onchain_data_size: U256,
+    ) -> Result<(), alloy::contract::Error> {
+        self.as_ref().updateState(program_output, onchain_data_hash, onchain_data_size).from(from).call().await?;
+        Ok(())
+    }
+
+    async fn simulate_update_state_kzg(
+        &self,
+        from: Address,
+        program_output: Vec<U256>,
+        kzg_proof: [u8; 48],
+    ) -> Result<(), alloy::contract::Error> {
+        self.as_ref().updateStateKzgDA(program_output, kzg_proof.into()).from(from).call().await?;
+        Ok(())
+    }
+
     async fn update_state(
         &self,
+        from: Address,
         program_output: Vec<U256>,
         onchain_data_hash: U256,
         onchain_data_size: U256,
-    ) -> Result<TransactionReceipt, RpcError<TransportErrorKind>> {
-        let base_fee = self.as_ref().provider().as_ref().get_gas_price().await.unwrap();
-        let from_address = self.as_ref().provider().as_ref().get_accounts().await.unwrap()[0];
+        nonce: u64,
+        fees: Eip1559Fees,
+    ) -> Result<B256, alloy::contract::Error> {
         let gas = self
             .as_ref()
             .updateState(program_output.clone(), onchain_data_hash, onchain_data_size)
-            .from(from_address)
+            .from(from)
             .estimate_gas()
-            .await
-            .unwrap();
+            .await?;
         let builder = self.as_ref().updateState(program_output, onchain_data_hash, onchain_data_size);
-        builder.from(from_address).nonce(2).gas(gas).gas_price(base_fee).send().await.unwrap().get_receipt().await
+        let pending_tx = builder
+            .from(from)
+            .nonce(nonce)
+            .gas(gas)
+            .max_fee_per_gas(fees.max_fee_per_gas)
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .send()
+            .await?;
+        let tx_hash = *pending_tx.tx_hash();
+        if let Err(e) = pending_tx.with_timeout(Some(STATE_UPDATE_RECEIPT_TIMEOUT)).get_receipt().await {
+            tracing::warn!("No receipt for the state update tx {} yet: {}", tx_hash, e);
+        }
+        Ok(tx_hash)
     }
 
     async fn update_state_kzg(
         &self,
+        from: Address,
         program_output: Vec<U256>,
         kzg_proof: [u8; 48],
-    ) -> Result<TransactionReceipt, RpcError<TransportErrorKind>> {
-        let base_fee = self.as_ref().provider().as_ref().get_gas_price().await.unwrap();
-        let from_address = self.as_ref().provider().as_ref().get_accounts().await.unwrap()[0];
+        nonce: u64,
+        fees: Eip1559Fees,
+    ) -> Result<B256, alloy::contract::Error> {
         let gas = self
             .as_ref()
             .updateStateKzgDA(program_output.clone(), kzg_proof.into())
-            .from(from_address)
+            .from(from)
             .estimate_gas()
-            .await
-            .unwrap();
+            .await?;
         let builder = self.as_ref().updateStateKzgDA(program_output, kzg_proof.into());
-        builder.from(from_address).nonce(2).gas(gas).gas_price(base_fee).send().await.unwrap().get_receipt().await
+        let pending_tx = builder
+            .from(from)
+            .nonce(nonce)
+            .gas(gas)
+            .max_fee_per_gas(fees.max_fee_per_gas)
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .send()
+            .await?;
+        let tx_hash = *pending_tx.tx_hash();
+        if let Err(e) = pending_tx.with_timeout(Some(STATE_UPDATE_RECEIPT_TIMEOUT)).get_receipt().await {
+            tracing::warn!("No receipt for the state update tx {} yet: {}", tx_hash, e);
+        }
+        Ok(tx_hash)
     }
 }
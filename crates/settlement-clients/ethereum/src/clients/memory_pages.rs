@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use alloy::{network::Ethereum, primitives::Address, transports::http::Http};
+
+use crate::clients::interfaces::memory_pages_interface::MemoryPageFactRegistry;
+use crate::types::LocalWalletSignerMiddleware;
+
+// TODO: should be moved to Zaun:
+// https://github.com/keep-starknet-strange/zaun
+
+/// Client to interact with the memory page fact registry of the SHARP verifier
+pub struct MemoryPageFactRegistryClient {
+    registry: MemoryPageFactRegistry::MemoryPageFactRegistryInstance<
+        Http<reqwest::Client>,
+        Arc<LocalWalletSignerMiddleware>,
+        Ethereum,
+    >,
+}
+
+impl MemoryPageFactRegistryClient {
+    pub fn new(address: Address, client: Arc<LocalWalletSignerMiddleware>) -> Self {
+        Self { registry: MemoryPageFactRegistry::new(address, client.clone()) }
+    }
+    pub fn contract_address(&self) -> Address {
+        *self.registry.address()
+    }
+}
+
+impl
+    AsRef<
+        MemoryPageFactRegistry::MemoryPageFactRegistryInstance<
+            Http<reqwest::Client>,
+            Arc<LocalWalletSignerMiddleware>,
+            Ethereum,
+        >,
+    > for MemoryPageFactRegistryClient
+{
+    fn as_ref(
+        &self,
+    ) -> &MemoryPageFactRegistry::MemoryPageFactRegistryInstance<
+        Http<reqwest::Client>,
+        Arc<LocalWalletSignerMiddleware>,
+        Ethereum,
+    > {
+        &self.registry
+    }
+}
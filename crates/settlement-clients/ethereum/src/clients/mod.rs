@@ -1,4 +1,10 @@
+pub mod batcher;
+pub mod fact_registry;
 pub mod interfaces;
+pub mod memory_pages;
 pub mod validity;
 
+pub use batcher::SettlementBatcherClient;
+pub use fact_registry::FactRegistryClient;
+pub use memory_pages::MemoryPageFactRegistryClient;
 pub use validity::StarknetValidityContractClient;
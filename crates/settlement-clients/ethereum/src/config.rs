@@ -3,15 +3,81 @@ use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 use settlement_client_interface::SettlementConfig;
 use url::Url;
-use utils::env_utils::get_env_var_or_panic;
+use utils::env_utils::{get_env_car_optional_or_panic, get_env_var_or_default, get_env_var_or_panic};
+
+use crate::fees::FeeCaps;
+use crate::safe::{SafeConfig, ENV_SAFE_ADDRESS, ENV_SAFE_TRANSACTION_SERVICE_URL};
+use crate::signer::SignerConfig;
 
 pub const ENV_ETHEREUM_RPC_URL: &str = "ETHEREUM_RPC_URL";
 pub const ENV_CORE_CONTRACT_ADDRESS: &str = "STARKNET_SOLIDITY_CORE_CONTRACT_ADDRESS";
+pub const ENV_PRIVATE_RELAY_URL: &str = "ETHEREUM_PRIVATE_RELAY_URL";
+pub const ENV_MEMORY_PAGES_CONTRACT_ADDRESS: &str = "MEMORY_PAGES_CONTRACT_ADDRESS";
+pub const ENV_SETTLEMENT_BATCHER_ADDRESS: &str = "ETHEREUM_SETTLEMENT_BATCHER_ADDRESS";
+pub const ENV_FACT_REGISTRY_ADDRESS: &str = "ETHEREUM_FACT_REGISTRY_ADDRESS";
+/// `pre_4844`, `kzg_da` (default) or `multi_blob_kzg_da`
+pub const ENV_CORE_CONTRACT_VERSION: &str = "STARKNET_CORE_CONTRACT_VERSION";
+/// Number of blocks, the one including it first, a settlement transaction needs before being
+/// considered final
+pub const ENV_SETTLEMENT_CONFIRMATIONS: &str = "ETHEREUM_SETTLEMENT_CONFIRMATIONS";
+/// `local` (default) or `aws_kms`
+pub const ENV_SIGNER: &str = "ETHEREUM_SIGNER";
+pub const ENV_AWS_KMS_KEY_ID: &str = "ETHEREUM_AWS_KMS_KEY_ID";
+
+pub const ENV_MAX_FEE_PER_GAS_CAP: &str = "ETHEREUM_MAX_FEE_PER_GAS_CAP";
+pub const ENV_MAX_PRIORITY_FEE_PER_GAS_CAP: &str = "ETHEREUM_MAX_PRIORITY_FEE_PER_GAS_CAP";
+pub const ENV_MAX_FEE_PER_BLOB_GAS_CAP: &str = "ETHEREUM_MAX_FEE_PER_BLOB_GAS_CAP";
+
+/// Version of the Starknet core contract deployed, they differ in the way the state is updated
+/// when the DA is done in blobs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoreContractVersion {
+    /// Before Starknet v0.13.1, the state can only be updated with `updateState`
+    Pre4844,
+    /// From Starknet v0.13.1, `updateStateKzgDA` takes the KZG proof of a single blob
+    #[default]
+    KzgDa,
+    /// From Starknet v0.13.3, `updateStateKzgDA` takes a KZG proof per blob
+    MultiBlobKzgDa,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EthereumSettlementConfig {
     pub rpc_url: Url,
     pub core_contract_address: String,
+    #[serde(default)]
+    pub core_contract_version: CoreContractVersion,
+    /// Caps on the fees of the settlement transactions, they depend on the chain settled on
+    pub fee_caps: FeeCaps,
+    /// RPC of a private relay (e.g. Flashbots Protect) the settlement transactions are sent through
+    /// instead of the public mempool
+    pub private_relay_url: Option<Url>,
+    /// Signer of the settlement transactions
+    #[serde(default)]
+    pub signer: SignerConfig,
+    /// Memory page fact registry of the SHARP verifier the memory pages of the proofs are
+    /// registered in, only needed when registering proofs
+    pub memory_pages_contract: Option<String>,
+    /// Settlement batcher the batched settlement operations are sent through, only needed when
+    /// settling in batches: a contract exposing the `aggregate3` of Multicall3 which only executes
+    /// the calls of its `owner()`, the operator account or the Safe, and registered as an operator
+    /// of the core contract. A stock Multicall3 can't be used, anyone could update the state
+    /// through it.
+    pub settlement_batcher_contract: Option<String>,
+    /// Fact registry of the SHARP verifier the proofs are checked against, only needed when
+    /// verifying the facts of the proofs
+    pub fact_registry_contract: Option<String>,
+    /// Confirmations a settlement transaction needs before it is verified
+    #[serde(default = "default_required_confirmations")]
+    pub required_confirmations: u64,
+    /// Safe the settlement transactions are proposed to instead of being sent by the operator
+    /// account, when the core contract is operated by a multisig
+    pub safe: Option<SafeConfig>,
+}
+
+fn default_required_confirmations() -> u64 {
+    1
 }
 
 impl SettlementConfig for EthereumSettlementConfig {
@@ -19,15 +85,83 @@ impl SettlementConfig for EthereumSettlementConfig {
         let rpc_url = get_env_var_or_panic(ENV_ETHEREUM_RPC_URL);
         let rpc_url = Url::from_str(&rpc_url).unwrap_or_else(|_| panic!("Failed to parse {}", ENV_ETHEREUM_RPC_URL));
         let core_contract_address = get_env_var_or_panic(ENV_CORE_CONTRACT_ADDRESS);
-        Self { rpc_url, core_contract_address }
+        let core_contract_version = match get_env_var_or_default(ENV_CORE_CONTRACT_VERSION, "kzg_da").as_str() {
+            "pre_4844" => CoreContractVersion::Pre4844,
+            "kzg_da" => CoreContractVersion::KzgDa,
+            "multi_blob_kzg_da" => CoreContractVersion::MultiBlobKzgDa,
+            version => panic!(
+                "Unsupported {} {}, expected pre_4844, kzg_da or multi_blob_kzg_da",
+                ENV_CORE_CONTRACT_VERSION, version
+            ),
+        };
+        let default_fee_caps = FeeCaps::default();
+        let fee_caps = FeeCaps {
+            max_fee_per_gas: fee_cap_from_env(ENV_MAX_FEE_PER_GAS_CAP, default_fee_caps.max_fee_per_gas),
+            max_priority_fee_per_gas: fee_cap_from_env(
+                ENV_MAX_PRIORITY_FEE_PER_GAS_CAP,
+                default_fee_caps.max_priority_fee_per_gas,
+            ),
+            max_fee_per_blob_gas: fee_cap_from_env(ENV_MAX_FEE_PER_BLOB_GAS_CAP, default_fee_caps.max_fee_per_blob_gas),
+        };
+        let private_relay_url = get_env_car_optional_or_panic(ENV_PRIVATE_RELAY_URL)
+            .map(|url| Url::from_str(&url).unwrap_or_else(|_| panic!("Failed to parse {}", ENV_PRIVATE_RELAY_URL)));
+        let signer = match get_env_var_or_default(ENV_SIGNER, "local").as_str() {
+            "local" => SignerConfig::Local,
+            "aws_kms" => SignerConfig::AwsKms { key_id: get_env_var_or_panic(ENV_AWS_KMS_KEY_ID) },
+            signer => panic!("Unsupported {} {}, expected local or aws_kms", ENV_SIGNER, signer),
+        };
+        let memory_pages_contract = get_env_car_optional_or_panic(ENV_MEMORY_PAGES_CONTRACT_ADDRESS);
+        let settlement_batcher_contract = get_env_car_optional_or_panic(ENV_SETTLEMENT_BATCHER_ADDRESS);
+        let fact_registry_contract = get_env_car_optional_or_panic(ENV_FACT_REGISTRY_ADDRESS);
+        let required_confirmations = get_env_var_or_default(ENV_SETTLEMENT_CONFIRMATIONS, "1")
+            .parse()
+            .unwrap_or_else(|_| panic!("Failed to parse {}", ENV_SETTLEMENT_CONFIRMATIONS));
+        if required_confirmations == 0 {
+            panic!("{} must be at least 1", ENV_SETTLEMENT_CONFIRMATIONS);
+        }
+        let safe = get_env_car_optional_or_panic(ENV_SAFE_ADDRESS).map(|address| {
+            let transaction_service_url = get_env_var_or_panic(ENV_SAFE_TRANSACTION_SERVICE_URL);
+            let transaction_service_url = Url::from_str(&transaction_service_url)
+                .unwrap_or_else(|_| panic!("Failed to parse {}", ENV_SAFE_TRANSACTION_SERVICE_URL));
+            SafeConfig { address, transaction_service_url }
+        });
+        Self {
+            rpc_url,
+            core_contract_address,
+            core_contract_version,
+            fee_caps,
+            private_relay_url,
+            signer,
+            memory_pages_contract,
+            settlement_batcher_contract,
+            fact_registry_contract,
+            required_confirmations,
+            safe,
+        }
     }
 }
 
+/// Reads a fee cap in wei from the environment, falling back to `default` when not set
+fn fee_cap_from_env(name: &str, default: u128) -> u128 {
+    get_env_car_optional_or_panic(name)
+        .map(|cap| cap.parse().unwrap_or_else(|_| panic!("Failed to parse {}, expected an amount in wei", name)))
+        .unwrap_or(default)
+}
+
 impl Default for EthereumSettlementConfig {
     fn default() -> Self {
         Self {
             rpc_url: "https://ethereum-sepolia.blockpi.network/v1/rpc/public".parse().unwrap(),
             core_contract_address: "0xE2Bb56ee936fd6433DC0F6e7e3b8365C906AA057".into(),
+            core_contract_version: CoreContractVersion::default(),
+            fee_caps: FeeCaps::default(),
+            private_relay_url: None,
+            signer: SignerConfig::default(),
+            memory_pages_contract: None,
+            settlement_batcher_contract: None,
+            fact_registry_contract: None,
+            required_confirmations: default_required_confirmations(),
+            safe: None,
         }
     }
 }
@@ -0,0 +1,207 @@
+use alloy::eips::BlockNumberOrTag;
+use alloy::providers::Provider;
+use alloy::rpc::types::FeeHistory;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use settlement_client_interface::FeeTooHighError;
+
+use crate::types::EthHttpProvider;
+
+/// Number of blocks of fee history the priority fee is derived from.
+pub const FEE_HISTORY_BLOCK_COUNT: u64 = 10;
+/// Percentile of the priority fees paid in the fee history blocks used as priority fee.
+pub const FEE_HISTORY_REWARD_PERCENTILE: f64 = 50.0;
+
+/// Bump applied to the fees of a stuck transaction when replacing it, nodes require at least 10%
+pub const FEE_BUMP_PERCENT: u128 = 12;
+/// Bump applied to the blob fee of a stuck blob transaction when replacing it, nodes require 100%
+pub const BLOB_FEE_BUMP_PERCENT: u128 = 100;
+
+/// EIP-1559 fees of a settlement transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Eip1559Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+/// Caps on the fees paid by the settlement transactions, in wei.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeCaps {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_blob_gas: u128,
+}
+
+impl Default for FeeCaps {
+    fn default() -> Self {
+        Self {
+            max_fee_per_gas: 200_000_000_000,
+            max_priority_fee_per_gas: 10_000_000_000,
+            max_fee_per_blob_gas: 100_000_000_000,
+        }
+    }
+}
+
+impl FeeCaps {
+    /// Fees of the next transaction derived from the fee history of the last blocks.
+    ///
+    /// The priority fee is the median of the priority fees paid in the last blocks. The max fee
+    /// leaves room for the base fee to double before the transaction is included but never goes
+    /// above the cap. Fails with a [`FeeTooHighError`] when the base fee and priority fee alone
+    /// are already above the cap.
+    pub fn eip1559_fees(&self, fee_history: &FeeHistory) -> Result<Eip1559Fees, FeeTooHighError> {
+        // the fee history holds the base fee of the block following the newest one
+        let base_fee = fee_history.base_fee_per_gas.last().copied().unwrap_or_default();
+
+        let mut rewards: Vec<u128> =
+            fee_history.reward.iter().flatten().filter_map(|block_rewards| block_rewards.first().copied()).collect();
+        rewards.sort_unstable();
+        let max_priority_fee_per_gas =
+            rewards.get(rewards.len() / 2).copied().unwrap_or_default().min(self.max_priority_fee_per_gas);
+
+        if base_fee + max_priority_fee_per_gas > self.max_fee_per_gas {
+            return Err(FeeTooHighError(format!(
+                "base fee {} wei and priority fee {} wei exceed the max fee per gas cap of {} wei",
+                base_fee, max_priority_fee_per_gas, self.max_fee_per_gas
+            )));
+        }
+        let max_fee_per_gas = (2 * base_fee + max_priority_fee_per_gas).min(self.max_fee_per_gas);
+
+        Ok(Eip1559Fees { max_fee_per_gas, max_priority_fee_per_gas })
+    }
+
+    /// Max fee per blob gas of the next blob transaction, leaving room for the blob base fee to
+    /// double within the cap. Fails with a [`FeeTooHighError`] when the blob base fee is already
+    /// above the cap.
+    pub fn max_fee_per_blob_gas(&self, blob_base_fee: u128) -> Result<u128, FeeTooHighError> {
+        if blob_base_fee > self.max_fee_per_blob_gas {
+            return Err(FeeTooHighError(format!(
+                "blob base fee {} wei exceeds the max fee per blob gas cap of {} wei",
+                blob_base_fee, self.max_fee_per_blob_gas
+            )));
+        }
+        Ok((2 * blob_base_fee).min(self.max_fee_per_blob_gas))
+    }
+
+    /// Fees of the transaction replacing a stuck one sent with `previous` fees.
+    ///
+    /// The fees are bumped by [`FEE_BUMP_PERCENT`] so that the node accepts the replacement, or
+    /// follow the market when it moved further. Fails with a [`FeeTooHighError`] when the bumped
+    /// fees are above the caps, the stuck transaction is then left as is.
+    pub fn bumped_fees(&self, previous: Eip1559Fees, fee_history: &FeeHistory) -> Result<Eip1559Fees, FeeTooHighError> {
+        let bumped = Eip1559Fees {
+            max_fee_per_gas: bump(previous.max_fee_per_gas, FEE_BUMP_PERCENT),
+            max_priority_fee_per_gas: bump(previous.max_priority_fee_per_gas, FEE_BUMP_PERCENT),
+        };
+        let fees = match self.eip1559_fees(fee_history) {
+            Ok(current) => Eip1559Fees {
+                max_fee_per_gas: bumped.max_fee_per_gas.max(current.max_fee_per_gas),
+                max_priority_fee_per_gas: bumped.max_priority_fee_per_gas.max(current.max_priority_fee_per_gas),
+            },
+            Err(_) => bumped,
+        };
+
+        if fees.max_fee_per_gas > self.max_fee_per_gas || fees.max_priority_fee_per_gas > self.max_priority_fee_per_gas
+        {
+            return Err(FeeTooHighError(format!(
+                "replacement fees {:?} exceed the max fee per gas cap of {} wei or the max priority fee per gas cap \
+                 of {} wei",
+                fees, self.max_fee_per_gas, self.max_priority_fee_per_gas
+            )));
+        }
+        Ok(fees)
+    }
+
+    /// Max fee per blob gas of the blob transaction replacing a stuck one sent with `previous`,
+    /// bumped by [`BLOB_FEE_BUMP_PERCENT`]. Fails with a [`FeeTooHighError`] when it is above the
+    /// cap.
+    pub fn bumped_max_fee_per_blob_gas(&self, previous: u128, blob_base_fee: u128) -> Result<u128, FeeTooHighError> {
+        let max_fee_per_blob_gas = bump(previous, BLOB_FEE_BUMP_PERCENT).max(2 * blob_base_fee);
+        if max_fee_per_blob_gas > self.max_fee_per_blob_gas {
+            return Err(FeeTooHighError(format!(
+                "replacement max fee per blob gas {} wei exceeds the cap of {} wei",
+                max_fee_per_blob_gas, self.max_fee_per_blob_gas
+            )));
+        }
+        Ok(max_fee_per_blob_gas)
+    }
+}
+
+/// Increases `fee` by `percent`, rounding up.
+fn bump(fee: u128, percent: u128) -> u128 {
+    (fee * (100 + percent)).div_ceil(100)
+}
+
+/// Fetches the fee history the EIP-1559 fees are derived from.
+pub async fn fetch_fee_history(provider: &EthHttpProvider) -> Result<FeeHistory> {
+    Ok(provider
+        .get_fee_history(FEE_HISTORY_BLOCK_COUNT, BlockNumberOrTag::Latest, &[FEE_HISTORY_REWARD_PERCENTILE])
+        .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee_history(base_fee: u128, rewards: &[u128]) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: vec![base_fee; rewards.len() + 1],
+            reward: Some(rewards.iter().map(|reward| vec![*reward]).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_eip1559_fees_from_fee_history() {
+        let caps = FeeCaps::default();
+        let fees = caps.eip1559_fees(&fee_history(20_000_000_000, &[3, 1, 2_000_000_000, 5, 4])).unwrap();
+
+        assert_eq!(fees.max_priority_fee_per_gas, 4);
+        assert_eq!(fees.max_fee_per_gas, 40_000_000_004);
+    }
+
+    #[test]
+    fn test_eip1559_fees_capped() {
+        let caps =
+            FeeCaps { max_fee_per_gas: 30_000_000_000, max_priority_fee_per_gas: 1_000_000_000, ..Default::default() };
+        let fees = caps.eip1559_fees(&fee_history(20_000_000_000, &[5_000_000_000])).unwrap();
+
+        assert_eq!(fees.max_priority_fee_per_gas, 1_000_000_000);
+        assert_eq!(fees.max_fee_per_gas, 30_000_000_000);
+    }
+
+    #[test]
+    fn test_eip1559_fees_too_high() {
+        let caps = FeeCaps { max_fee_per_gas: 10_000_000_000, ..Default::default() };
+        assert!(caps.eip1559_fees(&fee_history(20_000_000_000, &[1])).is_err());
+        assert!(caps.max_fee_per_blob_gas(caps.max_fee_per_blob_gas + 1).is_err());
+        assert_eq!(caps.max_fee_per_blob_gas(1).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_bumped_fees() {
+        let caps = FeeCaps::default();
+        let previous = Eip1559Fees { max_fee_per_gas: 100_000_000_000, max_priority_fee_per_gas: 1_000_000_000 };
+
+        // the market didn't move, the fees are bumped
+        let fees = caps.bumped_fees(previous, &fee_history(1_000_000_000, &[1])).unwrap();
+        assert_eq!(fees.max_fee_per_gas, 112_000_000_000);
+        assert_eq!(fees.max_priority_fee_per_gas, 1_120_000_000);
+
+        // the priority fees went up further than the bump
+        let fees = caps.bumped_fees(previous, &fee_history(1_000_000_000, &[5_000_000_000])).unwrap();
+        assert_eq!(fees.max_priority_fee_per_gas, 5_000_000_000);
+
+        // the bump goes above the cap
+        let caps = FeeCaps { max_fee_per_gas: 110_000_000_000, ..Default::default() };
+        assert!(caps.bumped_fees(previous, &fee_history(1_000_000_000, &[1])).is_err());
+    }
+
+    #[test]
+    fn test_bumped_max_fee_per_blob_gas() {
+        let caps = FeeCaps::default();
+        assert_eq!(caps.bumped_max_fee_per_blob_gas(10, 1).unwrap(), 20);
+        assert_eq!(caps.bumped_max_fee_per_blob_gas(10, 50).unwrap(), 100);
+        assert!(caps.bumped_max_fee_per_blob_gas(caps.max_fee_per_blob_gas, 1).is_err());
+    }
+}
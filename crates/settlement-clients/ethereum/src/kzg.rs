@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use alloy::consensus::BlobTransactionSidecar;
+use alloy::eips::eip4844::BYTES_PER_BLOB;
+use alloy::primitives::FixedBytes;
+use c_kzg::{Blob, Bytes32, KzgCommitment, KzgProof, KzgSettings};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+
+/// Index of the KZG evaluation point (x_0) in the program output when the DA is done in blobs.
+pub const X_0_POINT_INDEX: usize = 6;
+
+lazy_static! {
+    /// Trusted setup of the Ethereum KZG ceremony, shipped along with this crate.
+    pub static ref KZG_SETTINGS: KzgSettings = KzgSettings::load_trusted_setup_file(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/trusted_setup.txt").as_path()
+    )
+    .expect("Error loading trusted setup file");
+}
+
+/// Returns the KZG evaluation point x_0 committed to in the program output.
+pub fn x_0_point(program_output: &[[u8; 32]]) -> Result<Bytes32> {
+    let x_0 = program_output.get(X_0_POINT_INDEX).ok_or_else(|| {
+        eyre!("Program output has {} elements, x_0 point is expected at index {}", program_output.len(), X_0_POINT_INDEX)
+    })?;
+    Ok(Bytes32::from_bytes(x_0)?)
+}
+
+/// Builds the KZG proof of the evaluation of the blob at the x_0 point.
+///
+/// The proof is verified against the blob commitment before being returned, so that a bad proof
+/// is caught here rather than by the core contract.
+pub fn build_kzg_proof(blob_data: &[Vec<u8>], x_0: Bytes32) -> Result<KzgProof> {
+    // The core contracts before multi-blob support only accept a single KZG proof, so the state
+    // diff has to fit in one blob.
+    let [blob_data] = blob_data else {
+        return Err(eyre!("Expected a single blob to build the KZG proof, found {}", blob_data.len()));
+    };
+    build_blob_kzg_proof(blob_data, x_0)
+}
+
+/// Builds the KZG proofs of the evaluation of each blob at the x_0 point, as expected by the core
+/// contracts supporting multiple blobs.
+pub fn build_kzg_proofs(blob_data: &[Vec<u8>], x_0: Bytes32) -> Result<Vec<KzgProof>> {
+    if blob_data.is_empty() {
+        return Err(eyre!("Expected at least one blob to build the KZG proofs"));
+    }
+    blob_data.iter().map(|blob_data| build_blob_kzg_proof(blob_data, x_0)).collect()
+}
+
+fn build_blob_kzg_proof(blob_data: &[u8], x_0: Bytes32) -> Result<KzgProof> {
+    let blob = Blob::new(to_fixed_size_blob(blob_data)?);
+    let commitment = KzgCommitment::blob_to_kzg_commitment(&blob, &KZG_SETTINGS)?;
+    let (kzg_proof, y_0) = KzgProof::compute_kzg_proof(&blob, &x_0, &KZG_SETTINGS)?;
+
+    let verified =
+        KzgProof::verify_kzg_proof(&commitment.to_bytes(), &x_0, &y_0, &kzg_proof.to_bytes(), &KZG_SETTINGS)?;
+    if !verified {
+        return Err(eyre!("KZG proof verification failed for the x_0 point evaluation"));
+    }
+
+    Ok(kzg_proof)
+}
+
+/// Prepares the sidecar of the EIP-4844 transaction carrying the blobs.
+pub fn prepare_sidecar(state_diff: &[Vec<u8>]) -> Result<BlobTransactionSidecar> {
+    let mut sidecar_blobs = vec![];
+    let mut sidecar_commitments = vec![];
+    let mut sidecar_proofs = vec![];
+
+    for blob_data in state_diff {
+        let fixed_size_blob = to_fixed_size_blob(blob_data)?;
+        let blob = Blob::new(fixed_size_blob);
+
+        let commitment = KzgCommitment::blob_to_kzg_commitment(&blob, &KZG_SETTINGS)?;
+        let proof = KzgProof::compute_blob_kzg_proof(&blob, &commitment.to_bytes(), &KZG_SETTINGS)?;
+
+        sidecar_blobs.push(FixedBytes::new(fixed_size_blob));
+        sidecar_commitments.push(FixedBytes::new(commitment.to_bytes().into_inner()));
+        sidecar_proofs.push(FixedBytes::new(proof.to_bytes().into_inner()));
+    }
+
+    Ok(BlobTransactionSidecar::new(sidecar_blobs, sidecar_commitments, sidecar_proofs))
+}
+
+fn to_fixed_size_blob(blob_data: &[u8]) -> Result<[u8; BYTES_PER_BLOB]> {
+    blob_data.try_into().map_err(|_| eyre!("Blob has {} bytes, expected {}", blob_data.len(), BYTES_PER_BLOB))
+}
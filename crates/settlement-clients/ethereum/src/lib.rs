@@ -1,110 +1,319 @@
 pub mod clients;
 pub mod config;
 pub mod conversion;
+pub mod fees;
+pub mod kzg;
+pub mod nonce_manager;
+pub mod safe;
+pub mod signer;
+pub mod simulation;
 pub mod types;
 
-use alloy::consensus::{
-    BlobTransactionSidecar, SignableTransaction, TxEip4844, TxEip4844Variant, TxEip4844WithSidecar, TxEnvelope,
-};
+use alloy::consensus::{SignableTransaction, TxEip4844, TxEip4844Variant, TxEip4844WithSidecar, TxEnvelope};
 use alloy::eips::eip2718::Encodable2718;
+use alloy::eips::BlockNumberOrTag;
 use alloy::eips::eip2930::AccessList;
-use alloy::eips::eip4844::BYTES_PER_BLOB;
-use alloy::primitives::{Bytes, FixedBytes};
+use alloy::primitives::{keccak256, Bytes, I256};
+use alloy::sol_types::SolCall;
 use alloy::{
     network::EthereumWallet,
-    primitives::{Address, B256, U256},
+    primitives::{Address, TxKind, B256, U256},
     providers::{PendingTransactionConfig, Provider, ProviderBuilder},
-    rpc::types::TransactionReceipt,
-    signers::local::PrivateKeySigner,
+    rpc::client::RpcClient,
+    rpc::types::{BlockTransactions, Log, TransactionInput, TransactionReceipt, TransactionRequest},
+    transports::http::Http,
 };
 use async_trait::async_trait;
-use c_kzg::{Blob, Bytes32, KzgCommitment, KzgProof, KzgSettings};
 use color_eyre::eyre::eyre;
 use color_eyre::Result;
-use mockall::{automock, lazy_static, predicate::*};
+use mockall::{automock, predicate::*};
 use rstest::rstest;
-use std::fmt::Write;
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::clients::interfaces::batcher_interface::{SettlementBatcher, SettlementBatcherTrait};
+use crate::clients::interfaces::fact_registry_interface::FactRegistryTrait;
+use crate::clients::interfaces::memory_pages_interface::{MemoryPageFactRegistry, MemoryPageFactRegistryTrait};
+use crate::clients::interfaces::validity_interface::{
+    StarknetValidityContract, StarknetValidityContractMultiBlob, StarknetValidityContractTrait,
+};
+use settlement_client_interface::{
+    L1ToL2Message, MemoryPagesRegistration, SettlementClient, SettlementOperation, SettlementVerificationStatus,
+    SETTLEMENT_SETTINGS_NAME,
+};
+use utils::{build_http_client, settings::SettingsProvider};
 
-use crate::clients::interfaces::validity_interface::StarknetValidityContractTrait;
-use settlement_client_interface::{SettlementClient, SettlementVerificationStatus, SETTLEMENT_SETTINGS_NAME};
-use utils::{env_utils::get_env_var_or_panic, settings::SettingsProvider};
-
-use crate::clients::StarknetValidityContractClient;
-use crate::config::EthereumSettlementConfig;
+use crate::clients::{
+    FactRegistryClient, MemoryPageFactRegistryClient, SettlementBatcherClient, StarknetValidityContractClient,
+};
+use crate::config::{CoreContractVersion, EthereumSettlementConfig};
 use crate::conversion::{slice_slice_u8_to_vec_u256, slice_u8_to_u256};
+use crate::fees::{fetch_fee_history, Eip1559Fees, FeeCaps};
+use crate::kzg::{build_kzg_proof, build_kzg_proofs, prepare_sidecar, x_0_point};
+use crate::nonce_manager::NonceManager;
+use crate::safe::{SafeProposer, SafeTxStatus};
+use crate::signer::build_wallet;
+use crate::simulation::{contract_simulation_error, simulation_error};
 use crate::types::EthHttpProvider;
 
-pub const ENV_PRIVATE_KEY: &str = "ETHEREUM_PRIVATE_KEY";
+pub use crate::signer::ENV_PRIVATE_KEY;
+/// EIP-2718 type of the blob transactions
+const EIP4844_TX_TYPE: u8 = 3;
 
-lazy_static! {
-    pub static ref CURRENT_PATH: PathBuf = std::env::current_dir().unwrap();
-    pub static ref KZG_SETTINGS: KzgSettings = KzgSettings::load_trusted_setup_file(
-        CURRENT_PATH.join("../../../orchestrator/src/jobs/state_update_job/trusted_setup.txt").as_path()
-    )
-    .expect("Error loading trusted setup file");
-}
+/// Prime of the field the Cairo memory values belong to, the memory pages are hashed modulo it
+const STARK_PRIME: U256 =
+    U256::from_be_bytes(alloy::primitives::hex!("0800000000000011000000000000000000000000000000000000000000000001"));
 
 #[allow(dead_code)]
 pub struct EthereumSettlementClient {
     provider: Arc<EthHttpProvider>,
+    /// Provider the settlement transactions are sent and tracked through: the private relay when
+    /// configured, as the transactions don't show up in the public mempool, `provider` otherwise
+    tx_provider: Arc<EthHttpProvider>,
     core_contract_client: StarknetValidityContractClient,
+    core_contract_version: CoreContractVersion,
+    /// Only set when the memory page fact registry is configured
+    memory_pages_client: Option<MemoryPageFactRegistryClient>,
+    /// Only set when the settlement batcher is configured
+    batcher_client: Option<SettlementBatcherClient>,
+    /// Only set when the fact registry is configured
+    fact_registry_client: Option<FactRegistryClient>,
     wallet: EthereumWallet,
     wallet_address: Address,
+    nonce_manager: NonceManager,
+    fee_caps: FeeCaps,
+    /// Confirmations a settlement transaction needs before it is verified
+    required_confirmations: u64,
+    /// Blob transactions sent and not mined yet, the sidecar is needed to replace them and can't
+    /// be fetched back from the node
+    pending_blob_txs: Mutex<HashMap<B256, TxEip4844WithSidecar>>,
+    /// Only set in Safe operator mode: the settlement transactions are proposed to the Safe and
+    /// their external ids are the hashes of the Safe transactions
+    safe_proposer: Option<SafeProposer>,
 }
 
 impl EthereumSettlementClient {
-    pub fn with_settings(settings: &impl SettingsProvider) -> Self {
+    pub async fn with_settings(settings: &impl SettingsProvider) -> Self {
         let settlement_cfg: EthereumSettlementConfig = settings.get_settings(SETTLEMENT_SETTINGS_NAME).unwrap();
 
-        let private_key = get_env_var_or_panic(ENV_PRIVATE_KEY);
-        let signer: PrivateKeySigner = private_key.parse().expect("Failed to parse private key");
-        let wallet = EthereumWallet::from(signer.clone());
-
-        let wallet_address = signer.address();
+        let (wallet, wallet_address, hash_signer) =
+            build_wallet(&settlement_cfg.signer).await.expect("Failed to build the settlement signer");
 
-        let provider = Arc::new(
-            ProviderBuilder::new().with_recommended_fillers().wallet(wallet.clone()).on_http(settlement_cfg.rpc_url),
-        );
+        let provider = Arc::new(build_provider(settlement_cfg.rpc_url, &wallet));
+        let tx_provider = match settlement_cfg.private_relay_url {
+            Some(private_relay_url) => {
+                tracing::info!("Settlement transactions are sent through the private relay {}", private_relay_url);
+                Arc::new(build_provider(private_relay_url, &wallet))
+            }
+            None => provider.clone(),
+        };
         let core_contract_client = StarknetValidityContractClient::new(
             Address::from_str(&settlement_cfg.core_contract_address)
                 .expect("Failed to convert the validity contract address.")
                 .0
                 .into(),
-            provider.clone(),
+            tx_provider.clone(),
         );
+        let memory_pages_client = settlement_cfg.memory_pages_contract.map(|address| {
+            MemoryPageFactRegistryClient::new(
+                Address::from_str(&address).expect("Failed to convert the memory page fact registry address."),
+                tx_provider.clone(),
+            )
+        });
+
+        let batcher_client = settlement_cfg.settlement_batcher_contract.map(|address| {
+            SettlementBatcherClient::new(
+                Address::from_str(&address).expect("Failed to convert the settlement batcher address."),
+                tx_provider.clone(),
+            )
+        });
+
+        let fact_registry_client = settlement_cfg.fact_registry_contract.map(|address| {
+            FactRegistryClient::new(
+                Address::from_str(&address).expect("Failed to convert the fact registry address."),
+                provider.clone(),
+            )
+        });
+
+        let nonce_manager = NonceManager::new(wallet_address);
+
+        let safe_proposer = match settlement_cfg.safe {
+            Some(safe_config) => {
+                let chain_id: u64 = provider
+                    .get_chain_id()
+                    .await
+                    .expect("Failed to get the settlement chain id")
+                    .to_string()
+                    .parse()
+                    .expect("Failed to parse the settlement chain id");
+                let safe_proposer = SafeProposer::new(&safe_config, chain_id, wallet_address, hash_signer)
+                    .expect("Failed to build the Safe proposer");
+                tracing::info!("Settlement transactions are proposed to the Safe {}", safe_proposer.safe_address());
+                Some(safe_proposer)
+            }
+            None => None,
+        };
+
+        EthereumSettlementClient {
+            provider,
+            tx_provider,
+            core_contract_client,
+            core_contract_version: settlement_cfg.core_contract_version,
+            memory_pages_client,
+            batcher_client,
+            fact_registry_client,
+            wallet,
+            wallet_address,
+            nonce_manager,
+            fee_caps: settlement_cfg.fee_caps,
+            required_confirmations: settlement_cfg.required_confirmations,
+            pending_blob_txs: Mutex::new(HashMap::new()),
+            safe_proposer,
+        }
+    }
 
-        EthereumSettlementClient { provider, core_contract_client, wallet, wallet_address }
+    /// Account the settlement calls are made from, the Safe in Safe operator mode
+    fn settlement_sender(&self) -> Address {
+        self.safe_proposer.as_ref().map_or(self.wallet_address, |safe_proposer| safe_proposer.safe_address())
     }
 
-    /// Build kzg proof for the x_0 point evaluation
-    async fn build_proof(blob_data: Vec<Vec<u8>>, x_0_value: Bytes32) -> Result<KzgProof> {
-        // Asserting that there is only one blob in the whole Vec<Vec<u8>> array for now.
-        // Later we will add the support for multiple blob in single blob_data vec.
-        assert_eq!(blob_data.len(), 1);
+    /// Whether the state updates should be simulated before they're sent. The simulations run
+    /// against the latest state, which has none of the settlement transactions sent and not mined
+    /// yet: the state update of a block following one of them would revert in the simulation. Only
+    /// the first transaction of a range is simulated.
+    async fn should_simulate(&self) -> Result<bool> {
+        match &self.safe_proposer {
+            Some(safe_proposer) => Ok(!safe_proposer.has_queued().await?),
+            None => Ok(!self.nonce_manager.has_pending(&self.tx_provider).await?),
+        }
+    }
+
+    /// Fails in Safe operator mode, for the operations the Safe can't execute
+    fn ensure_no_safe(&self, operation: &str) -> Result<()> {
+        match self.safe_proposer {
+            Some(_) => Err(eyre!("{} is not supported in Safe operator mode", operation)),
+            None => Ok(()),
+        }
+    }
 
-        let fixed_size_blob: [u8; BYTES_PER_BLOB] = blob_data[0].as_slice().try_into()?;
+    /// Ethereum transaction behind an external id: the id itself, or in Safe operator mode the
+    /// transaction which executed the Safe transaction, `None` while it isn't executed.
+    async fn executed_tx_hash(&self, tx_hash: &str) -> Result<Option<B256>> {
+        let tx_hash = B256::from_str(tx_hash)?;
+        let Some(safe_proposer) = &self.safe_proposer else {
+            return Ok(Some(tx_hash));
+        };
+        match safe_proposer.status(tx_hash).await? {
+            SafeTxStatus::Executed { tx_hash, .. } => Ok(Some(tx_hash)),
+            SafeTxStatus::Pending => Ok(None),
+        }
+    }
 
-        let blob = Blob::new(fixed_size_blob);
-        let commitment = KzgCommitment::blob_to_kzg_commitment(&blob, &KZG_SETTINGS)?;
-        let (kzg_proof, y_0_value) = KzgProof::compute_kzg_proof(&blob, &x_0_value, &KZG_SETTINGS)?;
+    fn memory_pages_client(&self) -> Result<&MemoryPageFactRegistryClient> {
+        self.memory_pages_client.as_ref().ok_or_else(|| eyre!("Memory page fact registry address is not configured"))
+    }
 
-        // Verifying the proof for double check
-        let eval = KzgProof::verify_kzg_proof(
-            &commitment.to_bytes(),
-            &x_0_value,
-            &y_0_value,
-            &kzg_proof.to_bytes(),
-            &KZG_SETTINGS,
-        )?;
+    fn batcher_client(&self) -> Result<&SettlementBatcherClient> {
+        self.batcher_client.as_ref().ok_or_else(|| eyre!("Settlement batcher address is not configured"))
+    }
 
-        if !eval {
-            Err(eyre!("ERROR : Assertion failed, not able to verify the proof."))
-        } else {
-            Ok(kzg_proof)
+    /// Checks that the settlement batcher only executes the calls of the settlement sender and that
+    /// the core contract accepts the state updates it sends. Batching through a contract anyone can
+    /// call, e.g. a stock Multicall3, would let anyone update the state.
+    async fn ensure_batcher_gated(&self, batcher_client: &SettlementBatcherClient) -> Result<()> {
+        let batcher_address = batcher_client.contract_address();
+        let owner = batcher_client
+            .owner()
+            .await
+            .map_err(|e| eyre!("Failed to read the owner of the settlement batcher {}: {}", batcher_address, e))?;
+        if owner != self.settlement_sender() {
+            return Err(eyre!(
+                "Settlement batcher {} is owned by {}, not by the settlement sender {}",
+                batcher_address,
+                owner,
+                self.settlement_sender()
+            ));
         }
+        if !self.core_contract_client.is_operator(batcher_address).await? {
+            return Err(eyre!("Settlement batcher {} is not an operator of the core contract", batcher_address));
+        }
+        Ok(())
+    }
+
+    /// Checks that the `stateBlockNumber()` of the core contract, as of the block including the
+    /// transaction, reached the block the transaction logged as settled. Later state updates can be
+    /// included in the same block. Transactions which aren't state updates are verified as is.
+    async fn verify_settled_block(
+        &self,
+        receipt: &TransactionReceipt,
+        included_in: u64,
+    ) -> Result<SettlementVerificationStatus> {
+        let core_contract = self.core_contract_client.contract_address();
+        let Some(expected_block) = logged_settled_block(receipt.inner.logs(), core_contract) else {
+            return Ok(SettlementVerificationStatus::Verified);
+        };
+        let settled_block = self.core_contract_client.state_block_number_at(included_in).await?;
+        Ok(settled_block_status(receipt.transaction_hash, expected_block, settled_block))
+    }
+
+    /// Verifies an Ethereum settlement transaction: pending until it has the required
+    /// confirmations, a state update is only verified if the core contract settled the block it
+    /// logged.
+    async fn verify_executed_tx(&self, tx_hash: B256) -> Result<SettlementVerificationStatus> {
+        let maybe_tx_status: Option<TransactionReceipt> = self.provider.get_transaction_receipt(tx_hash).await?;
+        match maybe_tx_status {
+            Some(tx_status) => {
+                if !tx_status.status() {
+                    return Ok(SettlementVerificationStatus::Rejected(format!("Tx {} has been reverted", tx_hash)));
+                }
+                let included_in =
+                    tx_status.block_number.ok_or_else(|| eyre!("Receipt of tx {} has no block", tx_hash))?;
+                let latest_block = self.provider.get_block_number().await?;
+                if !has_required_confirmations(included_in, latest_block, self.required_confirmations) {
+                    return Ok(SettlementVerificationStatus::Pending);
+                }
+                self.verify_settled_block(&tx_status, included_in).await
+            }
+            // not mined yet, the transaction is pending as long as the node knows it
+            None => match self.tx_provider.get_transaction_by_hash(tx_hash).await? {
+                Some(_) => Ok(SettlementVerificationStatus::Pending),
+                None => Ok(SettlementVerificationStatus::Rejected(format!("Could not find status of tx: {}", tx_hash))),
+            },
+        }
+    }
+
+    /// EIP-1559 fees of the next settlement transaction, fails with a
+    /// [`settlement_client_interface::FeeTooHighError`] when they are above the configured caps.
+    async fn settlement_fees(&self) -> Result<Eip1559Fees> {
+        let fee_history = fetch_fee_history(&self.provider).await?;
+        Ok(self.fee_caps.eip1559_fees(&fee_history)?)
+    }
+
+    /// Signs and sends a blob transaction. The transaction is kept around until mined so that it
+    /// can be replaced if it gets stuck.
+    ///
+    /// Private relays don't take blob transactions, they always go through the public mempool.
+    async fn send_blob_tx(&self, tx_sidecar: TxEip4844WithSidecar) -> Result<B256> {
+        let nonce = tx_sidecar.tx.nonce;
+        let mut variant = TxEip4844Variant::from(tx_sidecar.clone());
+        let signature = self.wallet.default_signer().sign_transaction(&mut variant).await?;
+        let tx_signed = variant.into_signed(signature);
+        let tx_envelope: TxEnvelope = tx_signed.into();
+        let encoded = tx_envelope.encoded_2718();
+        let tx_hash = *self.provider.send_raw_transaction(&encoded).await?.tx_hash();
+
+        // the transaction is sent, failing to prune the mined ones is not an error
+        let mined_nonce =
+            self.tx_provider.get_transaction_count(self.wallet_address).latest().await.unwrap_or_default();
+        let mut pending_blob_txs = self.pending_blob_txs.lock().await;
+        // a replaced transaction can't be mined anymore
+        pending_blob_txs.retain(|_, pending| pending.tx.nonce >= mined_nonce && pending.tx.nonce != nonce);
+        pending_blob_txs.insert(tx_hash, tx_sidecar);
+
+        Ok(tx_hash)
     }
 }
 
@@ -118,6 +327,43 @@ impl SettlementClient for EthereumSettlementClient {
         todo!("register_proof is not implemented yet")
     }
 
+    /// Register the memory pages of a proof with `registerContinuousPageBatch`
+    async fn register_memory_pages(&self, registration: MemoryPagesRegistration) -> Result<String> {
+        let memory_pages_client = self.memory_pages_client()?;
+        let entries = memory_page_entries(registration);
+        memory_pages_client
+            .simulate_register_continuous_page_batch(self.settlement_sender(), entries.clone())
+            .await
+            .map_err(contract_simulation_error)?;
+        if let Some(safe_proposer) = &self.safe_proposer {
+            let call = MemoryPageFactRegistry::registerContinuousPageBatchCall { memoryPageEntries: entries };
+            let registry_address = memory_pages_client.contract_address();
+            let safe_tx_hash = safe_proposer.propose(registry_address, call.abi_encode().into()).await?;
+            return Ok(safe_tx_hash.to_string());
+        }
+        let fees = self.settlement_fees().await?;
+        let nonce = self.nonce_manager.next_nonce(&self.tx_provider).await?;
+        let tx_hash = match memory_pages_client
+            .register_continuous_page_batch(self.wallet_address, entries, nonce, fees)
+            .await
+        {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                self.nonce_manager.release(nonce).await;
+                return Err(e.into());
+            }
+        };
+        self.nonce_manager.register_sent(nonce, tx_hash).await;
+        Ok(format!("0x{:x}", tx_hash))
+    }
+
+    /// Check the fact with `isValid` on the fact registry of the SHARP verifier
+    async fn verify_fact(&self, fact: [u8; 32]) -> Result<bool> {
+        let fact_registry_client =
+            self.fact_registry_client.as_ref().ok_or_else(|| eyre!("Fact registry address is not configured"))?;
+        Ok(fact_registry_client.is_valid(B256::from(fact)).await?)
+    }
+
     /// Should be used to update state on core contract when DA is done in calldata
     async fn update_state_calldata(
         &self,
@@ -128,195 +374,614 @@ impl SettlementClient for EthereumSettlementClient {
         let program_output: Vec<U256> = slice_slice_u8_to_vec_u256(program_output.as_slice());
         let onchain_data_hash: U256 = slice_u8_to_u256(&onchain_data_hash);
         let onchain_data_size: U256 = onchain_data_size.try_into()?;
-        let tx_receipt =
-            self.core_contract_client.update_state(program_output, onchain_data_hash, onchain_data_size).await?;
-        Ok(format!("0x{:x}", tx_receipt.transaction_hash))
+        if self.should_simulate().await? {
+            self.core_contract_client
+                .simulate_update_state(
+                    self.settlement_sender(),
+                    program_output.clone(),
+                    onchain_data_hash,
+                    onchain_data_size,
+                )
+                .await
+                .map_err(contract_simulation_error)?;
+        }
+        if let Some(safe_proposer) = &self.safe_proposer {
+            let call = StarknetValidityContract::updateStateCall {
+                programOutput: program_output,
+                onchainDataHash: onchain_data_hash,
+                onchainDataSize: onchain_data_size,
+            };
+            let core_contract_address = self.core_contract_client.contract_address();
+            let safe_tx_hash = safe_proposer.propose(core_contract_address, call.abi_encode().into()).await?;
+            return Ok(safe_tx_hash.to_string());
+        }
+        let fees = self.settlement_fees().await?;
+        let nonce = self.nonce_manager.next_nonce(&self.tx_provider).await?;
+        let tx_hash = match self
+            .core_contract_client
+            .update_state(self.wallet_address, program_output, onchain_data_hash, onchain_data_size, nonce, fees)
+            .await
+        {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                self.nonce_manager.release(nonce).await;
+                return Err(e.into());
+            }
+        };
+        self.nonce_manager.register_sent(nonce, tx_hash).await;
+        Ok(format!("0x{:x}", tx_hash))
+    }
+
+    /// Send the operations through the settlement batcher with `aggregate3`, none of them is
+    /// allowed to fail
+    async fn settle_batch(&self, operations: Vec<SettlementOperation>) -> Result<String> {
+        let batcher_client = self.batcher_client()?;
+        self.ensure_batcher_gated(batcher_client).await?;
+        let memory_pages_registry = self.memory_pages_client.as_ref().map(|client| client.contract_address());
+        let calls = batch_calls(operations, self.core_contract_client.contract_address(), memory_pages_registry)?;
+        if self.should_simulate().await? {
+            batcher_client
+                .simulate_aggregate3(self.settlement_sender(), calls.clone())
+                .await
+                .map_err(contract_simulation_error)?;
+        }
+        if let Some(safe_proposer) = &self.safe_proposer {
+            let call = SettlementBatcher::aggregate3Call { calls };
+            let batcher_address = batcher_client.contract_address();
+            let safe_tx_hash = safe_proposer.propose(batcher_address, call.abi_encode().into()).await?;
+            return Ok(safe_tx_hash.to_string());
+        }
+        let fees = self.settlement_fees().await?;
+        let nonce = self.nonce_manager.next_nonce(&self.tx_provider).await?;
+        let tx_hash = match batcher_client.aggregate3(self.wallet_address, calls, nonce, fees).await {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                self.nonce_manager.release(nonce).await;
+                return Err(e.into());
+            }
+        };
+        self.nonce_manager.register_sent(nonce, tx_hash).await;
+        Ok(format!("0x{:x}", tx_hash))
     }
 
     /// Should be used to update state on core contract when DA is in blobs/alt DA
     async fn update_state_blobs(&self, program_output: Vec<[u8; 32]>, kzg_proof: [u8; 48]) -> Result<String> {
+        self.ensure_no_safe("DA in blobs")?;
+        if self.core_contract_version != CoreContractVersion::KzgDa {
+            return Err(eyre!(
+                "Updating the state with a single KZG proof is not supported by the {:?} core contract",
+                self.core_contract_version
+            ));
+        }
         let program_output: Vec<U256> = slice_slice_u8_to_vec_u256(&program_output);
-        let tx_receipt = self.core_contract_client.update_state_kzg(program_output, kzg_proof).await?;
-        Ok(format!("0x{:x}", tx_receipt.transaction_hash))
+        if self.should_simulate().await? {
+            self.core_contract_client
+                .simulate_update_state_kzg(self.wallet_address, program_output.clone(), kzg_proof)
+                .await
+                .map_err(contract_simulation_error)?;
+        }
+        let fees = self.settlement_fees().await?;
+        let nonce = self.nonce_manager.next_nonce(&self.tx_provider).await?;
+        let tx_hash = match self
+            .core_contract_client
+            .update_state_kzg(self.wallet_address, program_output, kzg_proof, nonce, fees)
+            .await
+        {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                self.nonce_manager.release(nonce).await;
+                return Err(e.into());
+            }
+        };
+        self.nonce_manager.register_sent(nonce, tx_hash).await;
+        Ok(format!("0x{:x}", tx_hash))
     }
 
     async fn update_state_with_blobs(&self, program_output: Vec<[u8; 32]>, state_diff: Vec<Vec<u8>>) -> Result<String> {
-        let trusted_setup = KzgSettings::load_trusted_setup_file(Path::new("./trusted_setup.txt"))
-            .expect("issue while loading the trusted setup");
-        let (sidecar_blobs, sidecar_commitments, sidecar_proofs) = prepare_sidecar(&state_diff, &trusted_setup).await?;
-        let sidecar = BlobTransactionSidecar::new(sidecar_blobs, sidecar_commitments, sidecar_proofs);
+        // a Safe can't send blob transactions, only EOAs can
+        self.ensure_no_safe("DA in blobs")?;
+        let input = match self.core_contract_version {
+            CoreContractVersion::Pre4844 => {
+                return Err(eyre!("The pre-EIP-4844 core contract doesn't support DA in blobs"));
+            }
+            CoreContractVersion::KzgDa => {
+                let kzg_proof = build_kzg_proof(&state_diff, x_0_point(&program_output)?)?.to_bytes().into_inner();
+                get_txn_input_bytes(program_output, kzg_proof)
+            }
+            CoreContractVersion::MultiBlobKzgDa => {
+                let kzg_proofs = build_kzg_proofs(&state_diff, x_0_point(&program_output)?)?
+                    .into_iter()
+                    .map(|kzg_proof| kzg_proof.to_bytes().into_inner())
+                    .collect();
+                get_multi_blob_txn_input_bytes(program_output, kzg_proofs)
+            }
+        };
+        let sidecar = prepare_sidecar(&state_diff)?;
 
-        let eip1559_est = self.provider.estimate_eip1559_fees(None).await?;
         let chain_id: u64 = self.provider.get_chain_id().await?.to_string().parse()?;
 
-        let max_fee_per_blob_gas: u128 = self.provider.get_blob_base_fee().await?.to_string().parse()?;
-        let max_priority_fee_per_gas: u128 = self.provider.get_max_priority_fee_per_gas().await?.to_string().parse()?;
-
-        let nonce = self.provider.get_transaction_count(self.wallet_address).await?.to_string().parse()?;
+        let fees = self.settlement_fees().await?;
+        let blob_base_fee: u128 = self.provider.get_blob_base_fee().await?.to_string().parse()?;
+        let max_fee_per_blob_gas = self.fee_caps.max_fee_per_blob_gas(blob_base_fee)?;
+
+        if self.should_simulate().await? {
+            let simulation = TransactionRequest {
+                from: Some(self.wallet_address),
+                to: Some(TxKind::Call(self.core_contract_client.contract_address())),
+                input: TransactionInput::new(input.clone()),
+                blob_versioned_hashes: Some(sidecar.versioned_hashes().collect()),
+                max_fee_per_blob_gas: Some(max_fee_per_blob_gas),
+                ..Default::default()
+            };
+            self.provider.call(&simulation).await.map_err(simulation_error)?;
+        }
 
-        // x_0_value : program_output[6]
-        let kzg_proof = Self::build_proof(
-            state_diff,
-            Bytes32::from_bytes(program_output[6].as_slice()).expect("Not able to get x_0 point params."),
-        )
-        .await
-        .expect("Unable to build KZG proof for given params.")
-        .to_owned();
+        let nonce = self.nonce_manager.next_nonce(&self.tx_provider).await?;
 
         let tx = TxEip4844 {
             chain_id,
             nonce,
             gas_limit: 30_000_000,
-            max_fee_per_gas: eip1559_est.max_fee_per_gas.to_string().parse()?,
-            max_priority_fee_per_gas,
+            max_fee_per_gas: fees.max_fee_per_gas,
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
             to: self.core_contract_client.contract_address(),
             value: U256::from(0),
             access_list: AccessList(vec![]),
             blob_versioned_hashes: sidecar.versioned_hashes().collect(),
             max_fee_per_blob_gas,
-            input: get_txn_input_bytes(program_output, kzg_proof),
+            input,
         };
-        let tx_sidecar = TxEip4844WithSidecar { tx: tx.clone(), sidecar: sidecar.clone() };
-        let mut variant = TxEip4844Variant::from(tx_sidecar);
-
-        // Sign and submit
-        let signature = self.wallet.default_signer().sign_transaction(&mut variant).await?;
-        let tx_signed = variant.into_signed(signature);
-        let tx_envelope: TxEnvelope = tx_signed.into();
-        let encoded = tx_envelope.encoded_2718();
-
-        let pending_tx = self.provider.send_raw_transaction(&encoded).await?;
+        let tx_sidecar = TxEip4844WithSidecar { tx, sidecar };
+
+        // the nonce is handed out again if the transaction doesn't make it to the node
+        let tx_hash = match self.send_blob_tx(tx_sidecar).await {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                self.nonce_manager.release(nonce).await;
+                return Err(e);
+            }
+        };
+        self.nonce_manager.register_sent(nonce, tx_hash).await;
 
-        Ok(pending_tx.tx_hash().to_string())
+        Ok(tx_hash.to_string())
     }
 
-    /// Should verify the inclusion of a tx in the settlement layer
+    /// Should verify the inclusion of a tx in the settlement layer. In Safe operator mode the Safe
+    /// transaction is pending until executed, then the executing transaction is verified.
     async fn verify_tx_inclusion(&self, tx_hash: &str) -> Result<SettlementVerificationStatus> {
-        let tx_hash = B256::from_str(tx_hash)?;
-        let maybe_tx_status: Option<TransactionReceipt> = self.provider.get_transaction_receipt(tx_hash).await?;
-        match maybe_tx_status {
-            Some(tx_status) => {
-                if tx_status.status() {
-                    Ok(SettlementVerificationStatus::Verified)
-                } else {
-                    Ok(SettlementVerificationStatus::Pending)
-                }
-            }
-            None => Ok(SettlementVerificationStatus::Rejected(format!("Could not find status of tx: {}", tx_hash))),
+        if let Some(safe_proposer) = &self.safe_proposer {
+            let safe_tx_hash = B256::from_str(tx_hash)?;
+            return match safe_proposer.status(safe_tx_hash).await? {
+                SafeTxStatus::Pending => Ok(SettlementVerificationStatus::Pending),
+                SafeTxStatus::Executed { tx_hash, successful: false } => Ok(SettlementVerificationStatus::Rejected(
+                    format!("Safe tx {} has been executed by tx {} and failed", safe_tx_hash, tx_hash),
+                )),
+                SafeTxStatus::Executed { tx_hash, successful: true } => self.verify_executed_tx(tx_hash).await,
+            };
         }
+        self.verify_executed_tx(B256::from_str(tx_hash)?).await
     }
 
-    /// Wait for a pending tx to achieve finality
+    /// Wait for a pending tx to get the required confirmations. In Safe operator mode, returns
+    /// right away while the Safe transaction isn't executed: the owners execute it, whenever.
     async fn wait_for_tx_finality(&self, tx_hash: &str) -> Result<()> {
-        let tx_hash = B256::from_str(tx_hash)?;
-        self.provider.watch_pending_transaction(PendingTransactionConfig::new(tx_hash)).await?;
+        let Some(tx_hash) = self.executed_tx_hash(tx_hash).await? else {
+            return Ok(());
+        };
+        self.provider
+            .watch_pending_transaction(
+                PendingTransactionConfig::new(tx_hash).with_required_confirmations(self.required_confirmations),
+            )
+            .await?;
         Ok(())
     }
 
+    /// The owners execute the Safe transactions, the operator doesn't pick their fees
+    fn supports_tx_replacement(&self) -> bool {
+        self.safe_proposer.is_none()
+    }
+
+    /// Replace a state update transaction stuck in the mempool, same nonce and bumped fees
+    async fn resubmit_tx_with_bumped_fees(&self, tx_hash: &str) -> Result<String> {
+        self.ensure_no_safe("Replacing a transaction")?;
+        let tx_hash = B256::from_str(tx_hash)?;
+        let tx = self
+            .tx_provider
+            .get_transaction_by_hash(tx_hash)
+            .await?
+            .ok_or_else(|| eyre!("Tx {} is unknown to the node, it can't be replaced", tx_hash))?;
+        if tx.block_number.is_some() {
+            return Err(eyre!("Tx {} is already mined, it can't be replaced", tx_hash));
+        }
+        let previous = Eip1559Fees {
+            max_fee_per_gas: tx.max_fee_per_gas.ok_or_else(|| eyre!("Tx {} is not an EIP-1559 transaction", tx_hash))?,
+            max_priority_fee_per_gas: tx
+                .max_priority_fee_per_gas
+                .ok_or_else(|| eyre!("Tx {} is not an EIP-1559 transaction", tx_hash))?,
+        };
+        let fees = self.fee_caps.bumped_fees(previous, &fetch_fee_history(&self.provider).await?)?;
+
+        let replacement_hash = if tx.transaction_type == Some(EIP4844_TX_TYPE) {
+            let mut tx_sidecar = self
+                .pending_blob_txs
+                .lock()
+                .await
+                .get(&tx_hash)
+                .cloned()
+                .ok_or_else(|| eyre!("Sidecar of blob tx {} is unknown, it can't be replaced", tx_hash))?;
+            let blob_base_fee: u128 = self.provider.get_blob_base_fee().await?.to_string().parse()?;
+            tx_sidecar.tx.max_fee_per_blob_gas =
+                self.fee_caps.bumped_max_fee_per_blob_gas(tx_sidecar.tx.max_fee_per_blob_gas, blob_base_fee)?;
+            tx_sidecar.tx.max_fee_per_gas = fees.max_fee_per_gas;
+            tx_sidecar.tx.max_priority_fee_per_gas = fees.max_priority_fee_per_gas;
+            self.send_blob_tx(tx_sidecar).await?
+        } else if let Ok(call) = StarknetValidityContract::updateStateCall::abi_decode(&tx.input, true) {
+            self.core_contract_client
+                .update_state(
+                    self.wallet_address,
+                    call.programOutput,
+                    call.onchainDataHash,
+                    call.onchainDataSize,
+                    tx.nonce,
+                    fees,
+                )
+                .await?
+        } else if let Ok(call) = StarknetValidityContract::updateStateKzgDACall::abi_decode(&tx.input, true) {
+            let kzg_proof: [u8; 48] = call.kzgProof.as_ref().try_into()?;
+            self.core_contract_client
+                .update_state_kzg(self.wallet_address, call.programOutput, kzg_proof, tx.nonce, fees)
+                .await?
+        } else if let Ok(call) = MemoryPageFactRegistry::registerContinuousPageBatchCall::abi_decode(&tx.input, true) {
+            self.memory_pages_client()?
+                .register_continuous_page_batch(self.wallet_address, call.memoryPageEntries, tx.nonce, fees)
+                .await?
+        } else if let Ok(call) = SettlementBatcher::aggregate3Call::abi_decode(&tx.input, true) {
+            self.batcher_client()?.aggregate3(self.wallet_address, call.calls, tx.nonce, fees).await?
+        } else {
+            return Err(eyre!("Tx {} is not a settlement transaction, it can't be replaced", tx_hash));
+        };
+        self.nonce_manager.register_sent(tx.nonce, replacement_hash).await;
+
+        Ok(format!("0x{:x}", replacement_hash))
+    }
+
+    /// Get the fee paid by a tx, including the blob fee
+    async fn get_tx_fee(&self, tx_hash: &str) -> Result<Option<u128>> {
+        let Some(tx_hash) = self.executed_tx_hash(tx_hash).await? else {
+            return Ok(None);
+        };
+        let Some(receipt) = self.provider.get_transaction_receipt(tx_hash).await? else {
+            return Ok(None);
+        };
+        let execution_fee = receipt.gas_used * receipt.effective_gas_price;
+        let blob_fee = receipt.blob_gas_used.unwrap_or_default() * receipt.blob_gas_price.unwrap_or_default();
+        Ok(Some(execution_fee + blob_fee))
+    }
+
+    /// Get the ETH balance of the operator wallet, in wei
+    async fn get_operator_balance(&self) -> Result<u128> {
+        let balance = self.provider.get_balance(self.wallet_address).await?;
+        balance.try_into().map_err(|_| eyre!("Balance of {} doesn't fit in a u128", self.wallet_address))
+    }
+
     /// Get the last block settled through the core contract
     async fn get_last_settled_block(&self) -> Result<u64> {
         let block_number = self.core_contract_client.state_block_number().await?;
         Ok(block_number.try_into()?)
     }
-}
 
-/// To prepare the sidecar for EIP 4844 transaction
-async fn prepare_sidecar(
-    state_diff: &[Vec<u8>],
-    trusted_setup: &KzgSettings,
-) -> Result<(Vec<FixedBytes<131072>>, Vec<FixedBytes<48>>, Vec<FixedBytes<48>>)> {
-    let mut sidecar_blobs = vec![];
-    let mut sidecar_commitments = vec![];
-    let mut sidecar_proofs = vec![];
+    /// Get the state root of the last block settled through the core contract
+    async fn get_state_root(&self) -> Result<[u8; 32]> {
+        let state_root = self.core_contract_client.state_root().await?;
+        Ok(state_root.to_be_bytes())
+    }
 
-    for blob_data in state_diff {
-        let fixed_size_blob: [u8; BYTES_PER_BLOB] = blob_data.as_slice().try_into()?;
+    /// Get the hash of the OS program accepted by the core contract
+    async fn get_program_hash(&self) -> Result<[u8; 32]> {
+        let program_hash = self.core_contract_client.program_hash().await?;
+        Ok(program_hash.to_be_bytes())
+    }
 
-        let blob = Blob::new(fixed_size_blob);
+    /// Get the latest block number of the settlement layer
+    async fn get_latest_block_number(&self) -> Result<u64> {
+        Ok(self.provider.get_block_number().await?)
+    }
+
+    /// Get the transactions sent by the operator wallet in the given block range
+    async fn get_operator_transactions(&self, from_block: u64, to_block: u64) -> Result<Vec<String>> {
+        let mut tx_hashes = Vec::new();
+        for block_number in from_block..to_block + 1 {
+            let block = self
+                .provider
+                .get_block_by_number(BlockNumberOrTag::Number(block_number), true)
+                .await?
+                .ok_or_else(|| eyre!("Block {} not found", block_number))?;
+            if let BlockTransactions::Full(transactions) = block.transactions {
+                tx_hashes.extend(
+                    transactions
+                        .iter()
+                        .filter(|tx| tx.from == self.wallet_address)
+                        .map(|tx| format!("0x{:x}", tx.hash)),
+                );
+            }
+        }
+        Ok(tx_hashes)
+    }
 
-        let commitment = KzgCommitment::blob_to_kzg_commitment(&blob, trusted_setup)?;
-        let proof = KzgProof::compute_blob_kzg_proof(&blob, &commitment.to_bytes(), trusted_setup)?;
+    /// Get the messages sent to the L2 through the core contract in the given block range
+    async fn get_messages_to_l2(&self, from_block: u64, to_block: u64) -> Result<Vec<L1ToL2Message>> {
+        let logs = self.core_contract_client.messages_to_l2(from_block, to_block).await?;
+        logs.into_iter()
+            .map(|(message, log)| {
+                let tx_hash = log.transaction_hash.ok_or_else(|| eyre!("Message to L2 log without transaction"))?;
+                let block_number = log.block_number.ok_or_else(|| eyre!("Message to L2 log without block"))?;
+                Ok(L1ToL2Message {
+                    message_hash: l1_to_l2_message_hash(&message).0,
+                    tx_hash: format!("0x{:x}", tx_hash),
+                    block_number,
+                    from_address: message.fromAddress.into_word().0,
+                    to_address: message.toAddress.to_be_bytes(),
+                    selector: message.selector.to_be_bytes(),
+                    payload: message.payload.iter().map(|value| value.to_be_bytes()).collect(),
+                    nonce: message.nonce.to_be_bytes(),
+                    fee: message.fee.try_into().map_err(|_| eyre!("Fee of message to L2 doesn't fit in a u128"))?,
+                })
+            })
+            .collect()
+    }
 
-        sidecar_blobs.push(FixedBytes::new(fixed_size_blob));
-        sidecar_commitments.push(FixedBytes::new(commitment.to_bytes().into_inner()));
-        sidecar_proofs.push(FixedBytes::new(proof.to_bytes().into_inner()));
+    /// The core contract keeps the fee plus one of the pending messages, and 0 for the consumed ones
+    async fn is_message_to_l2_consumed(&self, message_hash: [u8; 32]) -> Result<bool> {
+        let pending = self.core_contract_client.l1_to_l2_messages(B256::from(message_hash)).await?;
+        Ok(pending.is_zero())
     }
+}
+
+/// Hash of a message sent to the L2, as computed by the core contract: the keccak of the sender,
+/// the recipient, the nonce, the selector and the length-prefixed payload, packed as 32-byte words
+fn l1_to_l2_message_hash(message: &StarknetValidityContract::LogMessageToL2) -> B256 {
+    let mut words = vec![
+        message.fromAddress.into_word(),
+        message.toAddress.into(),
+        message.nonce.into(),
+        message.selector.into(),
+        U256::from(message.payload.len()).into(),
+    ];
+    words.extend(message.payload.iter().map(|value| B256::from(*value)));
+    keccak256(words.iter().flat_map(|word| word.0).collect::<Vec<u8>>())
+}
 
-    Ok((sidecar_blobs, sidecar_commitments, sidecar_proofs))
+/// Provider signing the transactions with `wallet` and sending them to `url`.
+fn build_provider(url: Url, wallet: &EthereumWallet) -> EthHttpProvider {
+    let http_client = build_http_client!(reqwest).expect("Failed to build the Ethereum HTTP client");
+    let client = RpcClient::new(Http::with_client(http_client, url), false);
+    ProviderBuilder::new().with_recommended_fillers().wallet(wallet.clone()).on_client(client)
 }
 
-/// Function to construct the transaction for updating the state in core contract.
+/// Function to construct the calldata of `updateStateKzgDA` for the blob transaction updating
+/// the state in core contract.
 fn get_txn_input_bytes(program_output: Vec<[u8; 32]>, kzg_proof: [u8; 48]) -> Bytes {
-    let program_output_hex_string = vec_u8_32_to_hex_string(program_output);
-    let kzg_proof_hex_string = u8_48_to_hex_string(kzg_proof);
-    // cast keccak "updateStateKzgDA(uint256[] calldata programOutput, bytes calldata kzgProof)" | cut -b 1-10
-    let function_selector = "0x1a790556";
+    let call = StarknetValidityContract::updateStateKzgDACall {
+        programOutput: slice_slice_u8_to_vec_u256(&program_output),
+        kzgProof: Bytes::copy_from_slice(&kzg_proof),
+    };
+    call.abi_encode().into()
+}
 
-    Bytes::from(program_output_hex_string + &kzg_proof_hex_string + function_selector)
+/// Entries of `registerContinuousPageBatch`, all the pages of a proof share its interaction
+/// elements.
+fn memory_page_entries(registration: MemoryPagesRegistration) -> Vec<MemoryPageFactRegistry::MemoryPageEntry> {
+    let z = slice_u8_to_u256(&registration.z);
+    let alpha = slice_u8_to_u256(&registration.alpha);
+    registration
+        .pages
+        .into_iter()
+        .map(|page| MemoryPageFactRegistry::MemoryPageEntry {
+            startAddr: U256::from(page.start_address),
+            values: slice_slice_u8_to_vec_u256(&page.values),
+            z,
+            alpha,
+            prime: STARK_PRIME,
+        })
+        .collect()
 }
 
-fn vec_u8_32_to_hex_string(data: Vec<[u8; 32]>) -> String {
-    data.into_iter().fold(String::new(), |mut output, arr| {
-        // Convert the array to a hex string
-        let hex = arr.iter().fold(String::new(), |mut output, byte| {
-            let _ = write!(output, "{byte:02x}");
-            output
-        });
+/// Calls of `aggregate3` performing the operations, in order.
+fn batch_calls(
+    operations: Vec<SettlementOperation>,
+    core_contract: Address,
+    memory_pages_registry: Option<Address>,
+) -> Result<Vec<SettlementBatcher::Call3>> {
+    operations
+        .into_iter()
+        .map(|operation| {
+            let (target, call_data): (Address, Bytes) = match operation {
+                SettlementOperation::UpdateStateCalldata { program_output, onchain_data_hash, onchain_data_size } => {
+                    let call = StarknetValidityContract::updateStateCall {
+                        programOutput: slice_slice_u8_to_vec_u256(&program_output),
+                        onchainDataHash: slice_u8_to_u256(&onchain_data_hash),
+                        onchainDataSize: U256::from(onchain_data_size),
+                    };
+                    (core_contract, call.abi_encode().into())
+                }
+                SettlementOperation::RegisterMemoryPages(registration) => {
+                    let registry = memory_pages_registry
+                        .ok_or_else(|| eyre!("Memory page fact registry address is not configured"))?;
+                    let call = MemoryPageFactRegistry::registerContinuousPageBatchCall {
+                        memoryPageEntries: memory_page_entries(registration),
+                    };
+                    (registry, call.abi_encode().into())
+                }
+            };
+            Ok(SettlementBatcher::Call3 { target, allowFailure: false, callData: call_data })
+        })
+        .collect()
+}
 
-        // Ensure the hex string is exactly 64 characters (32 bytes)
-        let _ = write!(output, "{hex:0>64}");
-        output
-    })
+/// Whether a transaction included in `included_in` has the required confirmations as of
+/// `latest_block`. A node behind the block including it, e.g. after a reorg, doesn't confirm it.
+fn has_required_confirmations(included_in: u64, latest_block: u64, required_confirmations: u64) -> bool {
+    latest_block >= included_in && latest_block - included_in + 1 >= required_confirmations
 }
 
-fn u8_48_to_hex_string(data: [u8; 48]) -> String {
-    // Split the array into two parts
-    let (first_32, last_16) = data.split_at(32);
+/// Block the state update of the core contract logged as settled, `None` when the logs have no
+/// state update
+fn logged_settled_block(logs: &[Log], core_contract: Address) -> Option<I256> {
+    logs.iter()
+        .filter(|log| log.address() == core_contract)
+        .find_map(|log| log.log_decode::<StarknetValidityContract::LogStateUpdate>().ok())
+        .map(|log| log.inner.data.blockNumber)
+}
 
-    // Convert and pad each part
-    let first_hex = to_padded_hex(first_32);
-    let second_hex = to_padded_hex(last_16);
+/// Status of a state update logging `expected_block` as settled, given the `stateBlockNumber()`
+/// of the core contract as of the block including it: behind it, the state update was undone
+fn settled_block_status(tx_hash: B256, expected_block: I256, settled_block: I256) -> SettlementVerificationStatus {
+    if settled_block < expected_block {
+        return SettlementVerificationStatus::Rejected(format!(
+            "Tx {} settled block {} but the core contract stateBlockNumber() is {}",
+            tx_hash, expected_block, settled_block
+        ));
+    }
+    SettlementVerificationStatus::Verified
+}
 
-    // Concatenate the two hex strings
-    first_hex + &second_hex
+/// Same as [`get_txn_input_bytes`] for the core contracts taking a KZG proof per blob.
+fn get_multi_blob_txn_input_bytes(program_output: Vec<[u8; 32]>, kzg_proofs: Vec<[u8; 48]>) -> Bytes {
+    let call = StarknetValidityContractMultiBlob::updateStateKzgDACall {
+        programOutput: slice_slice_u8_to_vec_u256(&program_output),
+        kzgProofs: kzg_proofs.iter().map(|kzg_proof| Bytes::copy_from_slice(kzg_proof)).collect(),
+    };
+    call.abi_encode().into()
 }
 
-// Function to convert a slice of u8 to a padded hex string
-fn to_padded_hex(slice: &[u8]) -> String {
-    let hex = slice.iter().fold(String::new(), |mut output, byte| {
-        let _ = write!(output, "{byte:02x}");
-        output
-    });
-    format!("{:0<64}", hex)
+#[rstest]
+fn test_txn_input_bytes() {
+    let program_output = vec![[1; 32], [2; 32]];
+    let kzg_proof = [3; 48];
+
+    let input = get_txn_input_bytes(program_output.clone(), kzg_proof);
+
+    assert_eq!(input[..4], StarknetValidityContract::updateStateKzgDACall::SELECTOR);
+    let decoded = StarknetValidityContract::updateStateKzgDACall::abi_decode(&input, true).unwrap();
+    assert_eq!(decoded.programOutput, slice_slice_u8_to_vec_u256(&program_output));
+    assert_eq!(decoded.kzgProof.as_ref(), kzg_proof.as_slice());
+}
+
+#[rstest]
+fn test_memory_page_entries() {
+    use settlement_client_interface::ContinuousMemoryPage;
+
+    let registration = MemoryPagesRegistration {
+        pages: vec![
+            ContinuousMemoryPage { start_address: 10, values: vec![[1; 32], [2; 32]] },
+            ContinuousMemoryPage { start_address: 12, values: vec![[3; 32]] },
+        ],
+        z: [4; 32],
+        alpha: [5; 32],
+    };
+
+    let entries = memory_page_entries(registration);
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].startAddr, U256::from(10));
+    assert_eq!(entries[0].values, slice_slice_u8_to_vec_u256(&[[1; 32], [2; 32]]));
+    assert_eq!(entries[1].startAddr, U256::from(12));
+    assert!(entries.iter().all(|entry| entry.z == slice_u8_to_u256(&[4; 32])
+        && entry.alpha == slice_u8_to_u256(&[5; 32])
+        && entry.prime == STARK_PRIME));
 }
 
 #[rstest]
-fn test_data_conversion() {
-    let data: [u8; 48] = [
-        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30,
-        31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48,
+fn test_batch_calls() {
+    let core_contract = Address::repeat_byte(1);
+    let memory_pages_registry = Address::repeat_byte(2);
+    let registration = MemoryPagesRegistration { pages: vec![], z: [4; 32], alpha: [5; 32] };
+    let operations = vec![
+        SettlementOperation::RegisterMemoryPages(registration),
+        SettlementOperation::UpdateStateCalldata {
+            program_output: vec![[1; 32]],
+            onchain_data_hash: [2; 32],
+            onchain_data_size: 3,
+        },
     ];
 
-    let result = u8_48_to_hex_string(data);
+    let calls = batch_calls(operations.clone(), core_contract, Some(memory_pages_registry)).unwrap();
 
-    assert_eq!(result, "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f3000000000000000000000000000000000");
+    assert_eq!(calls.len(), 2);
+    assert!(calls.iter().all(|call| !call.allowFailure));
+    assert_eq!(calls[0].target, memory_pages_registry);
+    assert_eq!(calls[0].callData[..4], MemoryPageFactRegistry::registerContinuousPageBatchCall::SELECTOR);
+    assert_eq!(calls[1].target, core_contract);
+    let decoded = StarknetValidityContract::updateStateCall::abi_decode(&calls[1].callData, true).unwrap();
+    assert_eq!(decoded.programOutput, slice_slice_u8_to_vec_u256(&[[1; 32]]));
+    assert_eq!(decoded.onchainDataSize, U256::from(3));
 
-    let mut data_2: [u8; 32] = [
-        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30,
-        31, 32,
-    ];
-    let mut data_vec: Vec<[u8; 32]> = Vec::new();
-    data_vec.push(data_2);
-    data_2.reverse();
-    data_vec.push(data_2);
-
-    let data_3: [u8; 32] = [
-        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30,
-        0, 0,
-    ];
-    data_vec.push(data_3);
+    // the memory pages can't be registered without the registry
+    assert!(batch_calls(operations, core_contract, None).is_err());
+}
 
-    let result_2 = vec_u8_32_to_hex_string(data_vec);
+#[rstest]
+fn test_multi_blob_txn_input_bytes() {
+    let program_output = vec![[1; 32], [2; 32]];
+    let kzg_proofs = vec![[3; 48], [4; 48]];
+
+    let input = get_multi_blob_txn_input_bytes(program_output.clone(), kzg_proofs.clone());
+
+    assert_eq!(input[..4], StarknetValidityContractMultiBlob::updateStateKzgDACall::SELECTOR);
+    assert_ne!(input[..4], StarknetValidityContract::updateStateKzgDACall::SELECTOR);
+    let decoded = StarknetValidityContractMultiBlob::updateStateKzgDACall::abi_decode(&input, true).unwrap();
+    assert_eq!(decoded.programOutput, slice_slice_u8_to_vec_u256(&program_output));
+    let decoded_proofs: Vec<Vec<u8>> = decoded.kzgProofs.iter().map(|kzg_proof| kzg_proof.to_vec()).collect();
+    assert_eq!(decoded_proofs, vec![kzg_proofs[0].to_vec(), kzg_proofs[1].to_vec()]);
+}
 
-    assert_eq!(result_2, "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20201f1e1d1c1b1a191817161514131211100f0e0d0c0b0a0908070605040302010102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e0000");
+#[rstest]
+#[case::below_threshold(100, 101, 3, false)]
+#[case::at_threshold(100, 102, 3, true)]
+#[case::above_threshold(100, 110, 3, true)]
+#[case::same_block(100, 100, 1, true)]
+#[case::reorged(100, 99, 1, false)]
+fn test_has_required_confirmations(
+    #[case] included_in: u64,
+    #[case] latest_block: u64,
+    #[case] required_confirmations: u64,
+    #[case] confirmed: bool,
+) {
+    assert_eq!(has_required_confirmations(included_in, latest_block, required_confirmations), confirmed);
+}
+
+#[rstest]
+fn test_logged_settled_block() {
+    use alloy::sol_types::SolEvent;
+
+    let core_contract = Address::repeat_byte(1);
+    let state_update = |address: Address, block_number: i64| Log {
+        inner: alloy::primitives::Log {
+            address,
+            data: StarknetValidityContract::LogStateUpdate {
+                globalRoot: U256::from(1),
+                blockNumber: I256::try_from(block_number).unwrap(),
+                blockHash: U256::from(2),
+            }
+            .encode_log_data(),
+        },
+        ..Default::default()
+    };
+
+    let logs = vec![state_update(Address::repeat_byte(2), 5), state_update(core_contract, 7)];
+    assert_eq!(logged_settled_block(&logs, core_contract), Some(I256::try_from(7).unwrap()));
+    assert_eq!(logged_settled_block(&logs[..1], core_contract), None);
+}
+
+#[rstest]
+#[case::settled(7, 7, true)]
+#[case::settled_by_a_later_update(7, 9, true)]
+#[case::reorged(7, 6, false)]
+fn test_settled_block_status(#[case] expected_block: i64, #[case] settled_block: i64, #[case] verified: bool) {
+    let status = settled_block_status(
+        B256::ZERO,
+        I256::try_from(expected_block).unwrap(),
+        I256::try_from(settled_block).unwrap(),
+    );
+    assert_eq!(status == SettlementVerificationStatus::Verified, verified);
 }
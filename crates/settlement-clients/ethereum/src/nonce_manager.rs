@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use alloy::primitives::{Address, B256};
+use alloy::providers::Provider;
+use color_eyre::Result;
+use tokio::sync::Mutex;
+
+use crate::types::EthHttpProvider;
+
+/// Hands out the nonces of the operator account for the transactions sent by the settlement
+/// client.
+///
+/// The nonce is fetched from the pending block of the node on first use and tracked locally
+/// afterwards, so that a transaction can be sent before the previous one is mined. The
+/// transactions in flight are remembered by nonce: when one of them is dropped from the mempool
+/// its nonce is handed out again, the next transaction replaces it instead of being stuck behind a
+/// gap.
+pub struct NonceManager {
+    address: Address,
+    state: Mutex<NonceState>,
+}
+
+#[derive(Debug, Default)]
+struct NonceState {
+    /// Next nonce to hand out, `None` until it is fetched from the node
+    next_nonce: Option<u64>,
+    /// Transactions sent but not mined yet, by nonce
+    pending: BTreeMap<u64, B256>,
+}
+
+impl NonceManager {
+    pub fn new(address: Address) -> Self {
+        Self { address, state: Mutex::new(NonceState::default()) }
+    }
+
+    /// Returns the nonce to use for the next transaction of the account, fetching it from the node
+    /// on first use.
+    pub async fn next_nonce(&self, provider: &EthHttpProvider) -> Result<u64> {
+        let mut state = self.state.lock().await;
+
+        let mined_nonce = provider.get_transaction_count(self.address).latest().await?;
+        state.prune_mined(mined_nonce);
+
+        let mut dropped_nonce = None;
+        for (nonce, tx_hash) in &state.pending {
+            if provider.get_transaction_by_hash(*tx_hash).await?.is_none() {
+                dropped_nonce = Some(*nonce);
+                break;
+            }
+        }
+        if let Some(nonce) = dropped_nonce {
+            tracing::warn!("Transaction with nonce {} of {} was dropped, its nonce is used again", nonce, self.address);
+            state.release_from(nonce);
+        }
+
+        let nonce = match state.next_nonce {
+            Some(nonce) => nonce.max(mined_nonce),
+            None => provider.get_transaction_count(self.address).pending().await?,
+        };
+        state.next_nonce = Some(nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Whether transactions of the account were sent and aren't mined yet.
+    pub async fn has_pending(&self, provider: &EthHttpProvider) -> Result<bool> {
+        let mut state = self.state.lock().await;
+        let mined_nonce = provider.get_transaction_count(self.address).latest().await?;
+        state.prune_mined(mined_nonce);
+        Ok(!state.pending.is_empty())
+    }
+
+    /// Records the transaction sent with `nonce`, it is tracked until mined.
+    pub async fn register_sent(&self, nonce: u64, tx_hash: B256) {
+        self.state.lock().await.pending.insert(nonce, tx_hash);
+    }
+
+    /// Returns `nonce` when no transaction could be sent with it.
+    pub async fn release(&self, nonce: u64) {
+        let mut state = self.state.lock().await;
+        if state.next_nonce == Some(nonce + 1) {
+            state.next_nonce = Some(nonce);
+        } else {
+            // later nonces were handed out in the meantime, leave it to the node to tell where
+            // the account stands
+            state.next_nonce = None;
+        }
+    }
+}
+
+impl NonceState {
+    /// Forgets the transactions mined with a nonce below `mined_nonce`.
+    fn prune_mined(&mut self, mined_nonce: u64) {
+        self.pending.retain(|nonce, _| *nonce >= mined_nonce);
+    }
+
+    /// Hands out `nonce` and the following ones again, the transactions sent with them are meant
+    /// to be replaced.
+    fn release_from(&mut self, nonce: u64) {
+        self.pending.split_off(&nonce);
+        self.next_nonce = Some(nonce);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_mined() {
+        let mut state = NonceState { next_nonce: Some(3), pending: BTreeMap::new() };
+        state.pending.insert(0, B256::with_last_byte(0));
+        state.pending.insert(1, B256::with_last_byte(1));
+        state.pending.insert(2, B256::with_last_byte(2));
+
+        state.prune_mined(2);
+
+        assert_eq!(state.pending.keys().copied().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(state.next_nonce, Some(3));
+    }
+
+    #[test]
+    fn test_release_from_dropped_nonce() {
+        let mut state = NonceState { next_nonce: Some(5), pending: BTreeMap::new() };
+        state.pending.insert(3, B256::with_last_byte(3));
+        state.pending.insert(4, B256::with_last_byte(4));
+
+        state.release_from(3);
+
+        assert!(state.pending.is_empty());
+        assert_eq!(state.next_nonce, Some(3));
+    }
+}
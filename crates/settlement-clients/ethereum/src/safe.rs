@@ -0,0 +1,344 @@
+//! Settlement through a Gnosis Safe: the settlement transactions are proposed to the Safe
+//! transaction service, for the owners to confirm and execute, instead of being sent by the
+//! operator account. The operator account must be an owner or a delegate of the Safe.
+
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::sol;
+use alloy::sol_types::{eip712_domain, SolStruct};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use url::Url;
+use utils::{build_http_client, trace_id_header};
+
+use crate::signer::HashSigner;
+
+pub const ENV_SAFE_ADDRESS: &str = "ETHEREUM_SAFE_ADDRESS";
+pub const ENV_SAFE_TRANSACTION_SERVICE_URL: &str = "ETHEREUM_SAFE_TRANSACTION_SERVICE_URL";
+
+/// Safe operating the core contract and the transaction service its transactions are proposed to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafeConfig {
+    pub address: String,
+    pub transaction_service_url: Url,
+}
+
+sol! {
+    #[allow(missing_docs)]
+    struct SafeTx {
+        address to;
+        uint256 value;
+        bytes data;
+        uint8 operation;
+        uint256 safeTxGas;
+        uint256 baseGas;
+        uint256 gasPrice;
+        address gasToken;
+        address refundReceiver;
+        uint256 nonce;
+    }
+}
+
+/// Execution status of a Safe transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SafeTxStatus {
+    /// Waiting for the confirmations of the owners or to be executed
+    Pending,
+    /// Executed by the Ethereum transaction `tx_hash`, which succeeded or not
+    Executed { tx_hash: B256, successful: bool },
+}
+
+#[derive(Debug, Deserialize)]
+struct SafeInfo {
+    nonce: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct MultisigTransactions {
+    results: Vec<MultisigTransaction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MultisigTransaction {
+    nonce: u64,
+    #[serde(default)]
+    is_executed: bool,
+    is_successful: Option<bool>,
+    transaction_hash: Option<B256>,
+}
+
+/// Proposes the settlement transactions to the Safe transaction service and tracks their execution
+pub struct SafeProposer {
+    http_client: reqwest::Client,
+    service_url: Url,
+    safe_address: Address,
+    chain_id: u64,
+    sender: Address,
+    signer: HashSigner,
+}
+
+impl SafeProposer {
+    pub fn new(config: &SafeConfig, chain_id: u64, sender: Address, signer: HashSigner) -> Result<Self> {
+        let http_client = build_http_client!(reqwest)?;
+        let safe_address = config.address.parse().map_err(|e| eyre!("Failed to parse the Safe address: {}", e))?;
+        let service_url = config.transaction_service_url.clone();
+        Ok(Self { http_client, service_url, safe_address, chain_id, sender, signer })
+    }
+
+    pub fn safe_address(&self) -> Address {
+        self.safe_address
+    }
+
+    /// Proposes a call from the Safe to `to` with `data`, signed by the operator account. Returns
+    /// the hash of the Safe transaction.
+    pub async fn propose(&self, to: Address, data: Bytes) -> Result<B256> {
+        let nonce = self.next_nonce().await?;
+        let safe_tx = safe_call(to, data, nonce);
+        let safe_tx_hash = safe_tx_hash(&safe_tx, self.chain_id, self.safe_address);
+        let signature = self.signer.sign_hash(&safe_tx_hash).await?;
+
+        let body = json!({
+            "to": safe_tx.to.to_checksum(None),
+            "value": safe_tx.value.to_string(),
+            "data": safe_tx.data.to_string(),
+            "operation": safe_tx.operation,
+            "safeTxGas": safe_tx.safeTxGas.to_string(),
+            "baseGas": safe_tx.baseGas.to_string(),
+            "gasPrice": safe_tx.gasPrice.to_string(),
+            "gasToken": safe_tx.gasToken.to_checksum(None),
+            "refundReceiver": safe_tx.refundReceiver.to_checksum(None),
+            "nonce": nonce,
+            "contractTransactionHash": safe_tx_hash.to_string(),
+            "sender": self.sender.to_checksum(None),
+            "signature": Bytes::from(signature.as_bytes().to_vec()).to_string(),
+            "origin": "madara-orchestrator",
+        });
+        let url = self.service_url.join(&format!("api/v1/safes/{}/multisig-transactions/", self.safe_address))?;
+        let response = trace_id_header!(self.http_client.post(url).json(&body)).send().await?;
+        if !response.status().is_success() {
+            return Err(eyre!(
+                "Safe transaction service rejected the transaction {}: {} {}",
+                safe_tx_hash,
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+        Ok(safe_tx_hash)
+    }
+
+    /// Execution status of the Safe transaction
+    pub async fn status(&self, safe_tx_hash: B256) -> Result<SafeTxStatus> {
+        let url = self.service_url.join(&format!("api/v1/multisig-transactions/{}/", safe_tx_hash))?;
+        let transaction: MultisigTransaction =
+            trace_id_header!(self.http_client.get(url)).send().await?.error_for_status()?.json().await?;
+        match (transaction.is_executed, transaction.transaction_hash) {
+            (true, Some(tx_hash)) => {
+                Ok(SafeTxStatus::Executed { tx_hash, successful: transaction.is_successful.unwrap_or(false) })
+            }
+            _ => Ok(SafeTxStatus::Pending),
+        }
+    }
+
+    /// Whether Safe transactions were proposed and aren't executed yet
+    pub async fn has_queued(&self) -> Result<bool> {
+        let (executed_nonce, next_nonce) = self.nonces().await?;
+        Ok(next_nonce > executed_nonce)
+    }
+
+    /// Nonce of the next Safe transaction: after the ones already proposed and not executed yet
+    async fn next_nonce(&self) -> Result<u64> {
+        Ok(self.nonces().await?.1)
+    }
+
+    /// Nonce of the Safe, the one of the next transaction to execute, and the one of the next
+    /// transaction to propose
+    async fn nonces(&self) -> Result<(u64, u64)> {
+        let url = self.service_url.join(&format!("api/v1/safes/{}/", self.safe_address))?;
+        let safe: SafeInfo =
+            trace_id_header!(self.http_client.get(url)).send().await?.error_for_status()?.json().await?;
+
+        let mut url = self.service_url.join(&format!("api/v1/safes/{}/multisig-transactions/", self.safe_address))?;
+        url.query_pairs_mut()
+            .append_pair("nonce__gte", &safe.nonce.to_string())
+            .append_pair("ordering", "-nonce")
+            .append_pair("limit", "1");
+        let queued: MultisigTransactions =
+            trace_id_header!(self.http_client.get(url)).send().await?.error_for_status()?.json().await?;
+        Ok((safe.nonce, queued.results.first().map_or(safe.nonce, |transaction| transaction.nonce + 1)))
+    }
+}
+
+/// Plain call from the Safe, without refund of the executor
+fn safe_call(to: Address, data: Bytes, nonce: u64) -> SafeTx {
+    SafeTx {
+        to,
+        value: U256::ZERO,
+        data,
+        operation: 0,
+        safeTxGas: U256::ZERO,
+        baseGas: U256::ZERO,
+        gasPrice: U256::ZERO,
+        gasToken: Address::ZERO,
+        refundReceiver: Address::ZERO,
+        nonce: U256::from(nonce),
+    }
+}
+
+/// EIP-712 hash of the Safe transaction, the one the owners sign
+pub fn safe_tx_hash(safe_tx: &SafeTx, chain_id: u64, safe_address: Address) -> B256 {
+    let domain = eip712_domain! {
+        chain_id: chain_id,
+        verifying_contract: safe_address,
+    };
+    safe_tx.eip712_signing_hash(&domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::primitives::{address, keccak256};
+    use alloy::signers::local::PrivateKeySigner;
+    use httpmock::prelude::*;
+
+    use super::*;
+
+    const SAFE: Address = address!("00000000000000000000000000000000000000aa");
+    const CHAIN_ID: u64 = 11155111;
+
+    fn safe_proposer(server: &MockServer, sender: Address) -> SafeProposer {
+        let config = SafeConfig {
+            address: SAFE.to_string(),
+            transaction_service_url: server.base_url().parse().unwrap(),
+        };
+        SafeProposer::new(&config, CHAIN_ID, sender, Arc::new(PrivateKeySigner::random())).unwrap()
+    }
+
+    /// Mocks the Safe at nonce `safe_nonce`, with Safe transactions proposed up to `last_queued_nonce`
+    fn mock_nonces(server: &MockServer, safe_nonce: u64, last_queued_nonce: Option<u64>) {
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/api/v1/safes/{}/", SAFE));
+            then.status(200).json_body(json!({ "address": SAFE.to_checksum(None), "nonce": safe_nonce }));
+        });
+        let results: Vec<_> =
+            last_queued_nonce.iter().map(|nonce| json!({ "nonce": nonce, "isExecuted": false })).collect();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/api/v1/safes/{}/multisig-transactions/", SAFE))
+                .query_param("nonce__gte", safe_nonce.to_string())
+                .query_param("ordering", "-nonce")
+                .query_param("limit", "1");
+            then.status(200).json_body(json!({ "results": results }));
+        });
+    }
+
+    #[test]
+    fn test_safe_tx_type_hash() {
+        assert_eq!(
+            keccak256(SafeTx::eip712_encode_type().as_bytes()),
+            keccak256(
+                "SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,uint256 \
+                 gasPrice,address gasToken,address refundReceiver,uint256 nonce)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_safe_tx_hash_depends_on_safe_and_nonce() {
+        let safe = address!("00000000000000000000000000000000000000aa");
+        let to = address!("00000000000000000000000000000000000000bb");
+        let hash = safe_tx_hash(&safe_call(to, Bytes::from(vec![1, 2, 3]), 0), 1, safe);
+
+        assert_ne!(hash, safe_tx_hash(&safe_call(to, Bytes::from(vec![1, 2, 3]), 1), 1, safe));
+        assert_ne!(hash, safe_tx_hash(&safe_call(to, Bytes::from(vec![1, 2, 3]), 0), 1, to));
+        assert_eq!(hash, safe_tx_hash(&safe_call(to, Bytes::from(vec![1, 2, 3]), 0), 1, safe));
+    }
+
+    #[tokio::test]
+    async fn test_propose_after_queued_transactions() {
+        let server = MockServer::start();
+        mock_nonces(&server, 5, Some(6));
+        let sender = address!("00000000000000000000000000000000000000cc");
+        let to = address!("00000000000000000000000000000000000000bb");
+        let data = Bytes::from(vec![1, 2, 3]);
+        let expected_hash = safe_tx_hash(&safe_call(to, data.clone(), 7), CHAIN_ID, SAFE);
+        let propose = server.mock(|when, then| {
+            when.method(POST).path(format!("/api/v1/safes/{}/multisig-transactions/", SAFE)).json_body_partial(
+                json!({
+                    "to": to.to_checksum(None),
+                    "data": "0x010203",
+                    "nonce": 7,
+                    "contractTransactionHash": expected_hash.to_string(),
+                    "sender": sender.to_checksum(None),
+                })
+                .to_string(),
+            );
+            then.status(201);
+        });
+
+        let safe_tx_hash = safe_proposer(&server, sender).propose(to, data).await.unwrap();
+
+        propose.assert();
+        assert_eq!(safe_tx_hash, expected_hash);
+    }
+
+    #[tokio::test]
+    async fn test_propose_rejected() {
+        let server = MockServer::start();
+        mock_nonces(&server, 0, None);
+        server.mock(|when, then| {
+            when.method(POST).path(format!("/api/v1/safes/{}/multisig-transactions/", SAFE));
+            then.status(422).body("sender is not an owner or a delegate");
+        });
+
+        let error = safe_proposer(&server, Address::ZERO).propose(SAFE, Bytes::new()).await.unwrap_err();
+
+        assert!(error.to_string().contains("sender is not an owner or a delegate"));
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let server = MockServer::start();
+        let safe_proposer = safe_proposer(&server, Address::ZERO);
+        let tx_hash = B256::repeat_byte(0xee);
+        for (safe_tx_hash, transaction, expected) in [
+            (
+                B256::repeat_byte(1),
+                json!({ "nonce": 0, "isExecuted": false, "transactionHash": null }),
+                SafeTxStatus::Pending,
+            ),
+            (
+                B256::repeat_byte(2),
+                json!({ "nonce": 1, "isExecuted": true, "isSuccessful": true, "transactionHash": tx_hash }),
+                SafeTxStatus::Executed { tx_hash, successful: true },
+            ),
+            (
+                B256::repeat_byte(3),
+                json!({ "nonce": 2, "isExecuted": true, "isSuccessful": false, "transactionHash": tx_hash }),
+                SafeTxStatus::Executed { tx_hash, successful: false },
+            ),
+        ] {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/api/v1/multisig-transactions/{}/", safe_tx_hash));
+                then.status(200).json_body(transaction);
+            });
+            assert_eq!(safe_proposer.status(safe_tx_hash).await.unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nonces() {
+        for (last_queued_nonce, expected_next_nonce, expected_queued) in
+            [(None, 5, false), (Some(5), 6, true), (Some(7), 8, true)]
+        {
+            let server = MockServer::start();
+            mock_nonces(&server, 5, last_queued_nonce);
+            let safe_proposer = safe_proposer(&server, Address::ZERO);
+
+            assert_eq!(safe_proposer.next_nonce().await.unwrap(), expected_next_nonce);
+            assert_eq!(safe_proposer.has_queued().await.unwrap(), expected_queued);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use alloy::network::EthereumWallet;
+use alloy::primitives::Address;
+use alloy::signers::aws::AwsSigner;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::Signer;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use utils::env_utils::get_env_var_or_panic;
+
+pub const ENV_PRIVATE_KEY: &str = "ETHEREUM_PRIVATE_KEY";
+
+/// Signer of the hashes the operator account signs off-chain, e.g. the Safe transactions
+pub type HashSigner = Arc<dyn Signer + Send + Sync>;
+
+/// Signer of the settlement transactions.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// Private key read from `ETHEREUM_PRIVATE_KEY`
+    #[default]
+    Local,
+    /// Key held by AWS KMS, the AWS credentials and region come from the default provider chain
+    AwsKms { key_id: String },
+}
+
+/// Builds the wallet signing the settlement transactions and returns it along with the address of
+/// the operator account and the same key as a [`HashSigner`].
+///
+/// The wallet erases the type of the signer so the providers are the same whichever signer is
+/// configured.
+pub async fn build_wallet(config: &SignerConfig) -> Result<(EthereumWallet, Address, HashSigner)> {
+    match config {
+        SignerConfig::Local => {
+            let signer: PrivateKeySigner = get_env_var_or_panic(ENV_PRIVATE_KEY)
+                .parse()
+                .map_err(|e| eyre!("Failed to parse private key: {}", e))?;
+            let address = signer.address();
+            Ok((EthereumWallet::from(signer.clone()), address, Arc::new(signer)))
+        }
+        SignerConfig::AwsKms { key_id } => {
+            let aws_config = aws_config::load_from_env().await;
+            let kms_client = aws_sdk_kms::Client::new(&aws_config);
+            let signer = AwsSigner::new(kms_client, key_id.clone(), None)
+                .await
+                .map_err(|e| eyre!("Failed to load the AWS KMS key {}: {}", key_id, e))?;
+            let address = signer.address();
+            Ok((EthereumWallet::from(signer.clone()), address, Arc::new(signer)))
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use alloy::primitives::hex;
+use alloy::sol_types::decode_revert_reason;
+use alloy::transports::{RpcError, TransportErrorKind};
+use color_eyre::eyre::eyre;
+use settlement_client_interface::SimulationRevertedError;
+
+/// Turns the error of a simulation into a [`SimulationRevertedError`] holding the decoded revert
+/// reason when the node reports an execution revert. Other errors, e.g. the node being down, say
+/// nothing about the transaction and are passed through.
+pub fn simulation_error(error: RpcError<TransportErrorKind>) -> color_eyre::Report {
+    match revert_reason(&error) {
+        Some(reason) => SimulationRevertedError(reason).into(),
+        None => eyre!("Failed to simulate the transaction: {}", error),
+    }
+}
+
+/// Same as [`simulation_error`] for the errors of the contract calls.
+pub fn contract_simulation_error(error: alloy::contract::Error) -> color_eyre::Report {
+    match error {
+        alloy::contract::Error::TransportError(error) => simulation_error(error),
+        error => eyre!("Failed to simulate the transaction: {}", error),
+    }
+}
+
+/// Decodes the revert reason of an `eth_call` error response: `Error(string)` and `Panic(uint256)`
+/// payloads are decoded, custom errors are returned as hex. Falls back to the message of the node,
+/// e.g. `execution reverted`, when the response carries no revert data.
+pub fn revert_reason(error: &RpcError<TransportErrorKind>) -> Option<String> {
+    let payload = error.as_error_resp()?;
+    let revert_data = payload
+        .data
+        .as_ref()
+        .and_then(|data| hex::decode(data.get().trim_matches('"')).ok())
+        .filter(|data| !data.is_empty());
+    match revert_data {
+        Some(data) => Some(decode_revert_reason(&data).unwrap_or_else(|| format!("0x{}", hex::encode(&data)))),
+        None if payload.message.contains("revert") => Some(payload.message.clone()),
+        None => None,
+    }
+}
@@ -7,6 +7,8 @@ use alloy::{
     transports::http::{Client, Http},
 };
 
+/// Provider signing the transactions with the wallet of the operator, whichever signer backs it
+/// (see [`crate::signer::SignerConfig`]).
 pub type LocalWalletSignerMiddleware = FillProvider<
     JoinFill<
         JoinFill<JoinFill<JoinFill<Identity, GasFiller>, NonceFiller>, ChainIdFiller>,
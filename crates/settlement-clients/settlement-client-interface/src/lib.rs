@@ -12,6 +12,65 @@ pub enum SettlementVerificationStatus {
     Rejected(String),
 }
 
+/// Returned by a settlement client when the fees to settle are above the caps it is configured
+/// with, the transaction isn't sent rather than overpaying.
+#[derive(thiserror::Error, Debug)]
+#[error("Fee too high: {0}")]
+pub struct FeeTooHighError(pub String);
+
+/// Returned by a settlement client when the simulation of a transaction reverts, the transaction
+/// isn't sent rather than wasting gas. Holds the decoded revert reason.
+#[derive(thiserror::Error, Debug)]
+#[error("Transaction would revert: {0}")]
+pub struct SimulationRevertedError(pub String);
+
+/// Continuous page of the public memory of a Cairo run, starting at `start_address`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContinuousMemoryPage {
+    pub start_address: u64,
+    pub values: Vec<[u8; 32]>,
+}
+
+/// Memory pages of a proof to register in the memory page fact registry, with the interaction
+/// elements `z` and `alpha` of the proof they are hashed with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryPagesRegistration {
+    pub pages: Vec<ContinuousMemoryPage>,
+    pub z: [u8; 32],
+    pub alpha: [u8; 32],
+}
+
+/// Settlement operation which can be sent along with others in a single transaction, see
+/// [`SettlementClient::settle_batch`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettlementOperation {
+    /// State update of a block whose DA is done in calldata
+    UpdateStateCalldata { program_output: Vec<[u8; 32]>, onchain_data_hash: [u8; 32], onchain_data_size: usize },
+    /// Registration of the memory pages of a proof
+    RegisterMemoryPages(MemoryPagesRegistration),
+}
+
+/// Message sent to the L2 through the core contract, e.g. a deposit of a bridge. The L2 executes
+/// it with an L1 handler transaction, it is then consumed on the settlement layer by the state
+/// update of the block which executed it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L1ToL2Message {
+    /// Hash of the message, the key it is stored under in the core contract
+    pub message_hash: [u8; 32],
+    /// Hash of the transaction which sent the message
+    pub tx_hash: String,
+    /// Settlement layer block of the transaction which sent the message
+    pub block_number: u64,
+    pub from_address: [u8; 32],
+    pub to_address: [u8; 32],
+    pub selector: [u8; 32],
+    pub payload: Vec<[u8; 32]>,
+    pub nonce: [u8; 32],
+    /// Fee paid for the L1 handler transaction, in the smallest unit of the settlement layer fee
+    /// token
+    pub fee: u128,
+}
+
 /// Trait for every new Settlement Layer to implement
 #[automock]
 #[async_trait]
@@ -20,6 +79,14 @@ pub trait SettlementClient: Send + Sync {
     /// which can be used to track the status.
     async fn register_proof(&self, proof: [u8; 32]) -> Result<String>;
 
+    /// Should register the memory pages of a proof in the memory page fact registry, in a single
+    /// transaction, and return its hash
+    async fn register_memory_pages(&self, registration: MemoryPagesRegistration) -> Result<String>;
+
+    /// Should return whether the fact of a proof is registered in the fact registry the
+    /// settlement layer verifies the proofs with
+    async fn verify_fact(&self, fact: [u8; 32]) -> Result<bool>;
+
     /// Should be used to update state on core contract when DA is done in calldata
     async fn update_state_calldata(
         &self,
@@ -28,6 +95,10 @@ pub trait SettlementClient: Send + Sync {
         onchain_data_size: usize,
     ) -> Result<String>;
 
+    /// Should send the operations, in order, in a single transaction and return its hash. The
+    /// transaction reverts as a whole if one of the operations fails.
+    async fn settle_batch(&self, operations: Vec<SettlementOperation>) -> Result<String>;
+
     /// Should be used to update state on contract and publish the blob on ethereum.
     async fn update_state_with_blobs(&self, program_output: Vec<[u8; 32]>, state_diff: Vec<Vec<u8>>) -> Result<String>;
 
@@ -37,11 +108,49 @@ pub trait SettlementClient: Send + Sync {
     /// Should verify the inclusion of a tx in the settlement layer
     async fn verify_tx_inclusion(&self, tx_hash: &str) -> Result<SettlementVerificationStatus>;
 
-    /// Should wait that the pending tx_hash is finalized
+    /// Should wait that the pending tx_hash is finalized. May return while the tx is still pending
+    /// when its execution isn't up to the operator, the inclusion has to be verified again.
     async fn wait_for_tx_finality(&self, tx_hash: &str) -> Result<()>;
 
+    /// Whether the stuck txs can be replaced with `resubmit_tx_with_bumped_fees`
+    fn supports_tx_replacement(&self) -> bool;
+
+    /// Should resubmit a transaction stuck in the mempool with the same nonce and bumped fees,
+    /// returning the hash of the replacement transaction
+    async fn resubmit_tx_with_bumped_fees(&self, tx_hash: &str) -> Result<String>;
+
+    /// Should return the fee paid by the tx, in the smallest unit of the settlement layer fee
+    /// token. Returns `None` if the tx isn't mined.
+    async fn get_tx_fee(&self, tx_hash: &str) -> Result<Option<u128>>;
+
+    /// Should return the balance of the operator account, in the smallest unit of the settlement
+    /// layer fee token
+    async fn get_operator_balance(&self) -> Result<u128>;
+
     /// Should retrieves the last settled block in the settlement layer
     async fn get_last_settled_block(&self) -> Result<u64>;
+
+    /// Should retrieve the state root currently stored in the core contract
+    async fn get_state_root(&self) -> Result<[u8; 32]>;
+
+    /// Should retrieve the hash of the Starknet OS program the core contract accepts the program
+    /// outputs of
+    async fn get_program_hash(&self) -> Result<[u8; 32]>;
+
+    /// Should return the latest block number of the settlement layer
+    async fn get_latest_block_number(&self) -> Result<u64>;
+
+    /// Should return the hashes of the transactions sent by the operator account
+    /// between `from_block` and `to_block` (both included)
+    async fn get_operator_transactions(&self, from_block: u64, to_block: u64) -> Result<Vec<String>>;
+
+    /// Should return the messages sent to the L2 through the core contract between `from_block`
+    /// and `to_block` (both included)
+    async fn get_messages_to_l2(&self, from_block: u64, to_block: u64) -> Result<Vec<L1ToL2Message>>;
+
+    /// Should return whether the message sent to the L2 isn't pending in the core contract
+    /// anymore, i.e. a settled state update consumed it
+    async fn is_message_to_l2_consumed(&self, message_hash: [u8; 32]) -> Result<bool>;
 }
 
 /// Trait for every new SettlementConfig to implement
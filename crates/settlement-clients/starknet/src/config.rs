@@ -3,7 +3,7 @@ use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 use settlement_client_interface::SettlementConfig;
 use url::Url;
-use utils::env_utils::{get_env_var_or_default, get_env_var_or_panic};
+use utils::env_utils::{get_env_car_optional_or_panic, get_env_var_or_default, get_env_var_or_panic};
 
 pub const ENV_STARKNET_RPC_URL: &str = "STARKNET_RPC_URL";
 pub const ENV_CORE_CONTRACT_ADDRESS: &str = "STARKNET_CAIRO_CORE_CONTRACT_ADDRESS";
@@ -11,11 +11,54 @@ pub const ENV_CORE_CONTRACT_ADDRESS: &str = "STARKNET_CAIRO_CORE_CONTRACT_ADDRES
 pub const ENV_STARKNET_FINALITY_RETRY_DELAY_IN_SECS: &str = "STARKNET_FINALITY_RETRY_WAIT_IN_SECS";
 pub const DEFAULT_FINALITY_RETRY_DELAY: &str = "60";
 
+pub const ENV_STARKNET_FEE_TOKEN: &str = "STARKNET_FEE_TOKEN";
+pub const DEFAULT_FEE_TOKEN: &str = "eth";
+pub const ENV_STARKNET_ACCOUNT_CLASS_HASH: &str = "STARKNET_ACCOUNT_CLASS_HASH";
+pub const ENV_STARKNET_ACCOUNT_DEPLOYMENT_SALT: &str = "STARKNET_ACCOUNT_DEPLOYMENT_SALT";
+pub const DEFAULT_ACCOUNT_DEPLOYMENT_SALT: &str = "0x0";
+
+/// Token used by the operator account to pay the settlement transactions fees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeToken {
+    /// Fees paid in ETH, sent as v1 transactions
+    Eth,
+    /// Fees paid in STRK, sent as v3 transactions
+    Strk,
+}
+
+impl FeeToken {
+    /// Address of the token contract, the same on Starknet mainnet and sepolia
+    pub fn contract_address(&self) -> &'static str {
+        match self {
+            FeeToken::Eth => "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7",
+            FeeToken::Strk => "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d",
+        }
+    }
+}
+
+impl FromStr for FeeToken {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "eth" => Ok(FeeToken::Eth),
+            "strk" => Ok(FeeToken::Strk),
+            _ => Err(format!("Unknown fee token {}, expected eth or strk", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StarknetSettlementConfig {
     pub rpc_url: Url,
     pub core_contract_address: String,
     pub tx_finality_retry_delay_in_seconds: u64,
+    pub fee_token: FeeToken,
+    /// Class hash of the OpenZeppelin account contract used to deploy the operator account
+    /// if it isn't deployed yet. The account deployment is skipped when not set.
+    pub account_class_hash: Option<String>,
+    pub account_deployment_salt: String,
 }
 
 impl SettlementConfig for StarknetSettlementConfig {
@@ -28,7 +71,20 @@ impl SettlementConfig for StarknetSettlementConfig {
             get_env_var_or_default(ENV_STARKNET_FINALITY_RETRY_DELAY_IN_SECS, DEFAULT_FINALITY_RETRY_DELAY)
                 .parse()
                 .expect("STARKNET_FINALITY_RETRY_WAIT_IN_SECS should be a delay in seconds");
-        StarknetSettlementConfig { rpc_url, core_contract_address, tx_finality_retry_delay_in_seconds }
+        let fee_token = get_env_var_or_default(ENV_STARKNET_FEE_TOKEN, DEFAULT_FEE_TOKEN)
+            .parse()
+            .unwrap_or_else(|e| panic!("Failed to parse {}: {}", ENV_STARKNET_FEE_TOKEN, e));
+        let account_class_hash = get_env_car_optional_or_panic(ENV_STARKNET_ACCOUNT_CLASS_HASH);
+        let account_deployment_salt =
+            get_env_var_or_default(ENV_STARKNET_ACCOUNT_DEPLOYMENT_SALT, DEFAULT_ACCOUNT_DEPLOYMENT_SALT);
+        StarknetSettlementConfig {
+            rpc_url,
+            core_contract_address,
+            tx_finality_retry_delay_in_seconds,
+            fee_token,
+            account_class_hash,
+            account_deployment_salt,
+        }
     }
 }
 
@@ -38,6 +94,9 @@ impl Default for StarknetSettlementConfig {
             rpc_url: "https://free-rpc.nethermind.io/sepolia-juno".parse().unwrap(),
             core_contract_address: "TODO:https://github.com/keep-starknet-strange/piltover".into(),
             tx_finality_retry_delay_in_seconds: 60,
+            fee_token: FeeToken::Eth,
+            account_class_hash: None,
+            account_deployment_salt: DEFAULT_ACCOUNT_DEPLOYMENT_SALT.into(),
         }
     }
 }
@@ -8,9 +8,12 @@ use color_eyre::eyre::eyre;
 use color_eyre::Result;
 use lazy_static::lazy_static;
 use mockall::{automock, predicate::*};
-use starknet::accounts::ConnectedAccount;
-use starknet::core::types::{ExecutionResult, MaybePendingTransactionReceipt};
-use starknet::providers::Provider;
+use starknet::accounts::{AccountFactory, ConnectedAccount, OpenZeppelinAccountFactory};
+use starknet::core::types::{
+    ExecutionResult, InvokeTransaction, MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, StarknetError,
+    Transaction, TransactionReceipt,
+};
+use starknet::providers::{Provider, ProviderError};
 use starknet::{
     accounts::{Account, Call, ExecutionEncoding, SingleOwnerAccount},
     core::{
@@ -20,25 +23,35 @@ use starknet::{
     providers::{jsonrpc::HttpTransport, JsonRpcClient},
     signers::{LocalWallet, SigningKey},
 };
+use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration};
 
-use settlement_client_interface::{SettlementClient, SettlementVerificationStatus, SETTLEMENT_SETTINGS_NAME};
+use settlement_client_interface::{
+    L1ToL2Message, MemoryPagesRegistration, SettlementClient, SettlementOperation, SettlementVerificationStatus,
+    SETTLEMENT_SETTINGS_NAME,
+};
+use utils::build_http_client;
 use utils::env_utils::get_env_var_or_panic;
 use utils::settings::SettingsProvider;
 
-use crate::config::StarknetSettlementConfig;
+use crate::config::{FeeToken, StarknetSettlementConfig};
 use crate::conversion::{slice_slice_u8_to_vec_field, slice_u8_to_field};
 
 pub struct StarknetSettlementClient {
     pub account: SingleOwnerAccount<Arc<JsonRpcClient<HttpTransport>>, LocalWallet>,
     pub core_contract_address: FieldElement,
     pub tx_finality_retry_delay_in_seconds: u64,
+    pub fee_token: FeeToken,
+    /// Next nonce of the operator account, `None` when it has to be fetched from the chain
+    next_nonce: Mutex<Option<FieldElement>>,
 }
 
 pub const ENV_PUBLIC_KEY: &str = "STARKNET_PUBLIC_KEY";
 pub const ENV_PRIVATE_KEY: &str = "STARKNET_PRIVATE_KEY";
 
 const MAX_RETRIES_VERIFY_TX_FINALITY: usize = 10;
+const MAX_RETRIES_RECEIPT_POLLING: usize = 5;
+const RECEIPT_POLLING_DELAY_IN_SECONDS: u64 = 2;
 
 // Assumed the contract called for settlement l ooks like:
 // https://github.com/keep-starknet-strange/piltover
@@ -46,31 +59,173 @@ const MAX_RETRIES_VERIFY_TX_FINALITY: usize = 10;
 impl StarknetSettlementClient {
     pub async fn with_settings(settings: &impl SettingsProvider) -> Self {
         let settlement_cfg: StarknetSettlementConfig = settings.get_settings(SETTLEMENT_SETTINGS_NAME).unwrap();
-        let provider = Arc::new(JsonRpcClient::new(HttpTransport::new(settlement_cfg.rpc_url)));
+        let http_client = build_http_client!(reqwest).expect("Failed to build the Starknet HTTP client");
+        let provider =
+            Arc::new(JsonRpcClient::new(HttpTransport::new_with_client(settlement_cfg.rpc_url, http_client)));
 
         let public_key = get_env_var_or_panic(ENV_PUBLIC_KEY);
         let signer_address = FieldElement::from_hex_be(&public_key).expect("invalid signer address");
 
         // TODO: Very insecure way of building the signer. Needs to be adjusted.
         let private_key = get_env_var_or_panic(ENV_PRIVATE_KEY);
-        let signer = FieldElement::from_hex_be(&private_key).expect("Invalid private key");
-        let signer = LocalWallet::from(SigningKey::from_secret_scalar(signer));
+        let private_key = FieldElement::from_hex_be(&private_key).expect("Invalid private key");
+        let signer = LocalWallet::from(SigningKey::from_secret_scalar(private_key));
 
         let core_contract_address =
             FieldElement::from_hex_be(&settlement_cfg.core_contract_address).expect("Invalid core contract address");
+        let chain_id = provider.chain_id().await.unwrap();
+
+        if let Some(account_class_hash) = &settlement_cfg.account_class_hash {
+            let class_hash = FieldElement::from_hex_be(account_class_hash).expect("Invalid account class hash");
+            let salt =
+                FieldElement::from_hex_be(&settlement_cfg.account_deployment_salt).expect("Invalid deployment salt");
+            let factory = OpenZeppelinAccountFactory::new(
+                class_hash,
+                chain_id,
+                LocalWallet::from(SigningKey::from_secret_scalar(private_key)),
+                provider.clone(),
+            )
+            .await
+            .expect("Failed to build the account factory");
+            Self::deploy_account_if_needed(
+                &factory,
+                signer_address,
+                salt,
+                settlement_cfg.fee_token,
+                settlement_cfg.tx_finality_retry_delay_in_seconds,
+            )
+            .await
+            .expect("Failed to deploy the operator account");
+        }
 
-        let account = SingleOwnerAccount::new(
-            provider.clone(),
-            signer,
-            signer_address,
-            provider.chain_id().await.unwrap(),
-            ExecutionEncoding::Legacy,
-        );
+        let account =
+            SingleOwnerAccount::new(provider.clone(), signer, signer_address, chain_id, ExecutionEncoding::Legacy);
 
         StarknetSettlementClient {
             account,
             core_contract_address,
             tx_finality_retry_delay_in_seconds: settlement_cfg.tx_finality_retry_delay_in_seconds,
+            fee_token: settlement_cfg.fee_token,
+            next_nonce: Mutex::new(None),
+        }
+    }
+
+    /// Deploys the operator account through the factory if it isn't deployed yet. The account
+    /// must have been funded with the fee token at its address beforehand.
+    async fn deploy_account_if_needed(
+        factory: &OpenZeppelinAccountFactory<LocalWallet, Arc<JsonRpcClient<HttpTransport>>>,
+        account_address: FieldElement,
+        salt: FieldElement,
+        fee_token: FeeToken,
+        retry_delay_in_seconds: u64,
+    ) -> Result<()> {
+        let provider = factory.provider();
+        match provider.get_class_hash_at(BlockId::Tag(BlockTag::Pending), account_address).await {
+            Ok(_) => return Ok(()),
+            Err(ProviderError::StarknetError(StarknetError::ContractNotFound)) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let deployment_result = match fee_token {
+            FeeToken::Eth => {
+                let deployment = factory.deploy_v1(salt);
+                Self::check_deployment_address(deployment.address(), account_address)?;
+                deployment.send().await
+            }
+            FeeToken::Strk => {
+                let deployment = factory.deploy_v3(salt);
+                Self::check_deployment_address(deployment.address(), account_address)?;
+                deployment.send().await
+            }
+        }
+        .map_err(|e| eyre!("Failed to deploy the operator account {:#x}: {}", account_address, e))?;
+
+        let tx_hash = deployment_result.transaction_hash;
+        for _ in 0..MAX_RETRIES_VERIFY_TX_FINALITY {
+            sleep(Duration::from_secs(retry_delay_in_seconds)).await;
+            match provider.get_transaction_receipt(tx_hash).await {
+                Ok(MaybePendingTransactionReceipt::Receipt(receipt)) => {
+                    return match receipt.execution_result() {
+                        ExecutionResult::Succeeded => Ok(()),
+                        ExecutionResult::Reverted { reason } => {
+                            Err(eyre!("Operator account deployment {:#x} reverted: {}", tx_hash, reason))
+                        }
+                    };
+                }
+                Ok(MaybePendingTransactionReceipt::PendingReceipt(_)) => continue,
+                Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(eyre!("Max retries exceeeded while waiting for the operator account deployment {:#x}", tx_hash))
+    }
+
+    /// Polls the receipt of a transaction which may not be known by the node yet, right after it
+    /// has been sent. Returns `None` if the transaction still can't be found after the retries.
+    async fn poll_transaction_receipt(&self, tx_hash: FieldElement) -> Result<Option<MaybePendingTransactionReceipt>> {
+        for attempt in 0..MAX_RETRIES_RECEIPT_POLLING {
+            match self.account.provider().get_transaction_receipt(tx_hash).await {
+                Ok(tx_receipt) => return Ok(Some(tx_receipt)),
+                Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => {
+                    if attempt + 1 < MAX_RETRIES_RECEIPT_POLLING {
+                        sleep(Duration::from_secs(RECEIPT_POLLING_DELAY_IN_SECONDS)).await;
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(None)
+    }
+
+    fn check_deployment_address(deployment_address: FieldElement, account_address: FieldElement) -> Result<()> {
+        if deployment_address != account_address {
+            return Err(eyre!(
+                "Operator account would be deployed at {:#x} but {:#x} is configured, check the class hash and salt",
+                deployment_address,
+                account_address
+            ));
+        }
+        Ok(())
+    }
+
+    /// Call to `update_state` on the core contract when DA is done in calldata
+    fn update_state_call(
+        &self,
+        program_output: Vec<[u8; 32]>,
+        onchain_data_hash: [u8; 32],
+        onchain_data_size: usize,
+    ) -> Call {
+        let program_output = slice_slice_u8_to_vec_field(program_output.as_slice());
+        let onchain_data_hash = slice_u8_to_field(&onchain_data_hash);
+        let mut calldata: Vec<FieldElement> = Vec::with_capacity(program_output.len() + 2);
+        calldata.extend(program_output);
+        calldata.push(onchain_data_hash);
+        calldata.push(FieldElement::from(onchain_data_size));
+        Call { to: self.core_contract_address, selector: *CONTRACT_WRITE_UPDATE_STATE_SELECTOR, calldata }
+    }
+
+    /// Sends the calls from the operator account, paying the fees in the configured token.
+    /// The nonce is tracked locally so that consecutive transactions don't have to wait for the
+    /// previous one to be included, it is fetched again from the chain after a failure.
+    async fn send_calls(&self, calls: Vec<Call>) -> Result<FieldElement> {
+        let mut next_nonce = self.next_nonce.lock().await;
+        let nonce = match *next_nonce {
+            Some(nonce) => nonce,
+            None => self.account.get_nonce().await?,
+        };
+        let invoke_result = match self.fee_token {
+            FeeToken::Eth => self.account.execute_v1(calls).nonce(nonce).send().await,
+            FeeToken::Strk => self.account.execute_v3(calls).nonce(nonce).send().await,
+        };
+        match invoke_result {
+            Ok(invoke_result) => {
+                *next_nonce = Some(nonce + FieldElement::ONE);
+                Ok(invoke_result.transaction_hash)
+            }
+            Err(e) => {
+                *next_nonce = None;
+                Err(e.into())
+            }
         }
     }
 }
@@ -83,6 +238,14 @@ lazy_static! {
     // It should get added to match the solidity implementation of the core contract.
     pub static ref CONTRACT_READ_STATE_BLOCK_NUMBER: FieldElement =
         get_selector_from_name("stateBlockNumber").expect("Invalid update state selector");
+    // TODO: same as `stateBlockNumber`, `stateRoot` should get added to piltover.
+    pub static ref CONTRACT_READ_STATE_ROOT: FieldElement =
+        get_selector_from_name("stateRoot").expect("Invalid state root selector");
+    // TODO: same as `stateBlockNumber`, `programHash` should get added to piltover.
+    pub static ref CONTRACT_READ_PROGRAM_HASH: FieldElement =
+        get_selector_from_name("programHash").expect("Invalid program hash selector");
+    pub static ref ERC20_READ_BALANCE_OF: FieldElement =
+        get_selector_from_name("balanceOf").expect("Invalid balance of selector");
 }
 
 // TODO: Note that we already have an implementation of the appchain core contract client available here:
@@ -102,6 +265,18 @@ impl SettlementClient for StarknetSettlementClient {
         !unimplemented!("register_proof not implemented yet")
     }
 
+    /// Proofs settled on Starknet are verified without a memory page fact registry
+    #[allow(unused)]
+    async fn register_memory_pages(&self, registration: MemoryPagesRegistration) -> Result<String> {
+        Err(eyre!("Registering memory pages is not supported on the Starknet settlement layer"))
+    }
+
+    /// The proofs settled on Starknet aren't registered in a SHARP fact registry
+    #[allow(unused)]
+    async fn verify_fact(&self, fact: [u8; 32]) -> Result<bool> {
+        Err(eyre!("Verifying proof facts is not supported on the Starknet settlement layer"))
+    }
+
     /// Should be used to update state on core contract when DA is done in calldata
     async fn update_state_calldata(
         &self,
@@ -109,22 +284,26 @@ impl SettlementClient for StarknetSettlementClient {
         onchain_data_hash: [u8; 32],
         onchain_data_size: usize,
     ) -> Result<String> {
-        let program_output = slice_slice_u8_to_vec_field(program_output.as_slice());
-        let onchain_data_hash = slice_u8_to_field(&onchain_data_hash);
-        let mut calldata: Vec<FieldElement> = Vec::with_capacity(program_output.len() + 2);
-        calldata.extend(program_output);
-        calldata.push(onchain_data_hash);
-        calldata.push(FieldElement::from(onchain_data_size));
-        let invoke_result = self
-            .account
-            .execute(vec![Call {
-                to: self.core_contract_address,
-                selector: *CONTRACT_WRITE_UPDATE_STATE_SELECTOR,
-                calldata,
-            }])
-            .send()
-            .await?;
-        Ok(format!("0x{:x}", invoke_result.transaction_hash))
+        let tx_hash =
+            self.send_calls(vec![self.update_state_call(program_output, onchain_data_hash, onchain_data_size)]).await?;
+        Ok(format!("0x{:x}", tx_hash))
+    }
+
+    /// Sends the operations as the calls of a single invoke transaction of the operator account
+    async fn settle_batch(&self, operations: Vec<SettlementOperation>) -> Result<String> {
+        let calls = operations
+            .into_iter()
+            .map(|operation| match operation {
+                SettlementOperation::UpdateStateCalldata { program_output, onchain_data_hash, onchain_data_size } => {
+                    Ok(self.update_state_call(program_output, onchain_data_hash, onchain_data_size))
+                }
+                SettlementOperation::RegisterMemoryPages(_) => {
+                    Err(eyre!("Registering memory pages is not supported on the Starknet settlement layer"))
+                }
+            })
+            .collect::<Result<Vec<Call>>>()?;
+        let tx_hash = self.send_calls(calls).await?;
+        Ok(format!("0x{:x}", tx_hash))
     }
 
     /// Should be used to update state on core contract when DA is in blobs/alt DA
@@ -136,7 +315,9 @@ impl SettlementClient for StarknetSettlementClient {
     /// Should verify the inclusion of a tx in the settlement layer
     async fn verify_tx_inclusion(&self, tx_hash: &str) -> Result<SettlementVerificationStatus> {
         let tx_hash = FieldElement::from_hex_be(tx_hash)?;
-        let tx_receipt = self.account.provider().get_transaction_receipt(tx_hash).await?;
+        let Some(tx_receipt) = self.poll_transaction_receipt(tx_hash).await? else {
+            return Ok(SettlementVerificationStatus::Pending);
+        };
         match tx_receipt {
             MaybePendingTransactionReceipt::Receipt(tx) => match tx.execution_result() {
                 ExecutionResult::Succeeded => Ok(SettlementVerificationStatus::Verified),
@@ -182,6 +363,62 @@ impl SettlementClient for StarknetSettlementClient {
         Ok(())
     }
 
+    /// Starknet transactions can't be replaced once sent, the fees are set when the transaction is
+    /// estimated
+    fn supports_tx_replacement(&self) -> bool {
+        false
+    }
+
+    /// Always fails, see `supports_tx_replacement`
+    #[allow(unused)]
+    async fn resubmit_tx_with_bumped_fees(&self, tx_hash: &str) -> Result<String> {
+        Err(eyre!("Resubmitting a transaction with bumped fees is not supported on the Starknet settlement layer"))
+    }
+
+    /// Get the fee paid by a tx, in the unit of the fee token of the operator account
+    async fn get_tx_fee(&self, tx_hash: &str) -> Result<Option<u128>> {
+        let tx_hash = FieldElement::from_hex_be(tx_hash)?;
+        match self.account.provider().get_transaction_receipt(tx_hash).await {
+            Ok(MaybePendingTransactionReceipt::Receipt(TransactionReceipt::Invoke(receipt))) => {
+                let fee: u128 = receipt
+                    .actual_fee
+                    .amount
+                    .try_into()
+                    .map_err(|_| eyre!("Fee of tx {:#x} doesn't fit in a u128", tx_hash))?;
+                Ok(Some(fee))
+            }
+            Ok(MaybePendingTransactionReceipt::Receipt(_)) => {
+                Err(eyre!("Tx {:#x} is not an invoke transaction", tx_hash))
+            }
+            Ok(MaybePendingTransactionReceipt::PendingReceipt(_)) => Ok(None),
+            Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the balance of the operator account in the fee token, the `u256` returned by the
+    /// token contract is split in its low and high parts.
+    async fn get_operator_balance(&self) -> Result<u128> {
+        let token_address = FieldElement::from_hex_be(self.fee_token.contract_address())?;
+        let balance = self
+            .account
+            .provider()
+            .call(
+                FunctionCall {
+                    contract_address: token_address,
+                    entry_point_selector: *ERC20_READ_BALANCE_OF,
+                    calldata: vec![self.account.address()],
+                },
+                BlockId::Tag(BlockTag::Latest),
+            )
+            .await?;
+        match balance.as_slice() {
+            [low, high] if *high == FieldElement::ZERO => Ok((*low).try_into()?),
+            [_, _] => Err(eyre!("Balance of the operator account doesn't fit in a u128")),
+            _ => Err(eyre!("Unexpected balanceOf result from the fee token contract: {:?}", balance)),
+        }
+    }
+
     /// Returns the last block settled from the core contract.
     async fn get_last_settled_block(&self) -> Result<u64> {
         let block_number = self
@@ -201,4 +438,84 @@ impl SettlementClient for StarknetSettlementClient {
         }
         Ok(block_number[0].try_into()?)
     }
+
+    /// Returns the state root of the last block settled from the core contract.
+    async fn get_state_root(&self) -> Result<[u8; 32]> {
+        let state_root = self
+            .account
+            .provider()
+            .call(
+                FunctionCall {
+                    contract_address: self.core_contract_address,
+                    entry_point_selector: *CONTRACT_READ_STATE_ROOT,
+                    calldata: vec![],
+                },
+                BlockId::Tag(BlockTag::Latest),
+            )
+            .await?;
+        if state_root.is_empty() {
+            return Err(eyre!("Could not fetch state root from core contract."));
+        }
+        Ok(state_root[0].to_bytes_be())
+    }
+
+    /// Returns the hash of the OS program accepted by the core contract.
+    async fn get_program_hash(&self) -> Result<[u8; 32]> {
+        let program_hash = self
+            .account
+            .provider()
+            .call(
+                FunctionCall {
+                    contract_address: self.core_contract_address,
+                    entry_point_selector: *CONTRACT_READ_PROGRAM_HASH,
+                    calldata: vec![],
+                },
+                BlockId::Tag(BlockTag::Latest),
+            )
+            .await?;
+        if program_hash.is_empty() {
+            return Err(eyre!("Could not fetch program hash from core contract."));
+        }
+        Ok(program_hash[0].to_bytes_be())
+    }
+
+    /// Returns the latest block number of the settlement layer.
+    async fn get_latest_block_number(&self) -> Result<u64> {
+        Ok(self.account.provider().block_number().await?)
+    }
+
+    /// Returns the invoke transactions sent by the operator account in the given block range.
+    async fn get_operator_transactions(&self, from_block: u64, to_block: u64) -> Result<Vec<String>> {
+        let operator_address = self.account.address();
+        let mut tx_hashes = Vec::new();
+        for block_number in from_block..to_block + 1 {
+            let block = self.account.provider().get_block_with_txs(BlockId::Number(block_number)).await?;
+            let transactions = match block {
+                MaybePendingBlockWithTxs::Block(block) => block.transactions,
+                MaybePendingBlockWithTxs::PendingBlock(block) => block.transactions,
+            };
+            for tx in transactions {
+                let sender_address = match &tx {
+                    Transaction::Invoke(InvokeTransaction::V1(invoke)) => invoke.sender_address,
+                    Transaction::Invoke(InvokeTransaction::V3(invoke)) => invoke.sender_address,
+                    _ => continue,
+                };
+                if sender_address == operator_address {
+                    tx_hashes.push(format!("0x{:x}", tx.transaction_hash()));
+                }
+            }
+        }
+        Ok(tx_hashes)
+    }
+
+    /// The core contract on Starknet doesn't send messages to the L3 yet
+    #[allow(unused)]
+    async fn get_messages_to_l2(&self, from_block: u64, to_block: u64) -> Result<Vec<L1ToL2Message>> {
+        Err(eyre!("Messages to the L2 are not supported on the Starknet settlement layer"))
+    }
+
+    #[allow(unused)]
+    async fn is_message_to_l2_consumed(&self, message_hash: [u8; 32]) -> Result<bool> {
+        Err(eyre!("Messages to the L2 are not supported on the Starknet settlement layer"))
+    }
 }
@@ -0,0 +1,112 @@
+use crate::env_utils::get_env_car_optional_or_panic;
+
+/// Proxy all the outbound HTTP(S) requests go through, e.g. `http://proxy.internal:3128`
+pub const ENV_OUTBOUND_PROXY_URL: &str = "OUTBOUND_PROXY_URL";
+/// PEM file of the root CAs trusted by the outbound clients on top of the system ones
+pub const ENV_OUTBOUND_ROOT_CA_PATH: &str = "OUTBOUND_ROOT_CA_PATH";
+
+const PEM_BEGIN_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END_CERTIFICATE: &str = "-----END CERTIFICATE-----";
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpClientError {
+    #[error("Failed to read the root CA file {0}: {1}")]
+    ReadRootCa(String, #[source] std::io::Error),
+    #[error("No certificate found in the root CA file {0}")]
+    EmptyRootCa(String),
+    #[error("Failed to build the HTTP client: {0}")]
+    Build(String),
+}
+
+impl HttpClientError {
+    pub fn build(e: impl std::fmt::Display) -> Self {
+        Self::Build(e.to_string())
+    }
+}
+
+/// Configuration shared by all the outbound HTTP clients: the Starknet, DA, prover, settlement
+/// and AWS clients.
+#[derive(Debug, Clone, Default)]
+pub struct HttpClientConfig {
+    pub proxy_url: Option<String>,
+    /// PEM encoded root certificates, one per entry
+    pub root_certificates_pem: Vec<Vec<u8>>,
+}
+
+impl HttpClientConfig {
+    pub fn from_env() -> Result<Self, HttpClientError> {
+        let proxy_url = get_env_car_optional_or_panic(ENV_OUTBOUND_PROXY_URL);
+        let root_certificates_pem = match get_env_car_optional_or_panic(ENV_OUTBOUND_ROOT_CA_PATH) {
+            Some(path) => {
+                let bundle =
+                    std::fs::read_to_string(&path).map_err(|e| HttpClientError::ReadRootCa(path.clone(), e))?;
+                let certificates = split_pem_certificates(&bundle);
+                if certificates.is_empty() {
+                    return Err(HttpClientError::EmptyRootCa(path));
+                }
+                certificates
+            }
+            None => Vec::new(),
+        };
+        Ok(Self { proxy_url, root_certificates_pem })
+    }
+}
+
+/// Splits a PEM bundle into its certificates, the clients take them one at a time.
+pub fn split_pem_certificates(bundle: &str) -> Vec<Vec<u8>> {
+    bundle
+        .split_inclusive(PEM_END_CERTIFICATE)
+        .filter_map(|block| {
+            let start = block.find(PEM_BEGIN_CERTIFICATE)?;
+            block.ends_with(PEM_END_CERTIFICATE).then(|| block[start..].as_bytes().to_vec())
+        })
+        .collect()
+}
+
+/// Builds a `reqwest::Client` with the proxy and root CAs of the [`HttpClientConfig`] read from
+/// the environment.
+///
+/// The clients depend on different versions of `reqwest`, the path of the one to build the client
+/// with is passed along: `build_http_client!(reqwest)` or
+/// `build_http_client!(alloy::transports::http::reqwest)`.
+#[macro_export]
+macro_rules! build_http_client {
+    ($($reqwest:ident)::+) => {{
+        (|| -> ::std::result::Result<$($reqwest)::+::Client, $crate::http_client::HttpClientError> {
+            let config = $crate::http_client::HttpClientConfig::from_env()?;
+            let mut builder = $($reqwest)::+::Client::builder();
+            if let Some(proxy_url) = &config.proxy_url {
+                let proxy = $($reqwest)::+::Proxy::all(proxy_url.as_str())
+                    .map_err($crate::http_client::HttpClientError::build)?;
+                builder = builder.proxy(proxy);
+            }
+            for pem in &config.root_certificates_pem {
+                let certificate = $($reqwest)::+::Certificate::from_pem(pem)
+                    .map_err($crate::http_client::HttpClientError::build)?;
+                builder = builder.add_root_certificate(certificate);
+            }
+            builder.build().map_err($crate::http_client::HttpClientError::build)
+        })()
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_pem_certificates;
+
+    #[test]
+    fn test_split_pem_certificates() {
+        let bundle = "# root A\n-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\
+                      -----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\n\
+                      -----BEGIN CERTIFICATE-----\nCCCC (truncated)\n";
+        let certificates = split_pem_certificates(bundle);
+        assert_eq!(
+            certificates,
+            vec![
+                b"-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----".to_vec(),
+                b"-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----".to_vec(),
+            ]
+        );
+        assert!(split_pem_certificates("no certificate").is_empty());
+    }
+}
@@ -1,6 +1,8 @@
 pub mod collections;
 pub mod env_utils;
+pub mod http_client;
 pub mod settings;
+pub mod trace;
 
 /// Evaluate `$x:expr` and if not true return `Err($y:expr)`.
 ///
@@ -0,0 +1,35 @@
+use std::future::Future;
+
+/// Header carrying the trace id of the job on the outbound HTTP requests
+pub const TRACE_ID_HEADER: &str = "x-trace-id";
+
+tokio::task_local! {
+    static TRACE_ID: String;
+}
+
+/// Runs the future with the trace id set, the outbound requests made by the future carry it, see
+/// [`trace_id_header!`](crate::trace_id_header)
+pub async fn with_trace_id<F: Future>(trace_id: String, future: F) -> F::Output {
+    TRACE_ID.scope(trace_id, future).await
+}
+
+/// Trace id of the job the current task is running for, if any
+pub fn current_trace_id() -> Option<String> {
+    TRACE_ID.try_with(Clone::clone).ok()
+}
+
+/// Adds the [`TRACE_ID_HEADER`] with the current trace id to a `reqwest::RequestBuilder`, the
+/// request is returned as is outside of a job.
+///
+/// The clients depend on different versions of `reqwest`, the header is set through the builder
+/// of whichever version the request was made with: `trace_id_header!(client.get(url)).send()`.
+#[macro_export]
+macro_rules! trace_id_header {
+    ($request:expr) => {{
+        let request = $request;
+        match $crate::trace::current_trace_id() {
+            Some(trace_id) => request.header($crate::trace::TRACE_ID_HEADER, trace_id),
+            None => request,
+        }
+    }};
+}
@@ -8,6 +8,11 @@ pub use mongodb::MongoDbServer;
 pub use node::Orchestrator;
 pub use orchestrator::database::mongodb::MongoDb as MongoDbClient;
 
+/// Environment of an orchestrator proving with the mock prover: the tasks succeed right away and
+/// their facts, registered nowhere, aren't checked
+pub const MOCK_PROVER_ENVS: [(&str, &str); 3] =
+    [("PROVER_SERVICE", "mock"), ("MOCK_PROVER_DELAY_SECS", "0"), ("MOCK_PROVER_FAILURE_RATE", "0")];
+
 const MIN_PORT: u16 = 49_152;
 const MAX_PORT: u16 = 65_535;
 
@@ -1,4 +1,4 @@
-use e2e_tests::{MongoDbServer, Orchestrator};
+use e2e_tests::{MongoDbServer, Orchestrator, MOCK_PROVER_ENVS};
 
 extern crate e2e_tests;
 
@@ -6,10 +6,18 @@ extern crate e2e_tests;
 #[tokio::test]
 async fn test_orchestrator_launches() {
     let mongodb = MongoDbServer::run().await;
-    let mut orchestrator = Orchestrator::run(vec![
-        // TODO: mock Madara RPC API
-        ("MADARA_RPC_URL", "http://localhost"),
-        ("MONGODB_CONNECTION_STRING", mongodb.endpoint().as_str()),
-    ]);
+    let mut orchestrator = Orchestrator::run(
+        [
+            vec![
+                // TODO: mock Madara RPC API
+                ("MADARA_RPC_URL", "http://localhost"),
+                ("MONGODB_CONNECTION_STRING", mongodb.endpoint().as_str()),
+                // the MongoDB container is a standalone server
+                ("MONGODB_ALLOW_NON_ATOMIC_TRANSACTIONS", "true"),
+            ],
+            MOCK_PROVER_ENVS.to_vec(),
+        ]
+        .concat(),
+    );
     orchestrator.wait_till_started().await;
 }